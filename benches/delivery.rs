@@ -0,0 +1,123 @@
+//! Benchmarks for the worker's per-notification hot path: `cargo bench --bench delivery`.
+//!
+//! Envelope and FCM request building need nothing external. The mark-success group
+//! runs only when `BENCH_DATABASE_URL` points at a migrated database (base schema +
+//! migrations/); it inserts its own rows with type `bench` and deletes them afterwards.
+//! Client connection fan-out lives in websocket-bus and is benchmarked there.
+
+use bus_client::BusEnvelope;
+use chrono::{TimeZone, Utc};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use notifications_service::db::NotificationQueries;
+use notifications_service::models::Notification;
+use notifications_service::push::FcmClient;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Notifications marked per worker batch (WORKER_BATCH_SIZE default)
+const MARK_BATCH: usize = 100;
+
+/// Every field a client sees, like a typical liked-post notification
+fn notification() -> Notification {
+    let created_at = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+    Notification {
+        id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        actor_user_id: Some(Uuid::new_v4()),
+        target_type: Some("post".to_string()),
+        target_id: Some(Uuid::new_v4()),
+        message: Some("Alice liked your post".to_string()),
+        payload: Some(serde_json::json!({ "post_id": 42, "reactions": ["like"] })),
+        deep_link: Some("app://posts/42".to_string()),
+        priority: Some("high".to_string()),
+        group_key: Some("post:42".to_string()),
+        deliver_at: created_at,
+        created_at,
+        ..Notification::draft("post_liked", "New like".to_string(), None)
+    }
+}
+
+fn bus_envelope(c: &mut Criterion) {
+    let notification = notification();
+    let mut group = c.benchmark_group("bus_envelope");
+    group.bench_function("json", |b| {
+        b.iter(|| BusEnvelope::new("notifications", "notification").with_payload(notification.bus_payload()))
+    });
+    group.bench_function("protobuf", |b| {
+        b.iter(|| BusEnvelope::new("notifications", "notification").with_payload(notification.bus_payload_protobuf()))
+    });
+    group.bench_function("broadcast", |b| {
+        b.iter(|| BusEnvelope::new("global_notifications", "broadcast").with_payload(notification.bus_broadcast_payload()))
+    });
+    group.finish();
+}
+
+fn fcm_request(c: &mut Criterion) {
+    let notification = notification();
+    let mut group = c.benchmark_group("fcm_request");
+    group.bench_function("prepare", |b| b.iter(|| FcmClient::prepare(&notification)));
+
+    // One prepare per locale, then a body per device token
+    for devices in [1usize, 5, 20] {
+        let tokens: Vec<String> = (0..devices).map(|i| format!("device-token-{:04}", i)).collect();
+        group.throughput(Throughput::Elements(devices as u64));
+        group.bench_with_input(BenchmarkId::new("fan_out", devices), &tokens, |b, tokens| {
+            b.iter(|| {
+                let push = FcmClient::prepare(&notification);
+                tokens.iter().map(|token| push.body_for(token).len()).sum::<usize>()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn mark_success(c: &mut Criterion) {
+    let Ok(database_url) = std::env::var("BENCH_DATABASE_URL") else {
+        eprintln!("BENCH_DATABASE_URL not set, skipping mark_success benchmarks");
+        return;
+    };
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    let pool = runtime
+        .block_on(PgPool::connect(&database_url))
+        .expect("connect to BENCH_DATABASE_URL");
+
+    let mut group = c.benchmark_group("mark_success");
+    group.throughput(Throughput::Elements(MARK_BATCH as u64));
+    group.bench_function(BenchmarkId::new("batch", MARK_BATCH), |b| {
+        b.iter_batched(
+            || runtime.block_on(insert_pending(&pool, MARK_BATCH)),
+            |ids| {
+                runtime.block_on(async {
+                    for id in ids {
+                        NotificationQueries::mark_success(&pool, id).await.expect("mark_success");
+                    }
+                })
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+
+    runtime
+        .block_on(
+            sqlx::query("DELETE FROM activity.notifications WHERE notification_type = 'bench'").execute(&pool),
+        )
+        .expect("delete bench rows");
+}
+
+/// Unprocessed rows for one user, ids in insert order
+async fn insert_pending(pool: &PgPool, count: usize) -> Vec<Uuid> {
+    sqlx::query_scalar(
+        "INSERT INTO activity.notifications (id, user_id, notification_type, title, deliver_at)
+         SELECT gen_random_uuid(), $1, 'bench', 'Bench', NOW() FROM generate_series(1, $2)
+         RETURNING id",
+    )
+    .bind(Uuid::new_v4())
+    .bind(count as i32)
+    .fetch_all(pool)
+    .await
+    .expect("insert bench rows")
+}
+
+criterion_group!(benches, bus_envelope, fcm_request, mark_success);
+criterion_main!(benches);
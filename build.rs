@@ -0,0 +1,37 @@
+/// Crates whose locked versions `GET /admin/status` reports (`status::Dependencies`)
+const REPORTED_DEPENDENCIES: &[&str] = &[
+    "tokio", "axum", "sqlx", "tonic", "reqwest", "rustls", "bus-client", "rdkafka", "async-nats", "redis",
+];
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Vendored protoc: no system protobuf install needed (CI, Docker, dev machines)
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    println!("cargo:rerun-if-changed=proto/notifications.proto");
+
+    tonic_build::configure()
+        .compile_well_known_types(false)
+        .compile_protos(&["proto/notifications.proto"], &["proto"])?;
+
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rustc-env=DEPENDENCY_VERSIONS={}", locked_versions());
+    Ok(())
+}
+
+/// `name=version,...` of the reported crates in Cargo.lock (a crate locked twice is listed twice)
+fn locked_versions() -> String {
+    let lock = std::fs::read_to_string("Cargo.lock").unwrap_or_default();
+    let mut versions = Vec::new();
+    let mut name = None;
+    for line in lock.lines() {
+        if line == "[[package]]" {
+            name = None;
+        } else if let Some(value) = line.strip_prefix("name = ") {
+            name = Some(value.trim_matches('"').to_string());
+        } else if let (Some(value), Some(package)) = (line.strip_prefix("version = "), &name) {
+            if REPORTED_DEPENDENCIES.contains(&package.as_str()) {
+                versions.push(format!("{}={}", package, value.trim_matches('"')));
+            }
+        }
+    }
+    versions.join(",")
+}
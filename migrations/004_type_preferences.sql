@@ -0,0 +1,42 @@
+-- Per-notification-type opt-out preferences
+-- Users can disable specific notification types entirely (e.g. "marketing").
+-- The worker marks such notifications as SUPPRESSED instead of delivering them.
+
+CREATE TABLE IF NOT EXISTS activity.user_notification_preferences (
+    user_id UUID NOT NULL,
+    notification_type TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, notification_type)
+);
+
+-- Suppression is a distinct terminal state: processed, but neither delivered nor failed
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS suppressed_at TIMESTAMP WITH TIME ZONE,
+ADD COLUMN IF NOT EXISTS suppression_reason TEXT;
+
+-- Stored procedure: Mark notification as suppressed (terminal, no delivery attempted)
+CREATE OR REPLACE FUNCTION activity.sp_notification_suppressed(
+    p_notification_id UUID,
+    p_reason TEXT
+) RETURNS BOOLEAN AS $$
+DECLARE
+    v_updated INTEGER;
+BEGIN
+    UPDATE activity.notifications
+    SET
+        is_processed = true,
+        suppressed_at = now(),
+        suppression_reason = p_reason,
+        updated_at = now()
+    WHERE id = p_notification_id
+      AND is_processed = false;
+
+    GET DIAGNOSTICS v_updated = ROW_COUNT;
+    RETURN v_updated > 0;
+END;
+$$ LANGUAGE plpgsql;
+
+COMMENT ON TABLE activity.user_notification_preferences IS 'Per-user opt-out of specific notification types (missing row = enabled)';
+COMMENT ON COLUMN activity.notifications.suppressed_at IS 'Set when the notification was suppressed by user preferences instead of delivered';
+COMMENT ON COLUMN activity.notifications.suppression_reason IS 'Why the notification was suppressed (e.g. type_disabled)';
@@ -0,0 +1,14 @@
+-- Per-channel preference matrix
+-- Each (notification_type, channel) pair can be enabled/disabled per user.
+-- Missing row = enabled. Channels: 'bus' (real-time), 'push' (FCM).
+
+CREATE TABLE IF NOT EXISTS activity.user_channel_preferences (
+    user_id UUID NOT NULL,
+    notification_type TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, notification_type, channel)
+);
+
+COMMENT ON TABLE activity.user_channel_preferences IS 'Per-user enable/disable of a delivery channel for a notification type (missing row = enabled)';
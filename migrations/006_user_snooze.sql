@@ -0,0 +1,10 @@
+-- Per-user snooze window
+-- While snoozed, the worker defers deliveries to snoozed_until (critical priority excepted).
+
+CREATE TABLE IF NOT EXISTS activity.user_snooze (
+    user_id UUID PRIMARY KEY,
+    snoozed_until TIMESTAMP WITH TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE activity.user_snooze IS 'Temporary per-user suppression window; deliveries are deferred until snoozed_until';
@@ -0,0 +1,12 @@
+-- Muted targets ("mute this conversation")
+-- Notifications whose (target_type, target_id) is muted by the recipient are suppressed.
+
+CREATE TABLE IF NOT EXISTS activity.muted_targets (
+    user_id UUID NOT NULL,
+    target_type TEXT NOT NULL,
+    target_id UUID NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, target_type, target_id)
+);
+
+COMMENT ON TABLE activity.muted_targets IS 'Per-user muted targets/threads; matching notifications are suppressed with reason target_muted';
@@ -0,0 +1,8 @@
+-- Thread/group key for client-side notification grouping
+-- Related notifications (same conversation, same post, ...) share a group_key so
+-- clients can stack them instead of showing separate banners.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS group_key TEXT;
+
+COMMENT ON COLUMN activity.notifications.group_key IS 'Grouping key for stacking related notifications (APNs thread-id, Android group, WS payload)';
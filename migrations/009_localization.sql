@@ -0,0 +1,23 @@
+-- Localization: message keys + recipient locale
+-- Producers may set message_key (Fluent message id) + message_args instead of (or in
+-- addition to) literal title/message. The literal text is the fallback.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS message_key TEXT,
+ADD COLUMN IF NOT EXISTS message_args JSONB;
+
+-- Device locale (push is rendered per device)
+ALTER TABLE activity.user_devices
+ADD COLUMN IF NOT EXISTS locale TEXT;
+
+-- Per-user notification settings (locale preference, used when the device has none)
+CREATE TABLE IF NOT EXISTS activity.user_notification_settings (
+    user_id UUID PRIMARY KEY,
+    locale TEXT,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+COMMENT ON COLUMN activity.notifications.message_key IS 'Fluent message id rendered per recipient locale (falls back to title/message)';
+COMMENT ON COLUMN activity.notifications.message_args IS 'Variables for message_key rendering, e.g. {"actor_name": "Alice"}';
+COMMENT ON COLUMN activity.user_devices.locale IS 'Device locale (BCP 47), preferred over the user setting for push';
+COMMENT ON TABLE activity.user_notification_settings IS 'Per-user notification settings (locale, ...)';
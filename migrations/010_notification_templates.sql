@@ -0,0 +1,20 @@
+-- Notification templates
+-- Product teams change copy without code deploys. A notification with template_key
+-- is rendered (Tera syntax) per recipient locale and channel; message_args are the variables.
+
+CREATE TABLE IF NOT EXISTS activity.notification_templates (
+    template_key TEXT NOT NULL,
+    locale TEXT NOT NULL DEFAULT 'en',
+    channel TEXT NOT NULL DEFAULT 'any',
+    title_tpl TEXT NOT NULL,
+    body_tpl TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (template_key, locale, channel)
+);
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS template_key TEXT;
+
+COMMENT ON TABLE activity.notification_templates IS 'Tera templates per (key, locale, channel); channel any = fallback for every channel';
+COMMENT ON COLUMN activity.notifications.template_key IS 'Render title/message from notification_templates (takes precedence over message_key)';
@@ -0,0 +1,44 @@
+-- Template versioning + delivery attempts
+-- Every template save creates a new immutable version; notification_templates holds
+-- the active version. Each delivery attempt records which template version rendered it,
+-- so support can audit exactly what text a user received.
+
+CREATE TABLE IF NOT EXISTS activity.notification_template_versions (
+    template_key TEXT NOT NULL,
+    locale TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    title_tpl TEXT NOT NULL,
+    body_tpl TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (template_key, locale, channel, version)
+);
+
+ALTER TABLE activity.notification_templates
+ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+
+-- Existing templates become version 1
+INSERT INTO activity.notification_template_versions
+    (template_key, locale, channel, version, title_tpl, body_tpl, created_at)
+SELECT template_key, locale, channel, version, title_tpl, body_tpl, updated_at
+FROM activity.notification_templates
+ON CONFLICT DO NOTHING;
+
+-- One row per delivery attempt (per channel, per device for push)
+CREATE TABLE IF NOT EXISTS activity.notification_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    notification_id UUID NOT NULL,
+    channel TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    detail TEXT,
+    template_key TEXT,
+    template_version INTEGER,
+    attempted_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_attempts_notification
+ON activity.notification_attempts (notification_id);
+
+COMMENT ON TABLE activity.notification_template_versions IS 'Immutable history of template content; notification_templates.version points at the active one';
+COMMENT ON TABLE activity.notification_attempts IS 'Delivery attempts per channel (bus, push per device) with outcome and rendering template version';
+COMMENT ON COLUMN activity.notification_attempts.outcome IS 'delivered | failed | no_connection | invalid_token';
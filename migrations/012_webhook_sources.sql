@@ -0,0 +1,19 @@
+-- Webhook ingestion sources
+-- Third-party systems POST to /ingest/webhook/{source}. Each source has its own HMAC
+-- secret and a mapping from JSON pointers in the webhook body to notification fields.
+
+CREATE TABLE IF NOT EXISTS activity.webhook_sources (
+    source TEXT PRIMARY KEY,
+    -- HMAC-SHA256 secret shared with the sender
+    secret TEXT NOT NULL,
+    -- Header carrying the hex signature (optionally prefixed with 'sha256=')
+    signature_header TEXT NOT NULL DEFAULT 'x-signature',
+    -- {"user_id": "/data/user", "title": "/subject", "notification_type": "billing"}
+    -- Values starting with '/' are JSON pointers, anything else is a literal
+    mapping JSONB NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE activity.webhook_sources IS 'Per-source HMAC secrets and payload mappings for /ingest/webhook/{source}';
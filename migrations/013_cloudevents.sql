@@ -0,0 +1,9 @@
+-- CloudEvents context on ingested notifications
+-- source/time of the originating event (CloudEvents 'source' and 'time' attributes)
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS event_source TEXT,
+ADD COLUMN IF NOT EXISTS event_time TIMESTAMP WITH TIME ZONE;
+
+COMMENT ON COLUMN activity.notifications.event_source IS 'CloudEvents source of the event that created this notification';
+COMMENT ON COLUMN activity.notifications.event_time IS 'CloudEvents time: when the originating event happened';
@@ -0,0 +1,36 @@
+-- Delivery receipts for producer services
+-- After a notification reaches a terminal state (delivered, or failed after max retries)
+-- the worker queues a signed receipt for the notification's callback_url and for every
+-- registered receipt webhook. A dispatcher POSTs them with retries.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS callback_url TEXT;
+
+-- Globally registered receipt endpoints (optionally limited to one notification type)
+CREATE TABLE IF NOT EXISTS activity.receipt_webhooks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    url TEXT NOT NULL,
+    notification_type TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+-- Outbox: one row per receipt per target URL
+CREATE TABLE IF NOT EXISTS activity.receipt_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    notification_id UUID NOT NULL,
+    url TEXT NOT NULL,
+    body JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    delivered_at TIMESTAMP WITH TIME ZONE,
+    last_error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_receipt_deliveries_pending
+ON activity.receipt_deliveries (next_attempt_at)
+WHERE delivered_at IS NULL;
+
+COMMENT ON COLUMN activity.notifications.callback_url IS 'Producer URL that receives a signed delivery receipt';
+COMMENT ON TABLE activity.receipt_deliveries IS 'Receipt outbox; rows with delivered_at IS NULL are retried with backoff';
@@ -0,0 +1,67 @@
+-- Multi-tenancy: one deployment serves several apps/brands
+-- Every notification, device and preference row belongs to a tenant. Existing data
+-- and producers that don't set tenant_id land in the 'default' tenant, which uses the
+-- service-wide FCM credentials and Bus topics.
+
+CREATE TABLE IF NOT EXISTS activity.tenants (
+    tenant_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    -- Tenant-specific Firebase project (NULL = service-wide FCM client)
+    fcm_project_id TEXT,
+    fcm_credentials_path TEXT,
+    -- Prefix for Bus topics: '<prefix>.notifications', '<prefix>.global_notifications'
+    bus_topic_prefix TEXT,
+    -- Notification creation quota (NULL = unlimited)
+    rate_limit_per_minute INTEGER,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+INSERT INTO activity.tenants (tenant_id, name)
+VALUES ('default', 'Default')
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS tenant_id TEXT NOT NULL DEFAULT 'default';
+
+ALTER TABLE activity.user_devices
+ADD COLUMN IF NOT EXISTS tenant_id TEXT NOT NULL DEFAULT 'default';
+
+CREATE INDEX IF NOT EXISTS idx_user_devices_tenant_user
+ON activity.user_devices (tenant_id, user_id);
+
+-- Preference tables: tenant becomes part of the key (same user, different app = separate settings)
+ALTER TABLE activity.user_notification_preferences
+ADD COLUMN IF NOT EXISTS tenant_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE activity.user_notification_preferences
+DROP CONSTRAINT IF EXISTS user_notification_preferences_pkey,
+ADD PRIMARY KEY (tenant_id, user_id, notification_type);
+
+ALTER TABLE activity.user_channel_preferences
+ADD COLUMN IF NOT EXISTS tenant_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE activity.user_channel_preferences
+DROP CONSTRAINT IF EXISTS user_channel_preferences_pkey,
+ADD PRIMARY KEY (tenant_id, user_id, notification_type, channel);
+
+ALTER TABLE activity.user_snooze
+ADD COLUMN IF NOT EXISTS tenant_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE activity.user_snooze
+DROP CONSTRAINT IF EXISTS user_snooze_pkey,
+ADD PRIMARY KEY (tenant_id, user_id);
+
+ALTER TABLE activity.muted_targets
+ADD COLUMN IF NOT EXISTS tenant_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE activity.muted_targets
+DROP CONSTRAINT IF EXISTS muted_targets_pkey,
+ADD PRIMARY KEY (tenant_id, user_id, target_type, target_id);
+
+ALTER TABLE activity.user_notification_settings
+ADD COLUMN IF NOT EXISTS tenant_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE activity.user_notification_settings
+DROP CONSTRAINT IF EXISTS user_notification_settings_pkey,
+ADD PRIMARY KEY (tenant_id, user_id);
+
+COMMENT ON TABLE activity.tenants IS 'Apps/brands served by this deployment, with per-tenant FCM, Bus and quota config';
+COMMENT ON COLUMN activity.notifications.tenant_id IS 'Owning tenant; selects FCM credentials, Bus topics and preference rows';
+COMMENT ON COLUMN activity.user_devices.tenant_id IS 'Tenant (app) the device token was registered for';
@@ -0,0 +1,8 @@
+-- Service identity of the creator
+-- Set for notifications created over the mTLS listener (client certificate URI/DNS SAN or CN),
+-- so a notification can always be traced back to the service that sent it.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS created_by TEXT;
+
+COMMENT ON COLUMN activity.notifications.created_by IS 'Authenticated service identity that created the notification (NULL = direct INSERT or bearer token)';
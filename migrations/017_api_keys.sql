@@ -0,0 +1,25 @@
+-- API keys for services calling the management / create API
+-- Replaces sharing one static token: every consumer gets its own key with scopes,
+-- optional expiry and tenant binding, and keys can be rotated or revoked individually.
+-- Only the SHA-256 hash is stored; the plaintext key is shown once at creation/rotation.
+
+CREATE TABLE IF NOT EXISTS activity.api_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    -- First characters of the key, to recognise it in listings/logs
+    key_prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    -- e.g. {notifications:create} or {admin}
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    -- Key may only create notifications for this tenant (NULL = any)
+    tenant_id TEXT,
+    expires_at TIMESTAMP WITH TIME ZONE,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    -- Key this one replaced (rotation chain)
+    rotated_from UUID,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+COMMENT ON TABLE activity.api_keys IS 'Hashed API keys with scopes; Authorization: Bearer nsk_...';
+COMMENT ON COLUMN activity.api_keys.key_hash IS 'Hex SHA-256 of the full key';
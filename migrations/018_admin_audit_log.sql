@@ -0,0 +1,18 @@
+-- Audit trail of management API mutations (templates, tenants, webhooks, API keys)
+-- Append-only: the service never updates or deletes rows.
+
+CREATE TABLE IF NOT EXISTS activity.admin_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    -- Certificate identity, api_key:<name>, user:<sub> or admin_token
+    actor TEXT NOT NULL,
+    -- e.g. template.save, api_key.revoke
+    action TEXT NOT NULL,
+    -- Request parameters (never secrets)
+    params JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_admin_audit_log_actor
+ON activity.admin_audit_log (actor, id DESC);
+
+COMMENT ON TABLE activity.admin_audit_log IS 'Append-only log of admin mutations; GET /api/v1/audit-log';
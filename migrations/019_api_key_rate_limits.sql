@@ -0,0 +1,5 @@
+-- Per-API-key creation quota (notifications/minute), next to tenants.rate_limit_per_minute
+-- NULL = unlimited. Rotated keys inherit the limit.
+
+ALTER TABLE activity.api_keys
+ADD COLUMN IF NOT EXISTS rate_limit_per_minute INTEGER CHECK (rate_limit_per_minute > 0);
@@ -0,0 +1,49 @@
+-- Fix the success/failure procedures from 001
+-- sp_notification_success compared a BOOLEAN with 0 and errored on every call, so
+-- delivered notifications were never marked processed. Both procedures also matched
+-- on notification_id; the worker passes notifications.id (like sp_notification_suppressed).
+
+CREATE OR REPLACE FUNCTION activity.sp_notification_success(
+    p_notification_id UUID
+) RETURNS BOOLEAN AS $$
+DECLARE
+    v_updated INTEGER;
+BEGIN
+    UPDATE activity.notifications
+    SET
+        is_processed = true,
+        updated_at = now()
+    WHERE id = p_notification_id
+      AND is_processed = false;
+
+    GET DIAGNOSTICS v_updated = ROW_COUNT;
+    RETURN v_updated > 0;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION activity.sp_notification_failure(
+    p_notification_id UUID,
+    p_error_message TEXT,
+    p_max_retries INTEGER DEFAULT 3
+) RETURNS BOOLEAN AS $$
+DECLARE
+    v_new_error_count INTEGER;
+    v_should_stop BOOLEAN := false;
+BEGIN
+    UPDATE activity.notifications
+    SET
+        error_count = error_count + 1,
+        last_error = p_error_message,
+        last_error_at = now(),
+        updated_at = now(),
+        -- If we hit max retries, mark as processed to stop the loop
+        is_processed = CASE
+            WHEN error_count + 1 >= p_max_retries THEN true
+            ELSE false
+        END
+    WHERE id = p_notification_id
+    RETURNING error_count, is_processed INTO v_new_error_count, v_should_stop;
+
+    RETURN COALESCE(v_should_stop, false);
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,2 @@
+-- DELIVERY_MODE=simulate records deliveries without calling FCM/Bus
+COMMENT ON COLUMN activity.notification_attempts.outcome IS 'delivered | failed | no_connection | invalid_token | simulated';
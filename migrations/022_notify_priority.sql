@@ -0,0 +1,14 @@
+-- NOTIFY payload carries the priority: `<id> <priority>`
+-- The worker debounces bursts of wake-ups (WORKER_WAKE_DEBOUNCE_MS) but wakes
+-- immediately for high/critical inserts. Older workers ignore the payload.
+
+CREATE OR REPLACE FUNCTION activity.fn_notification_inserted()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('notify_event', NEW.id::text || ' ' || COALESCE(NEW.priority, 'normal'));
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+COMMENT ON FUNCTION activity.fn_notification_inserted() IS
+    'Sends pg_notify signal (payload: id and priority) when a notification is inserted, waking up the Rust worker';
@@ -0,0 +1,38 @@
+-- Recurring notifications (weekly summaries, reminders)
+-- The scheduler materializes a regular notification row per recipient at each cron
+-- occurrence; from there the normal NOTIFY → worker path delivers it.
+
+CREATE TABLE IF NOT EXISTS activity.recurring_notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id TEXT NOT NULL DEFAULT 'default',
+    name TEXT NOT NULL,
+    -- 5-field cron (minute hour day-of-month month day-of-week), day names for weekdays
+    cron TEXT NOT NULL,
+    -- IANA zone the cron expression is evaluated in ('Europe/Amsterdam')
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+    -- {"type": "users", "user_ids": [...]} or {"type": "broadcast"}
+    audience JSONB NOT NULL,
+    notification_type TEXT NOT NULL,
+    -- Literal text, or the fallback when template_key/message_key render
+    title TEXT NOT NULL,
+    message TEXT,
+    template_key TEXT,
+    message_key TEXT,
+    message_args JSONB,
+    payload JSONB,
+    deep_link TEXT,
+    priority TEXT NOT NULL DEFAULT 'normal',
+    paused BOOLEAN NOT NULL DEFAULT false,
+    next_run_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    last_run_at TIMESTAMP WITH TIME ZONE,
+    created_by TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_recurring_notifications_due
+    ON activity.recurring_notifications (next_run_at)
+    WHERE NOT paused;
+
+COMMENT ON TABLE activity.recurring_notifications IS 'Cron schedules materialized into activity.notifications by the recurring scheduler';
+COMMENT ON COLUMN activity.recurring_notifications.next_run_at IS 'Next occurrence (UTC); missed occurrences are not caught up, only the latest one is sent';
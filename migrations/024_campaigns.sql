@@ -0,0 +1,54 @@
+-- Campaigns: one notification to a segment of users, fanned out at a throttled rate
+-- The campaign runner resolves the audience into campaign_recipients when the campaign
+-- starts and inserts regular notification rows in chunks (rate_per_minute).
+
+CREATE TABLE IF NOT EXISTS activity.campaigns (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id TEXT NOT NULL DEFAULT 'default',
+    name TEXT NOT NULL,
+    -- {"type": "users"} (ids uploaded into campaign_recipients) or
+    -- {"type": "segment", "sql": "SELECT user_id FROM ..."} (resolved at start)
+    audience JSONB NOT NULL,
+    notification_type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    message TEXT,
+    template_key TEXT,
+    message_key TEXT,
+    message_args JSONB,
+    payload JSONB,
+    deep_link TEXT,
+    priority TEXT NOT NULL DEFAULT 'normal',
+    start_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    rate_per_minute INTEGER NOT NULL CHECK (rate_per_minute > 0),
+    status TEXT NOT NULL DEFAULT 'scheduled'
+        CHECK (status IN ('scheduled', 'running', 'paused', 'completed', 'cancelled')),
+    total_recipients INTEGER NOT NULL DEFAULT 0,
+    queued_count INTEGER NOT NULL DEFAULT 0,
+    -- Throttle bookkeeping: rows are released for the time since the last chunk
+    last_fanout_at TIMESTAMP WITH TIME ZONE,
+    last_error TEXT,
+    created_by TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    started_at TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_campaigns_active
+    ON activity.campaigns (start_at)
+    WHERE status IN ('scheduled', 'running');
+
+CREATE TABLE IF NOT EXISTS activity.campaign_recipients (
+    campaign_id UUID NOT NULL REFERENCES activity.campaigns(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL,
+    -- Set when the recipient's notification row is inserted
+    notification_id UUID,
+    queued_at TIMESTAMP WITH TIME ZONE,
+    PRIMARY KEY (campaign_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_campaign_recipients_pending
+    ON activity.campaign_recipients (campaign_id, user_id)
+    WHERE notification_id IS NULL;
+
+COMMENT ON TABLE activity.campaigns IS 'Segmented one-off sends, fanned out into activity.notifications by the campaign runner';
+COMMENT ON TABLE activity.campaign_recipients IS 'Resolved audience per campaign and the notification created for each recipient';
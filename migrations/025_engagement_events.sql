@@ -0,0 +1,21 @@
+-- Engagement tracking: opens reported by clients and deep_link click-throughs
+-- Append-only; notification_type and tenant_id are copied so stats survive notification cleanup.
+
+CREATE TABLE IF NOT EXISTS activity.notification_engagement (
+    id BIGSERIAL PRIMARY KEY,
+    notification_id UUID NOT NULL,
+    -- Recipient; for broadcasts the user who opened it (nil for anonymous clicks)
+    user_id UUID NOT NULL,
+    tenant_id TEXT NOT NULL DEFAULT 'default',
+    notification_type TEXT NOT NULL,
+    event TEXT NOT NULL CHECK (event IN ('opened', 'clicked')),
+    occurred_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_engagement_notification
+    ON activity.notification_engagement (notification_id, event);
+
+CREATE INDEX IF NOT EXISTS idx_notification_engagement_type
+    ON activity.notification_engagement (notification_type, occurred_at);
+
+COMMENT ON TABLE activity.notification_engagement IS 'Open and click events per notification, summarized by GET /api/v1/engagement';
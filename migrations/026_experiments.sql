@@ -0,0 +1,39 @@
+-- A/B content experiments
+-- An experiment splits the recipients of one template or one campaign over variants
+-- (alternative title/body). The worker assigns a variant from a hash of the user id,
+-- so a user always sees the same variant, and records it on the delivery attempt.
+
+CREATE TABLE IF NOT EXISTS activity.experiments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    -- Exactly one target: notifications with this template_key, or rows of this campaign
+    template_key TEXT,
+    campaign_id UUID REFERENCES activity.campaigns(id) ON DELETE CASCADE,
+    -- [{"name": "A", "title": "...", "body": "..."}]; a missing title/body keeps the original text
+    variants JSONB NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT true,
+    created_by TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    stopped_at TIMESTAMP WITH TIME ZONE,
+    CHECK ((template_key IS NULL) <> (campaign_id IS NULL))
+);
+
+-- One running experiment per target
+CREATE UNIQUE INDEX IF NOT EXISTS idx_experiments_active_template
+    ON activity.experiments (template_key)
+    WHERE active AND template_key IS NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_experiments_active_campaign
+    ON activity.experiments (campaign_id)
+    WHERE active AND campaign_id IS NOT NULL;
+
+ALTER TABLE activity.notification_attempts
+ADD COLUMN IF NOT EXISTS experiment_id UUID,
+ADD COLUMN IF NOT EXISTS variant TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_notification_attempts_experiment
+    ON activity.notification_attempts (experiment_id, variant)
+    WHERE experiment_id IS NOT NULL;
+
+COMMENT ON TABLE activity.experiments IS 'A/B variants of notification text per template or campaign';
+COMMENT ON COLUMN activity.notification_attempts.variant IS 'Experiment variant the delivered text came from (NULL outside experiments)';
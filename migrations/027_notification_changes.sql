@@ -0,0 +1,64 @@
+-- Change log for client delta-sync (GET /api/v1/notifications/sync?since=)
+-- A trigger appends one row per change a client's inbox must follow. Rows become
+-- visible once the worker has processed them; suppressed rows never show up.
+-- The log id is the sync cursor (last_event_id).
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS read_at TIMESTAMP WITH TIME ZONE;
+
+CREATE TABLE IF NOT EXISTS activity.notification_changes (
+    id BIGSERIAL PRIMARY KEY,
+    notification_id UUID NOT NULL,
+    -- Nil user id for broadcasts (synced to every user of the tenant)
+    user_id UUID NOT NULL,
+    tenant_id TEXT NOT NULL DEFAULT 'default',
+    change TEXT NOT NULL CHECK (change IN ('created', 'updated', 'read', 'deleted')),
+    changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_changes_user
+    ON activity.notification_changes (tenant_id, user_id, id);
+
+CREATE OR REPLACE FUNCTION activity.fn_notification_changed()
+RETURNS TRIGGER AS $$
+DECLARE
+    v_change TEXT;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        IF OLD.is_processed AND OLD.suppressed_at IS NULL THEN
+            INSERT INTO activity.notification_changes (notification_id, user_id, tenant_id, change)
+            VALUES (OLD.id, OLD.user_id, OLD.tenant_id, 'deleted');
+        END IF;
+        RETURN NULL;
+    END IF;
+
+    IF NOT NEW.is_processed OR NEW.suppressed_at IS NOT NULL THEN
+        RETURN NULL;
+    END IF;
+
+    IF TG_OP = 'INSERT' OR NOT OLD.is_processed THEN
+        v_change := 'created';
+    ELSIF OLD.read_at IS NULL AND NEW.read_at IS NOT NULL THEN
+        v_change := 'read';
+    ELSIF (OLD.title, OLD.message, OLD.payload, OLD.deep_link, OLD.priority, OLD.group_key)
+          IS DISTINCT FROM (NEW.title, NEW.message, NEW.payload, NEW.deep_link, NEW.priority, NEW.group_key) THEN
+        v_change := 'updated';
+    END IF;
+
+    IF v_change IS NOT NULL THEN
+        INSERT INTO activity.notification_changes (notification_id, user_id, tenant_id, change)
+        VALUES (NEW.id, NEW.user_id, NEW.tenant_id, v_change);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_notification_changed ON activity.notifications;
+
+CREATE TRIGGER trg_notification_changed
+    AFTER INSERT OR UPDATE OR DELETE ON activity.notifications
+    FOR EACH ROW
+    EXECUTE FUNCTION activity.fn_notification_changed();
+
+COMMENT ON TABLE activity.notification_changes IS 'Inbox change log per user; id is the delta-sync cursor';
+COMMENT ON COLUMN activity.notifications.read_at IS 'When the recipient marked the notification read (POST /api/v1/notifications/read)';
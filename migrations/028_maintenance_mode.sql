@@ -0,0 +1,18 @@
+-- Maintenance mode: one service-wide flag that parks deliveries
+-- While enabled, notifications are still accepted and stored, but the worker sends
+-- nothing. After disabling, the parked backlog drains in priority order at
+-- MAINTENANCE_DRAIN_RATE_PER_SEC per worker.
+
+CREATE TABLE IF NOT EXISTS activity.maintenance_mode (
+    -- Single row
+    id BOOLEAN PRIMARY KEY DEFAULT true CHECK (id),
+    enabled BOOLEAN NOT NULL DEFAULT false,
+    reason TEXT,
+    changed_by TEXT,
+    changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+INSERT INTO activity.maintenance_mode (id, enabled) VALUES (true, false)
+ON CONFLICT (id) DO NOTHING;
+
+COMMENT ON TABLE activity.maintenance_mode IS 'Service-wide maintenance flag; while enabled the worker parks all deliveries';
@@ -0,0 +1,27 @@
+-- Cold-storage archives: manifest of exported notification batches
+-- The archiver writes processed notifications older than ARCHIVE_AFTER_DAYS to object
+-- storage (gzip NDJSON, one `to_jsonb(row)` per line), records the object here and
+-- deletes the rows in the same transaction. `notifications-service restore` reads an
+-- archive back in.
+
+CREATE TABLE IF NOT EXISTS activity.notification_archives (
+    id UUID PRIMARY KEY,
+    -- s3://bucket/key, gs://bucket/key or file:///path
+    object_url TEXT NOT NULL,
+    row_count INTEGER NOT NULL,
+    bytes BIGINT NOT NULL,
+    -- hex SHA-256 of the object, checked on restore
+    sha256 TEXT NOT NULL,
+    -- created_at range of the exported rows
+    from_created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    to_created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    -- Restored rows stay in the hot table for another ARCHIVE_AFTER_DAYS
+    restored_at TIMESTAMP WITH TIME ZONE,
+    restored_by TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_archives_created
+ON activity.notification_archives (from_created_at);
+
+COMMENT ON TABLE activity.notification_archives IS 'Notification batches exported to object storage and pruned from activity.notifications';
@@ -0,0 +1,14 @@
+-- Per-device preferences: quiet hours and enabled types per device
+-- Consulted for push only (the Bus reaches whatever the user has open). A device in its
+-- quiet hours is skipped unless the notification is critical; when every device is
+-- quiet the notification is deferred to the earliest quiet-hours end.
+
+ALTER TABLE activity.user_devices
+ADD COLUMN IF NOT EXISTS quiet_hours_start TIME,
+ADD COLUMN IF NOT EXISTS quiet_hours_end TIME,
+ADD COLUMN IF NOT EXISTS quiet_hours_timezone TEXT,
+ADD COLUMN IF NOT EXISTS enabled_types TEXT[];
+
+COMMENT ON COLUMN activity.user_devices.quiet_hours_start IS 'Local start of the nightly push pause (with quiet_hours_end, may wrap midnight)';
+COMMENT ON COLUMN activity.user_devices.quiet_hours_timezone IS 'IANA zone quiet hours are evaluated in (default UTC)';
+COMMENT ON COLUMN activity.user_devices.enabled_types IS 'Notification types this device gets pushes for (NULL = all)';
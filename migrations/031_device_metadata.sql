@@ -0,0 +1,21 @@
+-- Device metadata and targeting
+-- Apps report app/OS version and locale when they register a device (POST /api/v1/devices);
+-- last_seen_at moves on registration and on successful pushes. Campaigns can restrict
+-- delivery to matching devices (device_filter, e.g. {"app_version < 3.2"}); the worker
+-- copies the filter onto every fanned-out notification and checks it per device.
+
+ALTER TABLE activity.user_devices
+ADD COLUMN IF NOT EXISTS app_version TEXT,
+ADD COLUMN IF NOT EXISTS os_version TEXT,
+ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE activity.campaigns
+ADD COLUMN IF NOT EXISTS device_filter TEXT[];
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS device_filter TEXT[];
+
+COMMENT ON COLUMN activity.user_devices.app_version IS 'App version reported at registration (dotted, e.g. 3.1.4)';
+COMMENT ON COLUMN activity.user_devices.last_seen_at IS 'Last registration or successful push';
+COMMENT ON COLUMN activity.campaigns.device_filter IS 'Conditions every targeted device must meet (see src/targeting.rs), NULL = all devices';
+COMMENT ON COLUMN activity.notifications.device_filter IS 'Push only to devices meeting these conditions (copied from the campaign); skips the Bus';
@@ -0,0 +1,10 @@
+-- First-ack-wins delivery (FIRST_ACK_TYPES, e.g. incoming calls)
+-- The worker rings every device; the first device to ack (POST /api/v1/notifications/{id}/ack)
+-- is recorded here, and the others get a dismiss (FCM data message + Bus).
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS acked_at TIMESTAMP WITH TIME ZONE,
+ADD COLUMN IF NOT EXISTS acked_by_device TEXT;
+
+COMMENT ON COLUMN activity.notifications.acked_at IS 'First ack of a first-ack-wins notification (later acks lose)';
+COMMENT ON COLUMN activity.notifications.acked_by_device IS 'FCM token of the device that acked first (NULL = acked over the Bus/web)';
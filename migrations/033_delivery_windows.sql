@@ -0,0 +1,7 @@
+-- Business-hours delivery windows (DELIVERY_WINDOWS=marketing=09:00-20:00)
+-- Windows are in recipient-local time: the user's timezone, else DELIVERY_WINDOW_TIMEZONE.
+
+ALTER TABLE activity.user_notification_settings
+ADD COLUMN IF NOT EXISTS timezone TEXT;
+
+COMMENT ON COLUMN activity.user_notification_settings.timezone IS 'IANA zone (e.g. Europe/Amsterdam) for delivery windows; NULL = DELIVERY_WINDOW_TIMEZONE';
@@ -0,0 +1,5 @@
+-- Recent failures for GET /admin/failures and the admin UI (newest first)
+
+CREATE INDEX IF NOT EXISTS idx_notifications_last_error_at
+ON activity.notifications (last_error_at DESC)
+WHERE last_error_at IS NOT NULL;
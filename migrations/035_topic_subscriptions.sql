@@ -0,0 +1,21 @@
+-- User-subscribable topics ("follow this project")
+-- Users subscribe with PUT /api/v1/topics/{topic}. A notification with `topic` set (and the
+-- nil user_id) is expanded by the worker into one notification per subscriber, in the same
+-- transaction that marks it processed; the copies keep `topic` for reference.
+
+CREATE TABLE IF NOT EXISTS activity.topic_subscriptions (
+    tenant_id TEXT NOT NULL DEFAULT 'default',
+    topic TEXT NOT NULL,
+    user_id UUID NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (tenant_id, topic, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_topic_subscriptions_user
+ON activity.topic_subscriptions (tenant_id, user_id);
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS topic TEXT;
+
+COMMENT ON TABLE activity.topic_subscriptions IS 'Users following a topic (e.g. project:<id>); topic notifications fan out to them';
+COMMENT ON COLUMN activity.notifications.topic IS 'Topic target: with the nil user_id the worker fans out to subscribers; on the copies it records the source topic';
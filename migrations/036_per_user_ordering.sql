@@ -0,0 +1,6 @@
+-- Per-user ordering: CONSUMPTION_MODE=transactional only claims the oldest due
+-- notification of each recipient (the head), which this index finds per user
+
+CREATE INDEX IF NOT EXISTS idx_notifications_unprocessed_recipient
+ON activity.notifications (tenant_id, user_id, deliver_at, created_at)
+WHERE is_processed = false;
@@ -0,0 +1,45 @@
+-- Failure classification: the worker stores the root cause of the last failure next
+-- to last_error, so GET /admin/failures?category= can group recurring causes.
+-- Rows that failed before this migration keep a NULL category.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS failure_category TEXT;
+
+COMMENT ON COLUMN activity.notifications.failure_category IS
+    'Root cause of last_error (invalid_token, fcm_quota, fcm_unavailable, bus_unreachable, template_error, validation, no_devices, database, other)';
+
+-- The 3-argument version would make calls with a category ambiguous
+DROP FUNCTION IF EXISTS activity.sp_notification_failure(UUID, TEXT, INTEGER);
+
+CREATE OR REPLACE FUNCTION activity.sp_notification_failure(
+    p_notification_id UUID,
+    p_error_message TEXT,
+    p_max_retries INTEGER DEFAULT 3,
+    p_failure_category TEXT DEFAULT NULL
+) RETURNS BOOLEAN AS $$
+DECLARE
+    v_new_error_count INTEGER;
+    v_should_stop BOOLEAN := false;
+BEGIN
+    UPDATE activity.notifications
+    SET
+        error_count = error_count + 1,
+        last_error = p_error_message,
+        last_error_at = now(),
+        failure_category = p_failure_category,
+        updated_at = now(),
+        -- If we hit max retries, mark as processed to stop the loop
+        is_processed = CASE
+            WHEN error_count + 1 >= p_max_retries THEN true
+            ELSE false
+        END
+    WHERE id = p_notification_id
+    RETURNING error_count, is_processed INTO v_new_error_count, v_should_stop;
+
+    RETURN COALESCE(v_should_stop, false);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE INDEX IF NOT EXISTS idx_notifications_failure_category
+ON activity.notifications (failure_category, last_error_at DESC)
+WHERE failure_category IS NOT NULL;
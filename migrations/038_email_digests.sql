@@ -0,0 +1,55 @@
+-- Daily email digest of unread notifications (opt-in)
+-- Users opt in with PUT /api/v1/digest. The digest job sends one email a day at
+-- DIGEST_HOUR in the user's timezone, summarizing what is still unread in the inbox,
+-- and stamps those rows with digested_at so the next digest doesn't repeat them.
+
+CREATE TABLE IF NOT EXISTS activity.digest_subscriptions (
+    tenant_id TEXT NOT NULL DEFAULT 'default',
+    user_id UUID NOT NULL,
+    email TEXT NOT NULL,
+    -- NULL until the job schedules the first digest
+    next_run_at TIMESTAMP WITH TIME ZONE,
+    last_sent_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (tenant_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_digest_subscriptions_due
+ON activity.digest_subscriptions (next_run_at NULLS FIRST);
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS digested_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS idx_notifications_undigested
+ON activity.notifications (tenant_id, user_id, created_at DESC)
+WHERE read_at IS NULL AND digested_at IS NULL;
+
+-- Default copy (Tera, plain text); PUT /api/v1/templates/email_digest/{locale}/any replaces it
+INSERT INTO activity.notification_templates (template_key, locale, channel, title_tpl, body_tpl)
+VALUES
+    ('email_digest', 'en', 'any',
+     $tpl${{ count }} unread notification{% if count != 1 %}s{% endif %}$tpl$,
+     $tpl$You have {{ count }} unread notification{% if count != 1 %}s{% endif %}:
+{% for item in items %}
+- {{ item.title }}{% if item.body %}: {{ item.body }}{% endif %}{% endfor %}
+{% if more > 0 %}
+...and {{ more }} more.{% endif %}$tpl$),
+    ('email_digest', 'nl', 'any',
+     $tpl${{ count }} ongelezen melding{% if count != 1 %}en{% endif %}$tpl$,
+     $tpl$Je hebt {{ count }} ongelezen melding{% if count != 1 %}en{% endif %}:
+{% for item in items %}
+- {{ item.title }}{% if item.body %}: {{ item.body }}{% endif %}{% endfor %}
+{% if more > 0 %}
+...en nog {{ more }}.{% endif %}$tpl$)
+ON CONFLICT DO NOTHING;
+
+INSERT INTO activity.notification_template_versions
+    (template_key, locale, channel, version, title_tpl, body_tpl, created_at)
+SELECT template_key, locale, channel, version, title_tpl, body_tpl, updated_at
+FROM activity.notification_templates
+WHERE template_key = 'email_digest'
+ON CONFLICT DO NOTHING;
+
+COMMENT ON TABLE activity.digest_subscriptions IS 'Users who receive the daily email digest of unread notifications';
+COMMENT ON COLUMN activity.notifications.digested_at IS 'When the notification was summarized in an email digest (never summarized twice)';
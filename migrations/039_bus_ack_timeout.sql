@@ -0,0 +1,9 @@
+-- Bus delivery confirmation (BUS_ACK_TIMEOUT_SECS)
+-- A publish that reached a socket only proves the Bus queued it: the app may have crashed.
+-- The worker records the Bus delivery here and defers the row; a client ack
+-- (POST /api/v1/notifications/{id}/ack) confirms it, otherwise push takes over at deliver_at.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS bus_delivered_at TIMESTAMP WITH TIME ZONE;
+
+COMMENT ON COLUMN activity.notifications.bus_delivered_at IS 'Bus delivery waiting for a client ack; unacked by deliver_at = fall back to push';
@@ -0,0 +1,16 @@
+-- Push environments: sandbox (dev/staging builds) next to production
+-- Apps register dev builds with push_environment = 'sandbox'; the worker pushes those
+-- devices through the sandbox Firebase project (FCM_SANDBOX_*, or the tenant's own
+-- fcm_sandbox_* columns). FCM picks the APNs gateway from the token, so the project is
+-- all that differs. NULL = the service-wide default (PUSH_ENVIRONMENT).
+
+ALTER TABLE activity.user_devices
+ADD COLUMN IF NOT EXISTS push_environment TEXT
+    CHECK (push_environment IN ('production', 'sandbox'));
+
+ALTER TABLE activity.tenants
+ADD COLUMN IF NOT EXISTS fcm_sandbox_project_id TEXT,
+ADD COLUMN IF NOT EXISTS fcm_sandbox_credentials_path TEXT;
+
+COMMENT ON COLUMN activity.user_devices.push_environment IS 'production or sandbox (dev build), NULL = PUSH_ENVIRONMENT';
+COMMENT ON COLUMN activity.tenants.fcm_sandbox_project_id IS 'Tenant Firebase project for sandbox devices (NULL = the service-wide sandbox project)';
@@ -0,0 +1,18 @@
+-- Duplicate-broadcast suppression (BROADCAST_DEDUP_WINDOW_SECS)
+-- The worker fingerprints a broadcast's content (SHA-256) and claims the fingerprint here
+-- before sending. An identical broadcast within the window is suppressed as
+-- 'duplicate_broadcast'; allow_duplicate = true sends it anyway (intentional re-send).
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS allow_duplicate BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE IF NOT EXISTS activity.broadcast_fingerprints (
+    tenant_id TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    notification_id UUID NOT NULL,
+    sent_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, content_hash)
+);
+
+COMMENT ON COLUMN activity.notifications.allow_duplicate IS 'Broadcast only: send even if an identical broadcast went out within BROADCAST_DEDUP_WINDOW_SECS';
+COMMENT ON TABLE activity.broadcast_fingerprints IS 'Last broadcast sent per content hash (one row per distinct content)';
@@ -0,0 +1,23 @@
+-- Per-user broadcast fan-out (BROADCAST_FANOUT=per_user)
+-- Instead of one Bus/FCM topic send, a broadcast becomes one ordinary notification per
+-- user with a registered device, released in chunks at BROADCAST_FANOUT_RATE_PER_MINUTE.
+-- Users are walked in user_id order; `cursor` is the last one queued, so a restart
+-- resumes after it. Progress: GET /admin/broadcasts/{id}.
+
+CREATE TABLE IF NOT EXISTS activity.broadcast_fanouts (
+    notification_id UUID PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed')),
+    total_recipients INTEGER NOT NULL,
+    queued_count INTEGER NOT NULL DEFAULT 0,
+    cursor UUID,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_fanout_at TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_broadcast_fanouts_running
+ON activity.broadcast_fanouts (started_at) WHERE status = 'running';
+
+COMMENT ON COLUMN activity.broadcast_fanouts.total_recipients IS 'Users with a device when the fan-out started (devices added later may push queued_count past it)';
+COMMENT ON COLUMN activity.broadcast_fanouts.cursor IS 'Last user_id queued; the next chunk starts after it';
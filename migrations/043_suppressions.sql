@@ -0,0 +1,32 @@
+-- Suppression list: endpoints the worker must never deliver to
+-- A user (legal opt-out, uninstalled), an FCM token (dead) or an email address (bounced),
+-- per tenant or global (tenant_id NULL). Checked before every delivery; expires_at lets
+-- an entry lapse on its own. Managed at /api/v1/suppressions.
+-- Tokens are added automatically after SUPPRESS_AFTER_UNREGISTERED UNREGISTERED errors,
+-- counted in unregistered_tokens (the device row itself is removed on every error).
+
+CREATE TABLE IF NOT EXISTS activity.suppressions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id TEXT,
+    kind TEXT NOT NULL CHECK (kind IN ('user', 'token', 'email')),
+    value TEXT NOT NULL,
+    reason TEXT NOT NULL CHECK (reason IN ('legal_opt_out', 'uninstalled', 'bounced', 'unregistered', 'other')),
+    note TEXT,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_suppressions_entry
+ON activity.suppressions (kind, value, COALESCE(tenant_id, ''));
+
+CREATE TABLE IF NOT EXISTS activity.unregistered_tokens (
+    fcm_token TEXT PRIMARY KEY,
+    failures INTEGER NOT NULL DEFAULT 1,
+    first_failed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_failed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON COLUMN activity.suppressions.tenant_id IS 'NULL = global, applies to every tenant';
+COMMENT ON COLUMN activity.suppressions.value IS 'user_id, fcm_token or email address (lowercased), depending on kind';
+COMMENT ON COLUMN activity.suppressions.expires_at IS 'NULL = permanent';
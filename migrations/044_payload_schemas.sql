@@ -0,0 +1,18 @@
+-- Payload contracts: a JSON Schema per notification_type
+-- Registered with PUT /api/v1/payload-schemas/{type}. The create API (and every other
+-- ingestion source) rejects a notification whose payload doesn't match; the worker
+-- checks again before delivery and gives up on a mismatch (failure_category 'validation'),
+-- which catches rows inserted directly or queued before the schema changed.
+-- Types without a schema accept any payload.
+
+CREATE TABLE IF NOT EXISTS activity.payload_schemas (
+    notification_type TEXT PRIMARY KEY,
+    schema JSONB NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    updated_by TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+COMMENT ON COLUMN activity.payload_schemas.schema IS 'JSON Schema (draft 2020-12 unless $schema says otherwise) for notifications.payload';
+COMMENT ON COLUMN activity.payload_schemas.version IS 'Bumped on every change';
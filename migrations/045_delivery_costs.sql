@@ -0,0 +1,20 @@
+-- Delivery cost accounting: deliveries and their cost per tenant, UTC day and channel
+-- Unit prices come from CHANNEL_COSTS (bus, push, email); workers add their counts every
+-- few seconds. A tenant's daily_budget caps the day's total: once it is spent, non-critical
+-- notifications only use channels that cost nothing and email digests are skipped.
+
+CREATE TABLE IF NOT EXISTS activity.delivery_costs (
+    tenant_id TEXT NOT NULL,
+    day DATE NOT NULL,
+    channel TEXT NOT NULL,
+    deliveries BIGINT NOT NULL DEFAULT 0,
+    cost DOUBLE PRECISION NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, day, channel)
+);
+
+ALTER TABLE activity.tenants
+ADD COLUMN IF NOT EXISTS daily_budget DOUBLE PRECISION CHECK (daily_budget >= 0);
+
+COMMENT ON COLUMN activity.delivery_costs.cost IS 'deliveries x the CHANNEL_COSTS unit price at the time';
+COMMENT ON COLUMN activity.tenants.daily_budget IS 'Maximum delivery cost per UTC day (NULL = unlimited), same unit as CHANNEL_COSTS';
@@ -0,0 +1,13 @@
+-- Pinned announcements: banner-style messages that outlive their push
+-- A notification with pinned_until is delivered once like any other and is then listed by
+-- GET /api/v1/announcements until that time. Broadcasts are listed for every user of the
+-- tenant, topic sends through each subscriber's copy.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS pinned_until TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS idx_notifications_pinned
+ON activity.notifications (tenant_id, pinned_until)
+WHERE pinned_until IS NOT NULL;
+
+COMMENT ON COLUMN activity.notifications.pinned_until IS 'Listed as an announcement until this time (NULL = not an announcement)';
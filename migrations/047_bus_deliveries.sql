@@ -0,0 +1,23 @@
+-- Exactly-once accounting for Bus deliveries
+-- Every Bus publish that reached a connection is recorded under a fresh delivery_id (also
+-- sent in the payload) outside the claim transaction, so it survives a crash before the
+-- mark. A retry that finds a recent record marks the row instead of publishing again.
+-- The mark only succeeds for a row that is still unprocessed: a racing replica or an
+-- earlier run that got there first means the delivery, its receipt and its metrics were
+-- already counted. Marked rows keep the delivery they were counted for. Records are
+-- removed with the mark, or when the client never acked (BUS_ACK_TIMEOUT_SECS).
+
+CREATE TABLE IF NOT EXISTS activity.bus_deliveries (
+    delivery_id UUID PRIMARY KEY,
+    notification_id UUID NOT NULL,
+    delivered_to INTEGER NOT NULL,
+    published_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_bus_deliveries_notification
+ON activity.bus_deliveries (notification_id, published_at DESC);
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS bus_delivery_id UUID;
+
+COMMENT ON COLUMN activity.notifications.bus_delivery_id IS 'Bus publish the delivery was counted for (NULL = not delivered via the Bus)';
@@ -0,0 +1,35 @@
+-- NOTIFY on device changes: `device_changed` with payload `<tenant_id> <user_id>`
+-- Other services register, update and delete devices straight in activity.user_devices
+-- (including the per-device preferences of 030). Workers cache device lists per user
+-- (DEVICE_CACHE_TTL_SECS) and drop the user's entry on this signal instead of routing on a
+-- stale list until the TTL runs out. A device moved to another user notifies both.
+
+CREATE OR REPLACE FUNCTION activity.fn_user_device_changed()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        PERFORM pg_notify('device_changed', OLD.tenant_id || ' ' || OLD.user_id::text);
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        PERFORM pg_notify('device_changed', NEW.tenant_id || ' ' || NEW.user_id::text);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_user_device_changed ON activity.user_devices;
+CREATE TRIGGER trg_user_device_changed
+AFTER INSERT OR DELETE ON activity.user_devices
+FOR EACH ROW
+EXECUTE FUNCTION activity.fn_user_device_changed();
+
+-- Rewrites that change nothing (e.g. re-registering the same token) stay quiet
+DROP TRIGGER IF EXISTS trg_user_device_updated ON activity.user_devices;
+CREATE TRIGGER trg_user_device_updated
+AFTER UPDATE ON activity.user_devices
+FOR EACH ROW
+WHEN (OLD.* IS DISTINCT FROM NEW.*)
+EXECUTE FUNCTION activity.fn_user_device_changed();
+
+COMMENT ON FUNCTION activity.fn_user_device_changed() IS
+    'Sends pg_notify on device_changed (payload: tenant_id and user_id) so workers drop cached device lists';
@@ -0,0 +1,41 @@
+-- Re-engagement: a nudge for users who went quiet, configured per tenant
+-- A tenant's policy (PUT /api/v1/tenants/{id}/reengagement) names the inactivity period.
+-- The job sends one notification to every user of the tenant with a device older than
+-- that period and, within it, no read, no open/click and no Bus delivery (no open
+-- connection). reengagement_sends keeps the last send per user, and a user gets at most
+-- one per cooldown_days.
+
+CREATE TABLE IF NOT EXISTS activity.reengagement_policies (
+    tenant_id TEXT PRIMARY KEY,
+    inactive_days INTEGER NOT NULL CHECK (inactive_days > 0),
+    cooldown_days INTEGER NOT NULL DEFAULT 30 CHECK (cooldown_days > 0),
+    notification_type TEXT NOT NULL DEFAULT 'reengagement',
+    title TEXT NOT NULL,
+    message TEXT,
+    template_key TEXT,
+    deep_link TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    last_run_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS activity.reengagement_sends (
+    tenant_id TEXT NOT NULL,
+    user_id UUID NOT NULL,
+    notification_id UUID NOT NULL,
+    sent_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (tenant_id, user_id)
+);
+
+-- "Read anything lately?" per user
+CREATE INDEX IF NOT EXISTS idx_notifications_user_read
+ON activity.notifications (tenant_id, user_id, read_at)
+WHERE read_at IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_notification_engagement_user
+ON activity.notification_engagement (tenant_id, user_id, occurred_at);
+
+COMMENT ON TABLE activity.reengagement_policies IS 'Per tenant: nudge users inactive for inactive_days, at most once per cooldown_days';
+COMMENT ON COLUMN activity.reengagement_policies.title IS 'Copy of the nudge; template_key (message_args: inactive_days) renders it per locale instead';
+COMMENT ON TABLE activity.reengagement_sends IS 'Last re-engagement notification per user (the cooldown cap)';
@@ -0,0 +1,9 @@
+-- Fair scheduling: with WORKER_FAIR_SCHEDULING the worker fills a batch round-robin across
+-- tenants instead of oldest first, so one tenant's flood can't starve the others.
+-- scheduling_weight is a tenant's share per round: a tenant with weight 3 gets up to three
+-- rows for every one of a weight-1 tenant while both have a backlog.
+
+ALTER TABLE activity.tenants
+ADD COLUMN IF NOT EXISTS scheduling_weight INTEGER NOT NULL DEFAULT 1 CHECK (scheduling_weight > 0);
+
+COMMENT ON COLUMN activity.tenants.scheduling_weight IS 'Rows per round-robin turn when WORKER_FAIR_SCHEDULING is on (tenants without a row count as 1)';
@@ -0,0 +1,7 @@
+-- FCM answers a successful send with the message's name (projects/{project}/messages/{id}).
+-- Support escalations to Google need it, so delivered push attempts keep it.
+
+ALTER TABLE activity.notification_attempts
+ADD COLUMN IF NOT EXISTS provider_message_id TEXT;
+
+COMMENT ON COLUMN activity.notification_attempts.provider_message_id IS 'Message name FCM assigned to a delivered push (NULL for other channels and outcomes)';
@@ -0,0 +1,12 @@
+-- deliver_at_local: a wall-clock send time ("09:00 wherever the recipient is").
+-- The router resolves it in the recipient's timezone (user_notification_settings.timezone,
+-- else DELIVERY_WINDOW_TIMEZONE); deliver_at only says when the row may first be looked at.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS deliver_at_local TIMESTAMP;
+
+ALTER TABLE activity.campaigns
+ADD COLUMN IF NOT EXISTS deliver_at_local TIMESTAMP;
+
+COMMENT ON COLUMN activity.notifications.deliver_at_local IS 'Wall-clock send time in the recipient''s timezone (NULL = deliver_at decides)';
+COMMENT ON COLUMN activity.campaigns.deliver_at_local IS 'Wall-clock send time copied to every fanned-out notification';
@@ -0,0 +1,44 @@
+-- Delivery analytics rollups: per hour and per UTC day, per tenant, notification type and
+-- channel. Maintained by the analytics job (src/analytics.rs) so dashboards don't aggregate
+-- over notifications/notification_attempts, and kept after the raw rows are archived.
+-- Counts are bucketed by when the event happened (attempted_at, read_at).
+
+CREATE TABLE IF NOT EXISTS activity.delivery_rollups_hourly (
+    bucket TIMESTAMP WITH TIME ZONE NOT NULL,
+    tenant_id TEXT NOT NULL,
+    notification_type TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    sent BIGINT NOT NULL DEFAULT 0,
+    delivered BIGINT NOT NULL DEFAULT 0,
+    failed BIGINT NOT NULL DEFAULT 0,
+    read BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (bucket, tenant_id, notification_type, channel)
+);
+
+CREATE TABLE IF NOT EXISTS activity.delivery_rollups_daily (
+    bucket TIMESTAMP WITH TIME ZONE NOT NULL,
+    tenant_id TEXT NOT NULL,
+    notification_type TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    sent BIGINT NOT NULL DEFAULT 0,
+    delivered BIGINT NOT NULL DEFAULT 0,
+    failed BIGINT NOT NULL DEFAULT 0,
+    read BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (bucket, tenant_id, notification_type, channel)
+);
+
+-- The job recomputes recent buckets from the raw tables
+CREATE INDEX IF NOT EXISTS idx_notification_attempts_attempted_at
+ON activity.notification_attempts (attempted_at);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_read_at
+ON activity.notifications (read_at)
+WHERE read_at IS NOT NULL;
+
+COMMENT ON TABLE activity.delivery_rollups_hourly IS 'Delivery counts per UTC hour, tenant, type and channel (analytics job)';
+COMMENT ON TABLE activity.delivery_rollups_daily IS 'Delivery counts per UTC day, summed from delivery_rollups_hourly';
+COMMENT ON COLUMN activity.delivery_rollups_hourly.sent IS 'Delivery attempts (push: one per device)';
+COMMENT ON COLUMN activity.delivery_rollups_hourly.failed IS 'Attempts with outcome failed or invalid_token (no_connection is neither delivered nor failed)';
+COMMENT ON COLUMN activity.delivery_rollups_hourly.read IS 'Notifications marked read, on channel inbox (reads are not per channel)';
@@ -0,0 +1,14 @@
+-- Leader lease for warm standby: with LEADER_ELECTION every instance connects, LISTENs and
+-- warms its caches, but only the holder of the 'worker' lease claims notifications.
+-- The holder renews it well before expires_at; a standby takes over once it lapses, or
+-- right away when the leader releases it on shutdown.
+
+CREATE TABLE IF NOT EXISTS activity.leader_leases (
+    name TEXT PRIMARY KEY,
+    holder TEXT NOT NULL,
+    acquired_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+COMMENT ON TABLE activity.leader_leases IS 'Who leads (claims work) per role, until expires_at unless renewed';
+COMMENT ON COLUMN activity.leader_leases.holder IS 'Instance name (HOSTNAME) plus a per-process id';
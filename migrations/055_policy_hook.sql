@@ -0,0 +1,9 @@
+-- Content policy hook (POLICY_HOOK_URL): a veto suppresses the notification with reason
+-- 'policy_veto'; a failing hook with POLICY_HOOK_FAIL_MODE=closed retries it under
+-- failure_category 'policy_hook'. No new columns, the decision travels on the receipt.
+
+COMMENT ON COLUMN activity.notifications.failure_category IS
+    'Root cause of last_error (invalid_token, fcm_quota, fcm_unavailable, bus_unreachable, template_error, validation, no_devices, database, policy_hook, other)';
+
+COMMENT ON COLUMN activity.notifications.suppression_reason IS
+    'Why the notification was suppressed (e.g. type_disabled, policy_veto)';
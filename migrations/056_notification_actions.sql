@@ -0,0 +1,31 @@
+-- Inline actions: a notification can carry buttons (e.g. Accept / Decline) that go out in
+-- the Bus payload and the FCM data. The client answers with
+-- POST /api/v1/notifications/{id}/action; the answer is recorded here and forwarded to the
+-- producer (callback_url, receipt webhooks and BUS_EVENTS_TOPIC).
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS actions JSONB CHECK (actions IS NULL OR jsonb_typeof(actions) = 'array');
+
+COMMENT ON COLUMN activity.notifications.actions IS
+    'Inline action buttons: [{"id": "accept", "title": "Accept", "input": false}] (input = reply with text)';
+
+-- One answer per recipient: the first one wins, other devices see it on their next try
+CREATE TABLE IF NOT EXISTS activity.notification_actions (
+    notification_id UUID NOT NULL,
+    -- The user who answered (for broadcasts every user of the tenant can answer once)
+    user_id UUID NOT NULL,
+    tenant_id TEXT NOT NULL DEFAULT 'default',
+    notification_type TEXT NOT NULL,
+    action TEXT NOT NULL,
+    -- Reply text of an input action
+    input TEXT,
+    -- The answering device, when it said so
+    fcm_token TEXT,
+    occurred_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (notification_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_actions_type
+    ON activity.notification_actions (notification_type, occurred_at);
+
+COMMENT ON TABLE activity.notification_actions IS 'Answers to inline notification actions, one per notification and user';
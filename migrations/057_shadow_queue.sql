@@ -0,0 +1,70 @@
+-- Shadow queue (dual write / shadow read) to de-risk moving off the Postgres queue.
+-- While an instance runs with a shadow backend (SHADOW_KAFKA_BROKERS) it keeps a heartbeat
+-- in shadow_queue_state. Meanwhile every notification inserted (API, sources, direct INSERTs,
+-- topic and broadcast copies) gets a ledger row from the trigger below. The service publishes
+-- each ledger row to the shadow backend (dual write), consumes the backend like a future worker
+-- would (shadow read), and compares both sides once SHADOW_GRACE_SECS have passed.
+-- Without a heartbeat for 5 minutes the trigger stops writing, so turning the mode off
+-- needs nothing but removing the config.
+
+CREATE TABLE IF NOT EXISTS activity.shadow_queue_state (
+    -- Single row
+    id BOOLEAN PRIMARY KEY DEFAULT true CHECK (id),
+    backend TEXT,
+    heartbeat_at TIMESTAMP WITH TIME ZONE
+);
+
+INSERT INTO activity.shadow_queue_state (id) VALUES (true)
+ON CONFLICT (id) DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS activity.shadow_ledger (
+    notification_id UUID PRIMARY KEY,
+    tenant_id TEXT NOT NULL DEFAULT 'default',
+    ingested_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    -- Dual write: published to the shadow backend, with the content hash of the Postgres row
+    published_at TIMESTAMP WITH TIME ZONE,
+    pg_hash TEXT,
+    publish_attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    last_error TEXT,
+    -- Shadow read: first time the copy came back, how often, and its content hash
+    shadow_seen_at TIMESTAMP WITH TIME ZONE,
+    shadow_copies INTEGER NOT NULL DEFAULT 0,
+    shadow_hash TEXT,
+    -- Comparison (NULL divergence = both sides agree)
+    compared_at TIMESTAMP WITH TIME ZONE,
+    divergence TEXT CHECK (divergence IN ('unexpected', 'unpublished', 'missing', 'duplicate', 'content'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_shadow_ledger_unpublished
+    ON activity.shadow_ledger (next_attempt_at)
+    WHERE published_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_shadow_ledger_uncompared
+    ON activity.shadow_ledger (ingested_at)
+    WHERE compared_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_shadow_ledger_compared
+    ON activity.shadow_ledger (compared_at)
+    WHERE compared_at IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION activity.shadow_ledger_insert() RETURNS TRIGGER AS $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM activity.shadow_queue_state
+        WHERE heartbeat_at > now() - interval '5 minutes'
+    ) THEN
+        INSERT INTO activity.shadow_ledger (notification_id, tenant_id)
+        VALUES (NEW.id, NEW.tenant_id)
+        ON CONFLICT (notification_id) DO NOTHING;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_shadow_ledger_insert ON activity.notifications;
+CREATE TRIGGER trg_shadow_ledger_insert
+    AFTER INSERT ON activity.notifications
+    FOR EACH ROW EXECUTE FUNCTION activity.shadow_ledger_insert();
+
+COMMENT ON TABLE activity.shadow_ledger IS 'Shadow queue ledger: dual write, shadow read and their comparison per notification';
@@ -0,0 +1,26 @@
+-- Bundling window for rapid-fire events (BUNDLE_WINDOW_SECS)
+-- The first notification of a (tenant, user, type) goes out right away and opens a window.
+-- Rows arriving while it is open are held until the window ends (bundled_at set, deliver_at
+-- moved to the end); the first of them to be delivered absorbs the others (bundled_into) and
+-- goes out once, carrying the count. Every row stays in the inbox as it was inserted.
+
+CREATE TABLE IF NOT EXISTS activity.notification_bundles (
+    tenant_id TEXT NOT NULL,
+    user_id UUID NOT NULL,
+    notification_type TEXT NOT NULL,
+    window_ends_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    -- The notification that opened the window (it is delivered, not held)
+    opened_by UUID NOT NULL,
+    PRIMARY KEY (tenant_id, user_id, notification_type)
+);
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS bundled_at TIMESTAMP WITH TIME ZONE,
+ADD COLUMN IF NOT EXISTS bundled_into UUID;
+
+CREATE INDEX IF NOT EXISTS idx_notifications_bundled_into
+ON activity.notifications (bundled_into)
+WHERE bundled_into IS NOT NULL;
+
+COMMENT ON COLUMN activity.notifications.bundled_at IS 'Held for a bundle at this time (NULL = never held)';
+COMMENT ON COLUMN activity.notifications.bundled_into IS 'Delivered as part of the bundle sent for this notification';
@@ -0,0 +1,24 @@
+-- Broadcast audiences: notify everyone in an organization or team without listing them
+-- A notification with audience 'org:<id>' or 'team:<id>' (and the nil user_id) is expanded by
+-- the worker into one copy per member, like a topic. Members come from audience_members,
+-- which producers keep in sync per organization (PUT /api/v1/audiences/orgs/{org_id}/members),
+-- or from AUDIENCE_MEMBERS_QUERY against the application's own tables.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS audience TEXT;
+
+COMMENT ON COLUMN activity.notifications.audience IS 'org:<id> or team:<id>: expanded to the members (on the copies, the source audience)';
+
+CREATE TABLE IF NOT EXISTS activity.audience_members (
+    tenant_id TEXT NOT NULL DEFAULT 'default',
+    org_id TEXT NOT NULL,
+    -- '' = member of the organization outside any team; team ids are unique per tenant
+    team_id TEXT NOT NULL DEFAULT '',
+    user_id UUID NOT NULL,
+    synced_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (tenant_id, org_id, team_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_audience_members_team
+ON activity.audience_members (tenant_id, team_id)
+WHERE team_id <> '';
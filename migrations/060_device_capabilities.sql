@@ -0,0 +1,9 @@
+-- Device capabilities: what an installed app can handle, declared at registration
+-- ({"supports_actions": false, "max_payload_bytes": 2048}). The worker shapes each device's
+-- push to it, so an old app version never receives fields it crashes on.
+-- NULL (or a missing key) = not declared: the device gets everything.
+
+ALTER TABLE activity.user_devices
+ADD COLUMN IF NOT EXISTS capabilities JSONB;
+
+COMMENT ON COLUMN activity.user_devices.capabilities IS 'Declared by the app: supports_actions, max_payload_bytes (NULL = everything)';
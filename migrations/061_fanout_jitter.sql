@@ -0,0 +1,9 @@
+-- Delivery jitter for campaigns: each fanned-out notification gets deliver_at = queued time
+-- plus a random 0..jitter_secs, so a large audience isn't pushed in the same second and
+-- doesn't open the app (and hit the backend) all at once. Per-user broadcasts use
+-- BROADCAST_FANOUT_JITTER_SECS the same way.
+
+ALTER TABLE activity.campaigns
+ADD COLUMN IF NOT EXISTS jitter_secs INTEGER NOT NULL DEFAULT 0 CHECK (jitter_secs >= 0);
+
+COMMENT ON COLUMN activity.campaigns.jitter_secs IS 'Random delay window per notification (0 = send as queued)';
@@ -0,0 +1,18 @@
+-- NOTIFY payload carries the recipient: `<id> <priority> <user_id> <tenant_id>`
+-- With WORKER_WAKE_USER_BURST the listener throttles wake-ups per user, so a producer
+-- inserting many rows for one user in a loop wakes the worker once per refill instead of
+-- once per row. Older workers read only the first two fields.
+
+CREATE OR REPLACE FUNCTION activity.fn_notification_inserted()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify(
+        'notify_event',
+        NEW.id::text || ' ' || COALESCE(NEW.priority, 'normal') || ' ' || NEW.user_id::text || ' ' || NEW.tenant_id
+    );
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+COMMENT ON FUNCTION activity.fn_notification_inserted() IS
+    'Sends pg_notify signal (payload: id, priority, user and tenant) when a notification is inserted, waking up the Rust worker';
@@ -0,0 +1,9 @@
+-- Attachments: media a notification references in the attachment bucket (ATTACHMENT_STORE_URL)
+-- Producers upload the object and store its key here; the service signs the keys into
+-- short-lived GET URLs for every delivery and inbox read, so the bucket stays private.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS attachments JSONB CHECK (attachments IS NULL OR jsonb_typeof(attachments) = 'array');
+
+COMMENT ON COLUMN activity.notifications.attachments IS
+    'Media in the attachment bucket: [{"key": "chat/42/photo.jpg", "content_type": "image/jpeg"}] (keys relative to the store prefix)';
@@ -0,0 +1,24 @@
+-- Delivery SLO incidents (SLO_ALERT_INTERVAL_SECS)
+-- Opened when a notification type breaches its SLO_ALERT_RULES threshold; the operators get
+-- a notification through the service's own pipeline. At most one open incident per type and
+-- breach: replicas evaluating the same window race on the partial unique index, one wins.
+
+CREATE TABLE IF NOT EXISTS activity.slo_incidents (
+    id BIGSERIAL PRIMARY KEY,
+    notification_type TEXT NOT NULL,
+    breach TEXT NOT NULL CHECK (breach IN ('success_rate', 'p95_latency')),
+    -- Success rate (0-1) or p95 latency in seconds when the incident opened
+    observed DOUBLE PRECISION NOT NULL,
+    threshold DOUBLE PRECISION NOT NULL,
+    -- The alert sent to the operators
+    notification_id UUID NOT NULL,
+    opened_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_slo_incidents_open
+ON activity.slo_incidents (notification_type, breach)
+WHERE resolved_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_slo_incidents_opened_at
+ON activity.slo_incidents (opened_at);
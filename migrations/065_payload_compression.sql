@@ -0,0 +1,20 @@
+-- zstd-compressed payloads (PAYLOAD_COMPRESSION_THRESHOLD_BYTES)
+-- A payload larger than the threshold is stored in payload_zstd with payload_encoding = 'zstd'
+-- and a NULL payload. Readers that don't know the columns see no payload, so enable it only
+-- once every replica runs a binary that inflates them.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS payload_zstd BYTEA,
+ADD COLUMN IF NOT EXISTS payload_encoding TEXT;
+
+ALTER TABLE activity.notifications
+DROP CONSTRAINT IF EXISTS notifications_payload_encoding_check;
+
+ALTER TABLE activity.notifications
+ADD CONSTRAINT notifications_payload_encoding_check CHECK (
+    (payload_encoding IS NULL AND payload_zstd IS NULL)
+    OR (payload_encoding = 'zstd' AND payload_zstd IS NOT NULL AND payload IS NULL)
+);
+
+COMMENT ON COLUMN activity.notifications.payload_zstd IS 'Payload JSON compressed with zstd (payload is NULL then)';
+COMMENT ON COLUMN activity.notifications.payload_encoding IS 'zstd when the payload is in payload_zstd, NULL when it is in payload';
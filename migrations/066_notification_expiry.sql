@@ -0,0 +1,10 @@
+-- Expiry: after expires_at a notification is no longer worth delivering
+-- The worker suppresses rows it reaches too late (suppression_reason = 'expired'), and pushes
+-- carry the remaining time as the FCM/APNs TTL, so a device that was offline for days doesn't
+-- get them in a burst when it reconnects.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+
+COMMENT ON COLUMN activity.notifications.expires_at IS
+    'Not delivered after this time; the remainder is the push TTL (NULL = no expiry, low priority gets PUSH_LOW_PRIORITY_TTL_SECS)';
@@ -0,0 +1,11 @@
+-- Ingestion gate: `notifications-service drain` closes it before decommissioning
+-- While closed, the API, gRPC and the broker consumers refuse new notifications (brokers
+-- keep the messages), and campaigns, recurring schedules, per-user broadcasts and
+-- re-engagement create none. Rows already queued are still delivered.
+
+ALTER TABLE activity.maintenance_mode
+ADD COLUMN IF NOT EXISTS ingestion_closed BOOLEAN NOT NULL DEFAULT false,
+ADD COLUMN IF NOT EXISTS ingestion_closed_by TEXT,
+ADD COLUMN IF NOT EXISTS ingestion_closed_at TIMESTAMP WITH TIME ZONE;
+
+COMMENT ON COLUMN activity.maintenance_mode.ingestion_closed IS 'No new notifications are accepted (set by the drain command)';
@@ -0,0 +1,15 @@
+-- Deadline: time-sensitive notifications (OTP codes) that are worthless when late
+-- Claimed before everything else, earliest deadline first. The worker skips channels whose
+-- recent p90 latency doesn't fit the time left, and suppresses rows it reaches after the
+-- deadline (suppression_reason = 'expired'); pushes expire at FCM/APNs at the deadline.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS deadline TIMESTAMPTZ;
+
+COMMENT ON COLUMN activity.notifications.deadline IS
+    'Delivered before this time or not at all; claimed first (NULL = no deadline)';
+
+-- The claim orders on it: keep the few rows that have one cheap to find
+CREATE INDEX IF NOT EXISTS idx_notifications_deadline
+    ON activity.notifications (deadline)
+    WHERE is_processed = false AND deadline IS NOT NULL;
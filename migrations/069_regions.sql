@@ -0,0 +1,34 @@
+-- Regions: active-active deployments, each region with its own Bus and workers on one database
+-- A notification is homed in a region (the producer's `region`, else the region of the
+-- recipient's most recently seen device). Workers with REGION set claim their own rows and
+-- rows without a region; another region's rows only once they have been due for
+-- REGION_CLAIM_GRACE_SECS (that region is down or behind). Devices record the region they
+-- registered through, Bus publish records the region whose Bus took them.
+
+ALTER TABLE activity.notifications
+ADD COLUMN IF NOT EXISTS region TEXT;
+
+ALTER TABLE activity.user_devices
+ADD COLUMN IF NOT EXISTS region TEXT;
+
+ALTER TABLE activity.bus_deliveries
+ADD COLUMN IF NOT EXISTS region TEXT;
+
+COMMENT ON COLUMN activity.notifications.region IS
+    'Home region: claimed there first, elsewhere after REGION_CLAIM_GRACE_SECS (NULL = any region)';
+COMMENT ON COLUMN activity.user_devices.region IS
+    'Region the device last registered through (NULL = single-region deployment)';
+COMMENT ON COLUMN activity.bus_deliveries.region IS
+    'Region whose Bus took the publish';
+
+-- The recipient's home region: where their most recently seen device registered
+CREATE OR REPLACE FUNCTION activity.home_region(
+    p_tenant_id TEXT,
+    p_user_id UUID
+) RETURNS TEXT AS $$
+    SELECT region
+    FROM activity.user_devices
+    WHERE tenant_id = p_tenant_id AND user_id = p_user_id AND region IS NOT NULL
+    ORDER BY last_seen_at DESC NULLS LAST
+    LIMIT 1
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,29 @@
+-- Client error reports: an app that received a notification but couldn't render or process it
+-- (unknown template arguments, a payload its build can't parse, a missing deep-link route)
+-- reports it with a code and free-form context. Stored against the delivery attempt the
+-- client got it through, so explain shows it next to the template version that rendered it.
+-- Append-only; tenant_id and notification_type are copied as in notification_engagement.
+
+CREATE TABLE IF NOT EXISTS activity.notification_client_errors (
+    id BIGSERIAL PRIMARY KEY,
+    notification_id UUID NOT NULL,
+    -- The latest delivered attempt on the channel (NULL when none was recorded)
+    attempt_id BIGINT,
+    -- Reporting user; for broadcasts the user who received it
+    user_id UUID NOT NULL,
+    tenant_id TEXT NOT NULL DEFAULT 'default',
+    notification_type TEXT NOT NULL,
+    channel TEXT NOT NULL CHECK (channel IN ('bus', 'push')),
+    code TEXT NOT NULL,
+    context JSONB,
+    reported_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_client_errors_notification
+    ON activity.notification_client_errors (notification_id);
+
+CREATE INDEX IF NOT EXISTS idx_notification_client_errors_type
+    ON activity.notification_client_errors (notification_type, reported_at);
+
+COMMENT ON TABLE activity.notification_client_errors IS 'Rendering/processing failures reported by clients (POST /api/v1/notifications/{id}/error)';
+COMMENT ON COLUMN activity.notification_client_errors.code IS 'Client-defined, e.g. template_args_missing, payload_unparsable';
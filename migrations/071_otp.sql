@@ -0,0 +1,57 @@
+-- One-time passwords (built-in notification_type 'otp'): login and verification codes are
+-- delivered and then forgotten. They never enter a client's inbox, so the change log used by
+-- delta-sync skips them; the worker clears the code from the row once it is done.
+-- Same function as in 027 with the early return for otp rows added.
+
+CREATE OR REPLACE FUNCTION activity.fn_notification_changed()
+RETURNS TRIGGER AS $$
+DECLARE
+    v_change TEXT;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        IF OLD.is_processed AND OLD.suppressed_at IS NULL AND OLD.notification_type::text <> 'otp' THEN
+            INSERT INTO activity.notification_changes (notification_id, user_id, tenant_id, change)
+            VALUES (OLD.id, OLD.user_id, OLD.tenant_id, 'deleted');
+        END IF;
+        RETURN NULL;
+    END IF;
+
+    IF NOT NEW.is_processed OR NEW.suppressed_at IS NOT NULL OR NEW.notification_type::text = 'otp' THEN
+        RETURN NULL;
+    END IF;
+
+    IF TG_OP = 'INSERT' OR NOT OLD.is_processed THEN
+        v_change := 'created';
+    ELSIF OLD.read_at IS NULL AND NEW.read_at IS NOT NULL THEN
+        v_change := 'read';
+    ELSIF (OLD.title, OLD.message, OLD.payload, OLD.deep_link, OLD.priority, OLD.group_key)
+          IS DISTINCT FROM (NEW.title, NEW.message, NEW.payload, NEW.deep_link, NEW.priority, NEW.group_key) THEN
+        v_change := 'updated';
+    END IF;
+
+    IF v_change IS NOT NULL THEN
+        INSERT INTO activity.notification_changes (notification_id, user_id, tenant_id, change)
+        VALUES (NEW.id, NEW.user_id, NEW.tenant_id, v_change);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+COMMENT ON FUNCTION activity.fn_notification_changed() IS 'Appends inbox changes for delta-sync; otp rows are never logged';
+
+-- Shadow mode publishes ledgered rows (title, message, payload) to an outside backend before
+-- the worker gets to clear the code, so otp rows are kept out of the ledger. Same function
+-- as in 057 with the otp check added.
+CREATE OR REPLACE FUNCTION activity.shadow_ledger_insert() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.notification_type::text <> 'otp' AND EXISTS (
+        SELECT 1 FROM activity.shadow_queue_state
+        WHERE heartbeat_at > now() - interval '5 minutes'
+    ) THEN
+        INSERT INTO activity.shadow_ledger (notification_id, tenant_id)
+        VALUES (NEW.id, NEW.tenant_id)
+        ON CONFLICT (notification_id) DO NOTHING;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,6 @@
+-- Ingestion backpressure reads the backlog on the request path: the oldest due row and a
+-- count capped at BACKPRESSURE_MAX_DEPTH + 1 both come from this index, never a full scan
+
+CREATE INDEX IF NOT EXISTS idx_notifications_due
+ON activity.notifications (deliver_at)
+WHERE is_processed = false;
@@ -0,0 +1,17 @@
+-- Replay protection for /ingest/webhook/{source}
+-- Senders sign `{timestamp}.{body}`; a request is accepted within WEBHOOK_TOLERANCE_SECS of
+-- its timestamp, and only once: its signature is recorded here. A timestamp may lie up to the
+-- tolerance ahead of the clock, so rows are pruned after twice the tolerance, when a request
+-- with the same signature would be refused as stale anyway.
+
+CREATE TABLE IF NOT EXISTS activity.webhook_signatures (
+    source TEXT NOT NULL,
+    signature BYTEA NOT NULL,
+    received_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (source, signature)
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_signatures_received
+ON activity.webhook_signatures (received_at);
+
+COMMENT ON TABLE activity.webhook_signatures IS 'Signatures of accepted webhooks, to refuse replays within the timestamp tolerance';
@@ -0,0 +1,409 @@
+//! Producer client for notifications-service.
+//!
+//! ```ignore
+//! let client = NotificationsClient::http("http://notifications-service:8080", token);
+//! NotificationBuilder::new("friend_request")
+//!     .for_user(recipient)
+//!     .from_actor(sender)
+//!     .localized("friend_request", serde_json::json!({ "name": name }))
+//!     .deep_link(format!("app://users/{}", sender))
+//!     .send(&client)
+//!     .await?;
+//! ```
+//!
+//! Two transports:
+//! - `http` (default): `POST /api/v1/notifications` with the service's ADMIN_TOKEN
+//! - `postgres`: INSERT into `activity.notifications` directly (the table is the outbox;
+//!   the service picks rows up via NOTIFY)
+//!
+//! Every notification gets an id up front, so retrying `send` never creates duplicates.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[cfg(not(any(feature = "http", feature = "postgres")))]
+compile_error!("notifications-client needs the `http` and/or `postgres` feature");
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("invalid notification: {0}")]
+    Invalid(String),
+    #[cfg(feature = "http")]
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[cfg(feature = "http")]
+    #[error("service rejected notification ({status}): {body}")]
+    Rejected { status: u16, body: String },
+    #[cfg(feature = "postgres")]
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+    /// Bypasses quiet hours and snooze
+    Critical,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Critical => "critical",
+        }
+    }
+}
+
+/// Notification as accepted by the create API (same fields as the table)
+#[derive(Debug, Clone, Serialize)]
+pub struct NewNotification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub actor_user_id: Option<Uuid>,
+    pub notification_type: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub title: String,
+    pub message: Option<String>,
+    pub payload: Option<serde_json::Value>,
+    pub deep_link: Option<String>,
+    pub priority: Option<Priority>,
+    pub group_key: Option<String>,
+    pub message_key: Option<String>,
+    pub message_args: Option<serde_json::Value>,
+    pub template_key: Option<String>,
+    pub deliver_at: Option<DateTime<Utc>>,
+    pub callback_url: Option<String>,
+    /// Owning tenant (None = `default`)
+    pub tenant_id: Option<String>,
+    /// Deliver to the topic's subscribers instead of one user (user_id is then nil)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    /// Deliver to an organization's or team's members (`org:<id>` / `team:<id>`, user_id nil)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String>,
+}
+
+/// Typed builder: `NotificationBuilder::new("friend_request").for_user(id)...send(&client)`
+#[derive(Debug, Clone)]
+pub struct NotificationBuilder {
+    id: Option<Uuid>,
+    user_id: Option<Uuid>,
+    actor_user_id: Option<Uuid>,
+    notification_type: String,
+    target: Option<(String, Uuid)>,
+    title: Option<String>,
+    message: Option<String>,
+    payload: Option<serde_json::Value>,
+    deep_link: Option<String>,
+    priority: Option<Priority>,
+    group_key: Option<String>,
+    localized: Option<(String, serde_json::Value)>,
+    template_key: Option<String>,
+    deliver_at: Option<DateTime<Utc>>,
+    callback_url: Option<String>,
+    tenant_id: Option<String>,
+    topic: Option<String>,
+    audience: Option<String>,
+}
+
+impl NotificationBuilder {
+    pub fn new(notification_type: impl Into<String>) -> Self {
+        Self {
+            id: None,
+            user_id: None,
+            actor_user_id: None,
+            notification_type: notification_type.into(),
+            target: None,
+            title: None,
+            message: None,
+            payload: None,
+            deep_link: None,
+            priority: None,
+            group_key: None,
+            localized: None,
+            template_key: None,
+            deliver_at: None,
+            callback_url: None,
+            tenant_id: None,
+            topic: None,
+            audience: None,
+        }
+    }
+
+    /// Idempotency id (default: a fresh UUIDv7). Reuse it when retrying the same event.
+    pub fn id(mut self, id: Uuid) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Recipient (this, `for_topic`, `for_org` or `for_team` is required)
+    pub fn for_user(mut self, user_id: Uuid) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    /// Everyone subscribed to the topic (e.g. `project:42`) except the actor
+    pub fn for_topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = Some(topic.into());
+        self
+    }
+
+    /// Everyone in the organization, its teams included, except the actor
+    pub fn for_org(mut self, org_id: impl AsRef<str>) -> Self {
+        self.audience = Some(format!("org:{}", org_id.as_ref()));
+        self
+    }
+
+    /// Everyone in the team except the actor
+    pub fn for_team(mut self, team_id: impl AsRef<str>) -> Self {
+        self.audience = Some(format!("team:{}", team_id.as_ref()));
+        self
+    }
+
+    /// User that triggered the notification
+    pub fn from_actor(mut self, actor_user_id: Uuid) -> Self {
+        self.actor_user_id = Some(actor_user_id);
+        self
+    }
+
+    /// Entity the notification is about (drives muting per target)
+    pub fn target(mut self, target_type: impl Into<String>, target_id: Uuid) -> Self {
+        self.target = Some((target_type.into(), target_id));
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    pub fn payload(mut self, payload: serde_json::Value) -> Self {
+        self.payload = Some(payload);
+        self
+    }
+
+    pub fn deep_link(mut self, deep_link: impl Into<String>) -> Self {
+        self.deep_link = Some(deep_link.into());
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    pub fn group_key(mut self, group_key: impl Into<String>) -> Self {
+        self.group_key = Some(group_key.into());
+        self
+    }
+
+    /// Render title/message from the service's Fluent catalog per recipient locale
+    pub fn localized(mut self, message_key: impl Into<String>, args: serde_json::Value) -> Self {
+        self.localized = Some((message_key.into(), args));
+        self
+    }
+
+    /// Render title/message from a managed template (`/api/v1/templates`)
+    pub fn template(mut self, template_key: impl Into<String>) -> Self {
+        self.template_key = Some(template_key.into());
+        self
+    }
+
+    /// Schedule delivery (default: immediately)
+    pub fn deliver_at(mut self, deliver_at: DateTime<Utc>) -> Self {
+        self.deliver_at = Some(deliver_at);
+        self
+    }
+
+    /// URL that receives the signed delivery receipt
+    pub fn callback_url(mut self, url: impl Into<String>) -> Self {
+        self.callback_url = Some(url.into());
+        self
+    }
+
+    /// Tenant (app/brand) the notification belongs to (default: `default`)
+    pub fn tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    /// Validate and produce the notification (same rules as the service)
+    pub fn build(self) -> Result<NewNotification, ClientError> {
+        let recipients = [self.user_id.is_some(), self.topic.is_some(), self.audience.is_some()];
+        let user_id = match recipients.iter().filter(|set| **set).count() {
+            0 => {
+                return Err(ClientError::Invalid(
+                    "recipient is required (for_user, for_topic, for_org or for_team)".to_string(),
+                ))
+            }
+            1 => self.user_id.unwrap_or_else(Uuid::nil),
+            _ => {
+                return Err(ClientError::Invalid(
+                    "for_user, for_topic and for_org/for_team are exclusive".to_string(),
+                ))
+            }
+        };
+
+        if self.notification_type.trim().is_empty() {
+            return Err(ClientError::Invalid("notification_type is required".to_string()));
+        }
+        if self.title.is_none() && self.localized.is_none() && self.template_key.is_none() {
+            return Err(ClientError::Invalid(
+                "title is required unless localized() or template() is used".to_string(),
+            ));
+        }
+        if matches!(&self.localized, Some((_, args)) if !args.is_object()) {
+            return Err(ClientError::Invalid("message args must be a JSON object".to_string()));
+        }
+
+        let (target_type, target_id) = self.target.unzip();
+        let (message_key, message_args) = self.localized.unzip();
+
+        Ok(NewNotification {
+            id: self.id.unwrap_or_else(Uuid::now_v7),
+            user_id,
+            actor_user_id: self.actor_user_id,
+            notification_type: self.notification_type,
+            target_type,
+            target_id,
+            title: self.title.unwrap_or_default(),
+            message: self.message,
+            payload: self.payload,
+            deep_link: self.deep_link,
+            priority: self.priority,
+            group_key: self.group_key,
+            message_key,
+            message_args,
+            template_key: self.template_key,
+            deliver_at: self.deliver_at,
+            callback_url: self.callback_url,
+            tenant_id: self.tenant_id,
+            topic: self.topic,
+            audience: self.audience,
+        })
+    }
+
+    /// Build and submit; returns the notification id
+    pub async fn send(self, client: &NotificationsClient) -> Result<Uuid, ClientError> {
+        client.create(&self.build()?).await
+    }
+}
+
+/// Submits notifications over HTTP or straight into the database
+#[derive(Debug, Clone)]
+pub struct NotificationsClient {
+    transport: Transport,
+}
+
+#[derive(Debug, Clone)]
+enum Transport {
+    #[cfg(feature = "http")]
+    Http {
+        http: reqwest::Client,
+        base_url: String,
+        token: String,
+    },
+    #[cfg(feature = "postgres")]
+    Postgres(sqlx::PgPool),
+}
+
+impl NotificationsClient {
+    /// Create API at `<base_url>/api/v1/notifications`, authenticated with ADMIN_TOKEN
+    #[cfg(feature = "http")]
+    pub fn http(base_url: impl Into<String>, token: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            transport: Transport::Http {
+                http,
+                base_url: base_url.into().trim_end_matches('/').to_string(),
+                token: token.into(),
+            },
+        }
+    }
+
+    /// Direct INSERT into `activity.notifications` (pool on the service's database)
+    #[cfg(feature = "postgres")]
+    pub fn postgres(pool: sqlx::PgPool) -> Self {
+        Self {
+            transport: Transport::Postgres(pool),
+        }
+    }
+
+    /// Submit a notification; duplicates of an already accepted id are a no-op
+    pub async fn create(&self, notification: &NewNotification) -> Result<Uuid, ClientError> {
+        match &self.transport {
+            #[cfg(feature = "http")]
+            Transport::Http { http, base_url, token } => {
+                let response = http
+                    .post(format!("{}/api/v1/notifications", base_url))
+                    .bearer_auth(token)
+                    .json(notification)
+                    .send()
+                    .await?;
+
+                if !response.status().is_success() {
+                    let status = response.status().as_u16();
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(ClientError::Rejected { status, body });
+                }
+                Ok(notification.id)
+            }
+            #[cfg(feature = "postgres")]
+            Transport::Postgres(pool) => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO activity.notifications (
+                        id, user_id, actor_user_id, notification_type, target_type, target_id,
+                        title, message, payload, deep_link, priority,
+                        group_key, message_key, message_args, template_key, deliver_at, callback_url,
+                        tenant_id, topic, audience
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, COALESCE($11, 'normal'),
+                            $12, $13, $14, $15, COALESCE($16, NOW()), $17, COALESCE($18, 'default'), $19, $20)
+                    ON CONFLICT (id) DO NOTHING
+                    "#,
+                )
+                .bind(notification.id)
+                .bind(notification.user_id)
+                .bind(notification.actor_user_id)
+                .bind(&notification.notification_type)
+                .bind(&notification.target_type)
+                .bind(notification.target_id)
+                .bind(&notification.title)
+                .bind(&notification.message)
+                .bind(&notification.payload)
+                .bind(&notification.deep_link)
+                .bind(notification.priority.map(|p| p.as_str()))
+                .bind(&notification.group_key)
+                .bind(&notification.message_key)
+                .bind(&notification.message_args)
+                .bind(&notification.template_key)
+                .bind(notification.deliver_at)
+                .bind(&notification.callback_url)
+                .bind(&notification.tenant_id)
+                .bind(&notification.topic)
+                .bind(&notification.audience)
+                .execute(pool)
+                .await?;
+                Ok(notification.id)
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Delivery analytics rollups for product dashboards.
+//!
+//! Every ANALYTICS_INTERVAL_SECS the [`AnalyticsJob`] recomputes the latest hourly buckets of
+//! `activity.delivery_rollups_hourly` from the attempts and reads (per tenant, notification
+//! type and channel) and re-sums the days they fall in into `delivery_rollups_daily`. The
+//! first run backfills ANALYTICS_BACKFILL_DAYS. An advisory lock keeps it to one replica per
+//! run. `GET /admin/analytics` reads only the rollups, which outlive archived rows.
+
+use crate::db::AnalyticsQueries;
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument};
+
+pub struct AnalyticsJob {
+    pool: PgPool,
+    interval: Duration,
+    backfill_days: i64,
+}
+
+impl AnalyticsJob {
+    pub fn new(pool: PgPool, interval: Duration, backfill_days: i64) -> Self {
+        Self { pool, interval, backfill_days }
+    }
+
+    /// Job loop: roll up, then sleep
+    #[instrument(skip(self), name = "analytics")]
+    pub async fn run(&self) {
+        info!(
+            interval_secs = self.interval.as_secs(),
+            backfill_days = self.backfill_days,
+            "Analytics rollup job started"
+        );
+
+        loop {
+            match AnalyticsQueries::roll_up(&self.pool, self.backfill_days).await {
+                Ok(Some(run)) => {
+                    debug!(since = %run.since, hourly = run.hourly, daily = run.daily, "📊 Delivery rollups updated");
+                    metrics::gauge!("notifications_analytics_rolled_up_timestamp").set(chrono::Utc::now().timestamp() as f64);
+                }
+                Ok(None) => debug!("Another replica is rolling up, skipping this run"),
+                Err(e) => {
+                    error!(error = %e, "Failed to update delivery rollups");
+                    metrics::counter!("notifications_analytics_rollup_errors_total").increment(1);
+                }
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState, ErrorCode};
+use crate::db::AckQueries;
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AckRequest {
+    /// The acking device (it gets no dismiss); absent for web/Bus-only clients
+    pub fcm_token: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AckResponse {
+    /// True for the winning ack; false = another device acked first
+    pub first: bool,
+    pub acked_at: Option<DateTime<Utc>>,
+    pub acked_by_device: Option<String>,
+}
+
+/// POST /api/v1/notifications/{id}/ack
+///
+/// For FIRST_ACK_TYPES: the first ack wins and the user's other devices get a dismiss.
+/// With BUS_ACK_TIMEOUT_SECS it also confirms a Bus delivery, which otherwise falls back
+/// to push. The Bus only carries traffic from this service to clients, so acks come in
+/// over HTTP.
+pub async fn ack(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<AckRequest>,
+) -> Result<Json<AckResponse>, ApiError> {
+    let notification = AckQueries::find(&state.pool, &user.tenant_id, user.user_id, id)
+        .await?
+        .ok_or_else(|| ErrorCode::NotificationNotFound.with("id", id))?;
+    let dismisser = state.dismisser.clone().filter(|d| d.handles(&notification.notification_type));
+    if dismisser.is_none() && notification.bus_delivered_at.is_none() {
+        return Err(ApiError::BadRequest(format!(
+            "'{}' is not a first-ack type (FIRST_ACK_TYPES) and has no Bus delivery to confirm",
+            notification.notification_type
+        )));
+    }
+
+    let device = request.fcm_token.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    let Some(acked) = AckQueries::ack(&state.pool, id, device.as_deref()).await? else {
+        // Another device acked first (possibly a moment ago)
+        let current = AckQueries::find(&state.pool, &user.tenant_id, user.user_id, id).await?;
+        let (acked_at, acked_by_device) = current.map(|c| (c.acked_at, c.acked_by_device)).unwrap_or_default();
+        debug!(id = %id, user_id = %user.user_id, "Late ack, already dismissed");
+        return Ok(Json(AckResponse { first: false, acked_at, acked_by_device }));
+    };
+
+    metrics::counter!("notifications_acks_total").increment(1);
+    match dismisser {
+        Some(dismisser) => {
+            info!(id = %id, user_id = %user.user_id, from_device = device.is_some(), "Notification acked, dismissing on other devices");
+            let (tenant_id, user_id) = (user.tenant_id.clone(), user.user_id);
+            let notification_type = notification.notification_type;
+            tokio::spawn(async move {
+                dismisser.dismiss(&tenant_id, user_id, id, &notification_type, device.as_deref()).await;
+            });
+        }
+        None => info!(id = %id, user_id = %user.user_id, "Bus delivery confirmed by the client"),
+    }
+
+    Ok(Json(AckResponse { first: true, acked_at: acked.acked_at, acked_by_device: acked.acked_by_device }))
+}
@@ -0,0 +1,87 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState, ErrorCode};
+use crate::db::actions::RecordedAction;
+use crate::db::ActionQueries;
+use crate::models::NotificationAction;
+use axum::extract::{Path, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Longest reply text of an input action
+pub const MAX_ACTION_INPUT_CHARS: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct ActionRequest {
+    /// Id of the chosen action
+    pub action: String,
+    /// Reply text (input actions only)
+    pub input: Option<String>,
+    /// The answering device
+    pub fcm_token: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActionResponse {
+    /// True when this answer was recorded; false = the user had already answered
+    pub first: bool,
+    /// The recorded answer (the earlier one when `first` is false)
+    pub answer: RecordedAction,
+}
+
+/// POST /api/v1/notifications/{id}/action
+///
+/// The user chose one of the notification's inline actions, on a push button or in the
+/// app. Reply text only goes with an `input` action. The first answer per user is recorded
+/// and forwarded to the producer (`ActionRelay`); a later one, e.g. from another device,
+/// gets the recorded answer back and forwards nothing.
+pub async fn take_action(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ActionRequest>,
+) -> Result<Json<ActionResponse>, ApiError> {
+    let target = ActionQueries::find(&state.pool, &user.tenant_id, user.user_id, id)
+        .await?
+        .ok_or_else(|| ErrorCode::NotificationNotFound.with("id", id))?;
+    let actions = NotificationAction::parse(target.actions.as_ref());
+    let action = actions
+        .iter()
+        .find(|action| action.id == request.action)
+        .ok_or_else(|| ErrorCode::UnknownAction.with("id", id).with("action", &request.action))?;
+
+    let input = request.input.map(|input| input.trim().to_string()).filter(|input| !input.is_empty());
+    match &input {
+        Some(_) if !action.input => {
+            return Err(ErrorCode::ActionTakesNoInput.with("action", &action.id).into());
+        }
+        Some(input) if input.chars().count() > MAX_ACTION_INPUT_CHARS => {
+            return Err(ErrorCode::ActionInputTooLong.with("max", MAX_ACTION_INPUT_CHARS).into());
+        }
+        None if action.input => return Err(ErrorCode::ActionNeedsInput.with("action", &action.id).into()),
+        _ => {}
+    }
+
+    let device = request.fcm_token.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    let recorded =
+        ActionQueries::record(&state.pool, &target, user.user_id, &action.id, input.as_deref(), device.as_deref())
+            .await?;
+    let Some(answer) = recorded else {
+        let answer = ActionQueries::answer(&state.pool, id, user.user_id)
+            .await?
+            .ok_or_else(|| ApiError::Internal(format!("Answer to notification {} disappeared", id)))?;
+        debug!(id = %id, user_id = %user.user_id, "Action already answered");
+        return Ok(Json(ActionResponse { first: false, answer }));
+    };
+
+    info!(id = %id, user_id = %user.user_id, action = %answer.action, "Notification action taken");
+    metrics::counter!("notifications_engagement_total", "event" => "action").increment(1);
+    let relay = state.actions.clone();
+    let (user_id, forwarded) = (user.user_id, answer.clone());
+    tokio::spawn(async move {
+        relay.forward(&target, user_id, &forwarded).await;
+    });
+
+    Ok(Json(ActionResponse { first: true, answer }))
+}
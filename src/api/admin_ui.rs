@@ -0,0 +1,47 @@
+use super::auth::ReadOnlyAuth;
+use super::{ApiError, ApiState};
+use crate::db::resend::FailedNotification;
+use crate::db::ResendQueries;
+use crate::worker::FailureCategory;
+use axum::extract::{Query, State};
+use axum::response::Html;
+use axum::Json;
+use serde::Deserialize;
+
+/// Single page, no build step: plain JS against the admin API with the operator's token
+const ADMIN_UI: &str = include_str!("admin_ui.html");
+
+/// Failures shown by default / at most
+const DEFAULT_FAILURES: i64 = 50;
+const MAX_FAILURES: i64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct FailuresQuery {
+    pub category: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// GET /admin/ui
+///
+/// The page itself is public (behind the admin allowlist); every call it makes needs a token.
+pub async fn ui() -> Html<&'static str> {
+    Html(ADMIN_UI)
+}
+
+/// GET /admin/failures?category=&limit=
+pub async fn failures(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Query(query): Query<FailuresQuery>,
+) -> Result<Json<Vec<FailedNotification>>, ApiError> {
+    let category = match query.category.as_deref().filter(|name| !name.is_empty()) {
+        Some(name) => Some(FailureCategory::parse(name).ok_or_else(|| {
+            let known: Vec<&str> = FailureCategory::ALL.iter().map(|c| c.as_str()).collect();
+            ApiError::BadRequest(format!("Unknown category '{}' (one of {})", name, known.join(", ")))
+        })?),
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_FAILURES).clamp(1, MAX_FAILURES);
+    let failures = ResendQueries::recent_failures(&state.pool, category.map(|c| c.as_str()), limit).await?;
+    Ok(Json(failures))
+}
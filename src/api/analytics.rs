@@ -0,0 +1,110 @@
+use super::auth::ReadOnlyAuth;
+use super::{ApiError, ApiState};
+use crate::db::analytics::{Granularity, RollupRow};
+use crate::db::AnalyticsQueries;
+use axum::extract::{Query, State};
+use axum::Json;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const DEFAULT_RANGE: &str = "7d";
+const MAX_RANGE_DAYS: i64 = 366;
+/// Longest range served per hour (longer ones per day)
+const MAX_HOURLY_RANGE_DAYS: i64 = 14;
+/// Ranges up to this are per hour unless `granularity` says otherwise
+const AUTO_HOURLY_RANGE_HOURS: i64 = 48;
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQuery {
+    /// `<n>h` or `<n>d` back from now (default 7d, max 366d)
+    pub range: Option<String>,
+    /// hour | day (default: hour up to 48h, else day)
+    pub granularity: Option<String>,
+    pub tenant_id: Option<String>,
+}
+
+/// Delivery counts per bucket, type and channel, from the rollups
+#[derive(Debug, Serialize)]
+pub struct AnalyticsResponse {
+    pub range: String,
+    pub granularity: Granularity,
+    /// Start of the first bucket
+    pub since: DateTime<Utc>,
+    /// Over the whole range, per type and channel
+    pub totals: Vec<AnalyticsTotals>,
+    /// Oldest bucket first; buckets without activity are absent
+    pub series: Vec<RollupRow>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AnalyticsTotals {
+    pub notification_type: String,
+    pub channel: String,
+    pub sent: i64,
+    pub delivered: i64,
+    pub failed: i64,
+    pub read: i64,
+}
+
+/// GET /admin/analytics?range=7d&granularity=&tenant_id=
+///
+/// Served from `delivery_rollups_*` (see `crate::analytics`), so the newest bucket lags up
+/// to ANALYTICS_INTERVAL_SECS behind.
+pub async fn analytics(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<AnalyticsResponse>, ApiError> {
+    let range = query.range.unwrap_or_else(|| DEFAULT_RANGE.to_string());
+    let length = parse_range(&range)
+        .filter(|length| *length > Duration::zero() && *length <= Duration::days(MAX_RANGE_DAYS))
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!("range must be <n>h or <n>d, up to {}d", MAX_RANGE_DAYS))
+        })?;
+    let granularity = match query.granularity.as_deref() {
+        Some("hour") => Granularity::Hour,
+        Some("day") => Granularity::Day,
+        None if length <= Duration::hours(AUTO_HOURLY_RANGE_HOURS) => Granularity::Hour,
+        None => Granularity::Day,
+        Some(other) => return Err(ApiError::BadRequest(format!("granularity must be hour or day, got '{}'", other))),
+    };
+    if granularity == Granularity::Hour && length > Duration::days(MAX_HOURLY_RANGE_DAYS) {
+        return Err(ApiError::BadRequest(format!(
+            "granularity=hour is limited to {}d, use granularity=day",
+            MAX_HOURLY_RANGE_DAYS
+        )));
+    }
+
+    let bucket = match granularity {
+        Granularity::Hour => Duration::hours(1),
+        Granularity::Day => Duration::days(1),
+    };
+    let since = (Utc::now() - length).duration_trunc(bucket).map_err(|e| ApiError::Internal(e.to_string()))?;
+    let series = AnalyticsQueries::series(&state.pool, granularity, since, query.tenant_id.as_deref()).await?;
+
+    let mut totals: BTreeMap<(&str, &str), AnalyticsTotals> = BTreeMap::new();
+    for row in &series {
+        let total = totals.entry((&row.notification_type, &row.channel)).or_insert_with(|| AnalyticsTotals {
+            notification_type: row.notification_type.clone(),
+            channel: row.channel.clone(),
+            ..Default::default()
+        });
+        total.sent += row.sent;
+        total.delivered += row.delivered;
+        total.failed += row.failed;
+        total.read += row.read;
+    }
+    let totals = totals.into_values().collect();
+
+    Ok(Json(AnalyticsResponse { range, granularity, since, totals, series }))
+}
+
+/// `48h` / `7d`
+pub(super) fn parse_range(range: &str) -> Option<Duration> {
+    let range = range.trim();
+    if let Some(hours) = range.strip_suffix('h') {
+        return Duration::try_hours(hours.parse().ok()?);
+    }
+    Duration::try_days(range.strip_suffix('d')?.parse().ok()?)
+}
@@ -0,0 +1,24 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState};
+use crate::db::announcements::Announcement;
+use crate::db::AnnouncementQueries;
+use axum::extract::State;
+use axum::Json;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct AnnouncementsResponse {
+    pub announcements: Vec<Announcement>,
+}
+
+/// GET /api/v1/announcements
+///
+/// Notifications sent with `pinned_until` that haven't expired yet, for banners that
+/// must stay visible after the push was dismissed.
+pub async fn list_announcements(
+    State(state): State<ApiState>,
+    user: AuthUser,
+) -> Result<Json<AnnouncementsResponse>, ApiError> {
+    let announcements = AnnouncementQueries::active(&state.pool, &user.tenant_id, user.user_id).await?;
+    Ok(Json(AnnouncementsResponse { announcements }))
+}
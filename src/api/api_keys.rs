@@ -0,0 +1,152 @@
+use super::audit;
+use super::auth::{generate_api_key, hash_api_key, AdminAuth, SCOPES};
+use super::{ApiError, ApiState};
+use crate::db::api_keys::{ApiKey, NewApiKey};
+use crate::db::ApiKeyQueries;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+/// Default overlap during which a rotated key keeps working
+const DEFAULT_ROTATION_GRACE_SECS: i64 = 86_400;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    /// Restrict the key to one tenant (None = any tenant)
+    pub tenant_id: Option<String>,
+    /// Creation quota for this key (None = unlimited)
+    pub rate_limit_per_minute: Option<i32>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateApiKeyRequest {
+    /// How long the old key stays valid (default 24h)
+    pub grace_secs: Option<i64>,
+}
+
+/// New key material - the plain key is only ever returned here
+#[derive(Debug, Serialize)]
+pub struct IssuedApiKey {
+    pub key: String,
+    pub api_key: ApiKey,
+}
+
+/// GET /api/v1/api-keys
+pub async fn list_api_keys(
+    State(state): State<ApiState>,
+    _admin: AdminAuth,
+) -> Result<Json<Vec<ApiKey>>, ApiError> {
+    Ok(Json(ApiKeyQueries::list(&state.pool).await?))
+}
+
+/// POST /api/v1/api-keys
+pub async fn create_api_key(
+    State(state): State<ApiState>,
+    admin: AdminAuth,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<IssuedApiKey>), ApiError> {
+    if request.name.trim().is_empty() {
+        return Err(ApiError::BadRequest("name is required".to_string()));
+    }
+    if request.scopes.is_empty() {
+        return Err(ApiError::BadRequest("at least one scope is required".to_string()));
+    }
+    if let Some(scope) = request.scopes.iter().find(|s| !SCOPES.contains(&s.as_str())) {
+        return Err(ApiError::BadRequest(format!(
+            "Unknown scope '{}' (expected one of: {})",
+            scope,
+            SCOPES.join(", ")
+        )));
+    }
+    if matches!(request.rate_limit_per_minute, Some(limit) if limit <= 0) {
+        return Err(ApiError::BadRequest("rate_limit_per_minute must be positive".to_string()));
+    }
+    if matches!(request.expires_at, Some(at) if at <= Utc::now()) {
+        return Err(ApiError::BadRequest("expires_at must be in the future".to_string()));
+    }
+
+    let (key, key_prefix) = generate_api_key();
+    let new_key = NewApiKey {
+        name: &request.name,
+        key_prefix: &key_prefix,
+        scopes: &request.scopes,
+        tenant_id: request.tenant_id.as_deref(),
+        rate_limit_per_minute: request.rate_limit_per_minute,
+        expires_at: request.expires_at,
+    };
+    let api_key = ApiKeyQueries::create(&state.pool, &new_key, &hash_api_key(&key)).await?;
+
+    info!(id = %api_key.id, name = %api_key.name, scopes = ?api_key.scopes, "API key created");
+    audit::record(
+        &state.pool,
+        admin.actor(),
+        "api_key.create",
+        json!({
+            "id": api_key.id,
+            "name": api_key.name,
+            "scopes": api_key.scopes,
+            "tenant_id": api_key.tenant_id,
+            "rate_limit_per_minute": api_key.rate_limit_per_minute,
+            "expires_at": api_key.expires_at,
+        }),
+    )
+    .await;
+    Ok((StatusCode::CREATED, Json(IssuedApiKey { key, api_key })))
+}
+
+/// POST /api/v1/api-keys/{id}/rotate
+///
+/// Issues a successor with the same name, scopes and tenant; the old key keeps
+/// working for `grace_secs` so consumers can roll over without downtime.
+pub async fn rotate_api_key(
+    State(state): State<ApiState>,
+    admin: AdminAuth,
+    Path(id): Path<Uuid>,
+    request: Option<Json<RotateApiKeyRequest>>,
+) -> Result<(StatusCode, Json<IssuedApiKey>), ApiError> {
+    let grace_secs = request
+        .and_then(|Json(r)| r.grace_secs)
+        .unwrap_or(DEFAULT_ROTATION_GRACE_SECS);
+    if grace_secs < 0 {
+        return Err(ApiError::BadRequest("grace_secs must not be negative".to_string()));
+    }
+
+    let (key, key_prefix) = generate_api_key();
+    let old_expires_at = Utc::now() + Duration::seconds(grace_secs);
+    let api_key = ApiKeyQueries::rotate(&state.pool, id, &key_prefix, &hash_api_key(&key), old_expires_at)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Active API key {} not found", id)))?;
+
+    info!(old_id = %id, new_id = %api_key.id, grace_secs, "API key rotated");
+    audit::record(
+        &state.pool,
+        admin.actor(),
+        "api_key.rotate",
+        json!({ "id": id, "new_id": api_key.id, "grace_secs": grace_secs }),
+    )
+    .await;
+    Ok((StatusCode::CREATED, Json(IssuedApiKey { key, api_key })))
+}
+
+/// DELETE /api/v1/api-keys/{id}
+pub async fn revoke_api_key(
+    State(state): State<ApiState>,
+    admin: AdminAuth,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if !ApiKeyQueries::revoke(&state.pool, id).await? {
+        return Err(ApiError::NotFound(format!("API key {} not found", id)));
+    }
+
+    info!(id = %id, "API key revoked");
+    audit::record(&state.pool, admin.actor(), "api_key.revoke", json!({ "id": id })).await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,31 @@
+use super::auth::ReadOnlyAuth;
+use super::{ApiError, ApiState};
+use crate::db::archives::Archive;
+use crate::db::ArchiveQueries;
+use axum::extract::{Query, State};
+use axum::Json;
+use serde::Deserialize;
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct ListArchivesQuery {
+    pub limit: Option<i64>,
+}
+
+/// GET /api/v1/archives?limit=
+///
+/// Manifest of exported batches, newest first; restore one with
+/// `notifications-service restore --archive <id>`.
+pub async fn list_archives(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Query(query): Query<ListArchivesQuery>,
+) -> Result<Json<Vec<Archive>>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(ApiError::BadRequest(format!("limit must be between 1 and {}", MAX_LIMIT)));
+    }
+    Ok(Json(ArchiveQueries::list(&state.pool, limit).await?))
+}
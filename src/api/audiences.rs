@@ -0,0 +1,69 @@
+use super::audit;
+use super::auth::OperatorAuth;
+use super::{ApiError, ApiState};
+use crate::db::audiences::{AudienceMember, AudienceSync};
+use crate::db::tenants::DEFAULT_TENANT;
+use crate::db::{AudienceQueries, TenantQueries};
+use crate::models::Audience;
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+
+/// Most members in one sync (an organization larger than this needs AUDIENCE_MEMBERS_QUERY)
+const MAX_SYNC_MEMBERS: usize = 100_000;
+
+#[derive(Debug, Deserialize)]
+pub struct SyncQuery {
+    pub tenant_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncMembersRequest {
+    pub members: Vec<AudienceMember>,
+}
+
+/// PUT /api/v1/audiences/orgs/{org_id}/members?tenant_id=
+///
+/// Replaces the organization's members: users left out are no longer in its audiences, nor in
+/// its teams'. A user in several teams is listed once per team. Not used for delivery when
+/// AUDIENCE_MEMBERS_QUERY is set.
+pub async fn sync_org_members(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(org_id): Path<String>,
+    Query(query): Query<SyncQuery>,
+    Json(request): Json<SyncMembersRequest>,
+) -> Result<Json<AudienceSync>, ApiError> {
+    Audience::parse(&format!("org:{}", org_id))?;
+    for team_id in request.members.iter().filter_map(|m| m.team_id.as_deref()) {
+        Audience::parse(&format!("team:{}", team_id))?;
+    }
+    if request.members.len() > MAX_SYNC_MEMBERS {
+        return Err(ApiError::BadRequest(format!("At most {} members per sync", MAX_SYNC_MEMBERS)));
+    }
+
+    let tenant_id = query.tenant_id.unwrap_or_else(|| DEFAULT_TENANT.to_string());
+    if tenant_id != DEFAULT_TENANT && TenantQueries::find(&state.pool, &tenant_id).await?.is_none() {
+        return Err(ApiError::BadRequest(format!("Unknown tenant '{}'", tenant_id)));
+    }
+
+    let sync = AudienceQueries::sync_org(&state.pool, &tenant_id, &org_id, &request.members).await?;
+
+    info!(tenant_id = %tenant_id, org_id = %org_id, added = sync.added, removed = sync.removed, "Audience synced");
+    audit::record(
+        &state.pool,
+        caller.actor(),
+        "audience.sync",
+        json!({
+            "tenant_id": tenant_id,
+            "org_id": org_id,
+            "added": sync.added,
+            "removed": sync.removed,
+            "members": sync.members,
+        }),
+    )
+    .await;
+    Ok(Json(sync))
+}
@@ -0,0 +1,66 @@
+use super::auth::ReadOnlyAuth;
+use super::{ApiError, ApiState};
+use crate::db::audit::AuditEntry;
+use crate::db::AuditQueries;
+use axum::extract::{Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogQuery {
+    /// Only entries by this actor
+    pub actor: Option<String>,
+    /// Cursor: `next_before` of the previous page
+    pub before: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditEntry>,
+    /// Pass as `before` for the next page (None = last page)
+    pub next_before: Option<i64>,
+}
+
+/// GET /api/v1/audit-log?actor=&before=&limit=
+pub async fn list_audit_log(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<AuditLogPage>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(ApiError::BadRequest(format!("limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+
+    let entries = AuditQueries::list(&state.pool, query.actor.as_deref(), query.before, limit).await?;
+    let next_before = if entries.len() as i64 == limit {
+        entries.last().map(|e| e.id)
+    } else {
+        None
+    };
+
+    Ok(Json(AuditLogPage { entries, next_before }))
+}
+
+/// Record a completed mutation
+///
+/// Called after the change succeeded; a failed write is logged with the full entry
+/// instead of failing a request whose change is already applied.
+pub async fn record(pool: &PgPool, actor: &str, action: &str, params: serde_json::Value) {
+    if let Err(e) = AuditQueries::record(pool, actor, action, &params).await {
+        error!(
+            target: "audit",
+            actor = %actor,
+            action = %action,
+            params = %params,
+            error = %e,
+            "Failed to write audit log entry"
+        );
+    }
+}
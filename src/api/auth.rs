@@ -0,0 +1,355 @@
+use super::{ApiError, ApiState, ErrorCode};
+use crate::db::tenants::DEFAULT_TENANT;
+use crate::db::ApiKeyQueries;
+use crate::mtls::ServiceIdentity;
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Authenticated user, extracted from `Authorization: Bearer <jwt>`
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub user_id: Uuid,
+    /// `tenant_id` claim (app/brand the token was issued for), `default` when absent
+    pub tenant_id: String,
+}
+
+/// API keys are `nsk_<8 hex>_<secret>`; other bearer tokens are compared to ADMIN_TOKEN
+pub const API_KEY_PREFIX: &str = "nsk_";
+/// Full management access
+pub const SCOPE_ADMIN: &str = "admin";
+/// Manage templates and receipt webhooks
+pub const SCOPE_OPERATOR: &str = "operator";
+/// Read management data
+pub const SCOPE_READ_ONLY: &str = "read-only";
+/// POST /api/v1/notifications only
+pub const SCOPE_CREATE: &str = "notifications:create";
+/// Audit actor for callers using the shared ADMIN_TOKEN
+const ADMIN_TOKEN_ACTOR: &str = "admin_token";
+pub const SCOPES: [&str; 4] = [SCOPE_ADMIN, SCOPE_OPERATOR, SCOPE_READ_ONLY, SCOPE_CREATE];
+
+/// Management role; each role includes the ones below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    ReadOnly,
+    Operator,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::ReadOnly => SCOPE_READ_ONLY,
+            Role::Operator => SCOPE_OPERATOR,
+            Role::Admin => SCOPE_ADMIN,
+        }
+    }
+
+    /// Role named by an API key scope or the JWT `role` claim
+    pub fn parse(value: &str) -> Option<Role> {
+        match value {
+            SCOPE_READ_ONLY => Some(Role::ReadOnly),
+            SCOPE_OPERATOR => Some(Role::Operator),
+            SCOPE_ADMIN => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// Caller with the `admin` role: ADMIN_TOKEN, a client certificate mapped to `admin`
+/// (MTLS_IDENTITIES), an API key with the `admin` scope or a JWT with `role: admin`
+#[derive(Debug, Clone)]
+pub struct AdminAuth {
+    /// Caller identity (None = ADMIN_TOKEN)
+    pub service: Option<String>,
+}
+
+/// Caller with at least the `operator` role
+#[derive(Debug, Clone)]
+pub struct OperatorAuth {
+    /// Caller identity (None = ADMIN_TOKEN)
+    pub service: Option<String>,
+    pub role: Role,
+}
+
+/// Caller with at least the `read-only` role
+#[derive(Debug, Clone)]
+pub struct ReadOnlyAuth {
+    /// Caller identity (None = ADMIN_TOKEN)
+    pub service: Option<String>,
+    pub role: Role,
+}
+
+impl AdminAuth {
+    /// Name recorded in the audit log
+    pub fn actor(&self) -> &str {
+        self.service.as_deref().unwrap_or(ADMIN_TOKEN_ACTOR)
+    }
+}
+
+impl OperatorAuth {
+    /// Name recorded in the audit log
+    pub fn actor(&self) -> &str {
+        self.service.as_deref().unwrap_or(ADMIN_TOKEN_ACTOR)
+    }
+}
+
+/// Producer allowed to create notifications (`notifications:create` or `admin`)
+#[derive(Debug, Clone)]
+pub struct ProducerAuth {
+    /// Certificate identity or `api_key:<name>` (None = ADMIN_TOKEN)
+    pub service: Option<String>,
+    /// Tenant the API key is bound to (None = any)
+    pub tenant_id: Option<String>,
+    pub api_key_id: Option<Uuid>,
+    /// API key quota (None = unlimited)
+    pub rate_limit_per_minute: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+    #[serde(default)]
+    tenant_id: Option<String>,
+    /// Management role for operator tokens (absent for regular users)
+    #[serde(default)]
+    role: Option<String>,
+}
+
+#[async_trait]
+impl FromRequestParts<ApiState> for AuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &ApiState) -> Result<Self, Self::Rejection> {
+        let Some(jwt_secret) = &state.jwt_secret else {
+            return Err(ErrorCode::UserApiDisabled.into());
+        };
+
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or(ErrorCode::MissingToken)?;
+
+        let claims = decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(jwt_secret.as_bytes()),
+            &Validation::new(Algorithm::HS256),
+        )
+        .map_err(|e| {
+            debug!(error = %e, "JWT validation failed");
+            ErrorCode::InvalidToken
+        })?
+        .claims;
+
+        let user_id = Uuid::parse_str(&claims.sub)
+            .map_err(|_| ErrorCode::InvalidToken)?;
+
+        let tenant_id = claims.tenant_id.unwrap_or_else(|| DEFAULT_TENANT.to_string());
+
+        Ok(AuthUser { user_id, tenant_id })
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<ApiState> for AdminAuth {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &ApiState) -> Result<Self, Self::Rejection> {
+        let caller = authorize(parts, state, Role::Admin).await?;
+        Ok(AdminAuth {
+            service: caller.identity,
+        })
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<ApiState> for OperatorAuth {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &ApiState) -> Result<Self, Self::Rejection> {
+        let caller = authorize(parts, state, Role::Operator).await?;
+        Ok(OperatorAuth {
+            role: caller.role.unwrap_or(Role::Operator),
+            service: caller.identity,
+        })
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<ApiState> for ReadOnlyAuth {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &ApiState) -> Result<Self, Self::Rejection> {
+        let caller = authorize(parts, state, Role::ReadOnly).await?;
+        Ok(ReadOnlyAuth {
+            role: caller.role.unwrap_or(Role::ReadOnly),
+            service: caller.identity,
+        })
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<ApiState> for ProducerAuth {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &ApiState) -> Result<Self, Self::Rejection> {
+        let caller = authenticate_service(parts, state).await?;
+        if !caller.can_create {
+            return Err(ApiError::Forbidden(format!("API key lacks the '{}' scope", SCOPE_CREATE)));
+        }
+
+        Ok(ProducerAuth {
+            service: caller.identity,
+            tenant_id: caller.tenant_id,
+            api_key_id: caller.api_key_id,
+            rate_limit_per_minute: caller.rate_limit_per_minute,
+        })
+    }
+}
+
+/// Authenticated service or operator, before role checks
+struct ServiceCaller {
+    identity: Option<String>,
+    tenant_id: Option<String>,
+    /// Highest management role (None = producer-only API key)
+    role: Option<Role>,
+    can_create: bool,
+    api_key_id: Option<Uuid>,
+    rate_limit_per_minute: Option<i32>,
+}
+
+/// Authenticate and check the role; every decision goes to the `audit` log target
+async fn authorize(parts: &Parts, state: &ApiState, required: Role) -> Result<ServiceCaller, ApiError> {
+    let caller = authenticate_service(parts, state).await?;
+    let allowed = caller.role.is_some_and(|role| role >= required);
+
+    info!(
+        target: "audit",
+        identity = caller.identity.as_deref().unwrap_or(ADMIN_TOKEN_ACTOR),
+        role = caller.role.map(|r| r.as_str()),
+        required = required.as_str(),
+        method = %parts.method,
+        path = %parts.uri.path(),
+        allowed,
+        "Authorization decision"
+    );
+
+    if !allowed {
+        return Err(ApiError::Forbidden(format!("Requires the '{}' role", required.as_str())));
+    }
+    Ok(caller)
+}
+
+/// mTLS certificate, API key, ADMIN_TOKEN or operator JWT - in that order
+async fn authenticate_service(parts: &Parts, state: &ApiState) -> Result<ServiceCaller, ApiError> {
+    // Only set by the mTLS listener, after the certificate chain was verified
+    if let Some(identity) = parts.extensions.get::<ServiceIdentity>() {
+        return Ok(ServiceCaller {
+            identity: Some(identity.name.clone()),
+            tenant_id: identity.grant.tenant_id.clone(),
+            role: identity.grant.role,
+            can_create: true,
+            api_key_id: None,
+            rate_limit_per_minute: None,
+        });
+    }
+
+    let token = parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| ApiError::Unauthorized("Missing bearer token".to_string()))?;
+
+    if token.starts_with(API_KEY_PREFIX) {
+        let key = ApiKeyQueries::find_active(&state.pool, &hash_api_key(token))
+            .await?
+            .ok_or_else(|| ApiError::Unauthorized("Invalid or expired API key".to_string()))?;
+
+        let (pool, id) = (state.pool.clone(), key.id);
+        tokio::spawn(async move {
+            if let Err(e) = ApiKeyQueries::touch(&pool, id).await {
+                debug!(id = %id, error = %e, "Failed to record API key usage");
+            }
+        });
+
+        return Ok(ServiceCaller {
+            identity: Some(format!("api_key:{}", key.name)),
+            role: key.scopes.iter().filter_map(|s| Role::parse(s)).max(),
+            can_create: key.has_scope(SCOPE_CREATE) || key.has_scope(SCOPE_ADMIN),
+            tenant_id: key.tenant_id,
+            api_key_id: Some(key.id),
+            rate_limit_per_minute: key.rate_limit_per_minute,
+        });
+    }
+
+    if let Some(admin_token) = &state.admin_token {
+        if constant_time_eq(token.as_bytes(), admin_token.as_bytes()) {
+            return Ok(ServiceCaller {
+                identity: None,
+                tenant_id: None,
+                role: Some(Role::Admin),
+                can_create: true,
+                api_key_id: None,
+                rate_limit_per_minute: None,
+            });
+        }
+    }
+
+    // Operators signed in through the auth service carry a `role` claim
+    if let Some(jwt_secret) = &state.jwt_secret {
+        if let Ok(data) = decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(jwt_secret.as_bytes()),
+            &Validation::new(Algorithm::HS256),
+        ) {
+            let claims = data.claims;
+            let Some(role) = claims.role.as_deref().and_then(Role::parse) else {
+                return Err(ApiError::Forbidden("Token has no management role".to_string()));
+            };
+            return Ok(ServiceCaller {
+                identity: Some(format!("user:{}", claims.sub)),
+                tenant_id: claims.tenant_id,
+                role: Some(role),
+                can_create: role == Role::Admin,
+                api_key_id: None,
+                rate_limit_per_minute: None,
+            });
+        }
+    }
+
+    if state.admin_token.is_none() && state.jwt_secret.is_none() {
+        return Err(ApiError::Forbidden("Admin API not configured".to_string()));
+    }
+    Err(ApiError::Unauthorized("Invalid admin token".to_string()))
+}
+
+/// New random API key: (`nsk_<8 hex>_<secret>`, display prefix)
+pub fn generate_api_key() -> (String, String) {
+    let id = Uuid::new_v4().simple().to_string();
+    let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let key = format!("{}{}_{}", API_KEY_PREFIX, &id[..8], secret);
+    let prefix = key[..API_KEY_PREFIX.len() + 8].to_string();
+    (key, prefix)
+}
+
+/// Hex SHA-256 of an API key, as stored in `api_keys.key_hash`
+pub fn hash_api_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Compare secrets without leaking the matching prefix length through timing
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,314 @@
+use super::audit;
+use super::auth::{AdminAuth, OperatorAuth, ReadOnlyAuth};
+use super::{ApiError, ApiState};
+use crate::db::campaigns::{Campaign, CampaignAudience, CampaignStats, NewCampaign};
+use crate::db::tenants::DEFAULT_TENANT;
+use crate::db::{CampaignQueries, TenantQueries};
+use crate::targeting::DeviceFilter;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+/// Most user ids accepted per request (upload larger lists in several calls)
+const MAX_UPLOAD: usize = 10_000;
+
+/// Default throttle when `rate_per_minute` is omitted
+const DEFAULT_RATE_PER_MINUTE: i32 = 600;
+
+/// Widest delivery jitter window (an hour)
+const MAX_JITTER_SECS: i32 = 3600;
+
+/// Audience as submitted: uploaded ids come inline, segments as SQL
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AudienceRequest {
+    Users {
+        #[serde(default)]
+        user_ids: Vec<Uuid>,
+    },
+    /// Must return a `user_id` column, e.g. `SELECT id AS user_id FROM activity.users WHERE ...`
+    Segment { sql: String },
+    /// Everyone in the tenant with a device matching `device_filter` (e.g. an upgrade nag)
+    Devices,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCampaignRequest {
+    pub name: String,
+    pub audience: AudienceRequest,
+    pub notification_type: String,
+    #[serde(default)]
+    pub title: String,
+    pub message: Option<String>,
+    pub template_key: Option<String>,
+    pub message_key: Option<String>,
+    pub message_args: Option<serde_json::Value>,
+    pub payload: Option<serde_json::Value>,
+    pub deep_link: Option<String>,
+    pub priority: Option<String>,
+    pub tenant_id: Option<String>,
+    /// Default: now
+    pub start_at: Option<DateTime<Utc>>,
+    pub rate_per_minute: Option<i32>,
+    /// Push only to devices meeting all conditions, e.g. `["app_version < 3.2"]`
+    pub device_filter: Option<Vec<String>>,
+    /// Send at this wall-clock time in each recipient's timezone, e.g. `2026-03-29T09:00:00`
+    pub deliver_at_local: Option<NaiveDateTime>,
+    /// Spread delivery: each notification waits a random 0..jitter_secs after it is queued
+    pub jitter_secs: Option<i32>,
+}
+
+impl CreateCampaignRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name is required".to_string());
+        }
+        if self.notification_type.trim().is_empty() {
+            return Err("notification_type is required".to_string());
+        }
+        if self.title.trim().is_empty() && self.message_key.is_none() && self.template_key.is_none() {
+            return Err("title is required unless message_key or template_key is set".to_string());
+        }
+        if let Some(priority) = &self.priority {
+            if !matches!(priority.as_str(), "low" | "normal" | "high" | "critical") {
+                return Err(format!("Unknown priority '{}'", priority));
+            }
+        }
+        if matches!(&self.message_args, Some(args) if !args.is_object()) {
+            return Err("message_args must be a JSON object".to_string());
+        }
+        if matches!(self.rate_per_minute, Some(rate) if rate <= 0) {
+            return Err("rate_per_minute must be positive".to_string());
+        }
+        if matches!(self.jitter_secs, Some(jitter) if !(0..=MAX_JITTER_SECS).contains(&jitter)) {
+            return Err(format!("jitter_secs must be between 0 and {}", MAX_JITTER_SECS));
+        }
+        // The router delivers a deliver_at_local row at the exact local time, dropping the jitter
+        if self.jitter_secs.unwrap_or(0) > 0 && self.deliver_at_local.is_some() {
+            return Err("jitter_secs can't be combined with deliver_at_local".to_string());
+        }
+        if let Some(conditions) = &self.device_filter {
+            DeviceFilter::parse(conditions)?;
+        }
+        match &self.audience {
+            AudienceRequest::Users { user_ids } if user_ids.len() > MAX_UPLOAD => Err(format!(
+                "audience.user_ids is limited to {} per request, upload the rest via /recipients",
+                MAX_UPLOAD
+            )),
+            AudienceRequest::Segment { sql } if sql.trim().is_empty() => {
+                Err("audience.sql must not be empty".to_string())
+            }
+            AudienceRequest::Devices if self.device_filter.is_none() => {
+                Err("a devices audience needs device_filter".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadRecipientsRequest {
+    pub user_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadRecipientsResponse {
+    pub total_recipients: i32,
+}
+
+/// Campaign with its delivery progress
+#[derive(Debug, Serialize)]
+pub struct CampaignDetail {
+    #[serde(flatten)]
+    pub campaign: Campaign,
+    pub stats: CampaignStats,
+}
+
+/// GET /api/v1/campaigns
+pub async fn list_campaigns(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+) -> Result<Json<Vec<Campaign>>, ApiError> {
+    Ok(Json(CampaignQueries::list(&state.pool).await?))
+}
+
+/// GET /api/v1/campaigns/{id}
+pub async fn get_campaign(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CampaignDetail>, ApiError> {
+    let campaign = CampaignQueries::find(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Campaign {} not found", id)))?;
+    let stats = CampaignQueries::stats(&state.pool, id).await?;
+    Ok(Json(CampaignDetail { campaign, stats }))
+}
+
+/// POST /api/v1/campaigns
+///
+/// Admin only: a segment is SQL run with the service's database role.
+pub async fn create_campaign(
+    State(state): State<ApiState>,
+    admin: AdminAuth,
+    Json(request): Json<CreateCampaignRequest>,
+) -> Result<(StatusCode, Json<Campaign>), ApiError> {
+    request.validate().map_err(ApiError::BadRequest)?;
+
+    let tenant_id = request.tenant_id.clone().unwrap_or_else(|| DEFAULT_TENANT.to_string());
+    if tenant_id != DEFAULT_TENANT && TenantQueries::find(&state.pool, &tenant_id).await?.is_none() {
+        return Err(ApiError::BadRequest(format!("Unknown tenant '{}'", tenant_id)));
+    }
+
+    let (audience, user_ids) = match request.audience {
+        AudienceRequest::Users { user_ids } => (CampaignAudience::Users, user_ids),
+        AudienceRequest::Segment { sql } => {
+            CampaignQueries::validate_segment(&state.pool, &sql)
+                .await
+                .map_err(|e| ApiError::BadRequest(format!("Invalid segment: {}", e)))?;
+            (CampaignAudience::Segment { sql }, Vec::new())
+        }
+        AudienceRequest::Devices => (CampaignAudience::Devices, Vec::new()),
+    };
+
+    let campaign = NewCampaign {
+        tenant_id,
+        name: request.name,
+        audience,
+        notification_type: request.notification_type,
+        title: request.title,
+        message: request.message,
+        template_key: request.template_key,
+        message_key: request.message_key,
+        message_args: request.message_args,
+        payload: request.payload,
+        deep_link: request.deep_link,
+        priority: request.priority.unwrap_or_else(|| "normal".to_string()),
+        start_at: request.start_at.unwrap_or_else(Utc::now),
+        rate_per_minute: request.rate_per_minute.unwrap_or(DEFAULT_RATE_PER_MINUTE),
+        device_filter: request.device_filter,
+        deliver_at_local: request.deliver_at_local,
+        jitter_secs: request.jitter_secs.unwrap_or(0),
+    };
+    let created = CampaignQueries::create(&state.pool, &campaign, &user_ids, admin.actor()).await?;
+
+    info!(
+        id = %created.id,
+        name = %created.name,
+        start_at = %created.start_at,
+        recipients = created.total_recipients,
+        "Campaign created"
+    );
+    audit::record(
+        &state.pool,
+        admin.actor(),
+        "campaign.create",
+        json!({
+            "id": created.id,
+            "name": created.name,
+            "audience": created.audience,
+            "device_filter": created.device_filter,
+            "start_at": created.start_at,
+            "deliver_at_local": created.deliver_at_local,
+            "rate_per_minute": created.rate_per_minute,
+            "jitter_secs": created.jitter_secs,
+        }),
+    )
+    .await;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// POST /api/v1/campaigns/{id}/recipients
+///
+/// Appends user ids to a `users` campaign until it starts.
+pub async fn upload_recipients(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UploadRecipientsRequest>,
+) -> Result<Json<UploadRecipientsResponse>, ApiError> {
+    if request.user_ids.is_empty() || request.user_ids.len() > MAX_UPLOAD {
+        return Err(ApiError::BadRequest(format!("user_ids must contain 1 to {} ids", MAX_UPLOAD)));
+    }
+    let campaign = find_campaign(&state, id).await?;
+    if !matches!(campaign.audience.0, CampaignAudience::Users) {
+        return Err(ApiError::BadRequest("Recipients can only be uploaded to a users campaign".to_string()));
+    }
+
+    let total_recipients = CampaignQueries::add_recipients(&state.pool, id, &request.user_ids)
+        .await?
+        .ok_or_else(|| ApiError::BadRequest(format!("Campaign {} has already started", id)))?;
+
+    info!(id = %id, uploaded = request.user_ids.len(), total = total_recipients, "Campaign recipients uploaded");
+    audit::record(
+        &state.pool,
+        caller.actor(),
+        "campaign.upload_recipients",
+        json!({ "id": id, "uploaded": request.user_ids.len(), "total_recipients": total_recipients }),
+    )
+    .await;
+    Ok(Json(UploadRecipientsResponse { total_recipients }))
+}
+
+/// POST /api/v1/campaigns/{id}/pause
+pub async fn pause_campaign(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Campaign>, ApiError> {
+    let campaign = find_campaign(&state, id).await?;
+    let paused = CampaignQueries::pause(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::BadRequest(format!("Campaign {} is {} and can't be paused", id, campaign.status)))?;
+
+    info!(id = %id, "Campaign paused");
+    audit::record(&state.pool, caller.actor(), "campaign.pause", json!({ "id": id })).await;
+    Ok(Json(paused))
+}
+
+/// POST /api/v1/campaigns/{id}/resume
+///
+/// The throttle restarts from now; no burst for the time spent paused.
+pub async fn resume_campaign(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Campaign>, ApiError> {
+    let campaign = find_campaign(&state, id).await?;
+    let resumed = CampaignQueries::resume(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::BadRequest(format!("Campaign {} is {}, not paused", id, campaign.status)))?;
+
+    info!(id = %id, status = %resumed.status, "Campaign resumed");
+    audit::record(&state.pool, caller.actor(), "campaign.resume", json!({ "id": id })).await;
+    Ok(Json(resumed))
+}
+
+/// POST /api/v1/campaigns/{id}/cancel
+///
+/// Stops the fan-out; notifications already queued are still delivered.
+pub async fn cancel_campaign(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Campaign>, ApiError> {
+    let campaign = find_campaign(&state, id).await?;
+    let cancelled = CampaignQueries::cancel(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::BadRequest(format!("Campaign {} is already {}", id, campaign.status)))?;
+
+    info!(id = %id, queued = cancelled.queued_count, "Campaign cancelled");
+    audit::record(&state.pool, caller.actor(), "campaign.cancel", json!({ "id": id })).await;
+    Ok(Json(cancelled))
+}
+
+async fn find_campaign(state: &ApiState, id: Uuid) -> Result<Campaign, ApiError> {
+    CampaignQueries::find(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Campaign {} not found", id)))
+}
@@ -0,0 +1,72 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState, ErrorCode};
+use crate::db::ClientErrorQueries;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::info;
+use uuid::Uuid;
+
+/// Longest error code a client can report
+pub const MAX_CLIENT_ERROR_CODE_LEN: usize = 64;
+/// Largest serialized `context` kept with a report
+pub const MAX_CLIENT_ERROR_CONTEXT_BYTES: usize = 4096;
+
+#[derive(Debug, Deserialize)]
+pub struct ClientErrorRequest {
+    /// What went wrong, e.g. `template_args_missing` (a-z, 0-9, `_`, `.`, `-`)
+    pub code: String,
+    /// Anything that helps the app team reproduce it (app build, screen, parser message)
+    pub context: Option<Value>,
+    /// The device that got it as a push; absent = received over the Bus
+    pub fcm_token: Option<String>,
+}
+
+/// POST /api/v1/notifications/{id}/error
+///
+/// The client received the notification but couldn't render or process it. The report is
+/// stored against the delivery attempt it came in with and shows up in the explain
+/// timeline. Like acks this comes in over HTTP: the Bus only carries traffic to clients.
+pub async fn report(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ClientErrorRequest>,
+) -> Result<StatusCode, ApiError> {
+    let code = request.code.trim();
+    let valid_code = !code.is_empty()
+        && code.len() <= MAX_CLIENT_ERROR_CODE_LEN
+        && code.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '.' | '-'));
+    if !valid_code {
+        return Err(ApiError::BadRequest(format!(
+            "code must be 1-{} of a-z, 0-9, '_', '.', '-'",
+            MAX_CLIENT_ERROR_CODE_LEN
+        )));
+    }
+    let context = request.context.filter(|context| !context.is_null());
+    if context.as_ref().is_some_and(|context| context.to_string().len() > MAX_CLIENT_ERROR_CONTEXT_BYTES) {
+        return Err(ApiError::BadRequest(format!(
+            "context is limited to {} bytes",
+            MAX_CLIENT_ERROR_CONTEXT_BYTES
+        )));
+    }
+    let channel = if request.fcm_token.is_some_and(|t| !t.trim().is_empty()) { "push" } else { "bus" };
+
+    let attempt =
+        ClientErrorQueries::record(&state.pool, id, user.user_id, &user.tenant_id, channel, code, context.as_ref())
+            .await?
+            .ok_or_else(|| ErrorCode::NotificationNotFound.with("id", id))?;
+
+    info!(
+        id = %id,
+        user_id = %user.user_id,
+        channel = channel,
+        code = code,
+        attempt_id = ?attempt,
+        "Client reported a notification it couldn't handle"
+    );
+    metrics::counter!("notifications_client_errors_total", "channel" => channel).increment(1);
+    Ok(StatusCode::NO_CONTENT)
+}
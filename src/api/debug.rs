@@ -0,0 +1,27 @@
+use super::auth::AdminAuth;
+use super::ApiError;
+use crate::payload_sink::{self, PayloadEntry};
+use axum::extract::Query;
+use axum::Json;
+use serde::Deserialize;
+
+/// Entries returned by default / at most
+const DEFAULT_RECENT: usize = 100;
+const MAX_RECENT: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct RecentQuery {
+    /// fcm, fcm_topic or bus (None = all)
+    pub kind: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// GET /admin/debug/recent?kind=&limit=
+///
+/// The last payloads this pod sent, newest first. Admin scope: they hold user content.
+pub async fn recent(_caller: AdminAuth, Query(query): Query<RecentQuery>) -> Result<Json<Vec<PayloadEntry>>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_RECENT).min(MAX_RECENT);
+    payload_sink::recent(query.kind.as_deref(), limit)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound("Payload logging is off (DEBUG_LOG_PAYLOADS)".to_string()))
+}
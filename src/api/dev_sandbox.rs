@@ -0,0 +1,153 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState};
+use crate::db::devices::{Device, DeviceRegistration};
+use crate::db::explain::{ExplainedNotification, TrailEvent};
+use crate::db::{DeviceQueries, ExplainQueries};
+use crate::dev_sandbox::{self, DevSandbox, SandboxPush};
+use crate::ingest::{ingest, rate_limit};
+use crate::models::NewNotification;
+use crate::push::mock::MockResponse;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct SandboxDeviceRequest {
+    /// e.g. "android", "ios", "web" (default android)
+    pub device_type: Option<String>,
+    pub locale: Option<String>,
+    pub app_version: Option<String>,
+    /// How the mock FCM answers pushes to it: success (default), unregistered, rate_limited
+    /// or server_error
+    pub respond_with: Option<String>,
+}
+
+/// A sandbox notification with what the pipeline made of it
+#[derive(Debug, Serialize)]
+pub struct SandboxNotification {
+    pub status: &'static str,
+    pub notification: ExplainedNotification,
+    pub timeline: Vec<TrailEvent>,
+    /// FCM requests the mock received for it, oldest first
+    pub push: Vec<SandboxPush>,
+}
+
+/// The sandbox, or 404 when DEV_SANDBOX_TENANT isn't set
+fn sandbox(state: &ApiState) -> Result<&Arc<DevSandbox>, ApiError> {
+    state
+        .dev_sandbox
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("Developer sandbox is off (DEV_SANDBOX_TENANT)".to_string()))
+}
+
+/// POST /api/v1/sandbox/devices
+///
+/// Registers a fake device for the caller in the sandbox tenant. The token is generated
+/// here; pushes to it only ever reach the mock FCM.
+pub async fn register_device(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Json(request): Json<SandboxDeviceRequest>,
+) -> Result<Json<Device>, ApiError> {
+    let sandbox = sandbox(&state)?;
+    let respond_with = match request.respond_with.as_deref() {
+        Some(value) => MockResponse::parse(value).ok_or_else(|| {
+            ApiError::BadRequest("respond_with must be success, unregistered, rate_limited or server_error".to_string())
+        })?,
+        None => MockResponse::Success,
+    };
+    let optional = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let registration = DeviceRegistration {
+        fcm_token: DevSandbox::new_token(),
+        device_type: optional(request.device_type)
+            .map(|v| v.to_lowercase())
+            .unwrap_or_else(|| "android".to_string()),
+        locale: optional(request.locale),
+        app_version: optional(request.app_version),
+        os_version: None,
+        push_environment: None,
+        capabilities: None,
+        region: state.status.region.clone(),
+    };
+    sandbox.respond_for_token(&registration.fcm_token, respond_with);
+
+    let (device, _) = DeviceQueries::register(&state.pool, sandbox.tenant_id(), user.user_id, &registration).await?;
+    if let Some(cache) = &state.device_cache {
+        cache.invalidate(sandbox.tenant_id(), user.user_id).await;
+    }
+
+    info!(
+        user_id = %user.user_id,
+        device_type = %device.device_type,
+        respond_with = respond_with.as_str(),
+        "Sandbox device registered"
+    );
+    Ok(Json(device))
+}
+
+/// POST /api/v1/sandbox/notifications
+///
+/// The body of `POST /api/v1/notifications`, always sent to the caller in the sandbox
+/// tenant and ingested like any other notification.
+pub async fn send(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Json(mut notification): Json<NewNotification>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let sandbox = sandbox(&state)?;
+    if notification.topic.is_some() || notification.audience.is_some() {
+        return Err(ApiError::BadRequest("Sandbox notifications go to the caller (no topic or audience)".to_string()));
+    }
+    if notification.user_id.is_some_and(|user_id| user_id != user.user_id) {
+        return Err(ApiError::Forbidden("Sandbox notifications go to the caller".to_string()));
+    }
+    // The service would call it with the signed receipt
+    if notification.callback_url.is_some() {
+        return Err(ApiError::BadRequest("callback_url isn't available in the sandbox".to_string()));
+    }
+    notification.tenant_id = Some(sandbox.tenant_id().to_string());
+    notification.user_id = Some(user.user_id);
+    notification.created_by = Some(format!("sandbox:{}", user.user_id));
+    state.limits.check(&notification)?;
+    state.backpressure.check(&state.pool, &notification).await?;
+    rate_limit::check("sandbox", &format!("sandbox:{}", user.user_id), dev_sandbox::SENDS_PER_MINUTE)
+        .await
+        .map_err(ApiError::RateLimited)?;
+
+    let id = ingest(&state.pool, &notification).await?;
+
+    info!(id = %id, user_id = %user.user_id, notification_type = %notification.notification_type, "Sandbox notification sent");
+    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({ "id": id }))))
+}
+
+/// GET /api/v1/sandbox/notifications/{id}
+///
+/// The caller's sandbox notification: its state and decision trail as explain shows them,
+/// and the FCM requests the mock received. Pushes are kept in memory by the pod that sent
+/// them, so this reads the mock of the pod that answers.
+pub async fn inspect(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SandboxNotification>, ApiError> {
+    let sandbox = sandbox(&state)?;
+    let not_found = || ApiError::NotFound(format!("Sandbox notification {} not found", id));
+    let notification = ExplainQueries::find(&state.pool, id).await?.ok_or_else(not_found)?;
+    let target = &notification.notification;
+    if target.tenant_id != sandbox.tenant_id() || target.user_id != user.user_id {
+        return Err(not_found());
+    }
+
+    let timeline = ExplainQueries::timeline(&state.pool, id).await?;
+    Ok(Json(SandboxNotification {
+        status: notification.status(),
+        push: sandbox.pushes(id),
+        notification,
+        timeline,
+    }))
+}
@@ -0,0 +1,427 @@
+use super::audit;
+use super::auth::AdminAuth;
+use super::devices::MAX_VERSION_LEN;
+use super::{ApiError, ApiState};
+use crate::config::PushEnvironment;
+use crate::db::devices::DeviceRecord;
+use crate::db::tenants::DEFAULT_TENANT;
+use crate::db::{DeviceQueries, TenantQueries};
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+use uuid::Uuid;
+
+/// Largest import body accepted (the route's body limit)
+pub const MAX_IMPORT_BYTES: usize = 64 * 1024 * 1024;
+/// Line errors listed in the report; the rest are only counted
+const MAX_REPORTED_ERRORS: usize = 100;
+const MAX_TOKEN_LEN: usize = 4096;
+/// Columns an import needs; the others are optional, unknown ones are ignored
+const REQUIRED_COLUMNS: [&str; 3] = ["user_id", "fcm_token", "device_type"];
+const CSV_COLUMNS: [&str; 10] = [
+    "tenant_id",
+    "user_id",
+    "fcm_token",
+    "device_type",
+    "locale",
+    "app_version",
+    "os_version",
+    "push_environment",
+    "last_seen_at",
+    "created_at",
+];
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+    /// csv or ndjson (default: from Content-Type, else ndjson)
+    pub format: Option<String>,
+    /// Tenant of rows without a tenant_id (default: the default tenant)
+    pub tenant: Option<String>,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// Only this tenant's devices (default: all)
+    pub tenant: Option<String>,
+    /// ndjson (default) or csv
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    /// Data lines read
+    pub rows: usize,
+    /// New devices stored (on a dry run: that would be stored)
+    pub imported: usize,
+    /// Token registered already; left as it is
+    pub existing: usize,
+    /// Token repeated in the file; the first line wins
+    pub duplicates: usize,
+    pub invalid: usize,
+    /// First MAX_REPORTED_ERRORS invalid lines
+    pub errors: Vec<LineError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LineError {
+    /// 1-based, counting the CSV header
+    pub line: usize,
+    pub error: String,
+}
+
+/// Parsed device per input line number
+type ParsedLines = Vec<(usize, Result<DeviceRecord, String>)>;
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Csv,
+    Ndjson,
+}
+
+impl Format {
+    fn parse(value: &str) -> Result<Self, ApiError> {
+        match value {
+            "csv" => Ok(Format::Csv),
+            "ndjson" => Ok(Format::Ndjson),
+            _ => Err(ApiError::BadRequest(format!("format must be csv or ndjson, got '{}'", value))),
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Format::Csv => "text/csv; charset=utf-8",
+            Format::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+/// POST /admin/devices/import?dry_run=&format=&tenant=
+///
+/// Loads device tokens from a legacy push system into `user_devices`, as CSV (header row
+/// with at least user_id, fcm_token, device_type) or NDJSON (one device object per line).
+/// Every line is validated; tokens repeated in the file or registered already are skipped,
+/// so an import can be re-run. Defaults to a dry run that only reports what would happen.
+pub async fn import_devices(
+    State(state): State<ApiState>,
+    caller: AdminAuth,
+    Query(query): Query<ImportQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ImportReport>, ApiError> {
+    let format = match query.format.as_deref() {
+        Some(format) => Format::parse(format)?,
+        None => {
+            let content_type = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or("");
+            if content_type.contains("csv") {
+                Format::Csv
+            } else {
+                Format::Ndjson
+            }
+        }
+    };
+    let default_tenant = query.tenant.as_deref().unwrap_or(DEFAULT_TENANT);
+    let lines = match format {
+        Format::Csv => parse_csv(&body, default_tenant)?,
+        Format::Ndjson => parse_ndjson(&body, default_tenant),
+    };
+
+    let mut report = ImportReport {
+        dry_run: query.dry_run,
+        rows: lines.len(),
+        imported: 0,
+        existing: 0,
+        duplicates: 0,
+        invalid: 0,
+        errors: Vec::new(),
+    };
+    let mut tenants: HashMap<String, bool> = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut valid = Vec::new();
+    for (line, record) in lines {
+        let record = match record.and_then(normalize) {
+            Ok(record) => record,
+            Err(error) => {
+                report.reject(line, error);
+                continue;
+            }
+        };
+        let known = match tenants.get(&record.tenant_id) {
+            Some(known) => *known,
+            None => {
+                let known = record.tenant_id == DEFAULT_TENANT
+                    || TenantQueries::find(&state.pool, &record.tenant_id).await?.is_some();
+                tenants.insert(record.tenant_id.clone(), known);
+                known
+            }
+        };
+        if !known {
+            report.reject(line, format!("Unknown tenant '{}'", record.tenant_id));
+            continue;
+        }
+        if !seen.insert(record.fcm_token.clone()) {
+            report.duplicates += 1;
+            continue;
+        }
+        valid.push(record);
+    }
+
+    let tokens: Vec<String> = valid.iter().map(|d| d.fcm_token.clone()).collect();
+    let registered: HashSet<String> = DeviceQueries::registered_tokens(&state.pool, &tokens).await?.into_iter().collect();
+    let new: Vec<DeviceRecord> = valid.into_iter().filter(|d| !registered.contains(&d.fcm_token)).collect();
+    report.existing = registered.len();
+    report.imported = new.len();
+
+    if !query.dry_run && !new.is_empty() {
+        let inserted = DeviceQueries::import(&state.pool, &new).await? as usize;
+        // Registered between the check and the insert
+        report.existing += new.len() - inserted;
+        report.imported = inserted;
+        if let Some(cache) = &state.device_cache {
+            let users: HashSet<(&str, Uuid)> = new.iter().map(|d| (d.tenant_id.as_str(), d.user_id)).collect();
+            for (tenant_id, user_id) in users {
+                cache.invalidate(tenant_id, user_id).await;
+            }
+        }
+    }
+
+    info!(
+        dry_run = report.dry_run,
+        rows = report.rows,
+        imported = report.imported,
+        existing = report.existing,
+        duplicates = report.duplicates,
+        invalid = report.invalid,
+        "Device import {}",
+        if report.dry_run { "checked" } else { "finished" }
+    );
+    if !report.dry_run {
+        audit::record(
+            &state.pool,
+            caller.actor(),
+            "device.import",
+            json!({
+                "rows": report.rows,
+                "imported": report.imported,
+                "existing": report.existing,
+                "duplicates": report.duplicates,
+                "invalid": report.invalid,
+            }),
+        )
+        .await;
+    }
+    Ok(Json(report))
+}
+
+/// GET /admin/devices/export?tenant=&format=
+///
+/// Every device with its metadata (not its preferences), in the format import reads.
+/// Tokens are exported in full, so this needs the admin scope and is audited.
+pub async fn export_devices(
+    State(state): State<ApiState>,
+    caller: AdminAuth,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let format = Format::parse(query.format.as_deref().unwrap_or("ndjson"))?;
+    let devices = DeviceQueries::export(&state.pool, query.tenant.as_deref()).await?;
+
+    let mut body = String::new();
+    match format {
+        Format::Csv => {
+            body.push_str(&CSV_COLUMNS.join(","));
+            body.push('\n');
+            let timestamp = |t: Option<DateTime<Utc>>| t.map(|t| t.to_rfc3339()).unwrap_or_default();
+            for device in &devices {
+                let fields = [
+                    device.tenant_id.clone(),
+                    device.user_id.to_string(),
+                    device.fcm_token.clone(),
+                    device.device_type.clone(),
+                    device.locale.clone().unwrap_or_default(),
+                    device.app_version.clone().unwrap_or_default(),
+                    device.os_version.clone().unwrap_or_default(),
+                    device.push_environment.clone().unwrap_or_default(),
+                    timestamp(device.last_seen_at),
+                    timestamp(device.created_at),
+                ];
+                let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                body.push_str(&fields.join(","));
+                body.push('\n');
+            }
+        }
+        Format::Ndjson => {
+            for device in &devices {
+                body.push_str(&serde_json::to_string(device).map_err(|e| ApiError::Internal(e.to_string()))?);
+                body.push('\n');
+            }
+        }
+    }
+
+    info!(tenant = ?query.tenant, devices = devices.len(), "Devices exported");
+    audit::record(
+        &state.pool,
+        caller.actor(),
+        "device.export",
+        json!({ "tenant": query.tenant, "devices": devices.len() }),
+    )
+    .await;
+    Ok(([(header::CONTENT_TYPE, format.content_type())], body).into_response())
+}
+
+impl ImportReport {
+    fn reject(&mut self, line: usize, error: String) {
+        self.invalid += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(LineError { line, error });
+        }
+    }
+}
+
+/// Trim and check one device the way `POST /api/v1/devices` does
+fn normalize(mut record: DeviceRecord) -> Result<DeviceRecord, String> {
+    let optional = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    record.fcm_token = record.fcm_token.trim().to_string();
+    record.device_type = record.device_type.trim().to_lowercase();
+    record.locale = optional(record.locale);
+    record.app_version = optional(record.app_version);
+    record.os_version = optional(record.os_version);
+    record.push_environment = optional(record.push_environment)
+        .map(|value| {
+            PushEnvironment::parse(&value)
+                .map(|environment| environment.as_str().to_string())
+                .ok_or_else(|| format!("push_environment must be production or sandbox, got '{}'", value))
+        })
+        .transpose()?;
+
+    if record.user_id.is_nil() {
+        return Err("user_id must not be the nil UUID".to_string());
+    }
+    if record.fcm_token.is_empty() || record.device_type.is_empty() {
+        return Err("fcm_token and device_type are required".to_string());
+    }
+    if record.fcm_token.len() > MAX_TOKEN_LEN || record.fcm_token.contains(char::is_whitespace) {
+        return Err(format!("fcm_token must be at most {} characters without whitespace", MAX_TOKEN_LEN));
+    }
+    let versions = [&record.app_version, &record.os_version];
+    if versions.iter().any(|v| v.as_ref().is_some_and(|v| v.len() > MAX_VERSION_LEN)) {
+        return Err(format!("Versions are limited to {} characters", MAX_VERSION_LEN));
+    }
+    Ok(record)
+}
+
+/// One device object per line; lines without a tenant_id get `default_tenant`
+fn parse_ndjson(body: &str, default_tenant: &str) -> ParsedLines {
+    body.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let record = serde_json::from_str::<Value>(line)
+                .map_err(|e| format!("Malformed JSON: {}", e))
+                .and_then(|mut value| {
+                    if let Some(object) = value.as_object_mut() {
+                        object.entry("tenant_id").or_insert_with(|| Value::String(default_tenant.to_string()));
+                    }
+                    serde_json::from_value::<DeviceRecord>(value).map_err(|e| format!("Invalid device: {}", e))
+                });
+            (i + 1, record)
+        })
+        .collect()
+}
+
+/// Header row plus one device per line
+fn parse_csv(body: &str, default_tenant: &str) -> Result<ParsedLines, ApiError> {
+    let mut lines = body.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Err(ApiError::BadRequest("CSV has no header row".to_string()));
+    };
+    let columns: Vec<String> = split_csv_line(header)
+        .map_err(|e| ApiError::BadRequest(format!("CSV header: {}", e)))?
+        .iter()
+        .map(|column| column.trim().to_lowercase())
+        .collect();
+    if let Some(missing) = REQUIRED_COLUMNS.iter().find(|c| !columns.iter().any(|column| column == *c)) {
+        return Err(ApiError::BadRequest(format!("CSV header is missing the '{}' column", missing)));
+    }
+
+    Ok(lines
+        .map(|(i, line)| {
+            let record = split_csv_line(line).and_then(|fields| {
+                if fields.len() != columns.len() {
+                    return Err(format!("Expected {} fields, got {}", columns.len(), fields.len()));
+                }
+                let field = |name: &str| {
+                    columns
+                        .iter()
+                        .position(|column| column == name)
+                        .map(|i| fields[i].trim().to_string())
+                        .filter(|value| !value.is_empty())
+                };
+                Ok(DeviceRecord {
+                    tenant_id: field("tenant_id").unwrap_or_else(|| default_tenant.to_string()),
+                    user_id: field("user_id")
+                        .unwrap_or_default()
+                        .parse()
+                        .map_err(|_| "user_id must be a UUID".to_string())?,
+                    fcm_token: field("fcm_token").unwrap_or_default(),
+                    device_type: field("device_type").unwrap_or_default(),
+                    locale: field("locale"),
+                    app_version: field("app_version"),
+                    os_version: field("os_version"),
+                    push_environment: field("push_environment"),
+                    last_seen_at: None,
+                    created_at: None,
+                })
+            });
+            (i + 1, record)
+        })
+        .collect())
+}
+
+/// Split one CSV line; fields may be quoted with `"` (doubled inside), no line breaks in fields
+fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("Unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// Quote a CSV field when it needs it
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,214 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState, ErrorCode};
+use crate::config::PushEnvironment;
+use crate::db::devices::{Device, DevicePreferences, DeviceRegistration};
+use crate::db::DeviceQueries;
+use crate::models::{DeviceCapabilities, MIN_DEVICE_PAYLOAD_BYTES};
+use crate::push::fcm::MAX_PAYLOAD_BYTES;
+use axum::extract::State;
+use axum::Json;
+use chrono::NaiveTime;
+use chrono_tz::Tz;
+use serde::Deserialize;
+use tracing::info;
+
+/// Longest app/OS version string accepted
+pub const MAX_VERSION_LEN: usize = 64;
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterDeviceRequest {
+    pub fcm_token: String,
+    /// e.g. "android", "ios", "web"
+    pub device_type: String,
+    /// BCP 47, preferred over the user setting for push
+    pub locale: Option<String>,
+    /// Dotted, e.g. "3.1.4" (targeting compares the leading numbers)
+    pub app_version: Option<String>,
+    pub os_version: Option<String>,
+    /// "production" or "sandbox" (dev builds); absent keeps the stored one
+    pub push_environment: Option<String>,
+    /// What this app version handles; absent keeps the stored ones
+    pub capabilities: Option<DeviceCapabilities>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DevicePreferencesRequest {
+    pub fcm_token: String,
+    /// Absent or null = push at any time
+    pub quiet_hours: Option<QuietHours>,
+    /// Types this device gets pushes for; absent or null = all
+    pub enabled_types: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuietHours {
+    /// Local `HH:MM`; may be after `end` (wraps midnight, e.g. 22:00-07:00)
+    pub start: String,
+    pub end: String,
+    /// IANA zone, e.g. "Europe/Amsterdam" (default UTC)
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenRefreshRequest {
+    pub old_token: String,
+    pub new_token: String,
+}
+
+/// GET /api/v1/devices
+pub async fn list_devices(State(state): State<ApiState>, user: AuthUser) -> Result<Json<Vec<Device>>, ApiError> {
+    let devices = DeviceQueries::list(&state.pool, &user.tenant_id, user.user_id).await?;
+    Ok(Json(devices))
+}
+
+/// POST /api/v1/devices
+///
+/// Apps call this at startup: registers the token or refreshes its metadata and
+/// `last_seen_at`. Omitted metadata keeps the stored value.
+pub async fn register_device(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Json(request): Json<RegisterDeviceRequest>,
+) -> Result<Json<Device>, ApiError> {
+    let optional = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let registration = DeviceRegistration {
+        fcm_token: request.fcm_token.trim().to_string(),
+        device_type: request.device_type.trim().to_lowercase(),
+        locale: optional(request.locale),
+        app_version: optional(request.app_version),
+        os_version: optional(request.os_version),
+        push_environment: optional(request.push_environment)
+            .map(|value| parse_environment(&value))
+            .transpose()?,
+        capabilities: request.capabilities,
+        region: state.status.region.clone(),
+    };
+    if registration.fcm_token.is_empty() || registration.device_type.is_empty() {
+        return Err(ApiError::BadRequest("fcm_token and device_type are required".to_string()));
+    }
+    let versions = [&registration.app_version, &registration.os_version];
+    if versions.iter().any(|v| v.as_ref().is_some_and(|v| v.len() > MAX_VERSION_LEN)) {
+        return Err(ApiError::BadRequest(format!("Versions are limited to {} characters", MAX_VERSION_LEN)));
+    }
+    let max_payload = registration.capabilities.and_then(|c| c.max_payload_bytes);
+    if max_payload.is_some_and(|bytes| !(MIN_DEVICE_PAYLOAD_BYTES..=MAX_PAYLOAD_BYTES as u32).contains(&bytes)) {
+        return Err(ApiError::BadRequest(format!(
+            "capabilities.max_payload_bytes must be {}-{}",
+            MIN_DEVICE_PAYLOAD_BYTES, MAX_PAYLOAD_BYTES
+        )));
+    }
+
+    let (device, previous) = DeviceQueries::register(&state.pool, &user.tenant_id, user.user_id, &registration).await?;
+    if let Some(cache) = &state.device_cache {
+        cache.invalidate(&user.tenant_id, user.user_id).await;
+        if let Some((tenant_id, user_id)) = &previous {
+            cache.invalidate(tenant_id, *user_id).await;
+        }
+    }
+
+    info!(
+        user_id = %user.user_id,
+        device_type = %device.device_type,
+        app_version = ?device.app_version,
+        push_environment = ?device.push_environment,
+        capabilities = ?device.capabilities.as_deref(),
+        new = previous.is_none(),
+        "Device registered"
+    );
+    Ok(Json(device))
+}
+
+/// PUT /api/v1/devices/preferences
+///
+/// Replaces the device's preferences
+pub async fn set_device_preferences(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Json(request): Json<DevicePreferencesRequest>,
+) -> Result<Json<Device>, ApiError> {
+    let mut preferences = DevicePreferences::default();
+    if let Some(quiet) = &request.quiet_hours {
+        preferences.quiet_hours_start = Some(parse_time("quiet_hours.start", &quiet.start)?);
+        preferences.quiet_hours_end = Some(parse_time("quiet_hours.end", &quiet.end)?);
+        if preferences.quiet_hours_start == preferences.quiet_hours_end {
+            return Err(ApiError::BadRequest("quiet_hours.start and end must differ".to_string()));
+        }
+        if let Some(zone) = &quiet.timezone {
+            zone.parse::<Tz>()
+                .map_err(|_| ErrorCode::InvalidTimezone.with("timezone", zone))?;
+            preferences.quiet_hours_timezone = Some(zone.clone());
+        }
+    }
+    if let Some(types) = &request.enabled_types {
+        let types: Vec<String> = types.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+        preferences.enabled_types = Some(types);
+    }
+
+    let device = DeviceQueries::update_preferences(
+        &state.pool,
+        &user.tenant_id,
+        user.user_id,
+        &request.fcm_token,
+        &preferences,
+    )
+    .await?
+    .ok_or(ErrorCode::DeviceNotFound)?;
+    if let Some(cache) = &state.device_cache {
+        cache.invalidate(&user.tenant_id, user.user_id).await;
+    }
+
+    info!(
+        user_id = %user.user_id,
+        device_type = %device.device_type,
+        quiet_hours = request.quiet_hours.is_some(),
+        enabled_types = ?device.enabled_types,
+        "Device preferences updated"
+    );
+    Ok(Json(device))
+}
+
+/// POST /api/v1/devices/token-refresh
+///
+/// For the app's `onNewToken` callback: the device keeps its preferences under the new
+/// token instead of a second row piling up next to a stale one. Retries are harmless.
+pub async fn refresh_token(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Json(request): Json<TokenRefreshRequest>,
+) -> Result<Json<Device>, ApiError> {
+    let (old_token, new_token) = (request.old_token.trim(), request.new_token.trim());
+    if old_token.is_empty() || new_token.is_empty() {
+        return Err(ApiError::BadRequest("old_token and new_token are required".to_string()));
+    }
+    if old_token == new_token {
+        return Err(ApiError::BadRequest("new_token equals old_token".to_string()));
+    }
+
+    let device = DeviceQueries::replace_token(&state.pool, &user.tenant_id, user.user_id, old_token, new_token)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(db) if db.is_unique_violation() => {
+                ApiError::from(ErrorCode::TokenRegisteredElsewhere)
+            }
+            _ => ApiError::from(e),
+        })?
+        .ok_or(ErrorCode::DeviceNotFound)?;
+
+    // The worker must not push to the old token for another TTL
+    if let Some(cache) = &state.device_cache {
+        cache.invalidate(&user.tenant_id, user.user_id).await;
+    }
+    info!(user_id = %user.user_id, device_type = %device.device_type, "Device token refreshed");
+    Ok(Json(device))
+}
+
+fn parse_time(field: &str, value: &str) -> Result<NaiveTime, ApiError> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .map_err(|_| ErrorCode::InvalidTime.with("field", field).with("value", value).into())
+}
+
+fn parse_environment(value: &str) -> Result<String, ApiError> {
+    PushEnvironment::parse(value)
+        .map(|environment| environment.as_str().to_string())
+        .ok_or_else(|| ApiError::BadRequest(format!("push_environment must be production or sandbox, got '{}'", value)))
+}
@@ -0,0 +1,65 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState, ErrorCode};
+use crate::db::digests::DigestSubscription;
+use crate::db::DigestQueries;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use tracing::info;
+
+/// Longest address RFC 5321 allows
+const MAX_EMAIL_LEN: usize = 254;
+
+#[derive(Debug, Deserialize)]
+pub struct DigestRequest {
+    pub email: String,
+}
+
+/// GET /api/v1/digest
+pub async fn get_digest(
+    State(state): State<ApiState>,
+    user: AuthUser,
+) -> Result<Json<DigestSubscription>, ApiError> {
+    DigestQueries::get(&state.pool, &user.tenant_id, user.user_id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ErrorCode::DigestNotSubscribed.into())
+}
+
+/// PUT /api/v1/digest - opt in (or change the address)
+pub async fn subscribe(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Json(request): Json<DigestRequest>,
+) -> Result<Json<DigestSubscription>, ApiError> {
+    let email = request.email.trim();
+    validate_email(email).map_err(ApiError::BadRequest)?;
+
+    let subscription = DigestQueries::subscribe(&state.pool, &user.tenant_id, user.user_id, email).await?;
+
+    info!(user_id = %user.user_id, "Email digest subscribed");
+    Ok(Json(subscription))
+}
+
+/// DELETE /api/v1/digest - opt out
+pub async fn unsubscribe(State(state): State<ApiState>, user: AuthUser) -> Result<StatusCode, ApiError> {
+    if DigestQueries::unsubscribe(&state.pool, &user.tenant_id, user.user_id).await? {
+        info!(user_id = %user.user_id, "Email digest unsubscribed");
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Shape check only: the relay is the one that knows whether the address exists
+fn validate_email(email: &str) -> Result<(), String> {
+    let valid = email.len() <= MAX_EMAIL_LEN
+        && !email.chars().any(|c| c.is_whitespace() || c.is_control())
+        && email
+            .rsplit_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.') && !domain.starts_with('.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid email address '{}'", email))
+    }
+}
@@ -0,0 +1,71 @@
+use super::auth::{AuthUser, ReadOnlyAuth};
+use super::{ApiError, ApiState};
+use crate::db::engagement::EngagementSummary;
+use crate::db::EngagementQueries;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::Redirect;
+use axum::Json;
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Default window of `GET /api/v1/engagement`
+const DEFAULT_SUMMARY_DAYS: i64 = 7;
+
+#[derive(Debug, Deserialize)]
+pub struct EngagementQuery {
+    /// Notifications created since (default: 7 days ago)
+    pub since: Option<DateTime<Utc>>,
+    pub tenant_id: Option<String>,
+}
+
+/// POST /api/v1/notifications/{id}/opened
+///
+/// Reported by the client when the user opens the notification; repeated opens are
+/// stored as separate events.
+pub async fn opened(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if !EngagementQueries::record_open(&state.pool, id, user.user_id, &user.tenant_id).await? {
+        return Err(ApiError::NotFound(format!("Notification {} not found", id)));
+    }
+
+    debug!(id = %id, user_id = %user.user_id, "Notification opened");
+    metrics::counter!("notifications_engagement_total", "event" => "opened").increment(1);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/notifications/{id}/click
+///
+/// Click-through link for the notification's deep_link: records the click and
+/// redirects. No auth, so it works from a browser; the target is always the stored
+/// deep_link, never a caller-supplied URL. A READ_ONLY replica redirects without recording.
+pub async fn click(State(state): State<ApiState>, Path(id): Path<Uuid>) -> Result<Redirect, ApiError> {
+    let deep_link = if state.read_only {
+        EngagementQueries::deep_link(&state.pool, id).await?
+    } else {
+        EngagementQueries::record_click(&state.pool, id).await?
+    }
+    .ok_or_else(|| ApiError::NotFound(format!("Notification {} has no deep link", id)))?;
+
+    if !state.read_only {
+        debug!(id = %id, "Notification clicked");
+        metrics::counter!("notifications_engagement_total", "event" => "clicked").increment(1);
+    }
+    Ok(Redirect::to(&deep_link))
+}
+
+/// GET /api/v1/engagement?since=&tenant_id=
+pub async fn summary(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Query(query): Query<EngagementQuery>,
+) -> Result<Json<Vec<EngagementSummary>>, ApiError> {
+    let since = query.since.unwrap_or_else(|| Utc::now() - Duration::days(DEFAULT_SUMMARY_DAYS));
+    let rows = EngagementQueries::summary(&state.pool, since, query.tenant_id.as_deref()).await?;
+    Ok(Json(rows))
+}
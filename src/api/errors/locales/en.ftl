@@ -0,0 +1,29 @@
+# API error messages - English (ids are the `code` of the error body)
+
+unauthorized = Authentication required
+forbidden = Not allowed
+not_found = Not found
+invalid_request = Invalid request
+rate_limited = Rate limit of { $limit }/minute exceeded, retry in { $retry_after }s
+limit_exceeded = { $field } is over the limit of { $limit } ({ $actual })
+internal_error = Internal server error
+
+read_only = This instance is read-only, retry against the primary region
+ingestion_closed = This environment is being drained and accepts no new notifications
+queue_overloaded = The delivery queue is backed up and only accepts { $min_priority } priority and up, retry in { $retry_after }s
+
+missing_token = Missing bearer token
+invalid_token = Invalid token
+user_api_disabled = User API not configured
+notification_not_found = Notification { $id } not found
+device_not_found = Device not found
+digest_not_subscribed = Not subscribed to the email digest
+unknown_action = Notification { $id } has no action '{ $action }'
+action_takes_no_input = Action '{ $action }' takes no input
+action_needs_input = Action '{ $action }' needs input
+action_input_too_long = input is longer than { $max } characters
+invalid_timezone = Unknown timezone '{ $timezone }'
+invalid_time = { $field } must be HH:MM, got '{ $value }'
+token_registered_elsewhere = new_token is registered to another user
+invalid_cursor = since must be a cursor or an RFC 3339 timestamp
+limit_out_of_range = limit must be between { $min } and { $max }
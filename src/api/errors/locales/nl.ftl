@@ -0,0 +1,29 @@
+# API foutmeldingen - Nederlands (ids zijn de `code` van de error body)
+
+unauthorized = Authenticatie vereist
+forbidden = Niet toegestaan
+not_found = Niet gevonden
+invalid_request = Ongeldig verzoek
+rate_limited = Limiet van { $limit } per minuut overschreden, probeer het over { $retry_after } s opnieuw
+limit_exceeded = { $field } is groter dan de limiet van { $limit } ({ $actual })
+internal_error = Interne serverfout
+
+read_only = Deze instance is alleen-lezen, probeer het opnieuw in de primaire regio
+ingestion_closed = Deze omgeving wordt leeggemaakt en neemt geen nieuwe notificaties meer aan
+queue_overloaded = De afleverwachtrij loopt achter en neemt alleen prioriteit { $min_priority } en hoger aan, probeer het over { $retry_after } s opnieuw
+
+missing_token = Bearer token ontbreekt
+invalid_token = Ongeldig token
+user_api_disabled = Gebruikers-API is niet geconfigureerd
+notification_not_found = Notificatie { $id } niet gevonden
+device_not_found = Apparaat niet gevonden
+digest_not_subscribed = Niet aangemeld voor de e-maildigest
+unknown_action = Notificatie { $id } heeft geen actie '{ $action }'
+action_takes_no_input = Actie '{ $action }' accepteert geen invoer
+action_needs_input = Actie '{ $action }' vereist invoer
+action_input_too_long = input is langer dan { $max } tekens
+invalid_timezone = Onbekende tijdzone '{ $timezone }'
+invalid_time = { $field } moet HH:MM zijn, niet '{ $value }'
+token_registered_elsewhere = new_token is al geregistreerd bij een andere gebruiker
+invalid_cursor = since moet een cursor of een RFC 3339-tijdstip zijn
+limit_out_of_range = limit moet tussen { $min } en { $max } liggen
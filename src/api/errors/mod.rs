@@ -0,0 +1,312 @@
+//! Error catalog: stable codes and localized messages for API error bodies.
+//!
+//! Every API error is `{code, message, details}`. `code` is stable and what clients branch
+//! on, `message` is meant for people and `details` holds the specifics as fields (`id`,
+//! `limit`, ...). Errors raised with an [`ErrorCode`] are translated in full from the
+//! Fluent catalogs in `locales/`. The free-form `ApiError` variants get the generic message
+//! of their code in other languages, with the English text kept as `details.reason`.
+//! [`localize`] picks the language from Accept-Language (on `/api/v1`); without a match,
+//! and on other routes, errors are in English.
+//!
+//! Bodies that predate the catalog keep their old top-level fields next to these
+//! (the 422 of the create API still has `error`, `field`, `limit`, `actual`, `index`).
+
+use axum::extract::Request;
+use axum::http::header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE, CONTENT_LENGTH};
+use axum::http::{HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use tracing::{error, warn};
+use unic_langid::LanguageIdentifier;
+
+/// Language of the untranslated free-form messages
+const DEFAULT_LOCALE: &str = "en";
+
+/// Embedded catalogs: (language, source)
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("locales/en.ftl")),
+    ("nl", include_str!("locales/nl.ftl")),
+];
+
+static CATALOG: LazyLock<ErrorCatalog> = LazyLock::new(ErrorCatalog::new);
+
+/// Error body returned by all API endpoints
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    pub message: String,
+    pub details: Map<String, Value>,
+    /// Top-level fields of the body's shape before the catalog, kept for older clients
+    #[serde(flatten)]
+    pub compat: Map<String, Value>,
+}
+
+/// Stable error codes (`code` in the error body, the message id in the catalogs)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    // One per ApiError variant
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    InvalidRequest,
+    RateLimited,
+    LimitExceeded,
+    InternalError,
+
+    // Instance state
+    ReadOnly,
+    IngestionClosed,
+    QueueOverloaded,
+
+    // User-facing endpoints
+    MissingToken,
+    InvalidToken,
+    UserApiDisabled,
+    NotificationNotFound,
+    DeviceNotFound,
+    DigestNotSubscribed,
+    UnknownAction,
+    ActionTakesNoInput,
+    ActionNeedsInput,
+    ActionInputTooLong,
+    InvalidTimezone,
+    InvalidTime,
+    TokenRegisteredElsewhere,
+    InvalidCursor,
+    LimitOutOfRange,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::InvalidRequest => "invalid_request",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::LimitExceeded => "limit_exceeded",
+            ErrorCode::InternalError => "internal_error",
+            ErrorCode::ReadOnly => "read_only",
+            ErrorCode::IngestionClosed => "ingestion_closed",
+            ErrorCode::QueueOverloaded => "queue_overloaded",
+            ErrorCode::MissingToken => "missing_token",
+            ErrorCode::InvalidToken => "invalid_token",
+            ErrorCode::UserApiDisabled => "user_api_disabled",
+            ErrorCode::NotificationNotFound => "notification_not_found",
+            ErrorCode::DeviceNotFound => "device_not_found",
+            ErrorCode::DigestNotSubscribed => "digest_not_subscribed",
+            ErrorCode::UnknownAction => "unknown_action",
+            ErrorCode::ActionTakesNoInput => "action_takes_no_input",
+            ErrorCode::ActionNeedsInput => "action_needs_input",
+            ErrorCode::ActionInputTooLong => "action_input_too_long",
+            ErrorCode::InvalidTimezone => "invalid_timezone",
+            ErrorCode::InvalidTime => "invalid_time",
+            ErrorCode::TokenRegisteredElsewhere => "token_registered_elsewhere",
+            ErrorCode::InvalidCursor => "invalid_cursor",
+            ErrorCode::LimitOutOfRange => "limit_out_of_range",
+        }
+    }
+
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::Unauthorized | ErrorCode::MissingToken | ErrorCode::InvalidToken => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden | ErrorCode::UserApiDisabled => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound
+            | ErrorCode::NotificationNotFound
+            | ErrorCode::DeviceNotFound
+            | ErrorCode::DigestNotSubscribed => StatusCode::NOT_FOUND,
+            ErrorCode::RateLimited | ErrorCode::QueueOverloaded => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::LimitExceeded => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::ReadOnly | ErrorCode::IngestionClosed => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::InvalidRequest
+            | ErrorCode::UnknownAction
+            | ErrorCode::ActionTakesNoInput
+            | ErrorCode::ActionNeedsInput
+            | ErrorCode::ActionInputTooLong
+            | ErrorCode::InvalidTimezone
+            | ErrorCode::InvalidTime
+            | ErrorCode::TokenRegisteredElsewhere
+            | ErrorCode::InvalidCursor
+            | ErrorCode::LimitOutOfRange => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// The catalog message says nothing specific (free-form variants keep their text as `reason`)
+    fn is_generic(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::Unauthorized | ErrorCode::Forbidden | ErrorCode::NotFound | ErrorCode::InvalidRequest
+        )
+    }
+
+    /// This code with a detail (also a message argument)
+    pub fn with(self, name: &str, value: impl Serialize) -> CatalogError {
+        CatalogError::from(self).with(name, value)
+    }
+}
+
+/// An error from the catalog: code plus details
+#[derive(Debug, Clone)]
+pub struct CatalogError {
+    pub code: ErrorCode,
+    pub details: Map<String, Value>,
+}
+
+impl CatalogError {
+    pub fn with(mut self, name: &str, value: impl Serialize) -> Self {
+        self.details.insert(name.to_string(), serde_json::to_value(value).unwrap_or(Value::Null));
+        self
+    }
+}
+
+impl From<ErrorCode> for CatalogError {
+    fn from(code: ErrorCode) -> Self {
+        Self { code, details: Map::new() }
+    }
+}
+
+/// What an error response was built from, kept as a response extension for [`localize`]
+#[derive(Debug, Clone)]
+pub(super) struct ErrorInfo {
+    pub code: ErrorCode,
+    pub details: Map<String, Value>,
+    /// English message of a free-form error (None = render the catalog's)
+    pub text: Option<String>,
+    /// See [`ErrorResponse::compat`]; rendered as is in every language
+    pub compat: Map<String, Value>,
+}
+
+impl ErrorInfo {
+    /// The body in `locale` (a catalog language)
+    fn render(&self, locale: &str) -> ErrorResponse {
+        let translated = locale != DEFAULT_LOCALE;
+        let message = match &self.text {
+            Some(text) if !translated => text.clone(),
+            text => CATALOG
+                .format(locale, self.code, &self.details)
+                .or_else(|| text.clone())
+                .unwrap_or_else(|| self.code.as_str().to_string()),
+        };
+        let mut details = self.details.clone();
+        if let Some(text) = self.text.as_ref().filter(|_| translated && self.code.is_generic()) {
+            details.insert("reason".to_string(), Value::String(text.clone()));
+        }
+        ErrorResponse { code: self.code, message, details, compat: self.compat.clone() }
+    }
+
+    /// English response carrying this info for [`localize`]
+    pub fn respond(self, status: StatusCode) -> Response {
+        let mut response = (status, Json(self.render(DEFAULT_LOCALE))).into_response();
+        response.extensions_mut().insert(self);
+        response
+    }
+}
+
+/// Middleware: re-render error bodies in the best Accept-Language match
+pub async fn localize(request: Request, next: Next) -> Response {
+    let locale = request
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .map(negotiate)
+        .unwrap_or(DEFAULT_LOCALE);
+    let mut response = next.run(request).await;
+    let Some(info) = response.extensions_mut().remove::<ErrorInfo>() else {
+        return response;
+    };
+    response.headers_mut().insert(CONTENT_LANGUAGE, HeaderValue::from_static(locale));
+    if locale == DEFAULT_LOCALE {
+        return response;
+    }
+
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(CONTENT_LENGTH);
+    let body = Json(info.render(locale)).into_response().into_body();
+    Response::from_parts(parts, body)
+}
+
+/// Best catalog language for an Accept-Language header (`nl-NL,nl;q=0.9,en;q=0.8`)
+fn negotiate(accept_language: &str) -> &'static str {
+    let mut ranges: Vec<(&str, f32)> = accept_language
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.trim().parse().ok())
+                .unwrap_or(1.0);
+            (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+        })
+        .collect();
+    // Stable: equal weights keep the client's order
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    ranges
+        .into_iter()
+        .find_map(|(tag, _)| {
+            let language = tag.split(['-', '_']).next()?.to_lowercase();
+            CATALOGS.iter().map(|(lang, _)| *lang).find(|lang| *lang == language)
+        })
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+struct ErrorCatalog {
+    bundles: HashMap<&'static str, FluentBundle<FluentResource>>,
+}
+
+impl ErrorCatalog {
+    fn new() -> Self {
+        let mut bundles = HashMap::new();
+        for (lang, source) in CATALOGS {
+            let resource = match FluentResource::try_new(source.to_string()) {
+                Ok(resource) => resource,
+                Err((resource, errors)) => {
+                    error!(locale = lang, errors = ?errors, "Error catalog has syntax errors");
+                    resource
+                }
+            };
+            let langid: LanguageIdentifier = lang.parse().expect("valid built-in locale");
+            let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+            bundle.set_use_isolating(false);
+            if let Err(errors) = bundle.add_resource(resource) {
+                error!(locale = lang, errors = ?errors, "Failed to add error catalog");
+            }
+            bundles.insert(*lang, bundle);
+        }
+        Self { bundles }
+    }
+
+    fn format(&self, locale: &str, code: ErrorCode, details: &Map<String, Value>) -> Option<String> {
+        let bundle = self.bundles.get(locale)?;
+        let pattern = bundle.get_message(code.as_str())?.value()?;
+
+        let mut args = FluentArgs::new();
+        for (name, value) in details {
+            let value: FluentValue<'static> = match value {
+                Value::Number(n) => n.as_f64().map(FluentValue::from).unwrap_or(FluentValue::None),
+                Value::String(s) => FluentValue::from(s.clone()),
+                Value::Null => FluentValue::None,
+                other => FluentValue::from(other.to_string()),
+            };
+            args.set(name.clone(), value);
+        }
+
+        let mut errors = Vec::new();
+        let message = bundle.format_pattern(pattern, Some(&args), &mut errors).into_owned();
+        if !errors.is_empty() {
+            warn!(code = code.as_str(), locale, errors = ?errors, "Error message rendering failed");
+            return None;
+        }
+        Some(message)
+    }
+}
@@ -0,0 +1,195 @@
+use super::audit;
+use super::auth::{OperatorAuth, ReadOnlyAuth};
+use super::{ApiError, ApiState};
+use crate::db::experiments::{Experiment, NewExperiment, Variant};
+use crate::db::{CampaignQueries, ExperimentQueries};
+use crate::templates::TemplateRenderer;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use tracing::info;
+use uuid::Uuid;
+
+const MAX_VARIANTS: usize = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateExperimentRequest {
+    pub name: String,
+    /// Target: notifications rendered from this template...
+    pub template_key: Option<String>,
+    /// ... or the rows of this campaign
+    pub campaign_id: Option<Uuid>,
+    pub variants: Vec<Variant>,
+}
+
+impl CreateExperimentRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name is required".to_string());
+        }
+        if self.template_key.is_some() == self.campaign_id.is_some() {
+            return Err("set exactly one of template_key and campaign_id".to_string());
+        }
+        if !(2..=MAX_VARIANTS).contains(&self.variants.len()) {
+            return Err(format!("an experiment needs 2 to {} variants", MAX_VARIANTS));
+        }
+
+        let mut names = HashSet::new();
+        for variant in &self.variants {
+            if variant.name.trim().is_empty() {
+                return Err("variant name is required".to_string());
+            }
+            if !names.insert(variant.name.as_str()) {
+                return Err(format!("duplicate variant '{}'", variant.name));
+            }
+            TemplateRenderer::validate(
+                variant.title.as_deref().unwrap_or_default(),
+                variant.body.as_deref().unwrap_or_default(),
+            )
+            .map_err(|e| format!("variant '{}': {}", variant.name, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Open rate per variant: opened / delivered notifications
+#[derive(Debug, Serialize)]
+pub struct VariantResult {
+    pub variant: String,
+    pub delivered: i64,
+    pub opened: i64,
+    pub clicked: i64,
+    pub open_rate: f64,
+    pub click_rate: f64,
+}
+
+/// Experiment with its per-variant results
+#[derive(Debug, Serialize)]
+pub struct ExperimentDetail {
+    #[serde(flatten)]
+    pub experiment: Experiment,
+    pub results: Vec<VariantResult>,
+}
+
+/// GET /api/v1/experiments
+pub async fn list_experiments(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+) -> Result<Json<Vec<Experiment>>, ApiError> {
+    Ok(Json(ExperimentQueries::list(&state.pool).await?))
+}
+
+/// GET /api/v1/experiments/{id}
+///
+/// Every variant is listed, including those without deliveries yet.
+pub async fn get_experiment(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ExperimentDetail>, ApiError> {
+    let experiment = ExperimentQueries::find(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Experiment {} not found", id)))?;
+    let stats = ExperimentQueries::stats(&state.pool, id).await?;
+
+    let results = experiment
+        .variants
+        .0
+        .iter()
+        .map(|variant| {
+            let row = stats.iter().find(|row| row.variant == variant.name);
+            let delivered = row.map_or(0, |row| row.delivered);
+            let opened = row.map_or(0, |row| row.opened);
+            let clicked = row.map_or(0, |row| row.clicked);
+            VariantResult {
+                variant: variant.name.clone(),
+                delivered,
+                opened,
+                clicked,
+                open_rate: rate(opened, delivered),
+                click_rate: rate(clicked, delivered),
+            }
+        })
+        .collect();
+
+    Ok(Json(ExperimentDetail { experiment, results }))
+}
+
+/// POST /api/v1/experiments
+///
+/// Starts immediately; one running experiment per template or campaign.
+pub async fn create_experiment(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Json(request): Json<CreateExperimentRequest>,
+) -> Result<(StatusCode, Json<Experiment>), ApiError> {
+    request.validate().map_err(ApiError::BadRequest)?;
+
+    if let Some(campaign_id) = request.campaign_id {
+        if CampaignQueries::find(&state.pool, campaign_id).await?.is_none() {
+            return Err(ApiError::BadRequest(format!("Unknown campaign {}", campaign_id)));
+        }
+    }
+    if let Some(running) =
+        ExperimentQueries::find_active(&state.pool, request.template_key.as_deref(), request.campaign_id).await?
+    {
+        return Err(ApiError::BadRequest(format!(
+            "Experiment '{}' ({}) is already running for this target; stop it first",
+            running.name, running.id
+        )));
+    }
+
+    let experiment = NewExperiment {
+        name: request.name,
+        template_key: request.template_key,
+        campaign_id: request.campaign_id,
+        variants: request.variants,
+    };
+    let created = ExperimentQueries::create(&state.pool, &experiment, caller.actor()).await?;
+
+    info!(id = %created.id, name = %created.name, variants = created.variants.0.len(), "Experiment started");
+    audit::record(
+        &state.pool,
+        caller.actor(),
+        "experiment.create",
+        json!({
+            "id": created.id,
+            "name": created.name,
+            "template_key": created.template_key,
+            "campaign_id": created.campaign_id,
+            "variants": created.variants,
+        }),
+    )
+    .await;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// POST /api/v1/experiments/{id}/stop
+pub async fn stop_experiment(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Experiment>, ApiError> {
+    let stopped = match ExperimentQueries::stop(&state.pool, id).await? {
+        Some(stopped) => stopped,
+        None if ExperimentQueries::find(&state.pool, id).await?.is_some() => {
+            return Err(ApiError::BadRequest(format!("Experiment {} is already stopped", id)))
+        }
+        None => return Err(ApiError::NotFound(format!("Experiment {} not found", id))),
+    };
+
+    info!(id = %id, "Experiment stopped");
+    audit::record(&state.pool, caller.actor(), "experiment.stop", json!({ "id": id })).await;
+    Ok(Json(stopped))
+}
+
+fn rate(count: i64, delivered: i64) -> f64 {
+    if delivered == 0 {
+        0.0
+    } else {
+        count as f64 / delivered as f64
+    }
+}
@@ -0,0 +1,37 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState};
+use crate::db::ForegroundQueries;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Serialize;
+use tracing::{debug, info};
+
+#[derive(Debug, Serialize)]
+pub struct ForegroundResponse {
+    /// The user's notifications that are due but not delivered yet
+    pub pending: i64,
+    /// The worker was asked to deliver them now
+    pub boosted: bool,
+}
+
+/// POST /api/v1/foreground
+///
+/// Called by the app when it comes to the foreground. Rows that became due without a NOTIFY
+/// (a deferral that ended, an interrupted retry) otherwise wait for the worker's failsafe
+/// poll; this has the worker deliver the user's due notifications right away (FOREGROUND_BOOST).
+pub async fn foreground(
+    State(state): State<ApiState>,
+    user: AuthUser,
+) -> Result<(StatusCode, Json<ForegroundResponse>), ApiError> {
+    let pending = ForegroundQueries::count_due(&state.pool, &user.tenant_id, user.user_id).await?;
+    let boosted = state.foreground_boost && pending > 0;
+    if boosted {
+        ForegroundQueries::notify(&state.pool, &user.tenant_id, user.user_id).await?;
+        info!(user_id = %user.user_id, pending = pending, "App in foreground, boosting pending notifications");
+    } else {
+        debug!(user_id = %user.user_id, pending = pending, "App in foreground, nothing to boost");
+    }
+
+    Ok((StatusCode::ACCEPTED, Json(ForegroundResponse { pending, boosted })))
+}
@@ -0,0 +1,237 @@
+use super::auth::ReadOnlyAuth;
+use super::{ApiError, ApiState};
+use crate::db::attempts::AttemptEvent;
+use crate::db::broadcasts::BroadcastFanout;
+use crate::db::devices::Device;
+use crate::db::explain::{ExplainedNotification, TrailEvent};
+use crate::db::preferences::{ChannelPreference, TypePreference};
+use crate::db::suppressions::SuppressionQueries;
+use crate::db::tenants::DEFAULT_TENANT;
+use crate::db::{AttemptQueries, BroadcastQueries, DeviceQueries, ExplainQueries, NotificationQueries, PreferenceQueries};
+use crate::push::fcm::mask_token;
+use crate::targeting::DeviceFilter;
+use crate::worker::devices::{device_hold, DeviceHold};
+use crate::worker::router::{Route, RouteStep};
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Attempts returned per poll by default / at most
+const DEFAULT_ATTEMPTS: i64 = 100;
+const MAX_ATTEMPTS: i64 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct UserQuery {
+    /// Tenant the user belongs to (default `default`)
+    pub tenant: Option<String>,
+}
+
+/// Everything that decides where a user's notifications go
+#[derive(Debug, Serialize)]
+pub struct UserInspection {
+    pub tenant_id: String,
+    pub user_id: Uuid,
+    pub devices: Vec<Device>,
+    /// Explicit type toggles (anything missing = enabled)
+    pub types: Vec<TypePreference>,
+    pub channels: Vec<ChannelPreference>,
+    pub timezone: Option<String>,
+    pub snoozed_until: Option<DateTime<Utc>>,
+}
+
+/// Progress of a per-user broadcast fan-out
+#[derive(Debug, Serialize)]
+pub struct BroadcastProgress {
+    #[serde(flatten)]
+    pub fanout: BroadcastFanout,
+    pub percent_complete: f64,
+}
+
+/// Why a notification went where it went (`GET /admin/notifications/{id}/explain`)
+#[derive(Debug, Serialize)]
+pub struct Explanation {
+    /// delivered | suppressed | failed | retrying | scheduled | pending
+    pub status: &'static str,
+    #[serde(flatten)]
+    pub notification: ExplainedNotification,
+    /// The recipient is on the suppression list (kind `user`)
+    pub user_suppressed: bool,
+    /// The router's checks against the preferences as they are now (None for broadcasts and topic sends)
+    pub route: Option<RouteExplanation>,
+    /// The recipient's devices and what holds a push back from each right now
+    pub devices: Vec<DeviceExplanation>,
+    /// What happened, oldest first
+    pub timeline: Vec<TrailEvent>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RouteExplanation {
+    /// deliver | suppress | defer
+    pub decision: &'static str,
+    pub channels: Vec<&'static str>,
+    pub reason: Option<&'static str>,
+    pub until: Option<DateTime<Utc>>,
+    pub steps: Vec<RouteStep>,
+}
+
+impl RouteExplanation {
+    pub fn new(route: Route, steps: Vec<RouteStep>) -> Self {
+        match route {
+            Route::Deliver(channels) => Self {
+                decision: "deliver",
+                channels: channels.iter().map(|channel| channel.as_str()).collect(),
+                reason: None,
+                until: None,
+                steps,
+            },
+            Route::Suppress(reason) => Self {
+                decision: "suppress",
+                channels: Vec::new(),
+                reason: Some(reason),
+                until: None,
+                steps,
+            },
+            Route::Defer { until, reason } => Self {
+                decision: "defer",
+                channels: Vec::new(),
+                reason: Some(reason),
+                until: Some(until),
+                steps,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeviceExplanation {
+    /// Masked FCM token
+    pub token: String,
+    pub device_type: String,
+    /// not_targeted | type_disabled | quiet_hours (None = would get the push)
+    pub hold: Option<&'static str>,
+    pub held_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttemptsQuery {
+    /// Last `id` seen; absent = the most recent attempts
+    pub after: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// GET /admin/users/:user_id?tenant=
+///
+/// Support view of one user's devices and preferences (the user endpoints need their JWT).
+pub async fn user(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<UserQuery>,
+) -> Result<Json<UserInspection>, ApiError> {
+    let tenant_id = query.tenant.unwrap_or_else(|| DEFAULT_TENANT.to_string());
+    let devices = DeviceQueries::list(&state.pool, &tenant_id, user_id).await?;
+    let (types, channels) = PreferenceQueries::get_matrix(&state.pool, &tenant_id, user_id).await?;
+    let timezone = PreferenceQueries::get_timezone(&state.pool, &tenant_id, user_id).await?;
+    let snoozed_until = PreferenceQueries::get_active_snooze(&state.pool, &tenant_id, user_id).await?;
+    Ok(Json(UserInspection { tenant_id, user_id, devices, types, channels, timezone, snoozed_until }))
+}
+
+/// GET /admin/attempts?after=&limit=
+///
+/// Delivery attempts in the order they were recorded; poll with the last `id` to tail.
+pub async fn attempts(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Query(query): Query<AttemptsQuery>,
+) -> Result<Json<Vec<AttemptEvent>>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_ATTEMPTS).clamp(1, MAX_ATTEMPTS);
+    Ok(Json(AttemptQueries::list_since(&state.pool, query.after, limit).await?))
+}
+
+/// GET /admin/broadcasts/{id}
+///
+/// Only broadcasts sent with BROADCAST_FANOUT=per_user have progress; topic sends are 404.
+pub async fn broadcast(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Path(id): Path<Uuid>,
+) -> Result<Json<BroadcastProgress>, ApiError> {
+    let fanout = BroadcastQueries::find_fanout(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("No per-user fan-out for broadcast {}", id)))?;
+    Ok(Json(BroadcastProgress { percent_complete: fanout.percent_complete(), fanout }))
+}
+
+/// GET /admin/notifications/{id}/explain
+///
+/// The decision trail support needs for "why didn't I get this push": the row's state,
+/// the timeline of attempts, receipts and engagement, and the route and device holds
+/// re-evaluated against the recipient's current preferences. Routing isn't stored, so
+/// the route is what the worker would decide now, not necessarily what it decided then.
+pub async fn explain(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Explanation>, ApiError> {
+    let notification = ExplainQueries::find(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Notification {} not found", id)))?;
+    let timeline = ExplainQueries::timeline(&state.pool, id).await?;
+    let target = &notification.notification;
+
+    // Broadcasts and topic sends aren't routed per user
+    if target.user_id.is_nil() {
+        return Ok(Json(Explanation {
+            status: notification.status(),
+            notification,
+            user_suppressed: false,
+            route: None,
+            devices: Vec::new(),
+            timeline,
+        }));
+    }
+
+    let user_suppressed =
+        SuppressionQueries::is_suppressed(&state.pool, &target.tenant_id, "user", &target.user_id.to_string()).await?;
+
+    let (route, steps) = state.router.explain(target).await;
+    let route = RouteExplanation::new(route, steps);
+
+    // An unparsable filter matches no device, as in the worker
+    let filter = target.device_filter.as_deref().map(DeviceFilter::parse);
+    let now = Utc::now();
+    let devices = NotificationQueries::get_user_devices(&state.pool, &target.tenant_id, target.user_id)
+        .await?
+        .iter()
+        .map(|device| {
+            let hold = match &filter {
+                Some(Err(_)) => Some(DeviceHold::NotTargeted),
+                Some(Ok(filter)) => device_hold(device, target, Some(filter), now),
+                None => device_hold(device, target, None, now),
+            };
+            let (hold, held_until) = match hold {
+                None => (None, None),
+                Some(DeviceHold::NotTargeted) => (Some("not_targeted"), None),
+                Some(DeviceHold::TypeDisabled) => (Some("type_disabled"), None),
+                Some(DeviceHold::Quiet { until }) => (Some("quiet_hours"), Some(until)),
+            };
+            DeviceExplanation {
+                token: mask_token(&device.fcm_token),
+                device_type: device.device_type.clone(),
+                hold,
+                held_until,
+            }
+        })
+        .collect();
+
+    Ok(Json(Explanation {
+        status: notification.status(),
+        notification,
+        user_suppressed,
+        route: Some(route),
+        devices,
+        timeline,
+    }))
+}
@@ -0,0 +1,59 @@
+use super::audit;
+use super::auth::{OperatorAuth, ReadOnlyAuth};
+use super::{ApiError, ApiState};
+use crate::db::maintenance::MaintenanceMode;
+use crate::db::MaintenanceQueries;
+use axum::extract::State;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, warn};
+
+#[derive(Debug, Deserialize)]
+pub struct SetMaintenanceRequest {
+    pub enabled: bool,
+    /// Shown in GET and the audit log, e.g. "FCM project migration"
+    pub reason: Option<String>,
+}
+
+/// Maintenance flag plus what is waiting for it to end
+#[derive(Debug, Serialize)]
+pub struct MaintenanceStatus {
+    #[serde(flatten)]
+    pub mode: MaintenanceMode,
+    /// Due notifications not delivered yet
+    pub backlog: i64,
+}
+
+/// GET /api/v1/maintenance
+pub async fn get_maintenance(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+) -> Result<Json<MaintenanceStatus>, ApiError> {
+    let mode = MaintenanceQueries::get(&state.pool).await?;
+    let backlog = MaintenanceQueries::backlog(&state.pool).await?;
+    Ok(Json(MaintenanceStatus { mode, backlog }))
+}
+
+/// PUT /api/v1/maintenance
+///
+/// Workers pick the change up at their next batch. Switching off drains the backlog
+/// in priority order at MAINTENANCE_DRAIN_RATE_PER_SEC.
+pub async fn set_maintenance(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Json(request): Json<SetMaintenanceRequest>,
+) -> Result<Json<MaintenanceStatus>, ApiError> {
+    let reason = request.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    let mode = MaintenanceQueries::set(&state.pool, request.enabled, reason, caller.actor()).await?;
+    let backlog = MaintenanceQueries::backlog(&state.pool).await?;
+
+    if mode.enabled {
+        warn!(reason = ?mode.reason, by = %caller.actor(), "Maintenance mode enabled");
+    } else {
+        info!(backlog = backlog, by = %caller.actor(), "Maintenance mode disabled");
+    }
+    let action = if mode.enabled { "maintenance.enable" } else { "maintenance.disable" };
+    audit::record(&state.pool, caller.actor(), action, json!({ "reason": mode.reason })).await;
+    Ok(Json(MaintenanceStatus { mode, backlog }))
+}
@@ -0,0 +1,393 @@
+//! HTTP API (`/api/v1`): user endpoints authenticated with platform JWTs,
+//! management endpoints with the operator ADMIN_TOKEN.
+//! Only mounted when JWT_SECRET or ADMIN_TOKEN is configured.
+
+pub mod acks;
+pub mod actions;
+pub mod analytics;
+pub mod admin_ui;
+pub mod announcements;
+pub mod api_keys;
+pub mod archives;
+pub mod audiences;
+pub mod audit;
+pub mod auth;
+pub mod campaigns;
+pub mod client_errors;
+pub mod debug;
+pub mod dev_sandbox;
+pub mod device_transfer;
+pub mod devices;
+pub mod digest;
+pub mod engagement;
+pub mod errors;
+pub mod maintenance;
+pub mod experiments;
+pub mod foreground;
+pub mod inspect;
+pub mod muted;
+pub mod notifications;
+pub mod payload_schemas;
+pub mod preferences;
+pub mod prestop;
+pub mod receipts;
+pub mod recurring;
+pub mod reengagement;
+pub mod resend;
+pub mod schema;
+pub mod shadow;
+pub mod snooze;
+pub mod stats;
+pub mod status;
+pub mod suppressions;
+pub mod sync;
+pub mod templates;
+pub mod tenants;
+pub mod topics;
+pub mod webhooks;
+
+pub use errors::{CatalogError, ErrorCode, ErrorResponse};
+
+use crate::attachments::AttachmentSigner;
+use crate::dev_sandbox::DevSandbox;
+use crate::error::ValidationError;
+use crate::ingest::backpressure::{Backpressure, Overloaded};
+use crate::ingest::limits::{CreateLimits, LimitExceeded};
+use crate::ingest::rate_limit::QuotaExceeded;
+use crate::ingest::IngestError;
+use crate::ip_allowlist::{self, IpAllowlist};
+use crate::protocol::Protocols;
+use crate::realtime::RealtimeBus;
+use crate::status::ServiceStatus;
+use crate::worker::actions::ActionRelay;
+use crate::worker::devices::DeviceCache;
+use crate::worker::dismiss::Dismisser;
+use crate::worker::read_state::ReadStateFanout;
+use crate::worker::router::ChannelRouter;
+use crate::worker::test_send::TestSender;
+use crate::worker::{BusHealth, ChannelHealth, Drain, Leadership};
+use axum::extract::{DefaultBodyLimit, Request};
+use axum::http::{header, HeaderName, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
+use axum::Router;
+use errors::ErrorInfo;
+use serde_json::Map;
+use sqlx::PgPool;
+use std::sync::Arc;
+
+/// Shared state for API handlers
+#[derive(Clone)]
+pub struct ApiState {
+    pub pool: PgPool,
+    /// HS256 secret for user JWTs (None = user endpoints disabled)
+    pub jwt_secret: Option<Arc<str>>,
+    pub bus_client: Option<Arc<dyn RealtimeBus>>,
+    /// Versions the Bus messages go out in, and what `?protocol=` negotiates to (WS_PROTOCOLS)
+    pub protocols: Protocols,
+    /// Operator token for management endpoints (None = management disabled)
+    pub admin_token: Option<Arc<str>>,
+    /// Source addresses allowed on management endpoints (None = any)
+    pub admin_allowlist: Option<Arc<IpAllowlist>>,
+    /// The worker's device cache (None when DEVICE_CACHE_TTL_SECS=0)
+    pub device_cache: Option<DeviceCache>,
+    /// Dismiss fan-out for acks (None when FIRST_ACK_TYPES is empty)
+    pub dismisser: Option<Arc<Dismisser>>,
+    /// `POST /api/v1/notifications/test`
+    pub test_sender: Arc<TestSender>,
+    /// `read_state_changed` after `POST /api/v1/notifications/read`
+    pub read_state: Arc<ReadStateFanout>,
+    /// Forwards answers of `POST /api/v1/notifications/{id}/action` to the producer
+    pub actions: Arc<ActionRelay>,
+    /// Pod shutdown state (`POST /admin/prestop`)
+    pub drain: Drain,
+    /// Longest wait of `POST /admin/prestop` for in-flight deliveries
+    pub prestop_timeout_secs: u64,
+    /// Bus health probe (None = no Bus or BUS_HEALTH_INTERVAL_SECS=0)
+    pub bus_health: Option<BusHealth>,
+    /// Channel stats of the adaptive route (None = ADAPTIVE_ROUTING_LATENCY_MS=0)
+    pub channel_health: Option<ChannelHealth>,
+    /// Leader lease of the worker (None = LEADER_ELECTION off)
+    pub leadership: Option<Leadership>,
+    /// The worker's routing rules, for `GET /admin/notifications/{id}/explain`
+    pub router: Arc<ChannelRouter>,
+    /// Guardrails of the create endpoints (CREATE_MAX_*)
+    pub limits: CreateLimits,
+    /// Sheds non-urgent creates while the queue is backed up (BACKPRESSURE_*)
+    pub backpressure: Arc<Backpressure>,
+    /// `POST /api/v1/foreground` wakes the worker for the user (FOREGROUND_BOOST)
+    pub foreground_boost: bool,
+    /// What this instance started with, for `GET /admin/status`
+    pub status: Arc<ServiceStatus>,
+    /// READ_ONLY: a DR replica serving reads, writes are refused
+    pub read_only: bool,
+    /// Signs attachment URLs for the inbox (None = ATTACHMENT_STORE_URL not set)
+    pub attachments: Option<Arc<AttachmentSigner>>,
+    /// `/api/v1/sandbox/*` (None = DEV_SANDBOX_TENANT not set)
+    pub dev_sandbox: Option<Arc<DevSandbox>>,
+}
+
+/// Build the `/api/v1` router
+pub fn router(state: ApiState) -> Router {
+    // User endpoints (JWT) stay reachable from anywhere; the rest is management/producer
+    // surface and sits behind the admin IP allowlist when one is configured
+    let user = Router::new()
+        .route("/preferences", get(preferences::get_preferences))
+        .route("/announcements", get(announcements::list_announcements))
+        .route("/devices", get(devices::list_devices).post(devices::register_device))
+        .route("/devices/preferences", put(devices::set_device_preferences))
+        .route("/devices/token-refresh", post(devices::refresh_token))
+        .route("/foreground", post(foreground::foreground))
+        .route("/digest", get(digest::get_digest).put(digest::subscribe).delete(digest::unsubscribe))
+        .route("/notifications/snooze", post(snooze::snooze))
+        .route("/notifications/inbox-snapshot", get(sync::inbox_snapshot))
+        .route("/notifications/sync", get(sync::sync))
+        .route("/notifications/read", post(sync::mark_read))
+        .route("/notifications/:id/ack", post(acks::ack))
+        .route("/notifications/:id/action", post(actions::take_action))
+        .route("/notifications/:id/error", post(client_errors::report))
+        .route("/notifications/:id/opened", post(engagement::opened))
+        .route("/notifications/:id/click", get(engagement::click))
+        .route("/sandbox/devices", post(dev_sandbox::register_device))
+        .route("/sandbox/notifications", post(dev_sandbox::send))
+        .route("/sandbox/notifications/:id", get(dev_sandbox::inspect))
+        .route("/muted-targets", get(muted::list_muted).post(muted::mute))
+        .route("/muted-targets/:target_type/:target_id", delete(muted::unmute))
+        .route("/topics", get(topics::list_topics))
+        .route("/topics/:topic", put(topics::subscribe).delete(topics::unsubscribe));
+
+    let management = Router::new()
+        .route("/api-keys", get(api_keys::list_api_keys).post(api_keys::create_api_key))
+        .route("/api-keys/:id", delete(api_keys::revoke_api_key))
+        .route("/api-keys/:id/rotate", post(api_keys::rotate_api_key))
+        .route("/archives", get(archives::list_archives))
+        .route("/audiences/orgs/:org_id/members", put(audiences::sync_org_members))
+        .route("/audit-log", get(audit::list_audit_log))
+        .route("/engagement", get(engagement::summary))
+        .route("/experiments", get(experiments::list_experiments).post(experiments::create_experiment))
+        .route("/experiments/:id", get(experiments::get_experiment))
+        .route("/experiments/:id/stop", post(experiments::stop_experiment))
+        .route("/maintenance", get(maintenance::get_maintenance).put(maintenance::set_maintenance))
+        .route("/notifications", post(notifications::create_notification))
+        .route("/notifications/batch", post(notifications::create_batch))
+        .route("/notifications/test", post(notifications::test_notification))
+        .route("/payload-schemas", get(payload_schemas::list_schemas))
+        .route(
+            "/payload-schemas/:notification_type",
+            get(payload_schemas::get_schema)
+                .put(payload_schemas::save_schema)
+                .delete(payload_schemas::delete_schema),
+        )
+        .route("/templates", get(templates::list_templates))
+        .route("/templates/:key", get(templates::get_template))
+        .route(
+            "/templates/:key/:locale/:channel",
+            put(templates::save_template).delete(templates::delete_template),
+        )
+        .route(
+            "/templates/:key/:locale/:channel/versions",
+            get(templates::list_versions),
+        )
+        .route(
+            "/templates/:key/:locale/:channel/versions/:version/activate",
+            post(templates::activate_version),
+        )
+        .route("/templates/:key/preview", post(templates::preview_template))
+        .route("/campaigns", get(campaigns::list_campaigns).post(campaigns::create_campaign))
+        .route("/campaigns/:id", get(campaigns::get_campaign))
+        .route("/campaigns/:id/recipients", post(campaigns::upload_recipients))
+        .route("/campaigns/:id/pause", post(campaigns::pause_campaign))
+        .route("/campaigns/:id/resume", post(campaigns::resume_campaign))
+        .route("/campaigns/:id/cancel", post(campaigns::cancel_campaign))
+        .route(
+            "/receipt-webhooks",
+            get(receipts::list_webhooks).post(receipts::create_webhook),
+        )
+        .route("/receipt-webhooks/:id", delete(receipts::delete_webhook))
+        .route(
+            "/recurring-notifications",
+            get(recurring::list_recurring).post(recurring::create_recurring),
+        )
+        .route("/recurring-notifications/:id", delete(recurring::delete_recurring))
+        .route("/recurring-notifications/:id/pause", post(recurring::pause_recurring))
+        .route("/recurring-notifications/:id/resume", post(recurring::resume_recurring))
+        .route("/reengagement-policies", get(reengagement::list_policies))
+        .route("/resend", post(resend::resend))
+        .route(
+            "/suppressions",
+            get(suppressions::list_suppressions).post(suppressions::create_suppression),
+        )
+        .route("/suppressions/:id", delete(suppressions::delete_suppression))
+        .route("/tenants", get(tenants::list_tenants))
+        .route("/tenants/:tenant_id", put(tenants::save_tenant))
+        .route(
+            "/tenants/:tenant_id/reengagement",
+            put(reengagement::save_policy).delete(reengagement::delete_policy),
+        )
+        .route("/webhook-sources", get(webhooks::list_sources))
+        .route(
+            "/webhook-sources/:source",
+            put(webhooks::save_source).delete(webhooks::delete_source),
+        );
+    let management = ip_allowlist::protect(management, state.admin_allowlist.clone());
+
+    let api = user.merge(management);
+    let api = if state.read_only { api.layer(axum::middleware::from_fn(reject_writes)) } else { api };
+    api.layer(axum::middleware::from_fn(errors::localize)).with_state(state)
+}
+
+/// Build the `/admin` router (pod lifecycle, status and the ops UI), behind the admin IP allowlist
+pub fn admin_router(state: ApiState) -> Router {
+    let admin = Router::new()
+        .route("/analytics", get(analytics::analytics))
+        .route("/attempts", get(inspect::attempts))
+        .route("/broadcasts/:id", get(inspect::broadcast))
+        .route(
+            "/devices/import",
+            post(device_transfer::import_devices).layer(DefaultBodyLimit::max(device_transfer::MAX_IMPORT_BYTES)),
+        )
+        .route("/debug/recent", get(debug::recent))
+        .route("/devices/export", get(device_transfer::export_devices))
+        .route("/failures", get(admin_ui::failures))
+        .route("/notifications/:id/explain", get(inspect::explain))
+        .route("/schema-check", get(schema::schema_check))
+        .route("/shadow", get(shadow::shadow))
+        .route("/slo", get(stats::slo))
+        .route("/stats", get(stats::stats))
+        .route("/status", get(status::status))
+        .route("/ui", get(admin_ui::ui))
+        .route("/users/:user_id", get(inspect::user));
+    let admin = if state.read_only { admin.layer(axum::middleware::from_fn(reject_writes)) } else { admin };
+    // Pod lifecycle, so a read-only replica still drains on shutdown
+    let admin = admin.route("/prestop", post(prestop::prestop));
+    ip_allowlist::protect(admin, state.admin_allowlist.clone()).with_state(state)
+}
+
+/// Middleware: refuse anything but reads with 503 `read_only` (READ_ONLY)
+///
+/// The database is a replica, so a write would fail anyway; this fails it before any
+/// handler runs and tells the client to retry against the primary region.
+pub async fn reject_writes(request: Request, next: Next) -> Response {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(request).await;
+    }
+    ApiError::from(ErrorCode::ReadOnly).into_response()
+}
+
+/// API error mapped to an HTTP status
+#[derive(Debug)]
+pub enum ApiError {
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    BadRequest(String),
+    /// 429 with `Retry-After` and `X-RateLimit-*` headers
+    RateLimited(QuotaExceeded),
+    /// 422 with the exceeded limit in `details`
+    LimitExceeded(LimitExceeded),
+    /// 429 with `Retry-After`: the queue is backed up (BACKPRESSURE_*)
+    Overloaded(Overloaded),
+    Internal(String),
+    /// Coded error from the catalog (translated in full, see `errors`)
+    Catalog(CatalogError),
+}
+
+impl From<CatalogError> for ApiError {
+    fn from(e: CatalogError) -> Self {
+        ApiError::Catalog(e)
+    }
+}
+
+impl From<ErrorCode> for ApiError {
+    fn from(code: ErrorCode) -> Self {
+        ApiError::Catalog(code.into())
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        ApiError::Internal(format!("Database error: {}", e))
+    }
+}
+
+impl From<ValidationError> for ApiError {
+    fn from(e: ValidationError) -> Self {
+        ApiError::BadRequest(e.to_string())
+    }
+}
+
+impl From<LimitExceeded> for ApiError {
+    fn from(e: LimitExceeded) -> Self {
+        ApiError::LimitExceeded(e)
+    }
+}
+
+impl From<Overloaded> for ApiError {
+    fn from(e: Overloaded) -> Self {
+        ApiError::Overloaded(e)
+    }
+}
+
+impl From<IngestError> for ApiError {
+    fn from(e: IngestError) -> Self {
+        match e {
+            IngestError::Invalid(reason) => ApiError::BadRequest(reason),
+            IngestError::Database(e) => ApiError::from(e),
+            IngestError::RateLimited(e) => ApiError::RateLimited(e),
+            IngestError::Closed => ApiError::from(ErrorCode::IngestionClosed),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let free_form = |code: ErrorCode, text: String| ErrorInfo { code, details: Map::new(), text: Some(text), compat: Map::new() };
+        let info = match self {
+            ApiError::Unauthorized(e) => free_form(ErrorCode::Unauthorized, e),
+            ApiError::Forbidden(e) => free_form(ErrorCode::Forbidden, e),
+            ApiError::NotFound(e) => free_form(ErrorCode::NotFound, e),
+            ApiError::BadRequest(e) => free_form(ErrorCode::InvalidRequest, e),
+            ApiError::RateLimited(e) => {
+                let reset = e.quota.reset_after.as_secs().max(1);
+                let mut details = Map::new();
+                details.insert("limit".to_string(), e.quota.limit.into());
+                details.insert("retry_after".to_string(), reset.into());
+                let info = ErrorInfo { code: ErrorCode::RateLimited, details, text: Some(e.to_string()), compat: Map::new() };
+                let headers = [
+                    (header::RETRY_AFTER, reset.to_string()),
+                    (HeaderName::from_static("x-ratelimit-limit"), e.quota.limit.to_string()),
+                    (HeaderName::from_static("x-ratelimit-remaining"), e.quota.remaining.to_string()),
+                    (HeaderName::from_static("x-ratelimit-reset"), reset.to_string()),
+                ];
+                return (headers, info.respond(StatusCode::TOO_MANY_REQUESTS)).into_response();
+            }
+            ApiError::Overloaded(e) => {
+                let retry_after = e.retry_after.as_secs().max(1);
+                let mut details = Map::new();
+                details.insert("min_priority".to_string(), e.min_priority.into());
+                details.insert("retry_after".to_string(), retry_after.into());
+                let info = ErrorInfo { code: ErrorCode::QueueOverloaded, details, text: None, compat: Map::new() };
+                let headers = [(header::RETRY_AFTER, retry_after.to_string())];
+                return (headers, info.respond(StatusCode::TOO_MANY_REQUESTS)).into_response();
+            }
+            ApiError::LimitExceeded(e) => {
+                // The original 422 body (`error`, `field`, ...) stays at the top level for older producers
+                let compat = match serde_json::to_value(&e) {
+                    Ok(serde_json::Value::Object(compat)) => compat,
+                    _ => Map::new(),
+                };
+                let mut details = compat.clone();
+                details.remove("error");
+                ErrorInfo { code: ErrorCode::LimitExceeded, details, text: Some(e.error), compat }
+            }
+            ApiError::Internal(e) => {
+                tracing::error!(error = %e, "API request failed");
+                ErrorInfo { code: ErrorCode::InternalError, details: Map::new(), text: None, compat: Map::new() }
+            }
+            ApiError::Catalog(e) => ErrorInfo { code: e.code, details: e.details, text: None, compat: Map::new() },
+        };
+        let status = info.code.status();
+        info.respond(status)
+    }
+}
@@ -0,0 +1,73 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState};
+use crate::db::preferences::MutedTarget;
+use crate::db::PreferenceQueries;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use tracing::info;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct MuteRequest {
+    pub target_type: String,
+    pub target_id: Uuid,
+}
+
+/// GET /api/v1/muted-targets
+pub async fn list_muted(
+    State(state): State<ApiState>,
+    user: AuthUser,
+) -> Result<Json<Vec<MutedTarget>>, ApiError> {
+    let targets = PreferenceQueries::list_muted_targets(&state.pool, &user.tenant_id, user.user_id).await?;
+    Ok(Json(targets))
+}
+
+/// POST /api/v1/muted-targets
+pub async fn mute(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Json(request): Json<MuteRequest>,
+) -> Result<StatusCode, ApiError> {
+    if request.target_type.trim().is_empty() {
+        return Err(ApiError::BadRequest("target_type is required".to_string()));
+    }
+
+    PreferenceQueries::mute_target(
+        &state.pool,
+        &user.tenant_id,
+        user.user_id,
+        &request.target_type,
+        request.target_id,
+    )
+    .await?;
+
+    info!(
+        user_id = %user.user_id,
+        target_type = %request.target_type,
+        target_id = %request.target_id,
+        "Target muted"
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /api/v1/muted-targets/{target_type}/{target_id}
+pub async fn unmute(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Path((target_type, target_id)): Path<(String, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    let removed =
+        PreferenceQueries::unmute_target(&state.pool, &user.tenant_id, user.user_id, &target_type, target_id).await?;
+
+    if removed {
+        info!(
+            user_id = %user.user_id,
+            target_type = %target_type,
+            target_id = %target_id,
+            "Target unmuted"
+        );
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
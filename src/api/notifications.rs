@@ -0,0 +1,163 @@
+use super::auth::ProducerAuth;
+use super::{ApiError, ApiState};
+use crate::config::PushEnvironment;
+use crate::ingest::{ingest, rate_limit};
+use crate::models::NewNotification;
+use crate::worker::test_send::{TestSendReport, TestTarget};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::debug;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct TestNotificationRequest {
+    /// The notification as `POST /api/v1/notifications` takes it (`user_id` = target user)
+    #[serde(flatten)]
+    pub notification: NewNotification,
+    /// Target one device token instead of the user's devices
+    pub fcm_token: Option<String>,
+    /// Environment of `fcm_token` (default PUSH_ENVIRONMENT)
+    pub push_environment: Option<String>,
+    /// Render in this locale instead of the device/user locale
+    pub locale: Option<String>,
+    /// Only return the payloads (default); false sends to the target
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+/// POST /api/v1/notifications
+///
+/// Create API for producers that don't INSERT directly (see `notifications-client`).
+/// Idempotent when the body carries an `id`. The caller's certificate identity or
+/// API key name is recorded as `created_by`.
+pub async fn create_notification(
+    State(state): State<ApiState>,
+    producer: ProducerAuth,
+    Json(mut notification): Json<NewNotification>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    bind_producer(&producer, &mut notification)?;
+    state.limits.check(&notification)?;
+    state.backpressure.check(&state.pool, &notification).await?;
+    check_quota(&producer, 1).await?;
+
+    let id = ingest(&state.pool, &notification).await?;
+
+    debug!(id = %id, user_id = %notification.recipient(), "✓ Notification created via API");
+    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({ "id": id }))))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    pub notifications: Vec<NewNotification>,
+}
+
+/// POST /api/v1/notifications/batch
+///
+/// Every notification is checked (limits, validation, backpressure) and the whole batch
+/// is counted against the API key quota before the first is inserted; a failure names its
+/// `index`. Inserts then run in order and stop at the first error, so give each
+/// notification an `id` and retry the whole batch.
+pub async fn create_batch(
+    State(state): State<ApiState>,
+    producer: ProducerAuth,
+    Json(mut request): Json<BatchRequest>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    if request.notifications.is_empty() {
+        return Err(ApiError::BadRequest("notifications must not be empty".to_string()));
+    }
+    state.limits.check_batch(request.notifications.len())?;
+    for (index, notification) in request.notifications.iter_mut().enumerate() {
+        bind_producer(&producer, notification)?;
+        state.limits.check(notification).map_err(|e| e.at(index))?;
+        notification
+            .validate()
+            .map_err(|e| ApiError::BadRequest(format!("notifications[{}]: {}", index, e)))?;
+        state.backpressure.check(&state.pool, notification).await?;
+    }
+    check_quota(&producer, request.notifications.len() as u32).await?;
+
+    let mut ids = Vec::with_capacity(request.notifications.len());
+    for notification in &request.notifications {
+        ids.push(ingest(&state.pool, notification).await?);
+    }
+
+    debug!(count = ids.len(), "✓ Notification batch created via API");
+    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({ "ids": ids }))))
+}
+
+/// Tenant-bound callers (API keys, client certificates) can only create notifications for their own tenant
+fn bind_producer(producer: &ProducerAuth, notification: &mut NewNotification) -> Result<(), ApiError> {
+    if let Some(bound) = &producer.tenant_id {
+        match &notification.tenant_id {
+            Some(requested) if requested != bound => {
+                return Err(ApiError::Forbidden(format!("Caller is bound to tenant '{}'", bound)));
+            }
+            _ => notification.tenant_id = Some(bound.clone()),
+        }
+    }
+    notification.created_by = producer.service.clone();
+    Ok(())
+}
+
+/// Count `count` notifications against the API key's quota
+async fn check_quota(producer: &ProducerAuth, count: u32) -> Result<(), ApiError> {
+    if let (Some(key_id), Some(limit)) = (producer.api_key_id, producer.rate_limit_per_minute) {
+        rate_limit::check_many("api_key", &format!("api_key:{}", key_id), limit, count)
+            .await
+            .map_err(ApiError::RateLimited)?;
+    }
+    Ok(())
+}
+
+/// POST /api/v1/notifications/test
+///
+/// Renders the notification through the worker's template and FCM request code for one
+/// target: the user (`user_id`, Bus + every device) or a single `fcm_token`. Returns the
+/// payloads; with `"dry_run": false` also sends them. Nothing is stored, and preferences,
+/// quiet hours and snooze don't apply.
+pub async fn test_notification(
+    State(state): State<ApiState>,
+    producer: ProducerAuth,
+    Json(request): Json<TestNotificationRequest>,
+) -> Result<Json<TestSendReport>, ApiError> {
+    let mut notification = request.notification;
+    bind_producer(&producer, &mut notification)?;
+
+    if notification.topic.is_some() || notification.audience.is_some() {
+        return Err(ApiError::BadRequest("Topic and audience notifications can't be test-sent, target a user_id".to_string()));
+    }
+    let fcm_token = request.fcm_token.as_deref().map(str::trim).filter(|token| !token.is_empty());
+    let target = match (fcm_token, notification.user_id) {
+        (Some(fcm_token), _) => {
+            let environment = match request.push_environment.as_deref() {
+                Some(value) => PushEnvironment::parse(value).ok_or_else(|| {
+                    ApiError::BadRequest(format!("push_environment must be production or sandbox, got '{}'", value))
+                })?,
+                None => state.test_sender.default_environment(),
+            };
+            TestTarget::Token { fcm_token: fcm_token.to_string(), environment }
+        }
+        (None, Some(user_id)) if !user_id.is_nil() => TestTarget::User(user_id),
+        _ => return Err(ApiError::BadRequest("user_id or fcm_token is required".to_string())),
+    };
+    // A token target has no user: validate as if it had one
+    notification.user_id.get_or_insert_with(Uuid::nil);
+    notification.validate()?;
+
+    if !request.dry_run {
+        check_quota(&producer, 1).await?;
+    }
+
+    let report = state
+        .test_sender
+        .send(&notification.draft(), &target, request.locale.as_deref(), request.dry_run)
+        .await?;
+    Ok(Json(report))
+}
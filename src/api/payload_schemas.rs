@@ -0,0 +1,86 @@
+use super::audit;
+use super::auth::{OperatorAuth, ReadOnlyAuth};
+use super::{ApiError, ApiState};
+use crate::contracts;
+use crate::db::payload_schemas::PayloadSchema;
+use crate::db::PayloadSchemaQueries;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+pub struct SavePayloadSchemaRequest {
+    /// JSON Schema that `payload` must match
+    pub schema: Value,
+}
+
+/// GET /api/v1/payload-schemas
+pub async fn list_schemas(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+) -> Result<Json<Vec<PayloadSchema>>, ApiError> {
+    Ok(Json(PayloadSchemaQueries::list(&state.pool).await?))
+}
+
+/// GET /api/v1/payload-schemas/{notification_type}
+pub async fn get_schema(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Path(notification_type): Path<String>,
+) -> Result<Json<PayloadSchema>, ApiError> {
+    PayloadSchemaQueries::find(&state.pool, &notification_type)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("No payload schema for '{}'", notification_type)))
+}
+
+/// PUT /api/v1/payload-schemas/{notification_type}
+///
+/// Only applies to notifications created from now on; queued rows are checked again by
+/// the worker against the new schema.
+pub async fn save_schema(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(notification_type): Path<String>,
+    Json(request): Json<SavePayloadSchemaRequest>,
+) -> Result<Json<PayloadSchema>, ApiError> {
+    contracts::compile(&request.schema).map_err(|e| ApiError::BadRequest(format!("Invalid JSON Schema: {}", e)))?;
+
+    let schema = PayloadSchemaQueries::save(&state.pool, &notification_type, &request.schema, caller.actor()).await?;
+    contracts::invalidate(&notification_type).await;
+
+    info!(notification_type = %notification_type, version = schema.version, "Payload schema saved");
+    audit::record(
+        &state.pool,
+        caller.actor(),
+        "payload_schema.save",
+        json!({ "notification_type": notification_type, "version": schema.version }),
+    )
+    .await;
+    Ok(Json(schema))
+}
+
+/// DELETE /api/v1/payload-schemas/{notification_type}
+pub async fn delete_schema(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(notification_type): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !PayloadSchemaQueries::delete(&state.pool, &notification_type).await? {
+        return Err(ApiError::NotFound(format!("No payload schema for '{}'", notification_type)));
+    }
+    contracts::invalidate(&notification_type).await;
+
+    info!(notification_type = %notification_type, "Payload schema removed");
+    audit::record(
+        &state.pool,
+        caller.actor(),
+        "payload_schema.delete",
+        json!({ "notification_type": notification_type }),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,69 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState};
+use crate::db::PreferenceQueries;
+use crate::worker::Channel;
+use axum::extract::State;
+use axum::Json;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Full preference matrix for a client settings screen
+#[derive(Debug, Serialize)]
+pub struct PreferencesResponse {
+    /// Channels a type can be toggled for
+    pub channels: Vec<&'static str>,
+    /// Types the user has explicit preferences for (anything missing = all enabled)
+    pub types: Vec<TypePreferences>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TypePreferences {
+    pub notification_type: String,
+    pub enabled: bool,
+    pub channels: BTreeMap<String, bool>,
+}
+
+/// GET /api/v1/preferences
+pub async fn get_preferences(
+    State(state): State<ApiState>,
+    user: AuthUser,
+) -> Result<Json<PreferencesResponse>, ApiError> {
+    let (type_prefs, channel_prefs) =
+        PreferenceQueries::get_matrix(&state.pool, &user.tenant_id, user.user_id).await?;
+
+    let type_names: BTreeSet<&str> = type_prefs
+        .iter()
+        .map(|p| p.notification_type.as_str())
+        .chain(channel_prefs.iter().map(|p| p.notification_type.as_str()))
+        .collect();
+
+    let types = type_names
+        .into_iter()
+        .map(|name| {
+            let enabled = type_prefs
+                .iter()
+                .find(|p| p.notification_type == name)
+                .map(|p| p.enabled)
+                .unwrap_or(true);
+
+            let mut channels: BTreeMap<String, bool> = Channel::ALL
+                .iter()
+                .map(|c| (c.as_str().to_string(), true))
+                .collect();
+            for pref in channel_prefs.iter().filter(|p| p.notification_type == name) {
+                channels.insert(pref.channel.clone(), pref.enabled);
+            }
+
+            TypePreferences {
+                notification_type: name.to_string(),
+                enabled,
+                channels,
+            }
+        })
+        .collect();
+
+    Ok(Json(PreferencesResponse {
+        channels: Channel::ALL.iter().map(|c| c.as_str()).collect(),
+        types,
+    }))
+}
@@ -0,0 +1,45 @@
+use super::audit;
+use super::auth::AdminAuth;
+use super::{ApiError, ApiState};
+use axum::extract::State;
+use axum::Json;
+use serde::Serialize;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[derive(Debug, Serialize)]
+pub struct PreStopResponse {
+    /// False when deliveries were still running at PRESTOP_TIMEOUT_SECS
+    pub drained: bool,
+    pub in_flight: usize,
+    pub waited_ms: u64,
+}
+
+/// POST /admin/prestop
+///
+/// For the pod's preStop hook: `/readyz` turns 503, the worker stops claiming
+/// notifications, and this returns once in-flight deliveries finished (bounded by
+/// PRESTOP_TIMEOUT_SECS). Unclaimed rows are picked up by the other replicas. The service
+/// holds no WebSocket connections of its own (the Bus owns them), so none are closed here.
+/// Repeated calls wait again.
+pub async fn prestop(State(state): State<ApiState>, caller: AdminAuth) -> Result<Json<PreStopResponse>, ApiError> {
+    let start = Instant::now();
+    if state.drain.start() {
+        warn!(in_flight = state.drain.in_flight(), "preStop: draining, readiness is now failing");
+        audit::record(&state.pool, caller.actor(), "service.prestop", json!({})).await;
+    }
+
+    let drained = state.drain.wait_idle(Duration::from_secs(state.prestop_timeout_secs)).await;
+    let response = PreStopResponse {
+        drained,
+        in_flight: state.drain.in_flight(),
+        waited_ms: start.elapsed().as_millis() as u64,
+    };
+    if drained {
+        info!(waited_ms = response.waited_ms, "preStop: in-flight deliveries finished");
+    } else {
+        warn!(in_flight = response.in_flight, waited_ms = response.waited_ms, "preStop: timed out waiting for deliveries");
+    }
+    Ok(Json(response))
+}
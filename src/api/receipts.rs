@@ -0,0 +1,65 @@
+use super::audit;
+use super::auth::{OperatorAuth, ReadOnlyAuth};
+use super::{ApiError, ApiState};
+use crate::db::receipts::ReceiptWebhook;
+use crate::db::ReceiptQueries;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReceiptWebhookRequest {
+    pub url: String,
+    /// Only receipts for this type (None = all types)
+    pub notification_type: Option<String>,
+}
+
+/// GET /api/v1/receipt-webhooks
+pub async fn list_webhooks(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+) -> Result<Json<Vec<ReceiptWebhook>>, ApiError> {
+    Ok(Json(ReceiptQueries::list_webhooks(&state.pool).await?))
+}
+
+/// POST /api/v1/receipt-webhooks
+pub async fn create_webhook(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Json(request): Json<CreateReceiptWebhookRequest>,
+) -> Result<(StatusCode, Json<ReceiptWebhook>), ApiError> {
+    if !(request.url.starts_with("https://") || request.url.starts_with("http://")) {
+        return Err(ApiError::BadRequest("url must be an http(s) URL".to_string()));
+    }
+
+    let webhook = ReceiptQueries::create_webhook(&state.pool, &request.url, request.notification_type.as_deref()).await?;
+
+    info!(id = %webhook.id, url = %webhook.url, "Receipt webhook registered");
+    audit::record(
+        &state.pool,
+        caller.actor(),
+        "receipt_webhook.create",
+        json!({ "id": webhook.id, "url": webhook.url, "notification_type": webhook.notification_type }),
+    )
+    .await;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+/// DELETE /api/v1/receipt-webhooks/{id}
+pub async fn delete_webhook(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if !ReceiptQueries::delete_webhook(&state.pool, id).await? {
+        return Err(ApiError::NotFound(format!("Receipt webhook {} not found", id)));
+    }
+
+    info!(id = %id, "Receipt webhook removed");
+    audit::record(&state.pool, caller.actor(), "receipt_webhook.delete", json!({ "id": id })).await;
+    Ok(StatusCode::NO_CONTENT)
+}
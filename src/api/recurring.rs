@@ -0,0 +1,181 @@
+use super::audit;
+use super::auth::{OperatorAuth, ReadOnlyAuth};
+use super::{ApiError, ApiState};
+use crate::db::recurring::{Audience, NewRecurringNotification, RecurringNotification};
+use crate::db::tenants::DEFAULT_TENANT;
+use crate::db::{RecurringQueries, TenantQueries};
+use crate::recurring::CronSchedule;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+/// Most users one schedule may address (larger audiences: use a broadcast)
+const MAX_RECIPIENTS: usize = 10_000;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRecurringRequest {
+    pub name: String,
+    /// 5 fields, weekday names: `0 9 * * MON` = Mondays 09:00
+    pub cron: String,
+    /// IANA zone the cron runs in (default UTC)
+    pub timezone: Option<String>,
+    pub audience: Audience,
+    pub notification_type: String,
+    #[serde(default)]
+    pub title: String,
+    pub message: Option<String>,
+    pub template_key: Option<String>,
+    pub message_key: Option<String>,
+    pub message_args: Option<serde_json::Value>,
+    pub payload: Option<serde_json::Value>,
+    pub deep_link: Option<String>,
+    pub priority: Option<String>,
+    pub tenant_id: Option<String>,
+}
+
+impl CreateRecurringRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name is required".to_string());
+        }
+        if self.notification_type.trim().is_empty() {
+            return Err("notification_type is required".to_string());
+        }
+        if self.title.trim().is_empty() && self.message_key.is_none() && self.template_key.is_none() {
+            return Err("title is required unless message_key or template_key is set".to_string());
+        }
+        if let Some(priority) = &self.priority {
+            if !matches!(priority.as_str(), "low" | "normal" | "high" | "critical") {
+                return Err(format!("Unknown priority '{}'", priority));
+            }
+        }
+        if matches!(&self.message_args, Some(args) if !args.is_object()) {
+            return Err("message_args must be a JSON object".to_string());
+        }
+        if let Audience::Users { user_ids } = &self.audience {
+            if user_ids.is_empty() {
+                return Err("audience.user_ids must not be empty".to_string());
+            }
+            if user_ids.len() > MAX_RECIPIENTS {
+                return Err(format!("audience.user_ids is limited to {} users", MAX_RECIPIENTS));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// GET /api/v1/recurring-notifications
+pub async fn list_recurring(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+) -> Result<Json<Vec<RecurringNotification>>, ApiError> {
+    Ok(Json(RecurringQueries::list(&state.pool).await?))
+}
+
+/// POST /api/v1/recurring-notifications
+pub async fn create_recurring(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Json(request): Json<CreateRecurringRequest>,
+) -> Result<(StatusCode, Json<RecurringNotification>), ApiError> {
+    request.validate().map_err(ApiError::BadRequest)?;
+
+    let timezone = request.timezone.clone().unwrap_or_else(|| "UTC".to_string());
+    let schedule = CronSchedule::parse(&request.cron, &timezone).map_err(ApiError::BadRequest)?;
+    let next_run_at = schedule
+        .next_after(Utc::now())
+        .ok_or_else(|| ApiError::BadRequest(format!("cron '{}' never fires", request.cron)))?;
+
+    let tenant_id = request.tenant_id.clone().unwrap_or_else(|| DEFAULT_TENANT.to_string());
+    if tenant_id != DEFAULT_TENANT && TenantQueries::find(&state.pool, &tenant_id).await?.is_none() {
+        return Err(ApiError::BadRequest(format!("Unknown tenant '{}'", tenant_id)));
+    }
+
+    let recurring = NewRecurringNotification {
+        tenant_id,
+        name: request.name,
+        cron: request.cron,
+        timezone,
+        audience: request.audience,
+        notification_type: request.notification_type,
+        title: request.title,
+        message: request.message,
+        template_key: request.template_key,
+        message_key: request.message_key,
+        message_args: request.message_args,
+        payload: request.payload,
+        deep_link: request.deep_link,
+        priority: request.priority.unwrap_or_else(|| "normal".to_string()),
+    };
+    let created = RecurringQueries::create(&state.pool, &recurring, next_run_at, caller.actor()).await?;
+
+    info!(id = %created.id, name = %created.name, next_run_at = %next_run_at, "Recurring notification created");
+    audit::record(
+        &state.pool,
+        caller.actor(),
+        "recurring_notification.create",
+        json!({ "id": created.id, "name": created.name, "cron": created.cron, "timezone": created.timezone }),
+    )
+    .await;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// POST /api/v1/recurring-notifications/{id}/pause
+pub async fn pause_recurring(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RecurringNotification>, ApiError> {
+    let paused = RecurringQueries::set_paused(&state.pool, id, true, None)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Recurring notification {} not found", id)))?;
+
+    info!(id = %id, "Recurring notification paused");
+    audit::record(&state.pool, caller.actor(), "recurring_notification.pause", json!({ "id": id })).await;
+    Ok(Json(paused))
+}
+
+/// POST /api/v1/recurring-notifications/{id}/resume
+///
+/// Continues from the next future occurrence; occurrences missed while paused are skipped.
+pub async fn resume_recurring(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RecurringNotification>, ApiError> {
+    let recurring = RecurringQueries::find(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Recurring notification {} not found", id)))?;
+    let next_run_at = CronSchedule::parse(&recurring.cron, &recurring.timezone)
+        .map_err(ApiError::BadRequest)?
+        .next_after(Utc::now())
+        .ok_or_else(|| ApiError::BadRequest(format!("cron '{}' never fires", recurring.cron)))?;
+
+    let resumed = RecurringQueries::set_paused(&state.pool, id, false, Some(next_run_at))
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Recurring notification {} not found", id)))?;
+
+    info!(id = %id, next_run_at = %next_run_at, "Recurring notification resumed");
+    audit::record(&state.pool, caller.actor(), "recurring_notification.resume", json!({ "id": id })).await;
+    Ok(Json(resumed))
+}
+
+/// DELETE /api/v1/recurring-notifications/{id}
+pub async fn delete_recurring(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if !RecurringQueries::delete(&state.pool, id).await? {
+        return Err(ApiError::NotFound(format!("Recurring notification {} not found", id)));
+    }
+
+    info!(id = %id, "Recurring notification deleted");
+    audit::record(&state.pool, caller.actor(), "recurring_notification.delete", json!({ "id": id })).await;
+    Ok(StatusCode::NO_CONTENT)
+}
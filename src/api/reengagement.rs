@@ -0,0 +1,73 @@
+use super::audit;
+use super::auth::{AdminAuth, ReadOnlyAuth};
+use super::{ApiError, ApiState};
+use crate::db::reengagement::{ReengagementPolicy, ReengagementSettings};
+use crate::db::ReengagementQueries;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::json;
+use tracing::info;
+
+/// GET /api/v1/reengagement-policies
+pub async fn list_policies(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+) -> Result<Json<Vec<ReengagementPolicy>>, ApiError> {
+    Ok(Json(ReengagementQueries::list(&state.pool).await?))
+}
+
+/// PUT /api/v1/tenants/{tenant_id}/reengagement
+///
+/// Takes effect on the job's next run (REENGAGEMENT_INTERVAL_SECS).
+pub async fn save_policy(
+    State(state): State<ApiState>,
+    admin: AdminAuth,
+    Path(tenant_id): Path<String>,
+    Json(settings): Json<ReengagementSettings>,
+) -> Result<Json<ReengagementPolicy>, ApiError> {
+    if settings.inactive_days <= 0 {
+        return Err(ApiError::BadRequest("inactive_days must be positive".to_string()));
+    }
+    if settings.cooldown_days <= 0 {
+        return Err(ApiError::BadRequest("cooldown_days must be positive".to_string()));
+    }
+    if settings.title.trim().is_empty() {
+        return Err(ApiError::BadRequest("title is required".to_string()));
+    }
+    if settings.notification_type.trim().is_empty() {
+        return Err(ApiError::BadRequest("notification_type must not be empty".to_string()));
+    }
+
+    let policy = ReengagementQueries::upsert(&state.pool, &tenant_id, &settings).await?;
+
+    info!(
+        tenant_id = %tenant_id,
+        inactive_days = policy.inactive_days,
+        enabled = policy.enabled,
+        "Re-engagement policy saved"
+    );
+    audit::record(
+        &state.pool,
+        admin.actor(),
+        "reengagement.save",
+        json!({ "tenant_id": tenant_id, "settings": settings }),
+    )
+    .await;
+    Ok(Json(policy))
+}
+
+/// DELETE /api/v1/tenants/{tenant_id}/reengagement
+pub async fn delete_policy(
+    State(state): State<ApiState>,
+    admin: AdminAuth,
+    Path(tenant_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !ReengagementQueries::delete(&state.pool, &tenant_id).await? {
+        return Err(ApiError::NotFound(format!("Tenant '{}' has no re-engagement policy", tenant_id)));
+    }
+
+    info!(tenant_id = %tenant_id, "Re-engagement policy deleted");
+    audit::record(&state.pool, admin.actor(), "reengagement.delete", json!({ "tenant_id": tenant_id })).await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,69 @@
+use super::audit;
+use super::auth::AdminAuth;
+use super::{ApiError, ApiState};
+use crate::db::resend::{ResendCount, ResendWindow};
+use crate::db::ResendQueries;
+use crate::resend::audit_params;
+use axum::extract::State;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+pub struct ResendRequest {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub notification_type: Option<String>,
+    pub tenant_id: Option<String>,
+    /// Only count (default); send `false` to re-drive
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResendResponse {
+    pub dry_run: bool,
+    #[serde(flatten)]
+    pub count: ResendCount,
+}
+
+/// POST /api/v1/resend
+///
+/// Re-drives notifications that failed or were suppressed between `from` and `to`.
+/// Counts only unless `dry_run` is `false`.
+pub async fn resend(
+    State(state): State<ApiState>,
+    caller: AdminAuth,
+    Json(request): Json<ResendRequest>,
+) -> Result<Json<ResendResponse>, ApiError> {
+    if request.to <= request.from {
+        return Err(ApiError::BadRequest("to must be after from".to_string()));
+    }
+    let window = ResendWindow {
+        from: request.from,
+        to: request.to,
+        notification_type: request.notification_type,
+        tenant_id: request.tenant_id,
+    };
+
+    if request.dry_run {
+        let count = ResendQueries::count(&state.pool, &window).await?;
+        return Ok(Json(ResendResponse { dry_run: true, count }));
+    }
+
+    let count = ResendQueries::resend(&state.pool, &window).await?;
+    info!(
+        from = %window.from,
+        to = %window.to,
+        failed = count.failed,
+        suppressed = count.suppressed,
+        "Notifications re-driven"
+    );
+    audit::record(&state.pool, caller.actor(), "notifications.resend", audit_params(&window, &count)).await;
+    Ok(Json(ResendResponse { dry_run: false, count }))
+}
@@ -0,0 +1,21 @@
+use super::auth::ReadOnlyAuth;
+use super::{ApiError, ApiState};
+use crate::db::schema::SchemaReport;
+use crate::db::SchemaQueries;
+use axum::extract::State;
+use axum::Json;
+
+/// GET /admin/schema-check
+///
+/// The live `activity` schema against the columns this binary decodes: missing columns by
+/// name (with a likely rename when one looks close), instead of decode errors per row.
+pub async fn schema_check(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+) -> Result<Json<SchemaReport>, ApiError> {
+    let report = SchemaQueries::check(&state.pool).await?;
+    if !report.ok {
+        report.log();
+    }
+    Ok(Json(report))
+}
@@ -0,0 +1,59 @@
+use super::analytics::parse_range;
+use super::auth::ReadOnlyAuth;
+use super::{ApiError, ApiState};
+use crate::db::shadow::{ShadowDivergence, ShadowState, ShadowTotals};
+use crate::db::ShadowQueries;
+use axum::extract::{Query, State};
+use axum::Json;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_RANGE: &str = "24h";
+/// The ledger keeps compared rows 7 days
+const MAX_RANGE_DAYS: i64 = 7;
+const RECENT_DIVERGENCES: i64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct ShadowQuery {
+    /// `<n>h` or `<n>d` back from now (default 24h, max 7d)
+    pub range: Option<String>,
+}
+
+/// Dual write comparison between Postgres and the shadow queue backend
+#[derive(Debug, Serialize)]
+pub struct ShadowResponse {
+    pub range: String,
+    pub since: DateTime<Utc>,
+    pub state: ShadowState,
+    pub totals: ShadowTotals,
+    /// Newest first
+    pub recent_divergences: Vec<ShadowDivergence>,
+}
+
+/// GET /admin/shadow?range=24h
+///
+/// Counts are over notifications ingested in the range; see `crate::shadow` for what each
+/// divergence means. Rows younger than SHADOW_GRACE_SECS are still `pending`.
+pub async fn shadow(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Query(query): Query<ShadowQuery>,
+) -> Result<Json<ShadowResponse>, ApiError> {
+    let range = query.range.unwrap_or_else(|| DEFAULT_RANGE.to_string());
+    let length = parse_range(&range)
+        .filter(|length| *length > Duration::zero() && *length <= Duration::days(MAX_RANGE_DAYS))
+        .ok_or_else(|| ApiError::BadRequest(format!("range must be <n>h or <n>d, up to {}d", MAX_RANGE_DAYS)))?;
+    let since = Utc::now() - length;
+
+    let shadow_state = ShadowQueries::state(&state.pool).await?;
+    let totals = ShadowQueries::totals(&state.pool, since).await?;
+    let recent_divergences = ShadowQueries::recent_divergences(&state.pool, since, RECENT_DIVERGENCES).await?;
+
+    Ok(Json(ShadowResponse {
+        range,
+        since,
+        state: shadow_state,
+        totals,
+        recent_divergences,
+    }))
+}
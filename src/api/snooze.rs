@@ -0,0 +1,53 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState};
+use crate::db::PreferenceQueries;
+use axum::extract::State;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+#[derive(Debug, Deserialize)]
+pub struct SnoozeRequest {
+    /// Snooze until this time; null or a past time ends the snooze
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SnoozeResponse {
+    pub snoozed_until: Option<DateTime<Utc>>,
+}
+
+/// POST /api/v1/notifications/snooze
+pub async fn snooze(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Json(request): Json<SnoozeRequest>,
+) -> Result<Json<SnoozeResponse>, ApiError> {
+    let snoozed_until = request.until.filter(|until| *until > Utc::now());
+
+    match snoozed_until {
+        Some(until) => PreferenceQueries::set_snooze(&state.pool, &user.tenant_id, user.user_id, until).await?,
+        None => PreferenceQueries::clear_snooze(&state.pool, &user.tenant_id, user.user_id).await?,
+    }
+
+    info!(
+        user_id = %user.user_id,
+        snoozed_until = ?snoozed_until,
+        "Snooze state updated"
+    );
+
+    // Tell the user's connected clients (all devices) that snooze state changed
+    if let Some(bus) = state.bus_client.as_deref() {
+        let message = serde_json::json!({
+            "type": "snooze_changed",
+            "snoozed_until": snoozed_until,
+        });
+
+        if let Err(e) = state.protocols.publish_to_user(bus, user.user_id, "notifications", "snooze_changed", &message).await {
+            warn!(user_id = %user.user_id, error = %e, "Failed to publish snooze_changed via Bus");
+        }
+    }
+
+    Ok(Json(SnoozeResponse { snoozed_until }))
+}
@@ -0,0 +1,118 @@
+use super::auth::ReadOnlyAuth;
+use super::{ApiError, ApiState};
+use crate::db::slo::{SloIncident, TypeSlo};
+use crate::db::{CostQueries, MaintenanceQueries, SloQueries};
+use crate::worker::bus_health::BusStatus;
+use crate::worker::channel_health::RoutingStatus;
+use crate::worker::leader::LeaderStatus;
+use axum::extract::State;
+use axum::Json;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+/// Window `/admin/slo` reports on
+const SLO_WINDOW_SECS: i64 = 3600;
+
+/// Live state of this pod and the queue, for dashboards and on-call
+#[derive(Debug, Serialize)]
+pub struct StatsResponse {
+    /// Due notifications not delivered yet (all replicas)
+    pub backlog: i64,
+    pub maintenance: bool,
+    /// This pod received its preStop hook
+    pub draining: bool,
+    pub in_flight: usize,
+    /// Last Bus health probe (null = Bus not configured or probing off)
+    pub bus: Option<BusStatus>,
+    /// Adaptive route and the channel stats behind it (null = ADAPTIVE_ROUTING_LATENCY_MS=0)
+    pub routing: Option<RoutingStatus>,
+    /// Whether this pod holds the worker lease, and who does (null = LEADER_ELECTION off)
+    pub leader: Option<LeaderStatus>,
+    /// Today's (UTC) deliveries and cost per tenant, saved by every replica
+    pub costs: Vec<TenantCosts>,
+    /// Delivery SLO breaches not resolved yet (SLO_ALERT_INTERVAL_SECS), oldest first
+    pub incidents: Vec<SloIncident>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TenantCosts {
+    pub tenant_id: String,
+    pub cost: f64,
+    /// None = unlimited
+    pub daily_budget: Option<f64>,
+    /// Paid channels are skipped until the next UTC day
+    pub over_budget: bool,
+    pub channels: Vec<ChannelCost>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChannelCost {
+    pub channel: String,
+    pub deliveries: i64,
+    pub cost: f64,
+}
+
+/// GET /admin/stats
+pub async fn stats(State(state): State<ApiState>, _caller: ReadOnlyAuth) -> Result<Json<StatsResponse>, ApiError> {
+    let backlog = MaintenanceQueries::backlog(&state.pool).await?;
+    let maintenance = MaintenanceQueries::get(&state.pool).await?.enabled;
+    let mut costs: Vec<TenantCosts> = Vec::new();
+    // Rows come ordered by tenant
+    for row in CostQueries::for_day(&state.pool, Utc::now().date_naive()).await? {
+        if costs.last().is_none_or(|tenant| tenant.tenant_id != row.tenant_id) {
+            costs.push(TenantCosts {
+                tenant_id: row.tenant_id.clone(),
+                cost: 0.0,
+                daily_budget: row.daily_budget,
+                over_budget: false,
+                channels: Vec::new(),
+            });
+        }
+        if let Some(tenant) = costs.last_mut() {
+            tenant.cost += row.cost;
+            tenant.over_budget = tenant.daily_budget.is_some_and(|budget| tenant.cost >= budget);
+            tenant.channels.push(ChannelCost { channel: row.channel, deliveries: row.deliveries, cost: row.cost });
+        }
+    }
+    let incidents = SloQueries::open_incidents(&state.pool).await?;
+    Ok(Json(StatsResponse {
+        backlog,
+        maintenance,
+        draining: state.drain.is_draining(),
+        in_flight: state.drain.in_flight(),
+        bus: state.bus_health.as_ref().map(|health| health.status()),
+        routing: state.channel_health.as_ref().map(|health| health.status()),
+        leader: match &state.leadership {
+            Some(leadership) => Some(leadership.status().await),
+            None => None,
+        },
+        costs,
+        incidents,
+    }))
+}
+
+/// Delivery SLO per notification type over the last hour (all replicas)
+#[derive(Debug, Serialize)]
+pub struct SloResponse {
+    pub window_secs: i64,
+    pub since: DateTime<Utc>,
+    pub types: Vec<TypeSloReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TypeSloReport {
+    #[serde(flatten)]
+    pub counts: TypeSlo,
+    pub success_rate: f64,
+}
+
+/// GET /admin/slo
+pub async fn slo(State(state): State<ApiState>, _caller: ReadOnlyAuth) -> Result<Json<SloResponse>, ApiError> {
+    let since = Utc::now() - Duration::seconds(SLO_WINDOW_SECS);
+    let types = SloQueries::per_type(&state.pool, since)
+        .await?
+        .into_iter()
+        .map(|counts| TypeSloReport { success_rate: counts.success_rate(), counts })
+        .collect();
+    Ok(Json(SloResponse { window_secs: SLO_WINDOW_SECS, since, types }))
+}
@@ -0,0 +1,24 @@
+use super::auth::ReadOnlyAuth;
+use super::ApiState;
+use crate::status::ServiceStatus;
+use axum::extract::State;
+use axum::Json;
+use chrono::Utc;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct StatusResponse {
+    #[serde(flatten)]
+    pub status: ServiceStatus,
+    pub uptime_secs: i64,
+}
+
+/// GET /admin/status
+///
+/// The same object as the `service_started` log event, plus the uptime. It describes how
+/// this instance started; live state (backlog, draining, leader) is `GET /admin/stats`.
+pub async fn status(State(state): State<ApiState>, _caller: ReadOnlyAuth) -> Json<StatusResponse> {
+    let status = ServiceStatus::clone(&state.status);
+    let uptime_secs = (Utc::now() - status.started_at).num_seconds();
+    Json(StatusResponse { status, uptime_secs })
+}
@@ -0,0 +1,125 @@
+use super::audit;
+use super::auth::{OperatorAuth, ReadOnlyAuth};
+use super::{ApiError, ApiState};
+use crate::db::suppressions::{NewSuppression, Suppression};
+use crate::db::SuppressionQueries;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+const KINDS: &[&str] = &["user", "token", "email"];
+const REASONS: &[&str] = &["legal_opt_out", "uninstalled", "bounced", "unregistered", "other"];
+
+#[derive(Debug, Deserialize)]
+pub struct ListSuppressionsQuery {
+    /// The tenant's entries plus the global ones (None = all)
+    pub tenant_id: Option<String>,
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSuppressionRequest {
+    /// None = every tenant
+    pub tenant_id: Option<String>,
+    /// user (value = user_id), token (FCM token) or email
+    pub kind: String,
+    pub value: String,
+    pub reason: String,
+    pub note: Option<String>,
+    /// None = permanent
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// GET /api/v1/suppressions?tenant_id=&kind=
+pub async fn list_suppressions(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Query(query): Query<ListSuppressionsQuery>,
+) -> Result<Json<Vec<Suppression>>, ApiError> {
+    if let Some(kind) = query.kind.as_deref() {
+        validate(kind, KINDS, "kind")?;
+    }
+    let suppressions = SuppressionQueries::list(&state.pool, query.tenant_id.as_deref(), query.kind.as_deref()).await?;
+    Ok(Json(suppressions))
+}
+
+/// POST /api/v1/suppressions
+///
+/// Adding an endpoint that is already listed (same kind, value and tenant) replaces the entry.
+pub async fn create_suppression(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Json(request): Json<CreateSuppressionRequest>,
+) -> Result<(StatusCode, Json<Suppression>), ApiError> {
+    validate(&request.kind, KINDS, "kind")?;
+    validate(&request.reason, REASONS, "reason")?;
+    let value = match request.kind.as_str() {
+        "user" => request
+            .value
+            .parse::<Uuid>()
+            .map_err(|_| ApiError::BadRequest("value must be a user_id for kind user".to_string()))?
+            .to_string(),
+        "email" if !request.value.contains('@') => {
+            return Err(ApiError::BadRequest("value must be an email address for kind email".to_string()));
+        }
+        "email" => request.value.trim().to_lowercase(),
+        _ if request.value.trim().is_empty() => {
+            return Err(ApiError::BadRequest("value must not be empty".to_string()));
+        }
+        _ => request.value.trim().to_string(),
+    };
+
+    let suppression = NewSuppression {
+        tenant_id: request.tenant_id,
+        kind: request.kind,
+        value,
+        reason: request.reason,
+        note: request.note,
+        expires_at: request.expires_at,
+    };
+    let suppression = SuppressionQueries::create(&state.pool, &suppression, caller.actor()).await?;
+
+    info!(id = %suppression.id, kind = %suppression.kind, reason = %suppression.reason, "Suppression added");
+    audit::record(
+        &state.pool,
+        caller.actor(),
+        "suppression.create",
+        json!({
+            "id": suppression.id,
+            "tenant_id": suppression.tenant_id,
+            "kind": suppression.kind,
+            "reason": suppression.reason,
+            "expires_at": suppression.expires_at,
+        }),
+    )
+    .await;
+    Ok((StatusCode::CREATED, Json(suppression)))
+}
+
+/// DELETE /api/v1/suppressions/{id}
+pub async fn delete_suppression(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if !SuppressionQueries::delete(&state.pool, id).await? {
+        return Err(ApiError::NotFound(format!("Suppression {} not found", id)));
+    }
+
+    info!(id = %id, "Suppression removed");
+    audit::record(&state.pool, caller.actor(), "suppression.delete", json!({ "id": id })).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn validate(value: &str, allowed: &[&str], field: &str) -> Result<(), ApiError> {
+    if allowed.contains(&value) {
+        Ok(())
+    } else {
+        Err(ApiError::BadRequest(format!("Unknown {} '{}' (expected one of: {})", field, value, allowed.join(", "))))
+    }
+}
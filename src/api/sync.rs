@@ -0,0 +1,243 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState, ErrorCode};
+use crate::db::sync::{InboxHeader, SyncedNotification};
+use crate::db::SyncQueries;
+use crate::models::{NotificationAttachment, SyncNotifyMessage};
+use axum::extract::{Query, State};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+const DEFAULT_SYNC_LIMIT: i64 = 500;
+const MAX_SYNC_LIMIT: i64 = 1000;
+const MAX_MARK_READ: usize = 500;
+const DEFAULT_SNAPSHOT_LIMIT: i64 = 10;
+const MAX_SNAPSHOT_LIMIT: i64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct SyncQuery {
+    /// `cursor` of the previous response (last_event_id), or an RFC 3339 timestamp;
+    /// absent = everything
+    pub since: Option<String>,
+    /// Max changes per response (default 500, max 1000)
+    pub limit: Option<i64>,
+}
+
+/// Changes since the cursor, collapsed to the latest state per notification
+#[derive(Debug, Default, Serialize)]
+pub struct SyncResponse {
+    /// New in the inbox (may already be read)
+    pub created: Vec<SyncedNotification>,
+    /// Content changed since the client last saw it
+    pub updated: Vec<SyncedNotification>,
+    /// Only marked read: ids
+    pub read: Vec<Uuid>,
+    /// Removed: ids
+    pub deleted: Vec<Uuid>,
+    /// Pass as `since` next time
+    pub cursor: i64,
+    /// More changes are waiting: call again with `cursor` right away
+    pub has_more: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarkReadRequest {
+    pub ids: Vec<Uuid>,
+    /// The device marking them read (it gets no read-state push); absent for web
+    pub fcm_token: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarkReadResponse {
+    pub marked: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InboxSnapshotQuery {
+    /// Headers to include (default 10, max 50)
+    pub limit: Option<i64>,
+    /// The connection's `?protocol=` (absent = v1), see [`crate::protocol`]
+    pub protocol: Option<String>,
+}
+
+/// The `inbox_snapshot` frame: enough to render the inbox badge and list right after connect
+#[derive(Debug, Serialize)]
+pub struct InboxSnapshot {
+    #[serde(rename = "type")]
+    pub msg_type: &'static str,
+    pub unread_count: i64,
+    /// Newest first, without bodies
+    pub latest: Vec<InboxHeader>,
+    /// Pass as `since` to the sync endpoint for anything that changes afterwards
+    pub cursor: i64,
+}
+
+/// What a client must do with one notification after a run of changes
+#[derive(Default)]
+struct Collapsed {
+    created: bool,
+    updated: bool,
+    read: bool,
+    deleted: bool,
+}
+
+/// GET /api/v1/notifications/sync?since={cursor|timestamp}&limit=
+///
+/// Pairs with the WS `sync_notify` message: on receipt (or after being offline)
+/// clients call this with their last cursor instead of reloading the inbox.
+pub async fn sync(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Query(query): Query<SyncQuery>,
+) -> Result<Json<SyncResponse>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_SYNC_LIMIT);
+    if !(1..=MAX_SYNC_LIMIT).contains(&limit) {
+        return Err(ErrorCode::LimitOutOfRange.with("min", 1).with("max", MAX_SYNC_LIMIT).into());
+    }
+    let (after_id, after) = match query.since.as_deref() {
+        None => (0, None),
+        Some(since) => match since.parse::<i64>() {
+            Ok(id) => (id, None),
+            Err(_) => {
+                let timestamp = DateTime::parse_from_rfc3339(since).map_err(|_| ErrorCode::InvalidCursor)?;
+                (0, Some(timestamp.with_timezone(&Utc)))
+            }
+        },
+    };
+
+    let changes = SyncQueries::changes(&state.pool, &user.tenant_id, user.user_id, after_id, after, limit).await?;
+    let has_more = changes.len() as i64 == limit;
+    let cursor = match changes.last() {
+        Some(change) => change.id,
+        // Nothing new after a timestamp: hand out a numeric cursor for next time
+        None if after.is_some() => SyncQueries::latest_cursor(&state.pool).await?,
+        None => after_id,
+    };
+
+    let mut order = Vec::new();
+    let mut collapsed: HashMap<Uuid, Collapsed> = HashMap::new();
+    for change in &changes {
+        let entry = collapsed.entry(change.notification_id).or_insert_with(|| {
+            order.push(change.notification_id);
+            Collapsed::default()
+        });
+        match change.change.as_str() {
+            "created" => entry.created = true,
+            "updated" => entry.updated = true,
+            "read" => entry.read = true,
+            "deleted" => entry.deleted = true,
+            _ => {}
+        }
+    }
+
+    let mut response = SyncResponse { cursor, has_more, ..Default::default() };
+    let mut created_ids = Vec::new();
+    let mut updated_ids = Vec::new();
+    for id in order {
+        let entry = &collapsed[&id];
+        match entry {
+            // Created and deleted within the window: the client never saw it
+            Collapsed { created: true, deleted: true, .. } => {}
+            Collapsed { deleted: true, .. } => response.deleted.push(id),
+            Collapsed { created: true, .. } => created_ids.push(id),
+            Collapsed { updated: true, .. } => updated_ids.push(id),
+            Collapsed { read: true, .. } => response.read.push(id),
+            _ => {}
+        }
+    }
+    if !created_ids.is_empty() {
+        response.created = SyncQueries::fetch(&state.pool, &created_ids).await?;
+    }
+    if !updated_ids.is_empty() {
+        response.updated = SyncQueries::fetch(&state.pool, &updated_ids).await?;
+    }
+    sign_attachments(&state, response.created.iter_mut().chain(response.updated.iter_mut()));
+
+    debug!(
+        user_id = %user.user_id,
+        changes = changes.len(),
+        created = response.created.len(),
+        updated = response.updated.len(),
+        read = response.read.len(),
+        deleted = response.deleted.len(),
+        cursor = cursor,
+        "Delta sync served"
+    );
+    Ok(Json(response))
+}
+
+/// Attachment URLs valid from now: the ones in the push may have expired by the time the inbox is opened
+fn sign_attachments<'a>(state: &ApiState, notifications: impl Iterator<Item = &'a mut SyncedNotification>) {
+    let Some(signer) = &state.attachments else {
+        return;
+    };
+    let now = Utc::now();
+    for notification in notifications {
+        let attachments = NotificationAttachment::parse(notification.attachment_keys.as_ref());
+        notification.media = signer.sign_all(&attachments, now);
+    }
+}
+
+/// GET /api/v1/notifications/inbox-snapshot?limit=&protocol=
+///
+/// The frame websocket-bus sends after `connected` (see docs/api.md); clients without it can
+/// call this once instead of loading the inbox and the badge separately.
+pub async fn inbox_snapshot(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Query(query): Query<InboxSnapshotQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_SNAPSHOT_LIMIT);
+    if !(0..=MAX_SNAPSHOT_LIMIT).contains(&limit) {
+        return Err(ErrorCode::LimitOutOfRange.with("min", 0).with("max", MAX_SNAPSHOT_LIMIT).into());
+    }
+    // Cursor first: a change landing between the queries is synced again rather than missed
+    let cursor = SyncQueries::latest_cursor(&state.pool).await?;
+    let (unread_count, latest) = tokio::try_join!(
+        SyncQueries::unread_count(&state.pool, &user.tenant_id, user.user_id),
+        SyncQueries::latest_headers(&state.pool, &user.tenant_id, user.user_id, limit),
+    )?;
+    let snapshot = InboxSnapshot { msg_type: "inbox_snapshot", unread_count, latest, cursor };
+    let protocol = state.protocols.negotiate(query.protocol.as_deref());
+    Ok(Json(protocol.encode(serde_json::json!(snapshot))))
+}
+
+/// POST /api/v1/notifications/read
+///
+/// The user's connections get a `sync_notify` and a `read_state_changed` over the Bus,
+/// and with READ_STATE_PUSH their other devices a silent push with the unread count.
+pub async fn mark_read(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Json(request): Json<MarkReadRequest>,
+) -> Result<Json<MarkReadResponse>, ApiError> {
+    if request.ids.is_empty() || request.ids.len() > MAX_MARK_READ {
+        return Err(ApiError::BadRequest(format!("ids must contain 1 to {} ids", MAX_MARK_READ)));
+    }
+
+    let marked = SyncQueries::mark_read(&state.pool, &user.tenant_id, user.user_id, &request.ids).await?;
+    debug!(user_id = %user.user_id, marked = marked.len(), "Notifications marked read");
+    if marked.is_empty() {
+        return Ok(Json(MarkReadResponse { marked: 0 }));
+    }
+
+    if let Some(bus) = state.bus_client.as_deref() {
+        let message = serde_json::json!(SyncNotifyMessage::new(marked.len()));
+        if let Err(e) = state.protocols.publish_to_user(bus, user.user_id, "notifications", "sync_notify", &message).await {
+            warn!(user_id = %user.user_id, error = %e, "Failed to publish sync_notify via Bus");
+        }
+    }
+
+    let response = MarkReadResponse { marked: marked.len() as u64 };
+    let device = request.fcm_token.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    let read_state = state.read_state.clone();
+    let (tenant_id, user_id) = (user.tenant_id, user.user_id);
+    tokio::spawn(async move {
+        read_state.publish(&tenant_id, user_id, &marked, device.as_deref()).await;
+    });
+
+    Ok(Json(response))
+}
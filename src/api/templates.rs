@@ -0,0 +1,249 @@
+use super::audit;
+use super::auth::{OperatorAuth, ReadOnlyAuth};
+use super::{ApiError, ApiState};
+use crate::db::templates::{NotificationTemplate, TemplateVersion, ANY_CHANNEL};
+use crate::db::TemplateQueries;
+use crate::i18n::DEFAULT_LOCALE;
+use crate::models::Notification;
+use crate::push::FcmClient;
+use crate::templates::TemplateRenderer;
+use crate::worker::Channel;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use tracing::info;
+
+/// Placeholder token in push previews
+const PREVIEW_DEVICE_TOKEN: &str = "<device-token>";
+
+#[derive(Debug, Deserialize)]
+pub struct SaveTemplateRequest {
+    pub title_tpl: String,
+    pub body_tpl: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewRequest {
+    pub locale: Option<String>,
+    /// Sample variables (same shape as message_args)
+    #[serde(default = "no_variables")]
+    pub variables: serde_json::Value,
+    /// Unsaved draft to preview instead of the stored templates
+    pub title_tpl: Option<String>,
+    pub body_tpl: Option<String>,
+}
+
+/// Omitted variables render like a notification without message_args
+fn no_variables() -> serde_json::Value {
+    json!({})
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreviewResponse {
+    pub template_key: String,
+    /// Per channel: rendered text + exact outbound payload, or an error
+    pub channels: BTreeMap<&'static str, ChannelPreview>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum ChannelPreview {
+    Rendered {
+        locale: String,
+        title: String,
+        body: String,
+        payload: serde_json::Value,
+    },
+    Error {
+        error: String,
+    },
+}
+
+/// GET /api/v1/templates
+pub async fn list_templates(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+) -> Result<Json<Vec<NotificationTemplate>>, ApiError> {
+    Ok(Json(TemplateQueries::list(&state.pool, None).await?))
+}
+
+/// GET /api/v1/templates/{key}
+pub async fn get_template(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Path(key): Path<String>,
+) -> Result<Json<Vec<NotificationTemplate>>, ApiError> {
+    let templates = TemplateQueries::list(&state.pool, Some(&key)).await?;
+    if templates.is_empty() {
+        return Err(ApiError::NotFound(format!("Template '{}' not found", key)));
+    }
+    Ok(Json(templates))
+}
+
+/// PUT /api/v1/templates/{key}/{locale}/{channel}
+pub async fn save_template(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path((key, locale, channel)): Path<(String, String, String)>,
+    Json(request): Json<SaveTemplateRequest>,
+) -> Result<Json<NotificationTemplate>, ApiError> {
+    validate_channel(&channel)?;
+    TemplateRenderer::validate(&request.title_tpl, &request.body_tpl)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    let template = TemplateQueries::upsert(
+        &state.pool,
+        &key,
+        &locale,
+        &channel,
+        &request.title_tpl,
+        &request.body_tpl,
+    )
+    .await?;
+
+    info!(
+        template_key = %key,
+        locale = %locale,
+        channel = %channel,
+        version = template.version,
+        "Template saved"
+    );
+    audit::record(
+        &state.pool,
+        caller.actor(),
+        "template.save",
+        json!({ "key": key, "locale": locale, "channel": channel, "version": template.version }),
+    )
+    .await;
+    Ok(Json(template))
+}
+
+/// DELETE /api/v1/templates/{key}/{locale}/{channel}
+pub async fn delete_template(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path((key, locale, channel)): Path<(String, String, String)>,
+) -> Result<StatusCode, ApiError> {
+    if !TemplateQueries::delete(&state.pool, &key, &locale, &channel).await? {
+        return Err(ApiError::NotFound(format!("Template '{}/{}/{}' not found", key, locale, channel)));
+    }
+
+    info!(template_key = %key, locale = %locale, channel = %channel, "Template deleted");
+    audit::record(
+        &state.pool,
+        caller.actor(),
+        "template.delete",
+        json!({ "key": key, "locale": locale, "channel": channel }),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/v1/templates/{key}/{locale}/{channel}/versions
+pub async fn list_versions(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Path((key, locale, channel)): Path<(String, String, String)>,
+) -> Result<Json<Vec<TemplateVersion>>, ApiError> {
+    let versions = TemplateQueries::list_versions(&state.pool, &key, &locale, &channel).await?;
+    if versions.is_empty() {
+        return Err(ApiError::NotFound(format!("Template '{}/{}/{}' not found", key, locale, channel)));
+    }
+    Ok(Json(versions))
+}
+
+/// POST /api/v1/templates/{key}/{locale}/{channel}/versions/{version}/activate
+///
+/// Rollback (or roll forward) to a stored version; history is kept intact.
+pub async fn activate_version(
+    State(state): State<ApiState>,
+    caller: OperatorAuth,
+    Path((key, locale, channel, version)): Path<(String, String, String, i32)>,
+) -> Result<Json<NotificationTemplate>, ApiError> {
+    let template = TemplateQueries::activate(&state.pool, &key, &locale, &channel, version)
+        .await?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!("Template '{}/{}/{}' has no version {}", key, locale, channel, version))
+        })?;
+
+    info!(template_key = %key, locale = %locale, channel = %channel, version = version, "Template version activated");
+    audit::record(
+        &state.pool,
+        caller.actor(),
+        "template.activate",
+        json!({ "key": key, "locale": locale, "channel": channel, "version": version }),
+    )
+    .await;
+    Ok(Json(template))
+}
+
+/// POST /api/v1/templates/{key}/preview
+pub async fn preview_template(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+    Path(key): Path<String>,
+    Json(request): Json<PreviewRequest>,
+) -> Result<Json<PreviewResponse>, ApiError> {
+    let locale = request.locale.as_deref().unwrap_or(DEFAULT_LOCALE);
+    let mut channels = BTreeMap::new();
+
+    for channel in Channel::ALL {
+        let template = match (&request.title_tpl, &request.body_tpl) {
+            (Some(title_tpl), Some(body_tpl)) => Some(NotificationTemplate {
+                template_key: key.clone(),
+                locale: locale.to_string(),
+                channel: ANY_CHANNEL.to_string(),
+                version: 0,
+                title_tpl: title_tpl.clone(),
+                body_tpl: body_tpl.clone(),
+                updated_at: chrono::Utc::now(),
+            }),
+            _ => TemplateQueries::find(&state.pool, &key, locale, DEFAULT_LOCALE, channel.as_str()).await?,
+        };
+
+        let preview = match template {
+            None => ChannelPreview::Error {
+                error: format!("No template for channel '{}'", channel.as_str()),
+            },
+            Some(template) => match TemplateRenderer::render_template(&template, &request.variables) {
+                Ok(text) => {
+                    let mut sample = Notification::draft(&key, text.title.clone(), Some(text.body.clone()));
+                    sample.template_key = Some(key.clone());
+                    ChannelPreview::Rendered {
+                        locale: template.locale,
+                        payload: channel_payload(channel, &sample),
+                        title: text.title,
+                        body: text.body,
+                    }
+                }
+                Err(e) => ChannelPreview::Error { error: e.to_string() },
+            },
+        };
+
+        channels.insert(channel.as_str(), preview);
+    }
+
+    Ok(Json(PreviewResponse {
+        template_key: key,
+        channels,
+    }))
+}
+
+/// Outbound payload for a channel, built by the same code the worker uses
+fn channel_payload(channel: Channel, notification: &Notification) -> serde_json::Value {
+    match channel {
+        Channel::Bus => notification.bus_payload(),
+        Channel::Push => FcmClient::request_preview(PREVIEW_DEVICE_TOKEN, notification),
+    }
+}
+
+fn validate_channel(channel: &str) -> Result<(), ApiError> {
+    if channel == ANY_CHANNEL || Channel::ALL.iter().any(|c| c.as_str() == channel) {
+        Ok(())
+    } else {
+        Err(ApiError::BadRequest(format!("Unknown channel '{}'", channel)))
+    }
+}
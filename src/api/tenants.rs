@@ -0,0 +1,59 @@
+use super::audit;
+use super::auth::{AdminAuth, ReadOnlyAuth};
+use super::{ApiError, ApiState};
+use crate::db::tenants::{Tenant, TenantSettings};
+use crate::db::TenantQueries;
+use axum::extract::{Path, State};
+use axum::Json;
+use serde_json::json;
+use tracing::info;
+
+/// GET /api/v1/tenants
+pub async fn list_tenants(
+    State(state): State<ApiState>,
+    _caller: ReadOnlyAuth,
+) -> Result<Json<Vec<Tenant>>, ApiError> {
+    Ok(Json(TenantQueries::list(&state.pool).await?))
+}
+
+/// PUT /api/v1/tenants/{tenant_id}
+///
+/// Workers pick up changes within the tenant cache TTL (5 minutes).
+pub async fn save_tenant(
+    State(state): State<ApiState>,
+    admin: AdminAuth,
+    Path(tenant_id): Path<String>,
+    Json(settings): Json<TenantSettings>,
+) -> Result<Json<Tenant>, ApiError> {
+    if settings.fcm_project_id.is_some() != settings.fcm_credentials_path.is_some() {
+        return Err(ApiError::BadRequest(
+            "fcm_project_id and fcm_credentials_path must be set together".to_string(),
+        ));
+    }
+    if settings.fcm_sandbox_project_id.is_some() != settings.fcm_sandbox_credentials_path.is_some() {
+        return Err(ApiError::BadRequest(
+            "fcm_sandbox_project_id and fcm_sandbox_credentials_path must be set together".to_string(),
+        ));
+    }
+    if matches!(settings.rate_limit_per_minute, Some(limit) if limit <= 0) {
+        return Err(ApiError::BadRequest("rate_limit_per_minute must be positive".to_string()));
+    }
+    if matches!(settings.daily_budget, Some(budget) if !budget.is_finite() || budget < 0.0) {
+        return Err(ApiError::BadRequest("daily_budget must not be negative".to_string()));
+    }
+    if settings.scheduling_weight <= 0 {
+        return Err(ApiError::BadRequest("scheduling_weight must be positive".to_string()));
+    }
+
+    let tenant = TenantQueries::upsert(&state.pool, &tenant_id, &settings).await?;
+
+    info!(tenant_id = %tenant_id, enabled = tenant.enabled, "Tenant saved");
+    audit::record(
+        &state.pool,
+        admin.actor(),
+        "tenant.save",
+        json!({ "tenant_id": tenant_id, "settings": settings }),
+    )
+    .await;
+    Ok(Json(tenant))
+}
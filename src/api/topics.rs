@@ -0,0 +1,46 @@
+use super::auth::AuthUser;
+use super::{ApiError, ApiState};
+use crate::db::topics::TopicSubscription;
+use crate::db::TopicQueries;
+use crate::models::validate_topic;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use tracing::info;
+
+/// GET /api/v1/topics
+pub async fn list_topics(
+    State(state): State<ApiState>,
+    user: AuthUser,
+) -> Result<Json<Vec<TopicSubscription>>, ApiError> {
+    let topics = TopicQueries::list(&state.pool, &user.tenant_id, user.user_id).await?;
+    Ok(Json(topics))
+}
+
+/// PUT /api/v1/topics/{topic}
+pub async fn subscribe(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Path(topic): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    validate_topic(&topic)?;
+
+    TopicQueries::subscribe(&state.pool, &user.tenant_id, user.user_id, &topic).await?;
+
+    info!(user_id = %user.user_id, topic = %topic, "Topic subscribed");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /api/v1/topics/{topic}
+pub async fn unsubscribe(
+    State(state): State<ApiState>,
+    user: AuthUser,
+    Path(topic): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let removed = TopicQueries::unsubscribe(&state.pool, &user.tenant_id, user.user_id, &topic).await?;
+
+    if removed {
+        info!(user_id = %user.user_id, topic = %topic, "Topic unsubscribed");
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,95 @@
+use super::audit;
+use super::auth::AdminAuth;
+use super::{ApiError, ApiState};
+use crate::db::webhooks::WebhookSource;
+use crate::db::WebhookSourceQueries;
+use crate::ingest::webhook::validate_mapping;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+pub struct SaveWebhookSourceRequest {
+    pub secret: String,
+    /// Header with the hex HMAC signature (default `x-signature`)
+    pub signature_header: Option<String>,
+    pub mapping: serde_json::Value,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// GET /api/v1/webhook-sources
+pub async fn list_sources(
+    State(state): State<ApiState>,
+    _admin: AdminAuth,
+) -> Result<Json<Vec<WebhookSource>>, ApiError> {
+    Ok(Json(WebhookSourceQueries::list(&state.pool).await?))
+}
+
+/// PUT /api/v1/webhook-sources/{source}
+pub async fn save_source(
+    State(state): State<ApiState>,
+    admin: AdminAuth,
+    Path(source): Path<String>,
+    Json(request): Json<SaveWebhookSourceRequest>,
+) -> Result<Json<WebhookSource>, ApiError> {
+    if request.secret.len() < 16 {
+        return Err(ApiError::BadRequest("secret must be at least 16 characters".to_string()));
+    }
+    validate_mapping(&request.mapping).map_err(ApiError::BadRequest)?;
+
+    // Header names are case-insensitive; store lowercase for lookups
+    let signature_header = request
+        .signature_header
+        .as_deref()
+        .unwrap_or("x-signature")
+        .to_ascii_lowercase();
+
+    let webhook = WebhookSourceQueries::upsert(
+        &state.pool,
+        &source,
+        &request.secret,
+        &signature_header,
+        &request.mapping,
+        request.enabled,
+    )
+    .await?;
+
+    info!(source = %source, enabled = request.enabled, "Webhook source saved");
+    // The HMAC secret stays out of the audit log
+    audit::record(
+        &state.pool,
+        admin.actor(),
+        "webhook_source.save",
+        json!({
+            "source": source,
+            "signature_header": signature_header,
+            "mapping": request.mapping,
+            "enabled": request.enabled,
+        }),
+    )
+    .await;
+    Ok(Json(webhook))
+}
+
+/// DELETE /api/v1/webhook-sources/{source}
+pub async fn delete_source(
+    State(state): State<ApiState>,
+    admin: AdminAuth,
+    Path(source): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !WebhookSourceQueries::delete(&state.pool, &source).await? {
+        return Err(ApiError::NotFound(format!("Webhook source '{}' not found", source)));
+    }
+
+    info!(source = %source, "Webhook source deleted");
+    audit::record(&state.pool, admin.actor(), "webhook_source.delete", json!({ "source": source })).await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,229 @@
+//! Cold-storage archival: keeps `activity.notifications` small without losing history.
+//!
+//! Every ARCHIVE_POLL_INTERVAL_SECS the [`Archiver`] exports processed notifications
+//! older than ARCHIVE_AFTER_DAYS in batches of ARCHIVE_BATCH_SIZE: each batch becomes
+//! one gzip NDJSON object (`to_jsonb` of the full row per line) under ARCHIVE_URL,
+//! recorded in `activity.notification_archives` (manifest with row count, time range
+//! and SHA-256). The rows are deleted in the same transaction that writes the manifest,
+//! and only after the upload succeeded; a failed commit leaves an orphaned object, never
+//! a gap. [`restore`] reads one back (`GET /api/v1/archives` lists them):
+//!
+//! ```text
+//! notifications-service restore --archive <id>
+//! ```
+
+pub mod store;
+
+pub use store::ArchiveStore;
+
+use crate::api::audit;
+use crate::config::Config;
+use crate::db::archives::{Archive, ArchivedRow, NewArchive};
+use crate::db::ArchiveQueries;
+use chrono::{Duration as ChronoDuration, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::io::{BufRead, BufReader, Write};
+use std::time::Duration;
+use tracing::{debug, error, info, instrument};
+use uuid::Uuid;
+
+const RESTORE_USAGE: &str = "usage: notifications-service restore --archive <id>";
+/// Audit log actor for restores started from the command line
+const CLI_ACTOR: &str = "cli";
+
+/// Arguments of `notifications-service restore`
+#[derive(Debug, Clone)]
+pub struct RestoreArgs {
+    pub archive_id: Uuid,
+}
+
+impl RestoreArgs {
+    /// Parse the arguments after `restore`
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        match args {
+            [flag, value] if flag == "--archive" => value
+                .parse()
+                .map(|archive_id| Self { archive_id })
+                .map_err(|_| format!("--archive expects an archive id, got '{}'\n{}", value, RESTORE_USAGE)),
+            _ => Err(RESTORE_USAGE.to_string()),
+        }
+    }
+}
+
+pub struct Archiver {
+    pool: PgPool,
+    store: ArchiveStore,
+    after_days: u32,
+    batch_size: i64,
+    poll_interval: Duration,
+}
+
+impl Archiver {
+    pub fn new(pool: PgPool, store: ArchiveStore, after_days: u32, batch_size: i64, poll_interval: Duration) -> Self {
+        Self { pool, store, after_days, batch_size, poll_interval }
+    }
+
+    /// Archiver loop: export everything past the cutoff, sleep
+    #[instrument(skip(self), name = "archiver")]
+    pub async fn run(&self) {
+        info!(
+            after_days = self.after_days,
+            batch_size = self.batch_size,
+            poll_interval_secs = self.poll_interval.as_secs(),
+            "Archiver started"
+        );
+
+        loop {
+            match self.archive_due().await {
+                Ok(0) => debug!("Nothing to archive"),
+                Ok(archived) => info!(archived = archived, "Notifications archived"),
+                Err(e) => error!(error = %e, "Archiving failed"),
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Export and prune all due notifications - returns how many rows were archived
+    pub async fn archive_due(&self) -> Result<u64, String> {
+        let mut archived = 0;
+        loop {
+            let batch = self.archive_batch().await?;
+            archived += batch;
+            if (batch as i64) < self.batch_size {
+                return Ok(archived);
+            }
+        }
+    }
+
+    async fn archive_batch(&self) -> Result<u64, String> {
+        let cutoff = Utc::now() - ChronoDuration::days(i64::from(self.after_days));
+        let mut tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        let rows = ArchiveQueries::claim_batch(&mut tx, cutoff, self.batch_size)
+            .await
+            .map_err(|e| format!("Failed to read notifications: {}", e))?;
+        let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+            return Ok(0);
+        };
+
+        let id = Uuid::new_v4();
+        let key = format!("notifications/{}/{}.ndjson.gz", first.created_at.format("%Y/%m/%d"), id);
+        let body = encode(&rows)?;
+        let sha256 = hex(&Sha256::digest(&body));
+        let bytes = body.len() as i64;
+
+        // Upload before pruning: on failure the transaction rolls back and nothing is lost
+        let object_url = self.store.put(&key, body).await?;
+        let archive = NewArchive {
+            id,
+            object_url,
+            row_count: rows.len() as i32,
+            bytes,
+            sha256,
+            from_created_at: first.created_at,
+            to_created_at: last.created_at,
+        };
+        let ids: Vec<Uuid> = rows.iter().map(|row| row.id).collect();
+        let pruned = ArchiveQueries::record_and_prune(&mut tx, &archive, &ids)
+            .await
+            .map_err(|e| format!("Failed to record archive {}: {}", archive.object_url, e))?;
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to commit archive {}: {}", archive.object_url, e))?;
+
+        info!(
+            id = %id,
+            url = %archive.object_url,
+            rows = pruned,
+            bytes = bytes,
+            from = %archive.from_created_at,
+            to = %archive.to_created_at,
+            "Archive written"
+        );
+        metrics::counter!("notifications_archived_total").increment(pruned);
+        Ok(pruned)
+    }
+}
+
+/// `notifications-service restore`: restore one archive from the store in ARCHIVE_URL
+pub async fn run_restore(pool: &PgPool, config: &Config, args: &RestoreArgs) -> Result<u64, String> {
+    let store = ArchiveStore::open(config).await?;
+    let restored = restore(pool, &store, args.archive_id, CLI_ACTOR).await?;
+    audit::record(
+        pool,
+        CLI_ACTOR,
+        "archive.restore",
+        json!({ "id": args.archive_id, "restored": restored }),
+    )
+    .await;
+    Ok(restored)
+}
+
+/// Read an archive back into `activity.notifications` - returns the rows inserted
+///
+/// Checks the SHA-256 from the manifest first. Rows already present are skipped, and the
+/// archive's time range stays in the hot table for another ARCHIVE_AFTER_DAYS.
+pub async fn restore(pool: &PgPool, store: &ArchiveStore, id: Uuid, restored_by: &str) -> Result<u64, String> {
+    let archive = ArchiveQueries::find(pool, id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Archive {} not found", id))?;
+    let rows = read(store, &archive).await?;
+
+    let restored = ArchiveQueries::restore(pool, id, &rows, restored_by)
+        .await
+        .map_err(|e| format!("Failed to restore {}: {}", archive.object_url, e))?;
+    info!(id = %id, url = %archive.object_url, rows = rows.len(), restored = restored, "Archive restored");
+    Ok(restored)
+}
+
+/// The rows of an archive, after checking them against its manifest (SHA-256, row count)
+pub async fn read(store: &ArchiveStore, archive: &Archive) -> Result<Vec<serde_json::Value>, String> {
+    let body = store.get(&archive.object_url).await?;
+    let sha256 = hex(&Sha256::digest(&body));
+    if sha256 != archive.sha256 {
+        return Err(format!(
+            "Checksum mismatch for {}: manifest {}, object {}",
+            archive.object_url, archive.sha256, sha256
+        ));
+    }
+
+    let rows = decode(&body)?;
+    if rows.len() != archive.row_count as usize {
+        return Err(format!(
+            "{} holds {} rows, the manifest says {}",
+            archive.object_url,
+            rows.len(),
+            archive.row_count
+        ));
+    }
+    Ok(rows)
+}
+
+fn encode(rows: &[ArchivedRow]) -> Result<Vec<u8>, String> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for row in rows {
+        serde_json::to_writer(&mut encoder, &row.row).map_err(|e| e.to_string())?;
+        encoder.write_all(b"\n").map_err(|e| e.to_string())?;
+    }
+    encoder.finish().map_err(|e| format!("Compression failed: {}", e))
+}
+
+fn decode(body: &[u8]) -> Result<Vec<serde_json::Value>, String> {
+    BufReader::new(GzDecoder::new(body))
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+        .map(|line| {
+            let line = line.map_err(|e| format!("Decompression failed: {}", e))?;
+            serde_json::from_str(&line).map_err(|e| format!("Invalid archive line: {}", e))
+        })
+        .collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
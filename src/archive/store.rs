@@ -0,0 +1,257 @@
+use crate::config::Config;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use tracing::{debug, info};
+
+const GCS_SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+const GCS_BASE_URL: &str = "https://storage.googleapis.com";
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+
+/// Where archives are written: ARCHIVE_URL
+pub enum ArchiveStore {
+    /// `file:///path` (dev, tests, or a mounted volume)
+    File { root: PathBuf },
+    /// `gs://bucket/prefix`, authenticated with the FCM service account
+    Gcs { bucket: String, prefix: String, client: Client, auth: GoogleAuth },
+    /// `s3://bucket/prefix`, AWS default credential chain
+    #[cfg(feature = "s3")]
+    S3 { bucket: String, prefix: String, client: aws_sdk_s3::Client },
+}
+
+impl ArchiveStore {
+    /// Store for ARCHIVE_URL
+    pub async fn open(config: &Config) -> Result<Self, String> {
+        let url = config.archive_url.as_deref().ok_or("ARCHIVE_URL is not set")?;
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| format!("ARCHIVE_URL '{}' must be s3://, gs:// or file://", url))?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        let prefix = prefix.trim_matches('/').to_string();
+
+        let store = match scheme {
+            "file" => Self::File { root: PathBuf::from(rest) },
+            "gs" => {
+                let credentials = match (&config.fcm_credentials, &config.fcm_credentials_path) {
+                    (Some(json), _) => json.clone(),
+                    (None, Some(path)) => std::fs::read_to_string(path)
+                        .map_err(|e| format!("Failed to read {}: {}", path, e))?,
+                    (None, None) => {
+                        return Err("gs:// archives need FCM_CREDENTIALS or GOOGLE_APPLICATION_CREDENTIALS".to_string())
+                    }
+                };
+                Self::Gcs {
+                    bucket: bucket.to_string(),
+                    prefix,
+                    client: Client::new(),
+                    auth: GoogleAuth::from_json(&credentials)?,
+                }
+            }
+            #[cfg(feature = "s3")]
+            "s3" => {
+                let aws_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+                Self::S3 {
+                    bucket: bucket.to_string(),
+                    prefix,
+                    client: aws_sdk_s3::Client::new(&aws_config),
+                }
+            }
+            #[cfg(not(feature = "s3"))]
+            "s3" => return Err("s3:// archives need a build with the 's3' feature".to_string()),
+            other => return Err(format!("Unsupported ARCHIVE_URL scheme '{}'", other)),
+        };
+        info!(url = %url, "Archive store configured");
+        Ok(store)
+    }
+
+    /// Write an object under the configured prefix - returns its URL for the manifest
+    pub async fn put(&self, key: &str, body: Vec<u8>) -> Result<String, String> {
+        let bytes = body.len();
+        let url = match self {
+            Self::File { root } => {
+                let path = root.join(key);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent)
+                        .await
+                        .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+                }
+                tokio::fs::write(&path, body)
+                    .await
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                format!("file://{}", path.display())
+            }
+            Self::Gcs { bucket, prefix, client, auth } => {
+                let name = join_key(prefix, key);
+                let mut url = Url::parse(&format!("{}/upload/storage/v1/b/{}/o", GCS_BASE_URL, bucket))
+                    .map_err(|e| e.to_string())?;
+                url.query_pairs_mut().append_pair("uploadType", "media").append_pair("name", &name);
+
+                let response = client
+                    .post(url)
+                    .bearer_auth(auth.access_token(client).await?)
+                    .header(reqwest::header::CONTENT_TYPE, "application/gzip")
+                    .body(body)
+                    .send()
+                    .await
+                    .map_err(|e| format!("GCS upload failed: {}", e))?;
+                if !response.status().is_success() {
+                    let status = response.status();
+                    let detail = response.text().await.unwrap_or_default();
+                    return Err(format!("GCS upload failed: {} - {}", status, detail));
+                }
+                format!("gs://{}/{}", bucket, name)
+            }
+            #[cfg(feature = "s3")]
+            Self::S3 { bucket, prefix, client } => {
+                let name = join_key(prefix, key);
+                client
+                    .put_object()
+                    .bucket(bucket)
+                    .key(&name)
+                    .content_type("application/gzip")
+                    .body(body.into())
+                    .send()
+                    .await
+                    .map_err(|e| format!("S3 upload failed: {}", e))?;
+                format!("s3://{}/{}", bucket, name)
+            }
+        };
+        debug!(url = %url, bytes = bytes, "Archive object written");
+        Ok(url)
+    }
+
+    /// Read an object by the URL `put` returned
+    pub async fn get(&self, object_url: &str) -> Result<Vec<u8>, String> {
+        let (scheme, rest) = object_url
+            .split_once("://")
+            .ok_or_else(|| format!("Invalid object URL '{}'", object_url))?;
+        let (bucket, name) = rest.split_once('/').unwrap_or((rest, ""));
+
+        match (self, scheme) {
+            (Self::File { .. }, "file") => tokio::fs::read(rest)
+                .await
+                .map_err(|e| format!("Failed to read {}: {}", rest, e)),
+            (Self::Gcs { client, auth, .. }, "gs") => {
+                let mut url = Url::parse(&format!("{}/storage/v1/b/{}/o", GCS_BASE_URL, bucket))
+                    .map_err(|e| e.to_string())?;
+                url.path_segments_mut()
+                    .map_err(|_| "Invalid GCS URL".to_string())?
+                    .push(name);
+                url.query_pairs_mut().append_pair("alt", "media");
+
+                let response = client
+                    .get(url)
+                    .bearer_auth(auth.access_token(client).await?)
+                    .send()
+                    .await
+                    .map_err(|e| format!("GCS download failed: {}", e))?;
+                if !response.status().is_success() {
+                    return Err(format!("GCS download of {} failed: {}", object_url, response.status()));
+                }
+                response
+                    .bytes()
+                    .await
+                    .map(|bytes| bytes.to_vec())
+                    .map_err(|e| format!("GCS download failed: {}", e))
+            }
+            #[cfg(feature = "s3")]
+            (Self::S3 { client, .. }, "s3") => {
+                let output = client
+                    .get_object()
+                    .bucket(bucket)
+                    .key(name)
+                    .send()
+                    .await
+                    .map_err(|e| format!("S3 download of {} failed: {}", object_url, e))?;
+                output
+                    .body
+                    .collect()
+                    .await
+                    .map(|data| data.into_bytes().to_vec())
+                    .map_err(|e| format!("S3 download of {} failed: {}", object_url, e))
+            }
+            _ => Err(format!("{} is not in the store configured by ARCHIVE_URL", object_url)),
+        }
+    }
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}/{}", prefix, key)
+    }
+}
+
+#[derive(Deserialize)]
+struct ServiceAccount {
+    client_email: String,
+    private_key: String,
+}
+
+#[derive(Serialize)]
+struct JwtClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// OAuth2 access tokens for Cloud Storage from a service account
+pub struct GoogleAuth {
+    account: ServiceAccount,
+    cached: RwLock<Option<(String, Instant)>>,
+}
+
+impl GoogleAuth {
+    fn from_json(content: &str) -> Result<Self, String> {
+        let account = serde_json::from_str(content).map_err(|e| format!("Invalid service account JSON: {}", e))?;
+        Ok(Self { account, cached: RwLock::new(None) })
+    }
+
+    async fn access_token(&self, client: &Client) -> Result<String, String> {
+        if let Some((token, expires)) = self.cached.read().await.as_ref() {
+            if *expires > Instant::now() {
+                return Ok(token.clone());
+            }
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let claims = JwtClaims {
+            iss: &self.account.client_email,
+            scope: GCS_SCOPE,
+            aud: GOOGLE_TOKEN_URL,
+            iat: now,
+            exp: now + 3600,
+        };
+        let key = EncodingKey::from_rsa_pem(self.account.private_key.as_bytes())
+            .map_err(|e| format!("Invalid private key: {}", e))?;
+        let jwt = encode(&Header::new(Algorithm::RS256), &claims, &key).map_err(|e| e.to_string())?;
+
+        let response = client
+            .post(GOOGLE_TOKEN_URL)
+            .form(&[("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"), ("assertion", &jwt)])
+            .send()
+            .await
+            .map_err(|e| format!("Token request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Token request failed: {}", response.status()));
+        }
+        let token: TokenResponse = response.json().await.map_err(|e| format!("Invalid token response: {}", e))?;
+
+        // Refresh a minute early
+        let expires = Instant::now() + Duration::from_secs(token.expires_in.saturating_sub(60));
+        *self.cached.write().await = Some((token.access_token.clone(), expires));
+        Ok(token.access_token)
+    }
+}
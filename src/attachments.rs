@@ -0,0 +1,232 @@
+//! Pre-signed URLs for notification attachments.
+//!
+//! Producers upload media to the attachment bucket themselves and reference it by key
+//! (`attachments: [{"key": "...", "content_type": "image/png"}]`). The bucket stays private:
+//! every time a notification goes out (delivery, retry, resend) and every time the inbox
+//! serves it, the keys are signed into fresh GET URLs valid for ATTACHMENT_URL_TTL_SECS.
+//! Producers never hand out storage ACLs.
+//!
+//! ATTACHMENT_STORE_URL is `s3://bucket/prefix` or `gs://bucket/prefix`; keys are relative to
+//! the prefix. URLs are signed with an HMAC key pair (ATTACHMENT_ACCESS_KEY_ID /
+//! ATTACHMENT_SECRET_ACCESS_KEY): AWS Signature V4 for S3 and S3-compatible stores
+//! (ATTACHMENT_ENDPOINT, path-style), and the same scheme with GCS HMAC keys for gs://.
+//! Signing is local, so no request reaches the store.
+
+use crate::config::Config;
+use crate::models::{NotificationAttachment, SignedAttachment};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+/// Longest validity a V4 pre-signed URL can have (7 days)
+pub const MAX_URL_TTL_SECS: u64 = 7 * 24 * 3600;
+
+/// Signature flavour of the store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dialect {
+    /// AWS Signature V4 (`X-Amz-*`)
+    S3,
+    /// GCS V4 signing with an HMAC key (`X-Goog-*`)
+    Gcs,
+}
+
+impl Dialect {
+    fn algorithm(self) -> &'static str {
+        match self {
+            Dialect::S3 => "AWS4-HMAC-SHA256",
+            Dialect::Gcs => "GOOG4-HMAC-SHA256",
+        }
+    }
+
+    fn param(self, name: &str) -> String {
+        match self {
+            Dialect::S3 => format!("X-Amz-{}", name),
+            Dialect::Gcs => format!("X-Goog-{}", name),
+        }
+    }
+
+    fn service(self) -> &'static str {
+        match self {
+            Dialect::S3 => "s3",
+            Dialect::Gcs => "storage",
+        }
+    }
+
+    fn key_prefix(self) -> &'static str {
+        match self {
+            Dialect::S3 => "AWS4",
+            Dialect::Gcs => "GOOG4",
+        }
+    }
+
+    fn terminator(self) -> &'static str {
+        match self {
+            Dialect::S3 => "aws4_request",
+            Dialect::Gcs => "goog4_request",
+        }
+    }
+}
+
+/// Signs attachment keys into GET URLs (None in the config = attachments are not sent)
+pub struct AttachmentSigner {
+    dialect: Dialect,
+    /// `scheme://host[:port]` the URLs point at
+    origin: String,
+    /// `host[:port]`, the one signed header
+    host: String,
+    /// Path before the object key: `/bucket/prefix/` (path-style) or `/prefix/` (virtual-hosted)
+    base_path: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    ttl: ChronoDuration,
+}
+
+impl AttachmentSigner {
+    /// Signer for ATTACHMENT_STORE_URL (None when unset)
+    pub fn from_config(config: &Config) -> Result<Option<Self>, String> {
+        let Some(url) = config.attachment_store_url.as_deref() else {
+            return Ok(None);
+        };
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| format!("ATTACHMENT_STORE_URL '{}' must be s3:// or gs://", url))?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(format!("ATTACHMENT_STORE_URL '{}' has no bucket", url));
+        }
+        let dialect = match scheme {
+            "s3" => Dialect::S3,
+            "gs" => Dialect::Gcs,
+            other => return Err(format!("Unsupported ATTACHMENT_STORE_URL scheme '{}'", other)),
+        };
+        let (Some(access_key_id), Some(secret_access_key)) =
+            (config.attachment_access_key_id.clone(), config.attachment_secret_access_key.clone())
+        else {
+            return Err("ATTACHMENT_STORE_URL needs ATTACHMENT_ACCESS_KEY_ID and ATTACHMENT_SECRET_ACCESS_KEY".to_string());
+        };
+        if config.attachment_url_ttl_secs == 0 || config.attachment_url_ttl_secs > MAX_URL_TTL_SECS {
+            return Err(format!("ATTACHMENT_URL_TTL_SECS must be 1-{}", MAX_URL_TTL_SECS));
+        }
+
+        let region = config
+            .attachment_region
+            .clone()
+            .unwrap_or_else(|| if dialect == Dialect::Gcs { "auto" } else { "us-east-1" }.to_string());
+        let prefix = prefix.trim_matches('/');
+        let prefix = if prefix.is_empty() { String::new() } else { format!("{}/", encode_path(prefix)) };
+        // A custom endpoint (MinIO, R2, a GCS emulator) and GCS are path-style; AWS is virtual-hosted
+        let endpoint = match (&config.attachment_endpoint, dialect) {
+            (Some(endpoint), _) => Some(endpoint.clone()),
+            (None, Dialect::Gcs) => Some("https://storage.googleapis.com".to_string()),
+            (None, Dialect::S3) => None,
+        };
+        let (origin, host, base_path) = match endpoint {
+            Some(endpoint) => {
+                let endpoint = Url::parse(&endpoint).map_err(|e| format!("Invalid ATTACHMENT_ENDPOINT: {}", e))?;
+                let host = match (endpoint.host_str(), endpoint.port()) {
+                    (Some(host), Some(port)) => format!("{}:{}", host, port),
+                    (Some(host), None) => host.to_string(),
+                    (None, _) => return Err("ATTACHMENT_ENDPOINT has no host".to_string()),
+                };
+                let origin = format!("{}://{}", endpoint.scheme(), host);
+                (origin, host, format!("/{}/{}", encode_path(bucket), prefix))
+            }
+            None => {
+                let host = format!("{}.s3.{}.amazonaws.com", bucket, region);
+                (format!("https://{}", host), host, format!("/{}", prefix))
+            }
+        };
+
+        info!(url = %url, host = %host, ttl_secs = config.attachment_url_ttl_secs, "Attachment URL signing enabled");
+        Ok(Some(Self {
+            dialect,
+            origin,
+            host,
+            base_path,
+            region,
+            access_key_id,
+            secret_access_key,
+            ttl: ChronoDuration::seconds(config.attachment_url_ttl_secs as i64),
+        }))
+    }
+
+    /// Fresh URLs for a notification's attachments, valid from `now`
+    pub fn sign_all(&self, attachments: &[NotificationAttachment], now: DateTime<Utc>) -> Vec<SignedAttachment> {
+        attachments
+            .iter()
+            .map(|attachment| SignedAttachment {
+                url: self.presign(&attachment.key, now),
+                content_type: attachment.content_type.clone(),
+                expires_at: now + self.ttl,
+            })
+            .collect()
+    }
+
+    /// Query-string pre-signed GET URL for a key
+    pub fn presign(&self, key: &str, now: DateTime<Utc>) -> String {
+        let dialect = self.dialect;
+        let date = now.format("%Y%m%d").to_string();
+        let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let scope = format!("{}/{}/{}/{}", date, self.region, dialect.service(), dialect.terminator());
+        let path = format!("{}{}", self.base_path, encode_path(key));
+
+        // Canonical query: sorted by name (the X-*-prefixed names already sort this way)
+        let query = [
+            (dialect.param("Algorithm"), dialect.algorithm().to_string()),
+            (dialect.param("Credential"), format!("{}/{}", self.access_key_id, scope)),
+            (dialect.param("Date"), timestamp.clone()),
+            (dialect.param("Expires"), self.ttl.num_seconds().to_string()),
+            (dialect.param("SignedHeaders"), "host".to_string()),
+        ]
+        .iter()
+        .map(|(name, value)| format!("{}={}", encode(name), encode(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+
+        let canonical_request = format!("GET\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD", path, query, self.host);
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            dialect.algorithm(),
+            timestamp,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let secret = format!("{}{}", dialect.key_prefix(), self.secret_access_key);
+        let key = [date.as_str(), self.region.as_str(), dialect.service(), dialect.terminator()]
+            .iter()
+            .fold(secret.into_bytes(), |key, part| hmac_sha256(&key, part.as_bytes()));
+        let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        format!("{}{}?{}&{}={}", self.origin, path, query, dialect.param("Signature"), signature)
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// URI-encode everything but the unreserved characters (RFC 3986), as V4 signing requires
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// `encode` per path segment, keeping the slashes
+fn encode_path(path: &str) -> String {
+    path.split('/').map(encode).collect::<Vec<_>>().join("/")
+}
@@ -0,0 +1,51 @@
+//! Local mock of FCM for development without a Firebase project.
+//!
+//! Writes `mock-fcm-service-account.json` and serves the FCM/OAuth2 endpoints on
+//! MOCK_FCM_ADDR (default 127.0.0.1:9099). MOCK_FCM_RESPONSE selects the answer
+//! to every send: success (default), unregistered, rate_limited, server_error.
+
+use notifications_service::push::mock::{self, MockFcm, MockResponse};
+use std::env;
+use tracing::{error, info};
+
+const CREDENTIALS_FILE: &str = "mock-fcm-service-account.json";
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "notifications_service=debug".into()),
+        )
+        .init();
+
+    let addr = env::var("MOCK_FCM_ADDR").unwrap_or_else(|_| "127.0.0.1:9099".into());
+    let project_id = env::var("FCM_PROJECT_ID").unwrap_or_else(|_| "mock-project".into());
+    let response = env::var("MOCK_FCM_RESPONSE").unwrap_or_else(|_| "success".into());
+    let Some(response) = MockResponse::parse(&response) else {
+        error!(response = %response, "Unknown MOCK_FCM_RESPONSE (success, unregistered, rate_limited, server_error)");
+        std::process::exit(1);
+    };
+
+    if let Err(e) = std::fs::write(CREDENTIALS_FILE, mock::service_account_json(&project_id)) {
+        error!(error = %e, "Failed to write {}", CREDENTIALS_FILE);
+        std::process::exit(1);
+    }
+
+    let mock = match MockFcm::bind(&addr).await {
+        Ok(mock) => mock,
+        Err(e) => {
+            error!(error = %e, addr = %addr, "Failed to bind mock FCM server");
+            std::process::exit(1);
+        }
+    };
+    mock.respond_with(response);
+
+    info!("  GOOGLE_APPLICATION_CREDENTIALS={}", CREDENTIALS_FILE);
+    info!("  FCM_PROJECT_ID={}", project_id);
+    info!("  FCM_BASE_URL={}", mock.base_url());
+    info!("  FCM_TOKEN_URL={}", mock.token_url());
+
+    tokio::signal::ctrl_c().await.ok();
+    info!(sent = mock.sent().len(), "Mock FCM server stopped");
+}
@@ -0,0 +1,322 @@
+//! Operator CLI against a running service's admin API (day-2 operations from a terminal).
+//!
+//! ```text
+//! notifyctl [--url <base url>] [--token <token>] [--json] <command>
+//!
+//!   failed [--limit 50] [--category <c>]  notifications that used all their retries
+//!   requeue --from <time> --to <time> [--type <t>] [--tenant <id>] [--execute]
+//!   send-test --user <id> [--tenant <id>] [--type notifyctl_test] [--title <text>] [--priority <p>]
+//!   tail [--interval 2]                 follow delivery attempts (Ctrl-C stops)
+//!   user <id> [--tenant <id>]           a user's devices and preferences
+//! ```
+//!
+//! NOTIFYCTL_URL (default http://localhost:8080) and NOTIFYCTL_TOKEN (falling back to
+//! ADMIN_TOKEN) stand in for the flags. `requeue` takes the time formats of
+//! `notifications-service resend` and is a dry run without `--execute`. Output is a table,
+//! or JSON with `--json` (`tail` then prints one object per line).
+
+use notifications_service::resend::ResendArgs;
+use serde_json::{json, Value};
+use std::env;
+use std::time::Duration;
+
+const USAGE: &str = "usage: notifyctl [--url <base url>] [--token <token>] [--json] \
+                     <failed|requeue|send-test|tail|user> [options]";
+/// Attempts shown when `tail` starts
+const TAIL_BACKLOG: i64 = 20;
+
+struct Admin {
+    http: reqwest::Client,
+    url: String,
+    token: String,
+}
+
+impl Admin {
+    async fn get(&self, path: &str, query: &[(&str, String)]) -> Result<Value, String> {
+        let request = self.http.get(format!("{}{}", self.url, path)).query(query);
+        self.send(request).await
+    }
+
+    async fn post(&self, path: &str, body: Value) -> Result<Value, String> {
+        let request = self.http.post(format!("{}{}", self.url, path)).json(&body);
+        self.send(request).await
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Value, String> {
+        let response = request
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .map_err(|e| format!("Request to {} failed: {}", self.url, e))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let error = body["message"].as_str().map(str::to_string).unwrap_or_else(|| status.to_string());
+            return Err(format!("{} ({})", error, status.as_u16()));
+        }
+        Ok(body)
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = run(&args).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(args: &[String]) -> Result<(), String> {
+    let mut url = env::var("NOTIFYCTL_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+    let mut token = env::var("NOTIFYCTL_TOKEN").or_else(|_| env::var("ADMIN_TOKEN")).ok();
+    let mut json_output = false;
+
+    // Global flags come before the command
+    let mut rest = args;
+    while let Some(flag) = rest.first().filter(|arg| arg.starts_with("--")) {
+        match flag.as_str() {
+            "--json" => json_output = true,
+            "--url" | "--token" => {
+                let value = rest.get(1).ok_or_else(|| format!("{} needs a value\n{}", flag, USAGE))?;
+                if flag == "--url" {
+                    url = value.clone();
+                } else {
+                    token = Some(value.clone());
+                }
+                rest = &rest[1..];
+            }
+            _ => return Err(format!("Unknown option '{}'\n{}", flag, USAGE)),
+        }
+        rest = &rest[1..];
+    }
+    let (command, command_args) = rest.split_first().ok_or_else(|| USAGE.to_string())?;
+    let token = token.ok_or_else(|| "No token: set NOTIFYCTL_TOKEN or ADMIN_TOKEN, or pass --token".to_string())?;
+    let admin = Admin {
+        http: reqwest::Client::new(),
+        url: url.trim_end_matches('/').to_string(),
+        token,
+    };
+
+    match command.as_str() {
+        "failed" => failed(&admin, command_args, json_output).await,
+        "requeue" => requeue(&admin, command_args, json_output).await,
+        "send-test" => send_test(&admin, command_args, json_output).await,
+        "tail" => tail(&admin, command_args, json_output).await,
+        "user" => user(&admin, command_args, json_output).await,
+        other => Err(format!("Unknown command '{}'\n{}", other, USAGE)),
+    }
+}
+
+/// `--name value` options of a command; `positional` collects the rest
+fn options<'a>(
+    args: &'a [String],
+    names: &[&str],
+    positional: &mut Vec<&'a str>,
+) -> Result<Vec<(&'a str, &'a str)>, String> {
+    let mut values = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg);
+            continue;
+        }
+        if !names.contains(&arg.as_str()) {
+            return Err(format!("Unknown option '{}'\n{}", arg, USAGE));
+        }
+        let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        values.push((arg.as_str(), value.as_str()));
+    }
+    Ok(values)
+}
+
+fn option<'a>(values: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    values.iter().rev().find(|(flag, _)| *flag == name).map(|(_, value)| *value)
+}
+
+async fn failed(admin: &Admin, args: &[String], json_output: bool) -> Result<(), String> {
+    let values = options(args, &["--limit", "--category"], &mut Vec::new())?;
+    let mut query = vec![("limit", option(&values, "--limit").unwrap_or("50").to_string())];
+    if let Some(category) = option(&values, "--category") {
+        query.push(("category", category.to_string()));
+    }
+    let failures = admin.get("/admin/failures", &query).await?;
+    if json_output {
+        return print_json(&failures);
+    }
+    print_table(
+        &["FAILED AT", "ID", "TYPE", "TENANT", "USER", "ATTEMPTS", "CATEGORY", "ERROR"],
+        &failures,
+        &[
+            "last_error_at",
+            "id",
+            "notification_type",
+            "tenant_id",
+            "user_id",
+            "error_count",
+            "failure_category",
+            "last_error",
+        ],
+    );
+    Ok(())
+}
+
+async fn requeue(admin: &Admin, args: &[String], json_output: bool) -> Result<(), String> {
+    let parsed = ResendArgs::parse(args)?;
+    let window = &parsed.window;
+    let response = admin
+        .post(
+            "/api/v1/resend",
+            json!({
+                "from": window.from,
+                "to": window.to,
+                "notification_type": window.notification_type,
+                "tenant_id": window.tenant_id,
+                "dry_run": !parsed.execute,
+            }),
+        )
+        .await?;
+    if json_output {
+        return print_json(&response);
+    }
+    let (failed, suppressed) = (&response["failed"], &response["suppressed"]);
+    if parsed.execute {
+        println!("Requeued {} failed and {} suppressed notifications", failed, suppressed);
+    } else {
+        println!("Dry run: {} failed and {} suppressed notifications match (add --execute to requeue)", failed, suppressed);
+    }
+    Ok(())
+}
+
+async fn send_test(admin: &Admin, args: &[String], json_output: bool) -> Result<(), String> {
+    let values = options(args, &["--user", "--tenant", "--type", "--title", "--priority"], &mut Vec::new())?;
+    let user_id = option(&values, "--user").ok_or_else(|| "--user is required".to_string())?;
+    let response = admin
+        .post(
+            "/api/v1/notifications",
+            json!({
+                "user_id": user_id,
+                "tenant_id": option(&values, "--tenant"),
+                "notification_type": option(&values, "--type").unwrap_or("notifyctl_test"),
+                "title": option(&values, "--title").unwrap_or("Test notification"),
+                "message": "Sent with notifyctl",
+                "priority": option(&values, "--priority"),
+            }),
+        )
+        .await?;
+    if json_output {
+        return print_json(&response);
+    }
+    println!("Created {} (follow it with `notifyctl tail`)", cell(&response["id"]));
+    Ok(())
+}
+
+async fn tail(admin: &Admin, args: &[String], json_output: bool) -> Result<(), String> {
+    let values = options(args, &["--interval"], &mut Vec::new())?;
+    let interval: u64 = option(&values, "--interval")
+        .unwrap_or("2")
+        .parse()
+        .map_err(|_| "--interval must be a number of seconds".to_string())?;
+
+    let columns = ["attempted_at", "channel", "outcome", "notification_type", "user_id", "notification_id", "detail"];
+    let mut query = vec![("limit", TAIL_BACKLOG.to_string())];
+    loop {
+        let attempts = admin.get("/admin/attempts", &query).await?;
+        for attempt in attempts.as_array().into_iter().flatten() {
+            if json_output {
+                println!("{}", attempt);
+            } else {
+                let cells: Vec<String> = columns.iter().map(|column| cell(&attempt[*column])).collect();
+                println!("{}", cells.join("  "));
+            }
+            if let Some(id) = attempt["id"].as_i64() {
+                query = vec![("after", id.to_string())];
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(interval.max(1))).await;
+    }
+}
+
+async fn user(admin: &Admin, args: &[String], json_output: bool) -> Result<(), String> {
+    let mut positional = Vec::new();
+    let values = options(args, &["--tenant"], &mut positional)?;
+    let [user_id] = positional[..] else {
+        return Err("usage: notifyctl user <user id> [--tenant <id>]".to_string());
+    };
+    let query: Vec<_> = option(&values, "--tenant").map(|tenant| ("tenant", tenant.to_string())).into_iter().collect();
+    let user = admin.get(&format!("/admin/users/{}", user_id), &query).await?;
+    if json_output {
+        return print_json(&user);
+    }
+
+    println!("User {} (tenant {})", cell(&user["user_id"]), cell(&user["tenant_id"]));
+    println!("Timezone: {}   Snoozed until: {}", cell(&user["timezone"]), cell(&user["snoozed_until"]));
+    println!("\nDevices");
+    print_table(
+        &["TOKEN", "TYPE", "APP", "OS", "LOCALE", "QUIET", "TYPES", "LAST SEEN"],
+        &user["devices"],
+        &["fcm_token", "device_type", "app_version", "os_version", "locale", "quiet_hours", "enabled_types", "last_seen_at"],
+    );
+    println!("\nType preferences (unlisted types are enabled)");
+    print_table(&["TYPE", "ENABLED"], &user["types"], &["notification_type", "enabled"]);
+    println!("\nChannel preferences");
+    print_table(&["TYPE", "CHANNEL", "ENABLED"], &user["channels"], &["notification_type", "channel", "enabled"]);
+    Ok(())
+}
+
+fn print_json(value: &Value) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    println!("{}", text);
+    Ok(())
+}
+
+/// Aligned columns; `fields` picks the values from each row object
+fn print_table(headers: &[&str], rows: &Value, fields: &[&str]) {
+    let rows: Vec<Vec<String>> = rows
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|row| fields.iter().map(|field| field_cell(row, field)).collect())
+        .collect();
+    if rows.is_empty() {
+        println!("(none)");
+        return;
+    }
+
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| rows.iter().map(|row| row[i].chars().count()).chain([header.len()]).max().unwrap_or(0))
+        .collect();
+    let line = |cells: Vec<String>| {
+        let padded: Vec<String> = cells.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell)).collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+    line(headers.iter().map(|header| header.to_string()).collect());
+    for row in rows {
+        line(row);
+    }
+}
+
+fn field_cell(row: &Value, field: &str) -> String {
+    match field {
+        // Devices store quiet hours as three columns
+        "quiet_hours" if !row["quiet_hours_start"].is_null() => format!(
+            "{}-{} {}",
+            cell(&row["quiet_hours_start"]),
+            cell(&row["quiet_hours_end"]),
+            row["quiet_hours_timezone"].as_str().unwrap_or("UTC")
+        ),
+        "quiet_hours" => "-".to_string(),
+        _ => cell(&row[field]),
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(cell).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,89 @@
+//! Per-user broadcasts (`BROADCAST_FANOUT=per_user`).
+//!
+//! The worker turns a broadcast into a row in `activity.broadcast_fanouts` instead of a
+//! topic send. The [`BroadcastRunner`] then copies it, every poll, to as many users with
+//! a registered device as `BROADCAST_FANOUT_RATE_PER_MINUTE` allows, walking them in
+//! `user_id` order. The cursor (last user queued) is stored with each chunk, so after a
+//! restart the fan-out resumes where it left off. With `BROADCAST_FANOUT_JITTER_SECS`
+//! each copy is delayed by a random part of that window on top of the throttle.
+
+use super::fanout_allowance;
+use crate::db::{BroadcastQueries, MaintenanceQueries};
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument};
+use uuid::Uuid;
+
+pub struct BroadcastRunner {
+    pool: PgPool,
+    poll_interval: Duration,
+    rate_per_minute: i32,
+    jitter_secs: u64,
+}
+
+impl BroadcastRunner {
+    pub fn new(pool: PgPool, poll_interval: Duration, rate_per_minute: i32, jitter_secs: u64) -> Self {
+        Self { pool, poll_interval, rate_per_minute, jitter_secs }
+    }
+
+    /// Runner loop: release the next chunk of every running fan-out, sleep
+    #[instrument(skip(self), name = "broadcast_runner")]
+    pub async fn run(&self) {
+        info!(
+            poll_interval_secs = self.poll_interval.as_secs(),
+            rate_per_minute = self.rate_per_minute,
+            jitter_secs = self.jitter_secs,
+            "Broadcast fan-out runner started"
+        );
+
+        loop {
+            // A draining environment creates nothing new (`notifications-service drain`)
+            if MaintenanceQueries::ingestion_closed(&self.pool).await.unwrap_or(false) {
+                debug!("Ingestion closed - not releasing broadcast fan-outs");
+                tokio::time::sleep(self.poll_interval).await;
+                continue;
+            }
+            match BroadcastQueries::running(&self.pool).await {
+                Ok(running) => {
+                    for id in running {
+                        if let Err(e) = self.fan_out(id).await {
+                            error!(id = %id, error = %e, "Failed to fan out broadcast");
+                        }
+                    }
+                }
+                Err(e) => error!(error = %e, "Failed to list running broadcast fan-outs"),
+            }
+
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Release the users the throttle allows since the previous chunk
+    async fn fan_out(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(fanout) = BroadcastQueries::claim_running(&mut tx, id).await? else {
+            return Ok(());
+        };
+
+        let allowance = fanout_allowance(self.rate_per_minute, fanout.last_fanout_at.unwrap_or(fanout.started_at));
+        if allowance == 0 {
+            debug!(id = %id, "Broadcast throttled - nothing to release yet");
+            return Ok(());
+        }
+
+        let (created, completed) = BroadcastQueries::fan_out(&mut tx, &fanout, allowance, self.jitter_secs).await?;
+        tx.commit().await?;
+
+        debug!(id = %id, created = created, allowance = allowance, "Broadcast chunk released");
+        metrics::counter!("notifications_broadcast_fanned_out_total").increment(created);
+        if completed {
+            info!(
+                id = %id,
+                tenant_id = %fanout.tenant_id,
+                queued = fanout.queued_count as u64 + created,
+                "✅ Broadcast fully queued"
+            );
+        }
+        Ok(())
+    }
+}
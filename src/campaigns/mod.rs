@@ -0,0 +1,185 @@
+//! Campaigns: one notification to a segment of users, fanned out at a throttled rate.
+//!
+//! Campaigns live in `activity.campaigns` (managed via `/api/v1/campaigns`). When a
+//! campaign's `start_at` passes, the [`CampaignRunner`] resolves its audience into
+//! `activity.campaign_recipients` (uploaded ids are already there; a segment query or a
+//! device filter is run once, read-only) and then, every poll, inserts regular notification rows for as
+//! many recipients as `rate_per_minute` allows since the previous chunk
+//! (`created_by = campaign:<id>`). The NOTIFY trigger wakes the worker as usual.
+//! Campaign rows are locked with `FOR UPDATE SKIP LOCKED`, so every replica can run one.
+//!
+//! Per-user broadcasts ([`broadcasts`]) are released the same way.
+
+pub mod broadcasts;
+
+pub use broadcasts::BroadcastRunner;
+
+use crate::db::campaigns::{Campaign, CampaignAudience};
+use crate::db::{CampaignQueries, MaintenanceQueries};
+use crate::targeting::DeviceFilter;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument, warn};
+use uuid::Uuid;
+
+/// Largest audience a segment query or device filter may resolve to
+pub const MAX_SEGMENT_RECIPIENTS: i64 = 1_000_000;
+
+/// Recipients inserted per statement when a segment is resolved
+const RECIPIENT_INSERT_CHUNK: usize = 10_000;
+
+/// Throttle window: a runner that was down doesn't release more than a minute's worth at once
+const MAX_FANOUT_WINDOW_MS: i64 = 60_000;
+
+pub struct CampaignRunner {
+    pool: PgPool,
+    poll_interval: Duration,
+}
+
+impl CampaignRunner {
+    pub fn new(pool: PgPool, poll_interval: Duration) -> Self {
+        Self { pool, poll_interval }
+    }
+
+    /// Runner loop: start due campaigns, release the next chunk of each running one, sleep
+    #[instrument(skip(self), name = "campaign_runner")]
+    pub async fn run(&self) {
+        info!(poll_interval_secs = self.poll_interval.as_secs(), "Campaign runner started");
+
+        loop {
+            // A draining environment creates nothing new (`notifications-service drain`)
+            if MaintenanceQueries::ingestion_closed(&self.pool).await.unwrap_or(false) {
+                debug!("Ingestion closed - not starting or releasing campaigns");
+                tokio::time::sleep(self.poll_interval).await;
+                continue;
+            }
+            loop {
+                match self.start_next_due().await {
+                    Ok(true) => continue,
+                    Ok(false) => break,
+                    Err(e) => {
+                        error!(error = %e, "Failed to start campaign");
+                        break;
+                    }
+                }
+            }
+
+            match CampaignQueries::running(&self.pool).await {
+                Ok(running) => {
+                    for id in running {
+                        if let Err(e) = self.fan_out(id).await {
+                            error!(id = %id, error = %e, "Failed to fan out campaign");
+                        }
+                    }
+                }
+                Err(e) => error!(error = %e, "Failed to list running campaigns"),
+            }
+
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Resolve the audience of the earliest due campaign and mark it running - false when none is due
+    async fn start_next_due(&self) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(campaign) = CampaignQueries::claim_startable(&mut tx).await? else {
+            return Ok(false);
+        };
+
+        let resolved = match &campaign.audience.0 {
+            CampaignAudience::Users => None,
+            CampaignAudience::Segment { sql } => Some(
+                CampaignQueries::fetch_segment(&self.pool, sql, MAX_SEGMENT_RECIPIENTS + 1)
+                    .await
+                    .map_err(|e| format!("segment query failed: {}", e)),
+            ),
+            CampaignAudience::Devices => Some(self.resolve_devices(&campaign).await),
+        };
+        if let Some(resolved) = resolved {
+            let user_ids = match resolved {
+                Ok(user_ids) if user_ids.len() as i64 > MAX_SEGMENT_RECIPIENTS => {
+                    let reason = format!("audience exceeds {} recipients", MAX_SEGMENT_RECIPIENTS);
+                    return self.abandon(tx, &campaign, &reason).await;
+                }
+                Ok(user_ids) => user_ids,
+                Err(reason) => return self.abandon(tx, &campaign, &reason).await,
+            };
+            for chunk in user_ids.chunks(RECIPIENT_INSERT_CHUNK) {
+                CampaignQueries::insert_recipients(&mut tx, campaign.id, chunk).await?;
+            }
+        }
+
+        let total = CampaignQueries::start(&mut tx, campaign.id).await?;
+        tx.commit().await?;
+
+        info!(
+            id = %campaign.id,
+            name = %campaign.name,
+            recipients = total,
+            rate_per_minute = campaign.rate_per_minute,
+            "📣 Campaign started"
+        );
+        Ok(true)
+    }
+
+    /// Users with a device matching the campaign's `device_filter`
+    async fn resolve_devices(&self, campaign: &Campaign) -> Result<Vec<Uuid>, String> {
+        let conditions = campaign.device_filter.as_deref().unwrap_or_default();
+        let filter = DeviceFilter::parse(conditions)?;
+        CampaignQueries::fetch_device_audience(&self.pool, &campaign.tenant_id, &filter, MAX_SEGMENT_RECIPIENTS + 1)
+            .await
+            .map_err(|e| format!("device audience query failed: {}", e))
+    }
+
+    /// Cancel a campaign whose audience can't be resolved (it's not retried)
+    async fn abandon(
+        &self,
+        tx: sqlx::Transaction<'_, sqlx::Postgres>,
+        campaign: &Campaign,
+        reason: &str,
+    ) -> Result<bool, sqlx::Error> {
+        tx.rollback().await?;
+        warn!(id = %campaign.id, name = %campaign.name, reason = %reason, "Cancelling campaign");
+        CampaignQueries::fail(&self.pool, campaign.id, reason).await?;
+        Ok(true)
+    }
+
+    /// Release the recipients the throttle allows since the previous chunk
+    async fn fan_out(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(campaign) = CampaignQueries::claim_running(&mut tx, id).await? else {
+            return Ok(());
+        };
+
+        let since = campaign.last_fanout_at.or(campaign.started_at).unwrap_or_else(Utc::now);
+        let allowance = fanout_allowance(campaign.rate_per_minute, since);
+        if allowance == 0 {
+            debug!(id = %id, "Campaign throttled - nothing to release yet");
+            return Ok(());
+        }
+
+        let (created, completed) = CampaignQueries::fan_out(&mut tx, &campaign, allowance).await?;
+        tx.commit().await?;
+
+        debug!(id = %id, created = created, allowance = allowance, "Campaign chunk released");
+        metrics::counter!("notifications_campaign_fanned_out_total").increment(created);
+        if completed {
+            info!(
+                id = %id,
+                name = %campaign.name,
+                queued = campaign.queued_count as u64 + created,
+                "✅ Campaign fully queued"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Recipients `rate_per_minute` allows for the time since the last chunk
+///
+/// Fractions carry over: `last_fanout_at` only moves once a chunk is released.
+fn fanout_allowance(rate_per_minute: i32, since: DateTime<Utc>) -> i64 {
+    let elapsed_ms = (Utc::now() - since).num_milliseconds().clamp(0, MAX_FANOUT_WINDOW_MS);
+    rate_per_minute as i64 * elapsed_ms / 60_000
+}
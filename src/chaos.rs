@@ -0,0 +1,78 @@
+//! Fault injection for resilience testing (DEBUG_MODE + CHAOS_* only).
+//!
+//! The worker asks the injector before each FCM send, Bus publish and queue query.
+//! Injected faults look like the real ones (FCM 503, Bus timeout, DB error) so
+//! retry, backoff and give-up behavior can be exercised against healthy dependencies.
+
+use crate::config::ChaosConfig;
+use crate::error::BusError;
+use crate::push::fcm::FcmError;
+use std::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    config: ChaosConfig,
+}
+
+impl FaultInjector {
+    pub fn new(config: ChaosConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &ChaosConfig {
+        &self.config
+    }
+
+    /// Before an FCM send: added latency, then maybe a retryable 503
+    pub async fn fcm(&self) -> Result<(), FcmError> {
+        self.delay().await;
+        if self.roll(self.config.fcm_failure_rate, "fcm") {
+            return Err(FcmError::Rejected {
+                status: reqwest::StatusCode::SERVICE_UNAVAILABLE,
+                body: "chaos: injected FCM failure".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Before a Bus publish: added latency, then maybe a timeout
+    pub async fn bus(&self) -> Result<(), BusError> {
+        self.delay().await;
+        if self.roll(self.config.bus_timeout_rate, "bus") {
+            return Err(BusError("chaos: injected Bus timeout".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Before a worker query: added latency, then maybe a database error
+    pub async fn db(&self) -> Result<(), sqlx::Error> {
+        self.delay().await;
+        if self.roll(self.config.db_error_rate, "db") {
+            return Err(sqlx::Error::Protocol("chaos: injected database error".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn delay(&self) {
+        if self.config.latency_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(self.config.latency_ms)).await;
+        }
+    }
+
+    fn roll(&self, rate: f64, kind: &'static str) -> bool {
+        if rate <= 0.0 || random_unit() >= rate {
+            return false;
+        }
+        warn!(kind = kind, rate = rate, "💥 Chaos: injecting fault");
+        metrics::counter!("notifications_chaos_faults_total", "kind" => kind).increment(1);
+        true
+    }
+}
+
+/// Uniform in [0, 1) from the random bits of a v4 UUID (no rand dependency)
+fn random_unit() -> f64 {
+    let (_, random) = Uuid::new_v4().as_u64_pair();
+    (random & ((1 << 53) - 1)) as f64 / (1u64 << 53) as f64
+}
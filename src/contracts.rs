@@ -0,0 +1,83 @@
+//! Payload contracts: a JSON Schema per `notification_type` that `payload` must match.
+//!
+//! Schemas live in `activity.payload_schemas` (`/api/v1/payload-schemas`). Ingestion
+//! ([`crate::ingest::ingest`]) rejects a mismatch before anything is stored; the worker
+//! checks again before delivery, for rows that were inserted directly or queued before
+//! the schema changed. Types without a schema accept any payload. Compiled schemas are
+//! cached per process for [`CACHE_TTL`]; saving through the API invalidates this process's
+//! entry, other replicas pick the change up when theirs expires.
+
+use crate::db::PayloadSchemaQueries;
+use jsonschema::Validator;
+use moka::future::Cache;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{error, trace};
+
+pub const CACHE_TTL: Duration = Duration::from_secs(30);
+/// Mismatches listed in one error
+const MAX_ERRORS: usize = 10;
+
+/// Compiled schema per notification_type (None = the type has no schema)
+static SCHEMAS: LazyLock<Cache<String, Option<Arc<Validator>>>> =
+    LazyLock::new(|| Cache::builder().max_capacity(1_000).time_to_live(CACHE_TTL).build());
+
+#[derive(Debug, Error)]
+pub enum ContractError {
+    /// The payload doesn't match; one entry per mismatch, prefixed with its JSON pointer
+    #[error("payload does not match the '{notification_type}' schema: {}", .errors.join("; "))]
+    Violation { notification_type: String, errors: Vec<String> },
+    #[error("Failed to load the payload schema: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Compile a schema (run before saving it)
+pub fn compile(schema: &Value) -> Result<Validator, String> {
+    jsonschema::validator_for(schema).map_err(|e| e.to_string())
+}
+
+/// Check a payload against its type's schema; an absent payload is checked as `null`
+pub async fn check(pool: &PgPool, notification_type: &str, payload: Option<&Value>) -> Result<(), ContractError> {
+    let Some(validator) = validator(pool, notification_type).await? else {
+        return Ok(());
+    };
+    let payload = payload.unwrap_or(&Value::Null);
+    let errors: Vec<String> = validator
+        .iter_errors(payload)
+        .take(MAX_ERRORS)
+        .map(|e| format!("payload{}: {}", e.instance_path(), e))
+        .collect();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    metrics::counter!("notifications_payload_contract_violations_total").increment(1);
+    Err(ContractError::Violation { notification_type: notification_type.to_string(), errors })
+}
+
+/// Drop the cached schema after it changed
+pub async fn invalidate(notification_type: &str) {
+    SCHEMAS.invalidate(notification_type).await;
+}
+
+async fn validator(pool: &PgPool, notification_type: &str) -> Result<Option<Arc<Validator>>, sqlx::Error> {
+    if let Some(cached) = SCHEMAS.get(notification_type).await {
+        return Ok(cached);
+    }
+    trace!(notification_type = %notification_type, "Loading payload schema");
+    let validator = match PayloadSchemaQueries::find(pool, notification_type).await? {
+        Some(stored) => match compile(&stored.schema) {
+            Ok(validator) => Some(Arc::new(validator)),
+            // Only reachable for rows written around the API; don't block the type on it
+            Err(e) => {
+                error!(notification_type = %notification_type, error = %e, "Stored payload schema doesn't compile, ignoring it");
+                None
+            }
+        },
+        None => None,
+    };
+    SCHEMAS.insert(notification_type.to_string(), validator.clone()).await;
+    Ok(validator)
+}
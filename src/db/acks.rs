@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::{debug, instrument};
+use uuid::Uuid;
+
+pub struct AckQueries;
+
+impl AckQueries {
+    /// The recipient's notification with its ack state - None if it isn't theirs
+    #[instrument(skip(pool))]
+    pub async fn find(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<AckState>, sqlx::Error> {
+        sqlx::query_as::<_, AckState>(
+            r#"
+            SELECT id, notification_type::text AS notification_type, acked_at, acked_by_device, bus_delivered_at
+            FROM activity.notifications
+            WHERE id = $1 AND tenant_id = $2 AND user_id = $3
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(tenant_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Record the first ack - None when another device acked first
+    ///
+    /// A Bus delivery waiting for its ack becomes due again, so the worker confirms it
+    /// right away instead of at the deadline.
+    #[instrument(skip(pool, device))]
+    pub async fn ack(pool: &PgPool, id: Uuid, device: Option<&str>) -> Result<Option<AckState>, sqlx::Error> {
+        let result = sqlx::query_as::<_, AckState>(
+            r#"
+            UPDATE activity.notifications
+            SET acked_at = now(),
+                acked_by_device = $2,
+                deliver_at = CASE WHEN bus_delivered_at IS NOT NULL AND NOT is_processed THEN now() ELSE deliver_at END
+            WHERE id = $1 AND acked_at IS NULL
+            RETURNING id, notification_type::text AS notification_type, acked_at, acked_by_device, bus_delivered_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(device)
+        .fetch_optional(pool)
+        .await;
+
+        if let Ok(acked) = &result {
+            debug!(id = %id, first = acked.is_some(), "DB ack_notification: completed");
+        }
+        result
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AckState {
+    pub id: Uuid,
+    pub notification_type: String,
+    pub acked_at: Option<DateTime<Utc>>,
+    /// FCM token of the device that acked first
+    pub acked_by_device: Option<String>,
+    /// Set when a Bus delivery waits for this ack (BUS_ACK_TIMEOUT_SECS)
+    pub bus_delivered_at: Option<DateTime<Utc>>,
+}
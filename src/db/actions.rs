@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::{debug, instrument};
+use uuid::Uuid;
+
+pub struct ActionQueries;
+
+impl ActionQueries {
+    /// The recipient's notification with its actions - None if it isn't theirs
+    ///
+    /// Broadcast rows (nil user id) can be answered by any user of the tenant.
+    #[instrument(skip(pool))]
+    pub async fn find(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<ActionTarget>, sqlx::Error> {
+        sqlx::query_as::<_, ActionTarget>(
+            r#"
+            SELECT id, tenant_id, notification_type::text AS notification_type, actions, created_at
+            FROM activity.notifications
+            WHERE id = $1 AND tenant_id = $2
+              AND (user_id = $3 OR user_id = '00000000-0000-0000-0000-000000000000')
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(tenant_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Record the user's answer - None when they already answered (from another device)
+    #[instrument(skip(pool, target, input, fcm_token), fields(id = %target.id))]
+    pub async fn record(
+        pool: &PgPool,
+        target: &ActionTarget,
+        user_id: Uuid,
+        action: &str,
+        input: Option<&str>,
+        fcm_token: Option<&str>,
+    ) -> Result<Option<RecordedAction>, sqlx::Error> {
+        let result = sqlx::query_as::<_, RecordedAction>(
+            r#"
+            INSERT INTO activity.notification_actions
+                (notification_id, user_id, tenant_id, notification_type, action, input, fcm_token)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (notification_id, user_id) DO NOTHING
+            RETURNING action, input, occurred_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(target.id)
+        .bind(user_id)
+        .bind(&target.tenant_id)
+        .bind(&target.notification_type)
+        .bind(action)
+        .bind(input)
+        .bind(fcm_token)
+        .fetch_optional(pool)
+        .await;
+
+        if let Ok(recorded) = &result {
+            debug!(id = %target.id, first = recorded.is_some(), "DB record_action: completed");
+        }
+        result
+    }
+
+    /// The answer the user gave earlier
+    #[instrument(skip(pool))]
+    pub async fn answer(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<Option<RecordedAction>, sqlx::Error> {
+        sqlx::query_as::<_, RecordedAction>(
+            r#"
+            SELECT action, input, occurred_at
+            FROM activity.notification_actions
+            WHERE notification_id = $1 AND user_id = $2
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    }
+}
+
+/// Notification an action is taken on
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ActionTarget {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub notification_type: String,
+    pub actions: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A recorded answer
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RecordedAction {
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
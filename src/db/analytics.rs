@@ -0,0 +1,192 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+
+/// Recompute the hourly buckets since `$1` from the raw tables
+///
+/// Counts go by when the event happened, so only the latest buckets still change:
+/// attempts and reads are recorded at now().
+const ROLL_UP_HOURLY_SQL: &str = r#"
+            INSERT INTO activity.delivery_rollups_hourly
+                (bucket, tenant_id, notification_type, channel, sent, delivered, failed, read)
+            SELECT bucket, tenant_id, notification_type, channel,
+                   SUM(sent), SUM(delivered), SUM(failed), SUM(read)
+            FROM (
+                SELECT date_trunc('hour', a.attempted_at) AS bucket,
+                       n.tenant_id,
+                       n.notification_type::text AS notification_type,
+                       a.channel,
+                       COUNT(*) AS sent,
+                       COUNT(*) FILTER (WHERE a.outcome IN ('delivered', 'simulated')) AS delivered,
+                       COUNT(*) FILTER (WHERE a.outcome IN ('failed', 'invalid_token')) AS failed,
+                       0 AS read
+                FROM activity.notification_attempts a
+                JOIN activity.notifications n ON n.id = a.notification_id
+                WHERE a.attempted_at >= $1
+                GROUP BY 1, 2, 3, 4
+
+                UNION ALL
+
+                SELECT date_trunc('hour', n.read_at), n.tenant_id, n.notification_type::text, 'inbox',
+                       0, 0, 0, COUNT(*)
+                FROM activity.notifications n
+                WHERE n.read_at >= $1
+                GROUP BY 1, 2, 3
+            ) events
+            GROUP BY bucket, tenant_id, notification_type, channel
+            ON CONFLICT (bucket, tenant_id, notification_type, channel) DO UPDATE
+            SET sent = EXCLUDED.sent,
+                delivered = EXCLUDED.delivered,
+                failed = EXCLUDED.failed,
+                read = EXCLUDED.read,
+                updated_at = now()
+"#;
+
+/// Re-sum the UTC days touched since `$1` from the hourly buckets
+const ROLL_UP_DAILY_SQL: &str = r#"
+            INSERT INTO activity.delivery_rollups_daily
+                (bucket, tenant_id, notification_type, channel, sent, delivered, failed, read)
+            SELECT date_trunc('day', bucket AT TIME ZONE 'UTC') AT TIME ZONE 'UTC',
+                   tenant_id, notification_type, channel,
+                   SUM(sent), SUM(delivered), SUM(failed), SUM(read)
+            FROM activity.delivery_rollups_hourly
+            WHERE bucket >= date_trunc('day', $1 AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+            GROUP BY 1, 2, 3, 4
+            ON CONFLICT (bucket, tenant_id, notification_type, channel) DO UPDATE
+            SET sent = EXCLUDED.sent,
+                delivered = EXCLUDED.delivered,
+                failed = EXCLUDED.failed,
+                read = EXCLUDED.read,
+                updated_at = now()
+"#;
+
+const HOURLY_SERIES_SQL: &str = r#"
+            SELECT bucket, tenant_id, notification_type, channel, sent, delivered, failed, read
+            FROM activity.delivery_rollups_hourly
+            WHERE bucket >= $1 AND ($2::text IS NULL OR tenant_id = $2)
+            ORDER BY bucket, notification_type, channel, tenant_id
+"#;
+
+const DAILY_SERIES_SQL: &str = r#"
+            SELECT bucket, tenant_id, notification_type, channel, sent, delivered, failed, read
+            FROM activity.delivery_rollups_daily
+            WHERE bucket >= $1 AND ($2::text IS NULL OR tenant_id = $2)
+            ORDER BY bucket, notification_type, channel, tenant_id
+"#;
+
+pub struct AnalyticsQueries;
+
+impl AnalyticsQueries {
+    /// Bring the rollups up to date - None when another replica is already at it
+    ///
+    /// Starts an hour before the newest hourly bucket, or `backfill_days` back on the first run.
+    #[instrument(skip(pool))]
+    pub async fn roll_up(pool: &PgPool, backfill_days: i64) -> Result<Option<RollupRun>, sqlx::Error> {
+        trace!("DB roll_up");
+        let start = Instant::now();
+        let mut tx = pool.begin().await?;
+
+        let (locked,): (bool,) = sqlx::query_as("SELECT pg_try_advisory_xact_lock(hashtext('delivery_rollups'))")
+            .persistent(super::prepared_statements())
+            .fetch_one(&mut *tx)
+            .await?;
+        if !locked {
+            return Ok(None);
+        }
+
+        let (since,): (DateTime<Utc>,) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(MAX(bucket) - interval '1 hour', date_trunc('hour', now() - $1 * interval '1 day'))
+            FROM activity.delivery_rollups_hourly
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(backfill_days as f64)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let hourly = sqlx::query(ROLL_UP_HOURLY_SQL)
+            .persistent(super::prepared_statements())
+            .bind(since)
+            .execute(&mut *tx)
+            .await;
+        let hourly = match hourly {
+            Ok(r) => r.rows_affected(),
+            Err(e) => {
+                error!(since = %since, error = %e, "DB roll_up: hourly rollup failed");
+                return Err(e);
+            }
+        };
+        let daily = sqlx::query(ROLL_UP_DAILY_SQL)
+            .persistent(super::prepared_statements())
+            .bind(since)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+
+        debug!(
+            since = %since,
+            hourly = hourly,
+            daily = daily,
+            duration_ms = start.elapsed().as_millis() as u64,
+            "DB roll_up: completed"
+        );
+        Ok(Some(RollupRun { since, hourly, daily }))
+    }
+
+    /// Rollup rows since `since`, oldest first (all tenants when `tenant_id` is None)
+    #[instrument(skip(pool))]
+    pub async fn series(
+        pool: &PgPool,
+        granularity: Granularity,
+        since: DateTime<Utc>,
+        tenant_id: Option<&str>,
+    ) -> Result<Vec<RollupRow>, sqlx::Error> {
+        trace!("DB rollup_series: {:?} since {}", granularity, since);
+        let sql = match granularity {
+            Granularity::Hour => HOURLY_SERIES_SQL,
+            Granularity::Day => DAILY_SERIES_SQL,
+        };
+        sqlx::query_as::<_, RollupRow>(sql)
+            .persistent(super::prepared_statements())
+            .bind(since)
+            .bind(tenant_id)
+            .fetch_all(pool)
+            .await
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Hour,
+    Day,
+}
+
+/// What one rollup run recomputed
+#[derive(Debug, Clone)]
+pub struct RollupRun {
+    pub since: DateTime<Utc>,
+    /// Hourly rows written
+    pub hourly: u64,
+    /// Daily rows written
+    pub daily: u64,
+}
+
+/// Counts of one bucket, tenant, type and channel (`inbox` holds the reads)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RollupRow {
+    pub bucket: DateTime<Utc>,
+    pub tenant_id: String,
+    pub notification_type: String,
+    pub channel: String,
+    /// Delivery attempts (push: one per device)
+    pub sent: i64,
+    pub delivered: i64,
+    /// Outcome failed or invalid_token
+    pub failed: i64,
+    pub read: i64,
+}
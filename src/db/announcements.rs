@@ -0,0 +1,61 @@
+use super::compression;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+pub struct AnnouncementQueries;
+
+impl AnnouncementQueries {
+    /// Announcements still pinned for a user, newest first
+    ///
+    /// The user's own rows (including topic copies) and the tenant's broadcasts, once
+    /// delivered and unless suppressed.
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn active(pool: &PgPool, tenant_id: &str, user_id: Uuid) -> Result<Vec<Announcement>, sqlx::Error> {
+        sqlx::query_as::<_, Announcement>(
+            r#"
+            SELECT id, notification_type::text AS notification_type, title, message, payload, payload_zstd,
+                   deep_link, priority, pinned_until, created_at,
+                   CASE WHEN user_id = $2 THEN read_at END AS read_at
+            FROM activity.notifications
+            WHERE tenant_id = $1
+              AND pinned_until > now()
+              AND (user_id = $2 OR (user_id = '00000000-0000-0000-0000-000000000000' AND topic IS NULL))
+              AND is_processed AND suppressed_at IS NULL
+            ORDER BY created_at DESC, id DESC
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map(|mut rows| {
+            for row in &mut rows {
+                compression::inflate(row.id, &mut row.payload, &mut row.payload_zstd);
+            }
+            rows
+        })
+    }
+}
+
+/// A pinned notification as returned by `GET /api/v1/announcements`
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Announcement {
+    pub id: Uuid,
+    pub notification_type: String,
+    pub title: String,
+    pub message: Option<String>,
+    pub payload: Option<serde_json::Value>,
+    /// `payload` as stored when compressed, moved into it after the fetch
+    #[serde(skip)]
+    pub payload_zstd: Option<Vec<u8>>,
+    pub deep_link: Option<String>,
+    pub priority: Option<String>,
+    pub pinned_until: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    /// Broadcasts are shared, so only the user's own rows carry a read state
+    pub read_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+pub struct ApiKeyQueries;
+
+impl ApiKeyQueries {
+    /// Find a usable key by hash (not revoked, not expired)
+    #[instrument(skip(pool, key_hash))]
+    pub async fn find_active(pool: &PgPool, key_hash: &str) -> Result<Option<ApiKey>, sqlx::Error> {
+        trace!("DB find_api_key: looking up key by hash");
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT id, name, key_prefix, scopes, tenant_id, rate_limit_per_minute, expires_at, last_used_at,
+                   revoked_at, rotated_from, created_at
+            FROM activity.api_keys
+            WHERE key_hash = $1
+              AND revoked_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(key_hash)
+        .fetch_optional(pool)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok(found) => {
+                debug!(
+                    found = found.is_some(),
+                    duration_ms = duration.as_millis() as u64,
+                    "DB find_api_key: completed"
+                );
+            }
+            Err(e) => {
+                error!(
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB find_api_key: query failed"
+                );
+            }
+        }
+
+        result
+    }
+
+    /// Record usage (at most once a minute per key, to keep hot keys cheap)
+    #[instrument(skip(pool))]
+    pub async fn touch(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE activity.api_keys
+            SET last_used_at = now()
+            WHERE id = $1
+              AND (last_used_at IS NULL OR last_used_at < now() - INTERVAL '1 minute')
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    /// List all keys (hashes are never returned)
+    #[instrument(skip(pool))]
+    pub async fn list(pool: &PgPool) -> Result<Vec<ApiKey>, sqlx::Error> {
+        trace!("DB list_api_keys");
+
+        sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT id, name, key_prefix, scopes, tenant_id, rate_limit_per_minute, expires_at, last_used_at,
+                   revoked_at, rotated_from, created_at
+            FROM activity.api_keys
+            ORDER BY created_at DESC
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Store a new key
+    #[instrument(skip(pool, key, key_hash), fields(name = %key.name))]
+    pub async fn create(pool: &PgPool, key: &NewApiKey<'_>, key_hash: &str) -> Result<ApiKey, sqlx::Error> {
+        trace!("DB create_api_key: '{}'", key.name);
+
+        sqlx::query_as::<_, ApiKey>(
+            r#"
+            INSERT INTO activity.api_keys (name, key_prefix, key_hash, scopes, tenant_id, rate_limit_per_minute, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, name, key_prefix, scopes, tenant_id, rate_limit_per_minute, expires_at, last_used_at,
+                      revoked_at, rotated_from, created_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(key.name)
+        .bind(key.key_prefix)
+        .bind(key_hash)
+        .bind(key.scopes)
+        .bind(key.tenant_id)
+        .bind(key.rate_limit_per_minute)
+        .bind(key.expires_at)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Replace a key: insert the successor and let the old key expire after a grace period
+    ///
+    /// Returns None if the old key doesn't exist or is no longer active.
+    #[instrument(skip(pool, new_key_prefix, new_key_hash))]
+    pub async fn rotate(
+        pool: &PgPool,
+        id: Uuid,
+        new_key_prefix: &str,
+        new_key_hash: &str,
+        old_expires_at: DateTime<Utc>,
+    ) -> Result<Option<ApiKey>, sqlx::Error> {
+        trace!("DB rotate_api_key: {}", id);
+        let mut tx = pool.begin().await?;
+
+        // Lock the old key so concurrent rotations can't fork the chain
+        let old = sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT id, name, key_prefix, scopes, tenant_id, rate_limit_per_minute, expires_at, last_used_at,
+                   revoked_at, rotated_from, created_at
+            FROM activity.api_keys
+            WHERE id = $1
+              AND revoked_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
+            FOR UPDATE
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(old) = old else {
+            return Ok(None);
+        };
+
+        let new_key = sqlx::query_as::<_, ApiKey>(
+            r#"
+            INSERT INTO activity.api_keys (
+                name, key_prefix, key_hash, scopes, tenant_id, rate_limit_per_minute, expires_at, rotated_from
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, name, key_prefix, scopes, tenant_id, rate_limit_per_minute, expires_at, last_used_at,
+                      revoked_at, rotated_from, created_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(&old.name)
+        .bind(new_key_prefix)
+        .bind(new_key_hash)
+        .bind(&old.scopes)
+        .bind(&old.tenant_id)
+        .bind(old.rate_limit_per_minute)
+        .bind(old.expires_at)
+        .bind(old.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE activity.api_keys SET expires_at = LEAST(COALESCE(expires_at, $2), $2) WHERE id = $1",
+        )
+        .persistent(super::prepared_statements())
+        .bind(old.id)
+        .bind(old_expires_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(new_key))
+    }
+
+    /// Revoke a key immediately - returns false if it didn't exist or was already revoked
+    #[instrument(skip(pool))]
+    pub async fn revoke(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+        trace!("DB revoke_api_key: {}", id);
+
+        sqlx::query("UPDATE activity.api_keys SET revoked_at = now() WHERE id = $1 AND revoked_at IS NULL")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub tenant_id: Option<String>,
+    /// Creation quota (None = unlimited)
+    pub rate_limit_per_minute: Option<i32>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub rotated_from: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiKey {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+/// Fields for a new key (the hash is passed separately)
+#[derive(Debug)]
+pub struct NewApiKey<'a> {
+    pub name: &'a str,
+    pub key_prefix: &'a str,
+    pub scopes: &'a [String],
+    pub tenant_id: Option<&'a str>,
+    pub rate_limit_per_minute: Option<i32>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
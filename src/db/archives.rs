@@ -0,0 +1,228 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::types::Json;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+/// Rows per statement when an archive is restored
+const RESTORE_CHUNK: usize = 5_000;
+
+pub struct ArchiveQueries;
+
+impl ArchiveQueries {
+    /// Lock the oldest processed notifications created before `cutoff`, as full-row JSON
+    ///
+    /// Rows in the range of an archive restored after `cutoff` are skipped (kept hot
+    /// for another retention period). Locked `SKIP LOCKED`, so replicas split the work.
+    #[instrument(skip(tx))]
+    pub async fn claim_batch(
+        tx: &mut Transaction<'_, Postgres>,
+        cutoff: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<ArchivedRow>, sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, ArchivedRow>(
+            r#"
+            SELECT n.id, n.created_at, to_jsonb(n) AS row
+            FROM activity.notifications n
+            WHERE n.is_processed
+              AND n.created_at < $1
+              AND NOT EXISTS (
+                  SELECT 1 FROM activity.notification_archives a
+                  WHERE a.restored_at >= $1
+                    AND n.created_at BETWEEN a.from_created_at AND a.to_created_at
+              )
+            ORDER BY n.created_at, n.id
+            LIMIT $2
+            FOR UPDATE OF n SKIP LOCKED
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(cutoff)
+        .bind(limit)
+        .fetch_all(&mut **tx)
+        .await;
+
+        match &result {
+            Ok(rows) => debug!(
+                count = rows.len(),
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB claim_archive_batch: completed"
+            ),
+            Err(e) => error!(error = %e, "DB claim_archive_batch: query failed"),
+        }
+        result
+    }
+
+    /// Record an uploaded archive and delete its rows from the hot table
+    #[instrument(skip(tx, archive, ids), fields(id = %archive.id, rows = ids.len()))]
+    pub async fn record_and_prune(
+        tx: &mut Transaction<'_, Postgres>,
+        archive: &NewArchive,
+        ids: &[Uuid],
+    ) -> Result<u64, sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO activity.notification_archives
+                (id, object_url, row_count, bytes, sha256, from_created_at, to_created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(archive.id)
+        .bind(&archive.object_url)
+        .bind(archive.row_count)
+        .bind(archive.bytes)
+        .bind(&archive.sha256)
+        .bind(archive.from_created_at)
+        .bind(archive.to_created_at)
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query("DELETE FROM activity.notifications WHERE id = ANY($1)")
+            .persistent(super::prepared_statements())
+            .bind(ids)
+            .execute(&mut **tx)
+            .await
+            .map(|r| r.rows_affected())
+    }
+
+    /// Archives, newest first
+    #[instrument(skip(pool))]
+    pub async fn list(pool: &PgPool, limit: i64) -> Result<Vec<Archive>, sqlx::Error> {
+        trace!("DB list_archives");
+
+        sqlx::query_as::<_, Archive>(
+            r#"
+            SELECT id, object_url, row_count, bytes, sha256, from_created_at, to_created_at,
+                   created_at, restored_at, restored_by
+            FROM activity.notification_archives
+            ORDER BY from_created_at DESC
+            LIMIT $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Archives holding rows created in `[from, to)`, oldest first
+    #[instrument(skip(pool))]
+    pub async fn overlapping(pool: &PgPool, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Archive>, sqlx::Error> {
+        sqlx::query_as::<_, Archive>(
+            r#"
+            SELECT id, object_url, row_count, bytes, sha256, from_created_at, to_created_at,
+                   created_at, restored_at, restored_by
+            FROM activity.notification_archives
+            WHERE from_created_at < $2 AND to_created_at >= $1
+            ORDER BY from_created_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(from)
+        .bind(to)
+        .fetch_all(pool)
+        .await
+    }
+
+    #[instrument(skip(pool))]
+    pub async fn find(pool: &PgPool, id: Uuid) -> Result<Option<Archive>, sqlx::Error> {
+        sqlx::query_as::<_, Archive>(
+            r#"
+            SELECT id, object_url, row_count, bytes, sha256, from_created_at, to_created_at,
+                   created_at, restored_at, restored_by
+            FROM activity.notification_archives
+            WHERE id = $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Insert archived rows back into the hot table - returns how many were missing
+    ///
+    /// Rows that still (or again) exist are left alone. Columns added after the export
+    /// come back NULL.
+    #[instrument(skip(pool, rows), fields(id = %id, rows = rows.len()))]
+    pub async fn restore(
+        pool: &PgPool,
+        id: Uuid,
+        rows: &[serde_json::Value],
+        restored_by: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let start = Instant::now();
+        let mut tx = pool.begin().await?;
+
+        let mut restored = 0;
+        for chunk in rows.chunks(RESTORE_CHUNK) {
+            restored += sqlx::query(
+                r#"
+                INSERT INTO activity.notifications
+                SELECT * FROM jsonb_populate_recordset(NULL::activity.notifications, $1)
+                ON CONFLICT (id) DO NOTHING
+                "#,
+            )
+            .persistent(super::prepared_statements())
+            .bind(Json(chunk))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        sqlx::query("UPDATE activity.notification_archives SET restored_at = now(), restored_by = $2 WHERE id = $1")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .bind(restored_by)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        debug!(
+            id = %id,
+            restored = restored,
+            duration_ms = start.elapsed().as_millis() as u64,
+            "DB restore_archive: completed"
+        );
+        Ok(restored)
+    }
+}
+
+/// One notification as exported (`to_jsonb` of the full row)
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ArchivedRow {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub row: serde_json::Value,
+}
+
+/// Manifest entry for an object about to be committed
+#[derive(Debug, Clone)]
+pub struct NewArchive {
+    pub id: Uuid,
+    pub object_url: String,
+    pub row_count: i32,
+    pub bytes: i64,
+    pub sha256: String,
+    pub from_created_at: DateTime<Utc>,
+    pub to_created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Archive {
+    pub id: Uuid,
+    pub object_url: String,
+    pub row_count: i32,
+    pub bytes: i64,
+    pub sha256: String,
+    pub from_created_at: DateTime<Utc>,
+    pub to_created_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub restored_at: Option<DateTime<Utc>>,
+    pub restored_by: Option<String>,
+}
@@ -0,0 +1,157 @@
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+pub struct AttemptQueries;
+
+/// One delivery attempt to record (channel + outcome + rendering template and variant)
+#[derive(Debug, Clone)]
+pub struct NewAttempt<'a> {
+    pub notification_id: Uuid,
+    pub channel: &'a str,
+    pub outcome: &'a str,
+    pub detail: Option<&'a str>,
+    pub template_key: Option<&'a str>,
+    pub template_version: Option<i32>,
+    pub experiment_id: Option<Uuid>,
+    pub variant: Option<&'a str>,
+    /// FCM message name of a delivered push
+    pub provider_message_id: Option<&'a str>,
+}
+
+impl AttemptQueries {
+    /// Record a delivery attempt
+    #[instrument(skip(pool, attempt), fields(id = %attempt.notification_id, channel = %attempt.channel, outcome = %attempt.outcome))]
+    pub async fn record(pool: &PgPool, attempt: &NewAttempt<'_>) -> Result<(), sqlx::Error> {
+        trace!(
+            "DB record_attempt: {} via {} -> {}",
+            attempt.notification_id, attempt.channel, attempt.outcome
+        );
+        let start = Instant::now();
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO activity.notification_attempts
+                (notification_id, channel, outcome, detail, template_key, template_version, experiment_id, variant,
+                 provider_message_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(attempt.notification_id)
+        .bind(attempt.channel)
+        .bind(attempt.outcome)
+        .bind(attempt.detail)
+        .bind(attempt.template_key)
+        .bind(attempt.template_version)
+        .bind(attempt.experiment_id)
+        .bind(attempt.variant)
+        .bind(attempt.provider_message_id)
+        .execute(pool)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok(_) => {
+                debug!(
+                    id = %attempt.notification_id,
+                    duration_ms = duration.as_millis() as u64,
+                    "DB record_attempt: completed"
+                );
+            }
+            Err(e) => {
+                error!(
+                    id = %attempt.notification_id,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB record_attempt: insert failed"
+                );
+            }
+        }
+
+        result.map(|_| ())
+    }
+
+    /// List attempts for a notification (oldest first)
+    #[instrument(skip(pool), fields(id = %notification_id))]
+    pub async fn list_for_notification(
+        pool: &PgPool,
+        notification_id: Uuid,
+    ) -> Result<Vec<DeliveryAttempt>, sqlx::Error> {
+        trace!("DB list_attempts: fetching attempts for {}", notification_id);
+
+        sqlx::query_as::<_, DeliveryAttempt>(
+            r#"
+            SELECT channel, outcome, detail, template_key, template_version, variant, provider_message_id, attempted_at
+            FROM activity.notification_attempts
+            WHERE notification_id = $1
+            ORDER BY attempted_at, id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(notification_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Attempts recorded after `after_id` (oldest first), for tailing the delivery log
+    ///
+    /// Without a cursor the newest `limit` attempts are returned, still oldest first.
+    #[instrument(skip(pool))]
+    pub async fn list_since(pool: &PgPool, after_id: Option<i64>, limit: i64) -> Result<Vec<AttemptEvent>, sqlx::Error> {
+        trace!("DB list_attempts_since: after {:?}", after_id);
+
+        sqlx::query_as::<_, AttemptEvent>(
+            r#"
+            SELECT * FROM (
+                SELECT a.id, a.notification_id, n.tenant_id, n.user_id, n.notification_type::text AS notification_type,
+                       a.channel, a.outcome, a.detail, a.provider_message_id, a.attempted_at
+                FROM activity.notification_attempts a
+                LEFT JOIN activity.notifications n ON n.id = a.notification_id
+                WHERE $1::bigint IS NULL OR a.id > $1
+                ORDER BY CASE WHEN $1::bigint IS NULL THEN -a.id ELSE a.id END
+                LIMIT $2
+            ) recent
+            ORDER BY id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DeliveryAttempt {
+    pub channel: String,
+    pub outcome: String,
+    pub detail: Option<String>,
+    pub template_key: Option<String>,
+    pub template_version: Option<i32>,
+    pub variant: Option<String>,
+    pub provider_message_id: Option<String>,
+    pub attempted_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// One attempt with its notification, as tailed by `notifyctl tail`
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AttemptEvent {
+    /// Cursor for the next poll
+    pub id: i64,
+    pub notification_id: Uuid,
+    /// None once the notification was archived
+    pub tenant_id: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub notification_type: Option<String>,
+    pub channel: String,
+    pub outcome: String,
+    pub detail: Option<String>,
+    /// FCM message name of a delivered push
+    pub provider_message_id: Option<String>,
+    pub attempted_at: chrono::DateTime<chrono::Utc>,
+}
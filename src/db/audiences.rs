@@ -0,0 +1,176 @@
+use super::schema::copied_columns;
+use crate::models::Audience;
+use serde::{Deserialize, Serialize};
+use sqlx::{Acquire, PgPool, Postgres};
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+/// Organization and team membership for `org:` / `team:` audiences
+pub struct AudienceQueries;
+
+/// One member of an organization, optionally in a team
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudienceMember {
+    pub user_id: Uuid,
+    /// None = member of the organization outside any team
+    #[serde(default)]
+    pub team_id: Option<String>,
+}
+
+/// What a sync changed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AudienceSync {
+    pub added: u64,
+    pub removed: u64,
+    pub members: u64,
+}
+
+impl AudienceQueries {
+    /// Replace an organization's members (and their teams) with `members`
+    ///
+    /// One transaction: a notification expanded meanwhile sees the old or the new members,
+    /// never half of them.
+    #[instrument(skip(pool, members), fields(members = members.len()))]
+    pub async fn sync_org(
+        pool: &PgPool,
+        tenant_id: &str,
+        org_id: &str,
+        members: &[AudienceMember],
+    ) -> Result<AudienceSync, sqlx::Error> {
+        let user_ids: Vec<Uuid> = members.iter().map(|m| m.user_id).collect();
+        let team_ids: Vec<String> = members.iter().map(|m| m.team_id.clone().unwrap_or_default()).collect();
+
+        let mut tx = pool.begin().await?;
+        let removed = sqlx::query(
+            r#"
+            DELETE FROM activity.audience_members a
+            WHERE a.tenant_id = $1 AND a.org_id = $2
+              AND NOT EXISTS (
+                  SELECT 1 FROM unnest($3::uuid[], $4::text[]) AS m(user_id, team_id)
+                  WHERE m.user_id = a.user_id AND m.team_id = a.team_id
+              )
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(org_id)
+        .bind(&user_ids)
+        .bind(&team_ids)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let added = sqlx::query(
+            r#"
+            INSERT INTO activity.audience_members (tenant_id, org_id, team_id, user_id)
+            SELECT $1, $2, m.team_id, m.user_id
+            FROM unnest($3::uuid[], $4::text[]) AS m(user_id, team_id)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(org_id)
+        .bind(&user_ids)
+        .bind(&team_ids)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let total: i64 = sqlx::query_scalar(
+            "SELECT count(DISTINCT user_id) FROM activity.audience_members WHERE tenant_id = $1 AND org_id = $2",
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(org_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        debug!(org_id = %org_id, added = added, removed = removed, "DB sync_audience: completed");
+        Ok(AudienceSync { added, removed, members: total as u64 })
+    }
+
+    /// Users in an organization or team (from the synced table)
+    #[instrument(skip(pool))]
+    pub async fn members(pool: &PgPool, tenant_id: &str, audience: &Audience<'_>) -> Result<Vec<Uuid>, sqlx::Error> {
+        trace!("DB audience_members: resolving {}:{}", audience.scope(), audience.id());
+
+        let sql = match audience {
+            Audience::Org(_) => {
+                "SELECT DISTINCT user_id FROM activity.audience_members WHERE tenant_id = $1 AND org_id = $2"
+            }
+            Audience::Team(_) => {
+                "SELECT DISTINCT user_id FROM activity.audience_members WHERE tenant_id = $1 AND team_id = $2"
+            }
+        };
+        sqlx::query_scalar(sql)
+            .persistent(super::prepared_statements())
+            .bind(tenant_id)
+            .bind(audience.id())
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Copy an audience notification to each of `members` and mark it processed - returns the copies
+    ///
+    /// Same rules as `TopicQueries::expand`: one transaction, the actor skipped, no
+    /// callback_url on the copies.
+    #[instrument(skip(conn, members), fields(id = %id, members = members.len()))]
+    pub async fn expand(
+        conn: impl Acquire<'_, Database = Postgres>,
+        id: Uuid,
+        members: &[Uuid],
+    ) -> Result<u64, sqlx::Error> {
+        trace!("DB expand_audience: fanning out {}", id);
+        let start = Instant::now();
+
+        let mut tx = conn.begin().await?;
+        // Another replica expanding the same row waits here, then finds it processed
+        sqlx::query("SELECT 1 FROM activity.notifications WHERE id = $1 FOR UPDATE")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let created = sqlx::query(&format!(
+            r#"
+            INSERT INTO activity.notifications (id, user_id, audience, region, {})
+            SELECT gen_random_uuid(), m.user_id, n.audience,
+                   COALESCE(activity.home_region(n.tenant_id, m.user_id), n.region), {}
+            FROM activity.notifications n, (SELECT DISTINCT unnest($2::uuid[]) AS user_id) m
+            WHERE n.id = $1 AND n.is_processed = false
+              AND m.user_id <> '00000000-0000-0000-0000-000000000000'
+              AND m.user_id IS DISTINCT FROM n.actor_user_id
+            "#,
+            copied_columns(""),
+            copied_columns("n")
+        ))
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(members)
+        .execute(&mut *tx)
+        .await;
+
+        let created = match created {
+            Ok(r) => r.rows_affected(),
+            Err(e) => {
+                error!(id = %id, error = %e, "DB expand_audience: insert failed");
+                return Err(e);
+            }
+        };
+
+        sqlx::query("SELECT activity.sp_notification_success($1)")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        debug!(
+            id = %id,
+            created = created,
+            duration_ms = start.elapsed().as_millis() as u64,
+            "DB expand_audience: completed"
+        );
+        Ok(created)
+    }
+}
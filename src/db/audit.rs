@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+
+pub struct AuditQueries;
+
+impl AuditQueries {
+    /// Append an entry to the admin audit log
+    #[instrument(skip(pool, params))]
+    pub async fn record(
+        pool: &PgPool,
+        actor: &str,
+        action: &str,
+        params: &serde_json::Value,
+    ) -> Result<(), sqlx::Error> {
+        trace!("DB record_audit: {} by {}", action, actor);
+        let start = Instant::now();
+
+        let result = sqlx::query("INSERT INTO activity.admin_audit_log (actor, action, params) VALUES ($1, $2, $3)")
+            .persistent(super::prepared_statements())
+            .bind(actor)
+            .bind(action)
+            .bind(params)
+            .execute(pool)
+            .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok(_) => {
+                debug!(
+                    actor = %actor,
+                    action = %action,
+                    duration_ms = duration.as_millis() as u64,
+                    "DB record_audit: completed"
+                );
+            }
+            Err(e) => {
+                error!(
+                    actor = %actor,
+                    action = %action,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB record_audit: query failed"
+                );
+            }
+        }
+
+        result.map(|_| ())
+    }
+
+    /// Newest entries first; `before` is the id cursor from the previous page
+    #[instrument(skip(pool))]
+    pub async fn list(
+        pool: &PgPool,
+        actor: Option<&str>,
+        before: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<AuditEntry>, sqlx::Error> {
+        trace!("DB list_audit: before {:?}, limit {}", before, limit);
+
+        sqlx::query_as::<_, AuditEntry>(
+            r#"
+            SELECT id, actor, action, params, created_at
+            FROM activity.admin_audit_log
+            WHERE ($1::text IS NULL OR actor = $1)
+              AND ($2::bigint IS NULL OR id < $2)
+            ORDER BY id DESC
+            LIMIT $3
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(actor)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor: String,
+    pub action: String,
+    pub params: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
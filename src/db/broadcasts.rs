@@ -0,0 +1,263 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Acquire, PgExecutor, PgPool, Postgres, Transaction};
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+/// `created_by` of the per-user copies of a broadcast (followed by its id)
+pub const BROADCAST_CREATOR_PREFIX: &str = "broadcast:";
+
+pub struct BroadcastQueries;
+
+impl BroadcastQueries {
+    /// Claim a broadcast fingerprint - false when another broadcast took it within `window_secs`
+    ///
+    /// The upsert locks the fingerprint row, so of two identical broadcasts claimed at the
+    /// same time only one wins. The owner reclaims it on a redelivery; `force` always wins
+    /// (intentional re-send), and restarts the window for the copies after it.
+    #[instrument(skip(executor, content_hash))]
+    pub async fn claim(
+        executor: impl PgExecutor<'_>,
+        tenant_id: &str,
+        content_hash: &str,
+        id: Uuid,
+        window_secs: u64,
+        force: bool,
+    ) -> Result<bool, sqlx::Error> {
+        let claimed = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO activity.broadcast_fingerprints (tenant_id, content_hash, notification_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (tenant_id, content_hash) DO UPDATE
+            SET notification_id = EXCLUDED.notification_id,
+                sent_at = NOW()
+            WHERE $5
+               OR broadcast_fingerprints.notification_id = EXCLUDED.notification_id
+               OR broadcast_fingerprints.sent_at < NOW() - make_interval(secs => $4)
+            RETURNING notification_id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(content_hash)
+        .bind(id)
+        .bind(window_secs as f64)
+        .bind(force)
+        .fetch_optional(executor)
+        .await?;
+
+        debug!(id = %id, claimed = claimed.is_some(), "DB claim_broadcast_fingerprint: completed");
+        Ok(claimed.is_some())
+    }
+
+    /// Start the per-user fan-out of a broadcast and mark it processed - returns the recipients
+    ///
+    /// One transaction (a savepoint inside a claim), like a topic expansion: a redelivered
+    /// broadcast finds its fan-out already there and doesn't start a second one.
+    #[instrument(skip(conn), fields(id = %id))]
+    pub async fn start_fanout(
+        conn: impl Acquire<'_, Database = Postgres>,
+        id: Uuid,
+        tenant_id: &str,
+    ) -> Result<i32, sqlx::Error> {
+        let mut tx = conn.begin().await?;
+        let total = sqlx::query_scalar::<_, i32>(
+            r#"
+            WITH started AS (
+                INSERT INTO activity.broadcast_fanouts (notification_id, tenant_id, total_recipients)
+                SELECT $1, $2, COUNT(DISTINCT user_id)::int
+                FROM activity.user_devices
+                WHERE tenant_id = $2
+                ON CONFLICT (notification_id) DO NOTHING
+                RETURNING total_recipients
+            )
+            SELECT total_recipients FROM started
+            UNION ALL
+            SELECT total_recipients FROM activity.broadcast_fanouts WHERE notification_id = $1
+            LIMIT 1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(tenant_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("SELECT activity.sp_notification_success($1)")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        debug!(id = %id, total = total, "DB start_broadcast_fanout: completed");
+        Ok(total)
+    }
+
+    /// Fan-out progress of a broadcast - None when it wasn't fanned out per user
+    #[instrument(skip(pool))]
+    pub async fn find_fanout(pool: &PgPool, id: Uuid) -> Result<Option<BroadcastFanout>, sqlx::Error> {
+        sqlx::query_as::<_, BroadcastFanout>(
+            r#"
+            SELECT notification_id, tenant_id, status, total_recipients, queued_count, cursor,
+                   started_at, last_fanout_at, completed_at
+            FROM activity.broadcast_fanouts
+            WHERE notification_id = $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Broadcasts still being fanned out, oldest first
+    pub async fn running(pool: &PgPool) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar::<_, Uuid>(
+            "SELECT notification_id FROM activity.broadcast_fanouts WHERE status = 'running' ORDER BY started_at",
+        )
+        .persistent(super::prepared_statements())
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Lock a running fan-out for a chunk (other runners skip it)
+    pub async fn claim_running(
+        tx: &mut Transaction<'_, Postgres>,
+        id: Uuid,
+    ) -> Result<Option<BroadcastFanout>, sqlx::Error> {
+        sqlx::query_as::<_, BroadcastFanout>(
+            r#"
+            SELECT notification_id, tenant_id, status, total_recipients, queued_count, cursor,
+                   started_at, last_fanout_at, completed_at
+            FROM activity.broadcast_fanouts
+            WHERE notification_id = $1 AND status = 'running'
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    /// Copy the broadcast to the next `limit` users after the cursor and move the cursor
+    ///
+    /// Completes the fan-out when fewer than `limit` users were left. Returns the number of
+    /// notifications created and whether the fan-out is now completed. Each copy is due after
+    /// a random 0..`jitter_secs`.
+    pub async fn fan_out(
+        tx: &mut Transaction<'_, Postgres>,
+        fanout: &BroadcastFanout,
+        limit: i64,
+        jitter_secs: u64,
+    ) -> Result<(u64, bool), sqlx::Error> {
+        trace!("DB fan_out_broadcast: {} after {:?}", fanout.notification_id, fanout.cursor);
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, (i64, Option<Uuid>)>(
+            r#"
+            WITH batch AS (
+                SELECT DISTINCT user_id
+                FROM activity.user_devices
+                WHERE tenant_id = $2 AND ($3::uuid IS NULL OR user_id > $3)
+                ORDER BY user_id
+                LIMIT $4
+            ),
+            created AS (
+                INSERT INTO activity.notifications (
+                    id, user_id, actor_user_id, notification_type, target_type, target_id, title, message,
+                    payload, deep_link, priority, group_key, message_key, message_args, template_key,
+                    tenant_id, created_by, event_source, actions, attachments, payload_zstd, payload_encoding,
+                    expires_at, deadline, deliver_at, region
+                )
+                SELECT gen_random_uuid(), batch.user_id, n.actor_user_id, n.notification_type, n.target_type,
+                       n.target_id, n.title, n.message, n.payload, n.deep_link, n.priority, n.group_key,
+                       n.message_key, n.message_args, n.template_key, n.tenant_id, $5 || n.id::text,
+                       'notifications-service/broadcast', n.actions, n.attachments, n.payload_zstd,
+                       n.payload_encoding, n.expires_at, n.deadline, now() + make_interval(secs => random() * $6),
+                       COALESCE(activity.home_region(n.tenant_id, batch.user_id), n.region)
+                FROM activity.notifications n, batch
+                WHERE n.id = $1
+                RETURNING user_id
+            )
+            SELECT COUNT(*), (array_agg(user_id ORDER BY user_id DESC))[1] FROM created
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(fanout.notification_id)
+        .bind(&fanout.tenant_id)
+        .bind(fanout.cursor)
+        .bind(limit)
+        .bind(BROADCAST_CREATOR_PREFIX)
+        .bind(jitter_secs as f64)
+        .fetch_one(&mut **tx)
+        .await;
+
+        let (created, last_user) = match result {
+            Ok(row) => row,
+            Err(e) => {
+                error!(id = %fanout.notification_id, error = %e, "DB fan_out_broadcast: insert failed");
+                return Err(e);
+            }
+        };
+        let completed = created < limit;
+
+        sqlx::query(
+            r#"
+            UPDATE activity.broadcast_fanouts
+            SET queued_count = queued_count + $2,
+                cursor = COALESCE($3, cursor),
+                last_fanout_at = now(),
+                status = CASE WHEN $4 THEN 'completed' ELSE status END,
+                completed_at = CASE WHEN $4 THEN now() ELSE completed_at END
+            WHERE notification_id = $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(fanout.notification_id)
+        .bind(created as i32)
+        .bind(last_user)
+        .bind(completed)
+        .execute(&mut **tx)
+        .await?;
+
+        debug!(
+            id = %fanout.notification_id,
+            created = created,
+            completed = completed,
+            duration_ms = start.elapsed().as_millis() as u64,
+            "DB fan_out_broadcast: completed"
+        );
+        Ok((created as u64, completed))
+    }
+}
+
+/// Progress of a per-user broadcast fan-out
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct BroadcastFanout {
+    pub notification_id: Uuid,
+    pub tenant_id: String,
+    /// running / completed
+    pub status: String,
+    /// Users with a device when the fan-out started
+    pub total_recipients: i32,
+    /// Copies created so far
+    pub queued_count: i32,
+    /// Last user_id queued
+    pub cursor: Option<Uuid>,
+    pub started_at: DateTime<Utc>,
+    pub last_fanout_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl BroadcastFanout {
+    /// 0-100; a completed fan-out is 100 even if devices came and went meanwhile
+    pub fn percent_complete(&self) -> f64 {
+        if self.status == "completed" || self.total_recipients == 0 {
+            return 100.0;
+        }
+        (self.queued_count as f64 * 100.0 / self.total_recipients as f64).min(100.0)
+    }
+}
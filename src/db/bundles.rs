@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use tracing::{debug, instrument};
+use uuid::Uuid;
+
+/// Bundling windows per (tenant, user, type) and the rows held for them (BUNDLE_WINDOW_SECS)
+pub struct BundleQueries;
+
+/// The open window of a (tenant, user, type)
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct BundleWindow {
+    pub window_ends_at: DateTime<Utc>,
+    /// The notification that opened it: delivered, while the others are held
+    pub opened_by: Uuid,
+}
+
+impl BundleQueries {
+    /// The window `id` falls in, opening one for `id` when none is open
+    ///
+    /// The upsert locks the window row, so of two replicas racing for the same user exactly
+    /// one opens it. Asking again for the row that opened it returns the same window.
+    #[instrument(skip(pool))]
+    pub async fn open(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        notification_type: &str,
+        id: Uuid,
+        window_secs: u64,
+    ) -> Result<BundleWindow, sqlx::Error> {
+        sqlx::query_as::<_, BundleWindow>(
+            r#"
+            INSERT INTO activity.notification_bundles
+                (tenant_id, user_id, notification_type, window_ends_at, opened_by)
+            VALUES ($1, $2, $3, now() + make_interval(secs => $5), $4)
+            ON CONFLICT (tenant_id, user_id, notification_type) DO UPDATE SET
+                window_ends_at = CASE WHEN notification_bundles.window_ends_at <= now()
+                                      THEN EXCLUDED.window_ends_at
+                                      ELSE notification_bundles.window_ends_at END,
+                opened_by = CASE WHEN notification_bundles.window_ends_at <= now()
+                                 THEN EXCLUDED.opened_by
+                                 ELSE notification_bundles.opened_by END
+            RETURNING window_ends_at, opened_by
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(notification_type)
+        .bind(id)
+        .bind(window_secs as f64)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Hold a row until its window ends (keeps the first `bundled_at` when held again)
+    #[instrument(skip(executor))]
+    pub async fn hold(executor: impl PgExecutor<'_>, id: Uuid, until: DateTime<Utc>) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE activity.notifications
+             SET deliver_at = $2, bundled_at = COALESCE(bundled_at, now()), updated_at = now()
+             WHERE id = $1 AND is_processed = false",
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(until)
+        .execute(executor)
+        .await
+        .map(|_| ())
+    }
+
+    /// Mark the other due held rows of the bundle as delivered with `lead`; the bundle's size
+    ///
+    /// Counts rows absorbed by an earlier attempt of the same lead too, so a retried bundle
+    /// keeps its count. Rows held for a later window are not due yet and stay.
+    #[instrument(skip(executor))]
+    pub async fn absorb(
+        executor: impl PgExecutor<'_>,
+        lead: Uuid,
+        tenant_id: &str,
+        user_id: Uuid,
+        notification_type: &str,
+    ) -> Result<i64, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            WITH absorbed AS (
+                UPDATE activity.notifications
+                SET is_processed = true, bundled_into = $1, updated_at = now()
+                WHERE tenant_id = $2
+                  AND user_id = $3
+                  AND notification_type::text = $4
+                  AND id <> $1
+                  AND is_processed = false
+                  AND bundled_at IS NOT NULL
+                  AND deliver_at <= now()
+                RETURNING id
+            )
+            SELECT 1
+                + (SELECT count(*) FROM absorbed)
+                + (SELECT count(*) FROM activity.notifications WHERE bundled_into = $1)
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(lead)
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(notification_type)
+        .fetch_one(executor)
+        .await?;
+        debug!(lead = %lead, count = count, "DB absorb: bundle assembled");
+        Ok(count)
+    }
+
+    /// Already delivered as part of another row's bundle
+    #[instrument(skip(pool))]
+    pub async fn is_absorbed(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM activity.notifications WHERE id = $1 AND bundled_into IS NOT NULL)",
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    }
+}
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+pub struct BusDeliveryQueries;
+
+impl BusDeliveryQueries {
+    /// Record a publish that reached `delivered_to` connections (from the instance's `region`)
+    ///
+    /// Takes the pool, never the claim: the record has to outlive a rolled-back claim.
+    #[instrument(skip(pool), fields(id = %notification_id))]
+    pub async fn record(
+        pool: &PgPool,
+        delivery_id: Uuid,
+        notification_id: Uuid,
+        delivered_to: i32,
+        region: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO activity.bus_deliveries (delivery_id, notification_id, delivered_to, region)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (delivery_id) DO NOTHING
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(delivery_id)
+        .bind(notification_id)
+        .bind(delivered_to)
+        .bind(region)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    /// The newest unmarked publish for a notification since `since`
+    #[instrument(skip(pool), fields(id = %notification_id))]
+    pub async fn latest(
+        pool: &PgPool,
+        notification_id: Uuid,
+        since: DateTime<Utc>,
+    ) -> Result<Option<BusDelivery>, sqlx::Error> {
+        sqlx::query_as::<_, BusDelivery>(
+            r#"
+            SELECT delivery_id, notification_id, delivered_to, published_at, region
+            FROM activity.bus_deliveries
+            WHERE notification_id = $1 AND published_at >= $2
+            ORDER BY published_at DESC
+            LIMIT 1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(notification_id)
+        .bind(since)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Drop a notification's publish records (the client never acked them)
+    #[instrument(skip(pool), fields(id = %notification_id))]
+    pub async fn forget(pool: &PgPool, notification_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM activity.bus_deliveries WHERE notification_id = $1")
+            .persistent(super::prepared_statements())
+            .bind(notification_id)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    }
+
+    /// Mark the notification delivered via the Bus - false when it was marked already
+    ///
+    /// Only an unprocessed row is marked, so of two runs that published the same row only
+    /// one counts it. The publish records go with the mark.
+    #[instrument(skip(executor), fields(id = %notification_id))]
+    pub async fn mark(
+        executor: impl PgExecutor<'_>,
+        notification_id: Uuid,
+        delivery_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error> {
+        trace!("DB mark_bus_delivered: {} with delivery {:?}", notification_id, delivery_id);
+        let start = Instant::now();
+
+        let result = sqlx::query_scalar::<_, bool>(
+            r#"
+            WITH marked AS (
+                UPDATE activity.notifications
+                SET is_processed = true,
+                    bus_delivery_id = $2,
+                    updated_at = now()
+                WHERE id = $1 AND is_processed = false
+                RETURNING id
+            ),
+            consumed AS (
+                DELETE FROM activity.bus_deliveries
+                WHERE notification_id = $1 AND EXISTS (SELECT 1 FROM marked)
+            )
+            SELECT EXISTS (SELECT 1 FROM marked)
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(notification_id)
+        .bind(delivery_id)
+        .fetch_one(executor)
+        .await;
+
+        match &result {
+            Ok(marked) => debug!(
+                id = %notification_id,
+                marked = marked,
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB mark_bus_delivered: completed"
+            ),
+            Err(e) => error!(id = %notification_id, error = %e, "DB mark_bus_delivered: query failed"),
+        }
+        result
+    }
+}
+
+/// A recorded Bus publish (`activity.bus_deliveries`)
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct BusDelivery {
+    pub delivery_id: Uuid,
+    pub notification_id: Uuid,
+    pub delivered_to: i32,
+    pub published_at: DateTime<Utc>,
+    /// Region of the instance that published (migration 069)
+    #[sqlx(default)]
+    pub region: Option<String>,
+}
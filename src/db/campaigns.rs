@@ -0,0 +1,624 @@
+use crate::targeting::{DeviceFilter, FilterParam};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+/// `created_by` prefix of fanned-out rows (`campaign:<campaign id>`)
+pub const CAMPAIGN_CREATOR_PREFIX: &str = "campaign:";
+
+/// Longest a segment query may run when a campaign starts
+const SEGMENT_STATEMENT_TIMEOUT: &str = "60s";
+
+pub struct CampaignQueries;
+
+impl CampaignQueries {
+    /// List all campaigns, newest first
+    #[instrument(skip(pool))]
+    pub async fn list(pool: &PgPool) -> Result<Vec<Campaign>, sqlx::Error> {
+        trace!("DB list_campaigns");
+
+        sqlx::query_as::<_, Campaign>(
+            r#"
+            SELECT id, tenant_id, name, audience, notification_type, title, message, template_key,
+                   message_key, message_args, payload, deep_link, priority, start_at, rate_per_minute,
+                   status, total_recipients, queued_count, last_fanout_at, last_error, created_by,
+                   created_at, started_at, completed_at, device_filter, deliver_at_local, jitter_secs
+            FROM activity.campaigns
+            ORDER BY created_at DESC
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Find a campaign by id
+    #[instrument(skip(pool))]
+    pub async fn find(pool: &PgPool, id: Uuid) -> Result<Option<Campaign>, sqlx::Error> {
+        sqlx::query_as::<_, Campaign>(
+            r#"
+            SELECT id, tenant_id, name, audience, notification_type, title, message, template_key,
+                   message_key, message_args, payload, deep_link, priority, start_at, rate_per_minute,
+                   status, total_recipients, queued_count, last_fanout_at, last_error, created_by,
+                   created_at, started_at, completed_at, device_filter, deliver_at_local, jitter_secs
+            FROM activity.campaigns
+            WHERE id = $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Store a new campaign in status `scheduled`, with its first uploaded recipients
+    #[instrument(skip(pool, campaign, user_ids), fields(name = %campaign.name, recipients = user_ids.len()))]
+    pub async fn create(
+        pool: &PgPool,
+        campaign: &NewCampaign,
+        user_ids: &[Uuid],
+        created_by: &str,
+    ) -> Result<Campaign, sqlx::Error> {
+        trace!("DB create_campaign: '{}' starting at {}", campaign.name, campaign.start_at);
+        let mut tx = pool.begin().await?;
+
+        let mut created = sqlx::query_as::<_, Campaign>(
+            r#"
+            INSERT INTO activity.campaigns (
+                tenant_id, name, audience, notification_type, title, message, template_key,
+                message_key, message_args, payload, deep_link, priority, start_at, rate_per_minute,
+                created_by, device_filter, deliver_at_local, jitter_secs
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+            RETURNING id, tenant_id, name, audience, notification_type, title, message, template_key,
+                   message_key, message_args, payload, deep_link, priority, start_at, rate_per_minute,
+                   status, total_recipients, queued_count, last_fanout_at, last_error, created_by,
+                   created_at, started_at, completed_at, device_filter, deliver_at_local, jitter_secs
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(&campaign.tenant_id)
+        .bind(&campaign.name)
+        .bind(Json(&campaign.audience))
+        .bind(&campaign.notification_type)
+        .bind(&campaign.title)
+        .bind(&campaign.message)
+        .bind(&campaign.template_key)
+        .bind(&campaign.message_key)
+        .bind(&campaign.message_args)
+        .bind(&campaign.payload)
+        .bind(&campaign.deep_link)
+        .bind(&campaign.priority)
+        .bind(campaign.start_at)
+        .bind(campaign.rate_per_minute)
+        .bind(created_by)
+        .bind(&campaign.device_filter)
+        .bind(campaign.deliver_at_local)
+        .bind(campaign.jitter_secs)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Same transaction: the runner must not start the campaign before its recipients exist
+        if !user_ids.is_empty() {
+            Self::insert_recipients(&mut tx, created.id, user_ids).await?;
+            created.total_recipients = Self::count_recipients(&mut tx, created.id).await?;
+            Self::set_total(&mut tx, created.id, created.total_recipients).await?;
+        }
+        tx.commit().await?;
+
+        Ok(created)
+    }
+
+    /// Add uploaded user ids to a campaign that hasn't started yet
+    ///
+    /// Duplicates are ignored. Returns the new recipient total, or None when the
+    /// campaign doesn't exist or is no longer `scheduled`.
+    #[instrument(skip(pool, user_ids), fields(count = user_ids.len()))]
+    pub async fn add_recipients(pool: &PgPool, id: Uuid, user_ids: &[Uuid]) -> Result<Option<i32>, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        let scheduled = sqlx::query_as::<_, (Uuid,)>(
+            "SELECT id FROM activity.campaigns WHERE id = $1 AND status = 'scheduled' FOR UPDATE",
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if scheduled.is_none() {
+            return Ok(None);
+        }
+
+        Self::insert_recipients(&mut tx, id, user_ids).await?;
+        let total = Self::count_recipients(&mut tx, id).await?;
+        Self::set_total(&mut tx, id, total).await?;
+        tx.commit().await?;
+
+        debug!(id = %id, total = total, "DB add_recipients: completed");
+        Ok(Some(total))
+    }
+
+    /// Check that a segment query runs and returns a `user_id` column
+    ///
+    /// Executed read-only with `LIMIT 0`, so nothing is fetched yet.
+    #[instrument(skip(pool, sql))]
+    pub async fn validate_segment(pool: &PgPool, sql: &str) -> Result<(), sqlx::Error> {
+        Self::fetch_segment(pool, sql, 0).await.map(|_| ())
+    }
+
+    /// Run a segment query: up to `limit` distinct user ids
+    ///
+    /// The query comes from an admin and runs read-only with a statement timeout,
+    /// under the service's own database role.
+    #[instrument(skip(pool, sql))]
+    pub async fn fetch_segment(pool: &PgPool, sql: &str, limit: i64) -> Result<Vec<Uuid>, sqlx::Error> {
+        let start = Instant::now();
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("SET TRANSACTION READ ONLY")
+            .persistent(false)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!("SET LOCAL statement_timeout = '{}'", SEGMENT_STATEMENT_TIMEOUT))
+            .persistent(false)
+            .execute(&mut *tx)
+            .await?;
+
+        // The segment is SQL by design; it is only ever embedded as a sub-select
+        let query = format!(
+            "SELECT DISTINCT segment.user_id::uuid FROM ({}) AS segment WHERE segment.user_id IS NOT NULL LIMIT $1",
+            sql.trim().trim_end_matches(';')
+        );
+        let result = sqlx::query_scalar::<_, Uuid>(&query)
+            .persistent(false)
+            .bind(limit)
+            .fetch_all(&mut *tx)
+            .await;
+        tx.rollback().await?;
+
+        match &result {
+            Ok(user_ids) => debug!(
+                count = user_ids.len(),
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB fetch_segment: completed"
+            ),
+            Err(e) => error!(error = %e, "DB fetch_segment: segment query failed"),
+        }
+        result
+    }
+
+    /// Users of a tenant with at least one device matching the filter: up to `limit`
+    #[instrument(skip(pool, filter))]
+    pub async fn fetch_device_audience(
+        pool: &PgPool,
+        tenant_id: &str,
+        filter: &DeviceFilter,
+        limit: i64,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        let start = Instant::now();
+
+        let (predicate, params) = filter.sql("d", 3);
+        let sql = format!(
+            "SELECT DISTINCT d.user_id FROM activity.user_devices d WHERE d.tenant_id = $1 AND {} LIMIT $2",
+            predicate
+        );
+        // Built per filter, so not worth a named statement (as with segments)
+        let mut query = sqlx::query_scalar::<_, Uuid>(&sql)
+            .persistent(false)
+            .bind(tenant_id)
+            .bind(limit);
+        for param in params {
+            query = match param {
+                FilterParam::Version(version) => query.bind(version),
+                FilterParam::Text(text) => query.bind(text),
+            };
+        }
+        let result = query.fetch_all(pool).await;
+
+        match &result {
+            Ok(user_ids) => debug!(
+                count = user_ids.len(),
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB fetch_device_audience: completed"
+            ),
+            Err(e) => error!(error = %e, "DB fetch_device_audience: query failed"),
+        }
+        result
+    }
+
+    /// Pause a scheduled or running campaign - None if it doesn't exist or can't be paused
+    #[instrument(skip(pool))]
+    pub async fn pause(pool: &PgPool, id: Uuid) -> Result<Option<Campaign>, sqlx::Error> {
+        sqlx::query_as::<_, Campaign>(
+            r#"
+            UPDATE activity.campaigns
+            SET status = 'paused'
+            WHERE id = $1 AND status IN ('scheduled', 'running')
+            RETURNING id, tenant_id, name, audience, notification_type, title, message, template_key,
+                   message_key, message_args, payload, deep_link, priority, start_at, rate_per_minute,
+                   status, total_recipients, queued_count, last_fanout_at, last_error, created_by,
+                   created_at, started_at, completed_at, device_filter, deliver_at_local, jitter_secs
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Resume a paused campaign - back to `running`, or `scheduled` if it never started
+    ///
+    /// The throttle restarts from now: time spent paused doesn't build up a burst.
+    #[instrument(skip(pool))]
+    pub async fn resume(pool: &PgPool, id: Uuid) -> Result<Option<Campaign>, sqlx::Error> {
+        sqlx::query_as::<_, Campaign>(
+            r#"
+            UPDATE activity.campaigns
+            SET status = CASE WHEN started_at IS NULL THEN 'scheduled' ELSE 'running' END,
+                last_fanout_at = CASE WHEN started_at IS NULL THEN last_fanout_at ELSE now() END
+            WHERE id = $1 AND status = 'paused'
+            RETURNING id, tenant_id, name, audience, notification_type, title, message, template_key,
+                   message_key, message_args, payload, deep_link, priority, start_at, rate_per_minute,
+                   status, total_recipients, queued_count, last_fanout_at, last_error, created_by,
+                   created_at, started_at, completed_at, device_filter, deliver_at_local, jitter_secs
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Cancel a campaign that hasn't completed - rows already fanned out are still delivered
+    #[instrument(skip(pool))]
+    pub async fn cancel(pool: &PgPool, id: Uuid) -> Result<Option<Campaign>, sqlx::Error> {
+        sqlx::query_as::<_, Campaign>(
+            r#"
+            UPDATE activity.campaigns
+            SET status = 'cancelled', completed_at = now()
+            WHERE id = $1 AND status IN ('scheduled', 'running', 'paused')
+            RETURNING id, tenant_id, name, audience, notification_type, title, message, template_key,
+                   message_key, message_args, payload, deep_link, priority, start_at, rate_per_minute,
+                   status, total_recipients, queued_count, last_fanout_at, last_error, created_by,
+                   created_at, started_at, completed_at, device_filter, deliver_at_local, jitter_secs
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Delivery progress of a campaign's recipients
+    #[instrument(skip(pool))]
+    pub async fn stats(pool: &PgPool, id: Uuid) -> Result<CampaignStats, sqlx::Error> {
+        // A final sp_notification_failure sets updated_at = last_error_at; a later
+        // success bumps updated_at past it, so the comparison separates the two.
+        sqlx::query_as::<_, CampaignStats>(
+            r#"
+            SELECT COUNT(*) AS recipients,
+                   COUNT(r.notification_id) AS queued,
+                   COUNT(*) FILTER (WHERE n.id IS NOT NULL AND NOT n.is_processed) AS pending,
+                   COUNT(*) FILTER (
+                       WHERE n.is_processed AND n.suppressed_at IS NULL
+                         AND (n.last_error_at IS NULL OR n.last_error_at < n.updated_at)
+                   ) AS delivered,
+                   COUNT(*) FILTER (
+                       WHERE n.is_processed AND n.suppressed_at IS NULL
+                         AND n.last_error_at >= n.updated_at
+                   ) AS failed,
+                   COUNT(*) FILTER (WHERE n.suppressed_at IS NOT NULL) AS suppressed
+            FROM activity.campaign_recipients r
+            LEFT JOIN activity.notifications n ON n.id = r.notification_id
+            WHERE r.campaign_id = $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Lock the earliest scheduled campaign whose start time has passed
+    pub async fn claim_startable(tx: &mut Transaction<'_, Postgres>) -> Result<Option<Campaign>, sqlx::Error> {
+        sqlx::query_as::<_, Campaign>(
+            r#"
+            SELECT id, tenant_id, name, audience, notification_type, title, message, template_key,
+                   message_key, message_args, payload, deep_link, priority, start_at, rate_per_minute,
+                   status, total_recipients, queued_count, last_fanout_at, last_error, created_by,
+                   created_at, started_at, completed_at, device_filter, deliver_at_local, jitter_secs
+            FROM activity.campaigns
+            WHERE status = 'scheduled' AND start_at <= now()
+            ORDER BY start_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    /// Insert recipients (duplicates ignored) - returns the number added
+    pub async fn insert_recipients(
+        tx: &mut Transaction<'_, Postgres>,
+        id: Uuid,
+        user_ids: &[Uuid],
+    ) -> Result<u64, sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO activity.campaign_recipients (campaign_id, user_id)
+            SELECT $1, user_id FROM unnest($2::uuid[]) AS uploaded(user_id)
+            ON CONFLICT (campaign_id, user_id) DO NOTHING
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(user_ids)
+        .execute(&mut **tx)
+        .await
+        .map(|r| r.rows_affected())
+    }
+
+    /// Move a claimed campaign to `running` with its final recipient count
+    pub async fn start(tx: &mut Transaction<'_, Postgres>, id: Uuid) -> Result<i32, sqlx::Error> {
+        let total = Self::count_recipients(tx, id).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE activity.campaigns
+            SET status = 'running', started_at = now(), last_fanout_at = now(), total_recipients = $2
+            WHERE id = $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(total)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(total)
+    }
+
+    /// Cancel a campaign the runner can't start (broken segment, too many users)
+    #[instrument(skip(pool))]
+    pub async fn fail(pool: &PgPool, id: Uuid, reason: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE activity.campaigns SET status = 'cancelled', last_error = $2, completed_at = now() WHERE id = $1",
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(reason)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    /// Ids of all running campaigns
+    pub async fn running(pool: &PgPool) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar::<_, Uuid>("SELECT id FROM activity.campaigns WHERE status = 'running' ORDER BY started_at")
+            .persistent(super::prepared_statements())
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Lock a running campaign for a fan-out step (other runners skip it)
+    pub async fn claim_running(
+        tx: &mut Transaction<'_, Postgres>,
+        id: Uuid,
+    ) -> Result<Option<Campaign>, sqlx::Error> {
+        sqlx::query_as::<_, Campaign>(
+            r#"
+            SELECT id, tenant_id, name, audience, notification_type, title, message, template_key,
+                   message_key, message_args, payload, deep_link, priority, start_at, rate_per_minute,
+                   status, total_recipients, queued_count, last_fanout_at, last_error, created_by,
+                   created_at, started_at, completed_at, device_filter, deliver_at_local, jitter_secs
+            FROM activity.campaigns
+            WHERE id = $1 AND status = 'running'
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    /// Insert notifications for the next `limit` recipients of a locked campaign
+    ///
+    /// Marks the campaign completed once no recipient is left. Returns the number
+    /// of notifications created and whether the campaign is now completed.
+    /// A `deliver_at_local` is copied as is: the router resolves it in each recipient's
+    /// timezone, so a bad stored timezone can't fail the batch. With `jitter_secs` each row
+    /// gets its own random delay within the window.
+    pub async fn fan_out(
+        tx: &mut Transaction<'_, Postgres>,
+        campaign: &Campaign,
+        limit: i64,
+    ) -> Result<(u64, bool), sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query(
+            r#"
+            WITH batch AS (
+                SELECT user_id
+                FROM activity.campaign_recipients
+                WHERE campaign_id = $1 AND notification_id IS NULL
+                ORDER BY user_id
+                LIMIT $2
+            ),
+            created AS (
+                INSERT INTO activity.notifications (
+                    id, user_id, notification_type, title, message, payload, deep_link, priority,
+                    template_key, message_key, message_args, tenant_id, created_by, event_source,
+                    device_filter, deliver_at_local, deliver_at
+                )
+                SELECT gen_random_uuid(), batch.user_id, c.notification_type, c.title, c.message,
+                       c.payload, c.deep_link, c.priority, c.template_key, c.message_key, c.message_args,
+                       c.tenant_id, $3 || c.id::text, 'notifications-service/campaign', c.device_filter,
+                       c.deliver_at_local,
+                       GREATEST(now(), c.deliver_at_local AT TIME ZONE 'UTC' - interval '14 hours')
+                           + make_interval(secs => random() * c.jitter_secs)
+                FROM activity.campaigns c, batch
+                WHERE c.id = $1
+                RETURNING id, user_id
+            )
+            UPDATE activity.campaign_recipients r
+            SET notification_id = created.id, queued_at = now()
+            FROM created
+            WHERE r.campaign_id = $1 AND r.user_id = created.user_id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(campaign.id)
+        .bind(limit)
+        .bind(CAMPAIGN_CREATOR_PREFIX)
+        .execute(&mut **tx)
+        .await;
+
+        let created = match result {
+            Ok(r) => r.rows_affected(),
+            Err(e) => {
+                error!(id = %campaign.id, error = %e, "DB fan_out_campaign: insert failed");
+                return Err(e);
+            }
+        };
+
+        let completed = sqlx::query_scalar::<_, bool>(
+            r#"
+            UPDATE activity.campaigns c
+            SET queued_count = queued_count + $2,
+                last_fanout_at = now(),
+                status = CASE WHEN pending.remaining THEN status ELSE 'completed' END,
+                completed_at = CASE WHEN pending.remaining THEN completed_at ELSE now() END
+            FROM (
+                SELECT EXISTS (
+                    SELECT 1 FROM activity.campaign_recipients
+                    WHERE campaign_id = $1 AND notification_id IS NULL
+                ) AS remaining
+            ) pending
+            WHERE c.id = $1
+            RETURNING NOT pending.remaining
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(campaign.id)
+        .bind(created as i32)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        debug!(
+            id = %campaign.id,
+            created = created,
+            completed = completed,
+            duration_ms = start.elapsed().as_millis() as u64,
+            "DB fan_out_campaign: completed"
+        );
+        Ok((created, completed))
+    }
+
+    async fn set_total(tx: &mut Transaction<'_, Postgres>, id: Uuid, total: i32) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE activity.campaigns SET total_recipients = $2 WHERE id = $1")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .bind(total)
+            .execute(&mut **tx)
+            .await
+            .map(|_| ())
+    }
+
+    async fn count_recipients(tx: &mut Transaction<'_, Postgres>, id: Uuid) -> Result<i32, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM activity.campaign_recipients WHERE campaign_id = $1")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .fetch_one(&mut **tx)
+            .await
+            .map(|count| count as i32)
+    }
+}
+
+/// Who a campaign is sent to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CampaignAudience {
+    /// User ids uploaded with the campaign or via `POST .../{id}/recipients`
+    Users,
+    /// Query returning a `user_id` column, resolved when the campaign starts
+    Segment { sql: String },
+    /// Every user with a device matching `device_filter`, resolved when the campaign starts
+    Devices,
+}
+
+/// Campaign as submitted through the API
+#[derive(Debug, Clone)]
+pub struct NewCampaign {
+    pub tenant_id: String,
+    pub name: String,
+    pub audience: CampaignAudience,
+    pub notification_type: String,
+    pub title: String,
+    pub message: Option<String>,
+    pub template_key: Option<String>,
+    pub message_key: Option<String>,
+    pub message_args: Option<serde_json::Value>,
+    pub payload: Option<serde_json::Value>,
+    pub deep_link: Option<String>,
+    pub priority: String,
+    pub start_at: DateTime<Utc>,
+    pub rate_per_minute: i32,
+    pub device_filter: Option<Vec<String>>,
+    pub deliver_at_local: Option<NaiveDateTime>,
+    pub jitter_secs: i32,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Campaign {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub name: String,
+    pub audience: Json<CampaignAudience>,
+    pub notification_type: String,
+    pub title: String,
+    pub message: Option<String>,
+    pub template_key: Option<String>,
+    pub message_key: Option<String>,
+    pub message_args: Option<serde_json::Value>,
+    pub payload: Option<serde_json::Value>,
+    pub deep_link: Option<String>,
+    pub priority: String,
+    pub start_at: DateTime<Utc>,
+    pub rate_per_minute: i32,
+    pub status: String,
+    pub total_recipients: i32,
+    pub queued_count: i32,
+    pub last_fanout_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Push only to devices meeting these conditions (`crate::targeting`)
+    pub device_filter: Option<Vec<String>>,
+    /// Wall-clock send time in each recipient's timezone (None = as soon as queued)
+    pub deliver_at_local: Option<NaiveDateTime>,
+    /// Each notification is delayed by a random 0..jitter_secs after it is queued
+    pub jitter_secs: i32,
+}
+
+/// Recipient counts per delivery state
+#[derive(Debug, Clone, Default, Serialize, sqlx::FromRow)]
+pub struct CampaignStats {
+    pub recipients: i64,
+    /// Notification rows created so far
+    pub queued: i64,
+    /// Queued but not yet processed by the worker
+    pub pending: i64,
+    pub delivered: i64,
+    /// Gave up after MAX_RETRIES
+    pub failed: i64,
+    /// Skipped by user preferences, mutes or quiet hours
+    pub suppressed: i64,
+}
@@ -0,0 +1,59 @@
+use serde_json::Value;
+use sqlx::PgPool;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+pub struct ClientErrorQueries;
+
+impl ClientErrorQueries {
+    /// Record a client's rendering/processing failure - None if the notification isn't theirs
+    ///
+    /// Returns the delivery attempt it was filed against (the latest delivered one on
+    /// `channel`, None when none was recorded). Broadcast rows (nil user id) can be reported
+    /// by any user of the tenant, as with opens.
+    #[instrument(skip(pool, context), fields(id = %id, user_id = %user_id))]
+    pub async fn record(
+        pool: &PgPool,
+        id: Uuid,
+        user_id: Uuid,
+        tenant_id: &str,
+        channel: &str,
+        code: &str,
+        context: Option<&Value>,
+    ) -> Result<Option<Option<i64>>, sqlx::Error> {
+        trace!("DB record_client_error: notification {} by user {}: {}", id, user_id, code);
+
+        let result = sqlx::query_scalar::<_, Option<i64>>(
+            r#"
+            INSERT INTO activity.notification_client_errors
+                (notification_id, attempt_id, user_id, tenant_id, notification_type, channel, code, context)
+            SELECT n.id,
+                   (SELECT a.id FROM activity.notification_attempts a
+                    WHERE a.notification_id = n.id AND a.channel = $4 AND a.outcome = 'delivered'
+                    ORDER BY a.attempted_at DESC, a.id DESC
+                    LIMIT 1),
+                   $2, n.tenant_id, n.notification_type, $4, $5, $6
+            FROM activity.notifications n
+            WHERE n.id = $1
+              AND n.tenant_id = $3
+              AND (n.user_id = $2 OR n.user_id = '00000000-0000-0000-0000-000000000000')
+            RETURNING attempt_id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(user_id)
+        .bind(tenant_id)
+        .bind(channel)
+        .bind(code)
+        .bind(context)
+        .fetch_optional(pool)
+        .await;
+
+        match &result {
+            Ok(recorded) => debug!(id = %id, recorded = recorded.is_some(), "DB record_client_error: completed"),
+            Err(e) => error!(id = %id, error = %e, "DB record_client_error: insert failed"),
+        }
+        result
+    }
+}
@@ -0,0 +1,71 @@
+//! zstd compression of large payloads (PAYLOAD_COMPRESSION_THRESHOLD_BYTES).
+//!
+//! A payload whose JSON is larger than the threshold is stored in `payload_zstd` with
+//! `payload_encoding = 'zstd'` and a NULL `payload` (migration 065). Every query that reads
+//! `payload` for delivery or for clients also reads `payload_zstd`, and the row is inflated
+//! right after decoding, so the rest of the code only ever sees `payload`. Fan-out copies
+//! copy both columns as they are. Compression is process-wide, like the statement cache.
+
+use serde_json::Value;
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use tracing::error;
+use uuid::Uuid;
+
+/// `payload_encoding` of a compressed row
+pub const ZSTD: &str = "zstd";
+
+/// Payloads whose JSON is larger than this are compressed (0 = never)
+static THRESHOLD_BYTES: AtomicUsize = AtomicUsize::new(0);
+static LEVEL: AtomicI32 = AtomicI32::new(zstd::DEFAULT_COMPRESSION_LEVEL);
+
+/// Apply PAYLOAD_COMPRESSION_THRESHOLD_BYTES / PAYLOAD_COMPRESSION_LEVEL (reading works either way)
+pub fn configure(threshold_bytes: usize, level: i32) {
+    THRESHOLD_BYTES.store(threshold_bytes, Ordering::Relaxed);
+    let levels = zstd::compression_level_range();
+    LEVEL.store(level.clamp(1, *levels.end()), Ordering::Relaxed);
+}
+
+/// The columns to insert for a payload: `(payload, payload_zstd)`
+///
+/// Compressed only above the threshold, and only when that actually saves space.
+pub fn split(payload: Option<&Value>) -> (Option<&Value>, Option<Vec<u8>>) {
+    let threshold = THRESHOLD_BYTES.load(Ordering::Relaxed);
+    let Some(value) = payload.filter(|_| threshold > 0) else {
+        return (payload, None);
+    };
+    let Ok(json) = serde_json::to_vec(value) else {
+        return (payload, None);
+    };
+    if json.len() <= threshold {
+        return (payload, None);
+    }
+    match zstd::bulk::compress(&json, LEVEL.load(Ordering::Relaxed)) {
+        Ok(compressed) if compressed.len() < json.len() => {
+            metrics::counter!("notifications_payload_compressed_total").increment(1);
+            metrics::counter!("notifications_payload_compression_saved_bytes_total")
+                .increment((json.len() - compressed.len()) as u64);
+            (None, Some(compressed))
+        }
+        _ => (payload, None),
+    }
+}
+
+/// Move a compressed payload into `payload` (a no-op for rows stored as JSON)
+///
+/// A row that fails to decompress keeps a NULL payload and is logged; it is delivered
+/// without one rather than retried forever.
+pub fn inflate(id: Uuid, payload: &mut Option<Value>, compressed: &mut Option<Vec<u8>>) {
+    let Some(bytes) = compressed.take() else {
+        return;
+    };
+    let decoded = zstd::stream::decode_all(bytes.as_slice())
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_slice::<Value>(&json).map_err(|e| e.to_string()));
+    match decoded {
+        Ok(value) => *payload = Some(value),
+        Err(e) => {
+            error!(id = %id, error = %e, compressed_bytes = bytes.len(), "Failed to decompress payload");
+            metrics::counter!("notifications_payload_decompress_errors_total").increment(1);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::instrument;
+
+pub struct CostQueries;
+
+impl CostQueries {
+    /// Add deliveries and their cost to a tenant's day on one channel
+    #[instrument(skip(pool))]
+    pub async fn add(
+        pool: &PgPool,
+        tenant_id: &str,
+        day: NaiveDate,
+        channel: &str,
+        deliveries: i64,
+        cost: f64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO activity.delivery_costs (tenant_id, day, channel, deliveries, cost)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (tenant_id, day, channel) DO UPDATE
+            SET deliveries = activity.delivery_costs.deliveries + EXCLUDED.deliveries,
+                cost = activity.delivery_costs.cost + EXCLUDED.cost,
+                updated_at = NOW()
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(day)
+        .bind(channel)
+        .bind(deliveries)
+        .bind(cost)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    /// A tenant's daily budget (None = unlimited) and what it spent on `day` so far
+    #[instrument(skip(pool))]
+    pub async fn budget(pool: &PgPool, tenant_id: &str, day: NaiveDate) -> Result<(Option<f64>, f64), sqlx::Error> {
+        sqlx::query_as::<_, (Option<f64>, f64)>(
+            r#"
+            SELECT
+                (SELECT daily_budget FROM activity.tenants WHERE tenant_id = $1),
+                COALESCE((SELECT SUM(cost) FROM activity.delivery_costs WHERE tenant_id = $1 AND day = $2), 0)
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(day)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Deliveries and cost per tenant and channel on `day`, with each tenant's budget
+    #[instrument(skip(pool))]
+    pub async fn for_day(pool: &PgPool, day: NaiveDate) -> Result<Vec<DailyCost>, sqlx::Error> {
+        sqlx::query_as::<_, DailyCost>(
+            r#"
+            SELECT c.tenant_id, c.channel, c.deliveries, c.cost, t.daily_budget
+            FROM activity.delivery_costs c
+            LEFT JOIN activity.tenants t ON t.tenant_id = c.tenant_id
+            WHERE c.day = $1
+            ORDER BY c.tenant_id, c.channel
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(day)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DailyCost {
+    pub tenant_id: String,
+    pub channel: String,
+    pub deliveries: i64,
+    pub cost: f64,
+    pub daily_budget: Option<f64>,
+}
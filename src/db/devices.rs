@@ -0,0 +1,386 @@
+use crate::models::DeviceCapabilities;
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+pub struct DeviceQueries;
+
+impl DeviceQueries {
+    /// The user's devices for this tenant's app, with their preferences
+    #[instrument(skip(pool), fields(tenant_id = %tenant_id, user_id = %user_id))]
+    pub async fn list(pool: &PgPool, tenant_id: &str, user_id: Uuid) -> Result<Vec<Device>, sqlx::Error> {
+        trace!("DB list_devices");
+
+        sqlx::query_as::<_, Device>(
+            r#"
+            SELECT fcm_token, device_type, locale, app_version, os_version, quiet_hours_start, quiet_hours_end,
+                   quiet_hours_timezone, enabled_types, push_environment, capabilities, last_seen_at, created_at,
+                   region
+            FROM activity.user_devices
+            WHERE tenant_id = $1 AND user_id = $2
+            ORDER BY created_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Register a device or refresh its metadata - returns it with the previous owner
+    ///
+    /// The token identifies an app install: registering it for another user (new login on
+    /// the same phone) moves it and drops the previous owner's device preferences.
+    #[instrument(skip(pool, registration), fields(tenant_id = %tenant_id, user_id = %user_id))]
+    pub async fn register(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        registration: &DeviceRegistration,
+    ) -> Result<(Device, Option<(String, Uuid)>), sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, RegisteredDevice>(
+            r#"
+            WITH previous AS (
+                SELECT tenant_id, user_id FROM activity.user_devices WHERE fcm_token = $3
+            )
+            INSERT INTO activity.user_devices
+                (tenant_id, user_id, fcm_token, device_type, locale, app_version, os_version, push_environment,
+                 capabilities, region, last_seen_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, now())
+            ON CONFLICT (fcm_token) DO UPDATE
+            SET device_type = EXCLUDED.device_type,
+                locale = COALESCE(EXCLUDED.locale, user_devices.locale),
+                app_version = COALESCE(EXCLUDED.app_version, user_devices.app_version),
+                os_version = COALESCE(EXCLUDED.os_version, user_devices.os_version),
+                push_environment = COALESCE(EXCLUDED.push_environment, user_devices.push_environment),
+                capabilities = COALESCE(EXCLUDED.capabilities, user_devices.capabilities),
+                region = COALESCE(EXCLUDED.region, user_devices.region),
+                last_seen_at = now(),
+                -- Preferences stay with their owner: dropped when the install changes hands
+                quiet_hours_start = CASE WHEN (user_devices.tenant_id, user_devices.user_id) = ($1, $2)
+                    THEN user_devices.quiet_hours_start END,
+                quiet_hours_end = CASE WHEN (user_devices.tenant_id, user_devices.user_id) = ($1, $2)
+                    THEN user_devices.quiet_hours_end END,
+                quiet_hours_timezone = CASE WHEN (user_devices.tenant_id, user_devices.user_id) = ($1, $2)
+                    THEN user_devices.quiet_hours_timezone END,
+                enabled_types = CASE WHEN (user_devices.tenant_id, user_devices.user_id) = ($1, $2)
+                    THEN user_devices.enabled_types END,
+                tenant_id = EXCLUDED.tenant_id,
+                user_id = EXCLUDED.user_id
+            RETURNING fcm_token, device_type, locale, app_version, os_version, quiet_hours_start,
+                      quiet_hours_end, quiet_hours_timezone, enabled_types, push_environment, capabilities,
+                      last_seen_at, created_at, region,
+                      (SELECT tenant_id FROM previous) AS previous_tenant_id,
+                      (SELECT user_id FROM previous) AS previous_user_id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(&registration.fcm_token)
+        .bind(&registration.device_type)
+        .bind(&registration.locale)
+        .bind(&registration.app_version)
+        .bind(&registration.os_version)
+        .bind(&registration.push_environment)
+        .bind(registration.capabilities.map(Json))
+        .bind(&registration.region)
+        .fetch_one(pool)
+        .await;
+
+        match &result {
+            Ok(_) => debug!(duration_ms = start.elapsed().as_millis() as u64, "DB register_device: completed"),
+            Err(e) => error!(error = %e, "DB register_device: query failed"),
+        }
+        result.map(|registered| {
+            let previous = registered.previous_tenant_id.zip(registered.previous_user_id);
+            (registered.device, previous)
+        })
+    }
+
+    /// Move `last_seen_at` after successful pushes (at most once an hour per device)
+    #[instrument(skip(pool, fcm_tokens), fields(count = fcm_tokens.len()))]
+    pub async fn touch(pool: &PgPool, fcm_tokens: &[String]) -> Result<u64, sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE activity.user_devices
+            SET last_seen_at = now()
+            WHERE fcm_token = ANY($1)
+              AND (last_seen_at IS NULL OR last_seen_at < now() - INTERVAL '1 hour')
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(fcm_tokens)
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected())
+    }
+
+    /// Replace one device's preferences - None when the token isn't this user's
+    #[instrument(skip(pool, preferences, fcm_token), fields(tenant_id = %tenant_id, user_id = %user_id))]
+    pub async fn update_preferences(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        fcm_token: &str,
+        preferences: &DevicePreferences,
+    ) -> Result<Option<Device>, sqlx::Error> {
+        let result = sqlx::query_as::<_, Device>(
+            r#"
+            UPDATE activity.user_devices
+            SET quiet_hours_start = $4, quiet_hours_end = $5, quiet_hours_timezone = $6, enabled_types = $7
+            WHERE tenant_id = $1 AND user_id = $2 AND fcm_token = $3
+            RETURNING fcm_token, device_type, locale, app_version, os_version, quiet_hours_start, quiet_hours_end,
+                      quiet_hours_timezone, enabled_types, push_environment, capabilities, last_seen_at, created_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(fcm_token)
+        .bind(preferences.quiet_hours_start)
+        .bind(preferences.quiet_hours_end)
+        .bind(&preferences.quiet_hours_timezone)
+        .bind(&preferences.enabled_types)
+        .fetch_optional(pool)
+        .await;
+
+        if let Ok(device) = &result {
+            debug!(found = device.is_some(), "DB update_device_preferences: completed");
+        }
+        result
+    }
+
+    /// Move a device to a new FCM token, keeping its preferences and metadata
+    ///
+    /// A row the app already registered under `new_token` for the same user is replaced.
+    /// When `old_token` is gone but `new_token` is the user's, the refresh already happened
+    /// (client retry) and that device is returned. None = neither token is the user's.
+    /// A `new_token` registered to another user fails with a unique violation.
+    #[instrument(skip(pool, old_token, new_token), fields(tenant_id = %tenant_id, user_id = %user_id))]
+    pub async fn replace_token(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        old_token: &str,
+        new_token: &str,
+    ) -> Result<Option<Device>, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM activity.user_devices
+            WHERE tenant_id = $1 AND user_id = $2 AND fcm_token = $4
+              AND EXISTS (
+                  SELECT 1 FROM activity.user_devices
+                  WHERE tenant_id = $1 AND user_id = $2 AND fcm_token = $3
+              )
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(old_token)
+        .bind(new_token)
+        .execute(&mut *tx)
+        .await?;
+
+        let replaced = sqlx::query_as::<_, Device>(
+            r#"
+            UPDATE activity.user_devices
+            SET fcm_token = $4
+            WHERE tenant_id = $1 AND user_id = $2 AND fcm_token = $3
+            RETURNING fcm_token, device_type, locale, app_version, os_version, quiet_hours_start, quiet_hours_end,
+                      quiet_hours_timezone, enabled_types, push_environment, capabilities, last_seen_at, created_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(old_token)
+        .bind(new_token)
+        .fetch_optional(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        if replaced.is_some() {
+            debug!("DB replace_device_token: completed");
+            return Ok(replaced);
+        }
+        sqlx::query_as::<_, Device>(
+            r#"
+            SELECT fcm_token, device_type, locale, app_version, os_version, quiet_hours_start, quiet_hours_end,
+                   quiet_hours_timezone, enabled_types, push_environment, capabilities, last_seen_at, created_at,
+                   region
+            FROM activity.user_devices
+            WHERE tenant_id = $1 AND user_id = $2 AND fcm_token = $3
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(new_token)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Which of `fcm_tokens` are registered already
+    #[instrument(skip(pool, fcm_tokens), fields(count = fcm_tokens.len()))]
+    pub async fn registered_tokens(pool: &PgPool, fcm_tokens: &[String]) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar::<_, String>("SELECT fcm_token FROM activity.user_devices WHERE fcm_token = ANY($1)")
+            .persistent(super::prepared_statements())
+            .bind(fcm_tokens)
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Bulk-insert imported devices in one transaction - returns how many were new
+    ///
+    /// Tokens registered in the meantime are left as they are (`ON CONFLICT DO NOTHING`).
+    #[instrument(skip(pool, devices), fields(count = devices.len()))]
+    pub async fn import(pool: &PgPool, devices: &[DeviceRecord]) -> Result<u64, sqlx::Error> {
+        let start = Instant::now();
+        let mut tx = pool.begin().await?;
+        let mut inserted = 0;
+
+        for chunk in devices.chunks(IMPORT_CHUNK) {
+            let column = |field: fn(&DeviceRecord) -> Option<String>| chunk.iter().map(field).collect::<Vec<_>>();
+            inserted += sqlx::query(
+                r#"
+                INSERT INTO activity.user_devices
+                    (tenant_id, user_id, fcm_token, device_type, locale, app_version, os_version, push_environment)
+                SELECT * FROM UNNEST($1::TEXT[], $2::UUID[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::TEXT[],
+                                     $7::TEXT[], $8::TEXT[])
+                ON CONFLICT (fcm_token) DO NOTHING
+                "#,
+            )
+            .persistent(super::prepared_statements())
+            .bind(chunk.iter().map(|d| d.tenant_id.clone()).collect::<Vec<_>>())
+            .bind(chunk.iter().map(|d| d.user_id).collect::<Vec<_>>())
+            .bind(chunk.iter().map(|d| d.fcm_token.clone()).collect::<Vec<_>>())
+            .bind(chunk.iter().map(|d| d.device_type.clone()).collect::<Vec<_>>())
+            .bind(column(|d| d.locale.clone()))
+            .bind(column(|d| d.app_version.clone()))
+            .bind(column(|d| d.os_version.clone()))
+            .bind(column(|d| d.push_environment.clone()))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+
+        debug!(
+            inserted = inserted,
+            duration_ms = start.elapsed().as_millis() as u64,
+            "DB import_devices: completed"
+        );
+        Ok(inserted)
+    }
+
+    /// Every device, optionally of one tenant, oldest first
+    #[instrument(skip(pool))]
+    pub async fn export(pool: &PgPool, tenant_id: Option<&str>) -> Result<Vec<DeviceRecord>, sqlx::Error> {
+        sqlx::query_as::<_, DeviceRecord>(
+            r#"
+            SELECT tenant_id, user_id, fcm_token, device_type, locale, app_version, os_version, push_environment,
+                   last_seen_at, created_at
+            FROM activity.user_devices
+            WHERE $1::TEXT IS NULL OR tenant_id = $1
+            ORDER BY created_at, fcm_token
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+/// Rows per INSERT of a device import
+const IMPORT_CHUNK: usize = 1_000;
+
+/// A device as exported, and as imported from a legacy push system
+///
+/// Import ignores `last_seen_at`/`created_at`; preferences aren't part of the format.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DeviceRecord {
+    pub tenant_id: String,
+    pub user_id: Uuid,
+    pub fcm_token: String,
+    pub device_type: String,
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(default)]
+    pub app_version: Option<String>,
+    #[serde(default)]
+    pub os_version: Option<String>,
+    #[serde(default)]
+    pub push_environment: Option<String>,
+    #[serde(default)]
+    pub last_seen_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// What an app reports when it registers (or re-registers) a device
+#[derive(Debug, Clone)]
+pub struct DeviceRegistration {
+    pub fcm_token: String,
+    pub device_type: String,
+    pub locale: Option<String>,
+    pub app_version: Option<String>,
+    pub os_version: Option<String>,
+    /// production / sandbox; None keeps the stored one (new devices: PUSH_ENVIRONMENT)
+    pub push_environment: Option<String>,
+    /// None keeps the stored ones; declared ones replace them as a whole
+    pub capabilities: Option<DeviceCapabilities>,
+    /// REGION of the instance that took the registration (never from the app); None keeps the stored one
+    pub region: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct RegisteredDevice {
+    #[sqlx(flatten)]
+    device: Device,
+    previous_tenant_id: Option<String>,
+    previous_user_id: Option<Uuid>,
+}
+
+/// Per-device push preferences (see `worker::devices::device_hold`)
+#[derive(Debug, Clone, Default)]
+pub struct DevicePreferences {
+    pub quiet_hours_start: Option<NaiveTime>,
+    pub quiet_hours_end: Option<NaiveTime>,
+    pub quiet_hours_timezone: Option<String>,
+    pub enabled_types: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Device {
+    pub fcm_token: String,
+    pub device_type: String,
+    pub locale: Option<String>,
+    pub app_version: Option<String>,
+    pub os_version: Option<String>,
+    pub quiet_hours_start: Option<NaiveTime>,
+    pub quiet_hours_end: Option<NaiveTime>,
+    pub quiet_hours_timezone: Option<String>,
+    pub enabled_types: Option<Vec<String>>,
+    /// production / sandbox, null = the service default
+    pub push_environment: Option<String>,
+    /// Declared by the app, null = not declared (gets everything)
+    pub capabilities: Option<Json<DeviceCapabilities>>,
+    /// Last registration or successful push
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    /// Region the device last registered in, null = unknown (migration 069)
+    #[sqlx(default)]
+    pub region: Option<String>,
+}
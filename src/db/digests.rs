@@ -0,0 +1,199 @@
+use super::schema::notification_select;
+use crate::models::Notification;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+use std::time::Instant;
+use tracing::{debug, instrument, trace};
+use uuid::Uuid;
+
+pub struct DigestQueries;
+
+impl DigestQueries {
+    /// The user's digest subscription (None = not opted in)
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn get(pool: &PgPool, tenant_id: &str, user_id: Uuid) -> Result<Option<DigestSubscription>, sqlx::Error> {
+        trace!("DB get_digest: fetching for user {}", user_id);
+
+        sqlx::query_as::<_, DigestSubscription>(
+            r#"
+            SELECT tenant_id, user_id, email, next_run_at, last_sent_at, created_at
+            FROM activity.digest_subscriptions
+            WHERE tenant_id = $2 AND user_id = $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(tenant_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Opt in, or change the address of an existing subscription
+    #[instrument(skip(pool, email), fields(user_id = %user_id))]
+    pub async fn subscribe(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        email: &str,
+    ) -> Result<DigestSubscription, sqlx::Error> {
+        trace!("DB subscribe_digest: user {}", user_id);
+
+        sqlx::query_as::<_, DigestSubscription>(
+            r#"
+            INSERT INTO activity.digest_subscriptions (tenant_id, user_id, email)
+            VALUES ($3, $1, $2)
+            ON CONFLICT (tenant_id, user_id) DO UPDATE SET email = EXCLUDED.email, updated_at = now()
+            RETURNING tenant_id, user_id, email, next_run_at, last_sent_at, created_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(email)
+        .bind(tenant_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Opt out - returns false if the user wasn't subscribed
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn unsubscribe(pool: &PgPool, tenant_id: &str, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        trace!("DB unsubscribe_digest: user {}", user_id);
+
+        sqlx::query("DELETE FROM activity.digest_subscriptions WHERE tenant_id = $2 AND user_id = $1")
+            .persistent(super::prepared_statements())
+            .bind(user_id)
+            .bind(tenant_id)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+
+    /// Lock the most overdue subscription, or one that was never scheduled (other jobs skip it)
+    pub async fn claim_due(tx: &mut Transaction<'_, Postgres>) -> Result<Option<DigestSubscription>, sqlx::Error> {
+        sqlx::query_as::<_, DigestSubscription>(
+            r#"
+            SELECT tenant_id, user_id, email, next_run_at, last_sent_at, created_at
+            FROM activity.digest_subscriptions
+            WHERE next_run_at IS NULL OR next_run_at <= now()
+            ORDER BY next_run_at NULLS FIRST
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    /// Newest unread inbox notifications not summarized yet (at most `limit`)
+    ///
+    /// The inbox is what `/notifications/sync` shows: processed and not suppressed.
+    /// Notifications from before the opt-in are left out.
+    pub async fn unread(
+        tx: &mut Transaction<'_, Postgres>,
+        subscription: &DigestSubscription,
+        limit: i64,
+    ) -> Result<Vec<Notification>, sqlx::Error> {
+        sqlx::query_as::<_, Notification>(&format!(
+            r#"
+            SELECT {}
+            FROM activity.notifications
+            WHERE tenant_id = $1 AND user_id = $2
+              AND is_processed AND suppressed_at IS NULL
+              AND read_at IS NULL AND digested_at IS NULL
+              AND notification_type::text <> 'otp'
+              AND created_at >= $3 AND created_at <= now()
+            ORDER BY created_at DESC
+            LIMIT $4
+            "#,
+            notification_select("")
+        ))
+        .persistent(super::prepared_statements())
+        .bind(&subscription.tenant_id)
+        .bind(subscription.user_id)
+        .bind(subscription.created_at)
+        .bind(limit)
+        .fetch_all(&mut **tx)
+        .await
+        .map(|mut rows| {
+            rows.iter_mut().for_each(Notification::inflate_payload);
+            rows
+        })
+    }
+
+    /// Stamp every notification [`unread`](Self::unread) matches - returns how many
+    ///
+    /// `now()` is the transaction's start time, so rows inserted meanwhile are left for
+    /// the next digest.
+    pub async fn mark_digested(
+        executor: impl PgExecutor<'_>,
+        subscription: &DigestSubscription,
+    ) -> Result<u64, sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query(
+            r#"
+            UPDATE activity.notifications
+            SET digested_at = now()
+            WHERE tenant_id = $1 AND user_id = $2
+              AND is_processed AND suppressed_at IS NULL
+              AND read_at IS NULL AND digested_at IS NULL
+              AND notification_type::text <> 'otp'
+              AND created_at >= $3 AND created_at <= now()
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(&subscription.tenant_id)
+        .bind(subscription.user_id)
+        .bind(subscription.created_at)
+        .execute(executor)
+        .await?;
+
+        debug!(
+            user_id = %subscription.user_id,
+            marked = result.rows_affected(),
+            duration_ms = start.elapsed().as_millis() as u64,
+            "DB mark_digested: completed"
+        );
+        Ok(result.rows_affected())
+    }
+
+    /// Set the next digest time (and the send time when one went out)
+    pub async fn schedule(
+        executor: impl PgExecutor<'_>,
+        subscription: &DigestSubscription,
+        next_run_at: DateTime<Utc>,
+        sent: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE activity.digest_subscriptions
+            SET next_run_at = $3,
+                last_sent_at = CASE WHEN $4 THEN now() ELSE last_sent_at END
+            WHERE tenant_id = $1 AND user_id = $2
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(&subscription.tenant_id)
+        .bind(subscription.user_id)
+        .bind(next_run_at)
+        .bind(sent)
+        .execute(executor)
+        .await
+        .map(|_| ())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DigestSubscription {
+    #[serde(skip)]
+    pub tenant_id: String,
+    #[serde(skip)]
+    pub user_id: Uuid,
+    pub email: String,
+    /// None until the digest job scheduled the first digest
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
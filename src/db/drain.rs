@@ -0,0 +1,81 @@
+use super::archives::ArchivedRow;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::time::Instant;
+use tracing::{debug, error, instrument};
+use uuid::Uuid;
+
+/// Suppression reason of rows `notifications-service drain` exported instead of delivering
+pub const DRAINED_REASON: &str = "drained";
+
+/// Remaining work for `notifications-service drain`
+pub struct DrainQueries;
+
+/// Unprocessed notifications left in the queue
+#[derive(Debug, Clone, Copy, Default, sqlx::FromRow)]
+pub struct QueueCount {
+    pub unprocessed: i64,
+    /// Of which due now (the rest is scheduled for later)
+    pub due: i64,
+}
+
+impl DrainQueries {
+    pub async fn count(pool: &PgPool) -> Result<QueueCount, sqlx::Error> {
+        sqlx::query_as::<_, QueueCount>(
+            r#"
+            SELECT COUNT(*) AS unprocessed, COUNT(*) FILTER (WHERE deliver_at <= now()) AS due
+            FROM activity.notifications
+            WHERE is_processed = false
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Lock the oldest unprocessed notifications, as full-row JSON (the archive format)
+    ///
+    /// `SKIP LOCKED`: a row a worker is delivering right now is left to that worker.
+    #[instrument(skip(tx))]
+    pub async fn claim_batch(tx: &mut Transaction<'_, Postgres>, limit: i64) -> Result<Vec<ArchivedRow>, sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, ArchivedRow>(
+            r#"
+            SELECT n.id, n.created_at, to_jsonb(n) AS row
+            FROM activity.notifications n
+            WHERE n.is_processed = false
+            ORDER BY n.created_at, n.id
+            LIMIT $1
+            FOR UPDATE OF n SKIP LOCKED
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(limit)
+        .fetch_all(&mut **tx)
+        .await;
+
+        match &result {
+            Ok(rows) => debug!(
+                count = rows.len(),
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB claim_drain_batch: completed"
+            ),
+            Err(e) => error!(error = %e, "DB claim_drain_batch: query failed"),
+        }
+        result
+    }
+
+    /// Take exported rows out of the queue (suppressed as `drained`) - returns how many
+    #[instrument(skip(tx, ids), fields(count = ids.len()))]
+    pub async fn mark_exported(tx: &mut Transaction<'_, Postgres>, ids: &[Uuid]) -> Result<u64, sqlx::Error> {
+        let marked: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FILTER (WHERE activity.sp_notification_suppressed(id, $2)) FROM unnest($1::uuid[]) AS id",
+        )
+        .persistent(super::prepared_statements())
+        .bind(ids)
+        .bind(DRAINED_REASON)
+        .fetch_one(&mut **tx)
+        .await?;
+        Ok(marked as u64)
+    }
+}
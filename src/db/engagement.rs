@@ -0,0 +1,139 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+pub struct EngagementQueries;
+
+impl EngagementQueries {
+    /// Record that a user opened a notification - false if it isn't theirs (or doesn't exist)
+    ///
+    /// Broadcast rows (nil user id) can be opened by any user of the tenant.
+    #[instrument(skip(pool), fields(id = %id, user_id = %user_id))]
+    pub async fn record_open(pool: &PgPool, id: Uuid, user_id: Uuid, tenant_id: &str) -> Result<bool, sqlx::Error> {
+        trace!("DB record_open: notification {} by user {}", id, user_id);
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO activity.notification_engagement (notification_id, user_id, tenant_id, notification_type, event)
+            SELECT n.id, $2, n.tenant_id, n.notification_type, 'opened'
+            FROM activity.notifications n
+            WHERE n.id = $1
+              AND n.tenant_id = $3
+              AND (n.user_id = $2 OR n.user_id = '00000000-0000-0000-0000-000000000000')
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(user_id)
+        .bind(tenant_id)
+        .execute(pool)
+        .await;
+
+        match &result {
+            Ok(r) => debug!(id = %id, recorded = r.rows_affected() > 0, "DB record_open: completed"),
+            Err(e) => error!(id = %id, error = %e, "DB record_open: insert failed"),
+        }
+        result.map(|r| r.rows_affected() > 0)
+    }
+
+    /// Record a click-through and return the deep_link to redirect to
+    ///
+    /// None when the notification doesn't exist (anymore) or has no deep_link.
+    #[instrument(skip(pool), fields(id = %id))]
+    pub async fn record_click(pool: &PgPool, id: Uuid) -> Result<Option<String>, sqlx::Error> {
+        trace!("DB record_click: notification {}", id);
+
+        let result = sqlx::query_scalar::<_, String>(
+            r#"
+            WITH target AS (
+                SELECT id, user_id, tenant_id, notification_type, deep_link
+                FROM activity.notifications
+                WHERE id = $1 AND deep_link IS NOT NULL
+            ),
+            recorded AS (
+                INSERT INTO activity.notification_engagement (notification_id, user_id, tenant_id, notification_type, event)
+                SELECT id, user_id, tenant_id, notification_type, 'clicked' FROM target
+            )
+            SELECT deep_link FROM target
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(pool)
+        .await;
+
+        if let Err(e) = &result {
+            error!(id = %id, error = %e, "DB record_click: query failed");
+        }
+        result
+    }
+
+    /// The deep_link to redirect to, without recording a click (READ_ONLY replicas)
+    #[instrument(skip(pool), fields(id = %id))]
+    pub async fn deep_link(pool: &PgPool, id: Uuid) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT deep_link FROM activity.notifications WHERE id = $1 AND deep_link IS NOT NULL")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    /// Delivered notifications per type since `since`, and how many were opened / clicked
+    #[instrument(skip(pool))]
+    pub async fn summary(
+        pool: &PgPool,
+        since: DateTime<Utc>,
+        tenant_id: Option<&str>,
+    ) -> Result<Vec<EngagementSummary>, sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, EngagementSummary>(
+            r#"
+            SELECT n.notification_type,
+                   COUNT(*) AS delivered,
+                   COUNT(*) FILTER (WHERE EXISTS (
+                       SELECT 1 FROM activity.notification_engagement e
+                       WHERE e.notification_id = n.id AND e.event = 'opened'
+                   )) AS opened,
+                   COUNT(*) FILTER (WHERE EXISTS (
+                       SELECT 1 FROM activity.notification_engagement e
+                       WHERE e.notification_id = n.id AND e.event = 'clicked'
+                   )) AS clicked
+            FROM activity.notifications n
+            WHERE n.created_at >= $1
+              AND ($2::text IS NULL OR n.tenant_id = $2)
+              AND n.is_processed AND n.suppressed_at IS NULL
+              AND (n.last_error_at IS NULL OR n.last_error_at < n.updated_at)
+            GROUP BY n.notification_type
+            ORDER BY delivered DESC, n.notification_type
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(since)
+        .bind(tenant_id)
+        .fetch_all(pool)
+        .await;
+
+        match &result {
+            Ok(rows) => debug!(
+                types = rows.len(),
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB engagement_summary: completed"
+            ),
+            Err(e) => error!(error = %e, "DB engagement_summary: query failed"),
+        }
+        result
+    }
+}
+
+/// Engagement per notification type; opened/clicked count notifications, not events
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct EngagementSummary {
+    pub notification_type: String,
+    pub delivered: i64,
+    pub opened: i64,
+    pub clicked: i64,
+}
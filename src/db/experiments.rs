@@ -0,0 +1,200 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+pub struct ExperimentQueries;
+
+impl ExperimentQueries {
+    /// List all experiments, newest first
+    #[instrument(skip(pool))]
+    pub async fn list(pool: &PgPool) -> Result<Vec<Experiment>, sqlx::Error> {
+        trace!("DB list_experiments");
+
+        sqlx::query_as::<_, Experiment>(
+            r#"
+            SELECT id, name, template_key, campaign_id, variants, active, created_by, created_at, stopped_at
+            FROM activity.experiments
+            ORDER BY created_at DESC
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Find an experiment by id
+    #[instrument(skip(pool))]
+    pub async fn find(pool: &PgPool, id: Uuid) -> Result<Option<Experiment>, sqlx::Error> {
+        sqlx::query_as::<_, Experiment>(
+            r#"
+            SELECT id, name, template_key, campaign_id, variants, active, created_by, created_at, stopped_at
+            FROM activity.experiments
+            WHERE id = $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Running experiment for a campaign, else for a template (None = no experiment)
+    pub async fn find_active(
+        pool: &PgPool,
+        template_key: Option<&str>,
+        campaign_id: Option<Uuid>,
+    ) -> Result<Option<Experiment>, sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, Experiment>(
+            r#"
+            SELECT id, name, template_key, campaign_id, variants, active, created_by, created_at, stopped_at
+            FROM activity.experiments
+            WHERE active AND (campaign_id = $2 OR template_key = $1)
+            ORDER BY campaign_id IS NULL
+            LIMIT 1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(template_key)
+        .bind(campaign_id)
+        .fetch_optional(pool)
+        .await;
+
+        match &result {
+            Ok(experiment) => trace!(
+                experiment_id = ?experiment.as_ref().map(|e| e.id),
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB find_active_experiment: completed"
+            ),
+            Err(e) => error!(error = %e, "DB find_active_experiment: query failed"),
+        }
+        result
+    }
+
+    /// Store and start a new experiment
+    #[instrument(skip(pool, experiment), fields(name = %experiment.name))]
+    pub async fn create(
+        pool: &PgPool,
+        experiment: &NewExperiment,
+        created_by: &str,
+    ) -> Result<Experiment, sqlx::Error> {
+        sqlx::query_as::<_, Experiment>(
+            r#"
+            INSERT INTO activity.experiments (name, template_key, campaign_id, variants, created_by)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, name, template_key, campaign_id, variants, active, created_by, created_at, stopped_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(&experiment.name)
+        .bind(&experiment.template_key)
+        .bind(experiment.campaign_id)
+        .bind(Json(&experiment.variants))
+        .bind(created_by)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Stop a running experiment - None if it doesn't exist or already stopped
+    ///
+    /// Its stats stay queryable; new deliveries get the original text again.
+    #[instrument(skip(pool))]
+    pub async fn stop(pool: &PgPool, id: Uuid) -> Result<Option<Experiment>, sqlx::Error> {
+        sqlx::query_as::<_, Experiment>(
+            r#"
+            UPDATE activity.experiments
+            SET active = false, stopped_at = now()
+            WHERE id = $1 AND active
+            RETURNING id, name, template_key, campaign_id, variants, active, created_by, created_at, stopped_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Delivered / opened / clicked notifications per variant
+    #[instrument(skip(pool))]
+    pub async fn stats(pool: &PgPool, id: Uuid) -> Result<Vec<VariantStats>, sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, VariantStats>(
+            r#"
+            SELECT a.variant,
+                   COUNT(DISTINCT a.notification_id) AS delivered,
+                   COUNT(DISTINCT a.notification_id) FILTER (WHERE EXISTS (
+                       SELECT 1 FROM activity.notification_engagement e
+                       WHERE e.notification_id = a.notification_id AND e.event = 'opened'
+                   )) AS opened,
+                   COUNT(DISTINCT a.notification_id) FILTER (WHERE EXISTS (
+                       SELECT 1 FROM activity.notification_engagement e
+                       WHERE e.notification_id = a.notification_id AND e.event = 'clicked'
+                   )) AS clicked
+            FROM activity.notification_attempts a
+            WHERE a.experiment_id = $1 AND a.outcome = 'delivered'
+            GROUP BY a.variant
+            ORDER BY a.variant
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_all(pool)
+        .await;
+
+        match &result {
+            Ok(rows) => debug!(
+                id = %id,
+                variants = rows.len(),
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB experiment_stats: completed"
+            ),
+            Err(e) => error!(id = %id, error = %e, "DB experiment_stats: query failed"),
+        }
+        result
+    }
+}
+
+/// Alternative text; None keeps the original title/body (a control variant)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Variant {
+    pub name: String,
+    /// Tera template, same variables as notification templates
+    pub title: Option<String>,
+    pub body: Option<String>,
+}
+
+/// Experiment as submitted through the API
+#[derive(Debug, Clone)]
+pub struct NewExperiment {
+    pub name: String,
+    pub template_key: Option<String>,
+    pub campaign_id: Option<Uuid>,
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Experiment {
+    pub id: Uuid,
+    pub name: String,
+    pub template_key: Option<String>,
+    pub campaign_id: Option<Uuid>,
+    pub variants: Json<Vec<Variant>>,
+    pub active: bool,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub stopped_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct VariantStats {
+    pub variant: String,
+    pub delivered: i64,
+    pub opened: i64,
+    pub clicked: i64,
+}
@@ -0,0 +1,163 @@
+use super::schema::notification_select;
+use crate::models::Notification;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::LazyLock;
+use tracing::{instrument, trace};
+use uuid::Uuid;
+
+/// What [`ExplainedNotification`] reads from a row of `activity.notifications` (or a
+/// record of its type): the notification and the state the worker left on it
+pub(super) static EXPLAINED_COLUMNS: LazyLock<String> = LazyLock::new(|| {
+    format!(
+        r#"
+        {},
+        suppressed_at,
+        suppression_reason,
+        COALESCE(error_count, 0) AS error_count,
+        last_error,
+        last_error_at,
+        failure_category,
+        read_at,
+        updated_at
+"#,
+        notification_select("")
+    )
+});
+
+pub struct ExplainQueries;
+
+impl ExplainQueries {
+    /// A notification with the state the worker left on the row (None = unknown or archived)
+    #[instrument(skip(pool))]
+    pub async fn find(pool: &PgPool, id: Uuid) -> Result<Option<ExplainedNotification>, sqlx::Error> {
+        trace!("DB explain_find: {}", id);
+
+        sqlx::query_as::<_, ExplainedNotification>(&format!(
+            "SELECT {} FROM activity.notifications WHERE id = $1",
+            *EXPLAINED_COLUMNS
+        ))
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map(|row| {
+            row.map(|mut explained| {
+                explained.notification.inflate_payload();
+                explained
+            })
+        })
+    }
+
+    /// Everything recorded about a notification, oldest first
+    ///
+    /// The row's own milestones (created, Bus delivery awaiting an ack, acked, held for a
+    /// bundle, read, last error, suppressed), every delivery attempt, receipt webhooks, opens/clicks
+    /// and client error reports.
+    /// Earlier errors are only in the attempts: the row keeps the last one.
+    #[instrument(skip(pool))]
+    pub async fn timeline(pool: &PgPool, id: Uuid) -> Result<Vec<TrailEvent>, sqlx::Error> {
+        trace!("DB explain_timeline: {}", id);
+
+        sqlx::query_as::<_, TrailEvent>(
+            r#"
+            SELECT at, kind, channel, outcome, detail, provider_message_id FROM (
+                SELECT m.at, 'notification' AS kind, NULL::text AS channel, m.outcome, m.detail,
+                       NULL::text AS provider_message_id
+                FROM activity.notifications n,
+                LATERAL (VALUES
+                    (n.created_at, 'created', n.created_by),
+                    (n.bus_delivered_at, 'bus_awaiting_ack', NULL),
+                    (n.acked_at, 'acked', n.acked_by_device),
+                    (n.bundled_at, 'bundled', n.bundled_into::text),
+                    (n.read_at, 'read', NULL),
+                    (n.last_error_at, 'error', n.last_error),
+                    (n.suppressed_at, 'suppressed', n.suppression_reason)
+                ) AS m(at, outcome, detail)
+                WHERE n.id = $1 AND m.at IS NOT NULL
+
+                UNION ALL
+
+                SELECT attempted_at, 'attempt', channel, outcome, detail, provider_message_id
+                FROM activity.notification_attempts
+                WHERE notification_id = $1
+
+                UNION ALL
+
+                SELECT COALESCE(delivered_at, created_at), 'receipt', NULL,
+                       CASE WHEN delivered_at IS NOT NULL THEN 'delivered'
+                            WHEN last_error IS NOT NULL THEN 'retrying'
+                            ELSE 'pending' END,
+                       last_error, NULL
+                FROM activity.receipt_deliveries
+                WHERE notification_id = $1
+
+                UNION ALL
+
+                SELECT occurred_at, 'engagement', NULL, event, NULL, NULL
+                FROM activity.notification_engagement
+                WHERE notification_id = $1
+
+                UNION ALL
+
+                SELECT reported_at, 'client_error', channel, code, context::text, NULL
+                FROM activity.notification_client_errors
+                WHERE notification_id = $1
+            ) trail
+            ORDER BY at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ExplainedNotification {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub notification: Notification,
+    pub is_processed: bool,
+    pub suppressed_at: Option<DateTime<Utc>>,
+    pub suppression_reason: Option<String>,
+    pub error_count: i32,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub failure_category: Option<String>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ExplainedNotification {
+    /// Where the notification stands: delivered, suppressed, failed, retrying, scheduled or pending
+    ///
+    /// A success doesn't clear the last error, so as in `ResendQueries` a processed row
+    /// failed when its last error is its last update.
+    pub fn status(&self) -> &'static str {
+        let failed_last = self.last_error_at.is_some_and(|at| at >= self.updated_at);
+        match (self.is_processed, self.suppressed_at.is_some()) {
+            (true, true) => "suppressed",
+            (true, false) if failed_last => "failed",
+            (true, false) => "delivered",
+            (false, _) if self.last_error.is_some() => "retrying",
+            (false, _) if self.notification.deliver_at > Utc::now() => "scheduled",
+            (false, _) => "pending",
+        }
+    }
+}
+
+/// One entry of a notification's decision trail
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TrailEvent {
+    pub at: DateTime<Utc>,
+    /// notification | attempt | receipt | engagement | client_error (outcome = code, detail = context)
+    pub kind: String,
+    pub channel: Option<String>,
+    pub outcome: String,
+    pub detail: Option<String>,
+    /// FCM message name of a delivered push attempt (for escalations to Google)
+    pub provider_message_id: Option<String>,
+}
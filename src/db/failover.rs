@@ -0,0 +1,155 @@
+use sqlx::postgres::PgConnectOptions;
+use sqlx::{Connection, PgConnection, PgPool};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use tracing::{debug, info, warn};
+
+/// How long a health check waits for a host
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Primary and standby hosts behind the shared pool (DATABASE_URL=primary,standby,...)
+///
+/// Only host and port differ per host: credentials, database and TLS settings are the
+/// pool's own, so rotated credentials keep working after a failover. A host is healthy
+/// when it accepts a connection and is not in recovery (writable). [`check`](Self::check)
+/// moves the pool to the first healthy host in list order, which is a fail-back as soon
+/// as the primary is healthy again. Connections already in the pool stay until they fail
+/// or idle out; the NOTIFY listener reconnects on every switch ([`subscribe`](Self::subscribe)).
+#[derive(Clone)]
+pub struct DatabaseHosts {
+    inner: Arc<Hosts>,
+}
+
+struct Hosts {
+    pool: PgPool,
+    hosts: Vec<(String, u16)>,
+    active: AtomicUsize,
+    switched: watch::Sender<usize>,
+    /// One check at a time (the listener and the health check may both ask)
+    checking: Mutex<()>,
+}
+
+impl DatabaseHosts {
+    pub fn new(pool: PgPool, hosts: Vec<(String, u16)>, active: usize) -> Self {
+        metrics::gauge!("notifications_db_active_host").set(active as f64);
+        Self {
+            inner: Arc::new(Hosts {
+                pool,
+                hosts,
+                active: AtomicUsize::new(active),
+                switched: watch::channel(active).0,
+                checking: Mutex::new(()),
+            }),
+        }
+    }
+
+    /// Index of the host new connections go to (0 = primary)
+    pub fn active(&self) -> usize {
+        self.inner.active.load(Ordering::Relaxed)
+    }
+
+    /// `host:port` of the active host, for logs
+    pub fn active_host(&self) -> String {
+        let (host, port) = &self.inner.hosts[self.active()];
+        format!("{}:{}", host, port)
+    }
+
+    /// More than the primary configured?
+    pub fn has_standbys(&self) -> bool {
+        self.inner.hosts.len() > 1
+    }
+
+    /// The pool's connect options (credentials included), pointed at the active host
+    pub fn connect_options(&self) -> PgConnectOptions {
+        (*self.inner.pool.connect_options()).clone()
+    }
+
+    /// Changes to the active host
+    pub fn subscribe(&self) -> watch::Receiver<usize> {
+        self.inner.switched.subscribe()
+    }
+
+    /// Health-check the hosts and switch to the first healthy one - true if it switched
+    ///
+    /// The primary is probed first when a standby is active (fail-back); otherwise the
+    /// active host, and only when that one is down the others in list order. Nothing
+    /// changes when no host is healthy.
+    pub async fn check(&self) -> bool {
+        let _checking = self.inner.checking.lock().await;
+        let active = self.active();
+
+        if active != 0 && self.probe(0).await {
+            return self.switch(0);
+        }
+        if self.probe(active).await {
+            return false;
+        }
+        for index in (0..self.inner.hosts.len()).filter(|&i| i != 0 && i != active) {
+            if self.probe(index).await {
+                return self.switch(index);
+            }
+        }
+        warn!(active = %self.active_host(), "✗ No healthy database host, staying on the active one");
+        false
+    }
+
+    /// Background health check every `interval` (only useful with standbys)
+    pub async fn run(self, interval: Duration) {
+        info!(
+            hosts = self.inner.hosts.len(),
+            interval_secs = interval.as_secs(),
+            "Database failover health check started"
+        );
+        loop {
+            tokio::time::sleep(interval).await;
+            self.check().await;
+        }
+    }
+
+    fn options_for(&self, index: usize) -> PgConnectOptions {
+        let (host, port) = &self.inner.hosts[index];
+        self.connect_options().host(host).port(*port)
+    }
+
+    /// Reachable and writable?
+    async fn probe(&self, index: usize) -> bool {
+        let options = self.options_for(index);
+        let probe = async {
+            let mut conn = PgConnection::connect_with(&options).await?;
+            let writable: bool = sqlx::query_scalar("SELECT NOT pg_is_in_recovery()").fetch_one(&mut conn).await?;
+            let _ = conn.close().await;
+            Ok::<_, sqlx::Error>(writable)
+        };
+        let (host, port) = &self.inner.hosts[index];
+        match tokio::time::timeout(PROBE_TIMEOUT, probe).await {
+            Ok(Ok(true)) => true,
+            Ok(Ok(false)) => {
+                debug!(host = %host, port = port, "Database host is in recovery (read-only)");
+                false
+            }
+            Ok(Err(e)) => {
+                debug!(host = %host, port = port, error = %e, "Database host unreachable");
+                false
+            }
+            Err(_) => {
+                debug!(host = %host, port = port, "Database host health check timed out");
+                false
+            }
+        }
+    }
+
+    fn switch(&self, index: usize) -> bool {
+        let from = self.active_host();
+        self.inner.pool.set_connect_options(self.options_for(index));
+        self.inner.active.store(index, Ordering::Relaxed);
+        self.inner.switched.send_replace(index);
+
+        let kind = if index == 0 { "failback" } else { "failover" };
+        warn!(from = %from, to = %self.active_host(), kind = kind, "⇄ Database host switched");
+        metrics::counter!("notifications_db_failovers_total", "kind" => kind).increment(1);
+        metrics::gauge!("notifications_db_active_host").set(index as f64);
+        true
+    }
+}
@@ -0,0 +1,71 @@
+use super::schema::notification_select;
+use crate::models::Notification;
+use sqlx::PgPool;
+use tracing::{debug, instrument};
+use uuid::Uuid;
+
+/// NOTIFY channel for foreground boosts (payload: `<tenant_id> <user_id>`)
+pub const FOREGROUND_CHANNEL: &str = "user_foreground";
+
+/// A user's due notifications, for the foreground boost (see `crate::worker::foreground`)
+pub struct ForegroundQueries;
+
+impl ForegroundQueries {
+    /// Unprocessed notifications of the user that are due
+    #[instrument(skip(pool))]
+    pub async fn count_due(pool: &PgPool, tenant_id: &str, user_id: Uuid) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT count(*) FROM activity.notifications
+             WHERE tenant_id = $1 AND user_id = $2 AND is_processed = false AND deliver_at <= now()",
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Ask the worker (whichever replica runs it) to deliver the user's due notifications now
+    #[instrument(skip(pool))]
+    pub async fn notify(pool: &PgPool, tenant_id: &str, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .persistent(super::prepared_statements())
+            .bind(FOREGROUND_CHANNEL)
+            .bind(format!("{} {}", tenant_id, user_id))
+            .execute(pool)
+            .await
+            .map(|_| ())
+    }
+
+    /// The user's due notifications in delivery order, like `fetch_unprocessed` for one user
+    #[instrument(skip(pool))]
+    pub async fn fetch_due(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Notification>, sqlx::Error> {
+        let mut result = sqlx::query_as::<_, Notification>(&format!(
+            r#"
+            SELECT {}
+            FROM activity.notifications
+            WHERE tenant_id = $1
+              AND user_id = $2
+              AND is_processed = false
+              AND deliver_at <= now()
+            ORDER BY deliver_at ASC, created_at ASC
+            LIMIT $3
+            "#,
+            notification_select("")
+        ))
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+        result.iter_mut().for_each(Notification::inflate_payload);
+        debug!(count = result.len(), "DB fetch_due: completed");
+        Ok(result)
+    }
+}
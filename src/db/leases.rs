@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::{instrument, trace};
+
+pub struct LeaseQueries;
+
+impl LeaseQueries {
+    /// Take or renew the lease - Some(expires_at) when `holder` holds it afterwards
+    ///
+    /// Succeeds when nobody holds it, `holder` already does, or the current lease lapsed.
+    /// Expiry is the database's clock, so instances with skewed clocks agree on it.
+    #[instrument(skip(pool))]
+    pub async fn try_acquire(
+        pool: &PgPool,
+        name: &str,
+        holder: &str,
+        lease_secs: u64,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        trace!("DB lease_try_acquire: {} for {}", name, holder);
+        let row: Option<(DateTime<Utc>,)> = sqlx::query_as(
+            r#"
+            INSERT INTO activity.leader_leases (name, holder, acquired_at, expires_at)
+            VALUES ($1, $2, now(), now() + $3 * interval '1 second')
+            ON CONFLICT (name) DO UPDATE
+            SET holder = EXCLUDED.holder,
+                acquired_at = CASE WHEN leader_leases.holder = EXCLUDED.holder
+                                   THEN leader_leases.acquired_at ELSE now() END,
+                expires_at = EXCLUDED.expires_at
+            WHERE leader_leases.holder = EXCLUDED.holder OR leader_leases.expires_at < now()
+            RETURNING expires_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(name)
+        .bind(holder)
+        .bind(lease_secs as f64)
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.map(|(expires_at,)| expires_at))
+    }
+
+    /// Give the lease up (no-op when `holder` doesn't hold it)
+    #[instrument(skip(pool))]
+    pub async fn release(pool: &PgPool, name: &str, holder: &str) -> Result<bool, sqlx::Error> {
+        trace!("DB lease_release: {} by {}", name, holder);
+        let result = sqlx::query("DELETE FROM activity.leader_leases WHERE name = $1 AND holder = $2")
+            .persistent(super::prepared_statements())
+            .bind(name)
+            .bind(holder)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The current lease, lapsed or not (None = nobody ever took it, or it was released)
+    #[instrument(skip(pool))]
+    pub async fn current(pool: &PgPool, name: &str) -> Result<Option<Lease>, sqlx::Error> {
+        trace!("DB lease_current: {}", name);
+        sqlx::query_as::<_, Lease>(
+            "SELECT holder, acquired_at, expires_at FROM activity.leader_leases WHERE name = $1",
+        )
+        .persistent(super::prepared_statements())
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Lease {
+    pub holder: String,
+    /// When this holder took it over (renewals keep it)
+    pub acquired_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
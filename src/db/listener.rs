@@ -1,26 +1,242 @@
-use sqlx::postgres::PgListener;
-use std::time::Instant;
-use tokio::sync::mpsc;
+use super::failover::DatabaseHosts;
+use sqlx::postgres::{PgListener, PgPoolOptions};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
 use tracing::{debug, error, info, trace, warn};
+use uuid::Uuid;
 
 const NOTIFY_CHANNEL: &str = "notify_event";
 
+/// Users with a bucket kept before idle (full) buckets are dropped
+const MAX_TRACKED_USERS: usize = 10_000;
+
+/// Wake-up sent to the worker per NOTIFY
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wake {
+    Normal,
+    /// High/critical priority insert: skips the worker's debounce
+    Urgent,
+}
+
+impl Wake {
+    /// From the trigger payload `<id> <priority>` (older triggers send only the id)
+    pub fn from_payload(payload: &str) -> Self {
+        Self::from_priority(payload.split_whitespace().nth(1))
+    }
+
+    pub fn from_priority(priority: Option<&str>) -> Self {
+        match priority {
+            Some("high") | Some("critical") => Wake::Urgent,
+            _ => Wake::Normal,
+        }
+    }
+}
+
+/// Recipient from the trigger payload `<id> <priority> <user_id> <tenant_id>` (migration 062)
+fn payload_recipient(payload: &str) -> Option<(&str, Uuid)> {
+    let mut fields = payload.splitn(4, ' ');
+    let user_id = fields.nth(2)?.parse().ok()?;
+    Some((fields.next()?, user_id))
+}
+
+/// Wake-ups from the wake source to the worker, coalesced while the worker is busy
+///
+/// Signals the worker hasn't taken yet fold into one pending wake-up (urgent if any was),
+/// so sending never blocks and never drops: the worker always sees at least one wake-up
+/// after the last NOTIFY. Counters: `notifications_wake_signals_total{urgent}` and
+/// `notifications_wake_coalesced_total` (signals folded into a pending one).
+#[derive(Clone, Default)]
+pub struct WakeSignal {
+    inner: Arc<WakeState>,
+}
+
+#[derive(Default)]
+struct WakeState {
+    /// Folded wake-up and the number of signals in it
+    pending: Mutex<Option<(Wake, u64)>>,
+    notify: Notify,
+    /// The NOTIFY listener holds a LISTEN session (the worker's failsafe poll backs off on it)
+    listening: AtomicBool,
+    /// Users whose app came to the foreground since the worker last looked: (tenant, user)
+    boosted: Mutex<Vec<(String, Uuid)>>,
+}
+
+impl WakeSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn send(&self, wake: Wake) {
+        let urgent = if wake == Wake::Urgent { "true" } else { "false" };
+        metrics::counter!("notifications_wake_signals_total", "urgent" => urgent).increment(1);
+        {
+            let mut pending = self.inner.pending.lock().expect("wake signal lock poisoned");
+            *pending = match *pending {
+                Some((folded, signals)) => {
+                    metrics::counter!("notifications_wake_coalesced_total").increment(1);
+                    let wake = if folded == Wake::Urgent { folded } else { wake };
+                    Some((wake, signals + 1))
+                }
+                None => Some((wake, 1)),
+            };
+        }
+        // Stores a permit when the worker isn't waiting yet
+        self.inner.notify.notify_one();
+    }
+
+    /// Urgent wake-up with a targeted pass over one user's due notifications first
+    pub fn boost(&self, tenant_id: &str, user_id: Uuid) {
+        {
+            let mut boosted = self.inner.boosted.lock().expect("wake signal lock poisoned");
+            if !boosted.iter().any(|(tenant, user)| tenant == tenant_id && *user == user_id) {
+                boosted.push((tenant_id.to_string(), user_id));
+            }
+        }
+        self.send(Wake::Urgent);
+    }
+
+    /// Users boosted since the last call
+    pub fn take_boosted(&self) -> Vec<(String, Uuid)> {
+        std::mem::take(&mut *self.inner.boosted.lock().expect("wake signal lock poisoned"))
+    }
+
+    /// Wait for the next wake-up: the folded wake and how many signals it stands for
+    ///
+    /// Cancel-safe, so it can be raced in `select!`.
+    pub async fn recv(&self) -> (Wake, u64) {
+        loop {
+            if let Some(wakeup) = self.take() {
+                return wakeup;
+            }
+            self.inner.notify.notified().await;
+        }
+    }
+
+    /// Whether NOTIFY can wake the worker right now (always false for the replication source)
+    pub fn is_listening(&self) -> bool {
+        self.inner.listening.load(Ordering::Relaxed)
+    }
+
+    fn set_listening(&self, listening: bool) {
+        self.inner.listening.store(listening, Ordering::Relaxed);
+    }
+
+    fn take(&self) -> Option<(Wake, u64)> {
+        self.inner.pending.lock().expect("wake signal lock poisoned").take()
+    }
+}
+
+/// Per-user token bucket for NOTIFY wake-ups (WORKER_WAKE_USER_BURST)
+///
+/// A user wakes the worker at most `burst` times in a row, then once per `refill`. A signal
+/// past that reserves the user's next token instead: one deferred wake-up when it refills
+/// fetches everything the user inserted meanwhile as one batch. Later signals fold into the
+/// reservation. Urgent inserts and payloads without a recipient are never throttled.
+struct UserWakeLimiter {
+    burst: f64,
+    refill: Duration,
+    buckets: HashMap<(String, Uuid), Bucket>,
+    /// Deferred wake-ups, earliest first
+    reserved: BinaryHeap<Reverse<tokio::time::Instant>>,
+}
+
+struct Bucket {
+    /// Negative while a reservation waits for its token
+    tokens: f64,
+    updated: tokio::time::Instant,
+    reserved_until: Option<tokio::time::Instant>,
+}
+
+impl UserWakeLimiter {
+    /// None when off (burst 0)
+    fn new(burst: u32, refill: Duration) -> Option<Self> {
+        (burst > 0).then(|| Self {
+            burst: burst as f64,
+            refill: refill.max(Duration::from_millis(1)),
+            buckets: HashMap::new(),
+            reserved: BinaryHeap::new(),
+        })
+    }
+
+    /// Whether this signal may wake the worker now (false = covered by a deferred wake-up)
+    fn admit(&mut self, tenant_id: &str, user_id: Uuid, now: tokio::time::Instant) -> bool {
+        let key = (tenant_id.to_string(), user_id);
+        if self.buckets.len() >= MAX_TRACKED_USERS && !self.buckets.contains_key(&key) {
+            self.prune(now);
+        }
+        let (burst, refill) = (self.burst, self.refill);
+        let bucket = self
+            .buckets
+            .entry(key)
+            .or_insert(Bucket { tokens: burst, updated: now, reserved_until: None });
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() / refill.as_secs_f64()).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return true;
+        }
+        if bucket.reserved_until.is_some_and(|at| at > now) {
+            return false;
+        }
+        let at = now + refill.mul_f64(1.0 - bucket.tokens);
+        bucket.tokens -= 1.0;
+        bucket.reserved_until = Some(at);
+        self.reserved.push(Reverse(at));
+        false
+    }
+
+    /// When the earliest deferred wake-up is due
+    fn next_deferred(&self) -> Option<tokio::time::Instant> {
+        self.reserved.peek().map(|Reverse(at)| *at)
+    }
+
+    /// Drop the deferred wake-ups due by now: the one wake-up sent covers them all
+    fn take_deferred(&mut self, now: tokio::time::Instant) -> usize {
+        let mut due = 0;
+        while self.reserved.peek().is_some_and(|Reverse(at)| *at <= now) {
+            self.reserved.pop();
+            due += 1;
+        }
+        due
+    }
+
+    /// Forget users whose bucket has refilled (they would start full anyway)
+    fn prune(&mut self, now: tokio::time::Instant) {
+        let (burst, refill) = (self.burst, self.refill);
+        self.buckets.retain(|_, bucket| {
+            bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() / refill.as_secs_f64() < burst
+        });
+    }
+}
+
 pub struct NotificationListener {
-    database_url: String,
+    hosts: DatabaseHosts,
+    user_burst: u32,
+    user_refill: Duration,
 }
 
 impl NotificationListener {
-    pub fn new(database_url: String) -> Self {
+    /// Listens on the pool's active host and follows it on failover
+    pub fn new(hosts: DatabaseHosts) -> Self {
         debug!("Creating NotificationListener for channel '{}'", NOTIFY_CHANNEL);
-        Self { database_url }
+        Self { hosts, user_burst: 0, user_refill: Duration::ZERO }
+    }
+
+    /// Throttle wake-ups per user: `burst` in a row, then one per `refill` (0 = off)
+    pub fn with_user_burst(mut self, burst: u32, refill: Duration) -> Self {
+        self.user_burst = burst;
+        self.user_refill = refill;
+        self
     }
 
     /// Start listening for NOTIFY events and send signals to the worker
-    pub async fn listen(&self, tx: mpsc::Sender<()>) -> Result<(), sqlx::Error> {
-        info!("═══════════════════════════════════════════════════════════");
-        info!("  NOTIFY LISTENER STARTING");
-        info!("  Channel: {}", NOTIFY_CHANNEL);
-        info!("═══════════════════════════════════════════════════════════");
+    pub async fn listen(&self, signal: WakeSignal) -> Result<(), sqlx::Error> {
+        info!(channel = NOTIFY_CHANNEL, "NOTIFY listener starting");
 
         let mut reconnect_count = 0;
 
@@ -33,14 +249,22 @@ impl NotificationListener {
                 );
             }
 
-            match self.listen_loop(&tx, reconnect_count).await {
+            let session = self.listen_loop(&signal, reconnect_count).await;
+            signal.set_listening(false);
+            match session {
                 Ok(_) => {
-                    warn!(
+                    info!(
+                        host = %self.hosts.active_host(),
                         reconnect_count = reconnect_count,
-                        "Listener loop ended unexpectedly (no error), restarting..."
+                        "Database host switched, moving LISTEN"
                     );
                 }
                 Err(e) => {
+                    // The host may be gone for good: fail over now rather than at the next health check
+                    if self.hosts.has_standbys() && self.hosts.check().await {
+                        warn!(error = %e, reconnect_count = reconnect_count, "Listener error, failed over");
+                        continue;
+                    }
                     error!(
                         error = %e,
                         reconnect_count = reconnect_count,
@@ -52,11 +276,21 @@ impl NotificationListener {
         }
     }
 
-    async fn listen_loop(&self, tx: &mpsc::Sender<()>, session_id: u64) -> Result<(), sqlx::Error> {
+    /// Listen until an error (Err) or a switch to another database host (Ok)
+    async fn listen_loop(&self, signal: &WakeSignal, session_id: u64) -> Result<(), sqlx::Error> {
         trace!("Connecting to PostgreSQL for LISTEN...");
         let connect_start = Instant::now();
 
-        let mut listener = PgListener::connect(&self.database_url).await?;
+        let mut switched = self.hosts.subscribe();
+        switched.mark_unchanged();
+        // A pool of one, like `PgListener::connect`, but with the active host's options
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .max_lifetime(None)
+            .idle_timeout(None)
+            .connect_with(self.hosts.connect_options())
+            .await?;
+        let mut listener = PgListener::connect_with(&pool).await?;
 
         debug!(
             duration_ms = connect_start.elapsed().as_millis() as u64,
@@ -71,14 +305,34 @@ impl NotificationListener {
             session_id = session_id,
             "✓ Now listening for PostgreSQL NOTIFY events"
         );
+        signal.set_listening(true);
+        if session_id > 1 {
+            // Inserts made while reconnecting notified nobody: let the worker look once
+            signal.send(Wake::Normal);
+        }
 
         let mut message_count: u64 = 0;
+        // Per session: a reconnect wakes the worker anyway, which covers pending reservations
+        let mut limiter = UserWakeLimiter::new(self.user_burst, self.user_refill);
 
         loop {
             trace!("Waiting for next NOTIFY event...");
             let wait_start = Instant::now();
+            let deferred = limiter.as_ref().and_then(UserWakeLimiter::next_deferred);
 
-            match listener.recv().await {
+            let received = tokio::select! {
+                received = listener.recv() => received,
+                Ok(()) = switched.changed() => return Ok(()),
+                _ = tokio::time::sleep_until(deferred.unwrap_or_else(tokio::time::Instant::now)), if deferred.is_some() => {
+                    if let Some(limiter) = limiter.as_mut() {
+                        let users = limiter.take_deferred(tokio::time::Instant::now());
+                        trace!(users = users, "Throttled users' tokens refilled, waking the worker");
+                    }
+                    signal.send(Wake::Normal);
+                    continue;
+                }
+            };
+            match received {
                 Ok(notification) => {
                     message_count += 1;
                     let wait_duration = wait_start.elapsed();
@@ -99,30 +353,21 @@ impl NotificationListener {
                         notification.payload().len()
                     );
 
-                    // Signal worker to wake up
-                    trace!("Sending wake signal to worker...");
-                    match tx.try_send(()) {
-                        Ok(_) => {
-                            debug!(
-                                message_number = message_count,
-                                "Wake signal sent to worker successfully"
-                            );
-                        }
-                        Err(mpsc::error::TrySendError::Full(_)) => {
-                            warn!(
-                                message_number = message_count,
-                                queue_capacity = tx.capacity(),
-                                "Wake signal channel FULL - worker is busy (will process on next cycle)"
-                            );
-                        }
-                        Err(mpsc::error::TrySendError::Closed(_)) => {
-                            error!(
-                                message_number = message_count,
-                                "Wake signal channel CLOSED - worker may have crashed!"
-                            );
-                            // Continue anyway, maybe it will be fixed
+                    // Signal worker to wake up (folded into a pending wake-up while it is busy)
+                    let wake = Wake::from_payload(notification.payload());
+                    let throttled = match (&mut limiter, payload_recipient(notification.payload())) {
+                        (Some(limiter), Some((tenant_id, user_id))) if wake == Wake::Normal => {
+                            !limiter.admit(tenant_id, user_id, tokio::time::Instant::now())
                         }
+                        _ => false,
+                    };
+                    if throttled {
+                        metrics::counter!("notifications_wake_throttled_total").increment(1);
+                        trace!(message_number = message_count, "Wake signal deferred (user over burst)");
+                        continue;
                     }
+                    signal.send(wake);
+                    trace!(message_number = message_count, "Wake signal sent to worker");
                 }
                 Err(e) => {
                     error!(
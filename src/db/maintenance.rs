@@ -0,0 +1,163 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::{info, instrument, trace};
+
+pub struct MaintenanceQueries;
+
+impl MaintenanceQueries {
+    /// Current maintenance flag (single row, created by migration 028)
+    pub async fn get(pool: &PgPool) -> Result<MaintenanceMode, sqlx::Error> {
+        trace!("DB get_maintenance_mode");
+
+        sqlx::query_as::<_, MaintenanceMode>(
+            r#"
+            SELECT enabled, reason, changed_by, changed_at, ingestion_closed
+            FROM activity.maintenance_mode
+            WHERE id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Switch maintenance mode on or off
+    #[instrument(skip(pool))]
+    pub async fn set(
+        pool: &PgPool,
+        enabled: bool,
+        reason: Option<&str>,
+        changed_by: &str,
+    ) -> Result<MaintenanceMode, sqlx::Error> {
+        let result = sqlx::query_as::<_, MaintenanceMode>(
+            r#"
+            UPDATE activity.maintenance_mode
+            SET enabled = $1, reason = $2, changed_by = $3, changed_at = now()
+            WHERE id
+            RETURNING enabled, reason, changed_by, changed_at, ingestion_closed
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(enabled)
+        .bind(reason)
+        .bind(changed_by)
+        .fetch_one(pool)
+        .await;
+
+        if result.is_ok() {
+            info!(enabled = enabled, changed_by = %changed_by, "DB set_maintenance_mode: completed");
+        }
+        result
+    }
+
+    /// Whether new notifications are refused (`notifications-service drain`)
+    pub async fn ingestion_closed(pool: &PgPool) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar::<_, bool>("SELECT ingestion_closed FROM activity.maintenance_mode WHERE id")
+            .persistent(super::prepared_statements())
+            .fetch_one(pool)
+            .await
+    }
+
+    /// Close (or reopen) ingestion for the whole environment
+    #[instrument(skip(pool))]
+    pub async fn set_ingestion_closed(pool: &PgPool, closed: bool, changed_by: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE activity.maintenance_mode
+            SET ingestion_closed = $1, ingestion_closed_by = $2, ingestion_closed_at = CASE WHEN $1 THEN now() END
+            WHERE id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(closed)
+        .bind(changed_by)
+        .execute(pool)
+        .await?;
+        info!(closed = closed, changed_by = %changed_by, "DB set_ingestion_closed: completed");
+        Ok(())
+    }
+
+    /// Notifications waiting for delivery (the parked backlog during maintenance)
+    pub async fn backlog(pool: &PgPool) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM activity.notifications WHERE is_processed = false AND deliver_at <= now()",
+        )
+        .persistent(super::prepared_statements())
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Size and age of the backlog, for ingestion backpressure
+    ///
+    /// Both come from `idx_notifications_due`: the oldest due row, and a count that stops at
+    /// `depth_limit` (0 = not counted), so a huge backlog costs no more than a small one.
+    pub async fn backlog_health(pool: &PgPool, depth_limit: i64) -> Result<BacklogHealth, sqlx::Error> {
+        trace!("DB backlog_health: depth_limit={}", depth_limit);
+
+        sqlx::query_as::<_, BacklogHealth>(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM (
+                    SELECT 1 FROM activity.notifications
+                    WHERE is_processed = false AND deliver_at <= now()
+                    LIMIT $1
+                ) due) AS depth,
+                (SELECT deliver_at FROM activity.notifications
+                 WHERE is_processed = false AND deliver_at <= now()
+                 ORDER BY deliver_at
+                 LIMIT 1) AS oldest_due_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(depth_limit)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// The backlog per notification type, biggest first
+    pub async fn backlog_by_type(pool: &PgPool) -> Result<Vec<TypeBacklog>, sqlx::Error> {
+        trace!("DB backlog_by_type");
+
+        sqlx::query_as::<_, TypeBacklog>(
+            r#"
+            SELECT notification_type::text AS notification_type, COUNT(*) AS count, MIN(deliver_at) AS oldest_due_at
+            FROM activity.notifications
+            WHERE is_processed = false AND deliver_at <= now()
+            GROUP BY notification_type
+            ORDER BY count DESC
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_all(pool)
+        .await
+    }
+}
+
+/// Due, undelivered notifications
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct BacklogHealth {
+    /// Counted up to the `depth_limit` passed in
+    pub depth: i64,
+    /// When the longest-waiting one fell due (None = empty backlog)
+    pub oldest_due_at: Option<DateTime<Utc>>,
+}
+
+/// Due, undelivered notifications of one type
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TypeBacklog {
+    pub notification_type: String,
+    pub count: i64,
+    /// When the longest-waiting one fell due
+    pub oldest_due_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MaintenanceMode {
+    pub enabled: bool,
+    pub reason: Option<String>,
+    pub changed_by: Option<String>,
+    pub changed_at: DateTime<Utc>,
+    /// New notifications are refused while the environment drains
+    pub ingestion_closed: bool,
+}
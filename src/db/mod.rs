@@ -1,7 +1,92 @@
+pub mod acks;
+pub mod actions;
+pub mod analytics;
+pub mod announcements;
+pub mod api_keys;
+pub mod archives;
+pub mod attempts;
+pub mod audiences;
+pub mod audit;
+pub mod broadcasts;
+pub mod bundles;
+pub mod bus_deliveries;
+pub mod campaigns;
+pub mod client_errors;
+pub mod compression;
+pub mod costs;
+pub mod devices;
+pub mod digests;
+pub mod drain;
+pub mod engagement;
+pub mod explain;
+pub mod experiments;
+pub mod failover;
+pub mod foreground;
+pub mod leases;
 pub mod listener;
+pub mod maintenance;
+pub mod payload_schemas;
 pub mod pool;
+pub mod preferences;
 pub mod queries;
+pub mod receipts;
+pub mod recurring;
+pub mod reengagement;
+pub mod replay;
+pub mod replication;
+pub mod resend;
+pub mod schema;
+pub mod shadow;
+pub mod slo;
+pub mod suppressions;
+pub mod sync;
+pub mod templates;
+pub mod tenants;
+pub mod topics;
+pub mod webhooks;
 
+pub use acks::AckQueries;
+pub use actions::ActionQueries;
+pub use analytics::AnalyticsQueries;
+pub use announcements::AnnouncementQueries;
+pub use api_keys::ApiKeyQueries;
+pub use archives::ArchiveQueries;
+pub use attempts::AttemptQueries;
+pub use audiences::AudienceQueries;
+pub use audit::AuditQueries;
+pub use broadcasts::BroadcastQueries;
+pub use bundles::BundleQueries;
+pub use bus_deliveries::BusDeliveryQueries;
+pub use campaigns::CampaignQueries;
+pub use client_errors::ClientErrorQueries;
+pub use costs::CostQueries;
+pub use devices::DeviceQueries;
+pub use digests::DigestQueries;
+pub use drain::DrainQueries;
+pub use engagement::EngagementQueries;
+pub use experiments::ExperimentQueries;
+pub use explain::ExplainQueries;
+pub use failover::DatabaseHosts;
+pub use foreground::ForegroundQueries;
+pub use leases::LeaseQueries;
 pub use listener::NotificationListener;
-pub use pool::Database;
-pub use queries::NotificationQueries;
+pub use maintenance::MaintenanceQueries;
+pub use payload_schemas::PayloadSchemaQueries;
+pub use pool::{log_params, prepared_statements, Database};
+pub use preferences::PreferenceQueries;
+pub use queries::{NotificationQueries, RegionClaim};
+pub use receipts::ReceiptQueries;
+pub use recurring::RecurringQueries;
+pub use reengagement::ReengagementQueries;
+pub use replay::ReplayQueries;
+pub use replication::ReplicationSource;
+pub use resend::ResendQueries;
+pub use schema::SchemaQueries;
+pub use shadow::ShadowQueries;
+pub use slo::SloQueries;
+pub use suppressions::SuppressionQueries;
+pub use sync::SyncQueries;
+pub use templates::TemplateQueries;
+pub use tenants::TenantQueries;
+pub use topics::TopicQueries;
+pub use webhooks::WebhookSourceQueries;
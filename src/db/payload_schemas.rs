@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use tracing::instrument;
+
+pub struct PayloadSchemaQueries;
+
+impl PayloadSchemaQueries {
+    /// Every registered schema, by notification_type
+    #[instrument(skip(pool))]
+    pub async fn list(pool: &PgPool) -> Result<Vec<PayloadSchema>, sqlx::Error> {
+        sqlx::query_as::<_, PayloadSchema>(
+            r#"
+            SELECT notification_type, schema, version, updated_by, created_at, updated_at
+            FROM activity.payload_schemas
+            ORDER BY notification_type
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_all(pool)
+        .await
+    }
+
+    /// The schema for one notification_type, if any
+    #[instrument(skip(pool))]
+    pub async fn find(pool: &PgPool, notification_type: &str) -> Result<Option<PayloadSchema>, sqlx::Error> {
+        sqlx::query_as::<_, PayloadSchema>(
+            r#"
+            SELECT notification_type, schema, version, updated_by, created_at, updated_at
+            FROM activity.payload_schemas
+            WHERE notification_type = $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(notification_type)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Register or replace the schema for a notification_type (bumps `version`)
+    #[instrument(skip(pool, schema))]
+    pub async fn save(
+        pool: &PgPool,
+        notification_type: &str,
+        schema: &Value,
+        updated_by: &str,
+    ) -> Result<PayloadSchema, sqlx::Error> {
+        sqlx::query_as::<_, PayloadSchema>(
+            r#"
+            INSERT INTO activity.payload_schemas (notification_type, schema, updated_by)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (notification_type) DO UPDATE
+            SET schema = EXCLUDED.schema,
+                version = activity.payload_schemas.version + 1,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = now()
+            RETURNING notification_type, schema, version, updated_by, created_at, updated_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(notification_type)
+        .bind(schema)
+        .bind(updated_by)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Remove the schema - returns false if the type had none
+    #[instrument(skip(pool))]
+    pub async fn delete(pool: &PgPool, notification_type: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query("DELETE FROM activity.payload_schemas WHERE notification_type = $1")
+            .persistent(super::prepared_statements())
+            .bind(notification_type)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PayloadSchema {
+    pub notification_type: String,
+    pub schema: Value,
+    pub version: i32,
+    pub updated_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
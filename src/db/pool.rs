@@ -1,31 +1,112 @@
-use sqlx::postgres::PgPoolOptions;
-use sqlx::PgPool;
+use super::failover::DatabaseHosts;
+use log::LevelFilter;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{ConnectOptions, PgPool};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
-use tracing::info;
+use tracing::{info, warn};
+
+/// Named (server-side, cached) prepared statements; off with DB_STATEMENT_CACHE_CAPACITY=0
+static PREPARED_STATEMENTS: AtomicBool = AtomicBool::new(true);
+
+/// Passed to `.persistent()` by every query in `db`
+///
+/// False behind pgbouncer transaction pooling: a named statement prepared on one
+/// server connection doesn't exist on the next one the pooler hands out.
+pub fn prepared_statements() -> bool {
+    PREPARED_STATEMENTS.load(Ordering::Relaxed)
+}
+
+/// Bind values logged by the query wrappers (DEBUG_LOG_SQL)
+static LOG_SQL: AtomicBool = AtomicBool::new(false);
+
+/// Log a query's bind values under DEBUG_LOG_SQL (target `sqlx::params`)
+///
+/// sqlx logs the statement text but not what was bound to it. `params` is a
+/// `format_args!`, so nothing is formatted while the flag is off.
+pub fn log_params(query: &str, params: fmt::Arguments<'_>) {
+    if LOG_SQL.load(Ordering::Relaxed) {
+        info!(target: "sqlx::params", query, params = %params, "SQL parameters");
+    }
+}
 
 #[derive(Clone)]
 pub struct Database {
     pub pool: PgPool,
+    /// The pool's host and its standbys (see [`DatabaseHosts`])
+    pub hosts: DatabaseHosts,
 }
 
 impl Database {
     pub async fn connect(database_url: &str) -> Result<Self, sqlx::Error> {
-        info!("Connecting to database...");
+        Self::connect_with(PgConnectOptions::from_str(database_url)?).await
+    }
 
-        let pool = PgPoolOptions::new()
-            .max_connections(10)
-            .min_connections(2)
-            .acquire_timeout(Duration::from_secs(5))
-            .idle_timeout(Duration::from_secs(300))
-            .connect(database_url)
-            .await?;
+    /// Apply the statement cache size to `options` (0 = no prepared statements at all)
+    pub fn with_statement_cache(options: PgConnectOptions, capacity: usize) -> PgConnectOptions {
+        PREPARED_STATEMENTS.store(capacity > 0, Ordering::Relaxed);
+        options.statement_cache_capacity(capacity)
+    }
 
-        info!("Database connected successfully");
+    /// Apply DEBUG_LOG_SQL and DEBUG_SLOW_SQL_MS to `options`
+    ///
+    /// With `log_sql` sqlx logs every statement at INFO (target `sqlx::query`) and the
+    /// wrappers their bind values (`log_params`); without it statements aren't logged at
+    /// all. Statements slower than `slow_ms` are logged at WARN either way (0 = never).
+    pub fn with_query_logging(options: PgConnectOptions, log_sql: bool, slow_ms: u64) -> PgConnectOptions {
+        LOG_SQL.store(log_sql, Ordering::Relaxed);
+        let options = options.log_statements(if log_sql { LevelFilter::Info } else { LevelFilter::Off });
+        match slow_ms {
+            0 => options.log_slow_statements(LevelFilter::Off, Duration::ZERO),
+            ms => options.log_slow_statements(LevelFilter::Warn, Duration::from_millis(ms)),
+        }
+    }
 
-        Ok(Self { pool })
+    /// Connect with explicit options (credentials from a secret backend)
+    pub async fn connect_with(options: PgConnectOptions) -> Result<Self, sqlx::Error> {
+        Self::connect_hosts(options, &[]).await
+    }
+
+    /// Connect to `options`' host, or the first reachable standby (host, port) when it is down
+    pub async fn connect_hosts(options: PgConnectOptions, standbys: &[(String, u16)]) -> Result<Self, sqlx::Error> {
+        info!(standbys = standbys.len(), "Connecting to database...");
+
+        let hosts: Vec<(String, u16)> = std::iter::once((options.get_host().to_string(), options.get_port()))
+            .chain(standbys.iter().cloned())
+            .collect();
+        let mut last_error = None;
+        for (index, (host, port)) in hosts.iter().enumerate() {
+            let connected = PgPoolOptions::new()
+                .max_connections(10)
+                .min_connections(2)
+                .acquire_timeout(Duration::from_secs(5))
+                .idle_timeout(Duration::from_secs(300))
+                .connect_with(options.clone().host(host).port(*port))
+                .await;
+            match connected {
+                Ok(pool) => {
+                    info!(host = %host, port = port, primary = index == 0, "Database connected successfully");
+                    let hosts = DatabaseHosts::new(pool.clone(), hosts, index);
+                    return Ok(Self { pool, hosts });
+                }
+                Err(e) if index + 1 < hosts.len() => {
+                    warn!(host = %host, port = port, error = %e, "Database host unreachable, trying the next one");
+                    last_error = Some(e);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.expect("at least the primary host"))
     }
 
     pub fn pool(&self) -> &PgPool {
         &self.pool
     }
+
+    /// `server_version` of the active host (for `GET /admin/status`)
+    pub async fn server_version(&self) -> Result<String, sqlx::Error> {
+        sqlx::query_scalar("SHOW server_version").fetch_one(&self.pool).await
+    }
 }
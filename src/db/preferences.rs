@@ -0,0 +1,451 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+pub struct PreferenceQueries;
+
+impl PreferenceQueries {
+    /// Check if the user has this notification type enabled (missing row = enabled)
+    #[instrument(skip(pool), fields(user_id = %user_id, notification_type = %notification_type))]
+    pub async fn is_type_enabled(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        notification_type: &str,
+    ) -> Result<bool, sqlx::Error> {
+        trace!(
+            "DB is_type_enabled: checking preference for user {} type '{}'",
+            user_id, notification_type
+        );
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, (bool,)>(
+            r#"
+            SELECT COALESCE(
+                (SELECT enabled
+                 FROM activity.user_notification_preferences
+                 WHERE tenant_id = $3 AND user_id = $1 AND notification_type = $2),
+                true
+            )
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(notification_type)
+        .bind(tenant_id)
+        .fetch_one(pool)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok((enabled,)) => {
+                debug!(
+                    user_id = %user_id,
+                    notification_type = %notification_type,
+                    enabled = *enabled,
+                    duration_ms = duration.as_millis() as u64,
+                    "DB is_type_enabled: completed"
+                );
+            }
+            Err(e) => {
+                error!(
+                    user_id = %user_id,
+                    notification_type = %notification_type,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB is_type_enabled: query failed"
+                );
+            }
+        }
+
+        result.map(|(enabled,)| enabled)
+    }
+
+    /// Get channel preferences for one notification type (only explicit rows)
+    #[instrument(skip(pool), fields(user_id = %user_id, notification_type = %notification_type))]
+    pub async fn get_channel_preferences(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        notification_type: &str,
+    ) -> Result<Vec<ChannelPreference>, sqlx::Error> {
+        trace!(
+            "DB get_channel_preferences: fetching for user {} type '{}'",
+            user_id, notification_type
+        );
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, ChannelPreference>(
+            r#"
+            SELECT notification_type, channel, enabled
+            FROM activity.user_channel_preferences
+            WHERE tenant_id = $3 AND user_id = $1 AND notification_type = $2
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(notification_type)
+        .bind(tenant_id)
+        .fetch_all(pool)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok(prefs) => {
+                debug!(
+                    user_id = %user_id,
+                    notification_type = %notification_type,
+                    count = prefs.len(),
+                    duration_ms = duration.as_millis() as u64,
+                    "DB get_channel_preferences: completed"
+                );
+            }
+            Err(e) => {
+                error!(
+                    user_id = %user_id,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB get_channel_preferences: query failed"
+                );
+            }
+        }
+
+        result
+    }
+
+    /// Get all explicit type and channel preferences for a user
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn get_matrix(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+    ) -> Result<(Vec<TypePreference>, Vec<ChannelPreference>), sqlx::Error> {
+        trace!("DB get_matrix: fetching preference matrix for user {}", user_id);
+        let start = Instant::now();
+
+        let types = sqlx::query_as::<_, TypePreference>(
+            r#"
+            SELECT notification_type, enabled
+            FROM activity.user_notification_preferences
+            WHERE tenant_id = $2 AND user_id = $1
+            ORDER BY notification_type
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(tenant_id)
+        .fetch_all(pool)
+        .await?;
+
+        let channels = sqlx::query_as::<_, ChannelPreference>(
+            r#"
+            SELECT notification_type, channel, enabled
+            FROM activity.user_channel_preferences
+            WHERE tenant_id = $2 AND user_id = $1
+            ORDER BY notification_type, channel
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(tenant_id)
+        .fetch_all(pool)
+        .await?;
+
+        debug!(
+            user_id = %user_id,
+            type_rows = types.len(),
+            channel_rows = channels.len(),
+            duration_ms = start.elapsed().as_millis() as u64,
+            "DB get_matrix: completed"
+        );
+
+        Ok((types, channels))
+    }
+
+    /// Get the user's active snooze window end (None = not snoozed)
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn get_active_snooze(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        trace!("DB get_active_snooze: checking snooze for user {}", user_id);
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, (DateTime<Utc>,)>(
+            r#"
+            SELECT snoozed_until
+            FROM activity.user_snooze
+            WHERE tenant_id = $2 AND user_id = $1 AND snoozed_until > NOW()
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(tenant_id)
+        .fetch_optional(pool)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok(snooze) => {
+                debug!(
+                    user_id = %user_id,
+                    snoozed_until = ?snooze.as_ref().map(|(until,)| until),
+                    duration_ms = duration.as_millis() as u64,
+                    "DB get_active_snooze: completed"
+                );
+            }
+            Err(e) => {
+                error!(
+                    user_id = %user_id,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB get_active_snooze: query failed"
+                );
+            }
+        }
+
+        result.map(|row| row.map(|(until,)| until))
+    }
+
+    /// Set (or replace) the user's snooze window
+    #[instrument(skip(pool), fields(user_id = %user_id, until = %until))]
+    pub async fn set_snooze(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        until: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        trace!("DB set_snooze: snoozing user {} until {}", user_id, until);
+
+        sqlx::query(
+            r#"
+            INSERT INTO activity.user_snooze (tenant_id, user_id, snoozed_until, updated_at)
+            VALUES ($3, $1, $2, now())
+            ON CONFLICT (tenant_id, user_id)
+            DO UPDATE SET snoozed_until = EXCLUDED.snoozed_until, updated_at = now()
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(until)
+        .bind(tenant_id)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    /// Remove the user's snooze window
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn clear_snooze(pool: &PgPool, tenant_id: &str, user_id: Uuid) -> Result<(), sqlx::Error> {
+        trace!("DB clear_snooze: clearing snooze for user {}", user_id);
+
+        sqlx::query("DELETE FROM activity.user_snooze WHERE tenant_id = $2 AND user_id = $1")
+            .persistent(super::prepared_statements())
+            .bind(user_id)
+            .bind(tenant_id)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    }
+
+    /// Get the user's preferred locale (None = not set)
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn get_locale(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+    ) -> Result<Option<String>, sqlx::Error> {
+        trace!("DB get_locale: fetching locale for user {}", user_id);
+
+        sqlx::query_as::<_, (Option<String>,)>(
+            "SELECT locale FROM activity.user_notification_settings WHERE tenant_id = $2 AND user_id = $1"
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(tenant_id)
+        .fetch_optional(pool)
+        .await
+        .map(|row| row.and_then(|(locale,)| locale))
+    }
+
+    /// Get the user's timezone for delivery windows (None = not set)
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn get_timezone(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+    ) -> Result<Option<String>, sqlx::Error> {
+        trace!("DB get_timezone: fetching timezone for user {}", user_id);
+
+        sqlx::query_as::<_, (Option<String>,)>(
+            "SELECT timezone FROM activity.user_notification_settings WHERE tenant_id = $2 AND user_id = $1"
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(tenant_id)
+        .fetch_optional(pool)
+        .await
+        .map(|row| row.and_then(|(timezone,)| timezone))
+    }
+
+    /// Check if the user muted this target (conversation, post, ...)
+    #[instrument(skip(pool), fields(user_id = %user_id, target_type = %target_type, target_id = %target_id))]
+    pub async fn is_target_muted(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        target_type: &str,
+        target_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        trace!(
+            "DB is_target_muted: checking {}:{} for user {}",
+            target_type, target_id, user_id
+        );
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, (bool,)>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM activity.muted_targets
+                WHERE tenant_id = $4 AND user_id = $1 AND target_type = $2 AND target_id = $3
+            )
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(target_type)
+        .bind(target_id)
+        .bind(tenant_id)
+        .fetch_one(pool)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok((muted,)) => {
+                debug!(
+                    user_id = %user_id,
+                    target_type = %target_type,
+                    target_id = %target_id,
+                    muted = *muted,
+                    duration_ms = duration.as_millis() as u64,
+                    "DB is_target_muted: completed"
+                );
+            }
+            Err(e) => {
+                error!(
+                    user_id = %user_id,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB is_target_muted: query failed"
+                );
+            }
+        }
+
+        result.map(|(muted,)| muted)
+    }
+
+    /// List the user's muted targets
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn list_muted_targets(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+    ) -> Result<Vec<MutedTarget>, sqlx::Error> {
+        trace!("DB list_muted_targets: fetching for user {}", user_id);
+
+        sqlx::query_as::<_, MutedTarget>(
+            r#"
+            SELECT target_type, target_id, created_at
+            FROM activity.muted_targets
+            WHERE tenant_id = $2 AND user_id = $1
+            ORDER BY created_at DESC
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(tenant_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Mute a target for the user (idempotent)
+    #[instrument(skip(pool), fields(user_id = %user_id, target_type = %target_type, target_id = %target_id))]
+    pub async fn mute_target(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        target_type: &str,
+        target_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        trace!("DB mute_target: muting {}:{} for user {}", target_type, target_id, user_id);
+
+        sqlx::query(
+            r#"
+            INSERT INTO activity.muted_targets (tenant_id, user_id, target_type, target_id)
+            VALUES ($4, $1, $2, $3)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(target_type)
+        .bind(target_id)
+        .bind(tenant_id)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    /// Unmute a target - returns false if it wasn't muted
+    #[instrument(skip(pool), fields(user_id = %user_id, target_type = %target_type, target_id = %target_id))]
+    pub async fn unmute_target(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        target_type: &str,
+        target_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        trace!("DB unmute_target: unmuting {}:{} for user {}", target_type, target_id, user_id);
+
+        sqlx::query(
+            "DELETE FROM activity.muted_targets WHERE tenant_id = $4 AND user_id = $1 AND target_type = $2 AND target_id = $3"
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(target_type)
+        .bind(target_id)
+        .bind(tenant_id)
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected() > 0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TypePreference {
+    pub notification_type: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ChannelPreference {
+    pub notification_type: String,
+    pub channel: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MutedTarget {
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,47 +1,128 @@
-use crate::models::Notification;
-use sqlx::PgPool;
+use super::schema::notification_select;
+use crate::config::Config;
+use crate::models::{DeviceCapabilities, NewNotification, Notification};
+use chrono::{DateTime, NaiveTime, Utc};
+use sqlx::types::Json;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+use std::sync::LazyLock;
 use std::time::Instant;
 use tracing::{debug, error, info, trace, warn, instrument};
 use uuid::Uuid;
 
 pub struct NotificationQueries;
 
+/// Which rows a worker claims in an active-active deployment (REGION)
+#[derive(Debug, Clone)]
+pub struct RegionClaim {
+    /// Rows homed here, and rows without a region
+    pub region: String,
+    /// Other regions' rows once they have been due this long (that region is down or behind)
+    pub grace_secs: u64,
+}
+
+impl RegionClaim {
+    /// None without REGION: the worker claims every row
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config.region.clone().map(|region| Self { region, grace_secs: config.region_claim_grace_secs })
+    }
+}
+
+/// Due rows, oldest first (`fetch_unprocessed`)
+static UNPROCESSED_SQL: LazyLock<String> = LazyLock::new(|| {
+    format!(
+        r#"
+            SELECT {}
+            FROM activity.notifications
+            WHERE is_processed = false
+              AND deliver_at <= NOW()
+              AND ($3::text IS NULL OR region IS NULL OR region = $3
+                   OR deliver_at <= NOW() - make_interval(secs => $4))
+            ORDER BY deadline ASC NULLS LAST,
+                     CASE WHEN $2 THEN
+                         CASE priority WHEN 'critical' THEN 0 WHEN 'high' THEN 1 WHEN 'normal' THEN 2 ELSE 3 END
+                     ELSE 0 END,
+                     deliver_at ASC,
+                     created_at ASC
+            LIMIT $1
+"#,
+        notification_select("")
+    )
+});
+
+/// Due rows, round-robin across tenants by `scheduling_weight` (`fetch_unprocessed` with `fair`)
+static FAIR_UNPROCESSED_SQL: LazyLock<String> = LazyLock::new(|| {
+    format!(
+        r#"
+            SELECT {}
+            FROM (
+                SELECT n.*,
+                       CASE WHEN $2 THEN
+                           CASE n.priority WHEN 'critical' THEN 0 WHEN 'high' THEN 1 WHEN 'normal' THEN 2 ELSE 3 END
+                       ELSE 0 END AS rank_priority,
+                       row_number() OVER (
+                           PARTITION BY n.tenant_id
+                           ORDER BY CASE WHEN $2 THEN
+                                        CASE n.priority WHEN 'critical' THEN 0 WHEN 'high' THEN 1 WHEN 'normal' THEN 2 ELSE 3 END
+                                    ELSE 0 END,
+                                    n.deliver_at,
+                                    n.created_at
+                       ) AS tenant_position
+                FROM activity.notifications n
+                WHERE n.is_processed = false
+                  AND n.deliver_at <= NOW()
+                  AND ($3::text IS NULL OR n.region IS NULL OR n.region = $3
+                       OR n.deliver_at <= NOW() - make_interval(secs => $4))
+            ) due
+            LEFT JOIN activity.tenants t ON t.tenant_id = due.tenant_id
+            ORDER BY due.deadline ASC NULLS LAST,
+                     (due.tenant_position - 1) / COALESCE(t.scheduling_weight, 1),
+                     due.rank_priority,
+                     due.deliver_at ASC,
+                     due.created_at ASC
+            LIMIT $1
+"#,
+        notification_select("due")
+    )
+});
+
 impl NotificationQueries {
     /// Fetch all unprocessed notifications
-    #[instrument(skip(pool), fields(limit = limit))]
+    ///
+    /// Rows with a deadline first, earliest deadline first. Then oldest first; with
+    /// `by_priority` critical/high go before the rest (draining the backlog after
+    /// maintenance mode). With `fair` the batch is filled round-robin
+    /// across tenants: each round takes a tenant's next `scheduling_weight` rows (in the
+    /// order above), so a flooding tenant gets its share and the others still get theirs.
+    /// Within a tenant the order stays the same, so a user's rows keep their order.
+    /// With `region` only that region's rows (see [`RegionClaim`]).
+    #[instrument(skip(pool), fields(limit = limit, by_priority = by_priority, fair = fair))]
     pub async fn fetch_unprocessed(
         pool: &PgPool,
         limit: i64,
+        by_priority: bool,
+        fair: bool,
+        region: Option<&RegionClaim>,
     ) -> Result<Vec<Notification>, sqlx::Error> {
         trace!("DB fetch_unprocessed: starting query with limit={}", limit);
+        super::log_params(
+            "fetch_unprocessed",
+            format_args!("limit={} by_priority={} fair={} region={:?}", limit, by_priority, fair, region),
+        );
         let start = Instant::now();
 
-        let result = sqlx::query_as::<_, Notification>(
-            r#"
-            SELECT
-                id,
-                user_id,
-                actor_user_id,
-                notification_type::text as notification_type,
-                target_type,
-                target_id,
-                title,
-                message,
-                payload,
-                deep_link,
-                priority,
-                deliver_at,
-                created_at
-            FROM activity.notifications
-            WHERE is_processed = false
-              AND deliver_at <= NOW()
-            ORDER BY deliver_at ASC
-            LIMIT $1
-            "#,
-        )
-        .bind(limit)
-        .fetch_all(pool)
-        .await;
+        let sql = if fair { &*FAIR_UNPROCESSED_SQL } else { &*UNPROCESSED_SQL };
+        let result = sqlx::query_as::<_, Notification>(sql)
+            .persistent(super::prepared_statements())
+            .bind(limit)
+            .bind(by_priority)
+            .bind(region.map(|claim| claim.region.as_str()))
+            .bind(region.map_or(0.0, |claim| claim.grace_secs as f64))
+            .fetch_all(pool)
+            .await
+            .map(|mut rows| {
+                rows.iter_mut().for_each(Notification::inflate_payload);
+                rows
+            });
 
         let duration = start.elapsed();
 
@@ -81,20 +162,228 @@ impl NotificationQueries {
         result
     }
 
+    /// Claim the next due notification for CONSUMPTION_MODE=transactional (None = queue empty)
+    ///
+    /// The row lock is the claim: other replicas skip the row until the transaction ends,
+    /// and it ends by itself when this pod dies. `claim_timeout_secs` makes Postgres abort a
+    /// transaction left idle that long (a hung pod), which releases the row as well. Only a
+    /// recipient's oldest due row can be claimed, so while one replica delivers it the
+    /// others leave that user's newer rows alone; a row with a deadline doesn't wait its turn.
+    /// With `region` only that region's rows (see [`RegionClaim`]).
+    #[instrument(skip(tx), fields(by_priority = by_priority))]
+    pub async fn claim_next(
+        tx: &mut Transaction<'_, Postgres>,
+        by_priority: bool,
+        claim_timeout_secs: u64,
+        region: Option<&RegionClaim>,
+    ) -> Result<Option<Notification>, sqlx::Error> {
+        trace!("DB claim_next: claiming one notification");
+        super::log_params(
+            "claim_next",
+            format_args!(
+                "by_priority={} claim_timeout_secs={} region={:?}",
+                by_priority, claim_timeout_secs, region
+            ),
+        );
+        let start = Instant::now();
+
+        sqlx::query("SELECT set_config('idle_in_transaction_session_timeout', $1, true)")
+            .persistent(super::prepared_statements())
+            .bind(format!("{}s", claim_timeout_secs))
+            .execute(&mut **tx)
+            .await?;
+
+        // NO KEY UPDATE: attempts and receipts written meanwhile may still reference the row
+        let result = sqlx::query_as::<_, Notification>(&format!(
+            r#"
+            SELECT {}
+            FROM activity.notifications n
+            WHERE is_processed = false
+              AND deliver_at <= NOW()
+              AND ($2::text IS NULL OR n.region IS NULL OR n.region = $2
+                   OR n.deliver_at <= NOW() - make_interval(secs => $3))
+              AND (n.deadline IS NOT NULL OR NOT EXISTS (
+                  SELECT 1 FROM activity.notifications older
+                  WHERE older.tenant_id = n.tenant_id
+                    AND older.user_id = n.user_id
+                    AND older.is_processed = false
+                    AND older.deliver_at <= NOW()
+                    AND (older.deliver_at, older.created_at) < (n.deliver_at, n.created_at)
+              ))
+            ORDER BY deadline ASC NULLS LAST,
+                     CASE WHEN $1 THEN
+                         CASE priority WHEN 'critical' THEN 0 WHEN 'high' THEN 1 WHEN 'normal' THEN 2 ELSE 3 END
+                     ELSE 0 END,
+                     deliver_at ASC,
+                     created_at ASC
+            LIMIT 1
+            FOR NO KEY UPDATE SKIP LOCKED
+            "#,
+            notification_select("n")
+        ))
+        .persistent(super::prepared_statements())
+        .bind(by_priority)
+        .bind(region.map(|claim| claim.region.as_str()))
+        .bind(region.map_or(0.0, |claim| claim.grace_secs as f64))
+        .fetch_optional(&mut **tx)
+        .await
+        .map(|row| {
+            row.map(|mut notification| {
+                notification.inflate_payload();
+                notification
+            })
+        });
+
+        match &result {
+            Ok(Some(n)) => debug!(
+                id = %n.id,
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB claim_next: notification claimed"
+            ),
+            Ok(None) => trace!("DB claim_next: nothing due"),
+            Err(e) => error!(
+                duration_ms = start.elapsed().as_millis() as u64,
+                error = %e,
+                "DB claim_next: query failed"
+            ),
+        }
+
+        result
+    }
+
+    /// Earliest `deliver_at` of any unprocessed notification (None = queue empty)
+    ///
+    /// With WAKE_SOURCE=replication the worker sleeps until then instead of polling.
+    #[instrument(skip(pool))]
+    pub async fn next_deliver_at(pool: &PgPool) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        trace!("DB next_deliver_at");
+
+        sqlx::query_as::<_, (Option<DateTime<Utc>>,)>(
+            "SELECT MIN(deliver_at) FROM activity.notifications WHERE is_processed = false"
+        )
+        .persistent(super::prepared_statements())
+        .fetch_one(pool)
+        .await
+        .map(|(next,)| next)
+    }
+
+    /// Insert a notification from an ingestion source (NOTIFY trigger wakes the worker)
+    ///
+    /// Returns false when a row with the same id already exists (redelivery).
+    /// A `deliver_at_local` row becomes due when the earliest timezone (UTC+14) reaches that
+    /// wall-clock time; the router defers it from there to the recipient's own timezone.
+    #[instrument(skip(pool, notification), fields(id = %id, user_id = %notification.recipient()))]
+    pub async fn insert(
+        pool: &PgPool,
+        id: Uuid,
+        notification: &NewNotification,
+    ) -> Result<bool, sqlx::Error> {
+        trace!("DB insert: inserting notification {} for user {}", id, notification.recipient());
+        super::log_params("insert", format_args!("id={} notification={:?}", id, notification.loggable()));
+        let start = Instant::now();
+        let (payload, payload_zstd) = super::compression::split(notification.payload.as_ref());
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO activity.notifications (
+                id, user_id, actor_user_id, notification_type, target_type, target_id,
+                title, message, payload, deep_link, priority,
+                group_key, message_key, message_args, template_key, deliver_at,
+                event_source, event_time, callback_url, tenant_id, created_by, topic, allow_duplicate,
+                pinned_until, deliver_at_local, actions, audience, attachments, payload_zstd, payload_encoding,
+                expires_at, deadline, region
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, COALESCE($11, 'normal'),
+                    $12, $13, $14, $15, COALESCE($16, GREATEST(NOW(), $25::timestamp AT TIME ZONE 'UTC' - interval '14 hours')),
+                    $17, $18, $19, COALESCE($20, 'default'), $21, $22, $23,
+                    $24, $25, $26, $27, $28, $29, $30, $31, $32,
+                    COALESCE($33, activity.home_region(COALESCE($20, 'default'), $2)))
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(notification.recipient())
+        .bind(notification.actor_user_id)
+        .bind(&notification.notification_type)
+        .bind(&notification.target_type)
+        .bind(notification.target_id)
+        .bind(&notification.title)
+        .bind(&notification.message)
+        .bind(payload)
+        .bind(&notification.deep_link)
+        .bind(notification.stored_priority())
+        .bind(&notification.group_key)
+        .bind(&notification.message_key)
+        .bind(&notification.message_args)
+        .bind(&notification.template_key)
+        .bind(notification.deliver_at)
+        .bind(&notification.event_source)
+        .bind(notification.event_time)
+        .bind(&notification.callback_url)
+        .bind(&notification.tenant_id)
+        .bind(&notification.created_by)
+        .bind(&notification.topic)
+        .bind(notification.allow_duplicate)
+        .bind(notification.pinned_until)
+        .bind(notification.deliver_at_local)
+        .bind(notification.actions_json())
+        .bind(&notification.audience)
+        .bind(notification.attachments_json())
+        .bind(&payload_zstd)
+        .bind(payload_zstd.as_ref().map(|_| super::compression::ZSTD))
+        .bind(notification.expires_at)
+        .bind(notification.stored_deadline())
+        .bind(&notification.region)
+        .execute(pool)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok(r) if r.rows_affected() > 0 => {
+                debug!(
+                    id = %id,
+                    duration_ms = duration.as_millis() as u64,
+                    "DB insert: notification created"
+                );
+            }
+            Ok(_) => {
+                debug!(
+                    id = %id,
+                    duration_ms = duration.as_millis() as u64,
+                    "DB insert: notification already exists (duplicate delivery)"
+                );
+            }
+            Err(e) => {
+                error!(
+                    id = %id,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB insert: failed to insert notification"
+                );
+            }
+        }
+
+        result.map(|r| r.rows_affected() > 0)
+    }
+
     /// Mark notification as successfully delivered
-    #[instrument(skip(pool), fields(id = %id))]
+    #[instrument(skip(executor), fields(id = %id))]
     pub async fn mark_success(
-        pool: &PgPool,
+        executor: impl PgExecutor<'_>,
         id: Uuid,
     ) -> Result<bool, sqlx::Error> {
         trace!("DB mark_success: calling sp_notification_success({})", id);
+        super::log_params("mark_success", format_args!("id={}", id));
         let start = Instant::now();
 
         let result = sqlx::query_as::<_, (bool,)>(
             "SELECT activity.sp_notification_success($1)"
         )
+        .persistent(super::prepared_statements())
         .bind(id)
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await;
 
         let duration = start.elapsed();
@@ -128,27 +417,37 @@ impl NotificationQueries {
         result.map(|(success,)| success)
     }
 
-    /// Record delivery failure - returns true if max retries reached (stop trying)
-    #[instrument(skip(pool), fields(id = %id, error_message = %error_message, max_retries = max_retries))]
+    /// Record delivery failure and its category - returns true if max retries reached (stop trying)
+    #[instrument(skip(executor), fields(id = %id, error_message = %error_message, category = %category, max_retries = max_retries))]
     pub async fn mark_failure(
-        pool: &PgPool,
+        executor: impl PgExecutor<'_>,
         id: Uuid,
         error_message: &str,
+        category: &str,
         max_retries: i32,
     ) -> Result<bool, sqlx::Error> {
         trace!(
-            "DB mark_failure: calling sp_notification_failure({}, '{}', {})",
-            id, error_message, max_retries
+            "DB mark_failure: calling sp_notification_failure({}, '{}', {}, '{}')",
+            id, error_message, max_retries, category
+        );
+        super::log_params(
+            "mark_failure",
+            format_args!(
+                "id={} error={:?} max_retries={} category={}",
+                id, error_message, max_retries, category
+            ),
         );
         let start = Instant::now();
 
         let result = sqlx::query_as::<_, (bool,)>(
-            "SELECT activity.sp_notification_failure($1, $2, $3)"
+            "SELECT activity.sp_notification_failure($1, $2, $3, $4)"
         )
+        .persistent(super::prepared_statements())
         .bind(id)
         .bind(error_message)
         .bind(max_retries)
-        .fetch_one(pool)
+        .bind(category)
+        .fetch_one(executor)
         .await;
 
         let duration = start.elapsed();
@@ -185,22 +484,188 @@ impl NotificationQueries {
         result.map(|(max_reached,)| max_reached)
     }
 
-    /// Get FCM tokens for a user
-    #[instrument(skip(pool), fields(user_id = %user_id))]
+    /// Mark notification as suppressed (terminal state, not delivered and not failed)
+    #[instrument(skip(executor), fields(id = %id, reason = %reason))]
+    pub async fn mark_suppressed(
+        executor: impl PgExecutor<'_>,
+        id: Uuid,
+        reason: &str,
+    ) -> Result<bool, sqlx::Error> {
+        trace!("DB mark_suppressed: calling sp_notification_suppressed({}, '{}')", id, reason);
+        super::log_params("mark_suppressed", format_args!("id={} reason={}", id, reason));
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, (bool,)>(
+            "SELECT activity.sp_notification_suppressed($1, $2)"
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(reason)
+        .fetch_one(executor)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok((true,)) => {
+                debug!(
+                    id = %id,
+                    reason = %reason,
+                    duration_ms = duration.as_millis() as u64,
+                    "DB mark_suppressed: notification marked as suppressed"
+                );
+            }
+            Ok((false,)) => {
+                warn!(
+                    id = %id,
+                    duration_ms = duration.as_millis() as u64,
+                    "DB mark_suppressed: stored procedure returned false (notification not found?)"
+                );
+            }
+            Err(e) => {
+                error!(
+                    id = %id,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB mark_suppressed: failed to mark notification"
+                );
+            }
+        }
+
+        result.map(|(updated,)| updated)
+    }
+
+    /// Defer notification to a later delivery time (stays unprocessed)
+    #[instrument(skip(executor), fields(id = %id, until = %until))]
+    pub async fn defer(
+        executor: impl PgExecutor<'_>,
+        id: Uuid,
+        until: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        trace!("DB defer: moving deliver_at of {} to {}", id, until);
+        super::log_params("defer", format_args!("id={} until={}", id, until));
+        let start = Instant::now();
+
+        let result = sqlx::query(
+            "UPDATE activity.notifications SET deliver_at = $2, updated_at = now() WHERE id = $1 AND is_processed = false"
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(until)
+        .execute(executor)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok(_) => {
+                debug!(
+                    id = %id,
+                    until = %until,
+                    duration_ms = duration.as_millis() as u64,
+                    "DB defer: notification deferred"
+                );
+            }
+            Err(e) => {
+                error!(
+                    id = %id,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB defer: failed to defer notification"
+                );
+            }
+        }
+
+        result.map(|_| ())
+    }
+
+    /// Record a Bus delivery and defer the row to the client ack deadline
+    ///
+    /// Without an ack by `until` the worker picks it up again and falls back to push.
+    #[instrument(skip(executor), fields(id = %id, until = %until))]
+    pub async fn await_bus_ack(
+        executor: impl PgExecutor<'_>,
+        id: Uuid,
+        until: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        trace!("DB await_bus_ack: {} until {}", id, until);
+        super::log_params("await_bus_ack", format_args!("id={} until={}", id, until));
+
+        sqlx::query(
+            r#"
+            UPDATE activity.notifications
+            SET bus_delivered_at = now(), deliver_at = $2, updated_at = now()
+            WHERE id = $1 AND is_processed = false
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(until)
+        .execute(executor)
+        .await
+        .map(|_| ())
+    }
+
+    /// Strip the code from a processed OTP row: digit runs in the title masked, the rest cleared
+    ///
+    /// Returns false when the row isn't an OTP or is still pending (retry, Bus ack).
+    #[instrument(skip(pool), fields(id = %id))]
+    pub async fn scrub_code(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+        trace!("DB scrub_code: {}", id);
+
+        let result = sqlx::query(
+            r#"
+            UPDATE activity.notifications
+            SET title = regexp_replace(title, '[0-9]{4,}', '••••', 'g'),
+                message = NULL,
+                message_args = NULL,
+                payload = NULL,
+                payload_zstd = NULL,
+                payload_encoding = NULL,
+                updated_at = now()
+            WHERE id = $1 AND notification_type::text = 'otp' AND is_processed = true
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .execute(pool)
+        .await;
+
+        match &result {
+            Ok(done) => debug!(id = %id, scrubbed = done.rows_affected() > 0, "DB scrub_code: completed"),
+            Err(e) => error!(id = %id, error = %e, "DB scrub_code: update failed"),
+        }
+        result.map(|done| done.rows_affected() > 0)
+    }
+
+    /// Get FCM tokens a user registered for this tenant's app (suppressed tokens left out)
+    #[instrument(skip(pool), fields(tenant_id = %tenant_id, user_id = %user_id))]
     pub async fn get_user_devices(
         pool: &PgPool,
+        tenant_id: &str,
         user_id: Uuid,
     ) -> Result<Vec<UserDevice>, sqlx::Error> {
         trace!("DB get_user_devices: fetching devices for user {}", user_id);
+        super::log_params("get_user_devices", format_args!("tenant_id={} user_id={}", tenant_id, user_id));
         let start = Instant::now();
 
         let result = sqlx::query_as::<_, UserDevice>(
             r#"
-            SELECT fcm_token, device_type
-            FROM activity.user_devices
-            WHERE user_id = $1
+            SELECT fcm_token, device_type, locale, app_version, os_version,
+                   quiet_hours_start, quiet_hours_end, quiet_hours_timezone, enabled_types, push_environment,
+                   capabilities
+            FROM activity.user_devices d
+            WHERE tenant_id = $1 AND user_id = $2
+              AND NOT EXISTS (
+                  SELECT 1 FROM activity.suppressions s
+                  WHERE s.kind = 'token' AND s.value = d.fcm_token
+                    AND (s.tenant_id = d.tenant_id OR s.tenant_id IS NULL)
+                    AND (s.expires_at IS NULL OR s.expires_at > NOW())
+              )
             "#,
         )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
         .bind(user_id)
         .fetch_all(pool)
         .await;
@@ -258,9 +723,11 @@ impl NotificationQueries {
     pub async fn remove_device(pool: &PgPool, fcm_token: &str) -> Result<(), sqlx::Error> {
         let token_preview = Self::mask_token(fcm_token);
         trace!("DB remove_device: deleting device with token {}", token_preview);
+        super::log_params("remove_device", format_args!("fcm_token={}", token_preview));
         let start = Instant::now();
 
         let result = sqlx::query("DELETE FROM activity.user_devices WHERE fcm_token = $1")
+            .persistent(super::prepared_statements())
             .bind(fcm_token)
             .execute(pool)
             .await;
@@ -314,4 +781,23 @@ impl NotificationQueries {
 pub struct UserDevice {
     pub fcm_token: String,
     pub device_type: String,
+    pub locale: Option<String>,
+    /// Reported at registration, for targeting (`crate::targeting`)
+    pub app_version: Option<String>,
+    pub os_version: Option<String>,
+    /// Device preferences (see `worker::devices::device_hold`)
+    pub quiet_hours_start: Option<NaiveTime>,
+    pub quiet_hours_end: Option<NaiveTime>,
+    pub quiet_hours_timezone: Option<String>,
+    pub enabled_types: Option<Vec<String>>,
+    /// production / sandbox (None = PUSH_ENVIRONMENT)
+    pub push_environment: Option<String>,
+    /// What the app declared it handles (None = everything)
+    pub capabilities: Option<Json<DeviceCapabilities>>,
+}
+
+impl UserDevice {
+    pub fn capabilities(&self) -> DeviceCapabilities {
+        self.capabilities.as_deref().copied().unwrap_or_default()
+    }
 }
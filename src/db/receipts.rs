@@ -0,0 +1,201 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgExecutor, PgPool};
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+pub struct ReceiptQueries;
+
+impl ReceiptQueries {
+    /// Queue a receipt for the notification's callback_url and all matching receipt webhooks
+    ///
+    /// Returns the number of receipts queued (0 = nobody is interested).
+    #[instrument(skip(executor, body), fields(id = %notification_id))]
+    pub async fn enqueue(
+        executor: impl PgExecutor<'_>,
+        notification_id: Uuid,
+        notification_type: &str,
+        body: &serde_json::Value,
+    ) -> Result<u64, sqlx::Error> {
+        trace!("DB enqueue_receipt: queueing receipts for {}", notification_id);
+        let start = Instant::now();
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO activity.receipt_deliveries (notification_id, url, body)
+            SELECT $1, targets.url, $3
+            FROM (
+                SELECT callback_url AS url
+                FROM activity.notifications
+                WHERE id = $1 AND callback_url IS NOT NULL
+                UNION
+                SELECT url
+                FROM activity.receipt_webhooks
+                WHERE enabled AND (notification_type IS NULL OR notification_type = $2)
+            ) targets
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(notification_id)
+        .bind(notification_type)
+        .bind(body)
+        .execute(executor)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok(r) => {
+                debug!(
+                    id = %notification_id,
+                    queued = r.rows_affected(),
+                    duration_ms = duration.as_millis() as u64,
+                    "DB enqueue_receipt: completed"
+                );
+            }
+            Err(e) => {
+                error!(
+                    id = %notification_id,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB enqueue_receipt: insert failed"
+                );
+            }
+        }
+
+        result.map(|r| r.rows_affected())
+    }
+
+    /// Claim due receipts: bumps attempts and leases them for `lease_secs`
+    /// so concurrent dispatchers (replicas) don't send the same receipt twice
+    #[instrument(skip(pool))]
+    pub async fn claim_due(
+        pool: &PgPool,
+        limit: i64,
+        max_attempts: i32,
+        lease_secs: i64,
+    ) -> Result<Vec<PendingReceipt>, sqlx::Error> {
+        sqlx::query_as::<_, PendingReceipt>(
+            r#"
+            UPDATE activity.receipt_deliveries
+            SET attempts = attempts + 1,
+                next_attempt_at = now() + make_interval(secs => $3)
+            WHERE id IN (
+                SELECT id
+                FROM activity.receipt_deliveries
+                WHERE delivered_at IS NULL
+                  AND attempts < $2
+                  AND next_attempt_at <= now()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, notification_id, url, body, attempts
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(limit)
+        .bind(max_attempts)
+        .bind(lease_secs as f64)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Mark a receipt as delivered
+    #[instrument(skip(pool))]
+    pub async fn mark_delivered(pool: &PgPool, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE activity.receipt_deliveries SET delivered_at = now(), last_error = NULL WHERE id = $1")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    }
+
+    /// Record a failed attempt and schedule the next one
+    #[instrument(skip(pool, error_message))]
+    pub async fn mark_failed(
+        pool: &PgPool,
+        id: i64,
+        error_message: &str,
+        retry_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE activity.receipt_deliveries SET last_error = $2, next_attempt_at = $3 WHERE id = $1"
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(error_message)
+        .bind(retry_at)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    /// List registered receipt webhooks
+    #[instrument(skip(pool))]
+    pub async fn list_webhooks(pool: &PgPool) -> Result<Vec<ReceiptWebhook>, sqlx::Error> {
+        sqlx::query_as::<_, ReceiptWebhook>(
+            r#"
+            SELECT id, url, notification_type, enabled, created_at
+            FROM activity.receipt_webhooks
+            ORDER BY created_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Register a receipt webhook
+    #[instrument(skip(pool))]
+    pub async fn create_webhook(
+        pool: &PgPool,
+        url: &str,
+        notification_type: Option<&str>,
+    ) -> Result<ReceiptWebhook, sqlx::Error> {
+        sqlx::query_as::<_, ReceiptWebhook>(
+            r#"
+            INSERT INTO activity.receipt_webhooks (url, notification_type)
+            VALUES ($1, $2)
+            RETURNING id, url, notification_type, enabled, created_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(url)
+        .bind(notification_type)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Remove a receipt webhook - returns false if it didn't exist
+    #[instrument(skip(pool))]
+    pub async fn delete_webhook(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query("DELETE FROM activity.receipt_webhooks WHERE id = $1")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PendingReceipt {
+    pub id: i64,
+    pub notification_id: Uuid,
+    pub url: String,
+    pub body: serde_json::Value,
+    /// Including the current attempt
+    pub attempts: i32,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ReceiptWebhook {
+    pub id: Uuid,
+    pub url: String,
+    pub notification_type: Option<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,282 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+/// `created_by` prefix of materialized rows (`recurring:<schedule id>`)
+pub const RECURRING_CREATOR_PREFIX: &str = "recurring:";
+
+pub struct RecurringQueries;
+
+impl RecurringQueries {
+    /// List all recurring notifications
+    #[instrument(skip(pool))]
+    pub async fn list(pool: &PgPool) -> Result<Vec<RecurringNotification>, sqlx::Error> {
+        trace!("DB list_recurring");
+
+        sqlx::query_as::<_, RecurringNotification>(
+            r#"
+            SELECT id, tenant_id, name, cron, timezone, audience, notification_type, title, message,
+                   template_key, message_key, message_args, payload, deep_link, priority, paused,
+                   next_run_at, last_run_at, created_by, created_at, updated_at
+            FROM activity.recurring_notifications
+            ORDER BY tenant_id, name
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Find a recurring notification by id
+    #[instrument(skip(pool))]
+    pub async fn find(pool: &PgPool, id: Uuid) -> Result<Option<RecurringNotification>, sqlx::Error> {
+        sqlx::query_as::<_, RecurringNotification>(
+            r#"
+            SELECT id, tenant_id, name, cron, timezone, audience, notification_type, title, message,
+                   template_key, message_key, message_args, payload, deep_link, priority, paused,
+                   next_run_at, last_run_at, created_by, created_at, updated_at
+            FROM activity.recurring_notifications
+            WHERE id = $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Store a new schedule, first occurrence at `next_run_at`
+    #[instrument(skip(pool, recurring), fields(name = %recurring.name))]
+    pub async fn create(
+        pool: &PgPool,
+        recurring: &NewRecurringNotification,
+        next_run_at: DateTime<Utc>,
+        created_by: &str,
+    ) -> Result<RecurringNotification, sqlx::Error> {
+        trace!("DB create_recurring: '{}' first run at {}", recurring.name, next_run_at);
+
+        sqlx::query_as::<_, RecurringNotification>(
+            r#"
+            INSERT INTO activity.recurring_notifications (
+                tenant_id, name, cron, timezone, audience, notification_type, title, message,
+                template_key, message_key, message_args, payload, deep_link, priority,
+                next_run_at, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+            RETURNING id, tenant_id, name, cron, timezone, audience, notification_type, title, message,
+                   template_key, message_key, message_args, payload, deep_link, priority, paused,
+                   next_run_at, last_run_at, created_by, created_at, updated_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(&recurring.tenant_id)
+        .bind(&recurring.name)
+        .bind(&recurring.cron)
+        .bind(&recurring.timezone)
+        .bind(Json(&recurring.audience))
+        .bind(&recurring.notification_type)
+        .bind(&recurring.title)
+        .bind(&recurring.message)
+        .bind(&recurring.template_key)
+        .bind(&recurring.message_key)
+        .bind(&recurring.message_args)
+        .bind(&recurring.payload)
+        .bind(&recurring.deep_link)
+        .bind(&recurring.priority)
+        .bind(next_run_at)
+        .bind(created_by)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Pause, or resume with a fresh `next_run_at` - None if the schedule doesn't exist
+    #[instrument(skip(pool))]
+    pub async fn set_paused(
+        pool: &PgPool,
+        id: Uuid,
+        paused: bool,
+        next_run_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<RecurringNotification>, sqlx::Error> {
+        sqlx::query_as::<_, RecurringNotification>(
+            r#"
+            UPDATE activity.recurring_notifications
+            SET paused = $2, next_run_at = COALESCE($3, next_run_at), updated_at = now()
+            WHERE id = $1
+            RETURNING id, tenant_id, name, cron, timezone, audience, notification_type, title, message,
+                   template_key, message_key, message_args, payload, deep_link, priority, paused,
+                   next_run_at, last_run_at, created_by, created_at, updated_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .bind(paused)
+        .bind(next_run_at)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Delete a schedule - returns false if it didn't exist
+    ///
+    /// Notifications it already created are left alone.
+    #[instrument(skip(pool))]
+    pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query("DELETE FROM activity.recurring_notifications WHERE id = $1")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+
+    /// Lock the most overdue active schedule (other schedulers skip it)
+    pub async fn claim_due(
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> Result<Option<RecurringNotification>, sqlx::Error> {
+        sqlx::query_as::<_, RecurringNotification>(
+            r#"
+            SELECT id, tenant_id, name, cron, timezone, audience, notification_type, title, message,
+                   template_key, message_key, message_args, payload, deep_link, priority, paused,
+                   next_run_at, last_run_at, created_by, created_at, updated_at
+            FROM activity.recurring_notifications
+            WHERE NOT paused AND next_run_at <= now()
+            ORDER BY next_run_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    /// Insert one notification per recipient for an occurrence, then move the schedule on
+    ///
+    /// Returns the number of notifications created.
+    pub async fn materialize(
+        tx: &mut Transaction<'_, Postgres>,
+        recurring: &RecurringNotification,
+        occurrence: DateTime<Utc>,
+        next_run_at: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        let start = Instant::now();
+        let recipients = recurring.audience.recipients();
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO activity.notifications (
+                id, user_id, notification_type, title, message, payload, deep_link, priority,
+                template_key, message_key, message_args, deliver_at, tenant_id, created_by, event_source,
+                allow_duplicate
+            )
+            SELECT gen_random_uuid(), recipients.user_id, r.notification_type, r.title, r.message,
+                   r.payload, r.deep_link, r.priority, r.template_key, r.message_key, r.message_args,
+                   $2, r.tenant_id, $4 || r.id::text, 'notifications-service/recurring',
+                   -- A schedule repeats its broadcast on purpose
+                   true
+            FROM activity.recurring_notifications r, unnest($3::uuid[]) AS recipients(user_id)
+            WHERE r.id = $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(recurring.id)
+        .bind(occurrence)
+        .bind(&recipients)
+        .bind(RECURRING_CREATOR_PREFIX)
+        .execute(&mut **tx)
+        .await;
+
+        let created = match result {
+            Ok(r) => r.rows_affected(),
+            Err(e) => {
+                error!(id = %recurring.id, error = %e, "DB materialize_recurring: insert failed");
+                return Err(e);
+            }
+        };
+
+        sqlx::query(
+            "UPDATE activity.recurring_notifications SET last_run_at = $2, next_run_at = $3 WHERE id = $1",
+        )
+        .persistent(super::prepared_statements())
+        .bind(recurring.id)
+        .bind(occurrence)
+        .bind(next_run_at)
+        .execute(&mut **tx)
+        .await?;
+
+        debug!(
+            id = %recurring.id,
+            created = created,
+            next_run_at = %next_run_at,
+            duration_ms = start.elapsed().as_millis() as u64,
+            "DB materialize_recurring: completed"
+        );
+        Ok(created)
+    }
+}
+
+/// Who receives each occurrence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Audience {
+    Users { user_ids: Vec<Uuid> },
+    /// One broadcast row (nil user id): Bus topic + FCM topic
+    Broadcast,
+}
+
+impl Audience {
+    /// User ids to create rows for (nil for a broadcast)
+    pub fn recipients(&self) -> Vec<Uuid> {
+        match self {
+            Audience::Users { user_ids } => user_ids.clone(),
+            Audience::Broadcast => vec![Uuid::nil()],
+        }
+    }
+}
+
+/// Schedule as submitted through the API
+#[derive(Debug, Clone)]
+pub struct NewRecurringNotification {
+    pub tenant_id: String,
+    pub name: String,
+    pub cron: String,
+    pub timezone: String,
+    pub audience: Audience,
+    pub notification_type: String,
+    pub title: String,
+    pub message: Option<String>,
+    pub template_key: Option<String>,
+    pub message_key: Option<String>,
+    pub message_args: Option<serde_json::Value>,
+    pub payload: Option<serde_json::Value>,
+    pub deep_link: Option<String>,
+    pub priority: String,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RecurringNotification {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub name: String,
+    pub cron: String,
+    pub timezone: String,
+    pub audience: Json<Audience>,
+    pub notification_type: String,
+    pub title: String,
+    pub message: Option<String>,
+    pub template_key: Option<String>,
+    pub message_key: Option<String>,
+    pub message_args: Option<serde_json::Value>,
+    pub payload: Option<serde_json::Value>,
+    pub deep_link: Option<String>,
+    pub priority: String,
+    pub paused: bool,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
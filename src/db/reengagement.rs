@@ -0,0 +1,261 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+
+/// `created_by` of re-engagement notifications
+pub const REENGAGEMENT_CREATOR: &str = "reengagement";
+
+pub struct ReengagementQueries;
+
+impl ReengagementQueries {
+    /// All tenants' policies
+    #[instrument(skip(pool))]
+    pub async fn list(pool: &PgPool) -> Result<Vec<ReengagementPolicy>, sqlx::Error> {
+        trace!("DB list_reengagement_policies");
+
+        sqlx::query_as::<_, ReengagementPolicy>(
+            r#"
+            SELECT tenant_id, inactive_days, cooldown_days, notification_type, title, message,
+                   template_key, deep_link, enabled, last_run_at, updated_at
+            FROM activity.reengagement_policies
+            ORDER BY tenant_id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Create or replace a tenant's policy
+    #[instrument(skip(pool, settings), fields(tenant_id = %tenant_id))]
+    pub async fn upsert(
+        pool: &PgPool,
+        tenant_id: &str,
+        settings: &ReengagementSettings,
+    ) -> Result<ReengagementPolicy, sqlx::Error> {
+        trace!("DB upsert_reengagement_policy: '{}'", tenant_id);
+
+        sqlx::query_as::<_, ReengagementPolicy>(
+            r#"
+            INSERT INTO activity.reengagement_policies (
+                tenant_id, inactive_days, cooldown_days, notification_type, title, message,
+                template_key, deep_link, enabled
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (tenant_id)
+            DO UPDATE SET
+                inactive_days = EXCLUDED.inactive_days,
+                cooldown_days = EXCLUDED.cooldown_days,
+                notification_type = EXCLUDED.notification_type,
+                title = EXCLUDED.title,
+                message = EXCLUDED.message,
+                template_key = EXCLUDED.template_key,
+                deep_link = EXCLUDED.deep_link,
+                enabled = EXCLUDED.enabled,
+                updated_at = now()
+            RETURNING tenant_id, inactive_days, cooldown_days, notification_type, title, message,
+                      template_key, deep_link, enabled, last_run_at, updated_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(settings.inactive_days)
+        .bind(settings.cooldown_days)
+        .bind(&settings.notification_type)
+        .bind(&settings.title)
+        .bind(&settings.message)
+        .bind(&settings.template_key)
+        .bind(&settings.deep_link)
+        .bind(settings.enabled)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Remove a tenant's policy - false if it had none (the send history stays)
+    #[instrument(skip(pool))]
+    pub async fn delete(pool: &PgPool, tenant_id: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query("DELETE FROM activity.reengagement_policies WHERE tenant_id = $1")
+            .persistent(super::prepared_statements())
+            .bind(tenant_id)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+
+    /// Lock an enabled policy that didn't run for `interval_secs` (other jobs skip it)
+    pub async fn claim_due(
+        tx: &mut Transaction<'_, Postgres>,
+        interval_secs: i64,
+    ) -> Result<Option<ReengagementPolicy>, sqlx::Error> {
+        sqlx::query_as::<_, ReengagementPolicy>(
+            r#"
+            SELECT tenant_id, inactive_days, cooldown_days, notification_type, title, message,
+                   template_key, deep_link, enabled, last_run_at, updated_at
+            FROM activity.reengagement_policies
+            WHERE enabled AND (last_run_at IS NULL OR last_run_at <= now() - make_interval(secs => $1))
+            ORDER BY last_run_at NULLS FIRST
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(interval_secs as f64)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    /// Send the policy's notification to up to `limit` dormant users, then stamp the run
+    ///
+    /// Dormant: a device older than `inactive_days`, and within that period no read, no
+    /// open or click and no Bus delivery, and no re-engagement within `cooldown_days`. The
+    /// send history is written in the same statement, so concurrent runs can't double up.
+    /// Returns the number of notifications created.
+    pub async fn send(
+        tx: &mut Transaction<'_, Postgres>,
+        policy: &ReengagementPolicy,
+        limit: i64,
+    ) -> Result<u64, sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query_scalar::<_, i64>(
+            r#"
+            WITH cutoff AS (
+                SELECT now() - make_interval(days => $2) AS since,
+                       now() - make_interval(days => $3) AS cooldown
+            ),
+            dormant AS (
+                SELECT d.user_id
+                FROM activity.user_devices d, cutoff
+                WHERE d.tenant_id = $1
+                GROUP BY d.user_id, cutoff.since, cutoff.cooldown
+                HAVING MIN(d.created_at) <= cutoff.since
+                   AND NOT EXISTS (
+                       SELECT 1 FROM activity.notifications n
+                       WHERE n.tenant_id = $1 AND n.user_id = d.user_id AND n.read_at >= cutoff.since
+                   )
+                   AND NOT EXISTS (
+                       SELECT 1 FROM activity.notification_engagement e
+                       WHERE e.tenant_id = $1 AND e.user_id = d.user_id AND e.occurred_at >= cutoff.since
+                   )
+                   AND NOT EXISTS (
+                       SELECT 1
+                       FROM activity.notifications n
+                       JOIN activity.notification_attempts a ON a.notification_id = n.id
+                       WHERE n.tenant_id = $1 AND n.user_id = d.user_id AND n.created_at >= cutoff.since
+                         AND a.channel = 'bus' AND a.outcome = 'delivered' AND a.attempted_at >= cutoff.since
+                   )
+                   AND NOT EXISTS (
+                       SELECT 1 FROM activity.reengagement_sends s
+                       WHERE s.tenant_id = $1 AND s.user_id = d.user_id AND s.sent_at > cutoff.cooldown
+                   )
+                LIMIT $4
+            ),
+            sent AS (
+                INSERT INTO activity.reengagement_sends AS s (tenant_id, user_id, notification_id, sent_at)
+                SELECT $1, user_id, gen_random_uuid(), now() FROM dormant
+                ON CONFLICT (tenant_id, user_id) DO UPDATE
+                SET notification_id = EXCLUDED.notification_id, sent_at = EXCLUDED.sent_at
+                WHERE s.sent_at <= (SELECT cooldown FROM cutoff)
+                RETURNING user_id, notification_id
+            ),
+            created AS (
+                INSERT INTO activity.notifications (
+                    id, user_id, notification_type, title, message, deep_link, priority,
+                    template_key, message_args, tenant_id, created_by, event_source
+                )
+                SELECT sent.notification_id, sent.user_id, $5, $6, $7, $8, 'normal',
+                       $9, jsonb_build_object('inactive_days', $2::int), $1, $10, 'notifications-service/reengagement'
+                FROM sent
+                RETURNING id
+            ),
+            stamped AS (
+                UPDATE activity.reengagement_policies SET last_run_at = now() WHERE tenant_id = $1
+            )
+            SELECT COUNT(*) FROM created
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(&policy.tenant_id)
+        .bind(policy.inactive_days)
+        .bind(policy.cooldown_days)
+        .bind(limit)
+        .bind(&policy.notification_type)
+        .bind(&policy.title)
+        .bind(&policy.message)
+        .bind(&policy.deep_link)
+        .bind(&policy.template_key)
+        .bind(REENGAGEMENT_CREATOR)
+        .fetch_one(&mut **tx)
+        .await;
+
+        let duration = start.elapsed();
+        match result {
+            Ok(created) => {
+                debug!(
+                    tenant_id = %policy.tenant_id,
+                    created = created,
+                    duration_ms = duration.as_millis() as u64,
+                    "DB send_reengagement: completed"
+                );
+                Ok(created.max(0) as u64)
+            }
+            Err(e) => {
+                error!(
+                    tenant_id = %policy.tenant_id,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB send_reengagement: query failed"
+                );
+                Err(e)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ReengagementPolicy {
+    pub tenant_id: String,
+    pub inactive_days: i32,
+    pub cooldown_days: i32,
+    pub notification_type: String,
+    pub title: String,
+    pub message: Option<String>,
+    pub template_key: Option<String>,
+    pub deep_link: Option<String>,
+    pub enabled: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Editable policy fields (admin API)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReengagementSettings {
+    /// Days without a read, open or Bus connection before a user gets the nudge
+    pub inactive_days: i32,
+    /// At most one nudge per user per this many days
+    #[serde(default = "default_cooldown_days")]
+    pub cooldown_days: i32,
+    #[serde(default = "default_notification_type")]
+    pub notification_type: String,
+    pub title: String,
+    pub message: Option<String>,
+    /// Render the copy from this template instead (`inactive_days` is a variable)
+    pub template_key: Option<String>,
+    pub deep_link: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_cooldown_days() -> i32 {
+    30
+}
+
+fn default_notification_type() -> String {
+    "reengagement".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
@@ -0,0 +1,129 @@
+use super::explain::{ExplainedNotification, EXPLAINED_COLUMNS};
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, instrument};
+use uuid::Uuid;
+
+/// Per-user notifications the worker finished, created inside a time window
+///
+/// Broadcasts and topic sends aren't routed per user, and OTP codes are scrubbed after
+/// delivery, so neither is replayed.
+#[derive(Debug, Clone)]
+pub struct ReplayWindow {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub notification_type: Option<String>,
+    pub tenant_id: Option<String>,
+}
+
+/// Channels the worker tried for one notification
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AttemptedChannels {
+    pub notification_id: Uuid,
+    /// In the order of their first attempt
+    pub channels: Vec<String>,
+}
+
+const WINDOW_FILTER: &str = r#"
+    is_processed
+    AND user_id <> '00000000-0000-0000-0000-000000000000'
+    AND notification_type::text <> 'otp'
+    AND created_at >= $1 AND created_at < $2
+    AND ($3::text IS NULL OR notification_type::text = $3)
+    AND ($4::text IS NULL OR tenant_id = $4)
+"#;
+
+pub struct ReplayQueries;
+
+impl ReplayQueries {
+    /// Matching rows of the hot table, oldest first
+    #[instrument(skip(pool))]
+    pub async fn window(
+        pool: &PgPool,
+        window: &ReplayWindow,
+        limit: i64,
+    ) -> Result<Vec<ExplainedNotification>, sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, ExplainedNotification>(&format!(
+            "SELECT {} FROM activity.notifications WHERE {} ORDER BY created_at, id LIMIT $5",
+            *EXPLAINED_COLUMNS, WINDOW_FILTER
+        ))
+        .persistent(super::prepared_statements())
+        .bind(window.from)
+        .bind(window.to)
+        .bind(&window.notification_type)
+        .bind(&window.tenant_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await;
+
+        match &result {
+            Ok(rows) => debug!(
+                count = rows.len(),
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB replay_window: completed"
+            ),
+            Err(e) => error!(error = %e, "DB replay_window: query failed"),
+        }
+        result.map(inflate)
+    }
+
+    /// Matching rows of an archive (`to_jsonb` rows as exported), oldest first
+    ///
+    /// Read through the row type without touching the table; columns added after the
+    /// export come back NULL.
+    #[instrument(skip(pool, rows), fields(rows = rows.len()))]
+    pub async fn archived(
+        pool: &PgPool,
+        rows: &[serde_json::Value],
+        window: &ReplayWindow,
+        limit: i64,
+    ) -> Result<Vec<ExplainedNotification>, sqlx::Error> {
+        sqlx::query_as::<_, ExplainedNotification>(&format!(
+            "SELECT {} FROM jsonb_populate_recordset(NULL::activity.notifications, $6) \
+             WHERE {} ORDER BY created_at, id LIMIT $5",
+            *EXPLAINED_COLUMNS, WINDOW_FILTER
+        ))
+        .persistent(super::prepared_statements())
+        .bind(window.from)
+        .bind(window.to)
+        .bind(&window.notification_type)
+        .bind(&window.tenant_id)
+        .bind(limit)
+        .bind(Json(rows))
+        .fetch_all(pool)
+        .await
+        .map(inflate)
+    }
+
+    /// The channels attempted per notification (notifications without attempts are left out)
+    #[instrument(skip(pool, ids), fields(ids = ids.len()))]
+    pub async fn attempted_channels(pool: &PgPool, ids: &[Uuid]) -> Result<Vec<AttemptedChannels>, sqlx::Error> {
+        sqlx::query_as::<_, AttemptedChannels>(
+            r#"
+            SELECT notification_id, array_agg(channel ORDER BY first_attempt) AS channels
+            FROM (
+                SELECT notification_id, channel, MIN(id) AS first_attempt
+                FROM activity.notification_attempts
+                WHERE notification_id = ANY($1)
+                GROUP BY notification_id, channel
+            ) attempted
+            GROUP BY notification_id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(ids)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+fn inflate(mut rows: Vec<ExplainedNotification>) -> Vec<ExplainedNotification> {
+    for row in &mut rows {
+        row.notification.inflate_payload();
+    }
+    rows
+}
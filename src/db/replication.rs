@@ -0,0 +1,260 @@
+use super::failover::DatabaseHosts;
+use super::listener::{Wake, WakeSignal};
+use sqlx::{Connection, PgConnection};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, trace, warn};
+use uuid::Uuid;
+
+/// Changes read from the slot per call (the call stops at the next transaction boundary)
+const MAX_CHANGES_PER_READ: i32 = 10_000;
+
+/// Wake source for databases where triggers are forbidden (WAKE_SOURCE=replication)
+///
+/// Tails a logical replication slot with the built-in `pgoutput` plugin. The publication
+/// only carries inserts into `activity.notifications`, and each insert wakes the worker
+/// like a NOTIFY would (high/critical as urgent). sqlx has no replication-protocol
+/// support, so the slot is read with `pg_logical_slot_get_binary_changes` every
+/// REPLICATION_POLL_INTERVAL_MS on a dedicated connection. The slot is temporary: it
+/// belongs to that connection, so every replica has its own and nothing is left behind
+/// when a pod goes away. Needs `wal_level=logical` and a role with REPLICATION.
+pub struct ReplicationSource {
+    hosts: DatabaseHosts,
+    publication: String,
+    poll_interval: Duration,
+}
+
+impl ReplicationSource {
+    /// Reads the slot on the pool's active host and follows it on failover
+    pub fn new(hosts: DatabaseHosts, publication: String, poll_interval: Duration) -> Self {
+        debug!("Creating ReplicationSource for publication '{}'", publication);
+        Self { hosts, publication, poll_interval }
+    }
+
+    /// Publication names are interpolated into DDL, so only plain identifiers are accepted
+    pub fn validate_publication(name: &str) -> Result<(), String> {
+        let valid = name.chars().next().is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+            && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            && name.len() <= 63;
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("Invalid REPLICATION_PUBLICATION '{}' (lowercase letters, digits and _)", name))
+        }
+    }
+
+    /// Tail the slot and send wake-ups to the worker (reconnects on errors)
+    pub async fn run(&self, signal: WakeSignal) -> Result<(), sqlx::Error> {
+        info!(
+            publication = %self.publication,
+            poll_interval_ms = self.poll_interval.as_millis() as u64,
+            "Replication wake source starting"
+        );
+
+        let mut reconnect_count = 0;
+
+        loop {
+            reconnect_count += 1;
+            if reconnect_count > 1 {
+                debug!(attempt = reconnect_count, "Reconnecting replication slot...");
+            }
+
+            if let Err(e) = self.tail(&signal, reconnect_count).await {
+                if self.hosts.has_standbys() && self.hosts.check().await {
+                    warn!(error = %e, reconnect_count = reconnect_count, "Replication source error, failed over");
+                    continue;
+                }
+                error!(
+                    error = %e,
+                    reconnect_count = reconnect_count,
+                    "Replication source error, reconnecting in 5s..."
+                );
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    }
+
+    async fn tail(&self, signal: &WakeSignal, session_id: u64) -> Result<(), sqlx::Error> {
+        let connect_start = Instant::now();
+        let mut switched = self.hosts.subscribe();
+        switched.mark_unchanged();
+        let mut conn = PgConnection::connect_with(&self.hosts.connect_options()).await?;
+        debug!(
+            duration_ms = connect_start.elapsed().as_millis() as u64,
+            "PostgreSQL connection established for replication"
+        );
+
+        self.ensure_publication(&mut conn).await?;
+        let slot = format!("notifications_{}", Uuid::new_v4().simple());
+        sqlx::query("SELECT pg_create_logical_replication_slot($1, 'pgoutput', true)")
+            .bind(&slot)
+            .execute(&mut conn)
+            .await?;
+        info!(
+            slot = %slot,
+            publication = %self.publication,
+            session_id = session_id,
+            "✓ Tailing logical replication slot"
+        );
+
+        // Inserts made before the slot existed are not in it: let the worker look once
+        signal.send(Wake::Normal);
+
+        let mut relations = Relations::default();
+        let mut insert_count: u64 = 0;
+        loop {
+            // The slot lives on the old host: start over on the new one
+            if switched.has_changed().unwrap_or(false) {
+                info!(host = %self.hosts.active_host(), "Database host switched, moving the replication slot");
+                return Ok(());
+            }
+            let changes: Vec<(Vec<u8>,)> = sqlx::query_as(
+                r#"
+                SELECT data FROM pg_logical_slot_get_binary_changes(
+                    $1, NULL, $2, 'proto_version', '1', 'publication_names', $3)
+                "#,
+            )
+            .bind(&slot)
+            .bind(MAX_CHANGES_PER_READ)
+            .bind(&self.publication)
+            .fetch_all(&mut conn)
+            .await?;
+
+            // One wake per read is enough: the worker fetches everything that is due
+            let mut wake = None;
+            for (message,) in &changes {
+                if let Some(insert) = relations.decode(message) {
+                    insert_count += 1;
+                    trace!(inserts = insert_count, "Insert decoded from replication slot");
+                    if wake != Some(Wake::Urgent) {
+                        wake = Some(insert);
+                    }
+                }
+            }
+            match wake {
+                Some(wake) => {
+                    debug!(messages = changes.len(), urgent = wake == Wake::Urgent, "Inserts read from replication slot");
+                    signal.send(wake);
+                }
+                None => tokio::time::sleep(self.poll_interval).await,
+            }
+        }
+    }
+
+    async fn ensure_publication(&self, conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+        let exists: Option<(i32,)> = sqlx::query_as("SELECT 1 FROM pg_publication WHERE pubname = $1")
+            .bind(&self.publication)
+            .fetch_optional(&mut *conn)
+            .await?;
+        if exists.is_none() {
+            // Name checked by validate_publication
+            sqlx::query(&format!(
+                "CREATE PUBLICATION {} FOR TABLE activity.notifications WITH (publish = 'insert')",
+                self.publication
+            ))
+            .execute(&mut *conn)
+            .await?;
+            info!(publication = %self.publication, "Created publication for notification inserts");
+        }
+        Ok(())
+    }
+}
+
+/// Column names per relation, from pgoutput Relation messages
+///
+/// pgoutput sends a relation's columns before its first change in every read, so inserts
+/// can be decoded without querying the catalog.
+#[derive(Debug, Default)]
+pub struct Relations {
+    columns: HashMap<u32, Vec<String>>,
+}
+
+impl Relations {
+    /// Decode one pgoutput (protocol version 1) message - Some for an insert
+    ///
+    /// Other messages (begin, commit, type, origin, ...) only update the relation map or
+    /// are skipped.
+    pub fn decode(&mut self, message: &[u8]) -> Option<Wake> {
+        let mut reader = Reader { buf: message, pos: 0 };
+        match reader.u8()? {
+            b'R' => {
+                let oid = reader.u32()?;
+                reader.cstr()?; // namespace
+                reader.cstr()?; // relation name
+                reader.u8()?; // replica identity
+                let count = reader.u16()?;
+                let mut columns = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    reader.u8()?; // flags
+                    columns.push(reader.cstr()?.to_string());
+                    reader.u32()?; // type oid
+                    reader.u32()?; // type modifier
+                }
+                self.columns.insert(oid, columns);
+                None
+            }
+            b'I' => {
+                let oid = reader.u32()?;
+                if reader.u8()? != b'N' {
+                    return None;
+                }
+                let priority = self
+                    .columns
+                    .get(&oid)
+                    .and_then(|columns| columns.iter().position(|name| name == "priority"));
+                let values = reader.tuple()?;
+                Some(Wake::from_priority(priority.and_then(|i| values.get(i).copied().flatten())))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Big-endian cursor over one pgoutput message
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.buf.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn cstr(&mut self) -> Option<&'a str> {
+        let len = self.buf.get(self.pos..)?.iter().position(|&b| b == 0)?;
+        let text = std::str::from_utf8(self.take(len)?).ok()?;
+        self.pos += 1;
+        Some(text)
+    }
+
+    /// TupleData: text values (None = NULL or unchanged TOAST)
+    fn tuple(&mut self) -> Option<Vec<Option<&'a str>>> {
+        let count = self.u16()?;
+        let mut values = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            match self.u8()? {
+                b't' | b'b' => {
+                    let len = self.u32()? as usize;
+                    values.push(std::str::from_utf8(self.take(len)?).ok());
+                }
+                _ => values.push(None),
+            }
+        }
+        Some(values)
+    }
+}
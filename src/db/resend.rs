@@ -0,0 +1,171 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, info, instrument};
+
+/// Notifications that ended failed or suppressed inside a time window
+///
+/// Failed = given up after the last retry (processed, last error newer than the last
+/// update); the window applies to `last_error_at`. Suppressed rows match on `suppressed_at`.
+#[derive(Debug, Clone)]
+pub struct ResendWindow {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub notification_type: Option<String>,
+    pub tenant_id: Option<String>,
+}
+
+/// Matching rows, split by how they ended
+#[derive(Debug, Clone, Default, Serialize, sqlx::FromRow)]
+pub struct ResendCount {
+    pub failed: i64,
+    pub suppressed: i64,
+}
+
+pub struct ResendQueries;
+
+impl ResendQueries {
+    /// Count what [`ResendQueries::resend`] would re-drive (dry run)
+    #[instrument(skip(pool))]
+    pub async fn count(pool: &PgPool, window: &ResendWindow) -> Result<ResendCount, sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, ResendCount>(
+            r#"
+            SELECT COUNT(*) FILTER (WHERE suppressed_at IS NULL) AS failed,
+                   COUNT(*) FILTER (WHERE suppressed_at IS NOT NULL) AS suppressed
+            FROM activity.notifications
+            WHERE is_processed
+              AND ($3::text IS NULL OR notification_type::text = $3)
+              AND ($4::text IS NULL OR tenant_id = $4)
+              AND (
+                    (suppressed_at IS NULL AND last_error_at >= updated_at
+                     AND last_error_at >= $1 AND last_error_at < $2)
+                 OR (suppressed_at >= $1 AND suppressed_at < $2)
+              )
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(window.from)
+        .bind(window.to)
+        .bind(&window.notification_type)
+        .bind(&window.tenant_id)
+        .fetch_one(pool)
+        .await;
+
+        match &result {
+            Ok(count) => debug!(
+                failed = count.failed,
+                suppressed = count.suppressed,
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB resend_count: completed"
+            ),
+            Err(e) => error!(error = %e, "DB resend_count: query failed"),
+        }
+        result
+    }
+
+    /// Put matching notifications back in the queue - returns how many were re-driven
+    ///
+    /// Error count and suppression are cleared, so the worker runs the full pipeline
+    /// again (preferences and mutes still apply) with all retries. Rows are
+    /// picked up on the next poll; earlier attempts stay in `notification_attempts`.
+    #[instrument(skip(pool))]
+    pub async fn resend(pool: &PgPool, window: &ResendWindow) -> Result<ResendCount, sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, ResendCount>(
+            r#"
+            WITH resent AS (
+                UPDATE activity.notifications n
+                SET is_processed = false,
+                    error_count = 0,
+                    last_error = NULL,
+                    last_error_at = NULL,
+                    failure_category = NULL,
+                    suppressed_at = NULL,
+                    suppression_reason = NULL,
+                    deliver_at = now(),
+                    updated_at = now()
+                FROM (
+                    SELECT id, suppressed_at IS NOT NULL AS was_suppressed
+                    FROM activity.notifications
+                    WHERE is_processed
+                      AND ($3::text IS NULL OR notification_type::text = $3)
+                      AND ($4::text IS NULL OR tenant_id = $4)
+                      AND (
+                            (suppressed_at IS NULL AND last_error_at >= updated_at
+                             AND last_error_at >= $1 AND last_error_at < $2)
+                         OR (suppressed_at >= $1 AND suppressed_at < $2)
+                      )
+                    FOR UPDATE
+                ) matched
+                WHERE n.id = matched.id
+                RETURNING matched.was_suppressed
+            )
+            SELECT COUNT(*) FILTER (WHERE NOT was_suppressed) AS failed,
+                   COUNT(*) FILTER (WHERE was_suppressed) AS suppressed
+            FROM resent
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(window.from)
+        .bind(window.to)
+        .bind(&window.notification_type)
+        .bind(&window.tenant_id)
+        .fetch_one(pool)
+        .await;
+
+        match &result {
+            Ok(count) => info!(
+                failed = count.failed,
+                suppressed = count.suppressed,
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB resend: completed"
+            ),
+            Err(e) => error!(error = %e, "DB resend: query failed"),
+        }
+        result
+    }
+
+    /// Most recent notifications that gave up after their last retry (newest first)
+    ///
+    /// `category` narrows them to one `failure_category` (see `worker::FailureCategory`).
+    #[instrument(skip(pool))]
+    pub async fn recent_failures(
+        pool: &PgPool,
+        category: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<FailedNotification>, sqlx::Error> {
+        sqlx::query_as::<_, FailedNotification>(
+            r#"
+            SELECT id, tenant_id, user_id, notification_type::text AS notification_type, error_count,
+                   last_error, failure_category, last_error_at
+            FROM activity.notifications
+            WHERE is_processed AND suppressed_at IS NULL AND last_error_at >= updated_at
+              AND ($2::text IS NULL OR failure_category = $2)
+            ORDER BY last_error_at DESC
+            LIMIT $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(limit)
+        .bind(category)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct FailedNotification {
+    pub id: uuid::Uuid,
+    pub tenant_id: String,
+    pub user_id: uuid::Uuid,
+    pub notification_type: String,
+    pub error_count: Option<i32>,
+    pub last_error: Option<String>,
+    /// None for failures recorded before classification existed
+    pub failure_category: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+}
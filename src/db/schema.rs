@@ -0,0 +1,256 @@
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use tracing::{error, instrument};
+
+/// Columns the worker decodes (`query_as::<_, Notification>`) or filters on when claiming
+///
+/// Reads of `Notification` select exactly these through [`notification_select`]: add a
+/// column here, not to the queries.
+pub const NOTIFICATION_COLUMNS: &[&str] = &[
+    "id",
+    "tenant_id",
+    "user_id",
+    "actor_user_id",
+    "notification_type",
+    "target_type",
+    "target_id",
+    "title",
+    "message",
+    "payload",
+    "payload_zstd",
+    "deep_link",
+    "priority",
+    "group_key",
+    "message_key",
+    "message_args",
+    "template_key",
+    "actions",
+    "attachments",
+    "created_by",
+    "device_filter",
+    "topic",
+    "audience",
+    "bus_delivered_at",
+    "acked_at",
+    "allow_duplicate",
+    "pinned_until",
+    "expires_at",
+    "deadline",
+    "bundled_at",
+    "deliver_at_local",
+    "deliver_at",
+    "created_at",
+    "is_processed",
+];
+
+/// Columns a fan-out copy (topic subscriber, audience member) takes over from its parent
+///
+/// Not copied: the recipient and region (per copy), callback_url (one receipt per copy is
+/// not what the producer asked for) and the delivery state.
+pub const COPIED_COLUMNS: &[&str] = &[
+    "tenant_id",
+    "actor_user_id",
+    "notification_type",
+    "target_type",
+    "target_id",
+    "title",
+    "message",
+    "payload",
+    "payload_zstd",
+    "payload_encoding",
+    "deep_link",
+    "priority",
+    "group_key",
+    "message_key",
+    "message_args",
+    "template_key",
+    "actions",
+    "attachments",
+    "created_by",
+    "event_source",
+    "device_filter",
+    "pinned_until",
+    "expires_at",
+    "deadline",
+    "deliver_at_local",
+];
+
+/// [`NOTIFICATION_COLUMNS`] as a select list, qualified with `alias` (e.g. `n`) unless empty
+///
+/// `notification_type` is an enum in activitydb and is read as text.
+pub fn notification_select(alias: &str) -> String {
+    column_list(NOTIFICATION_COLUMNS, alias, |column| match column {
+        "notification_type" => Some("::text AS notification_type"),
+        _ => None,
+    })
+}
+
+/// [`COPIED_COLUMNS`], qualified with `alias` unless empty (`INSERT` and `SELECT` side alike)
+pub fn copied_columns(alias: &str) -> String {
+    column_list(COPIED_COLUMNS, alias, |_| None)
+}
+
+fn column_list(columns: &[&str], alias: &str, suffix: impl Fn(&str) -> Option<&'static str>) -> String {
+    let prefix = if alias.is_empty() { String::new() } else { format!("{}.", alias) };
+    columns
+        .iter()
+        .map(|column| format!("{}{}{}", prefix, column, suffix(column).unwrap_or_default()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Columns of `query_as::<_, Device>` / `UserDevice`
+pub const DEVICE_COLUMNS: &[&str] = &[
+    "tenant_id",
+    "user_id",
+    "fcm_token",
+    "device_type",
+    "locale",
+    "app_version",
+    "os_version",
+    "quiet_hours_start",
+    "quiet_hours_end",
+    "quiet_hours_timezone",
+    "enabled_types",
+    "push_environment",
+    "capabilities",
+    "last_seen_at",
+    "created_at",
+    "region",
+];
+
+/// Tables of the `activity` schema checked, with the columns this binary needs
+const EXPECTED: &[(&str, &[&str])] = &[("notifications", NOTIFICATION_COLUMNS), ("user_devices", DEVICE_COLUMNS)];
+
+/// Most edits between a missing column and a live one to call it a likely rename (besides
+/// one name containing the other, e.g. `topic` -> `topic_name`)
+const RENAME_DISTANCE: usize = 3;
+
+/// Compares the live `activity` schema with the columns this binary reads
+pub struct SchemaQueries;
+
+/// Result of a schema check (`GET /admin/schema-check`)
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaReport {
+    /// No expected column is missing
+    pub ok: bool,
+    pub tables: Vec<TableDrift>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableDrift {
+    pub table: String,
+    /// false = the table itself is missing (or not visible to the service's role)
+    pub exists: bool,
+    /// Expected columns the live table doesn't have
+    pub missing: Vec<String>,
+    /// Live columns unknown to this binary that look like a missing one, renamed
+    pub renamed: Vec<PossibleRename>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PossibleRename {
+    pub expected: String,
+    pub found: String,
+}
+
+impl SchemaReport {
+    /// Log every drifted table (one error line each)
+    pub fn log(&self) {
+        for drift in self.tables.iter().filter(|t| !t.exists || !t.missing.is_empty()) {
+            if !drift.exists {
+                error!(table = %format!("activity.{}", drift.table), "✗ Schema drift: table missing");
+                continue;
+            }
+            let renamed: Vec<String> = drift.renamed.iter().map(|r| format!("{} -> {}", r.expected, r.found)).collect();
+            error!(
+                table = %format!("activity.{}", drift.table),
+                missing = %drift.missing.join(", "),
+                renamed = %renamed.join(", "),
+                "✗ Schema drift: columns missing (apply the migrations, or queries will fail to decode)"
+            );
+        }
+    }
+
+    /// Expected columns missing across all tables (a missing table counts all of its columns)
+    pub fn missing_columns(&self) -> usize {
+        self.tables.iter().map(|t| t.missing.len()).sum()
+    }
+}
+
+impl SchemaQueries {
+    /// Introspect `information_schema.columns` and report what's missing
+    #[instrument(skip(pool))]
+    pub async fn check(pool: &PgPool) -> Result<SchemaReport, sqlx::Error> {
+        let tables: Vec<&str> = EXPECTED.iter().map(|(table, _)| *table).collect();
+        let live: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT table_name::text, column_name::text
+            FROM information_schema.columns
+            WHERE table_schema = 'activity' AND table_name::text = ANY($1)
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(&tables)
+        .fetch_all(pool)
+        .await?;
+
+        let mut columns: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for (table, column) in &live {
+            columns.entry(table.as_str()).or_default().insert(column.as_str());
+        }
+        let tables: Vec<TableDrift> = EXPECTED
+            .iter()
+            .map(|(table, expected)| drift(table, expected, columns.get(table)))
+            .collect();
+        let report = SchemaReport { ok: tables.iter().all(|t| t.missing.is_empty()), tables };
+
+        metrics::gauge!("notifications_schema_missing_columns").set(report.missing_columns() as f64);
+        Ok(report)
+    }
+}
+
+fn drift(table: &str, expected: &[&str], live: Option<&HashSet<&str>>) -> TableDrift {
+    let Some(live) = live else {
+        return TableDrift {
+            table: table.to_string(),
+            exists: false,
+            missing: expected.iter().map(|c| c.to_string()).collect(),
+            renamed: Vec::new(),
+        };
+    };
+    let missing: Vec<String> = expected.iter().filter(|c| !live.contains(*c)).map(|c| c.to_string()).collect();
+    // Postgres keeps no rename history: the closest unknown column is the best guess
+    let mut unknown: Vec<&str> = live.iter().copied().filter(|c| !expected.contains(c)).collect();
+    unknown.sort_unstable();
+    let renamed = missing
+        .iter()
+        .filter_map(|column| {
+            unknown
+                .iter()
+                .map(|candidate| (edit_distance(column, candidate), *candidate))
+                .filter(|(distance, candidate)| {
+                    *distance <= RENAME_DISTANCE || candidate.contains(column.as_str()) || column.contains(candidate)
+                })
+                .min()
+                .map(|(_, found)| PossibleRename { expected: column.clone(), found: found.to_string() })
+        })
+        .collect();
+    TableDrift { table: table.to_string(), exists: true, missing, renamed }
+}
+
+/// Levenshtein distance between two column names
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
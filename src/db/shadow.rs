@@ -0,0 +1,294 @@
+use super::schema::notification_select;
+use crate::models::Notification;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::{debug, instrument};
+use uuid::Uuid;
+
+/// Shadow queue ledger (`activity.shadow_ledger`, see `crate::shadow`)
+pub struct ShadowQueries;
+
+impl ShadowQueries {
+    /// Keep the ledger trigger on (it stops writing 5 minutes after the last heartbeat)
+    #[instrument(skip(pool))]
+    pub async fn heartbeat(pool: &PgPool, backend: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE activity.shadow_queue_state SET backend = $1, heartbeat_at = now()")
+            .persistent(super::prepared_statements())
+            .bind(backend)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    }
+
+    /// Claim ledger rows not yet published to the shadow backend, with their notification
+    ///
+    /// Leased for `lease_secs` like the receipt outbox, so replicas don't publish the same
+    /// row twice. Rows whose notification is gone (retention) or that were already compared
+    /// are left alone; the comparer reports them as unpublished.
+    #[instrument(skip(pool))]
+    pub async fn claim_unpublished(pool: &PgPool, limit: i64, lease_secs: i64) -> Result<Vec<Notification>, sqlx::Error> {
+        sqlx::query_as::<_, Notification>(&format!(
+            r#"
+            WITH claimed AS (
+                UPDATE activity.shadow_ledger
+                SET publish_attempts = publish_attempts + 1,
+                    next_attempt_at = now() + make_interval(secs => $2)
+                WHERE notification_id IN (
+                    SELECT notification_id
+                    FROM activity.shadow_ledger
+                    WHERE published_at IS NULL
+                      AND compared_at IS NULL
+                      AND next_attempt_at <= now()
+                    ORDER BY next_attempt_at
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING notification_id
+            )
+            SELECT {}
+            FROM claimed
+            JOIN activity.notifications n ON n.id = claimed.notification_id
+            ORDER BY n.created_at
+            "#,
+            notification_select("n")
+        ))
+        .persistent(super::prepared_statements())
+        .bind(limit)
+        .bind(lease_secs as f64)
+        .fetch_all(pool)
+        .await
+        .map(|mut rows| {
+            rows.iter_mut().for_each(Notification::inflate_payload);
+            rows
+        })
+    }
+
+    /// The copy is on the shadow backend; `pg_hash` is the Postgres side of the comparison
+    #[instrument(skip(pool, pg_hash))]
+    pub async fn mark_published(pool: &PgPool, notification_id: Uuid, pg_hash: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE activity.shadow_ledger SET published_at = now(), pg_hash = $2, last_error = NULL
+             WHERE notification_id = $1",
+        )
+        .persistent(super::prepared_statements())
+        .bind(notification_id)
+        .bind(pg_hash)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    /// Record a failed publish; the lease from the claim delays the next attempt
+    #[instrument(skip(pool, error_message))]
+    pub async fn mark_publish_failed(pool: &PgPool, notification_id: Uuid, error_message: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE activity.shadow_ledger SET last_error = $2 WHERE notification_id = $1")
+            .persistent(super::prepared_statements())
+            .bind(notification_id)
+            .bind(error_message)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    }
+
+    /// A copy came back from the shadow backend
+    ///
+    /// Copies of notifications the ledger doesn't know get a row of their own (they end up
+    /// as `unexpected`). Returns the copy count so far and, for the first copy, the lag
+    /// since the notification was ingested.
+    #[instrument(skip(pool, shadow_hash))]
+    pub async fn record_copy(
+        pool: &PgPool,
+        notification_id: Uuid,
+        tenant_id: &str,
+        shadow_hash: &str,
+    ) -> Result<ShadowCopy, sqlx::Error> {
+        sqlx::query_as::<_, ShadowCopy>(
+            r#"
+            INSERT INTO activity.shadow_ledger (notification_id, tenant_id, shadow_seen_at, shadow_copies, shadow_hash)
+            VALUES ($1, $2, now(), 1, $3)
+            ON CONFLICT (notification_id) DO UPDATE
+            SET shadow_copies = shadow_ledger.shadow_copies + 1,
+                shadow_seen_at = COALESCE(shadow_ledger.shadow_seen_at, now()),
+                shadow_hash = COALESCE(shadow_ledger.shadow_hash, EXCLUDED.shadow_hash)
+            RETURNING shadow_copies AS copies,
+                      EXTRACT(EPOCH FROM (now() - ingested_at))::float8 AS lag_secs
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(notification_id)
+        .bind(tenant_id)
+        .bind(shadow_hash)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Compare rows ingested more than `grace_secs` ago; returns the count per outcome
+    ///
+    /// Outcome (`none` = both sides agree), first match wins: `unexpected` (only the shadow
+    /// backend has it), `unpublished` (dual write never succeeded), `missing` (published but
+    /// never read back), `duplicate` (read back more than once), `content` (read back
+    /// different from what Postgres had).
+    #[instrument(skip(pool))]
+    pub async fn compare(pool: &PgPool, grace_secs: i64, limit: i64) -> Result<Vec<(String, i64)>, sqlx::Error> {
+        let result = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            WITH due AS (
+                SELECT notification_id
+                FROM activity.shadow_ledger
+                WHERE compared_at IS NULL
+                  AND ingested_at <= now() - make_interval(secs => $1)
+                ORDER BY ingested_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            ),
+            compared AS (
+                UPDATE activity.shadow_ledger l
+                SET compared_at = now(),
+                    divergence = CASE
+                        WHEN l.published_at IS NULL AND l.shadow_copies > 0 THEN 'unexpected'
+                        WHEN l.published_at IS NULL THEN 'unpublished'
+                        WHEN l.shadow_copies = 0 THEN 'missing'
+                        WHEN l.shadow_copies > 1 THEN 'duplicate'
+                        WHEN l.shadow_hash IS DISTINCT FROM l.pg_hash THEN 'content'
+                    END
+                FROM due
+                WHERE l.notification_id = due.notification_id
+                RETURNING l.divergence
+            )
+            SELECT COALESCE(divergence, 'none'), count(*)
+            FROM compared
+            GROUP BY 1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(grace_secs as f64)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+        debug!(outcomes = ?result, "DB shadow compare: completed");
+        Ok(result)
+    }
+
+    /// Drop compared rows older than `keep_days`
+    #[instrument(skip(pool))]
+    pub async fn purge(pool: &PgPool, keep_days: i32) -> Result<u64, sqlx::Error> {
+        sqlx::query("DELETE FROM activity.shadow_ledger WHERE compared_at < now() - make_interval(days => $1)")
+            .persistent(super::prepared_statements())
+            .bind(keep_days)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected())
+    }
+
+    /// Backend and heartbeat of the instance running shadow mode
+    #[instrument(skip(pool))]
+    pub async fn state(pool: &PgPool) -> Result<ShadowState, sqlx::Error> {
+        sqlx::query_as::<_, ShadowState>(
+            r#"
+            SELECT backend,
+                   heartbeat_at,
+                   COALESCE(heartbeat_at > now() - interval '5 minutes', false) AS active
+            FROM activity.shadow_queue_state
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Ledger totals over rows ingested since `since`
+    #[instrument(skip(pool))]
+    pub async fn totals(pool: &PgPool, since: DateTime<Utc>) -> Result<ShadowTotals, sqlx::Error> {
+        sqlx::query_as::<_, ShadowTotals>(
+            r#"
+            SELECT
+                count(*) AS ingested,
+                count(*) FILTER (WHERE compared_at IS NULL) AS pending,
+                count(*) FILTER (WHERE compared_at IS NOT NULL AND divergence IS NULL) AS matched,
+                count(*) FILTER (WHERE divergence = 'unexpected') AS unexpected,
+                count(*) FILTER (WHERE divergence = 'unpublished') AS unpublished,
+                count(*) FILTER (WHERE divergence = 'missing') AS missing,
+                count(*) FILTER (WHERE divergence = 'duplicate') AS duplicate,
+                count(*) FILTER (WHERE divergence = 'content') AS content,
+                (percentile_cont(0.95) WITHIN GROUP (
+                    ORDER BY EXTRACT(EPOCH FROM (shadow_seen_at - ingested_at))::float8
+                ) FILTER (WHERE shadow_seen_at IS NOT NULL))::float8 AS lag_p95_secs
+            FROM activity.shadow_ledger
+            WHERE ingested_at >= $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(since)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Most recent divergent rows since `since`
+    #[instrument(skip(pool))]
+    pub async fn recent_divergences(
+        pool: &PgPool,
+        since: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<ShadowDivergence>, sqlx::Error> {
+        sqlx::query_as::<_, ShadowDivergence>(
+            r#"
+            SELECT notification_id, tenant_id, divergence, ingested_at, published_at, publish_attempts,
+                   last_error, shadow_seen_at, shadow_copies
+            FROM activity.shadow_ledger
+            WHERE ingested_at >= $1
+              AND divergence IS NOT NULL
+            ORDER BY ingested_at DESC
+            LIMIT $2
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(since)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ShadowCopy {
+    /// Copies read back so far, including this one
+    pub copies: i32,
+    pub lag_secs: f64,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ShadowState {
+    pub backend: Option<String>,
+    pub heartbeat_at: Option<DateTime<Utc>>,
+    /// Heartbeat within 5 minutes: new notifications are being ledgered
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ShadowTotals {
+    pub ingested: i64,
+    /// Within SHADOW_GRACE_SECS, not compared yet
+    pub pending: i64,
+    pub matched: i64,
+    pub unexpected: i64,
+    pub unpublished: i64,
+    pub missing: i64,
+    pub duplicate: i64,
+    pub content: i64,
+    /// Ingest to first shadow read
+    pub lag_p95_secs: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ShadowDivergence {
+    pub notification_id: Uuid,
+    pub tenant_id: String,
+    pub divergence: String,
+    pub ingested_at: DateTime<Utc>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub publish_attempts: i32,
+    pub last_error: Option<String>,
+    pub shadow_seen_at: Option<DateTime<Utc>>,
+    pub shadow_copies: i32,
+}
@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, instrument};
+use uuid::Uuid;
+
+pub struct SloQueries;
+
+impl SloQueries {
+    /// Per type: notifications finished since `since`, how many were delivered and the p95 latency
+    ///
+    /// Finished = processed and not suppressed; without a delivered (or simulated) attempt
+    /// it gave up. Latency runs from deliver_at (when the row became due) to the first
+    /// delivered attempt. Broadcasts and topic sources have no per-user attempts and are left out.
+    #[instrument(skip(pool))]
+    pub async fn per_type(pool: &PgPool, since: DateTime<Utc>) -> Result<Vec<TypeSlo>, sqlx::Error> {
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, TypeSlo>(
+            r#"
+            WITH finished AS (
+                SELECT n.notification_type::text AS notification_type,
+                       n.deliver_at,
+                       (SELECT MIN(a.attempted_at)
+                        FROM activity.notification_attempts a
+                        WHERE a.notification_id = n.id AND a.outcome IN ('delivered', 'simulated')) AS delivered_at
+                FROM activity.notifications n
+                WHERE n.is_processed AND n.suppressed_at IS NULL
+                  AND n.updated_at >= $1
+                  AND n.user_id <> '00000000-0000-0000-0000-000000000000'
+            )
+            SELECT notification_type,
+                   COUNT(delivered_at) AS delivered,
+                   COUNT(*) - COUNT(delivered_at) AS failed,
+                   percentile_cont(0.95) WITHIN GROUP (
+                       ORDER BY GREATEST(EXTRACT(EPOCH FROM delivered_at - deliver_at), 0)::float8
+                   ) AS p95_latency_secs
+            FROM finished
+            GROUP BY notification_type
+            ORDER BY notification_type
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(since)
+        .fetch_all(pool)
+        .await;
+
+        match &result {
+            Ok(rows) => debug!(
+                types = rows.len(),
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB slo_per_type: completed"
+            ),
+            Err(e) => error!(error = %e, "DB slo_per_type: query failed"),
+        }
+        result
+    }
+
+    /// Open an incident for a type's breach - None when one is already open (another replica won)
+    #[instrument(skip(pool))]
+    pub async fn open_incident(
+        pool: &PgPool,
+        notification_type: &str,
+        breach: &str,
+        observed: f64,
+        threshold: f64,
+        notification_id: Uuid,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO activity.slo_incidents (notification_type, breach, observed, threshold, notification_id)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (notification_type, breach) WHERE resolved_at IS NULL DO NOTHING
+            RETURNING id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(notification_type)
+        .bind(breach)
+        .bind(observed)
+        .bind(threshold)
+        .bind(notification_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Drop an incident whose alert could not be created, so the next run opens it again
+    pub async fn discard_incident(pool: &PgPool, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM activity.slo_incidents WHERE id = $1")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    }
+
+    /// Resolve the open incident of a type's breach - returns it when this call resolved it
+    #[instrument(skip(pool))]
+    pub async fn resolve_incident(
+        pool: &PgPool,
+        notification_type: &str,
+        breach: &str,
+    ) -> Result<Option<SloIncident>, sqlx::Error> {
+        sqlx::query_as::<_, SloIncident>(
+            r#"
+            UPDATE activity.slo_incidents
+            SET resolved_at = NOW()
+            WHERE notification_type = $1 AND breach = $2 AND resolved_at IS NULL
+            RETURNING id, notification_type, breach, observed, threshold, notification_id, opened_at, resolved_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(notification_type)
+        .bind(breach)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Incidents not resolved yet, oldest first
+    pub async fn open_incidents(pool: &PgPool) -> Result<Vec<SloIncident>, sqlx::Error> {
+        sqlx::query_as::<_, SloIncident>(
+            r#"
+            SELECT id, notification_type, breach, observed, threshold, notification_id, opened_at, resolved_at
+            FROM activity.slo_incidents
+            WHERE resolved_at IS NULL
+            ORDER BY opened_at, id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_all(pool)
+        .await
+    }
+}
+
+/// A breached SLO (`activity.slo_incidents`), open until the type is healthy again
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SloIncident {
+    pub id: i64,
+    pub notification_type: String,
+    /// success_rate | p95_latency
+    pub breach: String,
+    /// Success rate (0-1) or p95 latency (seconds) when it opened
+    pub observed: f64,
+    pub threshold: f64,
+    /// The alert sent to the operators
+    pub notification_id: Uuid,
+    pub opened_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Delivery outcome of one notification type over the SLO window
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TypeSlo {
+    pub notification_type: String,
+    pub delivered: i64,
+    /// Gave up (MAX_RETRIES or a permanent error)
+    pub failed: i64,
+    /// Null when nothing was delivered
+    pub p95_latency_secs: Option<f64>,
+}
+
+impl TypeSlo {
+    /// delivered / (delivered + failed)
+    pub fn success_rate(&self) -> f64 {
+        let finished = self.delivered + self.failed;
+        if finished == 0 {
+            return 1.0;
+        }
+        self.delivered as f64 / finished as f64
+    }
+}
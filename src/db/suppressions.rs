@@ -0,0 +1,169 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+/// `created_by` of entries the worker added itself
+pub const AUTOMATIC_CREATOR: &str = "system:unregistered";
+
+pub struct SuppressionQueries;
+
+impl SuppressionQueries {
+    /// Suppression entries, optionally for one tenant (global entries included) and/or kind
+    #[instrument(skip(pool))]
+    pub async fn list(
+        pool: &PgPool,
+        tenant_id: Option<&str>,
+        kind: Option<&str>,
+    ) -> Result<Vec<Suppression>, sqlx::Error> {
+        sqlx::query_as::<_, Suppression>(
+            r#"
+            SELECT id, tenant_id, kind, value, reason, note, created_by, created_at, expires_at
+            FROM activity.suppressions
+            WHERE ($1::TEXT IS NULL OR tenant_id = $1 OR tenant_id IS NULL)
+              AND ($2::TEXT IS NULL OR kind = $2)
+            ORDER BY created_at DESC
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(kind)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Add an entry, or update reason/note/expiry of the existing one for the same endpoint
+    #[instrument(skip(pool, suppression), fields(kind = %suppression.kind))]
+    pub async fn create(
+        pool: &PgPool,
+        suppression: &NewSuppression,
+        created_by: &str,
+    ) -> Result<Suppression, sqlx::Error> {
+        sqlx::query_as::<_, Suppression>(
+            r#"
+            INSERT INTO activity.suppressions (tenant_id, kind, value, reason, note, created_by, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (kind, value, COALESCE(tenant_id, '')) DO UPDATE
+            SET reason = EXCLUDED.reason,
+                note = EXCLUDED.note,
+                created_by = EXCLUDED.created_by,
+                created_at = NOW(),
+                expires_at = EXCLUDED.expires_at
+            RETURNING id, tenant_id, kind, value, reason, note, created_by, created_at, expires_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(&suppression.tenant_id)
+        .bind(&suppression.kind)
+        .bind(&suppression.value)
+        .bind(&suppression.reason)
+        .bind(&suppression.note)
+        .bind(created_by)
+        .bind(suppression.expires_at)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Remove an entry - returns false if it didn't exist
+    #[instrument(skip(pool))]
+    pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query("DELETE FROM activity.suppressions WHERE id = $1")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+
+    /// Whether an unexpired entry (the tenant's own or a global one) covers `value`
+    #[instrument(skip(executor, value))]
+    pub async fn is_suppressed<'e, E>(executor: E, tenant_id: &str, kind: &str, value: &str) -> Result<bool, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM activity.suppressions
+                WHERE kind = $2 AND value = $3
+                  AND (tenant_id = $1 OR tenant_id IS NULL)
+                  AND (expires_at IS NULL OR expires_at > NOW())
+            )
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(kind)
+        .bind(value)
+        .fetch_one(executor)
+        .await
+    }
+
+    /// Count an UNREGISTERED error for a token; suppress it globally at `threshold` errors
+    ///
+    /// Returns true when this error added the entry. A manual entry for the token is kept.
+    #[instrument(skip(pool, fcm_token))]
+    pub async fn record_unregistered(pool: &PgPool, fcm_token: &str, threshold: i32) -> Result<bool, sqlx::Error> {
+        let failures = sqlx::query_scalar::<_, i32>(
+            r#"
+            INSERT INTO activity.unregistered_tokens (fcm_token)
+            VALUES ($1)
+            ON CONFLICT (fcm_token) DO UPDATE
+            SET failures = activity.unregistered_tokens.failures + 1,
+                last_failed_at = NOW()
+            RETURNING failures
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(fcm_token)
+        .fetch_one(pool)
+        .await?;
+
+        if failures < threshold {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO activity.suppressions (tenant_id, kind, value, reason, note, created_by)
+            VALUES (NULL, 'token', $1, 'unregistered', $2, $3)
+            ON CONFLICT (kind, value, COALESCE(tenant_id, '')) DO NOTHING
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(fcm_token)
+        .bind(format!("{} UNREGISTERED errors from FCM", failures))
+        .bind(AUTOMATIC_CREATOR)
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected() > 0)
+    }
+}
+
+/// Entry as submitted through the API
+#[derive(Debug, Clone)]
+pub struct NewSuppression {
+    pub tenant_id: Option<String>,
+    pub kind: String,
+    pub value: String,
+    pub reason: String,
+    pub note: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Suppression {
+    pub id: Uuid,
+    /// None = global
+    pub tenant_id: Option<String>,
+    /// user / token / email
+    pub kind: String,
+    pub value: String,
+    pub reason: String,
+    pub note: Option<String>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    /// None = permanent
+    pub expires_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,213 @@
+use super::compression;
+use crate::models::SignedAttachment;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+pub struct SyncQueries;
+
+impl SyncQueries {
+    /// Inbox changes for a user (and the tenant's broadcasts) after a cursor, oldest first
+    ///
+    /// `after_id` and `after` (timestamp cursor) both apply; pass 0 / None to ignore one.
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn changes(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        after_id: i64,
+        after: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<NotificationChange>, sqlx::Error> {
+        trace!("DB sync_changes: user {} after {} / {:?}", user_id, after_id, after);
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, NotificationChange>(
+            r#"
+            SELECT id, notification_id, change
+            FROM activity.notification_changes
+            WHERE tenant_id = $1
+              AND (user_id = $2 OR user_id = '00000000-0000-0000-0000-000000000000')
+              AND id > $3
+              AND ($4::timestamptz IS NULL OR changed_at > $4)
+            ORDER BY id
+            LIMIT $5
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(after_id)
+        .bind(after)
+        .bind(limit)
+        .fetch_all(pool)
+        .await;
+
+        match &result {
+            Ok(changes) => debug!(
+                user_id = %user_id,
+                count = changes.len(),
+                duration_ms = start.elapsed().as_millis() as u64,
+                "DB sync_changes: completed"
+            ),
+            Err(e) => error!(user_id = %user_id, error = %e, "DB sync_changes: query failed"),
+        }
+        result
+    }
+
+    /// Newest cursor (0 for an empty log)
+    pub async fn latest_cursor(pool: &PgPool) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>("SELECT COALESCE(MAX(id), 0) FROM activity.notification_changes")
+            .persistent(super::prepared_statements())
+            .fetch_one(pool)
+            .await
+    }
+
+    /// Current state of the given notifications, as returned to clients
+    pub async fn fetch(pool: &PgPool, ids: &[Uuid]) -> Result<Vec<SyncedNotification>, sqlx::Error> {
+        sqlx::query_as::<_, SyncedNotification>(
+            r#"
+            SELECT id, notification_type::text AS notification_type, actor_user_id, target_type, target_id,
+                   title, message, payload, payload_zstd, deep_link, priority, group_key, actions,
+                   attachments AS attachment_keys, created_at, read_at
+            FROM activity.notifications
+            WHERE id = ANY($1)
+            ORDER BY created_at, id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(ids)
+        .fetch_all(pool)
+        .await
+        .map(|mut rows| {
+            for row in &mut rows {
+                compression::inflate(row.id, &mut row.payload, &mut row.payload_zstd);
+            }
+            rows
+        })
+    }
+
+    /// Mark a user's own notifications read - returns the ids that changed
+    ///
+    /// Broadcast rows are shared by all users and can't be marked read.
+    #[instrument(skip(pool, ids), fields(user_id = %user_id, count = ids.len()))]
+    pub async fn mark_read(pool: &PgPool, tenant_id: &str, user_id: Uuid, ids: &[Uuid]) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE activity.notifications
+            SET read_at = now()
+            WHERE id = ANY($1) AND tenant_id = $2 AND user_id = $3 AND read_at IS NULL
+            RETURNING id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(ids)
+        .bind(tenant_id)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Unread notifications in the user's inbox (processed and not suppressed), for badges
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn unread_count(pool: &PgPool, tenant_id: &str, user_id: Uuid) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*)
+            FROM activity.notifications
+            WHERE tenant_id = $1 AND user_id = $2
+              AND is_processed AND suppressed_at IS NULL AND read_at IS NULL
+              AND notification_type::text <> 'otp'
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// The user's newest inbox rows (processed, not suppressed), headers only
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn latest_headers(
+        pool: &PgPool,
+        tenant_id: &str,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<InboxHeader>, sqlx::Error> {
+        sqlx::query_as::<_, InboxHeader>(
+            r#"
+            SELECT id, notification_type::text AS notification_type, title, priority, deep_link, group_key,
+                   created_at, read_at
+            FROM activity.notifications
+            WHERE tenant_id = $1 AND user_id = $2
+              AND is_processed AND suppressed_at IS NULL
+              AND notification_type::text <> 'otp'
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+/// One row of `activity.notification_changes`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct NotificationChange {
+    pub id: i64,
+    pub notification_id: Uuid,
+    /// created | updated | read | deleted
+    pub change: String,
+}
+
+/// Inbox entry as returned by the sync endpoint (field names as in the Bus payload)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SyncedNotification {
+    pub id: Uuid,
+    pub notification_type: String,
+    pub actor_user_id: Option<Uuid>,
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub title: String,
+    pub message: Option<String>,
+    pub payload: Option<serde_json::Value>,
+    /// `payload` as stored when compressed, moved into it after the fetch
+    #[serde(skip)]
+    pub payload_zstd: Option<Vec<u8>>,
+    pub deep_link: Option<String>,
+    pub priority: Option<String>,
+    pub group_key: Option<String>,
+    /// Inline actions (left out when there are none)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actions: Option<serde_json::Value>,
+    /// The stored attachment keys, never returned as such
+    #[serde(skip)]
+    pub attachment_keys: Option<serde_json::Value>,
+    /// `attachment_keys` signed for this response (left out when there are none)
+    #[sqlx(skip)]
+    #[serde(rename = "attachments", skip_serializing_if = "Vec::is_empty")]
+    pub media: Vec<SignedAttachment>,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+/// Inbox entry without its body, for the `inbox_snapshot` frame
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct InboxHeader {
+    pub id: Uuid,
+    pub notification_type: String,
+    pub title: String,
+    pub priority: Option<String>,
+    pub deep_link: Option<String>,
+    pub group_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,298 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::time::Instant;
+use tracing::{debug, error, info, instrument, trace};
+
+/// Channel value matching every delivery channel
+pub const ANY_CHANNEL: &str = "any";
+
+pub struct TemplateQueries;
+
+impl TemplateQueries {
+    /// Find the best template for (key, locale, channel)
+    ///
+    /// Preference: exact locale over `fallback_locale`, exact channel over `any`.
+    #[instrument(skip(pool), fields(template_key = %template_key, locale = %locale, channel = %channel))]
+    pub async fn find(
+        pool: &PgPool,
+        template_key: &str,
+        locale: &str,
+        fallback_locale: &str,
+        channel: &str,
+    ) -> Result<Option<NotificationTemplate>, sqlx::Error> {
+        trace!(
+            "DB find_template: key='{}' locale='{}' channel='{}'",
+            template_key, locale, channel
+        );
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, NotificationTemplate>(
+            r#"
+            SELECT template_key, locale, channel, version, title_tpl, body_tpl, updated_at
+            FROM activity.notification_templates
+            WHERE template_key = $1
+              AND locale IN ($2, $3)
+              AND channel IN ($4, $5)
+            ORDER BY (locale = $2) DESC, (channel = $4) DESC
+            LIMIT 1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(template_key)
+        .bind(locale)
+        .bind(fallback_locale)
+        .bind(channel)
+        .bind(ANY_CHANNEL)
+        .fetch_optional(pool)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok(template) => {
+                debug!(
+                    template_key = %template_key,
+                    found = template.is_some(),
+                    matched_locale = ?template.as_ref().map(|t| t.locale.as_str()),
+                    matched_channel = ?template.as_ref().map(|t| t.channel.as_str()),
+                    duration_ms = duration.as_millis() as u64,
+                    "DB find_template: completed"
+                );
+            }
+            Err(e) => {
+                error!(
+                    template_key = %template_key,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB find_template: query failed"
+                );
+            }
+        }
+
+        result
+    }
+
+    /// List templates, optionally for a single key
+    #[instrument(skip(pool))]
+    pub async fn list(
+        pool: &PgPool,
+        template_key: Option<&str>,
+    ) -> Result<Vec<NotificationTemplate>, sqlx::Error> {
+        trace!("DB list_templates: key={:?}", template_key);
+
+        sqlx::query_as::<_, NotificationTemplate>(
+            r#"
+            SELECT template_key, locale, channel, version, title_tpl, body_tpl, updated_at
+            FROM activity.notification_templates
+            WHERE $1::text IS NULL OR template_key = $1
+            ORDER BY template_key, locale, channel
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(template_key)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Save a template variant as a new version and make it active
+    #[instrument(skip(pool, title_tpl, body_tpl))]
+    pub async fn upsert(
+        pool: &PgPool,
+        template_key: &str,
+        locale: &str,
+        channel: &str,
+        title_tpl: &str,
+        body_tpl: &str,
+    ) -> Result<NotificationTemplate, sqlx::Error> {
+        trace!(
+            "DB upsert_template: key='{}' locale='{}' channel='{}'",
+            template_key, locale, channel
+        );
+
+        let mut tx = pool.begin().await?;
+
+        // Serialize concurrent saves of the same variant so versions stay gapless
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1 || '/' || $2 || '/' || $3))")
+            .persistent(super::prepared_statements())
+            .bind(template_key)
+            .bind(locale)
+            .bind(channel)
+            .execute(&mut *tx)
+            .await?;
+
+        let (version,) = sqlx::query_as::<_, (i32,)>(
+            r#"
+            INSERT INTO activity.notification_template_versions
+                (template_key, locale, channel, version, title_tpl, body_tpl)
+            SELECT $1, $2, $3, COALESCE(MAX(version), 0) + 1, $4, $5
+            FROM activity.notification_template_versions
+            WHERE template_key = $1 AND locale = $2 AND channel = $3
+            RETURNING version
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(template_key)
+        .bind(locale)
+        .bind(channel)
+        .bind(title_tpl)
+        .bind(body_tpl)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let template = Self::activate_in_tx(&mut tx, template_key, locale, channel, version)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+
+        tx.commit().await?;
+
+        debug!(
+            template_key = %template_key,
+            locale = %locale,
+            channel = %channel,
+            version = version,
+            "DB upsert_template: new version active"
+        );
+
+        Ok(template)
+    }
+
+    /// List all versions of a template variant (newest first)
+    #[instrument(skip(pool))]
+    pub async fn list_versions(
+        pool: &PgPool,
+        template_key: &str,
+        locale: &str,
+        channel: &str,
+    ) -> Result<Vec<TemplateVersion>, sqlx::Error> {
+        trace!(
+            "DB list_template_versions: key='{}' locale='{}' channel='{}'",
+            template_key, locale, channel
+        );
+
+        sqlx::query_as::<_, TemplateVersion>(
+            r#"
+            SELECT v.version, v.title_tpl, v.body_tpl, v.created_at,
+                   (t.version IS NOT DISTINCT FROM v.version) AS active
+            FROM activity.notification_template_versions v
+            LEFT JOIN activity.notification_templates t
+              ON t.template_key = v.template_key AND t.locale = v.locale AND t.channel = v.channel
+            WHERE v.template_key = $1 AND v.locale = $2 AND v.channel = $3
+            ORDER BY v.version DESC
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(template_key)
+        .bind(locale)
+        .bind(channel)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Make an existing version active (rollback / roll forward)
+    ///
+    /// Returns None if the version doesn't exist.
+    #[instrument(skip(pool))]
+    pub async fn activate(
+        pool: &PgPool,
+        template_key: &str,
+        locale: &str,
+        channel: &str,
+        version: i32,
+    ) -> Result<Option<NotificationTemplate>, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let template = Self::activate_in_tx(&mut tx, template_key, locale, channel, version).await?;
+        tx.commit().await?;
+
+        if template.is_some() {
+            info!(
+                template_key = %template_key,
+                locale = %locale,
+                channel = %channel,
+                version = version,
+                "DB activate_template: version activated"
+            );
+        }
+
+        Ok(template)
+    }
+
+    /// Copy a stored version into notification_templates
+    async fn activate_in_tx(
+        tx: &mut Transaction<'_, Postgres>,
+        template_key: &str,
+        locale: &str,
+        channel: &str,
+        version: i32,
+    ) -> Result<Option<NotificationTemplate>, sqlx::Error> {
+        sqlx::query_as::<_, NotificationTemplate>(
+            r#"
+            INSERT INTO activity.notification_templates
+                (template_key, locale, channel, version, title_tpl, body_tpl)
+            SELECT template_key, locale, channel, version, title_tpl, body_tpl
+            FROM activity.notification_template_versions
+            WHERE template_key = $1 AND locale = $2 AND channel = $3 AND version = $4
+            ON CONFLICT (template_key, locale, channel)
+            DO UPDATE SET
+                version = EXCLUDED.version,
+                title_tpl = EXCLUDED.title_tpl,
+                body_tpl = EXCLUDED.body_tpl,
+                updated_at = now()
+            RETURNING template_key, locale, channel, version, title_tpl, body_tpl, updated_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(template_key)
+        .bind(locale)
+        .bind(channel)
+        .bind(version)
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    /// Delete a template variant - returns false if it didn't exist
+    #[instrument(skip(pool))]
+    pub async fn delete(
+        pool: &PgPool,
+        template_key: &str,
+        locale: &str,
+        channel: &str,
+    ) -> Result<bool, sqlx::Error> {
+        trace!(
+            "DB delete_template: key='{}' locale='{}' channel='{}'",
+            template_key, locale, channel
+        );
+
+        sqlx::query(
+            "DELETE FROM activity.notification_templates WHERE template_key = $1 AND locale = $2 AND channel = $3"
+        )
+        .persistent(super::prepared_statements())
+        .bind(template_key)
+        .bind(locale)
+        .bind(channel)
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected() > 0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct NotificationTemplate {
+    pub template_key: String,
+    pub locale: String,
+    pub channel: String,
+    /// Active version (see notification_template_versions)
+    pub version: i32,
+    pub title_tpl: String,
+    pub body_tpl: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TemplateVersion {
+    pub version: i32,
+    pub title_tpl: String,
+    pub body_tpl: String,
+    pub created_at: DateTime<Utc>,
+    pub active: bool,
+}
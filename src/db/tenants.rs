@@ -0,0 +1,165 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+
+/// Tenant that owns rows without an explicit tenant_id
+pub const DEFAULT_TENANT: &str = "default";
+
+pub struct TenantQueries;
+
+impl TenantQueries {
+    /// Find a tenant by id
+    #[instrument(skip(pool))]
+    pub async fn find(pool: &PgPool, tenant_id: &str) -> Result<Option<Tenant>, sqlx::Error> {
+        trace!("DB find_tenant: looking up '{}'", tenant_id);
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, Tenant>(
+            r#"
+            SELECT tenant_id, name, fcm_project_id, fcm_credentials_path, fcm_sandbox_project_id,
+                   fcm_sandbox_credentials_path, bus_topic_prefix, rate_limit_per_minute, daily_budget, scheduling_weight, enabled, updated_at
+            FROM activity.tenants
+            WHERE tenant_id = $1
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .fetch_optional(pool)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok(found) => {
+                debug!(
+                    tenant_id = %tenant_id,
+                    found = found.is_some(),
+                    duration_ms = duration.as_millis() as u64,
+                    "DB find_tenant: completed"
+                );
+            }
+            Err(e) => {
+                error!(
+                    tenant_id = %tenant_id,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB find_tenant: query failed"
+                );
+            }
+        }
+
+        result
+    }
+
+    /// List all tenants
+    #[instrument(skip(pool))]
+    pub async fn list(pool: &PgPool) -> Result<Vec<Tenant>, sqlx::Error> {
+        trace!("DB list_tenants");
+
+        sqlx::query_as::<_, Tenant>(
+            r#"
+            SELECT tenant_id, name, fcm_project_id, fcm_credentials_path, fcm_sandbox_project_id,
+                   fcm_sandbox_credentials_path, bus_topic_prefix, rate_limit_per_minute, daily_budget, scheduling_weight, enabled, updated_at
+            FROM activity.tenants
+            ORDER BY tenant_id
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Create or replace a tenant
+    #[instrument(skip(pool, tenant), fields(tenant_id = %tenant_id))]
+    pub async fn upsert(pool: &PgPool, tenant_id: &str, tenant: &TenantSettings) -> Result<Tenant, sqlx::Error> {
+        trace!("DB upsert_tenant: '{}'", tenant_id);
+
+        sqlx::query_as::<_, Tenant>(
+            r#"
+            INSERT INTO activity.tenants (
+                tenant_id, name, fcm_project_id, fcm_credentials_path, fcm_sandbox_project_id,
+                fcm_sandbox_credentials_path, bus_topic_prefix, rate_limit_per_minute, daily_budget, scheduling_weight, enabled
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (tenant_id)
+            DO UPDATE SET
+                name = EXCLUDED.name,
+                fcm_project_id = EXCLUDED.fcm_project_id,
+                fcm_credentials_path = EXCLUDED.fcm_credentials_path,
+                fcm_sandbox_project_id = EXCLUDED.fcm_sandbox_project_id,
+                fcm_sandbox_credentials_path = EXCLUDED.fcm_sandbox_credentials_path,
+                bus_topic_prefix = EXCLUDED.bus_topic_prefix,
+                rate_limit_per_minute = EXCLUDED.rate_limit_per_minute,
+                daily_budget = EXCLUDED.daily_budget,
+                scheduling_weight = EXCLUDED.scheduling_weight,
+                enabled = EXCLUDED.enabled,
+                updated_at = now()
+            RETURNING tenant_id, name, fcm_project_id, fcm_credentials_path, fcm_sandbox_project_id,
+                      fcm_sandbox_credentials_path, bus_topic_prefix, rate_limit_per_minute, daily_budget, scheduling_weight, enabled, updated_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(tenant_id)
+        .bind(&tenant.name)
+        .bind(&tenant.fcm_project_id)
+        .bind(&tenant.fcm_credentials_path)
+        .bind(&tenant.fcm_sandbox_project_id)
+        .bind(&tenant.fcm_sandbox_credentials_path)
+        .bind(&tenant.bus_topic_prefix)
+        .bind(tenant.rate_limit_per_minute)
+        .bind(tenant.daily_budget)
+        .bind(tenant.scheduling_weight)
+        .bind(tenant.enabled)
+        .fetch_one(pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Tenant {
+    pub tenant_id: String,
+    pub name: String,
+    pub fcm_project_id: Option<String>,
+    pub fcm_credentials_path: Option<String>,
+    /// Firebase project for sandbox devices (dev builds)
+    pub fcm_sandbox_project_id: Option<String>,
+    pub fcm_sandbox_credentials_path: Option<String>,
+    pub bus_topic_prefix: Option<String>,
+    pub rate_limit_per_minute: Option<i32>,
+    /// Maximum delivery cost per UTC day (None = unlimited)
+    pub daily_budget: Option<f64>,
+    /// Share of each worker batch under WORKER_FAIR_SCHEDULING
+    pub scheduling_weight: i32,
+    pub enabled: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Editable tenant fields (admin API)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantSettings {
+    pub name: String,
+    pub fcm_project_id: Option<String>,
+    pub fcm_credentials_path: Option<String>,
+    /// Firebase project for sandbox devices (dev builds)
+    pub fcm_sandbox_project_id: Option<String>,
+    pub fcm_sandbox_credentials_path: Option<String>,
+    pub bus_topic_prefix: Option<String>,
+    pub rate_limit_per_minute: Option<i32>,
+    /// Maximum delivery cost per UTC day (None = unlimited)
+    pub daily_budget: Option<f64>,
+    /// Share of each worker batch under WORKER_FAIR_SCHEDULING (rows per round)
+    #[serde(default = "default_scheduling_weight")]
+    pub scheduling_weight: i32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_scheduling_weight() -> i32 {
+    1
+}
+
+fn default_enabled() -> bool {
+    true
+}
@@ -0,0 +1,133 @@
+use super::schema::copied_columns;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Acquire, PgPool, Postgres};
+use std::time::Instant;
+use tracing::{debug, error, instrument, trace};
+use uuid::Uuid;
+
+pub struct TopicQueries;
+
+impl TopicQueries {
+    /// Topics the user follows (newest first)
+    #[instrument(skip(pool), fields(user_id = %user_id))]
+    pub async fn list(pool: &PgPool, tenant_id: &str, user_id: Uuid) -> Result<Vec<TopicSubscription>, sqlx::Error> {
+        trace!("DB list_topics: fetching for user {}", user_id);
+
+        sqlx::query_as::<_, TopicSubscription>(
+            r#"
+            SELECT topic, created_at
+            FROM activity.topic_subscriptions
+            WHERE tenant_id = $2 AND user_id = $1
+            ORDER BY created_at DESC
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(tenant_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Follow a topic (idempotent)
+    #[instrument(skip(pool), fields(user_id = %user_id, topic = %topic))]
+    pub async fn subscribe(pool: &PgPool, tenant_id: &str, user_id: Uuid, topic: &str) -> Result<(), sqlx::Error> {
+        trace!("DB subscribe_topic: {} for user {}", topic, user_id);
+
+        sqlx::query(
+            r#"
+            INSERT INTO activity.topic_subscriptions (tenant_id, topic, user_id)
+            VALUES ($3, $2, $1)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(user_id)
+        .bind(topic)
+        .bind(tenant_id)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+
+    /// Stop following a topic - returns false if the user didn't follow it
+    #[instrument(skip(pool), fields(user_id = %user_id, topic = %topic))]
+    pub async fn unsubscribe(pool: &PgPool, tenant_id: &str, user_id: Uuid, topic: &str) -> Result<bool, sqlx::Error> {
+        trace!("DB unsubscribe_topic: {} for user {}", topic, user_id);
+
+        sqlx::query("DELETE FROM activity.topic_subscriptions WHERE tenant_id = $3 AND topic = $2 AND user_id = $1")
+            .persistent(super::prepared_statements())
+            .bind(user_id)
+            .bind(topic)
+            .bind(tenant_id)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+
+    /// Fan a topic notification out to its subscribers and mark it processed - returns the copies
+    ///
+    /// One transaction (a savepoint inside a claim): a retry after a failure never creates a
+    /// second set of copies. The actor is skipped (nobody is notified about their own
+    /// action), and the producer's callback_url is not copied (one receipt per subscriber is
+    /// not what the producer asked for).
+    #[instrument(skip(conn), fields(id = %id))]
+    pub async fn expand(conn: impl Acquire<'_, Database = Postgres>, id: Uuid) -> Result<u64, sqlx::Error> {
+        trace!("DB expand_topic: fanning out {}", id);
+        let start = Instant::now();
+
+        let mut tx = conn.begin().await?;
+        // Another replica expanding the same row waits here, then finds it processed
+        sqlx::query("SELECT 1 FROM activity.notifications WHERE id = $1 FOR UPDATE")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let created = sqlx::query(&format!(
+            r#"
+            INSERT INTO activity.notifications (id, user_id, topic, region, {})
+            SELECT gen_random_uuid(), s.user_id, n.topic,
+                   COALESCE(activity.home_region(n.tenant_id, s.user_id), n.region), {}
+            FROM activity.notifications n
+            JOIN activity.topic_subscriptions s ON s.tenant_id = n.tenant_id AND s.topic = n.topic
+            WHERE n.id = $1 AND n.is_processed = false
+              AND s.user_id IS DISTINCT FROM n.actor_user_id
+            "#,
+            copied_columns(""),
+            copied_columns("n")
+        ))
+        .persistent(super::prepared_statements())
+        .bind(id)
+        .execute(&mut *tx)
+        .await;
+
+        let created = match created {
+            Ok(r) => r.rows_affected(),
+            Err(e) => {
+                error!(id = %id, error = %e, "DB expand_topic: insert failed");
+                return Err(e);
+            }
+        };
+
+        sqlx::query("SELECT activity.sp_notification_success($1)")
+            .persistent(super::prepared_statements())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        debug!(
+            id = %id,
+            created = created,
+            duration_ms = start.elapsed().as_millis() as u64,
+            "DB expand_topic: completed"
+        );
+        Ok(created)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TopicSubscription {
+    pub topic: String,
+    pub created_at: DateTime<Utc>,
+}
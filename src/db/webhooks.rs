@@ -0,0 +1,175 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, instrument, trace};
+
+pub struct WebhookSourceQueries;
+
+impl WebhookSourceQueries {
+    /// Find an enabled webhook source
+    #[instrument(skip(pool))]
+    pub async fn find_enabled(pool: &PgPool, source: &str) -> Result<Option<WebhookSource>, sqlx::Error> {
+        trace!("DB find_webhook_source: looking up '{}'", source);
+        let start = Instant::now();
+
+        let result = sqlx::query_as::<_, WebhookSource>(
+            r#"
+            SELECT source, secret, signature_header, mapping, enabled, updated_at
+            FROM activity.webhook_sources
+            WHERE source = $1 AND enabled
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(source)
+        .fetch_optional(pool)
+        .await;
+
+        let duration = start.elapsed();
+
+        match &result {
+            Ok(found) => {
+                debug!(
+                    source = %source,
+                    found = found.is_some(),
+                    duration_ms = duration.as_millis() as u64,
+                    "DB find_webhook_source: completed"
+                );
+            }
+            Err(e) => {
+                error!(
+                    source = %source,
+                    duration_ms = duration.as_millis() as u64,
+                    error = %e,
+                    "DB find_webhook_source: query failed"
+                );
+            }
+        }
+
+        result
+    }
+
+    /// List all webhook sources
+    #[instrument(skip(pool))]
+    pub async fn list(pool: &PgPool) -> Result<Vec<WebhookSource>, sqlx::Error> {
+        trace!("DB list_webhook_sources");
+
+        sqlx::query_as::<_, WebhookSource>(
+            r#"
+            SELECT source, secret, signature_header, mapping, enabled, updated_at
+            FROM activity.webhook_sources
+            ORDER BY source
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Create or replace a webhook source
+    #[instrument(skip(pool, secret, mapping))]
+    pub async fn upsert(
+        pool: &PgPool,
+        source: &str,
+        secret: &str,
+        signature_header: &str,
+        mapping: &serde_json::Value,
+        enabled: bool,
+    ) -> Result<WebhookSource, sqlx::Error> {
+        trace!("DB upsert_webhook_source: '{}'", source);
+
+        sqlx::query_as::<_, WebhookSource>(
+            r#"
+            INSERT INTO activity.webhook_sources (source, secret, signature_header, mapping, enabled)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (source)
+            DO UPDATE SET
+                secret = EXCLUDED.secret,
+                signature_header = EXCLUDED.signature_header,
+                mapping = EXCLUDED.mapping,
+                enabled = EXCLUDED.enabled,
+                updated_at = now()
+            RETURNING source, secret, signature_header, mapping, enabled, updated_at
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(source)
+        .bind(secret)
+        .bind(signature_header)
+        .bind(mapping)
+        .bind(enabled)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Delete a webhook source - returns false if it didn't exist
+    #[instrument(skip(pool))]
+    pub async fn delete(pool: &PgPool, source: &str) -> Result<bool, sqlx::Error> {
+        trace!("DB delete_webhook_source: '{}'", source);
+
+        sqlx::query("DELETE FROM activity.webhook_sources WHERE source = $1")
+            .persistent(super::prepared_statements())
+            .bind(source)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected() > 0)
+    }
+
+    /// Record a verified signature - returns false if it was seen before (a replay)
+    ///
+    /// Prunes signatures older than twice `tolerance` in the same statement.
+    #[instrument(skip(pool, signature))]
+    pub async fn remember_signature(
+        pool: &PgPool,
+        source: &str,
+        signature: &[u8],
+        tolerance: Duration,
+    ) -> Result<bool, sqlx::Error> {
+        trace!("DB remember_webhook_signature: '{}'", source);
+
+        sqlx::query(
+            r#"
+            WITH pruned AS (
+                DELETE FROM activity.webhook_signatures
+                WHERE received_at < now() - make_interval(secs => $3)
+            )
+            INSERT INTO activity.webhook_signatures (source, signature)
+            VALUES ($1, $2)
+            ON CONFLICT (source, signature) DO NOTHING
+            "#,
+        )
+        .persistent(super::prepared_statements())
+        .bind(source)
+        .bind(signature)
+        .bind(2.0 * tolerance.as_secs_f64())
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected() > 0)
+    }
+
+    /// Drop a recorded signature, so the sender can retry a request that failed
+    #[instrument(skip(pool, signature))]
+    pub async fn forget_signature(pool: &PgPool, source: &str, signature: &[u8]) -> Result<(), sqlx::Error> {
+        trace!("DB forget_webhook_signature: '{}'", source);
+
+        sqlx::query("DELETE FROM activity.webhook_signatures WHERE source = $1 AND signature = $2")
+            .persistent(super::prepared_statements())
+            .bind(source)
+            .bind(signature)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct WebhookSource {
+    pub source: String,
+    /// Never returned by the API
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub signature_header: String,
+    pub mapping: serde_json::Value,
+    pub enabled: bool,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,115 @@
+//! Developer sandbox (DEV_SANDBOX_TENANT): a tenant where any user with a valid JWT registers
+//! fake devices and sends themselves notifications through the full pipeline.
+//!
+//! Push for that tenant goes to a [`MockFcm`] inside the process, never to Firebase, so a fake
+//! token can't reach a real device and every message can be read back
+//! (`GET /api/v1/sandbox/notifications/{id}`). That needs the API and the worker in the same
+//! process: another replica's worker pushes to its own mock.
+
+use crate::config::Config;
+use crate::db::tenants::{TenantSettings, DEFAULT_TENANT};
+use crate::db::TenantQueries;
+use crate::push::mock::{MockFcm, MockResponse};
+use crate::push::FcmClient;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+/// Fake device tokens start with this
+pub const TOKEN_PREFIX: &str = "sandbox-";
+/// Sends per developer and minute
+pub const SENDS_PER_MINUTE: i32 = 30;
+/// Push messages the mock keeps for reading back
+const KEEP_MESSAGES: usize = 10_000;
+const PROJECT_ID: &str = "dev-sandbox";
+
+pub struct DevSandbox {
+    tenant_id: String,
+    mock: MockFcm,
+    fcm: Arc<FcmClient>,
+}
+
+/// A push the mock received
+#[derive(Debug, Serialize)]
+pub struct SandboxPush {
+    /// FCM v1 `message`
+    pub message: Value,
+    /// What the mock answered (`success` unless the device was registered otherwise)
+    pub response: &'static str,
+}
+
+impl DevSandbox {
+    /// None without DEV_SANDBOX_TENANT; creates the tenant when it doesn't exist yet
+    pub async fn from_config(pool: &PgPool, config: &Config) -> Result<Option<Arc<Self>>, String> {
+        let Some(tenant_id) = &config.dev_sandbox_tenant else {
+            return Ok(None);
+        };
+        if tenant_id == DEFAULT_TENANT {
+            return Err("DEV_SANDBOX_TENANT can't be the default tenant".to_string());
+        }
+
+        let existing = TenantQueries::find(pool, tenant_id)
+            .await
+            .map_err(|e| format!("Failed to load the sandbox tenant: {}", e))?;
+        if existing.is_none() {
+            let settings = TenantSettings {
+                name: "Developer sandbox".to_string(),
+                fcm_project_id: None,
+                fcm_credentials_path: None,
+                fcm_sandbox_project_id: None,
+                fcm_sandbox_credentials_path: None,
+                // Off the production WebSocket gateway's topic
+                bus_topic_prefix: Some(tenant_id.clone()),
+                rate_limit_per_minute: None,
+                daily_budget: None,
+                scheduling_weight: 1,
+                enabled: true,
+            };
+            TenantQueries::upsert(pool, tenant_id, &settings)
+                .await
+                .map_err(|e| format!("Failed to create the sandbox tenant: {}", e))?;
+        }
+
+        let mock = MockFcm::start()
+            .await
+            .map_err(|e| format!("Failed to start the sandbox mock FCM: {}", e))?
+            .keep_last(KEEP_MESSAGES);
+        let fcm = Arc::new(mock.client(PROJECT_ID));
+        info!(tenant_id = %tenant_id, created = existing.is_none(), mock = %mock.base_url(), "Developer sandbox enabled");
+
+        Ok(Some(Arc::new(Self { tenant_id: tenant_id.clone(), mock, fcm })))
+    }
+
+    pub fn tenant_id(&self) -> &str {
+        &self.tenant_id
+    }
+
+    /// Client for every push of the sandbox tenant
+    pub fn fcm(&self) -> Arc<FcmClient> {
+        self.fcm.clone()
+    }
+
+    /// A new fake device token
+    pub fn new_token() -> String {
+        format!("{}{}", TOKEN_PREFIX, Uuid::new_v4().simple())
+    }
+
+    /// How the mock answers pushes to `token` (e.g. unregistered, to see the token cleaned up)
+    pub fn respond_for_token(&self, token: &str, response: MockResponse) {
+        self.mock.respond_for_token(token, response);
+    }
+
+    /// Pushes of one notification, oldest first
+    pub fn pushes(&self, id: Uuid) -> Vec<SandboxPush> {
+        let id = id.to_string();
+        self.mock
+            .sent_with_responses()
+            .into_iter()
+            .filter(|(message, _)| message["data"]["id"] == id.as_str())
+            .map(|(message, response)| SandboxPush { message, response: response.as_str() })
+            .collect()
+    }
+}
@@ -0,0 +1,288 @@
+//! Email digests: a daily summary of what a user hasn't read in-app.
+//!
+//! Users opt in with `PUT /api/v1/digest` (`activity.digest_subscriptions`). The
+//! [`DigestJob`] polls for due subscriptions and sends one email per user at DIGEST_HOUR
+//! in the user's timezone, listing the unread inbox notifications (newest first, at most
+//! DIGEST_MAX_ITEMS) in the user's locale. The copy is the `email_digest` template
+//! (migration 038 seeds en/nl). Summarized notifications get `digested_at` and are never
+//! in a second digest; a day without unread notifications sends nothing. Subscriptions
+//! are claimed with `FOR UPDATE SKIP LOCKED`, so every replica can run the job. A user or
+//! email address on the suppression list gets no digest; its notifications stay unread.
+
+use crate::db::digests::DigestSubscription;
+use crate::db::templates::ANY_CHANNEL;
+use crate::db::{DigestQueries, PreferenceQueries, SuppressionQueries, TemplateQueries};
+use crate::i18n::{Localizer, DEFAULT_LOCALE};
+use crate::models::Notification;
+use crate::recurring::CronSchedule;
+use crate::templates::TemplateRenderer;
+use crate::worker::{Channel, CostLedger};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde_json::json;
+use sqlx::{Connection, PgPool};
+use std::time::Duration;
+use tracing::{debug, error, info, instrument, warn};
+
+/// Template rendered for the email (title = subject, body = text)
+pub const DIGEST_TEMPLATE: &str = "email_digest";
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// HTTP mail relay (DIGEST_EMAIL_URL): one JSON POST `{from, to, subject, text}` per email
+pub struct EmailRelay {
+    http: reqwest::Client,
+    url: String,
+    token: Option<String>,
+    from: Option<String>,
+}
+
+impl EmailRelay {
+    pub fn new(url: String, token: Option<String>, from: Option<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self { http, url, token, from }
+    }
+
+    pub async fn send(&self, to: &str, subject: &str, text: &str) -> Result<(), String> {
+        let mut request = self.http.post(&self.url).json(&json!({
+            "from": self.from,
+            "to": to,
+            "subject": subject,
+            "text": text,
+        }));
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await.map_err(|e| format!("Mail relay request failed: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let detail = response.text().await.unwrap_or_default();
+            return Err(format!("Mail relay failed: {} - {}", status, detail));
+        }
+        Ok(())
+    }
+}
+
+pub struct DigestJob {
+    pool: PgPool,
+    relay: EmailRelay,
+    localizer: Localizer,
+    templates: TemplateRenderer,
+    hour: u32,
+    max_items: i64,
+    /// Zone for users without one (DELIVERY_WINDOW_TIMEZONE)
+    default_timezone: String,
+    poll_interval: Duration,
+    /// Email deliveries and daily budgets (None = not tracked)
+    costs: Option<CostLedger>,
+}
+
+impl DigestJob {
+    pub fn new(
+        pool: PgPool,
+        relay: EmailRelay,
+        hour: u32,
+        max_items: i64,
+        default_timezone: String,
+        poll_interval: Duration,
+    ) -> Self {
+        Self {
+            localizer: Localizer::new(),
+            templates: TemplateRenderer::new(pool.clone()),
+            pool,
+            relay,
+            hour,
+            max_items,
+            default_timezone,
+            poll_interval,
+            costs: None,
+        }
+    }
+
+    /// Count sent digests as email deliveries; skip them once the tenant's budget is spent
+    pub fn with_costs(mut self, costs: CostLedger) -> Self {
+        self.costs = Some(costs);
+        self
+    }
+
+    /// Job loop: handle every due subscription, then sleep
+    #[instrument(skip(self), name = "digest_job")]
+    pub async fn run(&self) {
+        info!(
+            hour = self.hour,
+            max_items = self.max_items,
+            poll_interval_secs = self.poll_interval.as_secs(),
+            "Email digest job started"
+        );
+
+        loop {
+            loop {
+                match self.run_next_due().await {
+                    Ok(true) => continue,
+                    Ok(false) => break,
+                    Err(e) => {
+                        error!(error = %e, "Failed to run email digest");
+                        break;
+                    }
+                }
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Send (or schedule) the most overdue digest - false when nothing is due
+    async fn run_next_due(&self) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(subscription) = DigestQueries::claim_due(&mut tx).await? else {
+            return Ok(false);
+        };
+        let user_id = subscription.user_id;
+        let next_run_at = self.next_run(&subscription).await;
+
+        // New subscriptions only get their first slot
+        if subscription.next_run_at.is_none() {
+            DigestQueries::schedule(&mut *tx, &subscription, next_run_at, false).await?;
+            tx.commit().await?;
+            debug!(user_id = %user_id, next_run_at = %next_run_at, "Email digest scheduled");
+            return Ok(true);
+        }
+
+        // Bounced addresses and opted-out users: skip this slot, keep the subscription
+        if self.is_suppressed(&mut tx, &subscription).await? {
+            info!(user_id = %user_id, "⊘ Email digest suppressed - on the suppression list");
+            DigestQueries::schedule(&mut *tx, &subscription, next_run_at, false).await?;
+            tx.commit().await?;
+            return Ok(true);
+        }
+
+        // Budget spent: the notifications stay unread for the next slot
+        if self.is_over_budget(&subscription).await {
+            info!(user_id = %user_id, tenant_id = %subscription.tenant_id, "⊘ Email digest skipped - daily delivery budget spent");
+            DigestQueries::schedule(&mut *tx, &subscription, next_run_at, false).await?;
+            tx.commit().await?;
+            return Ok(true);
+        }
+
+        let items = DigestQueries::unread(&mut tx, &subscription, self.max_items).await?;
+        let mut sent = false;
+        if items.is_empty() {
+            debug!(user_id = %user_id, "Nothing unread, no email digest");
+        } else {
+            // Savepoint: a failed send leaves the notifications for the next digest
+            let mut marked = tx.begin().await?;
+            let count = DigestQueries::mark_digested(&mut *marked, &subscription).await?;
+            match self.send(&subscription, &items, count).await {
+                Ok(()) => {
+                    marked.commit().await?;
+                    sent = true;
+                    info!(user_id = %user_id, notifications = count, "📧 Email digest sent");
+                    metrics::counter!("notifications_digests_sent_total").increment(1);
+                    if let Some(costs) = &self.costs {
+                        costs.record(&subscription.tenant_id, "email");
+                    }
+                }
+                Err(e) => {
+                    marked.rollback().await?;
+                    warn!(user_id = %user_id, error = %e, "Email digest failed, retrying at the next slot");
+                    metrics::counter!("notifications_digests_failed_total").increment(1);
+                }
+            }
+        }
+
+        DigestQueries::schedule(&mut *tx, &subscription, next_run_at, sent).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// The subscriber's email address or user is on the suppression list
+    async fn is_suppressed(&self, conn: &mut sqlx::PgConnection, subscription: &DigestSubscription) -> Result<bool, sqlx::Error> {
+        let tenant_id = &subscription.tenant_id;
+        let email = subscription.email.to_lowercase();
+        Ok(SuppressionQueries::is_suppressed(&mut *conn, tenant_id, "email", &email).await?
+            || SuppressionQueries::is_suppressed(&mut *conn, tenant_id, "user", &subscription.user_id.to_string()).await?)
+    }
+
+    /// Emails have a price and the tenant spent its daily budget
+    async fn is_over_budget(&self, subscription: &DigestSubscription) -> bool {
+        match &self.costs {
+            Some(costs) if costs.prices().is_paid("email") => costs.is_over_budget(&subscription.tenant_id).await,
+            _ => false,
+        }
+    }
+
+    /// Render the email in the user's locale and hand it to the relay
+    async fn send(&self, subscription: &DigestSubscription, items: &[Notification], count: u64) -> Result<(), String> {
+        let locale = PreferenceQueries::get_locale(&self.pool, &subscription.tenant_id, subscription.user_id)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(user_id = %subscription.user_id, error = %e, "Failed to load user locale, using default");
+                None
+            });
+
+        let mut rendered = Vec::with_capacity(items.len());
+        for notification in items {
+            let (title, body) = self.item_text(notification, locale.as_deref()).await;
+            rendered.push(json!({
+                "title": title,
+                "body": body,
+                "deep_link": notification.deep_link,
+                "notification_type": notification.notification_type,
+                "created_at": notification.created_at,
+            }));
+        }
+        let variables = json!({
+            "count": count,
+            "items": rendered,
+            "more": count.saturating_sub(items.len() as u64),
+        });
+
+        let locale = locale.as_deref().unwrap_or(DEFAULT_LOCALE);
+        let template = TemplateQueries::find(&self.pool, DIGEST_TEMPLATE, locale, DEFAULT_LOCALE, ANY_CHANNEL)
+            .await
+            .map_err(|e| format!("Template lookup failed: {}", e))?
+            .ok_or_else(|| format!("Template not found: {}", DIGEST_TEMPLATE))?;
+        let text = TemplateRenderer::render_template(&template, &variables).map_err(|e| e.to_string())?;
+        debug!(
+            user_id = %subscription.user_id,
+            locale = %template.locale,
+            version = template.version,
+            "Email digest rendered"
+        );
+
+        self.relay.send(&subscription.email, &text.title, &text.body).await
+    }
+
+    /// Title and body as the inbox shows them (template, else Fluent, else the literal text)
+    async fn item_text(&self, notification: &Notification, locale: Option<&str>) -> (String, Option<String>) {
+        if let Some(template_key) = &notification.template_key {
+            match self.templates.render(notification, template_key, locale, Channel::Bus.as_str()).await {
+                Ok(rendered) => return (rendered.text.title, Some(rendered.text.body)),
+                Err(e) => debug!(id = %notification.id, error = %e, "Template rendering failed, falling back"),
+            }
+        }
+        let localized = self.localizer.localize(notification, locale);
+        (localized.title.clone(), localized.message.clone())
+    }
+
+    /// Next DIGEST_HOUR in the user's timezone (else the default zone)
+    async fn next_run(&self, subscription: &DigestSubscription) -> DateTime<Utc> {
+        let timezone = PreferenceQueries::get_timezone(&self.pool, &subscription.tenant_id, subscription.user_id)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(user_id = %subscription.user_id, error = %e, "Failed to load user timezone, using default");
+                None
+            });
+
+        let expression = format!("0 {} * * *", self.hour);
+        let schedule = [timezone.as_deref(), Some(self.default_timezone.as_str()), Some("UTC")]
+            .into_iter()
+            .flatten()
+            .find_map(|timezone| CronSchedule::parse(&expression, timezone).ok());
+        schedule
+            .and_then(|schedule| schedule.next_after(Utc::now()))
+            .unwrap_or_else(|| Utc::now() + ChronoDuration::days(1))
+    }
+}
@@ -0,0 +1,236 @@
+//! `notifications-service drain`: empty an environment before decommissioning or migrating it.
+//!
+//! 1. Closes ingestion for the whole environment (migration 067): the API and gRPC answer
+//!    503, the Kafka/NATS/SQS consumers leave messages in the broker for the next
+//!    environment, and campaigns, recurring schedules, per-user broadcasts and re-engagement
+//!    stop creating rows. Producers that INSERT directly have to be stopped first.
+//! 2. Waits up to `--timeout` for the running replicas to deliver everything that is due.
+//! 3. With `--export`, writes every row still unprocessed (scheduled for later, still
+//!    retrying, or left because nothing runs) to an NDJSON file, one `to_jsonb` row per line
+//!    as archives store them, and takes it out of the queue (suppressed as `drained`). Each
+//!    batch is on disk before it is committed: a crash exports a row twice, never loses it.
+//! 4. Counts the queue again and fails unless it is empty.
+//!
+//! Unlike the pod drain (`worker::Drain`, `POST /admin/prestop`) this is per environment and
+//! doesn't end by itself; `--reopen` opens ingestion again.
+//!
+//! ```text
+//! notifications-service drain [--export <path.ndjson>] [--timeout <secs>]
+//! notifications-service drain --reopen
+//! ```
+
+use crate::api::audit;
+use crate::db::drain::QueueCount;
+use crate::db::{DrainQueries, MaintenanceQueries};
+use serde_json::json;
+use sqlx::PgPool;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+const USAGE: &str = "usage: notifications-service drain [--export <path.ndjson>] [--timeout <secs>] | drain --reopen";
+/// Audit log actor for drains started from the command line
+const CLI_ACTOR: &str = "cli";
+/// How long replicas get for deliveries that are due (default `--timeout`)
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+/// Lets requests that passed the gate just before it closed finish their insert
+const CLOSE_GRACE: Duration = Duration::from_secs(2);
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+/// Rows per export transaction
+const EXPORT_BATCH: i64 = 1000;
+/// Export rounds: rows a worker held locked during one are usually done by the next
+const EXPORT_PASSES: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct DrainArgs {
+    /// Write what is left to this file (must not exist yet)
+    pub export: Option<PathBuf>,
+    /// How long to wait for due rows to be delivered (0 = export right away)
+    pub timeout: Duration,
+    /// Open ingestion again instead of draining
+    pub reopen: bool,
+}
+
+impl DrainArgs {
+    /// Parse the arguments after `drain`
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut export = None;
+        let mut timeout = Duration::from_secs(DEFAULT_TIMEOUT_SECS);
+        let mut reopen = false;
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--reopen" => reopen = true,
+                "--export" | "--timeout" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value\n{}", flag, USAGE))?;
+                    if flag == "--export" {
+                        export = Some(PathBuf::from(value));
+                    } else {
+                        let secs = value
+                            .parse()
+                            .map_err(|_| format!("--timeout: invalid number of seconds '{}'\n{}", value, USAGE))?;
+                        timeout = Duration::from_secs(secs);
+                    }
+                }
+                _ => return Err(format!("Unknown option '{}'\n{}", flag, USAGE)),
+            }
+        }
+        if reopen && export.is_some() {
+            return Err(format!("--reopen takes no other options\n{}", USAGE));
+        }
+
+        Ok(Self { export, timeout, reopen })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DrainSummary {
+    /// Queue when ingestion closed
+    pub before: QueueCount,
+    /// Left the queue while waiting (delivered, or suppressed by the worker)
+    pub processed: i64,
+    pub exported: u64,
+    pub export: Option<PathBuf>,
+    /// Queue at the end (anything here fails the command)
+    pub remaining: QueueCount,
+    pub waited: Duration,
+}
+
+impl DrainSummary {
+    pub fn is_empty(&self) -> bool {
+        self.remaining.unprocessed == 0
+    }
+
+    pub fn log(&self) {
+        info!("═══════════════════════════════════════════════════════════");
+        if self.is_empty() {
+            info!("  DRAIN COMPLETE (queue empty, ingestion stays closed)");
+        } else {
+            warn!("  DRAIN INCOMPLETE (queue not empty, ingestion stays closed)");
+        }
+        info!("  Unprocessed at close:  {} ({} due)", self.before.unprocessed, self.before.due);
+        info!("  Processed meanwhile:   {} (waited {}s)", self.processed, self.waited.as_secs());
+        match &self.export {
+            Some(path) => info!("  Exported:              {} -> {}", self.exported, path.display()),
+            None => info!("  Exported:              - (no --export)"),
+        }
+        info!("  Remaining:             {} ({} due)", self.remaining.unprocessed, self.remaining.due);
+        info!("═══════════════════════════════════════════════════════════");
+    }
+}
+
+/// Close ingestion, wait for due deliveries, export the rest and verify the queue is empty
+pub async fn run(pool: &PgPool, args: &DrainArgs) -> Result<DrainSummary, String> {
+    let db_error = |e: sqlx::Error| format!("Database error: {}", e);
+    // Refuse before closing anything: an existing export is never overwritten
+    if let Some(path) = args.export.as_deref().filter(|path| path.exists()) {
+        return Err(format!("{} already exists", path.display()));
+    }
+
+    let maintenance = MaintenanceQueries::get(pool).await.map_err(db_error)?;
+    if maintenance.enabled && args.export.is_none() {
+        warn!("Maintenance mode is on: the replicas deliver nothing, so without --export the queue won't empty");
+    }
+    MaintenanceQueries::set_ingestion_closed(pool, true, CLI_ACTOR).await.map_err(db_error)?;
+    info!("Ingestion closed - no new notifications are accepted");
+    tokio::time::sleep(CLOSE_GRACE).await;
+
+    let before = DrainQueries::count(pool).await.map_err(db_error)?;
+    let start = Instant::now();
+    let mut count = before;
+    let mut last_progress = Instant::now();
+    while count.due > 0 && start.elapsed() < args.timeout {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        count = DrainQueries::count(pool).await.map_err(db_error)?;
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            info!(due = count.due, unprocessed = count.unprocessed, "Waiting for due notifications to be delivered");
+            last_progress = Instant::now();
+        }
+    }
+    let waited = start.elapsed();
+    if count.due > 0 {
+        warn!(due = count.due, timeout_secs = args.timeout.as_secs(), "Due notifications left after the timeout");
+    }
+
+    let mut exported = 0;
+    let mut remaining = count;
+    if let Some(path) = &args.export {
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut out = BufWriter::new(file);
+        for _ in 0..EXPORT_PASSES {
+            exported += export_pass(pool, &mut out, path).await?;
+            remaining = DrainQueries::count(pool).await.map_err(db_error)?;
+            if remaining.unprocessed == 0 {
+                break;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    } else {
+        remaining = DrainQueries::count(pool).await.map_err(db_error)?;
+    }
+
+    let summary = DrainSummary {
+        before,
+        processed: (before.unprocessed - count.unprocessed).max(0),
+        exported,
+        export: args.export.clone(),
+        remaining,
+        waited,
+    };
+    audit::record(
+        pool,
+        CLI_ACTOR,
+        "notifications.drain",
+        json!({
+            "export": summary.export,
+            "exported": summary.exported,
+            "processed": summary.processed,
+            "remaining": summary.remaining.unprocessed,
+        }),
+    )
+    .await;
+    Ok(summary)
+}
+
+/// Open ingestion again (an aborted decommission)
+pub async fn reopen(pool: &PgPool) -> Result<(), String> {
+    MaintenanceQueries::set_ingestion_closed(pool, false, CLI_ACTOR)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+    audit::record(pool, CLI_ACTOR, "notifications.drain_reopen", json!({})).await;
+    Ok(())
+}
+
+/// Export and dequeue every unprocessed row nobody holds a lock on - returns how many
+async fn export_pass(pool: &PgPool, out: &mut BufWriter<std::fs::File>, path: &Path) -> Result<u64, String> {
+    let write_error = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+    let db_error = |e: sqlx::Error| format!("Database error: {}", e);
+    let mut exported = 0;
+    loop {
+        let mut tx = pool.begin().await.map_err(db_error)?;
+        let rows = DrainQueries::claim_batch(&mut tx, EXPORT_BATCH).await.map_err(db_error)?;
+        if rows.is_empty() {
+            return Ok(exported);
+        }
+        for row in &rows {
+            serde_json::to_writer(&mut *out, &row.row).map_err(|e| write_error(e.into()))?;
+            out.write_all(b"\n").map_err(write_error)?;
+        }
+        out.flush().map_err(write_error)?;
+        out.get_ref().sync_all().map_err(write_error)?;
+
+        let ids: Vec<Uuid> = rows.iter().map(|row| row.id).collect();
+        exported += DrainQueries::mark_exported(&mut tx, &ids).await.map_err(db_error)?;
+        tx.commit().await.map_err(db_error)?;
+        info!(exported = exported, "Exported a batch of unprocessed notifications");
+    }
+}
@@ -0,0 +1,170 @@
+//! Delivery errors shared across modules.
+//!
+//! The worker carries a [`NotificationError`] from the failing call to `mark_failure`.
+//! The variant decides whether the notification is retried ([`NotificationError::is_retryable`])
+//! and which `failure_category` and metrics labels it gets ([`NotificationError::category`]).
+//! `Display` is what ends up in `last_error`.
+
+use crate::policy::PolicyError;
+use crate::push::fcm::FcmError;
+use crate::worker::FailureCategory;
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotificationError {
+    #[error(transparent)]
+    Db(#[from] DbError),
+    #[error(transparent)]
+    Push(#[from] PushError),
+    #[error(transparent)]
+    Bus(#[from] BusError),
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+    /// The content policy hook failed and POLICY_HOOK_FAIL_MODE is closed
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+    /// Push failed after the Bus publish had failed too
+    #[error("{push}; {bus}")]
+    Undelivered { push: Box<NotificationError>, bus: BusError },
+}
+
+impl NotificationError {
+    /// Add the Bus failure that push couldn't make up for
+    pub fn with_bus(self, bus: Option<BusError>) -> Self {
+        match bus {
+            Some(bus) => NotificationError::Undelivered { push: Box::new(self), bus },
+            None => self,
+        }
+    }
+
+    /// Worth another attempt after backoff? Permanent errors stop at the first failure.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            NotificationError::Db(e) => e.is_retryable(),
+            NotificationError::Push(e) => e.is_retryable(),
+            NotificationError::Bus(_) => true,
+            NotificationError::Validation(_) => false,
+            // The hook may answer the next attempt
+            NotificationError::Policy(_) => true,
+            // The Bus may be back by the next attempt
+            NotificationError::Undelivered { .. } => true,
+        }
+    }
+
+    /// Root cause, stored as `failure_category` and used as the metrics label
+    pub fn category(&self) -> FailureCategory {
+        match self {
+            NotificationError::Db(_) => FailureCategory::Database,
+            NotificationError::Push(e) => e.category(),
+            NotificationError::Bus(_) => FailureCategory::BusUnreachable,
+            NotificationError::Validation(_) => FailureCategory::Validation,
+            NotificationError::Policy(_) => FailureCategory::PolicyHook,
+            // A device or message problem is the more specific answer than the Bus
+            NotificationError::Undelivered { push, .. } => match push.category() {
+                category @ (FailureCategory::InvalidToken
+                | FailureCategory::FcmQuota
+                | FailureCategory::TemplateError
+                | FailureCategory::Validation) => category,
+                _ => FailureCategory::BusUnreachable,
+            },
+        }
+    }
+}
+
+impl From<FcmError> for NotificationError {
+    fn from(e: FcmError) -> Self {
+        if e.is_rejected_message() {
+            NotificationError::Validation(ValidationError::Rejected(e))
+        } else {
+            NotificationError::Push(PushError::Fcm(e))
+        }
+    }
+}
+
+/// A database call the delivery depends on failed
+#[derive(Debug, thiserror::Error)]
+#[error("{context}: {source}")]
+pub struct DbError {
+    pub context: &'static str,
+    #[source]
+    pub source: sqlx::Error,
+}
+
+impl DbError {
+    pub fn new(context: &'static str, source: sqlx::Error) -> Self {
+        Self { context, source }
+    }
+
+    /// Connection and pool problems pass; bad data (class 22), constraint violations (23),
+    /// schema errors (42) and decoding errors don't
+    pub fn is_retryable(&self) -> bool {
+        match &self.source {
+            sqlx::Error::Database(e) => !e.code().is_some_and(|code| matches!(code.get(..2), Some("22" | "23" | "42"))),
+            sqlx::Error::RowNotFound
+            | sqlx::Error::TypeNotFound { .. }
+            | sqlx::Error::ColumnNotFound(_)
+            | sqlx::Error::ColumnIndexOutOfBounds { .. }
+            | sqlx::Error::ColumnDecode { .. }
+            | sqlx::Error::Decode(_) => false,
+            _ => true,
+        }
+    }
+}
+
+/// No device was reached via FCM
+#[derive(Debug, thiserror::Error)]
+pub enum PushError {
+    #[error("FCM not configured")]
+    NotConfigured,
+    #[error("No registered devices")]
+    NoDevices,
+    /// Every token came back UNREGISTERED / INVALID_ARGUMENT (and was removed)
+    #[error("Every device token was invalid")]
+    InvalidTokens,
+    #[error("All push attempts failed")]
+    AllFailed,
+    /// The last device's FCM error
+    #[error(transparent)]
+    Fcm(#[from] FcmError),
+}
+
+impl PushError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            PushError::InvalidTokens => false,
+            PushError::Fcm(e) => !matches!(e, FcmError::InvalidToken) && !e.is_rejected_message(),
+            PushError::NotConfigured | PushError::NoDevices | PushError::AllFailed => true,
+        }
+    }
+
+    pub fn category(&self) -> FailureCategory {
+        match self {
+            PushError::NoDevices => FailureCategory::NoDevices,
+            PushError::InvalidTokens | PushError::Fcm(FcmError::InvalidToken) => FailureCategory::InvalidToken,
+            PushError::Fcm(e) if e.is_quota() => FailureCategory::FcmQuota,
+            PushError::Fcm(e) if e.is_rejected_message() => FailureCategory::Validation,
+            PushError::NotConfigured | PushError::AllFailed | PushError::Fcm(_) => FailureCategory::FcmUnavailable,
+        }
+    }
+}
+
+/// Publishing to the WebSocket Bus failed (error, timeout or injected fault)
+#[derive(Debug, thiserror::Error)]
+#[error("WebSocket Bus: {0}")]
+pub struct BusError(pub String);
+
+/// The notification itself is wrong - never retried
+#[derive(Debug, thiserror::Error)]
+pub enum ValidationError {
+    /// Rejected by our own checks (API, ingest)
+    #[error("{0}")]
+    Invalid(String),
+    /// FCM refused the message (400)
+    #[error(transparent)]
+    Rejected(FcmError),
+}
+
+impl ValidationError {
+    pub fn invalid(reason: impl Into<String>) -> Self {
+        ValidationError::Invalid(reason.into())
+    }
+}
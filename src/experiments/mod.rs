@@ -0,0 +1,28 @@
+//! A/B content experiments: alternative title/body per template or campaign.
+//!
+//! Experiments live in `activity.experiments` (managed via `/api/v1/experiments`).
+//! When the worker renders a notification whose template or campaign has a running
+//! experiment, [`assign`] picks the recipient's variant from a hash of experiment id
+//! and user id: stable across retries, channels and replicas, and independent between
+//! experiments. The variant is recorded on the delivery attempts, and
+//! `GET /api/v1/experiments/{id}` compares open rates per variant.
+
+use crate::db::experiments::{Experiment, Variant};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Variant a user sees in an experiment (None for an experiment without variants)
+pub fn assign(experiment: &Experiment, user_id: Uuid) -> Option<&Variant> {
+    let variants = &experiment.variants.0;
+    if variants.is_empty() {
+        return None;
+    }
+
+    let digest = Sha256::new()
+        .chain_update(experiment.id.as_bytes())
+        .chain_update(user_id.as_bytes())
+        .finalize();
+    let mut bucket = [0u8; 8];
+    bucket.copy_from_slice(&digest[..8]);
+    variants.get((u64::from_be_bytes(bucket) % variants.len() as u64) as usize)
+}
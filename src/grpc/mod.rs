@@ -0,0 +1,293 @@
+//! Protobuf contract (`proto/notifications.proto`) and the gRPC API.
+//!
+//! The gRPC server (GRPC_PORT) lets producers create notifications with a typed,
+//! versioned contract; it feeds the same ingest path as the other sources.
+//! The generated messages are also used for optional binary Bus payloads and
+//! NATS delivery events.
+
+use crate::ingest::backpressure::Backpressure;
+use crate::ingest::{ingest, IngestError};
+use crate::models::{NewNotification, Notification, NotificationAction, NotificationAttachment, SignedAttachment};
+use crate::worker::events::{DeliveryEvent, DeliveryStatus};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use prost::Message;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+pub mod pb {
+    tonic::include_proto!("notifications.v1");
+}
+
+use pb::notification_service_server::{NotificationService, NotificationServiceServer};
+
+/// gRPC implementation of `notifications.v1.NotificationService`
+pub struct GrpcApi {
+    pool: PgPool,
+    backpressure: Arc<Backpressure>,
+}
+
+impl GrpcApi {
+    /// Service with bearer-token auth (`authorization: Bearer <ADMIN_TOKEN>` metadata)
+    // tonic::Status as the error type is dictated by the Interceptor trait
+    #[allow(clippy::result_large_err)]
+    pub fn server(
+        pool: PgPool,
+        backpressure: Arc<Backpressure>,
+        token: Arc<str>,
+    ) -> tonic::service::interceptor::InterceptedService<
+        NotificationServiceServer<GrpcApi>,
+        impl tonic::service::Interceptor + Clone,
+    > {
+        let interceptor = move |request: Request<()>| {
+            let authorized = request
+                .metadata()
+                .get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+                .is_some_and(|t| crate::api::auth::constant_time_eq(t.as_bytes(), token.as_bytes()));
+
+            if authorized {
+                Ok(request)
+            } else {
+                Err(Status::unauthenticated("Invalid or missing bearer token"))
+            }
+        };
+
+        NotificationServiceServer::with_interceptor(GrpcApi { pool, backpressure }, interceptor)
+    }
+}
+
+#[tonic::async_trait]
+impl NotificationService for GrpcApi {
+    async fn create_notification(
+        &self,
+        request: Request<pb::CreateNotificationRequest>,
+    ) -> Result<Response<pb::CreateNotificationResponse>, Status> {
+        let notification = request
+            .into_inner()
+            .notification
+            .ok_or_else(|| Status::invalid_argument("notification is required"))?;
+        let notification = NewNotification::try_from(notification).map_err(Status::invalid_argument)?;
+        self.backpressure
+            .check(&self.pool, &notification)
+            .await
+            .map_err(|e| Status::resource_exhausted(e.to_string()))?;
+
+        let id = ingest(&self.pool, &notification).await.map_err(|e| match e {
+            IngestError::Invalid(reason) => Status::invalid_argument(reason),
+            IngestError::Database(e) => {
+                tracing::error!(error = %e, "gRPC create failed");
+                Status::unavailable("Database unavailable")
+            }
+            IngestError::RateLimited(e) => Status::resource_exhausted(e.to_string()),
+            IngestError::Closed => Status::unavailable("Ingestion is closed (environment draining)"),
+        })?;
+
+        debug!(id = %id, "✓ Notification created via gRPC");
+        Ok(Response::new(pb::CreateNotificationResponse { id: id.to_string() }))
+    }
+}
+
+/// Serve the gRPC API until the process exits
+pub async fn serve(
+    addr: std::net::SocketAddr,
+    pool: PgPool,
+    backpressure: Arc<Backpressure>,
+    token: Arc<str>,
+) -> Result<(), tonic::transport::Error> {
+    info!(addr = %addr, "gRPC API listening");
+    tonic::transport::Server::builder()
+        .add_service(GrpcApi::server(pool, backpressure, token))
+        .serve(addr)
+        .await
+}
+
+impl TryFrom<pb::NewNotification> for NewNotification {
+    type Error = String;
+
+    fn try_from(n: pb::NewNotification) -> Result<Self, Self::Error> {
+        Ok(NewNotification {
+            id: n.id.as_deref().map(parse_uuid).transpose()?,
+            // Audience targets have no recipient of their own
+            user_id: match (&n.audience, n.user_id.is_empty()) {
+                (Some(_), true) => None,
+                _ => Some(parse_uuid(&n.user_id)?),
+            },
+            actor_user_id: n.actor_user_id.as_deref().map(parse_uuid).transpose()?,
+            notification_type: n.notification_type,
+            target_type: n.target_type,
+            target_id: n.target_id.as_deref().map(parse_uuid).transpose()?,
+            title: n.title,
+            message: n.message,
+            payload: n.payload.map(struct_to_json),
+            deep_link: n.deep_link,
+            priority: n.priority,
+            group_key: n.group_key,
+            message_key: n.message_key,
+            message_args: n.message_args.map(struct_to_json),
+            template_key: n.template_key,
+            actions: (!n.actions.is_empty()).then(|| n.actions.into_iter().map(NotificationAction::from).collect()),
+            attachments: (!n.attachments.is_empty())
+                .then(|| n.attachments.into_iter().map(NotificationAttachment::from).collect()),
+            deliver_at: n.deliver_at.map(from_timestamp).transpose()?,
+            deliver_at_local: n.deliver_at_local.as_deref().map(parse_local_time).transpose()?,
+            event_source: None,
+            event_time: None,
+            callback_url: n.callback_url,
+            tenant_id: n.tenant_id,
+            topic: None,
+            audience: n.audience,
+            allow_duplicate: false,
+            pinned_until: n.pinned_until.map(from_timestamp).transpose()?,
+            expires_at: n.expires_at.map(from_timestamp).transpose()?,
+            deadline: n.deadline.map(from_timestamp).transpose()?,
+            region: n.region,
+            created_by: None,
+        })
+    }
+}
+
+impl From<&Notification> for pb::Notification {
+    fn from(n: &Notification) -> Self {
+        pb::Notification {
+            id: n.id.to_string(),
+            user_id: n.user_id.to_string(),
+            actor_user_id: n.actor_user_id.map(|id| id.to_string()),
+            notification_type: n.notification_type.clone(),
+            target_type: n.target_type.clone(),
+            target_id: n.target_id.map(|id| id.to_string()),
+            title: n.title.clone(),
+            message: n.message.clone(),
+            payload: n.payload.as_ref().and_then(json_to_struct),
+            deep_link: n.deep_link.clone(),
+            priority: n.priority.clone(),
+            group_key: n.group_key.clone(),
+            created_at: Some(to_timestamp(n.created_at)),
+            actions: n.inline_actions().into_iter().map(pb::NotificationAction::from).collect(),
+            attachments: n.media.iter().map(pb::SignedAttachment::from).collect(),
+        }
+    }
+}
+
+impl From<pb::Attachment> for NotificationAttachment {
+    fn from(a: pb::Attachment) -> Self {
+        NotificationAttachment { key: a.key, content_type: a.content_type }
+    }
+}
+
+impl From<&SignedAttachment> for pb::SignedAttachment {
+    fn from(a: &SignedAttachment) -> Self {
+        pb::SignedAttachment {
+            url: a.url.clone(),
+            content_type: a.content_type.clone(),
+            expires_at: Some(to_timestamp(a.expires_at)),
+        }
+    }
+}
+
+impl From<pb::NotificationAction> for NotificationAction {
+    fn from(a: pb::NotificationAction) -> Self {
+        NotificationAction { id: a.id, title: a.title, input: a.input }
+    }
+}
+
+impl From<NotificationAction> for pb::NotificationAction {
+    fn from(a: NotificationAction) -> Self {
+        pb::NotificationAction { id: a.id, title: a.title, input: a.input }
+    }
+}
+
+impl From<&DeliveryEvent> for pb::DeliveryEvent {
+    fn from(e: &DeliveryEvent) -> Self {
+        let status = match e.status {
+            DeliveryStatus::Delivered => pb::DeliveryStatus::Delivered,
+            DeliveryStatus::Failed => pb::DeliveryStatus::Failed,
+            DeliveryStatus::Suppressed => pb::DeliveryStatus::Suppressed,
+        };
+
+        pb::DeliveryEvent {
+            notification_id: e.notification_id.to_string(),
+            user_id: e.user_id.to_string(),
+            notification_type: e.notification_type.clone(),
+            status: status as i32,
+            channel: e.channel.map(str::to_string),
+            occurred_at: Some(to_timestamp(e.occurred_at)),
+            tenant_id: e.tenant_id.clone(),
+        }
+    }
+}
+
+/// Protobuf bytes of the client-facing notification
+pub fn encode_notification(notification: &Notification) -> Vec<u8> {
+    pb::Notification::from(notification).encode_to_vec()
+}
+
+/// Protobuf bytes of a delivery event
+pub fn encode_delivery_event(event: &DeliveryEvent) -> Vec<u8> {
+    pb::DeliveryEvent::from(event).encode_to_vec()
+}
+
+fn parse_uuid(value: &str) -> Result<Uuid, String> {
+    Uuid::parse_str(value).map_err(|_| format!("Invalid UUID '{}'", value))
+}
+
+fn to_timestamp(time: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: time.timestamp(),
+        nanos: time.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn from_timestamp(ts: prost_types::Timestamp) -> Result<DateTime<Utc>, String> {
+    DateTime::from_timestamp(ts.seconds, ts.nanos.max(0) as u32).ok_or_else(|| "Invalid timestamp".to_string())
+}
+
+fn parse_local_time(s: &str) -> Result<NaiveDateTime, String> {
+    s.parse().map_err(|_| format!("Invalid local time '{}' (expected YYYY-MM-DDTHH:MM:SS)", s))
+}
+
+fn struct_to_json(s: prost_types::Struct) -> serde_json::Value {
+    serde_json::Value::Object(s.fields.into_iter().map(|(k, v)| (k, value_to_json(v))).collect())
+}
+
+fn value_to_json(value: prost_types::Value) -> serde_json::Value {
+    use prost_types::value::Kind;
+
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => serde_json::Value::Null,
+        Some(Kind::BoolValue(b)) => serde_json::Value::Bool(b),
+        Some(Kind::NumberValue(n)) => serde_json::Number::from_f64(n)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        Some(Kind::StringValue(s)) => serde_json::Value::String(s),
+        Some(Kind::StructValue(s)) => struct_to_json(s),
+        Some(Kind::ListValue(list)) => serde_json::Value::Array(list.values.into_iter().map(value_to_json).collect()),
+    }
+}
+
+/// JSON object → Struct (None for non-objects, which Struct can't represent)
+fn json_to_struct(value: &serde_json::Value) -> Option<prost_types::Struct> {
+    let object = value.as_object()?;
+    Some(prost_types::Struct {
+        fields: object.iter().map(|(k, v)| (k.clone(), json_to_value(v))).collect(),
+    })
+}
+
+fn json_to_value(value: &serde_json::Value) -> prost_types::Value {
+    use prost_types::value::Kind;
+
+    let kind = match value {
+        serde_json::Value::Null => Kind::NullValue(0),
+        serde_json::Value::Bool(b) => Kind::BoolValue(*b),
+        serde_json::Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or_default()),
+        serde_json::Value::String(s) => Kind::StringValue(s.clone()),
+        serde_json::Value::Array(items) => Kind::ListValue(prost_types::ListValue {
+            values: items.iter().map(json_to_value).collect(),
+        }),
+        serde_json::Value::Object(_) => Kind::StructValue(json_to_struct(value).unwrap_or_default()),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
@@ -0,0 +1,34 @@
+# Notification catalog - English (default)
+# Each message has a .title and .body attribute; producers reference the message id
+# via activity.notifications.message_key and pass variables in message_args.
+
+friend_request =
+    .title = New friend request
+    .body = { $actor_name } wants to be your friend
+
+friend_request_accepted =
+    .title = Friend request accepted
+    .body = { $actor_name } accepted your friend request
+
+activity_invite =
+    .title = You're invited!
+    .body = { $actor_name } invited you to { $activity_title }
+
+activity_reminder =
+    .title = Starting soon
+    .body = { $activity_title } starts in { $minutes ->
+        [one] one minute
+       *[other] { $minutes } minutes
+    }
+
+activity_cancelled =
+    .title = Activity cancelled
+    .body = { $activity_title } has been cancelled
+
+new_message =
+    .title = { $actor_name }
+    .body = { $preview }
+
+comment_reply =
+    .title = New reply
+    .body = { $actor_name } replied to your comment
@@ -0,0 +1,32 @@
+# Notificatie catalogus - Nederlands
+
+friend_request =
+    .title = Nieuw vriendschapsverzoek
+    .body = { $actor_name } wil je vriend worden
+
+friend_request_accepted =
+    .title = Vriendschapsverzoek geaccepteerd
+    .body = { $actor_name } heeft je vriendschapsverzoek geaccepteerd
+
+activity_invite =
+    .title = Je bent uitgenodigd!
+    .body = { $actor_name } heeft je uitgenodigd voor { $activity_title }
+
+activity_reminder =
+    .title = Begint bijna
+    .body = { $activity_title } begint over { $minutes ->
+        [one] één minuut
+       *[other] { $minutes } minuten
+    }
+
+activity_cancelled =
+    .title = Activiteit geannuleerd
+    .body = { $activity_title } is geannuleerd
+
+new_message =
+    .title = { $actor_name }
+    .body = { $preview }
+
+comment_reply =
+    .title = Nieuwe reactie
+    .body = { $actor_name } heeft op je reactie gereageerd
@@ -0,0 +1,157 @@
+//! Localization of notification text with embedded Fluent catalogs.
+//!
+//! Notifications may carry a `message_key` (Fluent message id) + `message_args`
+//! instead of only literal text. The worker renders `.title` / `.body` in the
+//! recipient's locale and falls back to the literal title/message when the key
+//! is unknown or rendering fails.
+
+use crate::models::Notification;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use tracing::{debug, error, trace, warn};
+use unic_langid::LanguageIdentifier;
+
+/// Locale used when the recipient has none or it has no catalog
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Embedded catalogs: (language, source)
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("locales/en.ftl")),
+    ("nl", include_str!("locales/nl.ftl")),
+];
+
+/// Rendered title/body for one locale
+#[derive(Debug, Clone)]
+pub struct LocalizedText {
+    pub title: String,
+    pub body: String,
+}
+
+pub struct Localizer {
+    bundles: HashMap<&'static str, FluentBundle<FluentResource>>,
+}
+
+impl Localizer {
+    /// Load all embedded catalogs (invalid entries are logged and skipped)
+    pub fn new() -> Self {
+        let mut bundles = HashMap::new();
+
+        for (lang, source) in CATALOGS {
+            let resource = match FluentResource::try_new(source.to_string()) {
+                Ok(resource) => resource,
+                Err((resource, errors)) => {
+                    error!(locale = lang, errors = ?errors, "Fluent catalog has syntax errors");
+                    resource
+                }
+            };
+
+            let langid: LanguageIdentifier = lang.parse().expect("valid built-in locale");
+            let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+            // No Unicode isolation marks: push text is shown verbatim by the OS
+            bundle.set_use_isolating(false);
+            if let Err(errors) = bundle.add_resource(resource) {
+                error!(locale = lang, errors = ?errors, "Failed to add Fluent catalog");
+            }
+
+            bundles.insert(*lang, bundle);
+        }
+
+        debug!(locales = ?bundles.keys().collect::<Vec<_>>(), "Localizer initialized");
+        Self { bundles }
+    }
+
+    /// Render a message in the best matching locale (None = unknown key or render error)
+    pub fn render(
+        &self,
+        key: &str,
+        args: Option<&serde_json::Value>,
+        locale: Option<&str>,
+    ) -> Option<LocalizedText> {
+        let lang = self.resolve_locale(locale);
+        let bundle = self.bundles.get(lang)?;
+
+        let Some(message) = bundle.get_message(key) else {
+            warn!(key = %key, locale = lang, "Unknown message key, using literal text");
+            return None;
+        };
+
+        let fluent_args = args.map(to_fluent_args);
+        let mut errors = Vec::new();
+
+        let mut format = |attribute: &str| -> Option<String> {
+            let pattern = message.get_attribute(attribute)?.value();
+            Some(bundle.format_pattern(pattern, fluent_args.as_ref(), &mut errors).into_owned())
+        };
+
+        let title = format("title");
+        let body = format("body");
+
+        if !errors.is_empty() {
+            warn!(key = %key, locale = lang, errors = ?errors, "Message rendering failed, using literal text");
+            return None;
+        }
+
+        let (title, body) = (title?, body.unwrap_or_default());
+        trace!(key = %key, locale = lang, title = %title, "Message rendered");
+        Some(LocalizedText { title, body })
+    }
+
+    /// The notification with title/message rendered for `locale`
+    ///
+    /// Borrowed as-is when there is no `message_key` or it can't be rendered.
+    pub fn localize<'a>(&self, notification: &'a Notification, locale: Option<&str>) -> Cow<'a, Notification> {
+        let Some(key) = &notification.message_key else {
+            return Cow::Borrowed(notification);
+        };
+
+        match self.render(key, notification.message_args.as_ref(), locale) {
+            Some(text) => {
+                let mut localized = notification.clone();
+                localized.title = text.title;
+                localized.message = Some(text.body);
+                Cow::Owned(localized)
+            }
+            None => Cow::Borrowed(notification),
+        }
+    }
+
+    /// Map "nl-BE" / "nl_BE" / "NL" to a loaded catalog, else the default
+    fn resolve_locale(&self, locale: Option<&str>) -> &'static str {
+        let language = locale
+            .and_then(|l| l.split(['-', '_']).next())
+            .map(|l| l.to_lowercase());
+
+        CATALOGS
+            .iter()
+            .map(|(lang, _)| *lang)
+            .find(|lang| language.as_deref() == Some(*lang))
+            .unwrap_or(DEFAULT_LOCALE)
+    }
+}
+
+impl Default for Localizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Convert a JSON object of message args into Fluent arguments
+fn to_fluent_args(args: &serde_json::Value) -> FluentArgs<'static> {
+    let mut fluent_args = FluentArgs::new();
+
+    if let Some(map) = args.as_object() {
+        for (name, value) in map {
+            let value: FluentValue<'static> = match value {
+                serde_json::Value::Number(n) => n.as_f64().map(FluentValue::from).unwrap_or(FluentValue::None),
+                serde_json::Value::String(s) => FluentValue::from(s.clone()),
+                serde_json::Value::Null => FluentValue::None,
+                other => FluentValue::from(other.to_string()),
+            };
+            fluent_args.set(name.clone(), value);
+        }
+    }
+
+    fluent_args
+}
@@ -0,0 +1,195 @@
+//! Backpressure on the ingestion endpoints (BACKPRESSURE_MAX_DEPTH / BACKPRESSURE_MAX_AGE_SECS)
+//!
+//! While the due backlog is deeper or older than its limit, the create API (REST and gRPC)
+//! and webhook ingestion refuse notifications below BACKPRESSURE_MIN_PRIORITY with a 429
+//! and Retry-After, so what is still accepted isn't queued behind a flood. The backlog is read
+//! at most every [`SAMPLE_INTERVAL`] per instance, and only for notifications that could be
+//! refused; when it can't be read everything is accepted. One request reads it while the
+//! others go on with the previous reading, and the reading stays cheap however big the
+//! backlog gets: the count stops past BACKPRESSURE_MAX_DEPTH and the age is the oldest row
+//! of `idx_notifications_due`. Queue sources (Kafka, NATS, SQS) aren't checked: they
+//! already slow down with the worker.
+
+use crate::config::Config;
+use crate::db::MaintenanceQueries;
+use crate::models::NewNotification;
+use chrono::Utc;
+use sqlx::PgPool;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Least to most urgent
+const PRIORITIES: [&str; 4] = ["low", "normal", "high", "critical"];
+/// How long one backlog reading is used
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct Backpressure {
+    /// Due notifications (0 = no limit)
+    max_depth: i64,
+    /// Seconds the oldest due notification has waited (0 = no limit)
+    max_age_secs: i64,
+    /// Least urgent priority still accepted while overloaded
+    min_priority: &'static str,
+    retry_after: Duration,
+    sample: Mutex<Option<Sample>>,
+    /// A request is reading the backlog; the others use the last sample meanwhile
+    refreshing: AtomicBool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    taken: Instant,
+    depth: i64,
+    oldest_age_secs: i64,
+    overloaded: bool,
+}
+
+/// The queue is backed up and the notification isn't urgent enough; retry after `retry_after`
+#[derive(Debug, Clone)]
+pub struct Overloaded {
+    /// Least urgent priority still accepted
+    pub min_priority: &'static str,
+    pub retry_after: Duration,
+    /// Due notifications at the last reading (counted up to BACKPRESSURE_MAX_DEPTH + 1)
+    pub depth: i64,
+    pub oldest_age_secs: i64,
+}
+
+impl fmt::Display for Overloaded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Delivery queue is backed up ({} due, oldest {}s), only {} priority and up is accepted, retry in {}s",
+            self.depth,
+            self.oldest_age_secs,
+            self.min_priority,
+            self.retry_after.as_secs().max(1)
+        )
+    }
+}
+
+impl std::error::Error for Overloaded {}
+
+/// Position in PRIORITIES (unknown ones count as normal)
+fn rank(priority: &str) -> usize {
+    PRIORITIES.iter().position(|p| *p == priority).unwrap_or(1)
+}
+
+impl Backpressure {
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        let min_priority = config.backpressure_min_priority.trim().to_lowercase();
+        let min_priority = PRIORITIES.into_iter().find(|p| *p == min_priority).ok_or_else(|| {
+            format!(
+                "BACKPRESSURE_MIN_PRIORITY must be low, normal, high or critical, got '{}'",
+                config.backpressure_min_priority
+            )
+        })?;
+        let backpressure = Self {
+            max_depth: config.backpressure_max_depth,
+            max_age_secs: config.backpressure_max_age_secs,
+            min_priority,
+            retry_after: Duration::from_secs(config.backpressure_retry_after_secs.max(1)),
+            sample: Mutex::new(None),
+            refreshing: AtomicBool::new(false),
+        };
+        if backpressure.is_enabled() {
+            info!(
+                max_depth = backpressure.max_depth,
+                max_age_secs = backpressure.max_age_secs,
+                min_priority,
+                "Ingestion backpressure enabled"
+            );
+        }
+        Ok(backpressure)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_depth > 0 || self.max_age_secs > 0
+    }
+
+    /// Refuse `notification` while the backlog is over a limit, unless it is urgent enough
+    pub async fn check(&self, pool: &PgPool, notification: &NewNotification) -> Result<(), Overloaded> {
+        // OTP codes count as critical
+        let priority = notification.stored_priority().unwrap_or("normal");
+        if !self.is_enabled() || rank(priority) >= rank(self.min_priority) {
+            return Ok(());
+        }
+        let Some(sample) = self.sample(pool).await else {
+            return Ok(());
+        };
+        if !sample.overloaded {
+            return Ok(());
+        }
+
+        metrics::counter!("notifications_backpressure_rejected_total", "priority" => PRIORITIES[rank(priority)])
+            .increment(1);
+        Err(Overloaded {
+            min_priority: self.min_priority,
+            retry_after: self.retry_after,
+            depth: sample.depth,
+            oldest_age_secs: sample.oldest_age_secs,
+        })
+    }
+
+    /// The last backlog reading, read again once it is older than SAMPLE_INTERVAL
+    ///
+    /// No lock is held across the query: while one request reads the backlog, the others
+    /// get the previous reading (None before the first one, which accepts everything).
+    async fn sample(&self, pool: &PgPool) -> Option<Sample> {
+        let last = *self.sample.lock().expect("backpressure lock poisoned");
+        if last.is_some_and(|sample| sample.taken.elapsed() < SAMPLE_INTERVAL) {
+            return last;
+        }
+        if self.refreshing.swap(true, Ordering::AcqRel) {
+            return last;
+        }
+        // Cleared however this ends, including the request being dropped mid-query
+        let _refreshing = Refreshing(&self.refreshing);
+
+        // One past the limit is enough to know it is exceeded
+        let depth_limit = if self.max_depth > 0 { self.max_depth + 1 } else { 0 };
+        let health = match MaintenanceQueries::backlog_health(pool, depth_limit).await {
+            Ok(health) => health,
+            Err(e) => {
+                warn!(error = %e, "Failed to read the backlog - backpressure skipped");
+                return None;
+            }
+        };
+        let depth = health.depth;
+        let oldest_age_secs = health
+            .oldest_due_at
+            .map(|due_at| (Utc::now() - due_at).num_seconds().max(0))
+            .unwrap_or(0);
+        let overloaded = (self.max_depth > 0 && depth > self.max_depth)
+            || (self.max_age_secs > 0 && oldest_age_secs > self.max_age_secs);
+
+        let was_overloaded = last.is_some_and(|sample| sample.overloaded);
+        if overloaded && !was_overloaded {
+            warn!(
+                depth,
+                oldest_age_secs,
+                min_priority = self.min_priority,
+                "⏸ Backlog over its limit, shedding non-urgent notifications"
+            );
+        } else if !overloaded && was_overloaded {
+            info!(depth, oldest_age_secs, "▶ Backlog back within its limits, accepting all notifications");
+        }
+        metrics::gauge!("notifications_backpressure_active").set(if overloaded { 1.0 } else { 0.0 });
+
+        let sample = Sample { taken: Instant::now(), depth, oldest_age_secs, overloaded };
+        *self.sample.lock().expect("backpressure lock poisoned") = Some(sample);
+        Some(sample)
+    }
+}
+
+/// Holds [`Backpressure::refreshing`] for one backlog reading
+struct Refreshing<'a>(&'a AtomicBool);
+
+impl Drop for Refreshing<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
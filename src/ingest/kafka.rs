@@ -0,0 +1,148 @@
+//! Kafka ingestion source (feature `kafka`).
+//!
+//! Consumes JSON notifications from a topic with a consumer group. Offsets are
+//! committed only after the row is inserted (or the message is dead-lettered),
+//! so a crash means redelivery, never loss. Send an `id` for idempotency.
+
+use super::{ingest_json, IngestError};
+use crate::config::KafkaConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::error::KafkaError;
+use rdkafka::message::{BorrowedMessage, Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use rdkafka::{ClientConfig, Message};
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument, trace, warn};
+
+/// Max backoff while the database is unavailable
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+pub struct KafkaSource {
+    pool: PgPool,
+    consumer: StreamConsumer,
+    producer: FutureProducer,
+    topic: String,
+    dlq_topic: String,
+}
+
+impl KafkaSource {
+    pub fn new(config: &KafkaConfig, pool: PgPool) -> Result<Self, KafkaError> {
+        debug!(
+            brokers = %config.brokers,
+            topic = %config.topic,
+            group_id = %config.group_id,
+            dlq_topic = %config.dlq_topic,
+            "Creating KafkaSource"
+        );
+
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("group.id", &config.group_id)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()?;
+        consumer.subscribe(&[config.topic.as_str()])?;
+
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("message.timeout.ms", "10000")
+            .create()?;
+
+        Ok(Self {
+            pool,
+            consumer,
+            producer,
+            topic: config.topic.clone(),
+            dlq_topic: config.dlq_topic.clone(),
+        })
+    }
+
+    /// Consume forever (messages within a partition are handled in order)
+    #[instrument(skip(self), name = "kafka_source", fields(topic = %self.topic))]
+    pub async fn run(&self) {
+        info!(topic = %self.topic, dlq_topic = %self.dlq_topic, "Kafka source started");
+
+        loop {
+            match self.consumer.recv().await {
+                Ok(message) => self.handle(&message).await,
+                Err(e) => {
+                    error!(error = %e, "Kafka receive failed");
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+
+    /// Ingest one message, retrying transient errors until it is stored or dead-lettered
+    async fn handle(&self, message: &BorrowedMessage<'_>) {
+        trace!(
+            partition = message.partition(),
+            offset = message.offset(),
+            "Kafka message received"
+        );
+
+        let payload = message.payload().unwrap_or_default();
+        let mut backoff = Duration::from_millis(500);
+
+        loop {
+            let stored = match ingest_json(&self.pool, payload).await {
+                Ok(id) => {
+                    debug!(id = %id, offset = message.offset(), "✓ Kafka message ingested");
+                    true
+                }
+                Err(IngestError::Invalid(reason)) => {
+                    warn!(
+                        partition = message.partition(),
+                        offset = message.offset(),
+                        reason = %reason,
+                        "✗ Invalid notification, sending to DLQ"
+                    );
+                    self.dead_letter(message, &reason).await
+                }
+                Err(e @ (IngestError::Database(_) | IngestError::RateLimited(_) | IngestError::Closed)) => {
+                    warn!(
+                        offset = message.offset(),
+                        error = %e,
+                        retry_in_ms = backoff.as_millis() as u64,
+                        "Kafka ingest failed, retrying"
+                    );
+                    false
+                }
+            };
+
+            if stored {
+                if let Err(e) = self.consumer.commit_message(message, CommitMode::Async) {
+                    error!(error = %e, offset = message.offset(), "Failed to commit Kafka offset");
+                }
+                return;
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+        }
+    }
+
+    /// Forward a rejected message to the DLQ topic (original key + payload, reason in a header)
+    async fn dead_letter(&self, message: &BorrowedMessage<'_>, reason: &str) -> bool {
+        let headers = OwnedHeaders::new()
+            .insert(Header { key: "error", value: Some(reason) })
+            .insert(Header { key: "source_topic", value: Some(self.topic.as_str()) });
+
+        let mut record = FutureRecord::<[u8], [u8]>::to(&self.dlq_topic)
+            .payload(message.payload().unwrap_or_default())
+            .headers(headers);
+        if let Some(key) = message.key() {
+            record = record.key(key);
+        }
+
+        match self.producer.send(record, Timeout::After(Duration::from_secs(10))).await {
+            Ok(_) => true,
+            Err((e, _)) => {
+                error!(error = %e, dlq_topic = %self.dlq_topic, "Failed to publish to DLQ");
+                false
+            }
+        }
+    }
+}
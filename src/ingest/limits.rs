@@ -0,0 +1,111 @@
+//! Guardrails on the create API (`POST /api/v1/notifications` and `/batch`)
+//!
+//! Rejected before anything is inserted, so a pathological producer can't fill the queue
+//! with rows the worker and FCM would choke on. Each limit is CREATE_MAX_*; 0 turns it off.
+
+use crate::config::Config;
+use crate::models::NewNotification;
+use chrono::{Duration as ChronoDuration, Utc};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CreateLimits {
+    /// Characters of `title`
+    pub max_title_chars: usize,
+    /// Serialized bytes of `payload`
+    pub max_payload_bytes: usize,
+    /// Notifications per batch request
+    pub max_batch_size: usize,
+    /// How far ahead `deliver_at` / `deliver_at_local` may be
+    pub max_schedule_days: i64,
+}
+
+/// One limit exceeded: the 422 body
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitExceeded {
+    pub error: String,
+    /// title | payload | notifications | deliver_at | deliver_at_local
+    pub field: &'static str,
+    pub limit: u64,
+    pub actual: u64,
+    /// Position in the batch (absent for single creates)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+}
+
+impl LimitExceeded {
+    fn new(field: &'static str, unit: &str, limit: u64, actual: u64) -> Self {
+        metrics::counter!("notifications_create_rejected_total", "field" => field).increment(1);
+        Self {
+            error: format!("{} is over the limit of {} {} ({})", field, limit, unit, actual),
+            field,
+            limit,
+            actual,
+            index: None,
+        }
+    }
+
+    /// The same rejection for the notification at `index` of a batch
+    pub fn at(mut self, index: usize) -> Self {
+        self.error = format!("notifications[{}]: {}", index, self.error);
+        self.index = Some(index);
+        self
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.error)
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+impl CreateLimits {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_title_chars: config.create_max_title_chars,
+            max_payload_bytes: config.create_max_payload_bytes,
+            max_batch_size: config.create_max_batch_size,
+            max_schedule_days: config.create_max_schedule_days,
+        }
+    }
+
+    pub fn check(&self, notification: &NewNotification) -> Result<(), LimitExceeded> {
+        if self.max_title_chars > 0 {
+            let chars = notification.title.chars().count();
+            if chars > self.max_title_chars {
+                return Err(LimitExceeded::new("title", "characters", self.max_title_chars as u64, chars as u64));
+            }
+        }
+        if let Some(payload) = notification.payload.as_ref().filter(|_| self.max_payload_bytes > 0) {
+            let bytes = serde_json::to_vec(payload).map(|b| b.len()).unwrap_or(0);
+            if bytes > self.max_payload_bytes {
+                return Err(LimitExceeded::new("payload", "bytes", self.max_payload_bytes as u64, bytes as u64));
+            }
+        }
+        if self.max_schedule_days > 0 {
+            let horizon = Utc::now() + ChronoDuration::days(self.max_schedule_days);
+            // A local time is compared as if it were UTC: off by at most a day either way
+            let scheduled = match (notification.deliver_at, notification.deliver_at_local) {
+                (Some(at), _) => Some(("deliver_at", at)),
+                (None, Some(local)) => Some(("deliver_at_local", local.and_utc())),
+                (None, None) => None,
+            };
+            if let Some((field, at)) = scheduled.filter(|(_, at)| *at > horizon) {
+                let days = (at - Utc::now()).num_days();
+                return Err(LimitExceeded::new(field, "days ahead", self.max_schedule_days as u64, days as u64));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject a batch before looking at its notifications
+    pub fn check_batch(&self, size: usize) -> Result<(), LimitExceeded> {
+        if self.max_batch_size > 0 && size > self.max_batch_size {
+            return Err(LimitExceeded::new("notifications", "per batch", self.max_batch_size as u64, size as u64));
+        }
+        Ok(())
+    }
+}
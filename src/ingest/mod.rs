@@ -0,0 +1,132 @@
+//! Ingestion sources: alternatives to producers INSERTing into Postgres directly.
+//!
+//! Every source decodes a message into a [`NewNotification`], validates it (including
+//! the payload contract of its type) and inserts it into `activity.notifications`. The NOTIFY trigger then wakes the
+//! worker, so delivery goes through exactly the same pipeline as direct inserts.
+//! Sources that also emit delivery events subscribe to the worker's event channel.
+
+pub mod backpressure;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod limits;
+#[cfg(feature = "nats")]
+pub mod nats;
+pub mod rate_limit;
+#[cfg(feature = "sqs")]
+pub mod sqs;
+pub mod webhook;
+
+use crate::contracts::{self, ContractError};
+use crate::db::tenants::DEFAULT_TENANT;
+use crate::db::{MaintenanceQueries, NotificationQueries, TenantQueries};
+use crate::models::{CloudEvent, NewNotification};
+use rate_limit::QuotaExceeded;
+use sqlx::PgPool;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub enum IngestError {
+    /// Message can never be accepted (bad JSON, failed validation) - dead-letter it
+    Invalid(String),
+    /// Transient failure - retry the same message
+    Database(sqlx::Error),
+    /// Tenant quota used up - retry after the window resets
+    RateLimited(QuotaExceeded),
+    /// The environment is draining (`notifications-service drain`) - keep the message for the next one
+    Closed,
+}
+
+impl std::fmt::Display for IngestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IngestError::Invalid(e) => write!(f, "Invalid notification: {}", e),
+            IngestError::Database(e) => write!(f, "Database error: {}", e),
+            IngestError::RateLimited(e) => write!(f, "{}", e),
+            IngestError::Closed => write!(f, "Ingestion is closed (environment draining)"),
+        }
+    }
+}
+
+/// Decode, validate and insert a JSON notification - returns the notification id
+///
+/// Accepts a plain notification object or a structured-mode CloudEvent wrapping one.
+pub async fn ingest_json(pool: &PgPool, bytes: &[u8]) -> Result<Uuid, IngestError> {
+    let value: serde_json::Value = serde_json::from_slice(bytes)
+        .map_err(|e| IngestError::Invalid(format!("Malformed JSON: {}", e)))?;
+
+    let notification = if CloudEvent::is_structured(&value) {
+        let event: CloudEvent = serde_json::from_value(value)
+            .map_err(|e| IngestError::Invalid(format!("Malformed CloudEvent: {}", e)))?;
+        from_cloudevent(event)?
+    } else {
+        serde_json::from_value(value).map_err(|e| IngestError::Invalid(format!("Invalid notification: {}", e)))?
+    };
+
+    ingest(pool, &notification).await
+}
+
+/// Map a CloudEvent onto a notification: `data` holds the notification fields,
+/// id/source/type/time fill in what `data` leaves out
+pub fn from_cloudevent(event: CloudEvent) -> Result<NewNotification, IngestError> {
+    if event.specversion != CloudEvent::SPEC_VERSION {
+        return Err(IngestError::Invalid(format!("Unsupported specversion '{}'", event.specversion)));
+    }
+
+    let id = event.notification_id();
+    let mut data = match event.data {
+        Some(serde_json::Value::Object(data)) => data,
+        _ => return Err(IngestError::Invalid("CloudEvent data must be a JSON object".to_string())),
+    };
+    data.entry("notification_type")
+        .or_insert_with(|| serde_json::Value::String(event.event_type.clone()));
+
+    let mut notification: NewNotification = serde_json::from_value(serde_json::Value::Object(data))
+        .map_err(|e| IngestError::Invalid(format!("Invalid notification in CloudEvent data: {}", e)))?;
+
+    notification.id.get_or_insert(id);
+    notification.event_source = Some(event.source);
+    notification.event_time = event.time;
+    Ok(notification)
+}
+
+/// Validate and insert a notification - returns the notification id
+pub async fn ingest(pool: &PgPool, notification: &NewNotification) -> Result<Uuid, IngestError> {
+    notification.validate().map_err(|e| IngestError::Invalid(e.to_string()))?;
+    if MaintenanceQueries::ingestion_closed(pool).await.map_err(IngestError::Database)? {
+        return Err(IngestError::Closed);
+    }
+    match contracts::check(pool, &notification.notification_type, notification.payload.as_ref()).await {
+        Ok(()) => {}
+        Err(e @ ContractError::Violation { .. }) => return Err(IngestError::Invalid(e.to_string())),
+        Err(ContractError::Database(e)) => return Err(IngestError::Database(e)),
+    }
+
+    // Unknown or disabled tenants would never get their own credentials/topics
+    let tenant_id = notification.tenant_id.as_deref().unwrap_or(DEFAULT_TENANT);
+    let rate_limit = match TenantQueries::find(pool, tenant_id).await.map_err(IngestError::Database)? {
+        Some(tenant) if tenant.enabled || tenant_id == DEFAULT_TENANT => tenant.rate_limit_per_minute,
+        Some(_) => return Err(IngestError::Invalid(format!("Tenant '{}' is disabled", tenant_id))),
+        None if tenant_id == DEFAULT_TENANT => None,
+        None => return Err(IngestError::Invalid(format!("Unknown tenant '{}'", tenant_id))),
+    };
+
+    if let Some(limit) = rate_limit {
+        rate_limit::check("tenant", &format!("tenant:{}", tenant_id), limit)
+            .await
+            .map_err(IngestError::RateLimited)?;
+    }
+
+    let id = notification.id.unwrap_or_else(Uuid::now_v7);
+    let created = NotificationQueries::insert(pool, id, notification)
+        .await
+        .map_err(IngestError::Database)?;
+
+    if created {
+        debug!(id = %id, user_id = %notification.recipient(), "Notification ingested");
+    } else {
+        warn!(id = %id, "Duplicate notification ignored");
+    }
+
+    Ok(id)
+}
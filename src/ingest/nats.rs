@@ -0,0 +1,154 @@
+//! NATS JetStream ingestion + delivery event output (feature `nats`).
+//!
+//! Ingest: a durable pull consumer on `notifications.create` (explicit acks).
+//! Output: every worker delivery event is published on
+//! `notifications.<status>[.<channel>]`, e.g. `notifications.delivered.push`,
+//! as plain JSON, CloudEvents or protobuf (NATS_EVENTS_FORMAT).
+
+use super::{ingest_json, IngestError};
+use crate::config::{EventFormat, NatsConfig};
+use crate::worker::events::DeliveryEvent;
+use async_nats::jetstream::{self, consumer::pull, consumer::AckPolicy, AckKind};
+use futures::StreamExt;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, error, info, instrument, trace, warn};
+
+/// Redelivery delay when the database is unavailable
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+pub type NatsError = Box<dyn std::error::Error + Send + Sync>;
+
+pub struct NatsSource {
+    pool: PgPool,
+    client: async_nats::Client,
+    config: NatsConfig,
+}
+
+impl NatsSource {
+    pub async fn connect(config: &NatsConfig, pool: PgPool) -> Result<Self, NatsError> {
+        debug!(url = %config.url, stream = %config.stream, "Connecting to NATS");
+        let client = async_nats::connect(&config.url).await?;
+
+        Ok(Self {
+            pool,
+            client,
+            config: config.clone(),
+        })
+    }
+
+    /// Core client (shared by the event publisher)
+    pub fn client(&self) -> async_nats::Client {
+        self.client.clone()
+    }
+
+    /// Consume the create subject forever
+    #[instrument(skip(self), name = "nats_source", fields(subject = %self.config.create_subject))]
+    pub async fn run(&self) -> Result<(), NatsError> {
+        let js = jetstream::new(self.client.clone());
+
+        let stream = js
+            .get_or_create_stream(jetstream::stream::Config {
+                name: self.config.stream.clone(),
+                subjects: vec![self.config.create_subject.clone()],
+                ..Default::default()
+            })
+            .await?;
+
+        let consumer = stream
+            .get_or_create_consumer(
+                &self.config.durable,
+                pull::Config {
+                    durable_name: Some(self.config.durable.clone()),
+                    ack_policy: AckPolicy::Explicit,
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        info!(
+            stream = %self.config.stream,
+            subject = %self.config.create_subject,
+            durable = %self.config.durable,
+            "NATS ingestion started"
+        );
+
+        let mut messages = consumer.messages().await?;
+        while let Some(message) = messages.next().await {
+            let message = match message {
+                Ok(message) => message,
+                Err(e) => {
+                    warn!(error = %e, "NATS receive failed");
+                    continue;
+                }
+            };
+            trace!(subject = %message.subject, "NATS message received");
+
+            let ack = match ingest_json(&self.pool, &message.payload).await {
+                Ok(id) => {
+                    debug!(id = %id, "✓ NATS message ingested");
+                    AckKind::Ack
+                }
+                Err(IngestError::Invalid(reason)) => {
+                    // Never redeliver a message that can't be parsed or validated
+                    warn!(reason = %reason, "✗ Invalid notification, terminating message");
+                    AckKind::Term
+                }
+                Err(e @ (IngestError::Database(_) | IngestError::Closed)) => {
+                    warn!(error = %e, "NATS ingest failed, requesting redelivery");
+                    AckKind::Nak(Some(RETRY_DELAY))
+                }
+                Err(IngestError::RateLimited(e)) => {
+                    debug!(error = %e, "NATS message rate limited, requesting redelivery");
+                    AckKind::Nak(Some(e.quota.reset_after.max(RETRY_DELAY)))
+                }
+            };
+
+            if let Err(e) = message.ack_with(ack).await {
+                error!(error = %e, "Failed to ack NATS message");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Publish worker delivery events until the channel closes
+pub async fn publish_events(
+    client: async_nats::Client,
+    config: NatsConfig,
+    mut events: broadcast::Receiver<DeliveryEvent>,
+) {
+    let prefix = config.events_prefix;
+    info!(prefix = %prefix, format = ?config.events_format, "NATS delivery event publisher started");
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!(skipped = skipped, "NATS event publisher lagging, events dropped");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let subject = format!("{}.{}", prefix, event.routing_key());
+        let payload = match config.events_format {
+            EventFormat::Json => serde_json::to_vec(&event),
+            EventFormat::CloudEvents => serde_json::to_vec(&event.to_cloudevent(&prefix)),
+            EventFormat::Protobuf => Ok(crate::grpc::encode_delivery_event(&event)),
+        };
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!(error = %e, "Failed to serialize delivery event");
+                continue;
+            }
+        };
+
+        if let Err(e) = client.publish(subject.clone(), payload.into()).await {
+            warn!(subject = %subject, error = %e, "Failed to publish delivery event");
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use super::{Hit, LimiterError, LimiterStore};
+use axum::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Prune expired windows once the map grows beyond this many keys
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Windows kept in this process (the default, and the fallback while Redis is down)
+#[derive(Default)]
+pub struct MemoryLimiterStore {
+    windows: Mutex<HashMap<String, Window>>,
+}
+
+struct Window {
+    started: Instant,
+    length: Duration,
+    count: u32,
+}
+
+impl MemoryLimiterStore {
+    pub fn count(&self, key: &str, window: Duration, hits: u32, limit: u32) -> Hit {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().expect("rate limiter lock poisoned");
+
+        if windows.len() > PRUNE_THRESHOLD {
+            windows.retain(|_, w| now.duration_since(w.started) < w.length);
+        }
+
+        let live = windows.get(key).filter(|w| now.duration_since(w.started) < w.length);
+        let count = live.map_or(0, |w| w.count);
+        if count.saturating_add(hits) > limit {
+            // Refused: nothing is counted, and a window that hasn't started stays that way
+            return Hit {
+                admitted: false,
+                count,
+                reset_after: live.map_or(Duration::ZERO, |w| w.length.saturating_sub(now.duration_since(w.started))),
+            };
+        }
+
+        let current = windows.entry(key.to_string()).or_insert(Window {
+            started: now,
+            length: window,
+            count: 0,
+        });
+        if now.duration_since(current.started) >= current.length {
+            current.started = now;
+            current.length = window;
+            current.count = 0;
+        }
+        current.count += hits;
+
+        Hit {
+            admitted: true,
+            count: current.count,
+            reset_after: current.length.saturating_sub(now.duration_since(current.started)),
+        }
+    }
+}
+
+#[async_trait]
+impl LimiterStore for MemoryLimiterStore {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    async fn hit(&self, key: &str, window: Duration, hits: u32, limit: u32) -> Result<Hit, LimiterError> {
+        Ok(self.count(key, window, hits, limit))
+    }
+}
@@ -0,0 +1,157 @@
+//! Per-tenant and per-API-key creation quotas (notifications per minute).
+//!
+//! Fixed one-minute windows, counted in a [`LimiterStore`]. Only admitted notifications
+//! count: a refused request (or batch) leaves the window as it was. By default that is
+//! [`MemoryLimiterStore`], per service instance: with N replicas behind a load balancer
+//! the effective quota is up to N times the configured one. With LIMITER_REDIS_URL
+//! (feature `redis`) all replicas count in [`RedisLimiterStore`], so the quota holds
+//! cluster-wide; while Redis is unreachable each instance counts locally again.
+//! Limits come from `tenants.rate_limit_per_minute` and `api_keys.rate_limit_per_minute`
+//! (NULL = unlimited).
+
+pub mod memory;
+#[cfg(feature = "redis")]
+pub mod redis;
+
+pub use memory::MemoryLimiterStore;
+#[cfg(feature = "redis")]
+pub use self::redis::RedisLimiterStore;
+
+use crate::config::Config;
+use axum::async_trait;
+use std::sync::{Arc, LazyLock, OnceLock};
+use std::time::Duration;
+use tracing::warn;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Shared store (LIMITER_REDIS_URL), None = count in this instance
+static STORE: OnceLock<Arc<dyn LimiterStore>> = OnceLock::new();
+static LOCAL: LazyLock<MemoryLimiterStore> = LazyLock::new(MemoryLimiterStore::default);
+
+/// Where the quota windows are counted
+#[async_trait]
+pub trait LimiterStore: Send + Sync {
+    /// Metrics and log label (`memory`, `redis`)
+    fn name(&self) -> &str;
+
+    /// Count `hits` against `key` in its current window (started by the first admitted
+    /// hit), unless that would take the window over `limit`
+    async fn hit(&self, key: &str, window: Duration, hits: u32, limit: u32) -> Result<Hit, LimiterError>;
+}
+
+/// A window after trying to count hits
+#[derive(Debug, Clone, Copy)]
+pub struct Hit {
+    /// The hits fit under the limit and were counted
+    pub admitted: bool,
+    /// Hits in the window, including these if admitted
+    pub count: u32,
+    /// Time until the window resets
+    pub reset_after: Duration,
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Limiter store failed: {0}")]
+pub struct LimiterError(pub String);
+
+/// Quota state after an accepted notification
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time until the window resets
+    pub reset_after: Duration,
+}
+
+/// A quota was used up; retry after `quota.reset_after`
+#[derive(Debug, Clone)]
+pub struct QuotaExceeded {
+    /// `tenant:<id>` or `api_key:<id>`
+    pub key: String,
+    pub quota: Quota,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Rate limit of {}/minute exceeded for {}, retry in {}s",
+            self.quota.limit,
+            self.key,
+            self.quota.reset_after.as_secs().max(1)
+        )
+    }
+}
+
+/// Use the store configured by LIMITER_REDIS_URL (no-op if one is installed already)
+pub fn install(config: &Config) -> Result<(), String> {
+    if STORE.get().is_some() {
+        return Ok(());
+    }
+    match &config.limiter_redis_url {
+        #[cfg(feature = "redis")]
+        Some(url) => {
+            let store = RedisLimiterStore::new(url, &config.limiter_redis_prefix)?;
+            tracing::info!(prefix = %config.limiter_redis_prefix, "Rate limits counted in Redis (cluster-wide)");
+            let _ = STORE.set(Arc::new(store));
+        }
+        #[cfg(not(feature = "redis"))]
+        Some(_) => warn!("LIMITER_REDIS_URL set but built without the 'redis' feature - rate limits are per instance"),
+        None => {}
+    }
+    Ok(())
+}
+
+/// Count one notification against `key`; `kind` is the metrics label (tenant/api_key)
+pub async fn check(kind: &'static str, key: &str, limit: i32) -> Result<Quota, QuotaExceeded> {
+    check_many(kind, key, limit, 1).await
+}
+
+/// Count `hits` notifications against `key` at once, all admitted or all refused
+pub async fn check_many(kind: &'static str, key: &str, limit: i32, hits: u32) -> Result<Quota, QuotaExceeded> {
+    check_in(STORE.get().map(|store| store.as_ref()), kind, key, limit, hits).await
+}
+
+/// [`check_many`] against `store`, falling back to this instance's windows when it fails (None = only those)
+pub async fn check_in(
+    store: Option<&dyn LimiterStore>,
+    kind: &'static str,
+    key: &str,
+    limit: i32,
+    hits: u32,
+) -> Result<Quota, QuotaExceeded> {
+    let limit = limit.max(0) as u32;
+    let hit = match store {
+        Some(store) => match store.hit(key, WINDOW, hits, limit).await {
+            Ok(hit) => hit,
+            Err(e) => {
+                warn!(store = store.name(), error = %e, "Limiter store unavailable, counting in this instance");
+                metrics::counter!("notifications_limiter_store_errors_total", "store" => store.name().to_string())
+                    .increment(1);
+                LOCAL.count(key, WINDOW, hits, limit)
+            }
+        },
+        None => LOCAL.count(key, WINDOW, hits, limit),
+    };
+
+    if !hit.admitted {
+        metrics::counter!("notifications_rate_limited_total", "kind" => kind).increment(hits as u64);
+        warn!(key = %key, limit, hits, "⏸ Rate limit exceeded");
+        return Err(QuotaExceeded {
+            key: key.to_string(),
+            quota: Quota {
+                limit,
+                remaining: limit.saturating_sub(hit.count),
+                reset_after: hit.reset_after,
+            },
+        });
+    }
+
+    metrics::counter!("notifications_quota_accepted_total", "kind" => kind).increment(hits as u64);
+    Ok(Quota {
+        limit,
+        remaining: limit - hit.count,
+        reset_after: hit.reset_after,
+    })
+}
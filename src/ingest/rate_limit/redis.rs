@@ -0,0 +1,73 @@
+use super::{Hit, LimiterError, LimiterStore};
+use ::redis::aio::ConnectionManager;
+use ::redis::{Client, Script};
+use axum::async_trait;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+/// INCRBY unless that goes over the limit, starting the window on the first admitted hit,
+/// in one round trip; returns {admitted, count, ms left}
+const HIT_SCRIPT: &str = r#"
+local count = tonumber(redis.call('GET', KEYS[1]) or '0')
+if count + tonumber(ARGV[2]) > tonumber(ARGV[3]) then
+    return {0, count, math.max(redis.call('PTTL', KEYS[1]), 0)}
+end
+count = redis.call('INCRBY', KEYS[1], ARGV[2])
+local ttl = redis.call('PTTL', KEYS[1])
+if ttl < 0 then
+    redis.call('PEXPIRE', KEYS[1], ARGV[1])
+    ttl = tonumber(ARGV[1])
+end
+return {1, count, ttl}
+"#;
+
+/// Windows shared by all replicas (LIMITER_REDIS_URL), one key per quota that expires with its window
+pub struct RedisLimiterStore {
+    client: Client,
+    /// Connected on first use, reconnects by itself afterwards
+    connection: OnceCell<ConnectionManager>,
+    prefix: String,
+    script: Script,
+}
+
+impl RedisLimiterStore {
+    pub fn new(url: &str, prefix: &str) -> Result<Self, String> {
+        let client = Client::open(url).map_err(|e| format!("Invalid LIMITER_REDIS_URL: {}", e))?;
+        Ok(Self {
+            client,
+            connection: OnceCell::new(),
+            prefix: prefix.to_string(),
+            script: Script::new(HIT_SCRIPT),
+        })
+    }
+}
+
+#[async_trait]
+impl LimiterStore for RedisLimiterStore {
+    fn name(&self) -> &str {
+        "redis"
+    }
+
+    async fn hit(&self, key: &str, window: Duration, hits: u32, limit: u32) -> Result<Hit, LimiterError> {
+        let mut connection = self
+            .connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .map_err(|e| LimiterError(e.to_string()))?
+            .clone();
+        let (admitted, count, ttl_ms): (u8, u32, i64) = self
+            .script
+            .key(format!("{}ratelimit:{}", self.prefix, key))
+            .arg(window.as_millis() as u64)
+            .arg(hits)
+            .arg(limit)
+            .invoke_async(&mut connection)
+            .await
+            .map_err(|e| LimiterError(e.to_string()))?;
+        Ok(Hit {
+            admitted: admitted == 1,
+            count,
+            reset_after: Duration::from_millis(ttl_ms.max(0) as u64),
+        })
+    }
+}
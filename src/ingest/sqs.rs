@@ -0,0 +1,139 @@
+//! AWS SQS ingestion source (feature `sqs`).
+//!
+//! Long-polls the queue and batch-deletes messages once their notification is
+//! stored. Anything not deleted becomes visible again after the visibility
+//! timeout: database errors are retried that way, and invalid messages end up
+//! in the queue's dead-letter queue through its redrive policy.
+
+use super::{ingest_json, IngestError};
+use crate::config::SqsConfig;
+use aws_sdk_sqs::types::DeleteMessageBatchRequestEntry;
+use sqlx::PgPool;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, instrument, trace, warn};
+
+pub type SqsError = Box<dyn std::error::Error + Send + Sync>;
+
+pub struct SqsSource {
+    pool: PgPool,
+    client: aws_sdk_sqs::Client,
+    config: SqsConfig,
+}
+
+impl SqsSource {
+    /// Build a client from the default AWS credential chain (env, profile, IRSA, ...)
+    pub async fn new(config: &SqsConfig, pool: PgPool) -> Self {
+        let aws_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+
+        Self {
+            pool,
+            client: aws_sdk_sqs::Client::new(&aws_config),
+            config: config.clone(),
+        }
+    }
+
+    /// Poll forever
+    #[instrument(skip(self), name = "sqs_source", fields(queue = %self.config.queue_url))]
+    pub async fn run(&self) {
+        info!(
+            queue = %self.config.queue_url,
+            visibility_timeout_secs = self.config.visibility_timeout_secs,
+            "SQS ingestion started"
+        );
+
+        loop {
+            if let Err(e) = self.poll_once().await {
+                error!(error = %e, "SQS poll failed");
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    }
+
+    /// One long-poll round: receive, ingest, batch delete what was stored
+    async fn poll_once(&self) -> Result<(), SqsError> {
+        let output = self
+            .client
+            .receive_message()
+            .queue_url(&self.config.queue_url)
+            .max_number_of_messages(self.config.max_messages)
+            .wait_time_seconds(self.config.wait_time_secs)
+            .visibility_timeout(self.config.visibility_timeout_secs)
+            .send()
+            .await?;
+        let received_at = Instant::now();
+
+        let messages = output.messages();
+        if messages.is_empty() {
+            trace!("SQS long poll returned no messages");
+            return Ok(());
+        }
+        debug!(count = messages.len(), "SQS messages received");
+
+        let mut entries = Vec::with_capacity(messages.len());
+        for (i, message) in messages.iter().enumerate() {
+            let body = message.body().unwrap_or_default();
+
+            match ingest_json(&self.pool, body.as_bytes()).await {
+                Ok(id) => {
+                    debug!(id = %id, message_id = ?message.message_id(), "✓ SQS message ingested");
+                    if let Some(receipt_handle) = message.receipt_handle() {
+                        entries.push(
+                            DeleteMessageBatchRequestEntry::builder()
+                                .id(i.to_string())
+                                .receipt_handle(receipt_handle)
+                                .build()?,
+                        );
+                    }
+                }
+                Err(IngestError::Invalid(reason)) => {
+                    warn!(
+                        message_id = ?message.message_id(),
+                        reason = %reason,
+                        "✗ Invalid notification, leaving for the redrive policy"
+                    );
+                }
+                Err(e @ (IngestError::Database(_) | IngestError::RateLimited(_) | IngestError::Closed)) => {
+                    warn!(
+                        message_id = ?message.message_id(),
+                        error = %e,
+                        "SQS ingest failed, message will reappear after visibility timeout"
+                    );
+                }
+            }
+        }
+
+        // Past the visibility timeout another consumer may own these messages:
+        // deleting now could drop a copy that is being processed elsewhere
+        let visibility = Duration::from_secs(self.config.visibility_timeout_secs as u64);
+        if received_at.elapsed() >= visibility {
+            warn!(
+                elapsed_ms = received_at.elapsed().as_millis() as u64,
+                count = entries.len(),
+                "Visibility timeout exceeded, not deleting (duplicates are ignored by id)"
+            );
+            return Ok(());
+        }
+
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let result = self
+            .client
+            .delete_message_batch()
+            .queue_url(&self.config.queue_url)
+            .set_entries(Some(entries))
+            .send()
+            .await?;
+
+        for failed in result.failed() {
+            warn!(
+                entry = %failed.id(),
+                code = %failed.code(),
+                "Failed to delete SQS message (will be redelivered)"
+            );
+        }
+
+        Ok(())
+    }
+}
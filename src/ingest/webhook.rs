@@ -0,0 +1,216 @@
+//! Generic HTTP webhook ingestion: `POST /ingest/webhook/{source}`.
+//!
+//! Each source (Stripe, GitHub, ...) is a row in `activity.webhook_sources` with an
+//! HMAC-SHA256 secret and a mapping from JSON pointers in the webhook body onto
+//! notification fields, so third parties can notify users without glue services.
+//!
+//! The sender signs `{timestamp}.{body}` and sends the Unix timestamp in
+//! `X-Signature-Timestamp`. A request whose timestamp is more than WEBHOOK_TOLERANCE_SECS
+//! away from the clock is refused, and so is a second request with a signature that was
+//! already accepted (`activity.webhook_signatures`), so a captured webhook can't be replayed.
+
+use super::backpressure::Backpressure;
+use super::ingest;
+use crate::api::ApiError;
+use crate::db::WebhookSourceQueries;
+use crate::models::{CloudEvent, NewNotification};
+use crate::mtls::ServiceIdentity;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Extension, Json, Router};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Unix time (seconds) the sender signed the request at
+pub const TIMESTAMP_HEADER: &str = "x-signature-timestamp";
+
+/// How far a request's timestamp may be from the clock (WEBHOOK_TOLERANCE_SECS)
+#[derive(Debug, Clone, Copy)]
+struct Tolerance(Duration);
+
+/// Build the `/ingest` router
+pub fn router(pool: PgPool, backpressure: Arc<Backpressure>, tolerance: Duration) -> Router {
+    Router::new()
+        .route("/webhook/:source", post(receive))
+        .layer(Extension(backpressure))
+        .layer(Extension(Tolerance(tolerance)))
+        .with_state(pool)
+}
+
+/// POST /ingest/webhook/{source}
+async fn receive(
+    State(pool): State<PgPool>,
+    Path(source): Path<String>,
+    identity: Option<Extension<ServiceIdentity>>,
+    Extension(backpressure): Extension<Arc<Backpressure>>,
+    Extension(Tolerance(tolerance)): Extension<Tolerance>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let webhook = WebhookSourceQueries::find_enabled(&pool, &source)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown webhook source '{}'", source)))?;
+
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| ApiError::Unauthorized(format!("Missing {} header", name)))
+    };
+    let signature = header(&webhook.signature_header)?;
+    let timestamp = header(TIMESTAMP_HEADER)?;
+
+    let Some(signature) = verify_signature(webhook.secret.as_bytes(), timestamp, &body, signature) else {
+        warn!(source = %source, "✗ Webhook signature mismatch");
+        return Err(ApiError::Unauthorized("Invalid signature".to_string()));
+    };
+    if !is_fresh(timestamp, tolerance) {
+        warn!(source = %source, timestamp = %timestamp, "✗ Webhook timestamp outside the tolerance");
+        return Err(ApiError::Unauthorized(format!(
+            "{} is more than {}s away from the current time",
+            TIMESTAMP_HEADER,
+            tolerance.as_secs()
+        )));
+    }
+    if !WebhookSourceQueries::remember_signature(&pool, &source, &signature, tolerance).await? {
+        warn!(source = %source, "✗ Webhook replayed");
+        return Err(ApiError::Unauthorized("Signature was already used".to_string()));
+    }
+
+    let accepted = accept(&pool, &source, &webhook.mapping, identity, &backpressure, &body).await;
+    if accepted.is_err() {
+        // Not ingested: the sender may retry with the same signature
+        if let Err(e) = WebhookSourceQueries::forget_signature(&pool, &source, &signature).await {
+            warn!(source = %source, error = %e, "Failed to release webhook signature");
+        }
+    }
+    let (id, user_id) = accepted?;
+
+    info!(source = %source, id = %id, user_id = %user_id, "✓ Webhook ingested");
+    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({ "id": id }))))
+}
+
+/// Map a verified webhook body onto a notification and ingest it; returns (id, recipient)
+async fn accept(
+    pool: &PgPool,
+    source: &str,
+    mapping: &Value,
+    identity: Option<Extension<ServiceIdentity>>,
+    backpressure: &Backpressure,
+    body: &[u8],
+) -> Result<(Uuid, Uuid), ApiError> {
+    let body: Value = serde_json::from_slice(body)
+        .map_err(|e| ApiError::BadRequest(format!("Malformed JSON: {}", e)))?;
+
+    let mut notification = apply_mapping(source, mapping, &body).map_err(ApiError::BadRequest)?;
+
+    // Structured CloudEvent: pointers resolve against the envelope (`/data/...`),
+    // and the envelope provides id/source/time unless the mapping set them
+    if CloudEvent::is_structured(&body) {
+        let event: CloudEvent = serde_json::from_value(body)
+            .map_err(|e| ApiError::BadRequest(format!("Malformed CloudEvent: {}", e)))?;
+        notification.id.get_or_insert_with(|| event.notification_id());
+        notification.event_source.get_or_insert(event.source);
+        if notification.event_time.is_none() {
+            notification.event_time = event.time;
+        }
+    }
+
+    notification.created_by = identity.map(|Extension(identity)| identity.name);
+    backpressure.check(pool, &notification).await?;
+
+    let id = ingest(pool, &notification).await?;
+    Ok((id, notification.recipient()))
+}
+
+/// Check a hex HMAC-SHA256 signature (optionally prefixed with `sha256=`) of
+/// `{timestamp}.{body}` in constant time; returns the signature bytes if it matches
+pub fn verify_signature(secret: &[u8], timestamp: &str, body: &[u8], signature: &str) -> Option<Vec<u8>> {
+    let signature = signature.trim();
+    let signature = decode_hex(signature.strip_prefix("sha256=").unwrap_or(signature))?;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).ok()?;
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac.verify_slice(&signature).ok()?;
+    Some(signature)
+}
+
+/// Whether a Unix timestamp is within `tolerance` of the clock, either way
+fn is_fresh(timestamp: &str, tolerance: Duration) -> bool {
+    timestamp
+        .trim()
+        .parse::<i64>()
+        .is_ok_and(|timestamp| (Utc::now().timestamp() - timestamp).unsigned_abs() <= tolerance.as_secs())
+}
+
+/// Build a notification from a webhook body using the source's mapping
+///
+/// Rules starting with `/` are JSON pointers into the body, other values are
+/// literals. `payload` defaults to the whole body. A non-UUID `id` (e.g. Stripe's
+/// `evt_...`) is turned into a stable UUID so sender retries are deduplicated.
+pub fn apply_mapping(source: &str, mapping: &Value, body: &Value) -> Result<NewNotification, String> {
+    let rules = mapping
+        .as_object()
+        .ok_or_else(|| "Webhook mapping must be a JSON object".to_string())?;
+
+    let mut fields = serde_json::Map::new();
+    for (field, rule) in rules {
+        let value = match rule {
+            Value::String(pointer) if pointer.starts_with('/') => match body.pointer(pointer) {
+                Some(value) => value.clone(),
+                None => {
+                    debug!(source = %source, field = %field, pointer = %pointer, "Mapping pointer not found");
+                    continue;
+                }
+            },
+            literal => literal.clone(),
+        };
+        fields.insert(field.clone(), value);
+    }
+
+    fields.entry("payload").or_insert_with(|| body.clone());
+
+    if let Some(Value::String(id)) = fields.get("id") {
+        if Uuid::parse_str(id).is_err() {
+            let stable = Uuid::new_v5(&Uuid::NAMESPACE_URL, format!("webhook:{}:{}", source, id).as_bytes());
+            fields.insert("id".to_string(), Value::String(stable.to_string()));
+        }
+    }
+
+    serde_json::from_value(Value::Object(fields)).map_err(|e| format!("Mapping produced an invalid notification: {}", e))
+}
+
+/// Validate a mapping before saving it (admin API)
+pub fn validate_mapping(mapping: &Value) -> Result<(), String> {
+    let rules = mapping
+        .as_object()
+        .ok_or_else(|| "mapping must be a JSON object".to_string())?;
+
+    for required in ["user_id", "notification_type"] {
+        if !rules.contains_key(required) {
+            return Err(format!("mapping must define '{}'", required));
+        }
+    }
+    Ok(())
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
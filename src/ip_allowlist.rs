@@ -0,0 +1,111 @@
+//! CIDR allowlists for management (`/api/v1` admin routes and the `/admin` router) and
+//! ingestion (`/ingest`) endpoints - defense in depth for deployments without a service mesh.
+//!
+//! The client address is the TCP peer, or - behind TRUSTED_PROXY_HOPS reverse
+//! proxies - the X-Forwarded-For entry that many hops from the right. Entries
+//! further left are client-controlled and never trusted.
+
+use crate::api::ApiError;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use ipnet::IpNet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tracing::{trace, warn};
+
+#[derive(Debug, Clone)]
+pub struct IpAllowlist {
+    /// Label for logs (`admin`, `ingest`)
+    name: &'static str,
+    networks: Vec<IpNet>,
+    trusted_proxy_hops: usize,
+}
+
+impl IpAllowlist {
+    /// Parse a comma-separated list of CIDRs or single addresses
+    pub fn parse(name: &'static str, cidrs: &str, trusted_proxy_hops: usize) -> Result<Self, String> {
+        let networks = cidrs
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(|c| {
+                c.parse::<IpNet>()
+                    .or_else(|_| c.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| format!("Invalid CIDR '{}' in {} allowlist", c, name))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if networks.is_empty() {
+            return Err(format!("{} allowlist is empty", name));
+        }
+        Ok(Self {
+            name,
+            networks,
+            trusted_proxy_hops,
+        })
+    }
+
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        // IPv4-mapped IPv6 peers (dual-stack sockets) match IPv4 ranges
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            v4 => v4,
+        };
+        self.networks.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Client address for this request (None = undeterminable, e.g. missing XFF)
+    fn client_ip(&self, request: &Request) -> Option<IpAddr> {
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+
+        if self.trusted_proxy_hops == 0 {
+            return peer;
+        }
+
+        let forwarded: Vec<&str> = request
+            .headers()
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .collect();
+        let index = forwarded.len().checked_sub(self.trusted_proxy_hops)?;
+        forwarded[index].parse().ok()
+    }
+}
+
+/// Reject requests from addresses outside the allowlist with 403
+pub async fn enforce(State(allowlist): State<Arc<IpAllowlist>>, request: Request, next: Next) -> Response {
+    match allowlist.client_ip(&request) {
+        Some(ip) if allowlist.allows(ip) => {
+            trace!(allowlist = allowlist.name, ip = %ip, "IP allowed");
+            next.run(request).await
+        }
+        ip => {
+            warn!(
+                allowlist = allowlist.name,
+                ip = ?ip,
+                path = %request.uri().path(),
+                "✗ Request blocked by IP allowlist"
+            );
+            ApiError::Forbidden("Client address not allowed".to_string()).into_response()
+        }
+    }
+}
+
+/// Apply the allowlist (if configured) to every route of `router`
+pub fn protect<S>(router: Router<S>, allowlist: Option<Arc<IpAllowlist>>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    match allowlist {
+        Some(allowlist) => router.route_layer(middleware::from_fn_with_state(allowlist, enforce)),
+        None => router,
+    }
+}
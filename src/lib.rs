@@ -1,6 +1,47 @@
+pub mod analytics;
+pub mod api;
+pub mod archive;
+pub mod attachments;
+pub mod campaigns;
+pub mod chaos;
 pub mod config;
+pub mod contracts;
 pub mod db;
+pub mod dev_sandbox;
+pub mod digest;
+pub mod drain;
+pub mod error;
+pub mod experiments;
+pub mod grpc;
+pub mod i18n;
+pub mod ingest;
+pub mod ip_allowlist;
+pub mod loadgen;
 pub mod models;
+pub mod mtls;
+pub mod otp;
+pub mod payload_sink;
+pub mod policy;
+pub mod protocol;
 pub mod push;
+pub mod queue;
+pub mod realtime;
+pub mod receipts;
+pub mod recurring;
+pub mod reengagement;
+pub mod replay;
+pub mod resend;
+pub mod secrets;
+pub mod seed;
+pub mod service;
+pub mod shadow;
+pub mod signing;
+pub mod slo_alerts;
+pub mod status;
+pub mod targeting;
+pub mod templates;
 pub mod worker;
+
+pub use service::{Service, ServiceBuilder};
+
 // ws module removed - using websocket-bus via bus-client
@@ -0,0 +1,369 @@
+//! `notifications-service loadgen`: synthetic load for capacity planning.
+//!
+//! Inserts notifications at a fixed rate for a pool of synthetic users, each with a
+//! `callback_url` pointing at a receiver started by the load generator. End-to-end
+//! latency is taken from the delivery receipts (`completed_at - created_at`, i.e.
+//! insert to terminal state), so the service under test needs RECEIPT_SIGNING_SECRET.
+//!
+//! ```text
+//! notifications-service loadgen --rate 500/s --users 10k --duration 60s
+//!     [--type loadgen] [--drain 30s] [--listen 127.0.0.1:0] [--callback-host host:port]
+//! ```
+
+use crate::db::NotificationQueries;
+use crate::models::NewNotification;
+use crate::receipts;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+const USAGE: &str = "usage: notifications-service loadgen --rate <n>/s --users <n> --duration <secs> \
+[--type <notification_type>] [--drain <secs>] [--listen <addr>] [--callback-host <host:port>]";
+/// Concurrent inserts (matches the pool size)
+const MAX_IN_FLIGHT: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct LoadgenArgs {
+    /// Notifications per second
+    pub rate: f64,
+    pub users: u64,
+    pub duration: Duration,
+    /// How long to wait for outstanding receipts after the last insert
+    pub drain: Duration,
+    pub notification_type: String,
+    /// Receiver bind address
+    pub listen: String,
+    /// Address the service uses to reach the receiver (default: the bound address)
+    pub callback_host: Option<String>,
+}
+
+impl LoadgenArgs {
+    /// Parse the arguments after `loadgen`
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Self {
+            rate: 100.0,
+            users: 1000,
+            duration: Duration::from_secs(60),
+            drain: Duration::from_secs(30),
+            notification_type: "loadgen".to_string(),
+            listen: "127.0.0.1:0".to_string(),
+            callback_host: None,
+        };
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args.next().ok_or_else(|| format!("{} needs a value\n{}", flag, USAGE))?;
+            match flag.as_str() {
+                "--rate" => parsed.rate = parse_rate(value)?,
+                "--users" => parsed.users = parse_count(value)?,
+                "--duration" => parsed.duration = parse_duration(value)?,
+                "--drain" => parsed.drain = parse_duration(value)?,
+                "--type" => parsed.notification_type = value.clone(),
+                "--listen" => parsed.listen = value.clone(),
+                "--callback-host" => parsed.callback_host = Some(value.clone()),
+                _ => return Err(format!("Unknown option '{}'\n{}", flag, USAGE)),
+            }
+        }
+
+        if parsed.rate <= 0.0 || parsed.users == 0 || parsed.duration.is_zero() {
+            return Err(format!("--rate, --users and --duration must be positive\n{}", USAGE));
+        }
+        Ok(parsed)
+    }
+}
+
+/// `500/s`, `30000/m` or a plain number (per second)
+fn parse_rate(value: &str) -> Result<f64, String> {
+    let (number, per) = match value.split_once('/') {
+        Some((number, "s")) => (number, 1.0),
+        Some((number, "m")) => (number, 60.0),
+        Some(_) => return Err(format!("Invalid rate '{}' (expected <n>/s or <n>/m)", value)),
+        None => (value, 1.0),
+    };
+    number
+        .parse::<f64>()
+        .map(|n| n / per)
+        .map_err(|_| format!("Invalid rate '{}'", value))
+}
+
+/// `10k`, `1m` or a plain number
+fn parse_count(value: &str) -> Result<u64, String> {
+    let (number, multiplier) = match value.to_ascii_lowercase() {
+        v if v.ends_with('k') => (v.trim_end_matches('k').to_string(), 1_000),
+        v if v.ends_with('m') => (v.trim_end_matches('m').to_string(), 1_000_000),
+        v => (v, 1),
+    };
+    number
+        .parse::<u64>()
+        .map(|n| n * multiplier)
+        .map_err(|_| format!("Invalid count '{}'", value))
+}
+
+/// `60s`, `5m`, `1h` or plain seconds
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let (number, unit) = match value.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((i, _)) => value.split_at(i),
+        None => (value, "s"),
+    };
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => return Err(format!("Invalid duration '{}' (expected s, m or h)", value)),
+    };
+    number
+        .parse::<u64>()
+        .map(|n| Duration::from_secs(n * secs))
+        .map_err(|_| format!("Invalid duration '{}'", value))
+}
+
+#[derive(Deserialize)]
+struct Receipt {
+    notification_id: Uuid,
+    status: String,
+    created_at: DateTime<Utc>,
+    completed_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct Collector {
+    /// Notifications inserted by this run that have no receipt yet
+    pending: Mutex<HashSet<Uuid>>,
+    latencies_ms: Mutex<Vec<u64>>,
+    statuses: Mutex<HashMap<String, u64>>,
+    rejected: AtomicU64,
+}
+
+struct Receiver {
+    collector: Arc<Collector>,
+    signing_secret: String,
+}
+
+/// Result of a load run
+#[derive(Debug)]
+pub struct Report {
+    pub inserted: u64,
+    pub insert_errors: u64,
+    pub elapsed: Duration,
+    pub receipts: u64,
+    pub missing_receipts: u64,
+    pub statuses: HashMap<String, u64>,
+    /// Sorted insert → terminal state latencies
+    pub latencies_ms: Vec<u64>,
+}
+
+impl Report {
+    /// Latency at percentile `p` (0-100)
+    pub fn percentile(&self, p: f64) -> Option<u64> {
+        let last = self.latencies_ms.len().checked_sub(1)?;
+        let index = ((p / 100.0) * last as f64).round() as usize;
+        self.latencies_ms.get(index.min(last)).copied()
+    }
+
+    pub fn log(&self) {
+        let ms = |p| self.percentile(p).map_or("-".to_string(), |v| format!("{}ms", v));
+        info!("═══════════════════════════════════════════════════════════");
+        info!("  LOADGEN REPORT");
+        info!("  Inserted:          {} ({} errors)", self.inserted, self.insert_errors);
+        info!(
+            "  Insert rate:       {:.1}/s",
+            self.inserted as f64 / self.elapsed.as_secs_f64().max(0.001)
+        );
+        info!("  Receipts:          {} ({} missing)", self.receipts, self.missing_receipts);
+        for (status, count) in &self.statuses {
+            info!("    {:<16} {}", status, count);
+        }
+        info!("  Latency p50:       {}", ms(50.0));
+        info!("  Latency p90:       {}", ms(90.0));
+        info!("  Latency p99:       {}", ms(99.0));
+        info!("  Latency max:       {}", ms(100.0));
+        info!("═══════════════════════════════════════════════════════════");
+    }
+}
+
+/// Generate load against `pool` and collect receipts until drained
+pub async fn run(pool: PgPool, signing_secret: Option<String>, args: LoadgenArgs) -> Result<Report, String> {
+    let signing_secret = signing_secret
+        .ok_or("loadgen measures latency through delivery receipts: set RECEIPT_SIGNING_SECRET")?;
+
+    let collector = Arc::new(Collector::default());
+    let listener = TcpListener::bind(&args.listen)
+        .await
+        .map_err(|e| format!("Failed to bind receipt listener on {}: {}", args.listen, e))?;
+    let bound = listener.local_addr().map_err(|e| e.to_string())?;
+    let callback_url = format!(
+        "http://{}/receipts",
+        args.callback_host.clone().unwrap_or_else(|| bound.to_string())
+    );
+
+    let receiver = Arc::new(Receiver {
+        collector: collector.clone(),
+        signing_secret,
+    });
+    let app = Router::new().route("/receipts", post(receive)).with_state(receiver);
+    let server = tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            error!(error = %e, "Receipt listener stopped");
+        }
+    });
+
+    info!(
+        rate = args.rate,
+        users = args.users,
+        duration_secs = args.duration.as_secs(),
+        callback_url = %callback_url,
+        "Starting load generation"
+    );
+
+    let run_id = Uuid::new_v4();
+    let inserted = Arc::new(AtomicU64::new(0));
+    let insert_errors = Arc::new(AtomicU64::new(0));
+    let in_flight = Arc::new(Semaphore::new(MAX_IN_FLIGHT));
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / args.rate));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Burst);
+
+    let start = Instant::now();
+    let mut sequence: u64 = 0;
+    while start.elapsed() < args.duration {
+        ticker.tick().await;
+
+        let notification = NewNotification {
+            id: Some(Uuid::new_v4()),
+            user_id: Some(Uuid::new_v5(&run_id, &(sequence % args.users).to_le_bytes())),
+            actor_user_id: None,
+            notification_type: args.notification_type.clone(),
+            target_type: None,
+            target_id: None,
+            title: "Load test".to_string(),
+            message: Some(format!("loadgen {} #{}", run_id, sequence)),
+            payload: None,
+            deep_link: None,
+            priority: None,
+            group_key: None,
+            message_key: None,
+            message_args: None,
+            template_key: None,
+            actions: None,
+            attachments: None,
+            deliver_at: None,
+            deliver_at_local: None,
+            event_source: Some("notifications-service/loadgen".to_string()),
+            event_time: None,
+            callback_url: Some(callback_url.clone()),
+            tenant_id: None,
+            topic: None,
+            audience: None,
+            allow_duplicate: false,
+            pinned_until: None,
+            expires_at: None,
+            deadline: None,
+            region: None,
+            created_by: Some("loadgen".to_string()),
+        };
+        sequence += 1;
+
+        let permit = in_flight.clone().acquire_owned().await.expect("semaphore is never closed");
+        let (pool, collector, inserted, insert_errors) =
+            (pool.clone(), collector.clone(), inserted.clone(), insert_errors.clone());
+        tokio::spawn(async move {
+            let id = notification.id.expect("id set above");
+            // Registered before the insert: the receipt can't arrive earlier
+            collector.pending.lock().expect("collector lock poisoned").insert(id);
+            match NotificationQueries::insert(&pool, id, &notification).await {
+                Ok(_) => inserted.fetch_add(1, Ordering::Relaxed),
+                Err(_) => {
+                    collector.pending.lock().expect("collector lock poisoned").remove(&id);
+                    insert_errors.fetch_add(1, Ordering::Relaxed)
+                }
+            };
+            drop(permit);
+        });
+    }
+
+    // Let in-flight inserts finish
+    let _ = in_flight.acquire_many(MAX_IN_FLIGHT as u32).await;
+    let elapsed = start.elapsed();
+    info!(
+        inserted = inserted.load(Ordering::Relaxed),
+        elapsed_secs = elapsed.as_secs(),
+        drain_secs = args.drain.as_secs(),
+        "Inserts done, waiting for receipts"
+    );
+
+    let drain_start = Instant::now();
+    while drain_start.elapsed() < args.drain {
+        let pending = collector.pending.lock().expect("collector lock poisoned").len();
+        if pending == 0 {
+            break;
+        }
+        debug!(pending, "Waiting for receipts");
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    server.abort();
+
+    let rejected = collector.rejected.load(Ordering::Relaxed);
+    if rejected > 0 {
+        warn!(rejected, "Receipts with an invalid signature were ignored");
+    }
+
+    let mut latencies_ms = std::mem::take(&mut *collector.latencies_ms.lock().expect("collector lock poisoned"));
+    latencies_ms.sort_unstable();
+    let statuses = std::mem::take(&mut *collector.statuses.lock().expect("collector lock poisoned"));
+    let missing_receipts = collector.pending.lock().expect("collector lock poisoned").len() as u64;
+
+    Ok(Report {
+        inserted: inserted.load(Ordering::Relaxed),
+        insert_errors: insert_errors.load(Ordering::Relaxed),
+        elapsed,
+        receipts: latencies_ms.len() as u64,
+        missing_receipts,
+        statuses,
+        latencies_ms,
+    })
+}
+
+/// POST /receipts from the service's receipt dispatcher
+async fn receive(State(receiver): State<Arc<Receiver>>, headers: HeaderMap, body: Bytes) -> StatusCode {
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let body = String::from_utf8_lossy(&body);
+
+    let expected = receipts::sign(receiver.signing_secret.as_bytes(), header("x-receipt-timestamp"), &body);
+    if header("x-receipt-signature").strip_prefix("sha256=") != Some(expected.as_str()) {
+        receiver.collector.rejected.fetch_add(1, Ordering::Relaxed);
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let receipt: Receipt = match serde_json::from_str(&body) {
+        Ok(receipt) => receipt,
+        Err(_) => return StatusCode::BAD_REQUEST,
+    };
+
+    let collector = &receiver.collector;
+    // Receipts are at-least-once: count each notification once
+    if !collector.pending.lock().expect("collector lock poisoned").remove(&receipt.notification_id) {
+        return StatusCode::OK;
+    }
+
+    let latency = (receipt.completed_at - receipt.created_at).num_milliseconds().max(0) as u64;
+    collector.latencies_ms.lock().expect("collector lock poisoned").push(latency);
+    *collector
+        .statuses
+        .lock()
+        .expect("collector lock poisoned")
+        .entry(receipt.status)
+        .or_default() += 1;
+    StatusCode::OK
+}
@@ -1,35 +1,74 @@
-use axum::{routing::get, Router};
-use bus_client::BusClient;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+use notifications_service::archive::{self, RestoreArgs};
 use notifications_service::config::Config;
-use notifications_service::db::{Database, NotificationListener};
-use notifications_service::push::FcmClient;
-use notifications_service::worker::NotificationWorker;
+use notifications_service::db::{self, Database};
+use notifications_service::drain::{self, DrainArgs};
+use notifications_service::loadgen::{self, LoadgenArgs};
+use notifications_service::replay::{self, ReplayArgs};
+use notifications_service::resend::{self, ResendArgs};
+use notifications_service::seed::{self, SeedArgs};
+use notifications_service::secrets::{self, SecretResolver};
+use notifications_service::Service;
+use sqlx::postgres::PgConnectOptions;
+use std::str::FromStr;
 use std::sync::Arc;
-use tokio::net::TcpListener;
-use tokio::sync::mpsc;
+use std::time::Duration;
 use tracing::{debug, error, info, trace, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+/// What this invocation does (first CLI argument)
+enum Command {
+    Serve,
+    Drain(DrainArgs),
+    Loadgen(LoadgenArgs),
+    Replay(ReplayArgs),
+    Resend(ResendArgs),
+    Restore(RestoreArgs),
+    Seed(SeedArgs),
+}
+
 #[tokio::main]
 async fn main() {
+    // Subcommand: no arguments runs the service, the others are dev tools against the DB
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match args.first().map(String::as_str) {
+        None => Ok(Command::Serve),
+        Some("drain") => DrainArgs::parse(&args[1..]).map(Command::Drain),
+        Some("loadgen") => LoadgenArgs::parse(&args[1..]).map(Command::Loadgen),
+        Some("replay") => ReplayArgs::parse(&args[1..]).map(Command::Replay),
+        Some("resend") => ResendArgs::parse(&args[1..]).map(Command::Resend),
+        Some("restore") => RestoreArgs::parse(&args[1..]).map(Command::Restore),
+        Some("seed") => SeedArgs::parse(&args[1..]).map(Command::Seed),
+        Some(other) => Err(format!(
+            "Unknown command '{}' (expected no command, 'drain', 'loadgen', 'replay', 'resend', 'restore' or 'seed')",
+            other
+        )),
+    };
+    let command = command.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+
     // Load configuration FIRST (before logging, to know debug mode)
-    let config = Config::from_env();
+    let mut config = Config::from_env();
 
     // Initialize logging based on debug mode
     init_logging(&config);
 
-    info!("═══════════════════════════════════════════════════════════");
-    info!("  NOTIFICATIONS SERVICE STARTING");
-    info!("═══════════════════════════════════════════════════════════");
+    info!(version = env!("CARGO_PKG_VERSION"), "Notifications service starting");
 
     // Log debug configuration
     if config.debug.enabled {
         warn!("DEBUG MODE ENABLED - verbose logging active");
         debug!("Debug config:");
         debug!("  log_payloads: {}", config.debug.log_payloads);
-        debug!("  log_sql: {}", config.debug.log_sql);
+        debug!("  payload_file: {:?}", config.debug.payload_file);
+        debug!("  log_sql: {} (slow: {}ms)", config.debug.log_sql, config.debug.slow_sql_ms);
         debug!("  log_fcm_tokens: {}", config.debug.log_fcm_tokens);
         debug!("  log_timing: {}", config.debug.log_timing);
+        if let Some(chaos) = &config.debug.chaos {
+            warn!(?chaos, "CHAOS MODE ENABLED - injecting faults into FCM/Bus/DB calls");
+        }
     }
     info!(
         server_addr = %config.server_addr(),
@@ -40,10 +79,78 @@ async fn main() {
     );
     trace!("Full config: {:?}", config);
 
+    // Resolve vault:// and aws-sm:// references (no-op for plain values)
+    let secret_resolver = Arc::new(SecretResolver::new(config.vault.clone()));
+    if let Err(e) = secret_resolver.resolve_config(&mut config).await {
+        error!(error = %e, "Failed to resolve secrets");
+        std::process::exit(1);
+    }
+
+    // DATABASE_URL=primary,standby,...: standbys only contribute host and port
+    let database_urls = config.database_urls();
+    let base_options = match PgConnectOptions::from_str(database_urls.first().copied().unwrap_or_default()) {
+        Ok(options) => Database::with_query_logging(
+            Database::with_statement_cache(options, config.db_statement_cache_capacity),
+            config.debug.log_sql,
+            config.debug.slow_sql_ms,
+        ),
+        Err(e) => {
+            error!(error = %e, "Invalid DATABASE_URL");
+            std::process::exit(1);
+        }
+    };
+    let standbys: Vec<(String, u16)> = match database_urls
+        .iter()
+        .skip(1)
+        .map(|url| PgConnectOptions::from_str(url).map(|options| (options.get_host().to_string(), options.get_port())))
+        .collect()
+    {
+        Ok(standbys) => standbys,
+        Err(e) => {
+            error!(error = %e, "Invalid standby URL in DATABASE_URL");
+            std::process::exit(1);
+        }
+    };
+
+    db::compression::configure(config.payload_compression_threshold_bytes, config.payload_compression_level);
+    if config.payload_compression_threshold_bytes > 0 {
+        info!(
+            threshold_bytes = config.payload_compression_threshold_bytes,
+            level = config.payload_compression_level,
+            "Large payloads stored zstd-compressed"
+        );
+    }
+
+    if config.db_statement_cache_capacity == 0 {
+        info!("Prepared statements disabled (DB_STATEMENT_CACHE_CAPACITY=0)");
+    }
+
+    // Username/password from a secret backend are applied on top of DATABASE_URL
+    let database_credentials = match &config.database_credentials {
+        Some(uri) => match secret_resolver.fetch(uri).await {
+            Ok(secret) => Some((uri.clone(), secret)),
+            Err(e) => {
+                error!(error = %e, "Failed to read DATABASE_CREDENTIALS");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let connect_options = match &database_credentials {
+        Some((_, secret)) => match secret.connect_options(&base_options) {
+            Ok(options) => options,
+            Err(e) => {
+                error!(error = %e, "Invalid DATABASE_CREDENTIALS secret");
+                std::process::exit(1);
+            }
+        },
+        None => base_options.clone(),
+    };
+
     // Connect to database
     debug!("Connecting to database...");
     let start = std::time::Instant::now();
-    let db = match Database::connect(&config.database_url).await {
+    let db = match Database::connect_hosts(connect_options, &standbys).await {
         Ok(db) => {
             let duration = start.elapsed();
             info!(duration_ms = duration.as_millis() as u64, "Database connected");
@@ -55,144 +162,129 @@ async fn main() {
         }
     };
 
-    // Initialize FCM client (optional)
-    debug!("Initializing FCM client...");
-    let fcm_client = match (&config.fcm_credentials_path, &config.fcm_project_id) {
-        (Some(path), Some(project_id)) => {
-            trace!("FCM credentials path: {}", path);
-            trace!("FCM project ID: {}", project_id);
-            match FcmClient::new(path, project_id) {
-                Ok(client) => {
-                    info!(project_id = %project_id, "FCM client initialized");
-                    Some(Arc::new(client))
+    // Renew leased / rotated credentials before they expire under the pool
+    if let Some((uri, secret)) = database_credentials {
+        tokio::spawn(secrets::maintain_database_credentials(
+            secret_resolver.clone(),
+            uri,
+            base_options,
+            db.pool().clone(),
+            secret,
+            Duration::from_secs(config.database_credentials_refresh_secs),
+        ));
+    }
+
+    match command {
+        Command::Serve => {}
+        Command::Drain(drain_args) if drain_args.reopen => {
+            match drain::reopen(db.pool()).await {
+                Ok(()) => info!("Ingestion reopened"),
+                Err(e) => {
+                    error!(error = %e, "Reopening ingestion failed");
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Command::Drain(drain_args) => {
+            match drain::run(db.pool(), &drain_args).await {
+                Ok(summary) => {
+                    summary.log();
+                    if !summary.is_empty() {
+                        std::process::exit(1);
+                    }
                 }
                 Err(e) => {
-                    error!(error = %e, path = %path, "Failed to initialize FCM client - push disabled");
-                    None
+                    error!(error = %e, "Drain failed");
+                    std::process::exit(1);
                 }
             }
+            return;
         }
-        _ => {
-            warn!("FCM not configured - push notifications disabled");
-            debug!("  FCM_PROJECT_ID: {:?}", config.fcm_project_id);
-            debug!("  GOOGLE_APPLICATION_CREDENTIALS: {:?}", config.fcm_credentials_path);
-            None
+        Command::Loadgen(loadgen_args) => {
+            match loadgen::run(db.pool().clone(), config.receipt_signing_secret.clone(), loadgen_args).await {
+                Ok(report) => report.log(),
+                Err(e) => {
+                    error!(error = %e, "Load generation failed");
+                    std::process::exit(1);
+                }
+            }
+            return;
         }
-    };
-
-    // Initialize BusClient for websocket-bus
-    debug!("Initializing WebSocket Bus client...");
-    let bus_client = match (&config.websocket_bus_url, &config.service_token) {
-        (Some(url), Some(token)) => {
-            let client = BusClient::new(url, token);
-            info!(bus_url = %url, "WebSocket Bus client initialized");
-            Some(Arc::new(client))
+        Command::Replay(replay_args) => {
+            match replay::run(db.pool(), &config, &replay_args).await {
+                Ok(summary) => {
+                    summary.log();
+                    if !summary.is_clean() {
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    error!(error = %e, "Replay failed");
+                    std::process::exit(1);
+                }
+            }
+            return;
         }
-        _ => {
-            warn!("WebSocket Bus not configured - real-time delivery disabled");
-            debug!("  WEBSOCKET_BUS_URL: {:?}", config.websocket_bus_url);
-            debug!("  SERVICE_TOKEN: {:?}", config.service_token.as_ref().map(|_| "[REDACTED]"));
-            None
+        Command::Resend(resend_args) => {
+            match resend::run(db.pool(), &resend_args).await {
+                Ok(summary) => summary.log(),
+                Err(e) => {
+                    error!(error = %e, "Resend failed");
+                    std::process::exit(1);
+                }
+            }
+            return;
         }
-    };
-
-    // Channel for NOTIFY signals to worker
-    debug!("Creating wake channel (buffer size: 10)...");
-    let (wake_tx, wake_rx) = mpsc::channel::<()>(10);
-
-    // Start Postgres NOTIFY listener
-    debug!("Starting NOTIFY listener...");
-    let listener = NotificationListener::new(config.database_url.clone());
-    let listener_handle = tokio::spawn(async move {
-        if let Err(e) = listener.listen(wake_tx).await {
-            error!(error = %e, "NOTIFY listener failed");
+        Command::Restore(restore_args) => {
+            match archive::run_restore(db.pool(), &config, &restore_args).await {
+                Ok(restored) => info!(archive_id = %restore_args.archive_id, restored = restored, "Archive restored"),
+                Err(e) => {
+                    error!(error = %e, "Restore failed");
+                    std::process::exit(1);
+                }
+            }
+            return;
         }
-    });
-    info!("NOTIFY listener started");
+        Command::Seed(seed_args) => {
+            match seed::run(db.pool(), &seed_args).await {
+                Ok(summary) => summary.log(),
+                Err(e) => {
+                    error!(error = %e, "Seeding failed");
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+    }
 
-    // Start worker
-    debug!("Starting notification worker...");
-    let fcm_enabled = fcm_client.is_some();
-    let worker = NotificationWorker::new(
-        &db,
-        config.clone(),
-        bus_client.clone(),
-        fcm_client,
-    );
-    let worker_handle = tokio::spawn(async move {
-        worker.run(wake_rx).await;
-    });
-    info!(
-        poll_interval_secs = config.worker_poll_interval_secs,
-        batch_size = config.worker_batch_size,
-        "Notification worker started"
+    // Install the Prometheus recorder before anything records metrics; delivery latency is a
+    // histogram so p95 can be aggregated across replicas
+    let recorder = PrometheusBuilder::new().set_buckets_for_metric(
+        Matcher::Full("notifications_delivery_latency_seconds".to_string()),
+        &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0],
     );
-
-    // Start HTTP server (health + metrics only)
-    debug!("Starting HTTP server...");
-    let router = Router::new()
-        .route("/health", get(health_handler))
-        .route("/healthz", get(health_handler))
-        .route("/readyz", get(health_handler))
-        .route("/metrics", get(metrics_handler));
-
-    let addr = config.server_addr();
-
-    let tcp_listener = match TcpListener::bind(&addr).await {
-        Ok(l) => {
-            debug!("TCP listener bound to {}", addr);
-            l
-        }
+    let metrics = match recorder.and_then(|recorder| recorder.install_recorder()) {
+        Ok(handle) => handle,
         Err(e) => {
-            error!(error = %e, addr = %addr, "Failed to bind HTTP server");
+            error!(error = %e, "Failed to install Prometheus recorder");
             std::process::exit(1);
         }
     };
 
-    info!("═══════════════════════════════════════════════════════════");
-    info!("  SERVICE READY");
-    info!("  Health:    http://{}/health", addr);
-    info!("  Metrics:   http://{}/metrics", addr);
-    info!("  Bus:       {}", if bus_client.is_some() { "ENABLED" } else { "DISABLED" });
-    info!("  FCM:       {}", if fcm_enabled { "ENABLED" } else { "DISABLED" });
-    info!("═══════════════════════════════════════════════════════════");
-
-    // Run server with graceful shutdown
-    let server_handle = tokio::spawn(async move {
-        axum::serve(tcp_listener, router)
-            .with_graceful_shutdown(shutdown_signal())
-            .await
-            .expect("Server failed");
-    });
-
-    // Wait for any task to complete (shouldn't happen normally)
-    tokio::select! {
-        _ = listener_handle => {
-            error!("NOTIFY listener stopped unexpectedly");
-        }
-        _ = worker_handle => {
-            error!("Worker stopped unexpectedly");
-        }
-        _ = server_handle => {
-            info!("Server shutdown complete");
+    let service = match Service::builder().config(config).database(db).metrics(metrics).build() {
+        Ok(service) => service,
+        Err(e) => {
+            error!(error = %e, "Invalid service configuration");
+            std::process::exit(1);
         }
-    }
-
-    info!("═══════════════════════════════════════════════════════════");
-    info!("  NOTIFICATIONS SERVICE STOPPED");
-    info!("═══════════════════════════════════════════════════════════");
-}
+    };
 
-async fn health_handler() -> &'static str {
-    "OK"
-}
+    if let Err(e) = service.run(shutdown_signal()).await {
+        error!(error = %e, "Service stopped");
+    }
 
-async fn metrics_handler() -> String {
-    // Basic Prometheus metrics
-    let output = metrics_exporter_prometheus::PrometheusBuilder::new()
-        .build_recorder();
-    // For now, return empty metrics - can be expanded later
-    "# notifications_service metrics\n".to_string()
+    info!(event = "service_stopped", "Notifications service stopped");
 }
 
 async fn shutdown_signal() {
@@ -231,9 +323,11 @@ fn init_logging(config: &Config) {
                 "notifications_service=trace,tower_http=debug,axum=debug,sqlx=debug,bus_client=debug".into()
             })
     } else {
-        // Production: use RUST_LOG or default to info
+        // Production: use RUST_LOG or default to info, plus the statements sqlx logs
+        // (all of them with DEBUG_LOG_SQL, otherwise only slow ones)
+        let sqlx = if config.debug.log_sql { "sqlx::query=info,sqlx::params=info" } else { "sqlx::query=warn" };
         tracing_subscriber::EnvFilter::try_from_default_env()
-            .unwrap_or_else(|_| "notifications_service=info,bus_client=info".into())
+            .unwrap_or_else(|_| format!("notifications_service=info,bus_client=info,{}", sqlx).into())
     };
 
     if config.debug.enabled {
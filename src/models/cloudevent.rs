@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// CloudEvents 1.0 envelope (structured JSON mode)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudEvent {
+    pub specversion: String,
+    pub id: String,
+    pub source: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub datacontenttype: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl CloudEvent {
+    pub const SPEC_VERSION: &'static str = "1.0";
+
+    /// New event with a fresh id and the current time
+    pub fn new(source: &str, event_type: impl Into<String>, data: serde_json::Value) -> Self {
+        Self {
+            specversion: Self::SPEC_VERSION.to_string(),
+            id: Uuid::now_v7().to_string(),
+            source: source.to_string(),
+            event_type: event_type.into(),
+            subject: None,
+            time: Some(Utc::now()),
+            datacontenttype: Some("application/json".to_string()),
+            data: Some(data),
+        }
+    }
+
+    /// Structured-mode detection: a JSON object carrying `specversion`
+    pub fn is_structured(value: &serde_json::Value) -> bool {
+        value.get("specversion").is_some_and(|v| v.is_string())
+    }
+
+    /// Notification id for this event: the id itself if it's a UUID, otherwise a
+    /// stable UUID from source + id (CloudEvents ids are only unique per source)
+    pub fn notification_id(&self) -> Uuid {
+        Uuid::parse_str(&self.id).unwrap_or_else(|_| {
+            Uuid::new_v5(&Uuid::NAMESPACE_URL, format!("cloudevents:{}:{}", self.source, self.id).as_bytes())
+        })
+    }
+}
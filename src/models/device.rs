@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+/// Smallest `max_payload_bytes` a device may declare (room for the title and the essential data)
+pub const MIN_DEVICE_PAYLOAD_BYTES: u32 = 1024;
+
+/// What an installed app can handle, declared at registration; push is shaped to it
+///
+/// An absent field means the app didn't say, and gets everything the service sends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DeviceCapabilities {
+    /// false = no `actions` data key and no APNs category (apps that crash on unknown buttons)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_actions: Option<bool>,
+    /// Payload limit below FCM's 4096 bytes; larger messages are trimmed to fit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_payload_bytes: Option<u32>,
+}
+
+impl DeviceCapabilities {
+    pub fn supports_actions(&self) -> bool {
+        self.supports_actions.unwrap_or(true)
+    }
+}
@@ -1,9 +1,22 @@
+mod cloudevent;
+mod device;
 mod notification;
 
+pub use cloudevent::CloudEvent;
+pub use device::{DeviceCapabilities, MIN_DEVICE_PAYLOAD_BYTES};
+
 pub use notification::{
+    Audience,
     ClientMessage,
     ConnectedMessage,
+    NewNotification,
     Notification,
+    NotificationAction,
+    NotificationAttachment,
     PongMessage,
+    SignedAttachment,
     SyncNotifyMessage,
+    validate_actions,
+    validate_attachments,
+    validate_topic,
 };
@@ -1,11 +1,21 @@
-use chrono::{DateTime, Utc};
+use crate::db::campaigns::CAMPAIGN_CREATOR_PREFIX;
+use crate::db::compression;
+use crate::db::tenants::DEFAULT_TENANT;
+use crate::error::ValidationError;
+use crate::otp;
+use crate::policy::PolicyDecision;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::FromRow;
+use std::borrow::Cow;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct Notification {
     pub id: Uuid,
+    /// Owning tenant (app/brand): selects FCM credentials, Bus topics and preferences
+    pub tenant_id: String,
     pub user_id: Uuid,
     pub actor_user_id: Option<Uuid>,
     pub notification_type: String,
@@ -14,8 +24,97 @@ pub struct Notification {
     pub title: String,
     pub message: Option<String>,
     pub payload: Option<serde_json::Value>,
+    /// `payload` as stored when it was compressed (moved into `payload` by `inflate_payload`)
+    #[sqlx(default)]
+    #[serde(skip)]
+    pub payload_zstd: Option<Vec<u8>>,
     pub deep_link: Option<String>,
     pub priority: Option<String>,
+    /// Clients stack notifications sharing a group key (conversation, post, ...)
+    pub group_key: Option<String>,
+    /// Fluent message id rendered per recipient locale (title/message are the fallback)
+    pub message_key: Option<String>,
+    pub message_args: Option<serde_json::Value>,
+    /// Render title/message from notification_templates (takes precedence over message_key)
+    pub template_key: Option<String>,
+    /// Inline action buttons (`[NotificationAction]`); answered via `POST .../{id}/action`
+    #[sqlx(default)]
+    pub actions: Option<serde_json::Value>,
+    /// Media in the attachment bucket (`[NotificationAttachment]`), sent as pre-signed URLs
+    #[sqlx(default)]
+    #[serde(skip)]
+    pub attachments: Option<serde_json::Value>,
+    /// `attachments` signed for this delivery (set by the worker, not stored on the row)
+    #[sqlx(skip)]
+    #[serde(skip)]
+    pub media: Vec<SignedAttachment>,
+    /// Template version that rendered this copy (set by the worker, not stored on the row)
+    #[sqlx(skip)]
+    #[serde(skip)]
+    pub template_version: Option<i32>,
+    /// Producer identity (`campaign:<id>`, `recurring:<id>`, API key name, ...)
+    #[serde(skip)]
+    pub created_by: Option<String>,
+    /// Experiment and variant that produced this copy (set by the worker, not stored on the row)
+    #[sqlx(skip)]
+    #[serde(skip)]
+    pub experiment_id: Option<Uuid>,
+    #[sqlx(skip)]
+    #[serde(skip)]
+    pub variant: Option<String>,
+    /// Content policy decision for this delivery (set by the worker, reported on the receipt)
+    #[sqlx(skip)]
+    #[serde(skip)]
+    pub policy: Option<PolicyDecision>,
+    /// Push only to devices meeting these conditions (`crate::targeting`); skips the Bus
+    #[serde(skip)]
+    pub device_filter: Option<Vec<String>>,
+    /// Topic target (nil user_id: fan out to subscribers); on the copies, the source topic
+    #[serde(skip)]
+    pub topic: Option<String>,
+    /// Audience target (`org:<id>` / `team:<id>`, nil user_id: fan out to the members); on the copies, the source
+    #[sqlx(default)]
+    #[serde(skip)]
+    pub audience: Option<String>,
+    /// Delivered over the Bus, waiting for a client ack (BUS_ACK_TIMEOUT_SECS)
+    #[sqlx(default)]
+    #[serde(skip)]
+    pub bus_delivered_at: Option<DateTime<Utc>>,
+    #[sqlx(default)]
+    #[serde(skip)]
+    pub acked_at: Option<DateTime<Utc>>,
+    /// Broadcast only: send even when an identical broadcast just went out
+    #[sqlx(default)]
+    #[serde(skip)]
+    pub allow_duplicate: bool,
+    /// Announcement: delivered once, then served by `/api/v1/announcements` until this time
+    #[sqlx(default)]
+    #[serde(skip)]
+    pub pinned_until: Option<DateTime<Utc>>,
+    /// Not delivered after this time; the rest of it is the push TTL
+    #[sqlx(default)]
+    #[serde(skip)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Time-sensitive (OTP codes): claimed first, and never delivered after this time
+    #[sqlx(default)]
+    #[serde(skip)]
+    pub deadline: Option<DateTime<Utc>>,
+    /// When pushes of this delivery expire on FCM/APNs (set by the worker, not stored on the row)
+    #[sqlx(skip)]
+    #[serde(skip)]
+    pub push_expires_at: Option<DateTime<Utc>>,
+    /// Held for a bundle (BUNDLE_WINDOW_SECS): the first held row to go out absorbs the rest
+    #[sqlx(default)]
+    #[serde(skip)]
+    pub bundled_at: Option<DateTime<Utc>>,
+    /// Events this delivery stands for (set by the worker when a bundle goes out)
+    #[sqlx(skip)]
+    #[serde(skip)]
+    pub bundle_count: Option<i64>,
+    /// Wall-clock send time, resolved in the recipient's timezone by the router
+    #[sqlx(default)]
+    #[serde(skip)]
+    pub deliver_at_local: Option<NaiveDateTime>,
     pub deliver_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
 }
@@ -28,6 +127,694 @@ impl Notification {
             Some("high") | Some("critical")
         )
     }
+
+    /// Low priority: pushes expire early and replace each other (collapse) on the device
+    pub fn is_low_priority(&self) -> bool {
+        self.priority.as_deref() == Some("low")
+    }
+
+    /// When it stops being worth delivering: the earlier of `expires_at` and `deadline`
+    pub fn expiry(&self) -> Option<DateTime<Utc>> {
+        match (self.expires_at, self.deadline) {
+            (Some(expires_at), Some(deadline)) => Some(expires_at.min(deadline)),
+            (expires_at, deadline) => expires_at.or(deadline),
+        }
+    }
+
+    /// Addressed to a topic's subscribers rather than one user (expanded by the worker)
+    pub fn is_topic_target(&self) -> bool {
+        self.user_id.is_nil() && self.topic.is_some()
+    }
+
+    /// Addressed to an organization's or team's members (expanded by the worker)
+    pub fn is_audience_target(&self) -> bool {
+        self.user_id.is_nil() && self.audience.is_some()
+    }
+
+    /// Campaign that fanned this row out (`created_by = campaign:<id>`)
+    pub fn campaign_id(&self) -> Option<Uuid> {
+        self.created_by
+            .as_deref()
+            .and_then(|creator| creator.strip_prefix(CAMPAIGN_CREATOR_PREFIX))
+            .and_then(|id| Uuid::parse_str(id).ok())
+    }
+
+    /// The inline actions (see [`NotificationAction::parse`])
+    pub fn inline_actions(&self) -> Vec<NotificationAction> {
+        NotificationAction::parse(self.actions.as_ref())
+    }
+
+    /// Decompress a payload stored as `payload_zstd` (every query decoding rows calls this)
+    pub fn inflate_payload(&mut self) {
+        compression::inflate(self.id, &mut self.payload, &mut self.payload_zstd);
+    }
+
+    /// The attachments (see [`NotificationAttachment::parse`])
+    pub fn attachment_keys(&self) -> Vec<NotificationAttachment> {
+        NotificationAttachment::parse(self.attachments.as_ref())
+    }
+
+    /// SHA-256 of what recipients see, for spotting a broadcast inserted twice
+    ///
+    /// Covers the unrendered content; id, timestamps and the producer are left out.
+    pub fn content_hash(&self) -> String {
+        let content = serde_json::json!([
+            self.notification_type,
+            self.title,
+            self.message,
+            self.payload,
+            self.deep_link,
+            self.priority,
+            self.group_key,
+            self.message_key,
+            self.message_args,
+            self.template_key,
+        ]);
+        Sha256::digest(content.to_string().as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Unsaved notification with default fields (previews, test sends)
+    pub fn draft(notification_type: &str, title: String, message: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::nil(),
+            tenant_id: DEFAULT_TENANT.to_string(),
+            user_id: Uuid::nil(),
+            actor_user_id: None,
+            notification_type: notification_type.to_string(),
+            target_type: None,
+            target_id: None,
+            title,
+            message,
+            payload: None,
+            payload_zstd: None,
+            deep_link: None,
+            priority: None,
+            group_key: None,
+            message_key: None,
+            message_args: None,
+            template_key: None,
+            actions: None,
+            attachments: None,
+            media: Vec::new(),
+            template_version: None,
+            created_by: None,
+            experiment_id: None,
+            variant: None,
+            policy: None,
+            device_filter: None,
+            topic: None,
+            audience: None,
+            bus_delivered_at: None,
+            acked_at: None,
+            allow_duplicate: false,
+            pinned_until: None,
+            expires_at: None,
+            deadline: None,
+            push_expires_at: None,
+            bundled_at: None,
+            bundle_count: None,
+            deliver_at_local: None,
+            deliver_at: now,
+            created_at: now,
+        }
+    }
+
+    /// Full notification as published on the WebSocket Bus (cached by clients)
+    pub fn bus_payload(&self) -> serde_json::Value {
+        serde_json::to_value(BusPayload {
+            id: self.id,
+            user_id: self.user_id,
+            actor_user_id: self.actor_user_id,
+            notification_type: &self.notification_type,
+            target_type: self.target_type.as_deref(),
+            target_id: self.target_id,
+            title: &self.title,
+            message: self.message.as_deref(),
+            payload: self.payload.as_ref(),
+            deep_link: self.deep_link.as_deref(),
+            priority: self.priority.as_deref(),
+            group_key: self.group_key.as_deref(),
+            actions: self.actions.as_ref().filter(|actions| !actions.is_null()),
+            attachments: &self.media,
+            bundle_count: self.bundle_count,
+            status: "unread",
+            created_at: self.created_at,
+        })
+        .unwrap_or_default()
+    }
+
+    /// Broadcast as published on the tenant's `global_notifications` Bus topic
+    pub fn bus_broadcast_payload(&self) -> serde_json::Value {
+        serde_json::to_value(BusBroadcastPayload {
+            kind: "broadcast",
+            id: self.id,
+            title: &self.title,
+            message: self.message.as_deref(),
+            payload: self.payload.as_ref(),
+            created_at: self.created_at,
+        })
+        .unwrap_or_default()
+    }
+
+    /// Bus payload carrying a base64 `notifications.v1.Notification` (BUS_PAYLOAD_ENCODING=protobuf)
+    pub fn bus_payload_protobuf(&self) -> serde_json::Value {
+        use base64::Engine;
+
+        serde_json::json!({
+            "encoding": "protobuf",
+            "type": "notifications.v1.Notification",
+            "data": base64::engine::general_purpose::STANDARD.encode(crate::grpc::encode_notification(self))
+        })
+    }
+}
+
+/// `Notification::bus_payload`, serialized straight from the borrowed row
+#[derive(Serialize)]
+struct BusPayload<'a> {
+    id: Uuid,
+    user_id: Uuid,
+    actor_user_id: Option<Uuid>,
+    notification_type: &'a str,
+    target_type: Option<&'a str>,
+    target_id: Option<Uuid>,
+    title: &'a str,
+    message: Option<&'a str>,
+    payload: Option<&'a serde_json::Value>,
+    deep_link: Option<&'a str>,
+    priority: Option<&'a str>,
+    group_key: Option<&'a str>,
+    /// Left out without actions, so older clients see the same payload
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actions: Option<&'a serde_json::Value>,
+    /// Pre-signed media URLs, left out without attachments
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    attachments: &'a [SignedAttachment],
+    /// Only on a bundle: how many events it stands for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bundle_count: Option<i64>,
+    status: &'static str,
+    created_at: DateTime<Utc>,
+}
+
+/// `Notification::bus_broadcast_payload`
+#[derive(Serialize)]
+struct BusBroadcastPayload<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    id: Uuid,
+    title: &'a str,
+    message: Option<&'a str>,
+    payload: Option<&'a serde_json::Value>,
+    created_at: DateTime<Utc>,
+}
+
+/// Notification submitted through an ingestion source (Kafka, ...)
+///
+/// Mirrors the columns producers INSERT directly. `id` is optional but recommended:
+/// sources are at-least-once, and a producer id makes redelivery idempotent.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewNotification {
+    pub id: Option<Uuid>,
+    /// Recipient; absent (or the nil UUID) together with `topic` targets the topic's subscribers
+    #[serde(default)]
+    pub user_id: Option<Uuid>,
+    pub actor_user_id: Option<Uuid>,
+    pub notification_type: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub title: String,
+    pub message: Option<String>,
+    pub payload: Option<serde_json::Value>,
+    pub deep_link: Option<String>,
+    pub priority: Option<String>,
+    pub group_key: Option<String>,
+    pub message_key: Option<String>,
+    pub message_args: Option<serde_json::Value>,
+    pub template_key: Option<String>,
+    /// Inline action buttons, e.g. Accept / Decline (at most MAX_ACTIONS)
+    #[serde(default)]
+    pub actions: Option<Vec<NotificationAction>>,
+    /// Media already uploaded to the attachment bucket (at most MAX_ATTACHMENTS)
+    #[serde(default)]
+    pub attachments: Option<Vec<NotificationAttachment>>,
+    pub deliver_at: Option<DateTime<Utc>>,
+    /// Send at this wall-clock time in each recipient's timezone (e.g. `2026-03-29T09:00:00`)
+    #[serde(default)]
+    pub deliver_at_local: Option<NaiveDateTime>,
+    /// CloudEvents `source`/`time` when ingested as a CloudEvent
+    #[serde(default)]
+    pub event_source: Option<String>,
+    #[serde(default)]
+    pub event_time: Option<DateTime<Utc>>,
+    /// Producer URL for the signed delivery receipt
+    #[serde(default)]
+    pub callback_url: Option<String>,
+    /// Owning tenant (None = `default`)
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    /// Fan out to everyone following this topic (`[a-z0-9_.:-]`, e.g. `project:42`)
+    #[serde(default)]
+    pub topic: Option<String>,
+    /// Fan out to everyone in an organization (`org:<id>`, its teams included) or a team (`team:<id>`)
+    #[serde(default)]
+    pub audience: Option<String>,
+    /// Broadcast only: skip the duplicate-broadcast check (intentional re-send)
+    #[serde(default)]
+    pub allow_duplicate: bool,
+    /// Keep it listed as an announcement (`/api/v1/announcements`) until this time
+    #[serde(default)]
+    pub pinned_until: Option<DateTime<Utc>>,
+    /// Not delivered after this time (suppressed as `expired`); pushes get the rest as TTL
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Time-sensitive: claimed first, only sent on channels fast enough, suppressed as
+    /// `expired` once it passes
+    #[serde(default)]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Home region (active-active): delivered by that region's workers. None = the region of
+    /// the recipient's most recently seen device, or any region
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Authenticated service that created it (mTLS identity; never taken from the body)
+    #[serde(skip)]
+    pub created_by: Option<String>,
+}
+
+impl NewNotification {
+    /// The `user_id` column: the nil UUID for topic targets (and broadcasts)
+    pub fn recipient(&self) -> Uuid {
+        self.user_id.unwrap_or_else(Uuid::nil)
+    }
+
+    pub fn is_otp(&self) -> bool {
+        otp::is_otp(&self.notification_type)
+    }
+
+    /// The `priority` column (None = `normal`): OTP codes default to `critical`
+    pub fn stored_priority(&self) -> Option<&str> {
+        self.priority.as_deref().or_else(|| self.is_otp().then_some("critical"))
+    }
+
+    /// The `deadline` column: OTP codes without one expire `otp::DEFAULT_TTL_SECS` after they are due
+    pub fn stored_deadline(&self) -> Option<DateTime<Utc>> {
+        self.deadline.or_else(|| {
+            self.is_otp()
+                .then(|| self.deliver_at.unwrap_or_else(Utc::now) + chrono::Duration::seconds(otp::DEFAULT_TTL_SECS))
+        })
+    }
+
+    /// This notification as it may be logged: an OTP code's text redacted, its payload and args left out
+    pub fn loggable(&self) -> Cow<'_, Self> {
+        if !self.is_otp() {
+            return Cow::Borrowed(self);
+        }
+        let redacted = |_: &serde_json::Value| serde_json::json!(otp::REDACTED);
+        Cow::Owned(Self {
+            title: otp::redact(&self.title),
+            message: self.message.as_deref().map(otp::redact),
+            payload: self.payload.as_ref().map(redacted),
+            message_args: self.message_args.as_ref().map(redacted),
+            ..self.clone()
+        })
+    }
+
+    /// The `actions` column (None for no actions)
+    pub fn actions_json(&self) -> Option<serde_json::Value> {
+        self.actions
+            .as_ref()
+            .filter(|actions| !actions.is_empty())
+            .and_then(|actions| serde_json::to_value(actions).ok())
+    }
+
+    /// The `attachments` column (None for no attachments)
+    pub fn attachments_json(&self) -> Option<serde_json::Value> {
+        self.attachments
+            .as_ref()
+            .filter(|attachments| !attachments.is_empty())
+            .and_then(|attachments| serde_json::to_value(attachments).ok())
+    }
+
+    /// The row [`ingest`](crate::ingest::ingest) would insert, without inserting it (test sends)
+    ///
+    /// Column defaults are applied as the INSERT does; `id` is random when not given.
+    pub fn draft(&self) -> Notification {
+        let mut notification = Notification::draft(&self.notification_type, self.title.clone(), self.message.clone());
+        notification.id = self.id.unwrap_or_else(Uuid::new_v4);
+        notification.tenant_id = self.tenant_id.clone().unwrap_or_else(|| DEFAULT_TENANT.to_string());
+        notification.user_id = self.recipient();
+        notification.actor_user_id = self.actor_user_id;
+        notification.target_type = self.target_type.clone();
+        notification.target_id = self.target_id;
+        notification.payload = self.payload.clone();
+        notification.deep_link = self.deep_link.clone();
+        notification.priority = Some(self.stored_priority().unwrap_or("normal").to_string());
+        notification.group_key = self.group_key.clone();
+        notification.message_key = self.message_key.clone();
+        notification.message_args = self.message_args.clone();
+        notification.template_key = self.template_key.clone();
+        notification.actions = self.actions_json();
+        notification.attachments = self.attachments_json();
+        notification.created_by = self.created_by.clone();
+        notification.topic = self.topic.clone();
+        notification.audience = self.audience.clone();
+        notification.allow_duplicate = self.allow_duplicate;
+        notification.pinned_until = self.pinned_until;
+        notification.expires_at = self.expires_at;
+        notification.deadline = self.stored_deadline();
+        notification.deliver_at_local = self.deliver_at_local;
+        if let Some(deliver_at) = self.deliver_at {
+            notification.deliver_at = deliver_at;
+        }
+        notification
+    }
+
+    /// Reject notifications the worker could never deliver sensibly
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let addressed = self.user_id.is_some_and(|id| !id.is_nil());
+        match (&self.topic, &self.audience, self.user_id) {
+            (Some(_), Some(_), _) => return Err(ValidationError::invalid("topic and audience are mutually exclusive")),
+            (Some(topic), None, _) => {
+                validate_topic(topic)?;
+                if addressed {
+                    return Err(ValidationError::invalid("user_id and topic are mutually exclusive"));
+                }
+            }
+            (None, Some(audience), _) => {
+                Audience::parse(audience)?;
+                if addressed {
+                    return Err(ValidationError::invalid("user_id and audience are mutually exclusive"));
+                }
+            }
+            (None, None, None) => {
+                return Err(ValidationError::invalid("user_id is required unless topic or audience is set"))
+            }
+            (None, None, Some(_)) => {}
+        }
+        if self.notification_type.trim().is_empty() {
+            return Err(ValidationError::invalid("notification_type is required"));
+        }
+        if self.title.trim().is_empty() && self.message_key.is_none() && self.template_key.is_none() {
+            return Err(ValidationError::invalid("title is required unless message_key or template_key is set"));
+        }
+        if let Some(priority) = &self.priority {
+            if !matches!(priority.as_str(), "low" | "normal" | "high" | "critical") {
+                return Err(ValidationError::invalid(format!("Unknown priority '{}'", priority)));
+            }
+        }
+        if matches!(&self.message_args, Some(args) if !args.is_object()) {
+            return Err(ValidationError::invalid("message_args must be a JSON object"));
+        }
+        if let Some(actions) = &self.actions {
+            validate_actions(actions)?;
+        }
+        if let Some(attachments) = &self.attachments {
+            validate_attachments(attachments)?;
+        }
+        if matches!(&self.tenant_id, Some(tenant) if tenant.trim().is_empty()) {
+            return Err(ValidationError::invalid("tenant_id must not be empty"));
+        }
+        if let Some(url) = &self.callback_url {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return Err(ValidationError::invalid("callback_url must be an http(s) URL"));
+            }
+        }
+        if self.deliver_at_local.is_some() {
+            if self.deliver_at.is_some() {
+                return Err(ValidationError::invalid("deliver_at and deliver_at_local are mutually exclusive"));
+            }
+            if self.topic.is_none() && self.audience.is_none() && self.recipient().is_nil() {
+                return Err(ValidationError::invalid(
+                    "deliver_at_local needs recipients with a timezone (a user, topic or audience, not a broadcast)",
+                ));
+            }
+        }
+        if let Some(pinned_until) = self.pinned_until {
+            if pinned_until <= self.deliver_at.unwrap_or_else(Utc::now) {
+                return Err(ValidationError::invalid("pinned_until must be after deliver_at (or now)"));
+            }
+        }
+        if let Some(expires_at) = self.expires_at {
+            if expires_at <= self.deliver_at.unwrap_or_else(Utc::now) {
+                return Err(ValidationError::invalid("expires_at must be after deliver_at (or now)"));
+            }
+        }
+        if self.is_otp() && (self.topic.is_some() || self.audience.is_some() || self.recipient().is_nil()) {
+            return Err(ValidationError::invalid("otp notifications go to one user_id (no topic, audience or broadcast)"));
+        }
+        if let Some(deadline) = self.stored_deadline() {
+            if deadline <= self.deliver_at.unwrap_or_else(Utc::now) {
+                return Err(ValidationError::invalid("deadline must be after deliver_at (or now)"));
+            }
+            // The recipient's timezone could put the send time past it
+            if self.deliver_at_local.is_some() {
+                return Err(ValidationError::invalid("deadline can't be combined with deliver_at_local"));
+            }
+        }
+        if let Some(region) = &self.region {
+            validate_region(region)?;
+        }
+        Ok(())
+    }
+}
+
+/// Longest topic name accepted
+pub const MAX_TOPIC_LEN: usize = 128;
+
+/// Topic names: lowercase letters, digits and `_ . : -` (e.g. `project:42`)
+pub fn validate_topic(topic: &str) -> Result<(), ValidationError> {
+    let valid = !topic.is_empty()
+        && topic.len() <= MAX_TOPIC_LEN
+        && topic
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '.' | ':' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::invalid(format!(
+            "Invalid topic '{}' (1-{} of a-z, 0-9, _ . : -)",
+            topic, MAX_TOPIC_LEN
+        )))
+    }
+}
+
+/// Longest region name accepted
+pub const MAX_REGION_LEN: usize = 32;
+
+/// Region names: lowercase letters, digits and `-` (e.g. `eu-west`, as in REGION)
+pub fn validate_region(region: &str) -> Result<(), ValidationError> {
+    let valid = !region.is_empty()
+        && region.len() <= MAX_REGION_LEN
+        && region.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::invalid(format!(
+            "Invalid region '{}' (1-{} of a-z, 0-9, -)",
+            region, MAX_REGION_LEN
+        )))
+    }
+}
+
+/// Longest organization or team id in an audience
+pub const MAX_AUDIENCE_ID_LEN: usize = 128;
+
+/// Broadcast audience: everyone in an organization (its teams included), or in one team
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Audience<'a> {
+    Org(&'a str),
+    Team(&'a str),
+}
+
+impl<'a> Audience<'a> {
+    /// `org:<id>` or `team:<id>` (ids are the producer's own: any text without whitespace)
+    pub fn parse(audience: &'a str) -> Result<Self, ValidationError> {
+        let parsed = match audience.split_once(':') {
+            Some(("org", id)) => Audience::Org(id),
+            Some(("team", id)) => Audience::Team(id),
+            _ => {
+                return Err(ValidationError::invalid(format!(
+                    "Invalid audience '{}' (expected org:<id> or team:<id>)",
+                    audience
+                )))
+            }
+        };
+        let id = parsed.id();
+        if id.is_empty() || id.len() > MAX_AUDIENCE_ID_LEN || id.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(ValidationError::invalid(format!(
+                "Invalid audience id '{}' (1-{} characters, no whitespace)",
+                id, MAX_AUDIENCE_ID_LEN
+            )));
+        }
+        Ok(parsed)
+    }
+
+    /// `org` or `team`
+    pub fn scope(&self) -> &'static str {
+        match self {
+            Audience::Org(_) => "org",
+            Audience::Team(_) => "team",
+        }
+    }
+
+    pub fn id(&self) -> &'a str {
+        match self {
+            Audience::Org(id) | Audience::Team(id) => id,
+        }
+    }
+}
+
+/// Most inline actions per notification (Android shows three buttons)
+pub const MAX_ACTIONS: usize = 3;
+/// Longest action id and button title
+pub const MAX_ACTION_ID_LEN: usize = 32;
+pub const MAX_ACTION_TITLE_CHARS: usize = 40;
+
+/// An inline action button (`{"id": "accept", "title": "Accept"}`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationAction {
+    /// What the client posts back and the producer receives (`[a-z0-9_-]`)
+    pub id: String,
+    /// Button label, shown as is (localize it before sending)
+    pub title: String,
+    /// Reply action: the client asks for text and sends it as `input`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub input: bool,
+}
+
+impl NotificationAction {
+    /// Actions stored in the `actions` column, skipping entries that aren't `{id, title, input}`
+    /// (producers that INSERT directly aren't validated)
+    pub fn parse(actions: Option<&serde_json::Value>) -> Vec<Self> {
+        let Some(serde_json::Value::Array(actions)) = actions else {
+            return Vec::new();
+        };
+        actions
+            .iter()
+            .filter_map(|action| serde_json::from_value(action.clone()).ok())
+            .collect()
+    }
+}
+
+/// Action ids unique and `[a-z0-9_-]`, titles non-empty, at most MAX_ACTIONS
+pub fn validate_actions(actions: &[NotificationAction]) -> Result<(), ValidationError> {
+    if actions.len() > MAX_ACTIONS {
+        return Err(ValidationError::invalid(format!("At most {} actions are allowed", MAX_ACTIONS)));
+    }
+    for (index, action) in actions.iter().enumerate() {
+        let valid_id = !action.id.is_empty()
+            && action.id.len() <= MAX_ACTION_ID_LEN
+            && action
+                .id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-'));
+        if !valid_id {
+            return Err(ValidationError::invalid(format!(
+                "Invalid action id '{}' (1-{} of a-z, 0-9, _ -)",
+                action.id, MAX_ACTION_ID_LEN
+            )));
+        }
+        if actions[..index].iter().any(|other| other.id == action.id) {
+            return Err(ValidationError::invalid(format!("Duplicate action id '{}'", action.id)));
+        }
+        if action.title.trim().is_empty() || action.title.chars().count() > MAX_ACTION_TITLE_CHARS {
+            return Err(ValidationError::invalid(format!(
+                "Action '{}' needs a title of 1-{} characters",
+                action.id, MAX_ACTION_TITLE_CHARS
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Most attachments per notification
+pub const MAX_ATTACHMENTS: usize = 4;
+/// Longest object key (S3 and GCS allow 1024 bytes)
+pub const MAX_ATTACHMENT_KEY_LEN: usize = 1024;
+/// Media types an attachment may have (`image/*`, `video/*`, `audio/*`, or these)
+const ATTACHMENT_DOCUMENT_TYPES: [&str; 1] = ["application/pdf"];
+
+/// Media referenced by key in the attachment bucket (`{"key": "...", "content_type": "image/png"}`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationAttachment {
+    /// Object key below ATTACHMENT_STORE_URL's prefix
+    pub key: String,
+    pub content_type: String,
+}
+
+impl NotificationAttachment {
+    /// Attachments stored in the `attachments` column, skipping entries that aren't
+    /// `{key, content_type}` (producers that INSERT directly aren't validated)
+    pub fn parse(attachments: Option<&serde_json::Value>) -> Vec<Self> {
+        let Some(serde_json::Value::Array(attachments)) = attachments else {
+            return Vec::new();
+        };
+        attachments
+            .iter()
+            .filter_map(|attachment| serde_json::from_value::<Self>(attachment.clone()).ok())
+            .filter(|attachment| validate_attachment_key(&attachment.key).is_ok())
+            .collect()
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.content_type.starts_with("image/")
+    }
+}
+
+/// An attachment as sent to clients: a GET URL that stops working at `expires_at`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedAttachment {
+    pub url: String,
+    pub content_type: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl SignedAttachment {
+    pub fn is_image(&self) -> bool {
+        self.content_type.starts_with("image/")
+    }
+}
+
+/// At most MAX_ATTACHMENTS, relative keys that stay below the prefix, media content types
+pub fn validate_attachments(attachments: &[NotificationAttachment]) -> Result<(), ValidationError> {
+    if attachments.len() > MAX_ATTACHMENTS {
+        return Err(ValidationError::invalid(format!("At most {} attachments are allowed", MAX_ATTACHMENTS)));
+    }
+    for attachment in attachments {
+        validate_attachment_key(&attachment.key)?;
+        let content_type = attachment.content_type.as_str();
+        let media = ["image/", "video/", "audio/"]
+            .iter()
+            .any(|kind| content_type.len() > kind.len() && content_type.starts_with(kind));
+        if !media && !ATTACHMENT_DOCUMENT_TYPES.contains(&content_type) {
+            return Err(ValidationError::invalid(format!(
+                "Attachment '{}' has unsupported content_type '{}' (image/*, video/*, audio/* or application/pdf)",
+                attachment.key, content_type
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A key can't climb out of the prefix (`..`, a leading `/`) or hide control characters
+fn validate_attachment_key(key: &str) -> Result<(), ValidationError> {
+    let valid = !key.is_empty()
+        && key.len() <= MAX_ATTACHMENT_KEY_LEN
+        && !key.starts_with('/')
+        && !key.chars().any(|c| c.is_control() || c == '\\')
+        && key.split('/').all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::invalid(format!(
+            "Invalid attachment key '{}' (1-{} bytes, relative, no empty, . or .. segments)",
+            key.escape_debug(),
+            MAX_ATTACHMENT_KEY_LEN
+        )))
+    }
 }
 
 /// Message sent to client via WebSocket
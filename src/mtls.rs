@@ -0,0 +1,227 @@
+//! Mutual-TLS listener for service-to-service endpoints (`/api/v1`, `/ingest`).
+//!
+//! Clients must present a certificate signed by MTLS_CLIENT_CA_PATH. The certificate
+//! is mapped to a [`ServiceIdentity`] (first URI SAN, e.g. a SPIFFE id, then DNS SAN,
+//! then CN) that is recorded as `created_by` on notifications created over this listener.
+//!
+//! A verified certificate only proves who the caller is. What it may do comes from
+//! MTLS_IDENTITIES (`identity=role[:tenant]`, comma-separated): `admin`, `operator` or
+//! `read-only` for the management endpoints, or `create` for none, optionally bound to a
+//! tenant. Every identity may create notifications; an unmapped one only for the
+//! default tenant.
+
+use crate::api::auth::Role;
+use crate::config::MtlsConfig;
+use crate::db::tenants::DEFAULT_TENANT;
+use axum::extract::ConnectInfo;
+use axum::{Extension, Router};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use rustls::pki_types::CertificateDer;
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, info, trace, warn};
+use x509_parser::extensions::GeneralName;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// Caller authenticated by its client certificate
+#[derive(Debug, Clone)]
+pub struct ServiceIdentity {
+    /// URI SAN, DNS SAN or CN of the certificate
+    pub name: String,
+    pub grant: IdentityGrant,
+}
+
+/// What MTLS_IDENTITIES allows an identity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdentityGrant {
+    /// Management role (None = create only)
+    pub role: Option<Role>,
+    /// Tenant it creates notifications for (None = any)
+    pub tenant_id: Option<String>,
+}
+
+/// Parsed MTLS_IDENTITIES
+#[derive(Debug, Default)]
+pub struct IdentityMap(HashMap<String, IdentityGrant>);
+
+impl IdentityMap {
+    /// Parse `identity=role[:tenant],...`; an unknown role or a repeated identity is an error
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut grants = HashMap::new();
+        for entry in value.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (identity, grant) = entry
+                .rsplit_once('=')
+                .ok_or_else(|| format!("Invalid MTLS_IDENTITIES entry '{}': expected identity=role[:tenant]", entry))?;
+            let (role, tenant_id) = match grant.split_once(':') {
+                Some((role, tenant_id)) if !tenant_id.is_empty() => (role, Some(tenant_id.to_string())),
+                Some(_) => return Err(format!("Invalid MTLS_IDENTITIES entry '{}': empty tenant", entry)),
+                None => (grant, None),
+            };
+            let role = match role {
+                "create" => None,
+                role => Some(Role::parse(role).ok_or_else(|| {
+                    format!("Invalid MTLS_IDENTITIES role '{}' (admin, operator, read-only or create)", role)
+                })?),
+            };
+            let identity = identity.trim();
+            if identity.is_empty() {
+                return Err(format!("Invalid MTLS_IDENTITIES entry '{}': empty identity", entry));
+            }
+            if grants.insert(identity.to_string(), IdentityGrant { role, tenant_id }).is_some() {
+                return Err(format!("MTLS_IDENTITIES lists '{}' twice", identity));
+            }
+        }
+        Ok(Self(grants))
+    }
+
+    /// The grant for `identity`: create for the default tenant when it isn't listed
+    pub fn grant(&self, identity: &str) -> IdentityGrant {
+        self.0.get(identity).cloned().unwrap_or_else(|| IdentityGrant {
+            role: None,
+            tenant_id: Some(DEFAULT_TENANT.to_string()),
+        })
+    }
+}
+
+/// Build the rustls config: server cert + required client certs from the CA bundle
+pub fn server_config(config: &MtlsConfig) -> Result<Arc<ServerConfig>, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+    let certs = load_certs(&config.cert_path)?;
+    let key = rustls_pemfile::private_key(&mut open(&config.key_path)?)
+        .map_err(|e| format!("Failed to read {}: {}", config.key_path, e))?
+        .ok_or_else(|| format!("No private key in {}", config.key_path))?;
+
+    let mut roots = RootCertStore::empty();
+    for ca in load_certs(&config.client_ca_path)? {
+        roots
+            .add(ca)
+            .map_err(|e| format!("Invalid CA certificate in {}: {}", config.client_ca_path, e))?;
+    }
+
+    let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(|e| format!("Failed to build client verifier: {}", e))?;
+
+    let mut server_config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS protocol setup failed: {}", e))?
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Invalid server certificate/key: {}", e))?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(Arc::new(server_config))
+}
+
+/// Accept mTLS connections forever, serving `router` with the caller's identity attached
+pub async fn serve(
+    addr: SocketAddr,
+    router: Router,
+    tls: Arc<ServerConfig>,
+    identities: Arc<IdentityMap>,
+) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    let acceptor = TlsAcceptor::from(tls);
+    info!(addr = %addr, "mTLS listener started");
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!(error = %e, "mTLS accept failed");
+                continue;
+            }
+        };
+
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+        let identities = identities.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    debug!(peer = %peer, error = %e, "✗ mTLS handshake rejected");
+                    return;
+                }
+            };
+
+            // The verifier guarantees a chain; no usable name means no identity
+            let identity = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .and_then(|cert| identity_from_cert(cert.as_ref()));
+            let Some(identity) = identity else {
+                warn!(peer = %peer, "✗ Client certificate has no CN/SAN, closing connection");
+                return;
+            };
+            let grant = identities.grant(&identity);
+            trace!(peer = %peer, identity = %identity, role = ?grant.role, tenant_id = ?grant.tenant_id, "mTLS client connected");
+
+            let router = router
+                .layer(Extension(ServiceIdentity { name: identity, grant }))
+                .layer(Extension(ConnectInfo(peer)));
+            let service = TowerToHyperService::new(router);
+            if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!(peer = %peer, error = %e, "mTLS connection closed with error");
+            }
+        });
+    }
+}
+
+/// Service identity from a DER certificate: URI SAN, then DNS SAN, then subject CN
+pub fn identity_from_cert(der: &[u8]) -> Option<String> {
+    let (_, cert) = X509Certificate::from_der(der).ok()?;
+
+    if let Ok(Some(san)) = cert.subject_alternative_name() {
+        let names = &san.value.general_names;
+        let uri = names.iter().find_map(|name| match name {
+            GeneralName::URI(uri) => Some(uri.to_string()),
+            _ => None,
+        });
+        let dns = names.iter().find_map(|name| match name {
+            GeneralName::DNSName(dns) => Some(dns.to_string()),
+            _ => None,
+        });
+        if let Some(name) = uri.or(dns) {
+            return Some(name);
+        }
+    }
+
+    let common_name = cert
+        .subject()
+        .iter_common_name()
+        .next()
+        .and_then(|cn| cn.as_str().ok())
+        .map(str::to_string);
+    common_name
+}
+
+fn open(path: &str) -> Result<std::io::BufReader<std::fs::File>, String> {
+    std::fs::File::open(path)
+        .map(std::io::BufReader::new)
+        .map_err(|e| format!("Failed to open {}: {}", path, e))
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+    if certs.is_empty() {
+        return Err(format!("No certificates in {}", path));
+    }
+    Ok(certs)
+}
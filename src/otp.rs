@@ -0,0 +1,64 @@
+//! Built-in one-time-password type (`notification_type = "otp"`): login and verification codes.
+//!
+//! A code is worthless after a few minutes and must not outlive its delivery, so `otp` rows
+//! are handled apart from the configurable types:
+//!
+//! - Ingestion: one recipient only (no topic, audience or broadcast); priority defaults to
+//!   `critical` (past snooze, quiet hours and bundling) and the deadline to
+//!   [`DEFAULT_TTL_SECS`] after the row is due, so it is claimed first and pushes expire.
+//! - Delivery: Bus and push to every device at once, like FIRST_ACK_TYPES.
+//! - Never in the inbox: sync, the inbox snapshot, unread counts and digests skip the type,
+//!   and the worker scrubs the code from the row once it is delivered, suppressed or failed.
+//! - Redaction: logs show [`redact`]ed text and DEBUG_LOG_PAYLOADS records no content.
+//! - Shadow mode: the ledger trigger skips the type, so codes are never published to the
+//!   shadow backend.
+//! - `notifications_otp_delivery_seconds{channel}`: created_at to delivery.
+
+use std::borrow::Cow;
+
+pub const OTP_TYPE: &str = "otp";
+/// Deadline of a code whose producer set none, from when it is due
+pub const DEFAULT_TTL_SECS: i64 = 300;
+/// Digit runs at least this long are taken for codes
+const MIN_CODE_DIGITS: usize = 4;
+const MASK: &str = "••••";
+/// Stands in for content the payload sink doesn't keep
+pub const REDACTED: &str = "<redacted: otp>";
+
+pub fn is_otp(notification_type: &str) -> bool {
+    notification_type == OTP_TYPE
+}
+
+/// `text` with every run of 4 or more digits masked (`Your code is 482913` -> `Your code is ••••`)
+pub fn redact(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut digits = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        flush(&mut redacted, &mut digits);
+        redacted.push(c);
+    }
+    flush(&mut redacted, &mut digits);
+    redacted
+}
+
+fn flush(redacted: &mut String, digits: &mut String) {
+    if digits.len() >= MIN_CODE_DIGITS {
+        redacted.push_str(MASK);
+    } else {
+        redacted.push_str(digits);
+    }
+    digits.clear();
+}
+
+/// Text of a notification as it may be logged: redacted for OTP codes, as is otherwise
+pub fn loggable<'a>(notification_type: &str, text: &'a str) -> Cow<'a, str> {
+    if is_otp(notification_type) {
+        Cow::Owned(redact(text))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
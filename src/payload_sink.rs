@@ -0,0 +1,161 @@
+//! Full request/response payloads for troubleshooting (DEBUG_LOG_PAYLOADS).
+//!
+//! Instead of interleaving bodies with the trace logs, the FCM requests and responses and
+//! the Bus envelopes the worker sends are recorded here as one JSON object each: in a ring
+//! buffer of the last DEBUG_PAYLOAD_BUFFER entries, served at `GET /admin/debug/recent`,
+//! and optionally appended as NDJSON to DEBUG_PAYLOAD_FILE (rotated to `<file>.1` at
+//! DEBUG_PAYLOAD_FILE_MAX_MB). The sink is process-wide: the first service that starts with
+//! DEBUG_LOG_PAYLOADS installs it. FCM tokens are masked unless DEBUG_LOG_FCM_TOKENS is set.
+
+use crate::config::DebugConfig;
+use crate::push::fcm::mask_token;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Mutex, OnceLock};
+use tracing::{info, warn};
+
+/// Entries queued for the file writer before new ones are dropped
+const FILE_QUEUE: usize = 10_000;
+
+static SINK: OnceLock<PayloadSink> = OnceLock::new();
+
+struct PayloadSink {
+    recent: Mutex<VecDeque<PayloadEntry>>,
+    capacity: usize,
+    file: Option<SyncSender<String>>,
+    log_fcm_tokens: bool,
+    seq: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PayloadEntry {
+    pub seq: u64,
+    pub at: DateTime<Utc>,
+    /// `fcm`, `fcm_topic` or `bus`
+    pub kind: &'static str,
+    #[serde(flatten)]
+    pub detail: Map<String, Value>,
+}
+
+/// Install the sink when DEBUG_LOG_PAYLOADS is set (no-op if one is installed already)
+pub fn install(config: &DebugConfig) -> Result<(), String> {
+    if !config.log_payloads || SINK.get().is_some() {
+        return Ok(());
+    }
+    let file = match &config.payload_file {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            let file = open(&path).map_err(|e| format!("Cannot open DEBUG_PAYLOAD_FILE {}: {}", path.display(), e))?;
+            let (sender, receiver) = sync_channel(FILE_QUEUE);
+            let max_bytes = config.payload_file_max_mb.max(1) * 1024 * 1024;
+            std::thread::Builder::new()
+                .name("payload-sink".to_string())
+                .spawn(move || write_file(receiver, path, file, max_bytes))
+                .map_err(|e| format!("Cannot start the payload file writer: {}", e))?;
+            Some(sender)
+        }
+        None => None,
+    };
+    info!(
+        buffer = config.payload_buffer,
+        file = config.payload_file.as_deref().unwrap_or("-"),
+        "⚠️  Payload logging enabled (DEBUG_LOG_PAYLOADS) - full bodies are kept"
+    );
+    let _ = SINK.set(PayloadSink {
+        recent: Mutex::new(VecDeque::with_capacity(config.payload_buffer)),
+        capacity: config.payload_buffer,
+        file,
+        log_fcm_tokens: config.log_fcm_tokens,
+        seq: AtomicU64::new(0),
+    });
+    Ok(())
+}
+
+/// Whether payloads are recorded (callers skip building them otherwise)
+pub fn enabled() -> bool {
+    SINK.get().is_some()
+}
+
+/// Record one payload; `build` returns a JSON object and only runs when the sink is on
+pub fn record(kind: &'static str, build: impl FnOnce() -> Value) {
+    let Some(sink) = SINK.get() else {
+        return;
+    };
+    let detail = match build() {
+        Value::Object(detail) => detail,
+        other => Map::from_iter([("body".to_string(), other)]),
+    };
+    let entry = PayloadEntry { seq: sink.seq.fetch_add(1, Ordering::Relaxed) + 1, at: Utc::now(), kind, detail };
+
+    if let Some(file) = &sink.file {
+        if let Ok(line) = serde_json::to_string(&entry) {
+            if let Err(TrySendError::Full(_)) = file.try_send(line) {
+                metrics::counter!("notifications_debug_payloads_dropped_total").increment(1);
+            }
+        }
+    }
+    if sink.capacity > 0 {
+        if let Ok(mut recent) = sink.recent.lock() {
+            if recent.len() == sink.capacity {
+                recent.pop_front();
+            }
+            recent.push_back(entry);
+        }
+    }
+}
+
+/// The newest entries first, optionally of one kind (None = the sink is off)
+pub fn recent(kind: Option<&str>, limit: usize) -> Option<Vec<PayloadEntry>> {
+    let sink = SINK.get()?;
+    let recent = sink.recent.lock().ok()?;
+    Some(
+        recent
+            .iter()
+            .rev()
+            .filter(|entry| kind.is_none_or(|kind| entry.kind == kind))
+            .take(limit)
+            .cloned()
+            .collect(),
+    )
+}
+
+/// An FCM token as it may appear in a recorded payload
+pub fn token(fcm_token: &str) -> String {
+    match SINK.get() {
+        Some(sink) if sink.log_fcm_tokens => fcm_token.to_string(),
+        _ => mask_token(fcm_token),
+    }
+}
+
+fn open(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// File writer thread: appends lines, moves the file to `<file>.1` when it is full
+fn write_file(receiver: Receiver<String>, path: PathBuf, mut file: File, max_bytes: u64) {
+    let mut size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let rotated = PathBuf::from(format!("{}.1", path.display()));
+    for line in receiver {
+        if size + line.len() as u64 + 1 > max_bytes && size > 0 {
+            let reopened = std::fs::rename(&path, &rotated).and_then(|()| open(&path));
+            match reopened {
+                Ok(new_file) => {
+                    file = new_file;
+                    size = 0;
+                }
+                Err(e) => warn!(path = %path.display(), error = %e, "Failed to rotate the payload file"),
+            }
+        }
+        match writeln!(file, "{}", line) {
+            Ok(()) => size += line.len() as u64 + 1,
+            Err(e) => warn!(path = %path.display(), error = %e, "Failed to write the payload file"),
+        }
+    }
+}
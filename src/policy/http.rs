@@ -0,0 +1,73 @@
+use super::{ContentPolicy, PolicyError, PolicyVerdict};
+use crate::models::Notification;
+use axum::async_trait;
+use std::time::Duration;
+use tracing::trace;
+
+/// POSTs the content to POLICY_HOOK_URL and reads `{action, policy, reason, title, ...}` back
+///
+/// Any non-2xx answer or unreadable body is a hook failure (see POLICY_HOOK_FAIL_MODE).
+pub struct HttpPolicyHook {
+    http: reqwest::Client,
+    url: String,
+    name: String,
+}
+
+impl HttpPolicyHook {
+    /// `timeout` caps the request; `PolicyGate` applies the same bound to the whole review
+    pub fn new(url: &str, timeout: Duration) -> Result<Self, String> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid POLICY_HOOK_URL '{}': {}", url, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("POLICY_HOOK_URL must be http(s), got '{}'", url));
+        }
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| format!("Failed to create policy hook client: {}", e))?;
+        Ok(Self {
+            http,
+            name: parsed.host_str().unwrap_or("policy_hook").to_string(),
+            url: url.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl ContentPolicy for HttpPolicyHook {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn review(&self, notification: &Notification) -> Result<PolicyVerdict, PolicyError> {
+        trace!(id = %notification.id, url = %self.url, "Calling policy hook");
+        let body = serde_json::json!({
+            "notification_id": notification.id,
+            "tenant_id": notification.tenant_id,
+            "user_id": notification.user_id,
+            "notification_type": notification.notification_type,
+            "title": notification.title,
+            "message": notification.message,
+            "payload": notification.payload,
+            "deep_link": notification.deep_link,
+            "message_key": notification.message_key,
+            "message_args": notification.message_args,
+            "template_key": notification.template_key,
+            "created_by": notification.created_by,
+        });
+        let response = self
+            .http
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| PolicyError(e.to_string()))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(PolicyError(format!("hook answered HTTP {}", status.as_u16())));
+        }
+        response
+            .json::<PolicyVerdict>()
+            .await
+            .map_err(|e| PolicyError(format!("unreadable verdict: {}", e)))
+    }
+}
@@ -0,0 +1,166 @@
+//! Content policy hook: veto or rewrite a notification right before delivery.
+//!
+//! [`ContentPolicy`] reviews the content (strip profanity, enforce branding, block a
+//! campaign) and answers allow, rewrite or veto. [`HttpPolicyHook`] POSTs the notification
+//! to POLICY_HOOK_URL; another implementation can be handed to `ServiceBuilder::content_policy`.
+//! [`PolicyGate`] bounds every review by POLICY_HOOK_TIMEOUT_MS and applies
+//! POLICY_HOOK_FAIL_MODE when the hook fails: open delivers unreviewed, closed retries the
+//! notification with backoff. The decision travels with the notification to its receipt.
+
+pub mod http;
+
+pub use http::HttpPolicyHook;
+
+use crate::config::PolicyFailMode;
+use crate::models::Notification;
+use axum::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Reviews notification content before it is delivered
+#[async_trait]
+pub trait ContentPolicy: Send + Sync {
+    /// Recorded as the policy when the verdict doesn't name one
+    fn name(&self) -> &str;
+
+    async fn review(&self, notification: &Notification) -> Result<PolicyVerdict, PolicyError>;
+}
+
+/// A hook's answer (the HTTP hook's response body)
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolicyVerdict {
+    pub action: PolicyAction,
+    /// Which rule decided (e.g. `profanity-v2`)
+    #[serde(default)]
+    pub policy: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Rewrite only: replacements, absent fields stay as they were
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub payload: Option<serde_json::Value>,
+    /// Template and catalog variables, so rendered text is rewritten too
+    #[serde(default)]
+    pub message_args: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    Allow,
+    Rewrite,
+    Veto,
+}
+
+/// The hook failed or timed out
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Policy hook failed: {0}")]
+pub struct PolicyError(pub String);
+
+/// What was applied, as recorded on the receipt (`policy`)
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyDecision {
+    /// allowed | rewritten | vetoed
+    pub decision: &'static str,
+    pub policy: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Hook failure that POLICY_HOOK_FAIL_MODE=open delivered through
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The worker's view of a review
+pub enum Reviewed {
+    /// Deliver this (the original, or the rewritten copy) with `policy` set
+    Deliver(Box<Notification>),
+    Vetoed(PolicyDecision),
+}
+
+/// Timeout and fail mode around a [`ContentPolicy`]
+#[derive(Clone)]
+pub struct PolicyGate {
+    hook: Arc<dyn ContentPolicy>,
+    timeout: Duration,
+    fail_mode: PolicyFailMode,
+}
+
+impl PolicyGate {
+    pub fn new(hook: Arc<dyn ContentPolicy>, timeout: Duration, fail_mode: PolicyFailMode) -> Self {
+        Self { hook, timeout, fail_mode }
+    }
+
+    /// Review a notification - Err only when the hook failed and the fail mode is closed
+    pub async fn review(&self, notification: &Notification) -> Result<Reviewed, PolicyError> {
+        let start = Instant::now();
+        let verdict = match tokio::time::timeout(self.timeout, self.hook.review(notification)).await {
+            Ok(result) => result,
+            Err(_) => Err(PolicyError(format!("no answer within {}ms", self.timeout.as_millis()))),
+        };
+        metrics::histogram!("notifications_policy_review_duration_seconds").record(start.elapsed().as_secs_f64());
+
+        let verdict = match verdict {
+            Ok(verdict) => verdict,
+            Err(e) if self.fail_mode == PolicyFailMode::Closed => {
+                metrics::counter!("notifications_policy_decisions_total", "decision" => "hook_failed").increment(1);
+                return Err(e);
+            }
+            Err(e) => {
+                warn!(id = %notification.id, error = %e, "Policy hook failed, delivering unreviewed (fail open)");
+                metrics::counter!("notifications_policy_decisions_total", "decision" => "failed_open").increment(1);
+                let mut unreviewed = notification.clone();
+                unreviewed.policy = Some(PolicyDecision {
+                    decision: "allowed",
+                    policy: self.hook.name().to_string(),
+                    reason: None,
+                    error: Some(e.0),
+                });
+                return Ok(Reviewed::Deliver(Box::new(unreviewed)));
+            }
+        };
+
+        let decision = |decision: &'static str| PolicyDecision {
+            decision,
+            policy: verdict.policy.clone().unwrap_or_else(|| self.hook.name().to_string()),
+            reason: verdict.reason.clone(),
+            error: None,
+        };
+        let reviewed = match verdict.action {
+            PolicyAction::Veto => Reviewed::Vetoed(decision("vetoed")),
+            PolicyAction::Allow => {
+                let mut allowed = notification.clone();
+                allowed.policy = Some(decision("allowed"));
+                Reviewed::Deliver(Box::new(allowed))
+            }
+            PolicyAction::Rewrite => {
+                let mut rewritten = notification.clone();
+                rewritten.policy = Some(decision("rewritten"));
+                if let Some(title) = verdict.title.clone() {
+                    rewritten.title = title;
+                }
+                if let Some(message) = verdict.message.clone() {
+                    rewritten.message = Some(message);
+                }
+                if let Some(payload) = verdict.payload.clone() {
+                    rewritten.payload = Some(payload);
+                }
+                if let Some(message_args) = verdict.message_args.clone() {
+                    rewritten.message_args = Some(message_args);
+                }
+                Reviewed::Deliver(Box::new(rewritten))
+            }
+        };
+        let label = match &reviewed {
+            Reviewed::Vetoed(_) => "vetoed",
+            Reviewed::Deliver(n) => n.policy.as_ref().map_or("allowed", |policy| policy.decision),
+        };
+        debug!(id = %notification.id, decision = label, "Policy hook reviewed notification");
+        metrics::counter!("notifications_policy_decisions_total", "decision" => label).increment(1);
+        Ok(reviewed)
+    }
+}
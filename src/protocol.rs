@@ -0,0 +1,149 @@
+//! Wire protocol versions of the messages clients receive over the Bus.
+//!
+//! v1 is the original shape. v2 is the same message with the version in it (`"v": 2`),
+//! so later versions can change shapes and clients can tell them apart. A client asks for a
+//! version at connect with `?protocol=2`; websocket-bus owns the connection, settles the
+//! version with [`Protocols::negotiate`] and subscribes the connection to that version's
+//! topics ([`Protocol::topic`]: v1 keeps the plain topic, v2 adds `.v2`). The service
+//! publishes every message once per version in WS_PROTOCOLS (default `1`), so during a
+//! migration window (`1,2`) old and new app builds are served side by side; dropping `1`
+//! afterwards ends it.
+
+use crate::realtime::RealtimeBus;
+use bus_client::{BusEnvelope, BusResult};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Protocol {
+    V1,
+    V2,
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 2] = [Protocol::V1, Protocol::V2];
+
+    pub fn number(self) -> u8 {
+        match self {
+            Protocol::V1 => 1,
+            Protocol::V2 => 2,
+        }
+    }
+
+    /// The Bus topic connections on this version subscribe to
+    pub fn topic(self, topic: &str) -> String {
+        match self {
+            Protocol::V1 => topic.to_string(),
+            _ => format!("{}.v{}", topic, self.number()),
+        }
+    }
+
+    /// Shape a server message (a JSON object with `type`) for this version
+    pub fn encode(self, mut message: Value) -> Value {
+        if let (Protocol::V2, Value::Object(fields)) = (self, &mut message) {
+            fields.insert("v".to_string(), Value::from(self.number()));
+        }
+        message
+    }
+}
+
+/// The versions the service emits (WS_PROTOCOLS, e.g. `1,2`), lowest first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Protocols(Vec<Protocol>);
+
+impl Default for Protocols {
+    fn default() -> Self {
+        Self(vec![Protocol::V1])
+    }
+}
+
+impl Protocols {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut versions = Vec::new();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let version = Protocol::ALL
+                .into_iter()
+                .find(|p| entry.trim_start_matches('v').parse() == Ok(p.number()))
+                .ok_or_else(|| format!("Unknown protocol version '{}' in WS_PROTOCOLS (1, 2)", entry))?;
+            if !versions.contains(&version) {
+                versions.push(version);
+            }
+        }
+        if versions.is_empty() {
+            return Err("WS_PROTOCOLS must list at least one version".to_string());
+        }
+        versions.sort();
+        Ok(Self(versions))
+    }
+
+    pub fn versions(&self) -> &[Protocol] {
+        &self.0
+    }
+
+    /// The version for a client that asked for `requested` (the `protocol` query parameter)
+    ///
+    /// The highest emitted version up to the request. Clients that don't ask are v1 builds;
+    /// a request below every emitted version gets the lowest one.
+    pub fn negotiate(&self, requested: Option<&str>) -> Protocol {
+        let requested: u8 = requested.and_then(|r| r.trim().parse().ok()).unwrap_or(1);
+        self.0
+            .iter()
+            .rev()
+            .find(|p| p.number() <= requested)
+            .or(self.0.first())
+            .copied()
+            .unwrap_or(Protocol::V1)
+    }
+
+    /// One envelope per emitted version: the message shaped for it, on its topic
+    pub fn envelopes(&self, topic: &str, event_type: &str, message: &Value) -> Vec<BusEnvelope> {
+        self.0
+            .iter()
+            .map(|p| BusEnvelope::new(p.topic(topic), event_type).with_payload(p.encode(message.clone())))
+            .collect()
+    }
+
+    /// Publish to a user's connections in every version - the connections reached
+    ///
+    /// Fails only when no version could be published.
+    pub async fn publish_to_user(
+        &self,
+        bus: &dyn RealtimeBus,
+        user_id: Uuid,
+        topic: &str,
+        event_type: &str,
+        message: &Value,
+    ) -> BusResult<usize> {
+        let mut results = Vec::with_capacity(self.0.len());
+        for envelope in self.envelopes(topic, event_type, message) {
+            results.push(bus.publish_to_user(user_id, &envelope).await);
+        }
+        combine(results)
+    }
+
+    /// Publish to everyone on a topic in every version (broadcasts)
+    pub async fn publish(&self, bus: &dyn RealtimeBus, topic: &str, event_type: &str, message: &Value) -> BusResult<usize> {
+        let mut results = Vec::with_capacity(self.0.len());
+        for envelope in self.envelopes(topic, event_type, message) {
+            results.push(bus.publish(&envelope).await);
+        }
+        combine(results)
+    }
+}
+
+/// Connections reached by the versions that were published, or the first error if none was
+fn combine(results: Vec<BusResult<usize>>) -> BusResult<usize> {
+    let mut delivered_to = None;
+    let mut error = None;
+    for result in results {
+        match result {
+            Ok(count) => *delivered_to.get_or_insert(0) += count,
+            Err(e) => error = error.or(Some(e)),
+        }
+    }
+    match (delivered_to, error) {
+        (Some(delivered_to), _) => Ok(delivered_to),
+        (None, Some(e)) => Err(e),
+        (None, None) => Ok(0),
+    }
+}
@@ -1,20 +1,34 @@
-use crate::models::Notification;
+use crate::models::{DeviceCapabilities, Notification};
+use crate::otp;
+use crate::payload_sink;
+use chrono::Utc;
 use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, trace, warn};
 
 const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
-const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+/// Default endpoints; overridable (FCM_BASE_URL / FCM_TOKEN_URL) to point at a mock
+pub const FCM_BASE_URL: &str = "https://fcm.googleapis.com";
+pub const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+/// FCM rejects messages whose payload (notification + data) is larger than this
+pub const MAX_PAYLOAD_BYTES: usize = 4096;
+/// Data keys an oversized message keeps; `fetch_full` tells the app to get the rest via sync
+const ESSENTIAL_DATA_KEYS: [&str; 5] = ["id", "type", "deep_link", "actions", "bundle_count"];
+/// APNs rejects an `apns-collapse-id` longer than this
+const MAX_COLLAPSE_ID_BYTES: usize = 64;
 
 /// FCM HTTP v1 API Client
 pub struct FcmClient {
     client: Client,
     project_id: String,
     service_account: ServiceAccount,
+    fcm_base_url: String,
+    token_url: String,
     /// Cached access token with expiry
     token_cache: Arc<RwLock<Option<CachedToken>>>,
 }
@@ -30,7 +44,6 @@ struct CachedToken {
 struct ServiceAccount {
     client_email: String,
     private_key: String,
-    project_id: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -48,14 +61,37 @@ struct TokenResponse {
     expires_in: u64,
 }
 
-#[derive(Debug, Serialize)]
-struct FcmRequest {
-    message: FcmMessage,
+/// Body of a successful `messages:send`
+#[derive(Debug, Deserialize)]
+struct SendResponse {
+    /// `projects/{project_id}/messages/{message_id}`
+    name: String,
+}
+
+/// FCM message for one notification, serialized once and sent to any number of devices
+///
+/// Holds the `message` fields except `token`; `body_for` splices the token in, so a
+/// fan-out to N devices serializes the notification once instead of N times.
+#[derive(Debug, Clone)]
+pub struct PreparedPush {
+    notification_id: uuid::Uuid,
+    /// `"notification":{..},"data":{..},...` (a JSON object without its braces)
+    fields: Arc<str>,
+    /// Carries an OTP code: the payload sink logs a placeholder instead of the body
+    redacted: bool,
+}
+
+impl PreparedPush {
+    /// Request body for one device
+    pub fn body_for(&self, fcm_token: &str) -> String {
+        let token = serde_json::Value::from(fcm_token);
+        format!("{{\"message\":{{\"token\":{},{}}}}}", token, self.fields)
+    }
 }
 
+/// `message` of a device send, without the per-device `token`
 #[derive(Debug, Serialize)]
 struct FcmMessage {
-    token: String,
     notification: FcmNotification,
     data: std::collections::HashMap<String, String>,
     android: AndroidConfig,
@@ -66,16 +102,90 @@ struct FcmMessage {
 struct FcmNotification {
     title: String,
     body: String,
+    /// Pre-signed URL of the first image attachment, shown expanded by Android and iOS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 struct AndroidConfig {
     priority: String,
+    /// How long FCM holds the message for an offline device (`"3600s"`; unset = 4 weeks)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ttl: Option<String>,
+    /// A newer message with the same key replaces an undelivered one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collapse_key: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 struct ApnsConfig {
+    #[serde(skip_serializing_if = "ApnsHeaders::is_empty")]
+    headers: ApnsHeaders,
     payload: ApnsPayload,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fcm_options: Option<ApnsFcmOptions>,
+}
+
+/// The image an iOS notification service extension downloads (needs `mutable-content`)
+#[derive(Debug, Serialize)]
+struct ApnsFcmOptions {
+    image: String,
+}
+
+/// APNs request headers FCM passes through: the iOS side of `ttl` / `collapse_key`
+#[derive(Debug, Default, Serialize)]
+struct ApnsHeaders {
+    /// Unix time after which APNs stops trying ("0" = try once, don't store)
+    #[serde(rename = "apns-expiration", skip_serializing_if = "Option::is_none")]
+    expiration: Option<String>,
+    #[serde(rename = "apns-collapse-id", skip_serializing_if = "Option::is_none")]
+    collapse_id: Option<String>,
+}
+
+impl ApnsHeaders {
+    fn is_empty(&self) -> bool {
+        self.expiration.is_none() && self.collapse_id.is_none()
+    }
+}
+
+/// Expiry and collapse settings of a push, in both providers' terms
+#[derive(Debug, Default)]
+struct PushLifetime {
+    ttl: Option<String>,
+    collapse_key: Option<String>,
+    apns: ApnsHeaders,
+}
+
+impl PushLifetime {
+    /// From the worker's `push_expires_at` and, for low priority, the group key or type
+    ///
+    /// A push prepared after its expiry (a slow fan-out) gets a zero TTL: both providers try
+    /// once and drop it rather than hold it. Collapse keys longer than APNs allows are hashed,
+    /// the same on both platforms.
+    fn of(notification: &Notification) -> Self {
+        let (ttl, expiration) = match notification.push_expires_at {
+            Some(expires_at) => {
+                let secs = (expires_at - Utc::now()).num_seconds().max(0);
+                let expiration = if secs == 0 { "0".to_string() } else { expires_at.timestamp().to_string() };
+                (Some(format!("{}s", secs)), Some(expiration))
+            }
+            None => (None, None),
+        };
+        let collapse_key = notification.is_low_priority().then(|| {
+            let key = notification.group_key.as_deref().unwrap_or(&notification.notification_type);
+            if key.len() <= MAX_COLLAPSE_ID_BYTES {
+                key.to_string()
+            } else {
+                Sha256::digest(key.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+            }
+        });
+        Self {
+            ttl,
+            collapse_key: collapse_key.clone(),
+            apns: ApnsHeaders { expiration, collapse_id: collapse_key },
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -89,24 +199,45 @@ struct Aps {
     badge: i32,
     #[serde(rename = "content-available")]
     content_available: i32,
+    /// Groups notifications with the same thread-id in Notification Center
+    #[serde(rename = "thread-id", skip_serializing_if = "Option::is_none")]
+    thread_id: Option<String>,
+    /// Notification with actions: the app's UNNotificationCategory with the buttons (the type)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    /// 1 = the app's notification service extension may fetch the attachments first
+    #[serde(rename = "mutable-content", skip_serializing_if = "Option::is_none")]
+    mutable_content: Option<i32>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum FcmError {
+    #[error("FCM client not initialized")]
     NotInitialized,
+    #[error("OAuth token error: {0}")]
     TokenError(String),
+    /// The request got no answer (connect error, timeout)
+    #[error("FCM send error: {0}")]
     SendError(String),
+    /// FCM answered with an error status
+    #[error("FCM send error: {status}: {body}")]
+    Rejected { status: reqwest::StatusCode, body: String },
+    #[error("Invalid FCM device token")]
     InvalidToken,
 }
 
-impl std::fmt::Display for FcmError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            FcmError::NotInitialized => write!(f, "FCM client not initialized"),
-            FcmError::TokenError(e) => write!(f, "OAuth token error: {}", e),
-            FcmError::SendError(e) => write!(f, "FCM send error: {}", e),
-            FcmError::InvalidToken => write!(f, "Invalid FCM device token"),
-        }
+impl FcmError {
+    /// Rate limited (429 QUOTA_EXCEEDED / RESOURCE_EXHAUSTED)
+    pub fn is_quota(&self) -> bool {
+        matches!(self, FcmError::Rejected { status, body }
+            if *status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                || body.contains("QUOTA_EXCEEDED")
+                || body.contains("RESOURCE_EXHAUSTED"))
+    }
+
+    /// FCM refused the message itself (400): sending it again won't help
+    pub fn is_rejected_message(&self) -> bool {
+        matches!(self, FcmError::Rejected { status, .. } if *status == reqwest::StatusCode::BAD_REQUEST)
     }
 }
 
@@ -130,8 +261,13 @@ impl FcmClient {
                 format!("Failed to read credentials: {}", e)
             })?;
 
+        Self::from_json(&content, project_id)
+    }
+
+    /// Create new FCM client from service account JSON (e.g. from a secret backend)
+    pub fn from_json(content: &str, project_id: &str) -> Result<Self, String> {
         trace!("Parsing service account JSON...");
-        let service_account: ServiceAccount = serde_json::from_str(&content)
+        let service_account: ServiceAccount = serde_json::from_str(content)
             .map_err(|e| {
                 error!(error = %e, "Failed to parse FCM credentials JSON");
                 format!("Failed to parse credentials: {}", e)
@@ -147,10 +283,32 @@ impl FcmClient {
             client: Client::new(),
             project_id: project_id.to_string(),
             service_account,
+            fcm_base_url: FCM_BASE_URL.to_string(),
+            token_url: TOKEN_URL.to_string(),
             token_cache: Arc::new(RwLock::new(None)),
         })
     }
 
+    /// Send to other FCM / OAuth2 endpoints (e.g. `push::mock::MockFcm`)
+    pub fn with_endpoints(mut self, fcm_base_url: &str, token_url: &str) -> Self {
+        debug!(fcm_base_url = %fcm_base_url, token_url = %token_url, "FCM endpoints overridden");
+        self.fcm_base_url = fcm_base_url.trim_end_matches('/').to_string();
+        self.token_url = token_url.to_string();
+        self
+    }
+
+    pub fn fcm_base_url(&self) -> &str {
+        &self.fcm_base_url
+    }
+
+    pub fn token_url(&self) -> &str {
+        &self.token_url
+    }
+
+    fn send_url(&self) -> String {
+        format!("{}/v1/projects/{}/messages:send", self.fcm_base_url, self.project_id)
+    }
+
     /// Get valid OAuth2 access token (cached or fresh)
     async fn get_access_token(&self) -> Result<String, FcmError> {
         trace!("Checking OAuth2 token cache...");
@@ -221,7 +379,7 @@ impl FcmClient {
         let claims = JwtClaims {
             iss: self.service_account.client_email.clone(),
             scope: FCM_SCOPE.to_string(),
-            aud: TOKEN_URL.to_string(),
+            aud: self.token_url.clone(),
             iat: now,
             exp: now + 3600, // 1 hour
         };
@@ -261,7 +419,7 @@ impl FcmClient {
 
         let response = self
             .client
-            .post(TOKEN_URL)
+            .post(&self.token_url)
             .form(&[
                 ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
                 ("assertion", &jwt),
@@ -317,36 +475,16 @@ impl FcmClient {
         })
     }
 
-    /// Send push notification to a single device
-    pub async fn send(
-        &self,
-        fcm_token: &str,
-        notification: &Notification,
-    ) -> Result<(), FcmError> {
-        let start = Instant::now();
-        let token_preview = mask_token(fcm_token);
-
-        trace!(
-            token = %token_preview,
-            id = %notification.id,
-            notification_type = %notification.notification_type,
-            "Sending FCM push notification..."
-        );
-
-        // Get OAuth2 token
-        let token_start = Instant::now();
-        let access_token = self.get_access_token().await?;
-        let token_time = token_start.elapsed();
-        trace!(
-            duration_ms = token_time.as_millis() as u64,
-            "OAuth2 token retrieved"
-        );
-
-        let url = format!(
-            "https://fcm.googleapis.com/v1/projects/{}/messages:send",
-            self.project_id
-        );
+    /// Serialize the FCM v1 message for a notification (once per fan-out)
+    pub fn prepare(notification: &Notification) -> PreparedPush {
+        Self::prepare_for(notification, &DeviceCapabilities::default())
+    }
 
+    /// The message shaped to what a device declared it handles (once per kind of device)
+    ///
+    /// Without action support the buttons are left out (the notification still shows);
+    /// a lower payload limit trims the message like FCM's own limit does.
+    pub fn prepare_for(notification: &Notification, capabilities: &DeviceCapabilities) -> PreparedPush {
         // Build request data
         let mut data = std::collections::HashMap::new();
         data.insert(
@@ -360,6 +498,24 @@ impl FcmClient {
         if let Some(deep_link) = &notification.deep_link {
             data.insert("deep_link".to_string(), deep_link.clone());
         }
+        // Android has no server-side grouping: the app groups by this key
+        if let Some(group_key) = &notification.group_key {
+            data.insert("group_key".to_string(), group_key.clone());
+        }
+        // Buttons the app adds to the notification (JSON array of {id, title, input})
+        let actions = if capabilities.supports_actions() { notification.inline_actions() } else { Vec::new() };
+        if !actions.is_empty() {
+            data.insert("actions".to_string(), serde_json::to_string(&actions).unwrap_or_default());
+        }
+        // A bundle: the app shows the count ("5 new comments"), the text is the first event's
+        if let Some(count) = notification.bundle_count {
+            data.insert("bundle_count".to_string(), count.to_string());
+        }
+        // Pre-signed media (JSON array of {url, content_type, expires_at}), the first image inline
+        if !notification.media.is_empty() {
+            data.insert("attachments".to_string(), serde_json::to_string(&notification.media).unwrap_or_default());
+        }
+        let image = notification.media.iter().find(|media| media.is_image()).map(|media| media.url.clone());
 
         let priority = notification.priority.as_deref().unwrap_or("normal");
         let android_priority = if priority == "high" || priority == "critical" {
@@ -367,44 +523,254 @@ impl FcmClient {
         } else {
             "normal"
         };
+        let lifetime = PushLifetime::of(notification);
 
-        let request = FcmRequest {
-            message: FcmMessage {
-                token: fcm_token.to_string(),
-                notification: FcmNotification {
-                    title: notification.title.clone(),
-                    body: notification.message.clone().unwrap_or_default(),
-                },
-                data,
-                android: AndroidConfig {
-                    priority: android_priority.to_string(),
-                },
-                apns: ApnsConfig {
-                    payload: ApnsPayload {
-                        aps: Aps {
-                            sound: "default".to_string(),
-                            badge: 1,
-                            content_available: 1,
-                        },
+        let mut message = FcmMessage {
+            notification: FcmNotification {
+                title: notification.title.clone(),
+                body: notification.message.clone().unwrap_or_default(),
+                image: image.clone(),
+            },
+            data,
+            android: AndroidConfig {
+                priority: android_priority.to_string(),
+                ttl: lifetime.ttl,
+                collapse_key: lifetime.collapse_key,
+            },
+            apns: ApnsConfig {
+                headers: lifetime.apns,
+                payload: ApnsPayload {
+                    aps: Aps {
+                        sound: "default".to_string(),
+                        badge: 1,
+                        content_available: 1,
+                        thread_id: notification.group_key.clone(),
+                        category: (!actions.is_empty()).then(|| notification.notification_type.clone()),
+                        mutable_content: (!notification.media.is_empty()).then_some(1),
                     },
                 },
+                fcm_options: image.map(|image| ApnsFcmOptions { image }),
             },
         };
 
+        let limit = capabilities
+            .max_payload_bytes
+            .map_or(MAX_PAYLOAD_BYTES, |bytes| (bytes as usize).min(MAX_PAYLOAD_BYTES));
+        if let Some(trimmed) = fit_payload(&mut message, limit) {
+            warn!(
+                id = %notification.id,
+                notification_type = %notification.notification_type,
+                trimmed = trimmed,
+                payload_bytes = payload_size(&message),
+                "FCM payload over {} bytes - trimmed",
+                limit
+            );
+            metrics::counter!("notifications_fcm_payload_trimmed_total", "trimmed" => trimmed).increment(1);
+        }
+
+        // Plain strings and maps: serialization can't fail
+        let json = serde_json::to_string(&message).unwrap_or_else(|_| "{}".to_string());
+        PreparedPush {
+            notification_id: notification.id,
+            fields: Arc::from(&json[1..json.len() - 1]),
+            redacted: otp::is_otp(&notification.notification_type),
+        }
+    }
+
+    /// Data-only message telling a device to take down a notification another device acked
+    ///
+    /// No `notification` block, so nothing is shown; iOS gets it as a background push.
+    pub fn prepare_dismiss(notification_id: uuid::Uuid) -> PreparedPush {
+        let message = serde_json::json!({
+            "data": { "action": "dismiss", "id": notification_id.to_string() },
+            "android": { "priority": "high" },
+            "apns": {
+                "headers": { "apns-push-type": "background", "apns-priority": "5" },
+                "payload": { "aps": { "content-available": 1 } },
+            },
+        });
+        let json = message.to_string();
+        PreparedPush {
+            notification_id,
+            fields: Arc::from(&json[1..json.len() - 1]),
+            redacted: false,
+        }
+    }
+
+    /// Silent message telling a user's devices that notifications were read elsewhere
+    ///
+    /// Data-only like a dismiss; on iOS the background push also sets the app badge.
+    /// `ids` is comma-separated because FCM data values must be strings.
+    pub fn prepare_read_state(ids: &[uuid::Uuid], unread_count: i64) -> PreparedPush {
+        let joined = ids.iter().map(ToString::to_string).collect::<Vec<_>>().join(",");
+        let message = serde_json::json!({
+            "data": {
+                "action": "read_state_changed",
+                "ids": joined,
+                "unread_count": unread_count.to_string(),
+            },
+            "apns": {
+                "headers": { "apns-push-type": "background", "apns-priority": "5" },
+                "payload": { "aps": { "content-available": 1, "badge": unread_count } },
+            },
+        });
+        let json = message.to_string();
+        PreparedPush {
+            notification_id: ids.first().copied().unwrap_or_default(),
+            fields: Arc::from(&json[1..json.len() - 1]),
+            redacted: false,
+        }
+    }
+
+    /// Build the FCM v1 request for a topic send (broadcasts)
+    fn build_topic_request(topic: &str, notification: &Notification, extra_data: &[(&str, String)]) -> serde_json::Value {
+        // Build request data
+        let mut data = std::collections::HashMap::new();
+        data.insert("id".to_string(), notification.id.to_string());
+        data.insert("type".to_string(), notification.notification_type.clone());
+        if let Some(deep_link) = &notification.deep_link {
+            data.insert("deep_link".to_string(), deep_link.clone());
+        }
+        if let Some(group_key) = &notification.group_key {
+            data.insert("group_key".to_string(), group_key.clone());
+        }
+        for (key, value) in extra_data {
+            data.insert(key.to_string(), value.clone());
+        }
+        if !notification.media.is_empty() {
+            data.insert("attachments".to_string(), serde_json::to_string(&notification.media).unwrap_or_default());
+        }
+
+        // Construct message payload for Topic
+        // Note: For topics, we use 'topic' field instead of 'token'
+        // Ideally, we might want 'condition' for more complex logic, but 'topic' is simpler.
+        let mut request = serde_json::json!({
+            "message": {
+                "topic": topic,
+                "notification": {
+                    "title": notification.title,
+                    "body": notification.message.as_deref().unwrap_or_default(),
+                },
+                "data": data,
+                "android": {
+                    "priority": "high", // Broadcasts usually important
+                },
+                "apns": {
+                    "payload": {
+                        "aps": {
+                            "sound": "default",
+                            "badge": 1,
+                            "content-available": 1,
+                        }
+                    }
+                }
+            }
+        });
+        let lifetime = PushLifetime::of(notification);
+        if let Some(ttl) = lifetime.ttl {
+            request["message"]["android"]["ttl"] = serde_json::json!(ttl);
+        }
+        if let Some(collapse_key) = lifetime.collapse_key {
+            request["message"]["android"]["collapse_key"] = serde_json::json!(collapse_key);
+        }
+        if !lifetime.apns.is_empty() {
+            request["message"]["apns"]["headers"] = serde_json::json!(lifetime.apns);
+        }
+        if let Some(image) = notification.media.iter().find(|media| media.is_image()) {
+            request["message"]["notification"]["image"] = serde_json::json!(image.url);
+            request["message"]["apns"]["payload"]["aps"]["mutable-content"] = serde_json::json!(1);
+            request["message"]["apns"]["fcm_options"] = serde_json::json!({ "image": image.url });
+        }
+        request
+    }
+
+    /// Topic request body exactly as `send_to_topic` sends it (previews, wire-format tests)
+    pub fn topic_request_preview(topic: &str, notification: &Notification, extra_data: &[(&str, String)]) -> serde_json::Value {
+        Self::build_topic_request(topic, notification, extra_data)
+    }
+
+    /// Dismiss request body exactly as it would be sent (wire-format tests)
+    pub fn dismiss_request_preview(fcm_token: &str, notification_id: uuid::Uuid) -> serde_json::Value {
+        serde_json::from_str(&Self::prepare_dismiss(notification_id).body_for(fcm_token)).unwrap_or_default()
+    }
+
+    /// Read-state request body exactly as it would be sent (wire-format tests)
+    pub fn read_state_request_preview(fcm_token: &str, ids: &[uuid::Uuid], unread_count: i64) -> serde_json::Value {
+        serde_json::from_str(&Self::prepare_read_state(ids, unread_count).body_for(fcm_token)).unwrap_or_default()
+    }
+
+    /// FCM v1 request body exactly as it would be sent (for previews/dry runs)
+    pub fn request_preview(fcm_token: &str, notification: &Notification) -> serde_json::Value {
+        Self::request_preview_for(fcm_token, notification, &DeviceCapabilities::default())
+    }
+
+    /// FCM v1 request body as a device with these capabilities would get it
+    pub fn request_preview_for(
+        fcm_token: &str,
+        notification: &Notification,
+        capabilities: &DeviceCapabilities,
+    ) -> serde_json::Value {
+        serde_json::from_str(&Self::prepare_for(notification, capabilities).body_for(fcm_token)).unwrap_or_default()
+    }
+
+    /// Send push notification to a single device
+    ///
+    /// Returns the message name FCM assigned, see `send_prepared`.
+    pub async fn send(
+        &self,
+        fcm_token: &str,
+        notification: &Notification,
+    ) -> Result<Option<String>, FcmError> {
+        self.send_prepared(fcm_token, &Self::prepare(notification)).await
+    }
+
+    /// Send a message from `prepare` to a single device (fan-outs reuse one `PreparedPush`)
+    ///
+    /// Returns the message name FCM assigned (`projects/{project_id}/messages/{id}`), the
+    /// reference Google support asks for. None when the success body didn't have one.
+    pub async fn send_prepared(
+        &self,
+        fcm_token: &str,
+        push: &PreparedPush,
+    ) -> Result<Option<String>, FcmError> {
+        let start = Instant::now();
+        let token_preview = mask_token(fcm_token);
+
         trace!(
-            title = %notification.title,
-            body = notification.message.as_deref().unwrap_or(""),
-            android_priority = %android_priority,
-            "FCM request payload prepared"
+            token = %token_preview,
+            id = %push.notification_id,
+            "Sending FCM push notification..."
         );
 
+        // Get OAuth2 token
+        let token_start = Instant::now();
+        let access_token = self.get_access_token().await?;
+        let token_time = token_start.elapsed();
+        trace!(
+            duration_ms = token_time.as_millis() as u64,
+            "OAuth2 token retrieved"
+        );
+
+        let url = self.send_url();
+
+        let body = push.body_for(fcm_token);
+        trace!(body_bytes = body.len(), "FCM request payload prepared");
+        let logged_request = payload_sink::enabled().then(|| {
+            if push.redacted {
+                serde_json::json!({ "message": { "token": fcm_token, "notification": otp::REDACTED } }).to_string()
+            } else {
+                body.clone()
+            }
+        });
+
         // Send request
         let send_start = Instant::now();
         let response = self
             .client
             .post(&url)
             .bearer_auth(&access_token)
-            .json(&request)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
             .send()
             .await
             .map_err(|e| {
@@ -428,17 +794,33 @@ impl FcmClient {
         );
 
         if status.is_success() {
+            let response = response.text().await.unwrap_or_default();
+            if let Some(request) = logged_request {
+                log_exchange(fcm_token, push.notification_id, &request, status, &response);
+            }
+            // The push went out either way: a body we can't read only costs the reference
+            let message_name = match serde_json::from_str::<SendResponse>(&response) {
+                Ok(sent) => Some(sent.name),
+                Err(e) => {
+                    warn!(token = %token_preview, error = %e, "FCM success response without a message name");
+                    None
+                }
+            };
             debug!(
                 token = %token_preview,
                 status = %status,
+                message_name = message_name.as_deref().unwrap_or("-"),
                 total_duration_ms = total_time.as_millis() as u64,
                 send_duration_ms = send_time.as_millis() as u64,
                 "✓ FCM push sent successfully"
             );
-            return Ok(());
+            return Ok(message_name);
         }
 
         let body = response.text().await.unwrap_or_default();
+        if let Some(request) = logged_request {
+            log_exchange(fcm_token, push.notification_id, &request, status, &body);
+        }
 
         // Check for invalid token errors
         if body.contains("UNREGISTERED") || body.contains("INVALID_ARGUMENT") {
@@ -459,14 +841,15 @@ impl FcmClient {
             duration_ms = total_time.as_millis() as u64,
             "FCM send failed"
         );
-        Err(FcmError::SendError(format!("{}: {}", status, body)))
+        Err(FcmError::Rejected { status, body })
     }
 
-    /// Send push notification to a topic (Broadcast)
+    /// Send push notification to a topic (Broadcast); `extra_data` is added to the data map
     pub async fn send_to_topic(
         &self,
         topic: &str,
         notification: &Notification,
+        extra_data: &[(&str, String)],
     ) -> Result<(), FcmError> {
         let start = Instant::now();
 
@@ -480,44 +863,9 @@ impl FcmClient {
         // Get OAuth2 token
         let access_token = self.get_access_token().await?;
 
-        let url = format!(
-            "https://fcm.googleapis.com/v1/projects/{}/messages:send",
-            self.project_id
-        );
+        let url = self.send_url();
 
-        // Build request data
-        let mut data = std::collections::HashMap::new();
-        data.insert("id".to_string(), notification.id.to_string());
-        data.insert("type".to_string(), notification.notification_type.clone());
-        if let Some(deep_link) = &notification.deep_link {
-            data.insert("deep_link".to_string(), deep_link.clone());
-        }
-
-        // Construct message payload for Topic
-        // Note: For topics, we use 'topic' field instead of 'token'
-        // Ideally, we might want 'condition' for more complex logic, but 'topic' is simpler.
-        let request = serde_json::json!({
-            "message": {
-                "topic": topic,
-                "notification": {
-                    "title": notification.title,
-                    "body": notification.message.as_deref().unwrap_or_default(),
-                },
-                "data": data,
-                "android": {
-                    "priority": "high", // Broadcasts usually important
-                },
-                "apns": {
-                    "payload": {
-                        "aps": {
-                            "sound": "default",
-                            "badge": 1,
-                            "content-available": 1,
-                        }
-                    }
-                }
-            }
-        });
+        let request = Self::build_topic_request(topic, notification, extra_data);
 
         // Send request
         let response = self
@@ -534,6 +882,20 @@ impl FcmClient {
 
         let status = response.status();
         let total_time = start.elapsed();
+        let body = if status.is_success() && !payload_sink::enabled() {
+            String::new()
+        } else {
+            response.text().await.unwrap_or_default()
+        };
+        payload_sink::record("fcm_topic", || {
+            serde_json::json!({
+                "topic": topic,
+                "notification_id": notification.id,
+                "request": request,
+                "status": status.as_u16(),
+                "response": parse_or_text(&body),
+            })
+        });
 
         if status.is_success() {
             info!(
@@ -544,20 +906,88 @@ impl FcmClient {
             );
             Ok(())
         } else {
-            let body = response.text().await.unwrap_or_default();
             error!(
                 topic = %topic,
                 status = %status,
                 body = %body,
                 "FCM broadcast failed"
             );
-            Err(FcmError::SendError(format!("{}: {}", status, body)))
+            Err(FcmError::Rejected { status, body })
         }
     }
 }
 
+/// Bytes FCM counts against MAX_PAYLOAD_BYTES
+fn payload_size(message: &FcmMessage) -> usize {
+    serde_json::to_string(&message.notification).map_or(0, |json| json.len())
+        + serde_json::to_string(&message.data).map_or(0, |json| json.len())
+}
+
+/// Shrink an oversized message until it is at most `limit` bytes: first the data map (down to
+/// ESSENTIAL_DATA_KEYS plus `fetch_full`), then the body, then the title
+///
+/// Returns the last part that had to give (the metrics label), or None if it already fit.
+/// A message that is still too large (e.g. a huge deep link) goes out as is; over
+/// MAX_PAYLOAD_BYTES FCM answers 400, which is not retried.
+fn fit_payload(message: &mut FcmMessage, limit: usize) -> Option<&'static str> {
+    if payload_size(message) <= limit {
+        return None;
+    }
+
+    message.data.retain(|key, _| ESSENTIAL_DATA_KEYS.contains(&key.as_str()));
+    message.data.insert("fetch_full".to_string(), "true".to_string());
+    let size = payload_size(message);
+    if size <= limit {
+        return Some("data");
+    }
+
+    truncate_text(&mut message.notification.body, size - limit);
+    let size = payload_size(message);
+    if size <= limit {
+        return Some("body");
+    }
+
+    truncate_text(&mut message.notification.title, size - limit);
+    Some("title")
+}
+
+/// Cut at least `excess` bytes off the end of `text` (on a char boundary), marking the cut with …
+fn truncate_text(text: &mut String, excess: usize) {
+    const ELLIPSIS: &str = "…";
+    let mut keep = text.len().saturating_sub(excess + ELLIPSIS.len());
+    while !text.is_char_boundary(keep) {
+        keep -= 1;
+    }
+    text.truncate(keep);
+    if !text.is_empty() {
+        text.push_str(ELLIPSIS);
+    }
+}
+
+/// Record a device send for the payload sink (DEBUG_LOG_PAYLOADS), token masked unless allowed
+fn log_exchange(fcm_token: &str, notification_id: uuid::Uuid, request: &str, status: reqwest::StatusCode, response: &str) {
+    payload_sink::record("fcm", || {
+        let mut request = parse_or_text(request);
+        if request["message"]["token"].is_string() {
+            request["message"]["token"] = serde_json::json!(payload_sink::token(fcm_token));
+        }
+        serde_json::json!({
+            "token": payload_sink::token(fcm_token),
+            "notification_id": notification_id,
+            "request": request,
+            "status": status.as_u16(),
+            "response": parse_or_text(response),
+        })
+    });
+}
+
+/// A body as JSON when it is JSON, else as a string
+fn parse_or_text(body: &str) -> serde_json::Value {
+    serde_json::from_str(body).unwrap_or_else(|_| serde_json::json!(body))
+}
+
 /// Mask FCM token for logging (security)
-fn mask_token(token: &str) -> String {
+pub fn mask_token(token: &str) -> String {
     if token.len() > 12 {
         format!("{}...{}", &token[..6], &token[token.len()-4..])
     } else if token.len() > 4 {
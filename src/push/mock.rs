@@ -0,0 +1,255 @@
+//! Mock of the FCM HTTP v1 and OAuth2 token endpoints for tests and local dev.
+//!
+//! Point an `FcmClient` at it with `FcmClient::with_endpoints(mock.base_url(), mock.token_url())`
+//! (or FCM_BASE_URL / FCM_TOKEN_URL) and credentials from `service_account_json()`.
+//! Responses are configurable per device token: success, UNREGISTERED, 429 or 500,
+//! and every accepted send request is recorded for assertions.
+
+use super::FcmClient;
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tracing::{debug, error, info};
+
+/// Test-only RSA key: the mock never verifies the JWT, FcmClient just needs something to sign with
+const MOCK_PRIVATE_KEY: &str = include_str!("mock_key.pem");
+const MOCK_ACCESS_TOKEN: &str = "mock-access-token";
+
+/// How the mock answers a send request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockResponse {
+    Success,
+    /// 404 UNREGISTERED: the device token is gone
+    Unregistered,
+    /// 429 QUOTA_EXCEEDED with Retry-After
+    RateLimited,
+    /// 500 INTERNAL
+    ServerError,
+}
+
+impl MockResponse {
+    /// `success`, `unregistered`, `rate_limited` or `server_error`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "success" => Some(Self::Success),
+            "unregistered" => Some(Self::Unregistered),
+            "rate_limited" => Some(Self::RateLimited),
+            "server_error" => Some(Self::ServerError),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Unregistered => "unregistered",
+            Self::RateLimited => "rate_limited",
+            Self::ServerError => "server_error",
+        }
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    default_response: Mutex<Option<MockResponse>>,
+    per_token: Mutex<HashMap<String, MockResponse>>,
+    /// `message` objects of every send request, with the answer they got
+    sent: Mutex<Vec<(Value, MockResponse)>>,
+    /// Oldest send requests are dropped beyond this many (None = all kept)
+    keep_last: Mutex<Option<usize>>,
+    token_requests: AtomicUsize,
+}
+
+impl MockState {
+    fn response_for(&self, message: &Value) -> MockResponse {
+        let token = message["token"].as_str().unwrap_or_default();
+        if let Some(response) = self.per_token.lock().expect("mock lock poisoned").get(token) {
+            return *response;
+        }
+        self.default_response
+            .lock()
+            .expect("mock lock poisoned")
+            .unwrap_or(MockResponse::Success)
+    }
+}
+
+/// A running mock FCM server (stops with the runtime it was started on)
+#[derive(Clone)]
+pub struct MockFcm {
+    addr: SocketAddr,
+    state: Arc<MockState>,
+}
+
+impl MockFcm {
+    /// Serve on a random local port
+    pub async fn start() -> std::io::Result<Self> {
+        Self::bind("127.0.0.1:0").await
+    }
+
+    /// Serve on a fixed address (local dev)
+    pub async fn bind(addr: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(MockState::default());
+
+        let app = router(state.clone());
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                error!(error = %e, "Mock FCM server stopped");
+            }
+        });
+        info!(addr = %addr, "Mock FCM server started");
+
+        Ok(Self { addr, state })
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn token_url(&self) -> String {
+        format!("http://{}/token", self.addr)
+    }
+
+    /// FcmClient wired to this mock
+    pub fn client(&self, project_id: &str) -> FcmClient {
+        FcmClient::from_json(&service_account_json(project_id), project_id)
+            .expect("mock service account is valid")
+            .with_endpoints(&self.base_url(), &self.token_url())
+    }
+
+    /// Response for all tokens without a specific one (default: success)
+    pub fn respond_with(&self, response: MockResponse) {
+        *self.state.default_response.lock().expect("mock lock poisoned") = Some(response);
+    }
+
+    /// Response for one device token
+    pub fn respond_for_token(&self, token: &str, response: MockResponse) {
+        self.state
+            .per_token
+            .lock()
+            .expect("mock lock poisoned")
+            .insert(token.to_string(), response);
+    }
+
+    /// Keep only the newest `limit` send requests (a mock that runs for days: the developer sandbox)
+    pub fn keep_last(self, limit: usize) -> Self {
+        *self.state.keep_last.lock().expect("mock lock poisoned") = Some(limit);
+        self
+    }
+
+    /// Messages of all send requests so far, in order
+    pub fn sent(&self) -> Vec<Value> {
+        let sent = self.state.sent.lock().expect("mock lock poisoned");
+        sent.iter().map(|(message, _)| message.clone()).collect()
+    }
+
+    /// Messages of all send requests so far with their answers, in order
+    pub fn sent_with_responses(&self) -> Vec<(Value, MockResponse)> {
+        self.state.sent.lock().expect("mock lock poisoned").clone()
+    }
+
+    /// Send requests since the last call, with their answers (long runs: keeps memory flat)
+    pub fn take_sent(&self) -> Vec<(Value, MockResponse)> {
+        std::mem::take(&mut *self.state.sent.lock().expect("mock lock poisoned"))
+    }
+
+    /// Messages sent to one device token
+    pub fn sent_to(&self, token: &str) -> Vec<Value> {
+        self.sent()
+            .into_iter()
+            .filter(|message| message["token"] == token)
+            .collect()
+    }
+
+    /// Number of OAuth2 token exchanges (tests the token cache)
+    pub fn token_requests(&self) -> usize {
+        self.state.token_requests.load(Ordering::Relaxed)
+    }
+}
+
+/// Service account JSON accepted by `FcmClient::from_json`
+pub fn service_account_json(project_id: &str) -> String {
+    json!({
+        "type": "service_account",
+        "project_id": project_id,
+        "client_email": format!("mock@{}.iam.gserviceaccount.com", project_id),
+        "private_key": MOCK_PRIVATE_KEY,
+    })
+    .to_string()
+}
+
+fn router(state: Arc<MockState>) -> Router {
+    Router::new()
+        .route("/token", post(token))
+        // `messages:send` is not a valid route segment, match the rest of the path
+        .route("/v1/projects/*path", post(send))
+        .with_state(state)
+}
+
+async fn token(State(state): State<Arc<MockState>>) -> Json<Value> {
+    state.token_requests.fetch_add(1, Ordering::Relaxed);
+    Json(json!({
+        "access_token": MOCK_ACCESS_TOKEN,
+        "expires_in": 3600,
+        "token_type": "Bearer",
+    }))
+}
+
+async fn send(State(state): State<Arc<MockState>>, Path(path): Path<String>, Json(body): Json<Value>) -> Response {
+    let Some(project_id) = path.strip_suffix("/messages:send") else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let message = body["message"].clone();
+    let response = state.response_for(&message);
+    debug!(project_id = %project_id, response = ?response, "Mock FCM send");
+    {
+        let mut sent = state.sent.lock().expect("mock lock poisoned");
+        sent.push((message, response));
+        if let Some(limit) = *state.keep_last.lock().expect("mock lock poisoned") {
+            let excess = sent.len().saturating_sub(limit);
+            sent.drain(..excess);
+        }
+    }
+
+    match response {
+        MockResponse::Success => {
+            let name = format!("projects/{}/messages/{}", project_id, uuid::Uuid::new_v4());
+            Json(json!({ "name": name })).into_response()
+        }
+        MockResponse::Unregistered => fcm_error(StatusCode::NOT_FOUND, "NOT_FOUND", "UNREGISTERED"),
+        MockResponse::RateLimited => {
+            let mut response = fcm_error(StatusCode::TOO_MANY_REQUESTS, "RESOURCE_EXHAUSTED", "QUOTA_EXCEEDED");
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, header::HeaderValue::from_static("1"));
+            response
+        }
+        MockResponse::ServerError => fcm_error(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL", "INTERNAL"),
+    }
+}
+
+/// Error body in the shape FCM v1 returns
+fn fcm_error(status: StatusCode, grpc_status: &str, error_code: &str) -> Response {
+    let body = json!({
+        "error": {
+            "code": status.as_u16(),
+            "message": format!("Mock FCM error: {}", error_code),
+            "status": grpc_status,
+            "details": [{
+                "@type": "type.googleapis.com/google.firebase.fcm.v1.FcmError",
+                "errorCode": error_code,
+            }],
+        }
+    });
+    (status, Json(body)).into_response()
+}
@@ -1,3 +1,4 @@
 pub mod fcm;
+pub mod mock;
 
 pub use fcm::FcmClient;
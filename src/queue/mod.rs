@@ -0,0 +1,63 @@
+//! The queue the worker takes notifications from.
+//!
+//! [`QueueStore`] is what the batch worker needs from a queue backend: fetch the due rows,
+//! find the next `deliver_at`, record each outcome, and wake the worker when rows arrive.
+//! Production uses [`PgQueueStore`] on `activity.notifications`, woken by NOTIFY or a logical
+//! replication slot (WAKE_SOURCE). Another backend (CockroachDB, MySQL via sqlx-any, an
+//! in-memory queue for tests) can be handed to `ServiceBuilder::queue_store` by implementing
+//! the trait.
+//!
+//! Only the queue itself is behind the trait. Inserts, devices, preferences, bundles, topics
+//! and the rest of the worker's lookups still go to the Postgres pool. CONSUMPTION_MODE=
+//! transactional holds a Postgres row lock for the whole delivery, so it needs
+//! `PgQueueStore`.
+
+pub mod postgres;
+
+pub use postgres::PgQueueStore;
+
+use crate::db::listener::WakeSignal;
+use crate::models::Notification;
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Due notifications and their outcomes - updates return false when the row was already done
+#[async_trait]
+pub trait QueueStore: Send + Sync {
+    /// Logged at startup (`postgres`, ...)
+    fn name(&self) -> &str;
+
+    /// Due rows, oldest first; critical/high first with `by_priority`, round-robin across
+    /// tenants with `fair`. A user's rows must keep their order either way.
+    async fn fetch_due(&self, limit: i64, by_priority: bool, fair: bool) -> Result<Vec<Notification>, sqlx::Error>;
+
+    /// Earliest `deliver_at` of anything unprocessed (None = queue empty)
+    async fn next_deliver_at(&self) -> Result<Option<DateTime<Utc>>, sqlx::Error>;
+
+    async fn mark_success(&self, id: Uuid) -> Result<bool, sqlx::Error>;
+
+    /// Record a failed attempt - returns true once `max_retries` is reached (stop trying)
+    async fn mark_failure(
+        &self,
+        id: Uuid,
+        error_message: &str,
+        category: &str,
+        max_retries: i32,
+    ) -> Result<bool, sqlx::Error>;
+
+    /// Terminal without delivering (opted out, suppressed, expired, ...)
+    async fn mark_suppressed(&self, id: Uuid, reason: &str) -> Result<bool, sqlx::Error>;
+
+    /// Keep the row unprocessed, due again at `until`
+    async fn defer(&self, id: Uuid, until: DateTime<Utc>) -> Result<(), sqlx::Error>;
+
+    /// Delivered on the Bus: due again at `until` for the push fallback unless acked by then
+    async fn await_bus_ack(&self, id: Uuid, until: DateTime<Utc>) -> Result<(), sqlx::Error>;
+
+    /// Send `signal` whenever rows are inserted, until an unrecoverable error
+    ///
+    /// A store that can't push returns Ok(()) right away; the worker's failsafe poll then
+    /// finds new rows.
+    async fn listen(&self, signal: WakeSignal) -> Result<(), sqlx::Error>;
+}
@@ -0,0 +1,98 @@
+use super::QueueStore;
+use crate::config::{Config, WakeSource};
+use crate::db::listener::WakeSignal;
+use crate::db::{Database, DatabaseHosts, NotificationListener, NotificationQueries, RegionClaim, ReplicationSource};
+use crate::models::Notification;
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::info;
+use uuid::Uuid;
+
+/// `activity.notifications`, woken by NOTIFY or a logical replication slot (WAKE_SOURCE)
+pub struct PgQueueStore {
+    pool: PgPool,
+    hosts: DatabaseHosts,
+    wake_source: WakeSource,
+    user_burst: u32,
+    user_refill: Duration,
+    publication: String,
+    replication_poll: Duration,
+    /// REGION: only this region's rows
+    region: Option<RegionClaim>,
+}
+
+impl PgQueueStore {
+    pub fn from_config(db: &Database, config: &Config) -> Self {
+        Self {
+            pool: db.pool().clone(),
+            hosts: db.hosts.clone(),
+            wake_source: config.wake_source,
+            user_burst: config.worker_wake_user_burst,
+            user_refill: Duration::from_millis(config.worker_wake_user_refill_ms),
+            publication: config.replication_publication.clone(),
+            replication_poll: Duration::from_millis(config.replication_poll_interval_ms.max(10)),
+            region: RegionClaim::from_config(config),
+        }
+    }
+}
+
+#[async_trait]
+impl QueueStore for PgQueueStore {
+    fn name(&self) -> &str {
+        "postgres"
+    }
+
+    async fn fetch_due(&self, limit: i64, by_priority: bool, fair: bool) -> Result<Vec<Notification>, sqlx::Error> {
+        NotificationQueries::fetch_unprocessed(&self.pool, limit, by_priority, fair, self.region.as_ref()).await
+    }
+
+    async fn next_deliver_at(&self) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        NotificationQueries::next_deliver_at(&self.pool).await
+    }
+
+    async fn mark_success(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        NotificationQueries::mark_success(&self.pool, id).await
+    }
+
+    async fn mark_failure(
+        &self,
+        id: Uuid,
+        error_message: &str,
+        category: &str,
+        max_retries: i32,
+    ) -> Result<bool, sqlx::Error> {
+        NotificationQueries::mark_failure(&self.pool, id, error_message, category, max_retries).await
+    }
+
+    async fn mark_suppressed(&self, id: Uuid, reason: &str) -> Result<bool, sqlx::Error> {
+        NotificationQueries::mark_suppressed(&self.pool, id, reason).await
+    }
+
+    async fn defer(&self, id: Uuid, until: DateTime<Utc>) -> Result<(), sqlx::Error> {
+        NotificationQueries::defer(&self.pool, id, until).await
+    }
+
+    async fn await_bus_ack(&self, id: Uuid, until: DateTime<Utc>) -> Result<(), sqlx::Error> {
+        NotificationQueries::await_bus_ack(&self.pool, id, until).await
+    }
+
+    async fn listen(&self, signal: WakeSignal) -> Result<(), sqlx::Error> {
+        match self.wake_source {
+            WakeSource::Notify => {
+                info!("NOTIFY listener started");
+                NotificationListener::new(self.hosts.clone())
+                    .with_user_burst(self.user_burst, self.user_refill)
+                    .listen(signal)
+                    .await
+            }
+            WakeSource::Replication => {
+                info!(publication = %self.publication, "Replication wake source started");
+                ReplicationSource::new(self.hosts.clone(), self.publication.clone(), self.replication_poll)
+                    .run(signal)
+                    .await
+            }
+        }
+    }
+}
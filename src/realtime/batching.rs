@@ -0,0 +1,96 @@
+//! Groups the worker's per-user publishes into bulk `publish_to_users` requests.
+//!
+//! The worker delivers a claimed batch in WORKER_CONCURRENCY lanes, and each notification
+//! needs its own user's connection count back (the push fallback depends on it). Wrapped
+//! in [`UserBatchingBus`], the publishes those lanes make at the same time, plus those
+//! that queue up while a request is in flight, go out as one envelope list; each caller
+//! still gets its own result. With a single lane there is nothing to group, as deliveries
+//! run one after another to keep per-user order.
+
+use super::RealtimeBus;
+use axum::async_trait;
+use bus_client::{BusEnvelope, BusError, BusResult};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+use tracing::trace;
+use uuid::Uuid;
+
+/// Most messages sent in one `publish_to_users` request
+const MAX_USER_BATCH: usize = 100;
+
+/// A `publish_to_user` waiting for the next request
+struct Pending {
+    user_id: Uuid,
+    envelope: BusEnvelope,
+    reply: oneshot::Sender<BusResult<usize>>,
+}
+
+/// [`RealtimeBus`] that batches `publish_to_user` calls; everything else passes through
+pub struct UserBatchingBus {
+    inner: Arc<dyn RealtimeBus>,
+    pending: mpsc::UnboundedSender<Pending>,
+}
+
+impl UserBatchingBus {
+    /// Wrap `inner` and start the task that sends the batches (needs a Tokio runtime)
+    pub fn new(inner: Arc<dyn RealtimeBus>) -> Self {
+        let (pending, queue) = mpsc::unbounded_channel();
+        tokio::spawn(send_batches(inner.clone(), queue));
+        Self { inner, pending }
+    }
+}
+
+/// Send what is queued as one request per round, until the bus is dropped
+async fn send_batches(bus: Arc<dyn RealtimeBus>, mut queue: mpsc::UnboundedReceiver<Pending>) {
+    while let Some(first) = queue.recv().await {
+        // Let the other lanes of the same batch reach their publish first
+        tokio::task::yield_now().await;
+        let mut batch = vec![first];
+        while batch.len() < MAX_USER_BATCH {
+            match queue.try_recv() {
+                Ok(pending) => batch.push(pending),
+                Err(_) => break,
+            }
+        }
+
+        let (messages, replies): (Vec<_>, Vec<_>) =
+            batch.into_iter().map(|p| ((p.user_id, p.envelope), p.reply)).unzip();
+        trace!(messages = messages.len(), "Publishing user batch to Bus");
+        let results = bus.publish_to_users(&messages).await;
+        // A caller that went away (shutdown) doesn't need its result
+        for (reply, result) in replies.into_iter().zip(results) {
+            let _ = reply.send(result);
+        }
+    }
+}
+
+fn stopped() -> BusError {
+    BusError::ServerError("Bus batch publisher stopped".to_string())
+}
+
+#[async_trait]
+impl RealtimeBus for UserBatchingBus {
+    async fn publish(&self, envelope: &BusEnvelope) -> BusResult<usize> {
+        self.inner.publish(envelope).await
+    }
+
+    async fn publish_to_user(&self, user_id: Uuid, envelope: &BusEnvelope) -> BusResult<usize> {
+        let (reply, result) = oneshot::channel();
+        self.pending
+            .send(Pending { user_id, envelope: envelope.clone(), reply })
+            .map_err(|_| stopped())?;
+        result.await.unwrap_or_else(|_| Err(stopped()))
+    }
+
+    async fn publish_batch(&self, envelopes: &[BusEnvelope]) -> BusResult<usize> {
+        self.inner.publish_batch(envelopes).await
+    }
+
+    async fn publish_to_users(&self, messages: &[(Uuid, BusEnvelope)]) -> Vec<BusResult<usize>> {
+        self.inner.publish_to_users(messages).await
+    }
+
+    async fn health_check(&self) -> BusResult<bool> {
+        self.inner.health_check().await
+    }
+}
@@ -0,0 +1,168 @@
+//! In-process [`RealtimeBus`] for tests and local dev.
+//!
+//! Nothing leaves the process: every envelope is recorded for assertions, users are
+//! "connected" with [`MemoryBus::connect`] (publishes to users without connections reach
+//! nobody, like websocket-bus), [`MemoryBus::fail_with`] makes publishes fail to
+//! exercise the fallback and retry paths, and [`MemoryBus::set_latency`] makes them slow.
+
+use super::RealtimeBus;
+use axum::async_trait;
+use bus_client::{BusEnvelope, BusError, BusResult};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// One recorded publish
+#[derive(Debug, Clone)]
+pub struct Published {
+    /// None for topic publishes
+    pub user_id: Option<Uuid>,
+    pub envelope: BusEnvelope,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    /// Open connections per user
+    connections: HashMap<Uuid, usize>,
+    /// Connections reached by a topic publish
+    subscribers: usize,
+    failure: Option<String>,
+    /// How long every publish takes
+    latency: Duration,
+    unhealthy: bool,
+    published: Vec<Published>,
+    /// Publish calls, a batch counting once
+    requests: usize,
+}
+
+/// Clones share the recorded publishes and connections
+#[derive(Clone, Default)]
+pub struct MemoryBus {
+    state: Arc<Mutex<MemoryState>>,
+}
+
+impl MemoryBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Give a user `connections` open connections (0 disconnects them)
+    pub fn connect(&self, user_id: Uuid, connections: usize) {
+        self.state().connections.insert(user_id, connections);
+    }
+
+    /// Connections every topic publish reaches (default 0)
+    pub fn set_subscribers(&self, subscribers: usize) {
+        self.state().subscribers = subscribers;
+    }
+
+    /// Fail every publish with a server error until `recover`
+    pub fn fail_with(&self, message: &str) {
+        self.state().failure = Some(message.to_string());
+    }
+
+    /// Make every publish take `latency` (default none)
+    pub fn set_latency(&self, latency: Duration) {
+        self.state().latency = latency;
+    }
+
+    /// Report unhealthy to the health probe until `recover`
+    pub fn set_unhealthy(&self) {
+        self.state().unhealthy = true;
+    }
+
+    pub fn recover(&self) {
+        let mut state = self.state();
+        state.failure = None;
+        state.unhealthy = false;
+    }
+
+    /// Every publish so far, in order (failed ones are not recorded)
+    pub fn published(&self) -> Vec<Published> {
+        self.state().published.clone()
+    }
+
+    /// Publishes since the last call (long runs: keeps memory flat)
+    pub fn take_published(&self) -> Vec<Published> {
+        std::mem::take(&mut self.state().published)
+    }
+
+    /// Publish calls so far (a batch counts once, failed ones too)
+    pub fn requests(&self) -> usize {
+        self.state().requests
+    }
+
+    /// Envelopes published to one user
+    pub fn published_to(&self, user_id: Uuid) -> Vec<BusEnvelope> {
+        self.published()
+            .into_iter()
+            .filter(|p| p.user_id == Some(user_id))
+            .map(|p| p.envelope)
+            .collect()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MemoryState> {
+        self.state.lock().expect("memory bus lock poisoned")
+    }
+
+    /// Count a publish call and wait out the configured latency (the lock isn't held meanwhile)
+    async fn delay(&self) {
+        let latency = {
+            let mut state = self.state();
+            state.requests += 1;
+            state.latency
+        };
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+    }
+
+    fn record(&self, user_id: Option<Uuid>, envelope: &BusEnvelope) -> BusResult<usize> {
+        let mut state = self.state();
+        if let Some(message) = &state.failure {
+            return Err(BusError::ServerError(message.clone()));
+        }
+        let delivered_to = match user_id {
+            Some(user_id) => state.connections.get(&user_id).copied().unwrap_or(0),
+            None => state.subscribers,
+        };
+        state.published.push(Published { user_id, envelope: envelope.clone() });
+        Ok(delivered_to)
+    }
+}
+
+#[async_trait]
+impl RealtimeBus for MemoryBus {
+    async fn publish(&self, envelope: &BusEnvelope) -> BusResult<usize> {
+        self.delay().await;
+        self.record(None, envelope)
+    }
+
+    async fn publish_to_user(&self, user_id: Uuid, envelope: &BusEnvelope) -> BusResult<usize> {
+        self.delay().await;
+        self.record(Some(user_id), envelope)
+    }
+
+    async fn publish_batch(&self, envelopes: &[BusEnvelope]) -> BusResult<usize> {
+        self.delay().await;
+        let mut delivered_to = 0;
+        for envelope in envelopes {
+            delivered_to += self.record(None, envelope)?;
+        }
+        Ok(delivered_to)
+    }
+
+    async fn publish_to_users(&self, messages: &[(Uuid, BusEnvelope)]) -> Vec<BusResult<usize>> {
+        self.delay().await;
+        messages.iter().map(|(user_id, envelope)| self.record(Some(*user_id), envelope)).collect()
+    }
+
+    async fn health_check(&self) -> BusResult<bool> {
+        let state = self.state();
+        match &state.failure {
+            Some(message) => Err(BusError::ServerError(message.clone())),
+            None => Ok(!state.unhealthy),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! The real-time channel the worker and API publish client messages on.
+//!
+//! [`RealtimeBus`] is what the service needs from a bus: publish to a topic, to one user's
+//! connections, a batch (to topics or to users), and a health probe. Production uses websocket-bus through
+//! [`BusClient`] (WEBSOCKET_BUS_URL); tests and local runs can hand [`MemoryBus`] to
+//! `ServiceBuilder::bus_client` and assert on what was published, and another transport
+//! (NATS, Redis pub/sub) only has to implement the trait. The worker publishes through
+//! [`UserBatchingBus`], which groups the per-user publishes of concurrent lanes.
+
+pub mod batching;
+pub mod memory;
+
+pub use batching::UserBatchingBus;
+pub use memory::MemoryBus;
+
+use axum::async_trait;
+use bus_client::{BusClient, BusEnvelope, BusResult};
+use uuid::Uuid;
+
+/// Publishes envelopes to connected clients - every call returns the connections reached
+#[async_trait]
+pub trait RealtimeBus: Send + Sync {
+    /// Publish to every connection subscribed to the envelope's topic
+    async fn publish(&self, envelope: &BusEnvelope) -> BusResult<usize>;
+
+    /// Publish to the connections of one user
+    async fn publish_to_user(&self, user_id: Uuid, envelope: &BusEnvelope) -> BusResult<usize>;
+
+    /// Publish several envelopes in one request
+    async fn publish_batch(&self, envelopes: &[BusEnvelope]) -> BusResult<usize>;
+
+    /// Publish each envelope to its user's connections - the result per message, in order
+    async fn publish_to_users(&self, messages: &[(Uuid, BusEnvelope)]) -> Vec<BusResult<usize>>;
+
+    /// Whether the bus is reachable and healthy
+    async fn health_check(&self) -> BusResult<bool>;
+}
+
+#[async_trait]
+impl RealtimeBus for BusClient {
+    async fn publish(&self, envelope: &BusEnvelope) -> BusResult<usize> {
+        BusClient::publish(self, envelope).await.map(|r| r.delivered_to)
+    }
+
+    async fn publish_to_user(&self, user_id: Uuid, envelope: &BusEnvelope) -> BusResult<usize> {
+        BusClient::publish_to_user(self, user_id, envelope).await.map(|r| r.delivered_to)
+    }
+
+    async fn publish_batch(&self, envelopes: &[BusEnvelope]) -> BusResult<usize> {
+        BusClient::publish_batch(self, envelopes).await.map(|r| r.total_delivered)
+    }
+
+    async fn publish_to_users(&self, messages: &[(Uuid, BusEnvelope)]) -> Vec<BusResult<usize>> {
+        // bus-client's batch call is topic-routed with one total, so the per-user
+        // publishes go out side by side instead
+        let publishes = messages.iter().map(|(user_id, envelope)| BusClient::publish_to_user(self, *user_id, envelope));
+        futures::future::join_all(publishes)
+            .await
+            .into_iter()
+            .map(|result| result.map(|r| r.delivered_to))
+            .collect()
+    }
+
+    async fn health_check(&self) -> BusResult<bool> {
+        BusClient::health_check(self).await
+    }
+}
@@ -0,0 +1,147 @@
+//! Delivery receipts: signed POSTs to producer callback URLs.
+//!
+//! The worker queues receipts in `activity.receipt_deliveries` when a notification
+//! reaches a terminal state; the [`ReceiptDispatcher`] sends them with retries.
+//! Each request carries `X-Receipt-Timestamp` and
+//! `X-Receipt-Signature: sha256=<hex HMAC-SHA256 of "<timestamp>.<body>">`.
+
+use crate::db::receipts::PendingReceipt;
+use crate::db::ReceiptQueries;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument, trace, warn};
+
+/// Receipts claimed per dispatch round
+const BATCH_SIZE: i64 = 50;
+/// Lease on claimed receipts; must exceed the HTTP timeout
+const LEASE_SECS: i64 = 60;
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct ReceiptDispatcher {
+    pool: PgPool,
+    http: reqwest::Client,
+    signing_secret: String,
+    max_attempts: i32,
+}
+
+impl ReceiptDispatcher {
+    pub fn new(pool: PgPool, signing_secret: String, max_attempts: i32) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            pool,
+            http,
+            signing_secret,
+            max_attempts,
+        }
+    }
+
+    /// Dispatch loop (polls the outbox)
+    #[instrument(skip(self), name = "receipt_dispatcher")]
+    pub async fn run(&self) {
+        info!(max_attempts = self.max_attempts, "Receipt dispatcher started");
+
+        loop {
+            match ReceiptQueries::claim_due(&self.pool, BATCH_SIZE, self.max_attempts, LEASE_SECS).await {
+                Ok(receipts) if receipts.is_empty() => {
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+                Ok(receipts) => {
+                    debug!(count = receipts.len(), "Dispatching receipts");
+                    for receipt in receipts {
+                        self.send(&receipt).await;
+                    }
+                }
+                Err(e) => {
+                    error!(error = %e, "Failed to claim receipts");
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    /// POST one receipt and record the outcome
+    async fn send(&self, receipt: &PendingReceipt) {
+        let body = receipt.body.to_string();
+        let timestamp = Utc::now().timestamp().to_string();
+        let signature = sign(self.signing_secret.as_bytes(), &timestamp, &body);
+
+        trace!(id = receipt.id, url = %receipt.url, attempt = receipt.attempts, "Sending receipt");
+
+        let result = self
+            .http
+            .post(&receipt.url)
+            .header("content-type", "application/json")
+            .header("x-receipt-timestamp", &timestamp)
+            .header("x-receipt-signature", format!("sha256={}", signature))
+            .body(body)
+            .send()
+            .await;
+
+        let error = match result {
+            Ok(response) if response.status().is_success() => {
+                debug!(
+                    id = receipt.id,
+                    notification_id = %receipt.notification_id,
+                    url = %receipt.url,
+                    "✓ Receipt delivered"
+                );
+                if let Err(e) = ReceiptQueries::mark_delivered(&self.pool, receipt.id).await {
+                    error!(id = receipt.id, error = %e, "Failed to mark receipt delivered");
+                }
+                return;
+            }
+            Ok(response) => format!("HTTP {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+
+        let retry_at = Utc::now() + backoff(receipt.attempts);
+        if receipt.attempts >= self.max_attempts {
+            warn!(
+                id = receipt.id,
+                notification_id = %receipt.notification_id,
+                url = %receipt.url,
+                error = %error,
+                "✗ Receipt failed permanently - max attempts reached"
+            );
+        } else {
+            debug!(
+                id = receipt.id,
+                url = %receipt.url,
+                error = %error,
+                retry_at = %retry_at,
+                "Receipt failed, will retry"
+            );
+        }
+
+        if let Err(e) = ReceiptQueries::mark_failed(&self.pool, receipt.id, &error, retry_at).await {
+            error!(id = receipt.id, error = %e, "Failed to record receipt failure");
+        }
+    }
+}
+
+/// Hex HMAC-SHA256 over `<timestamp>.<body>`
+pub fn sign(secret: &[u8], timestamp: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Exponential backoff: 30s, 1m, 2m, ... capped at 1h
+fn backoff(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.clamp(1, 8) as u32 - 1;
+    chrono::Duration::seconds((30i64 << exponent).min(3600))
+}
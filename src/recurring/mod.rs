@@ -0,0 +1,135 @@
+//! Recurring notifications: cron schedules materialized into notification rows.
+//!
+//! Schedules live in `activity.recurring_notifications` (managed via
+//! `/api/v1/recurring-notifications`). The [`RecurringScheduler`] polls for due
+//! schedules and, per occurrence, inserts one regular notification per recipient
+//! (`created_by = recurring:<id>`); the NOTIFY trigger then wakes the worker as usual.
+//! Schedules are claimed with `FOR UPDATE SKIP LOCKED`, so every replica can run one.
+
+use crate::db::recurring::RecurringNotification;
+use crate::db::{MaintenanceQueries, RecurringQueries};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument, warn};
+
+/// Cron expression evaluated in an IANA time zone
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    schedule: cron::Schedule,
+    timezone: Tz,
+}
+
+impl CronSchedule {
+    /// Parse a 5-field expression (`minute hour day-of-month month day-of-week`)
+    ///
+    /// Weekdays must be names (`MON-FRI`): the cron crate numbers them 1-7 from
+    /// Sunday, so a Unix-style `1` would silently mean Sunday.
+    pub fn parse(expression: &str, timezone: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "cron must have 5 fields (minute hour day-of-month month day-of-week), got '{}'",
+                expression
+            ));
+        }
+        if fields[4].chars().any(|c| c.is_ascii_digit()) {
+            return Err(format!("use day names (MON-SUN) for the day-of-week field, got '{}'", fields[4]));
+        }
+
+        let schedule = cron::Schedule::from_str(&format!("0 {}", fields.join(" ")))
+            .map_err(|e| format!("invalid cron '{}': {}", expression, e))?;
+        let timezone = Tz::from_str(timezone).map_err(|_| format!("unknown time zone '{}'", timezone))?;
+
+        Ok(Self { schedule, timezone })
+    }
+
+    /// First occurrence strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule
+            .after(&after.with_timezone(&self.timezone))
+            .next()
+            .map(|next| next.with_timezone(&Utc))
+    }
+}
+
+pub struct RecurringScheduler {
+    pool: PgPool,
+    poll_interval: Duration,
+}
+
+impl RecurringScheduler {
+    pub fn new(pool: PgPool, poll_interval: Duration) -> Self {
+        Self { pool, poll_interval }
+    }
+
+    /// Scheduler loop: materialize everything due, then sleep
+    #[instrument(skip(self), name = "recurring_scheduler")]
+    pub async fn run(&self) {
+        info!(poll_interval_secs = self.poll_interval.as_secs(), "Recurring notification scheduler started");
+
+        loop {
+            // A draining environment creates nothing new (`notifications-service drain`)
+            if MaintenanceQueries::ingestion_closed(&self.pool).await.unwrap_or(false) {
+                debug!("Ingestion closed - not materializing recurring notifications");
+                tokio::time::sleep(self.poll_interval).await;
+                continue;
+            }
+            loop {
+                match self.run_next_due().await {
+                    Ok(true) => continue,
+                    Ok(false) => break,
+                    Err(e) => {
+                        error!(error = %e, "Failed to materialize recurring notification");
+                        break;
+                    }
+                }
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Materialize the most overdue schedule - false when nothing is due
+    async fn run_next_due(&self) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(recurring) = RecurringQueries::claim_due(&mut tx).await? else {
+            return Ok(false);
+        };
+
+        // Missed occurrences (downtime, resume) are not caught up: one send, then the next future slot
+        let occurrence = recurring.next_run_at;
+        let next_run_at = match next_run(&recurring, Utc::now().max(occurrence)) {
+            Ok(next_run_at) => next_run_at,
+            Err(reason) => {
+                tx.rollback().await?;
+                warn!(id = %recurring.id, name = %recurring.name, reason = %reason, "Pausing recurring notification");
+                RecurringQueries::set_paused(&self.pool, recurring.id, true, None).await?;
+                return Ok(true);
+            }
+        };
+
+        let created = RecurringQueries::materialize(&mut tx, &recurring, occurrence, next_run_at).await?;
+        tx.commit().await?;
+
+        info!(
+            id = %recurring.id,
+            name = %recurring.name,
+            occurrence = %occurrence,
+            notifications = created,
+            next_run_at = %next_run_at,
+            "🔁 Recurring notification materialized"
+        );
+        metrics::counter!("notifications_recurring_materialized_total").increment(created);
+        Ok(true)
+    }
+}
+
+/// Next occurrence of a stored schedule after `after`
+fn next_run(recurring: &RecurringNotification, after: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let schedule = CronSchedule::parse(&recurring.cron, &recurring.timezone)?;
+    let next = schedule.next_after(after).ok_or_else(|| "cron has no future occurrence".to_string())?;
+    debug!(id = %recurring.id, next_run_at = %next, "Next recurring occurrence");
+    Ok(next)
+}
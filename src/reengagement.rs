@@ -0,0 +1,76 @@
+//! Re-engagement: a nudge for users who went quiet.
+//!
+//! A tenant opts in with a policy (`PUT /api/v1/tenants/{id}/reengagement`,
+//! `activity.reengagement_policies`). Every REENGAGEMENT_INTERVAL_SECS the
+//! [`ReengagementJob`] runs each enabled policy once: users of the tenant with a device older
+//! than `inactive_days` who, within that period, read nothing, opened or clicked nothing and
+//! got nothing over the Bus (so had no open connection) get one regular notification
+//! (`created_by = reengagement`). The worker delivers it like any other, so type opt-outs,
+//! snooze and delivery windows apply. A user gets at most one per `cooldown_days`. Policies
+//! are claimed with `FOR UPDATE SKIP LOCKED`, so every replica can run the job.
+
+use crate::db::{MaintenanceQueries, ReengagementQueries};
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument};
+
+/// Users nudged per policy and run; the rest follow on the next run
+const BATCH_SIZE: i64 = 1000;
+
+pub struct ReengagementJob {
+    pool: PgPool,
+    interval: Duration,
+}
+
+impl ReengagementJob {
+    pub fn new(pool: PgPool, interval: Duration) -> Self {
+        Self { pool, interval }
+    }
+
+    /// Job loop: run every policy that is due, then sleep
+    #[instrument(skip(self), name = "reengagement")]
+    pub async fn run(&self) {
+        info!(interval_secs = self.interval.as_secs(), "Re-engagement job started");
+
+        loop {
+            // A draining environment creates nothing new (`notifications-service drain`)
+            if MaintenanceQueries::ingestion_closed(&self.pool).await.unwrap_or(false) {
+                debug!("Ingestion closed - not running re-engagement policies");
+                tokio::time::sleep(self.interval).await;
+                continue;
+            }
+            loop {
+                match self.run_next_due().await {
+                    Ok(true) => continue,
+                    Ok(false) => break,
+                    Err(e) => {
+                        error!(error = %e, "Failed to run re-engagement policy");
+                        break;
+                    }
+                }
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    /// Run the policy that waited longest - false when none is due
+    async fn run_next_due(&self) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(policy) = ReengagementQueries::claim_due(&mut tx, self.interval.as_secs() as i64).await? else {
+            return Ok(false);
+        };
+        let created = ReengagementQueries::send(&mut tx, &policy, BATCH_SIZE).await?;
+        tx.commit().await?;
+
+        if created > 0 {
+            info!(
+                tenant_id = %policy.tenant_id,
+                inactive_days = policy.inactive_days,
+                notifications = created,
+                "👋 Re-engagement notifications created"
+            );
+        }
+        metrics::counter!("notifications_reengagement_created_total").increment(created);
+        Ok(true)
+    }
+}
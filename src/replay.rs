@@ -0,0 +1,461 @@
+//! `notifications-service replay`: run a past window of real traffic through the current
+//! routing and rendering code, without sending anything.
+//!
+//! Reads the per-user notifications the worker finished inside the window (the hot table,
+//! and with `--archives` also the archives in ARCHIVE_URL that overlap it). Each one is
+//! routed by the [`ChannelRouter`] and rendered as a dry-run test send ([`TestSender`]):
+//! the Bus envelope plus one FCM request per registered device. Nothing is sent, stored or
+//! changed. Two comparisons come out of it:
+//!
+//! - routing against what the worker recorded: a suppression reason of the router's own,
+//!   or an attempted channel, that the current route no longer agrees with;
+//! - routes and payloads against `--baseline`, the `--out` report of an earlier run. The
+//!   usual flow is a replay on the old build, then the same replay on the new one.
+//!
+//! Preferences, devices and snooze are read as they are now. The clock checks (local send
+//! time, delivery windows) run at the row's `deliver_at`. The command exits 1 when anything
+//! differs, so it can gate a rollout.
+//!
+//! ```text
+//! notifications-service replay --from 2024-05-01T10:00 --to 11:00 [--type payout_failed] [--tenant id]
+//!     [--limit 1000] [--archives] [--out report.ndjson] [--baseline previous.ndjson]
+//! ```
+
+use crate::api::inspect::RouteExplanation;
+use crate::archive::{self, ArchiveStore};
+use crate::config::Config;
+use crate::db::explain::ExplainedNotification;
+use crate::db::replay::ReplayWindow;
+use crate::db::{ArchiveQueries, ReplayQueries};
+use crate::resend::{parse_time, parse_time_of_day};
+use crate::templates::TemplateLookups;
+use crate::worker::test_send::{TestSendReport, TestSender, TestTarget};
+use crate::worker::{ChannelRouter, DeliveryWindows, FallbackChains};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+const USAGE: &str = "usage: notifications-service replay --from <time> --to <time> [--type <notification_type>] [--tenant <id>] [--limit <n>] [--archives] [--out <report.ndjson>] [--baseline <report.ndjson>]";
+/// Notifications per replay unless `--limit` says otherwise
+const DEFAULT_LIMIT: i64 = 1000;
+/// Differences shown in the summary (`--out` has all of them)
+const LOGGED_DIFFERENCES: usize = 20;
+/// Suppressions the router decides; other reasons (expired, suppression list, ...) are
+/// set before or after routing and say nothing about it
+const ROUTER_SUPPRESSIONS: [&str; 3] = ["type_disabled", "target_muted", "channels_disabled"];
+
+#[derive(Debug, Clone)]
+pub struct ReplayArgs {
+    pub window: ReplayWindow,
+    pub limit: i64,
+    /// Also read the archives overlapping the window (ARCHIVE_URL)
+    pub archives: bool,
+    /// Write the report here, one notification per line (a later `--baseline`)
+    pub out: Option<PathBuf>,
+    /// Compare against the report of an earlier run
+    pub baseline: Option<PathBuf>,
+}
+
+impl ReplayArgs {
+    /// Parse the arguments after `replay`
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut from = None;
+        let mut to = None;
+        let mut notification_type = None;
+        let mut tenant_id = None;
+        let mut limit = DEFAULT_LIMIT;
+        let mut archives = false;
+        let mut out = None;
+        let mut baseline = None;
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--archives" => archives = true,
+                "--from" | "--to" | "--type" | "--tenant" | "--limit" | "--out" | "--baseline" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value\n{}", flag, USAGE))?;
+                    match flag.as_str() {
+                        "--from" => from = Some(value.clone()),
+                        "--to" => to = Some(value.clone()),
+                        "--type" => notification_type = Some(value.clone()),
+                        "--tenant" => tenant_id = Some(value.clone()),
+                        "--limit" => {
+                            limit = value
+                                .parse()
+                                .ok()
+                                .filter(|limit| *limit > 0)
+                                .ok_or_else(|| format!("--limit: invalid number '{}'\n{}", value, USAGE))?;
+                        }
+                        "--out" => out = Some(PathBuf::from(value)),
+                        _ => baseline = Some(PathBuf::from(value)),
+                    }
+                }
+                _ => return Err(format!("Unknown option '{}'\n{}", flag, USAGE)),
+            }
+        }
+
+        let from = from.ok_or_else(|| format!("--from is required\n{}", USAGE))?;
+        let to = to.ok_or_else(|| format!("--to is required\n{}", USAGE))?;
+        let from = parse_time(&from).ok_or_else(|| format!("--from: invalid time '{}'\n{}", from, USAGE))?;
+        let to = parse_time(&to)
+            .or_else(|| parse_time_of_day(&to).map(|time| from.date_naive().and_time(time).and_utc()))
+            .ok_or_else(|| format!("--to: invalid time '{}'\n{}", to, USAGE))?;
+        if to <= from {
+            return Err(format!("--to must be after --from\n{}", USAGE));
+        }
+
+        Ok(Self {
+            window: ReplayWindow { from, to, notification_type, tenant_id },
+            limit,
+            archives,
+            out,
+            baseline,
+        })
+    }
+}
+
+/// One replayed notification: a line of the report
+#[derive(Debug, Serialize)]
+pub struct ReplayedNotification {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub notification_type: String,
+    pub created_at: DateTime<Utc>,
+    /// hot | archive
+    pub source: &'static str,
+    pub recorded: Recorded,
+    /// The route the current code and config decide
+    pub route: RouteExplanation,
+    /// What would be sent now (dry run)
+    pub payloads: TestSendReport,
+    pub differences: Vec<Difference>,
+}
+
+/// What the worker did with the notification
+#[derive(Debug, Serialize)]
+pub struct Recorded {
+    /// delivered | suppressed | failed
+    pub status: &'static str,
+    pub suppression_reason: Option<String>,
+    /// Channels it attempted, in order
+    pub channels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Difference {
+    /// recorded (the route against what the worker did) | baseline (against `--baseline`)
+    pub against: &'static str,
+    /// JSON pointer into the report line
+    pub path: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplaySummary {
+    pub window: ReplayWindow,
+    pub replayed: usize,
+    pub from_archives: usize,
+    /// Notifications routed differently than recorded
+    pub routing_changed: usize,
+    /// Notifications that differ from `--baseline` (None without one)
+    pub baseline_changed: Option<usize>,
+    /// Replayed notifications the baseline doesn't have
+    pub not_in_baseline: usize,
+    pub out: Option<PathBuf>,
+    /// The first differences, for the log
+    pub examples: Vec<(Uuid, Difference)>,
+}
+
+impl ReplaySummary {
+    /// Nothing differs (the command's exit code)
+    pub fn is_clean(&self) -> bool {
+        self.routing_changed == 0 && self.baseline_changed.unwrap_or(0) == 0
+    }
+
+    pub fn log(&self) {
+        info!("═══════════════════════════════════════════════════════════");
+        if self.is_clean() {
+            info!("  REPLAY COMPLETE (no differences)");
+        } else {
+            warn!("  REPLAY COMPLETE (differences found)");
+        }
+        info!("  Window:           {} .. {}", self.window.from.to_rfc3339(), self.window.to.to_rfc3339());
+        info!("  Type:             {}", self.window.notification_type.as_deref().unwrap_or("(all)"));
+        info!("  Tenant:           {}", self.window.tenant_id.as_deref().unwrap_or("(all)"));
+        info!("  Replayed:         {} ({} from archives)", self.replayed, self.from_archives);
+        info!("  Routing changed:  {}", self.routing_changed);
+        match self.baseline_changed {
+            Some(changed) => info!("  Baseline changed: {} ({} not in the baseline)", changed, self.not_in_baseline),
+            None => info!("  Baseline changed: - (no --baseline)"),
+        }
+        match &self.out {
+            Some(path) => info!("  Report:           {}", path.display()),
+            None => info!("  Report:           - (no --out)"),
+        }
+        for (id, difference) in &self.examples {
+            info!(
+                "  {} {} {}: {} -> {}",
+                id, difference.against, difference.path, difference.before, difference.after
+            );
+        }
+        info!("═══════════════════════════════════════════════════════════");
+    }
+}
+
+/// Replay the window and compare; Err only when it couldn't run
+pub async fn run(pool: &PgPool, config: &Config, args: &ReplayArgs) -> Result<ReplaySummary, String> {
+    let db_error = |e: sqlx::Error| format!("Database error: {}", e);
+    // Read before --out is created: both may name the same file
+    let baseline = args.baseline.as_deref().map(read_baseline).transpose()?;
+    let router = router(pool, config)?;
+    let lookups = TemplateLookups::from_config(pool.clone(), config);
+    if let Some(lookups) = &lookups {
+        lookups.validate().await?;
+    }
+    let sender = TestSender::new(pool.clone(), config, None, None)
+        .with_bus_preview()
+        .with_template_lookups(lookups);
+
+    let mut rows: Vec<(ExplainedNotification, &'static str)> = ReplayQueries::window(pool, &args.window, args.limit)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|row| (row, "hot"))
+        .collect();
+    if args.archives {
+        let hot: HashSet<Uuid> = rows.iter().map(|(row, _)| row.notification.id).collect();
+        let store = ArchiveStore::open(config).await?;
+        let overlapping = ArchiveQueries::overlapping(pool, args.window.from, args.window.to)
+            .await
+            .map_err(db_error)?;
+        for archive in overlapping {
+            let archived = archive::read(&store, &archive).await?;
+            let matching = ReplayQueries::archived(pool, &archived, &args.window, args.limit)
+                .await
+                .map_err(|e| format!("Failed to read archive {}: {}", archive.object_url, e))?;
+            // Restored archives are in the hot table too
+            rows.extend(
+                matching
+                    .into_iter()
+                    .filter(|row| !hot.contains(&row.notification.id))
+                    .map(|row| (row, "archive")),
+            );
+        }
+        rows.sort_by_key(|(row, _)| (row.notification.created_at, row.notification.id));
+        rows.truncate(args.limit as usize);
+    }
+
+    let ids: Vec<Uuid> = rows.iter().map(|(row, _)| row.notification.id).collect();
+    let mut attempted: HashMap<Uuid, Vec<String>> = ReplayQueries::attempted_channels(pool, &ids)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|attempt| (attempt.notification_id, attempt.channels))
+        .collect();
+
+    let mut out = match &args.out {
+        Some(path) => Some(BufWriter::new(
+            File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?,
+        )),
+        None => None,
+    };
+    let mut summary = ReplaySummary {
+        window: args.window.clone(),
+        replayed: 0,
+        from_archives: 0,
+        routing_changed: 0,
+        baseline_changed: baseline.as_ref().map(|_| 0),
+        not_in_baseline: 0,
+        out: args.out.clone(),
+        examples: Vec::new(),
+    };
+
+    for (row, source) in rows {
+        let notification = &row.notification;
+        let recorded = Recorded {
+            status: row.status(),
+            suppression_reason: row.suppression_reason.clone(),
+            channels: attempted.remove(&notification.id).unwrap_or_default(),
+        };
+        let (route, steps) = router.explain_at(notification, notification.deliver_at).await;
+        let route = RouteExplanation::new(route, steps);
+        let payloads = sender
+            .send(notification, &TestTarget::User(notification.user_id), None, true)
+            .await
+            .map_err(db_error)?;
+
+        let mut replayed = ReplayedNotification {
+            id: notification.id,
+            tenant_id: notification.tenant_id.clone(),
+            notification_type: notification.notification_type.clone(),
+            created_at: notification.created_at,
+            source,
+            differences: routing_differences(&recorded, &route),
+            recorded,
+            route,
+            payloads,
+        };
+        if !replayed.differences.is_empty() {
+            summary.routing_changed += 1;
+        }
+        if let Some(baseline) = &baseline {
+            match baseline.get(&replayed.id) {
+                Some(before) => {
+                    let after = serde_json::to_value(&replayed).map_err(|e| e.to_string())?;
+                    let mut differences = Vec::new();
+                    diff("", &compared(before), &compared(&after), &mut differences);
+                    if !differences.is_empty() {
+                        *summary.baseline_changed.get_or_insert(0) += 1;
+                    }
+                    replayed.differences.extend(differences);
+                }
+                None => summary.not_in_baseline += 1,
+            }
+        }
+
+        for difference in &replayed.differences {
+            if summary.examples.len() < LOGGED_DIFFERENCES {
+                summary.examples.push((replayed.id, difference.clone()));
+            }
+        }
+        if let (Some(out), Some(path)) = (out.as_mut(), &args.out) {
+            serde_json::to_writer(&mut *out, &replayed)
+                .map_err(|e| e.to_string())
+                .and_then(|_| out.write_all(b"\n").map_err(|e| e.to_string()))
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        summary.replayed += 1;
+        if source == "archive" {
+            summary.from_archives += 1;
+        }
+    }
+    if let (Some(out), Some(path)) = (out.as_mut(), &args.out) {
+        out.flush().map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+
+    Ok(summary)
+}
+
+/// The router as the worker builds it from FALLBACK_CHAINS and DELIVERY_WINDOWS
+fn router(pool: &PgPool, config: &Config) -> Result<ChannelRouter, String> {
+    let chains = config.fallback_chains.as_deref().map(FallbackChains::parse).transpose()?;
+    let windows = config.delivery_windows.as_deref().map(DeliveryWindows::parse).transpose()?;
+    let timezone: Tz = config
+        .delivery_window_timezone
+        .parse()
+        .map_err(|_| format!("Unknown DELIVERY_WINDOW_TIMEZONE '{}'", config.delivery_window_timezone))?;
+    Ok(ChannelRouter::new(pool.clone())
+        .with_chains(chains.unwrap_or_default())
+        .with_windows(windows.unwrap_or_default(), timezone))
+}
+
+/// Report lines of an earlier run by notification id
+fn read_baseline(path: &Path) -> Result<HashMap<Uuid, Value>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut lines = HashMap::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value =
+            serde_json::from_str(&line).map_err(|e| format!("Invalid line in {}: {}", path.display(), e))?;
+        let id = value["id"]
+            .as_str()
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(|| format!("Line without an id in {}", path.display()))?;
+        lines.insert(id, value);
+    }
+    Ok(lines)
+}
+
+/// Where a route sends the notification, in one string (`deliver:bus>push`, `suppress:snoozed`)
+fn decision(route: &RouteExplanation) -> String {
+    match route.decision {
+        "deliver" => format!("deliver:{}", route.channels.join(">")),
+        decision => format!("{}:{}", decision, route.reason.unwrap_or_default()),
+    }
+}
+
+/// The route against what the worker recorded
+fn routing_differences(recorded: &Recorded, route: &RouteExplanation) -> Vec<Difference> {
+    let differs = |path: &str, before: Value| Difference {
+        against: "recorded",
+        path: path.to_string(),
+        before,
+        after: json!(decision(route)),
+    };
+    match (recorded.suppression_reason.as_deref(), route.decision) {
+        (Some(reason), _) if !ROUTER_SUPPRESSIONS.contains(&reason) => Vec::new(),
+        (Some(reason), "suppress") if route.reason == Some(reason) => Vec::new(),
+        (Some(reason), _) => vec![differs("/route/decision", json!(format!("suppress:{}", reason)))],
+        (None, "deliver") => {
+            let dropped = recorded.channels.iter().any(|channel| !route.channels.contains(&channel.as_str()));
+            if dropped {
+                vec![Difference {
+                    against: "recorded",
+                    path: "/route/channels".to_string(),
+                    before: json!(recorded.channels),
+                    after: json!(route.channels),
+                }]
+            } else {
+                Vec::new()
+            }
+        }
+        (None, _) => vec![differs("/route/decision", json!("deliver"))],
+    }
+}
+
+/// What a baseline comparison looks at: the route's outcome and the payloads
+fn compared(line: &Value) -> Value {
+    json!({
+        "route": {
+            "decision": line["route"]["decision"],
+            "channels": line["route"]["channels"],
+            "reason": line["route"]["reason"],
+            "until": line["route"]["until"],
+        },
+        "payloads": {
+            "bus": line["payloads"]["bus"],
+            "push": line["payloads"]["push"],
+            "warnings": line["payloads"]["warnings"],
+        },
+    })
+}
+
+/// Every leaf that differs between two values, as JSON pointers
+fn diff(path: &str, before: &Value, after: &Value, out: &mut Vec<Difference>) {
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let pointer = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                diff(&pointer, a.get(key).unwrap_or(&Value::Null), b.get(key).unwrap_or(&Value::Null), out);
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for index in 0..a.len().max(b.len()) {
+                let pointer = format!("{}/{}", path, index);
+                diff(&pointer, a.get(index).unwrap_or(&Value::Null), b.get(index).unwrap_or(&Value::Null), out);
+            }
+        }
+        (a, b) if a != b => out.push(Difference {
+            against: "baseline",
+            path: path.to_string(),
+            before: a.clone(),
+            after: b.clone(),
+        }),
+        _ => {}
+    }
+}
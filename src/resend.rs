@@ -0,0 +1,143 @@
+//! `notifications-service resend`: re-drive notifications after an incident.
+//!
+//! Finds notifications that failed (all retries used) or were suppressed inside a time
+//! window and puts them back in the queue. Without `--execute` it only counts, so the
+//! usual flow is a dry run, a look at the numbers, then the same command with `--execute`.
+//! The same operation is available as `POST /api/v1/resend`.
+//!
+//! ```text
+//! notifications-service resend --from 2024-05-01T10:00 --to 11:00 [--type payout_failed] [--tenant id] [--execute]
+//! ```
+//!
+//! Times are RFC 3339 or `YYYY-MM-DDTHH:MM[:SS]` in UTC; a `--to` of just `HH:MM[:SS]`
+//! means that time on the day of `--from`.
+
+use crate::api::audit;
+use crate::db::resend::{ResendCount, ResendWindow};
+use crate::db::ResendQueries;
+use chrono::{DateTime, NaiveDateTime, NaiveTime, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use tracing::info;
+
+const USAGE: &str = "usage: notifications-service resend --from <time> --to <time> [--type <notification_type>] [--tenant <id>] [--execute]";
+/// Audit log actor for resends started from the command line
+const CLI_ACTOR: &str = "cli";
+
+#[derive(Debug, Clone)]
+pub struct ResendArgs {
+    pub window: ResendWindow,
+    /// Actually re-drive; otherwise only count (dry run)
+    pub execute: bool,
+}
+
+impl ResendArgs {
+    /// Parse the arguments after `resend`
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut from = None;
+        let mut to = None;
+        let mut notification_type = None;
+        let mut tenant_id = None;
+        let mut execute = false;
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--execute" => execute = true,
+                "--from" | "--to" | "--type" | "--tenant" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value\n{}", flag, USAGE))?;
+                    match flag.as_str() {
+                        "--from" => from = Some(value.clone()),
+                        "--to" => to = Some(value.clone()),
+                        "--type" => notification_type = Some(value.clone()),
+                        _ => tenant_id = Some(value.clone()),
+                    }
+                }
+                _ => return Err(format!("Unknown option '{}'\n{}", flag, USAGE)),
+            }
+        }
+
+        let from = from.ok_or_else(|| format!("--from is required\n{}", USAGE))?;
+        let to = to.ok_or_else(|| format!("--to is required\n{}", USAGE))?;
+        let from = parse_time(&from).ok_or_else(|| format!("--from: invalid time '{}'\n{}", from, USAGE))?;
+        let to = parse_time(&to)
+            .or_else(|| parse_time_of_day(&to).map(|time| from.date_naive().and_time(time).and_utc()))
+            .ok_or_else(|| format!("--to: invalid time '{}'\n{}", to, USAGE))?;
+        if to <= from {
+            return Err(format!("--to must be after --from\n{}", USAGE));
+        }
+
+        Ok(Self {
+            window: ResendWindow { from, to, notification_type, tenant_id },
+            execute,
+        })
+    }
+}
+
+pub(crate) fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|time| time.and_utc())
+}
+
+pub(crate) fn parse_time_of_day(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M"))
+        .ok()
+}
+
+#[derive(Debug, Clone)]
+pub struct ResendSummary {
+    pub window: ResendWindow,
+    pub count: ResendCount,
+    pub executed: bool,
+}
+
+impl ResendSummary {
+    pub fn log(&self) {
+        info!("═══════════════════════════════════════════════════════════");
+        if self.executed {
+            info!("  RESEND COMPLETE");
+        } else {
+            info!("  RESEND DRY RUN (nothing changed, add --execute)");
+        }
+        info!("  Window:      {} .. {}", self.window.from.to_rfc3339(), self.window.to.to_rfc3339());
+        info!("  Type:        {}", self.window.notification_type.as_deref().unwrap_or("(all)"));
+        info!("  Tenant:      {}", self.window.tenant_id.as_deref().unwrap_or("(all)"));
+        info!("  Failed:      {}", self.count.failed);
+        info!("  Suppressed:  {}", self.count.suppressed);
+        info!("═══════════════════════════════════════════════════════════");
+    }
+}
+
+/// Count (dry run) or re-drive the window
+pub async fn run(pool: &PgPool, args: &ResendArgs) -> Result<ResendSummary, String> {
+    if !args.execute {
+        let count = ResendQueries::count(pool, &args.window)
+            .await
+            .map_err(|e| format!("Failed to count notifications: {}", e))?;
+        return Ok(ResendSummary { window: args.window.clone(), count, executed: false });
+    }
+
+    let count = ResendQueries::resend(pool, &args.window)
+        .await
+        .map_err(|e| format!("Failed to resend notifications: {}", e))?;
+    audit::record(pool, CLI_ACTOR, "notifications.resend", audit_params(&args.window, &count)).await;
+    Ok(ResendSummary { window: args.window.clone(), count, executed: true })
+}
+
+/// Audit log parameters of an executed resend (CLI and API)
+pub fn audit_params(window: &ResendWindow, count: &ResendCount) -> serde_json::Value {
+    json!({
+        "from": window.from,
+        "to": window.to,
+        "notification_type": window.notification_type,
+        "tenant_id": window.tenant_id,
+        "failed": count.failed,
+        "suppressed": count.suppressed,
+    })
+}
@@ -0,0 +1,334 @@
+//! Secret backends for configuration values.
+//!
+//! Any secret env var (DATABASE_URL, DATABASE_CREDENTIALS, JWT_SECRET, ADMIN_TOKEN,
+//! SERVICE_TOKEN, RECEIPT_SIGNING_SECRET, BROADCAST_SIGNING_KEY, FCM_CREDENTIALS,
+//! DIGEST_EMAIL_TOKEN, ATTACHMENT_SECRET_ACCESS_KEY)
+//! may hold a URI instead of the value itself:
+//!
+//! - `vault://<path>#<field>` - `GET $VAULT_ADDR/v1/<path>` (KV v1/v2, or any engine
+//!   returning `data`); the field may be omitted when the secret has a single key
+//! - `aws-sm://<secret-id>#<field>` - AWS Secrets Manager (`secretsmanager` feature);
+//!   without a field the whole secret string is used
+//!
+//! DATABASE_CREDENTIALS points at a secret with `username`/`password` (e.g. Vault's
+//! `database/creds/<role>`). Those are applied on top of DATABASE_URL and kept fresh
+//! in the background: Vault leases are renewed and replaced before they run out (at
+//! two thirds of the lease, at most every 5 seconds), other backends are re-read every
+//! DATABASE_CREDENTIALS_REFRESH_SECS.
+
+use crate::config::{Config, VaultConfig};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sqlx::postgres::PgConnectOptions;
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+const VAULT_SCHEME: &str = "vault://";
+const AWS_SM_SCHEME: &str = "aws-sm://";
+/// Retry delay after a failed renewal/refresh
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+/// Shortest wait between renewals: a lease of 0 (or a few seconds) must not hammer Vault
+const MIN_RENEW_INTERVAL: Duration = Duration::from_secs(5);
+
+/// True if the value must be resolved through a secret backend
+pub fn is_secret_ref(value: &str) -> bool {
+    value.starts_with(VAULT_SCHEME) || value.starts_with(AWS_SM_SCHEME)
+}
+
+/// Secret as returned by a backend, with its Vault lease (if any)
+#[derive(Debug, Clone)]
+pub struct Secret {
+    data: Map<String, Value>,
+    pub lease: Option<Lease>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Lease {
+    pub id: String,
+    pub duration: Duration,
+    pub renewable: bool,
+}
+
+impl Secret {
+    /// Field as a string (None = the secret's only field)
+    fn field(&self, field: Option<&str>) -> Result<String, String> {
+        let value = match field {
+            Some(field) => self.data.get(field).ok_or_else(|| format!("secret has no field '{}'", field))?,
+            None if self.data.len() == 1 => self.data.values().next().expect("len checked"),
+            None => return Err("secret has several fields, select one with #<field>".to_string()),
+        };
+        Ok(match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+    }
+
+    /// Apply `username`/`password` to the base connect options
+    pub fn connect_options(&self, base: &PgConnectOptions) -> Result<PgConnectOptions, String> {
+        let username = self.field(Some("username"))?;
+        let password = self.field(Some("password"))?;
+        Ok(base.clone().username(&username).password(&password))
+    }
+}
+
+#[derive(Deserialize)]
+struct VaultResponse {
+    #[serde(default)]
+    lease_id: String,
+    #[serde(default)]
+    lease_duration: u64,
+    #[serde(default)]
+    renewable: bool,
+    #[serde(default)]
+    data: Option<Map<String, Value>>,
+}
+
+/// Resolves `vault://` and `aws-sm://` references
+pub struct SecretResolver {
+    http: reqwest::Client,
+    vault: Option<VaultConfig>,
+    #[cfg(feature = "secretsmanager")]
+    aws: tokio::sync::OnceCell<aws_sdk_secretsmanager::Client>,
+}
+
+impl SecretResolver {
+    pub fn new(vault: Option<VaultConfig>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            http,
+            vault,
+            #[cfg(feature = "secretsmanager")]
+            aws: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    /// Replace secret references in the config with their values
+    pub async fn resolve_config(&self, config: &mut Config) -> Result<(), String> {
+        config.database_url = self.resolve(&config.database_url).await?;
+
+        for value in [
+            &mut config.jwt_secret,
+            &mut config.admin_token,
+            &mut config.service_token,
+            &mut config.receipt_signing_secret,
+            &mut config.broadcast_signing_key,
+            &mut config.fcm_credentials,
+            &mut config.digest_email_token,
+            &mut config.attachment_secret_access_key,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *value = self.resolve(value).await?;
+        }
+        Ok(())
+    }
+
+    /// Value of a secret reference; plain values are returned unchanged
+    pub async fn resolve(&self, value: &str) -> Result<String, String> {
+        if !is_secret_ref(value) {
+            return Ok(value.to_string());
+        }
+
+        let (uri, field) = split_field(value);
+        // Plain AWS secrets are a single string, not a JSON object
+        if field.is_none() && uri.starts_with(AWS_SM_SCHEME) {
+            return self.aws_secret_string(&uri[AWS_SM_SCHEME.len()..]).await;
+        }
+
+        let secret = self.fetch(uri).await?;
+        secret.field(field).map_err(|e| format!("{}: {}", uri, e))
+    }
+
+    /// Fetch a whole secret (the `#field` part of the URI is ignored)
+    pub async fn fetch(&self, value: &str) -> Result<Secret, String> {
+        let (uri, _) = split_field(value);
+
+        if let Some(path) = uri.strip_prefix(VAULT_SCHEME) {
+            return self.vault_read(path).await;
+        }
+        if let Some(secret_id) = uri.strip_prefix(AWS_SM_SCHEME) {
+            let raw = self.aws_secret_string(secret_id).await?;
+            let data = serde_json::from_str(&raw).map_err(|_| format!("{}: secret is not a JSON object", uri))?;
+            return Ok(Secret { data, lease: None });
+        }
+        Err(format!("Unsupported secret reference '{}'", uri))
+    }
+
+    async fn vault_read(&self, path: &str) -> Result<Secret, String> {
+        let vault = self
+            .vault
+            .as_ref()
+            .ok_or_else(|| format!("vault://{} used but VAULT_ADDR is not set", path))?;
+
+        let mut request = self
+            .http
+            .get(format!("{}/v1/{}", vault.addr, path.trim_start_matches('/')))
+            .header("X-Vault-Token", &vault.token);
+        if let Some(namespace) = &vault.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+
+        let response: VaultResponse = self.vault_send(request, path).await?;
+        let mut data = response.data.unwrap_or_default();
+
+        // KV v2 nests the secret as data.data next to data.metadata
+        if let (Some(Value::Object(inner)), true) = (data.get("data"), data.contains_key("metadata")) {
+            data = inner.clone();
+        }
+
+        let lease = (!response.lease_id.is_empty()).then(|| Lease {
+            id: response.lease_id,
+            duration: Duration::from_secs(response.lease_duration),
+            renewable: response.renewable,
+        });
+
+        debug!(path = %path, lease = lease.is_some(), "Vault secret read");
+        Ok(Secret { data, lease })
+    }
+
+    /// Extend a Vault lease; returns the renewed lease
+    pub async fn renew(&self, lease: &Lease) -> Result<Lease, String> {
+        let vault = self.vault.as_ref().ok_or("VAULT_ADDR is not set")?;
+
+        let mut request = self
+            .http
+            .put(format!("{}/v1/sys/leases/renew", vault.addr))
+            .header("X-Vault-Token", &vault.token)
+            .json(&serde_json::json!({ "lease_id": lease.id, "increment": lease.duration.as_secs() }));
+        if let Some(namespace) = &vault.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+
+        let response: VaultResponse = self.vault_send(request, "sys/leases/renew").await?;
+        Ok(Lease {
+            id: lease.id.clone(),
+            duration: Duration::from_secs(response.lease_duration),
+            renewable: response.renewable,
+        })
+    }
+
+    async fn vault_send(&self, request: reqwest::RequestBuilder, path: &str) -> Result<VaultResponse, String> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Vault request for {} failed: {}", path, e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Vault returned {} for {}: {}", status, path, body));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid Vault response for {}: {}", path, e))
+    }
+
+    #[cfg(feature = "secretsmanager")]
+    async fn aws_secret_string(&self, secret_id: &str) -> Result<String, String> {
+        let client = self
+            .aws
+            .get_or_init(|| async {
+                let aws_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+                aws_sdk_secretsmanager::Client::new(&aws_config)
+            })
+            .await;
+
+        let output = client
+            .get_secret_value()
+            .secret_id(secret_id)
+            .send()
+            .await
+            .map_err(|e| format!("aws-sm://{}: {}", secret_id, e))?;
+
+        debug!(secret_id = %secret_id, "AWS secret read");
+        output
+            .secret_string()
+            .map(str::to_string)
+            .ok_or_else(|| format!("aws-sm://{}: secret has no string value", secret_id))
+    }
+
+    #[cfg(not(feature = "secretsmanager"))]
+    async fn aws_secret_string(&self, secret_id: &str) -> Result<String, String> {
+        Err(format!(
+            "aws-sm://{} used but built without the 'secretsmanager' feature",
+            secret_id
+        ))
+    }
+}
+
+/// `scheme://path#field` -> (`scheme://path`, field)
+fn split_field(value: &str) -> (&str, Option<&str>) {
+    match value.split_once('#') {
+        Some((uri, field)) if !field.is_empty() => (uri, Some(field)),
+        Some((uri, _)) => (uri, None),
+        None => (value, None),
+    }
+}
+
+/// Keep the pool's database credentials valid for as long as the service runs
+///
+/// New connections pick up replaced credentials; existing ones are recycled by the
+/// pool's idle timeout / max lifetime.
+pub async fn maintain_database_credentials(
+    resolver: std::sync::Arc<SecretResolver>,
+    uri: String,
+    base: PgConnectOptions,
+    pool: PgPool,
+    mut secret: Secret,
+    refresh: Duration,
+) {
+    info!(lease = secret.lease.is_some(), "Database credential renewal started");
+
+    loop {
+        let wait = match &secret.lease {
+            // Renew/replace at two thirds of the lease, like Vault Agent
+            Some(lease) => lease.duration * 2 / 3,
+            None => refresh,
+        };
+        tokio::time::sleep(wait.max(MIN_RENEW_INTERVAL)).await;
+
+        if let Some(lease) = secret.lease.as_ref().filter(|l| l.renewable) {
+            match resolver.renew(lease).await {
+                // A shorter lease than asked for means max TTL is close: rotate instead
+                Ok(renewed) if renewed.duration * 2 >= lease.duration => {
+                    debug!(lease_secs = renewed.duration.as_secs(), "✓ Database credential lease renewed");
+                    secret.lease = Some(renewed);
+                    continue;
+                }
+                Ok(_) => info!("Database credential lease near max TTL, requesting new credentials"),
+                Err(e) => warn!(error = %e, "Lease renewal failed, requesting new credentials"),
+            }
+        }
+
+        let fresh = loop {
+            match resolver.fetch(&uri).await {
+                Ok(fresh) => break fresh,
+                Err(e) => {
+                    warn!(error = %e, retry_secs = RETRY_DELAY.as_secs(), "✗ Failed to refresh database credentials");
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+            }
+        };
+
+        if fresh.lease.is_some() || fresh.data != secret.data {
+            match fresh.connect_options(&base) {
+                Ok(options) => {
+                    // Stay on the host the pool failed over to (`db::DatabaseHosts`)
+                    let current = pool.connect_options();
+                    pool.set_connect_options(options.host(current.get_host()).port(current.get_port()));
+                    info!("✓ Database credentials replaced");
+                }
+                Err(e) => warn!(error = %e, "✗ New database credentials are unusable, keeping the old ones"),
+            }
+        }
+        secret = fresh;
+    }
+}
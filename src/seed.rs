@@ -0,0 +1,266 @@
+//! `notifications-service seed`: dev data for running the stack locally.
+//!
+//! Creates users (random ids, printed at the end), one or two devices each with fake
+//! FCM tokens, and notifications of mixed types and priorities spread over the last
+//! 30 days. Notifications are stored as already processed (history) unless `--deliver`
+//! is given, so fake tokens never reach a real FCM project by accident.
+//!
+//! ```text
+//! notifications-service seed [--users 20] [--per-user 50] [--tenant default] [--deliver] [--reset]
+//! ```
+
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+const USAGE: &str = "usage: notifications-service seed [--users <n>] [--per-user <n>] [--tenant <id>] [--deliver] [--reset]";
+/// `created_by` of seeded rows (what `--reset` deletes)
+const SEED_CREATOR: &str = "seed";
+const SEED_TOKEN_PREFIX: &str = "seed-";
+
+#[derive(Debug, Clone)]
+pub struct SeedArgs {
+    pub users: usize,
+    pub per_user: usize,
+    pub tenant_id: String,
+    /// Leave notifications unprocessed so the worker delivers them (use with mock-fcm)
+    pub deliver: bool,
+    /// Delete earlier seed data first
+    pub reset: bool,
+}
+
+impl SeedArgs {
+    /// Parse the arguments after `seed`
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Self {
+            users: 20,
+            per_user: 50,
+            tenant_id: "default".to_string(),
+            deliver: false,
+            reset: false,
+        };
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--deliver" => parsed.deliver = true,
+                "--reset" => parsed.reset = true,
+                "--users" | "--per-user" | "--tenant" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value\n{}", flag, USAGE))?;
+                    match flag.as_str() {
+                        "--users" => parsed.users = parse_count(flag, value)?,
+                        "--per-user" => parsed.per_user = parse_count(flag, value)?,
+                        _ => parsed.tenant_id = value.clone(),
+                    }
+                }
+                _ => return Err(format!("Unknown option '{}'\n{}", flag, USAGE)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+fn parse_count(flag: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got '{}'\n{}", flag, value, USAGE))
+}
+
+/// Notification shapes the apps render differently
+struct Kind {
+    notification_type: &'static str,
+    priority: &'static str,
+    title: &'static str,
+    /// `{actor}` is replaced by an actor name
+    message: &'static str,
+    target_type: Option<&'static str>,
+}
+
+const KINDS: &[Kind] = &[
+    Kind {
+        notification_type: "post_liked",
+        priority: "normal",
+        title: "New like",
+        message: "{actor} liked your post",
+        target_type: Some("post"),
+    },
+    Kind {
+        notification_type: "comment_added",
+        priority: "normal",
+        title: "New comment",
+        message: "{actor} commented: \"Looks great, count me in!\"",
+        target_type: Some("post"),
+    },
+    Kind {
+        notification_type: "message_received",
+        priority: "high",
+        title: "{actor}",
+        message: "Are you still coming tonight?",
+        target_type: Some("conversation"),
+    },
+    Kind {
+        notification_type: "friend_request",
+        priority: "normal",
+        title: "Friend request",
+        message: "{actor} wants to connect with you",
+        target_type: Some("user"),
+    },
+    Kind {
+        notification_type: "event_reminder",
+        priority: "high",
+        title: "Starting soon",
+        message: "Sunday run in the park starts in 1 hour",
+        target_type: Some("activity"),
+    },
+    Kind {
+        notification_type: "security_alert",
+        priority: "critical",
+        title: "New sign-in",
+        message: "Your account was used to sign in on a new device",
+        target_type: None,
+    },
+    Kind {
+        notification_type: "marketing",
+        priority: "low",
+        title: "Discover new activities",
+        message: "10 popular activities near you this weekend",
+        target_type: None,
+    },
+];
+
+const ACTORS: &[&str] = &["Alice", "Bram", "Chloé", "Daan", "Emma", "Finn", "Guus", "Hana", "Ivo", "Julia"];
+
+/// Result of a seed run
+#[derive(Debug)]
+pub struct SeedSummary {
+    pub user_ids: Vec<Uuid>,
+    pub devices: usize,
+    pub notifications: usize,
+}
+
+impl SeedSummary {
+    pub fn log(&self) {
+        info!("═══════════════════════════════════════════════════════════");
+        info!("  SEED COMPLETE");
+        info!("  Users:          {}", self.user_ids.len());
+        info!("  Devices:        {}", self.devices);
+        info!("  Notifications:  {}", self.notifications);
+        info!("  User ids:");
+        for user_id in &self.user_ids {
+            info!("    {}", user_id);
+        }
+        info!("═══════════════════════════════════════════════════════════");
+    }
+}
+
+/// Insert the dev data (one transaction per user)
+pub async fn run(pool: &PgPool, args: &SeedArgs) -> Result<SeedSummary, String> {
+    if args.reset {
+        reset(pool).await?;
+    }
+    if args.deliver {
+        warn!("--deliver: seeded notifications are queued for delivery to fake device tokens");
+    }
+
+    let now = Utc::now();
+    let mut summary = SeedSummary {
+        user_ids: Vec::with_capacity(args.users),
+        devices: 0,
+        notifications: 0,
+    };
+
+    for user_index in 0..args.users {
+        let user_id = Uuid::new_v4();
+        let mut tx = pool.begin().await.map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        // One or two devices, alternating platform and locale
+        for device_index in 0..(1 + user_index % 2) {
+            let device_type = if (user_index + device_index) % 2 == 0 { "android" } else { "ios" };
+            let locale = if user_index % 3 == 0 { "nl" } else { "en" };
+            sqlx::query(
+                "INSERT INTO activity.user_devices (user_id, fcm_token, device_type, locale, tenant_id)
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(user_id)
+            .bind(format!("{}{}", SEED_TOKEN_PREFIX, Uuid::new_v4().simple()))
+            .bind(device_type)
+            .bind(locale)
+            .bind(&args.tenant_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to insert device: {}", e))?;
+            summary.devices += 1;
+        }
+
+        for n in 0..args.per_user {
+            // Deterministic spread over kinds/actors, newest first
+            let kind = &KINDS[(user_index * 7 + n * 3) % KINDS.len()];
+            let actor = ACTORS[(user_index + n * 5) % ACTORS.len()];
+            let created_at = now - Duration::minutes((n as i64) * 30 * 24 * 60 / args.per_user.max(1) as i64);
+            let target_id = kind.target_type.map(|_| Uuid::new_v4());
+            let group_key = match (kind.target_type, target_id) {
+                (Some(target_type), Some(target_id)) => Some(format!("{}:{}", target_type, target_id)),
+                _ => None,
+            };
+            let deep_link = group_key.as_ref().map(|key| format!("app://{}", key.replace(':', "/")));
+
+            sqlx::query(
+                r#"
+                INSERT INTO activity.notifications (
+                    id, user_id, notification_type, target_type, target_id, title, message,
+                    payload, deep_link, priority, group_key, deliver_at, created_at,
+                    is_processed, tenant_id, created_by, event_source
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $12, $13, $14, $15,
+                        'notifications-service/seed')
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(kind.notification_type)
+            .bind(kind.target_type)
+            .bind(target_id)
+            .bind(kind.title.replace("{actor}", actor))
+            .bind(kind.message.replace("{actor}", actor))
+            .bind(serde_json::json!({ "actor_name": actor }))
+            .bind(deep_link)
+            .bind(kind.priority)
+            .bind(group_key)
+            .bind(created_at)
+            .bind(!args.deliver)
+            .bind(&args.tenant_id)
+            .bind(SEED_CREATOR)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to insert notification: {}", e))?;
+            summary.notifications += 1;
+        }
+
+        tx.commit().await.map_err(|e| format!("Failed to commit seed data: {}", e))?;
+        summary.user_ids.push(user_id);
+    }
+
+    Ok(summary)
+}
+
+/// Remove rows from earlier seed runs
+async fn reset(pool: &PgPool) -> Result<(), String> {
+    let notifications = sqlx::query("DELETE FROM activity.notifications WHERE created_by = $1")
+        .bind(SEED_CREATOR)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete seeded notifications: {}", e))?;
+    let devices = sqlx::query("DELETE FROM activity.user_devices WHERE fcm_token LIKE $1")
+        .bind(format!("{}%", SEED_TOKEN_PREFIX))
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete seeded devices: {}", e))?;
+
+    info!(
+        notifications = notifications.rows_affected(),
+        devices = devices.rows_affected(),
+        "Removed earlier seed data"
+    );
+    Ok(())
+}
@@ -0,0 +1,1107 @@
+//! Service assembly: everything `main()` starts once config and database are ready.
+//!
+//! ```ignore
+//! let service = Service::builder()
+//!     .config(config)
+//!     .database(db)
+//!     .push_provider(fcm)          // optional, default: from FCM_* config
+//!     .sandbox_push_provider(fcm)  // optional, default: from FCM_SANDBOX_* config
+//!     .build()?;
+//! service.run(shutdown_signal()).await?;
+//! ```
+//!
+//! Tests and embedders pass their own (mock) dependencies; anything not given is
+//! built from the config exactly as the binary does.
+
+use crate::analytics::AnalyticsJob;
+use crate::api::{self, ApiState};
+use crate::archive::{ArchiveStore, Archiver};
+use crate::attachments::AttachmentSigner;
+use crate::campaigns::{BroadcastRunner, CampaignRunner};
+use crate::config::{BroadcastFanout, Config, ConsumptionMode, WakeSource};
+use crate::db::listener::WakeSignal;
+use crate::db::{Database, ReplicationSource, SchemaQueries};
+use crate::dev_sandbox::DevSandbox;
+use crate::digest::{DigestJob, EmailRelay};
+use crate::grpc;
+use crate::ingest::{self, backpressure::Backpressure};
+use crate::ip_allowlist::{self, IpAllowlist};
+use crate::mtls;
+use crate::payload_sink;
+use crate::policy::{ContentPolicy, HttpPolicyHook, PolicyGate};
+#[cfg(feature = "kafka")]
+use crate::shadow::KafkaShadowQueue;
+use crate::shadow::{ShadowQueue, ShadowRunner};
+use crate::protocol::Protocols;
+use crate::push::FcmClient;
+use crate::queue::{PgQueueStore, QueueStore};
+use crate::realtime::{RealtimeBus, UserBatchingBus};
+use crate::receipts::ReceiptDispatcher;
+use crate::recurring::RecurringScheduler;
+use crate::reengagement::ReengagementJob;
+use crate::signing::BroadcastSigner;
+use crate::slo_alerts::SloAlertJob;
+use crate::status::{Channels, ServiceStatus};
+use crate::templates::TemplateLookups;
+use crate::worker::actions::ActionRelay;
+use crate::worker::dismiss::Dismisser;
+use crate::worker::read_state::ReadStateFanout;
+use crate::worker::router::ChannelRouter;
+use crate::worker::test_send::TestSender;
+use crate::worker::{backlog, events, foreground, AudienceResolver, BusHealth, ChannelCosts, ChannelHealth, CostLedger, DeliveryWindows, Drain, FallbackChains, Leadership, NotificationWorker};
+use axum::http::StatusCode;
+use axum::{routing::get, Json, Router};
+use bus_client::BusClient;
+use chrono_tz::Tz;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use serde_json::json;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, trace, warn};
+
+#[derive(Default)]
+pub struct ServiceBuilder {
+    config: Option<Config>,
+    database: Option<Database>,
+    push_provider: Option<Arc<FcmClient>>,
+    sandbox_push_provider: Option<Arc<FcmClient>>,
+    bus_client: Option<Arc<dyn RealtimeBus>>,
+    content_policy: Option<Arc<dyn ContentPolicy>>,
+    shadow_queue: Option<Arc<dyn ShadowQueue>>,
+    queue_store: Option<Arc<dyn QueueStore>>,
+    metrics: Option<PrometheusHandle>,
+}
+
+impl ServiceBuilder {
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    pub fn database(mut self, database: Database) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Push provider to use instead of the one configured by FCM_* settings
+    pub fn push_provider(mut self, push_provider: Arc<FcmClient>) -> Self {
+        self.push_provider = Some(push_provider);
+        self
+    }
+
+    /// Push provider for sandbox devices instead of the one configured by FCM_SANDBOX_* settings
+    pub fn sandbox_push_provider(mut self, push_provider: Arc<FcmClient>) -> Self {
+        self.sandbox_push_provider = Some(push_provider);
+        self
+    }
+
+    /// Bus to use instead of the websocket-bus client configured by WEBSOCKET_BUS_URL (e.g. `MemoryBus`)
+    pub fn bus_client(mut self, bus_client: Arc<dyn RealtimeBus>) -> Self {
+        self.bus_client = Some(bus_client);
+        self
+    }
+
+    /// Content policy to use instead of the HTTP hook configured by POLICY_HOOK_URL
+    pub fn content_policy(mut self, policy: Arc<dyn ContentPolicy>) -> Self {
+        self.content_policy = Some(policy);
+        self
+    }
+
+    /// Shadow queue backend to use instead of the one configured by SHADOW_KAFKA_BROKERS (e.g. `MemoryShadowQueue`)
+    pub fn shadow_queue(mut self, queue: Arc<dyn ShadowQueue>) -> Self {
+        self.shadow_queue = Some(queue);
+        self
+    }
+
+    /// Queue backend to use instead of `activity.notifications` (`PgQueueStore`)
+    ///
+    /// Batch consumption only: CONSUMPTION_MODE=transactional claims Postgres rows.
+    pub fn queue_store(mut self, store: Arc<dyn QueueStore>) -> Self {
+        self.queue_store = Some(store);
+        self
+    }
+
+    /// Handle of the installed Prometheus recorder, rendered at `/metrics`
+    ///
+    /// Without one `/metrics` is served from a recorder that is not installed
+    /// globally (and stays empty), so several services can share a process.
+    pub fn metrics(mut self, metrics: PrometheusHandle) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Validate the configuration and create the clients; nothing is started yet
+    pub fn build(self) -> Result<Service, String> {
+        let config = self.config.ok_or("Service requires a config")?;
+        let db = self.database.ok_or("Service requires a database")?;
+        payload_sink::install(&config.debug)?;
+        ingest::rate_limit::install(&config)?;
+
+        let fcm_client = match self.push_provider {
+            Some(client) => Some(client),
+            None => fcm_from_config(&config),
+        };
+        let fcm_sandbox_client = match self.sandbox_push_provider {
+            Some(client) => Some(client),
+            None => fcm_sandbox_from_config(&config),
+        };
+        let bus_client = match self.bus_client {
+            Some(client) => Some(client),
+            None => bus_from_config(&config),
+        };
+        let queue_store: Arc<dyn QueueStore> = match self.queue_store {
+            Some(_) if config.consumption_mode == ConsumptionMode::Transactional => {
+                return Err("CONSUMPTION_MODE=transactional needs the Postgres queue store".to_string());
+            }
+            Some(store) => store,
+            None => Arc::new(PgQueueStore::from_config(&db, &config)),
+        };
+        info!(queue = queue_store.name(), "Queue store");
+
+        // Broadcast signing key (optional)
+        let broadcast_signer = match config.broadcast_signing_key.as_deref().map(BroadcastSigner::from_base64_seed) {
+            Some(Ok(signer)) => {
+                info!(key_id = %signer.public_key().key_id, "Broadcast signing enabled");
+                Some(Arc::new(signer))
+            }
+            Some(Err(e)) => return Err(format!("Invalid broadcast signing key: {}", e)),
+            None => None,
+        };
+
+        // Attachment URL signing (optional): a half-configured store is a startup error
+        let attachment_signer = AttachmentSigner::from_config(&config)?.map(Arc::new);
+
+        // Escalation of delivery SLO breaches to operators (optional)
+        let slo_alerts = SloAlertJob::from_config(db.pool().clone(), &config)?.map(Arc::new);
+
+        // IP allowlists (optional): invalid CIDRs are a startup error, never "allow all"
+        let parse_allowlist = |name, cidrs: &Option<String>| -> Result<_, String> {
+            match cidrs {
+                Some(cidrs) => {
+                    let allowlist = IpAllowlist::parse(name, cidrs, config.trusted_proxy_hops)?;
+                    info!(allowlist = name, cidrs = %cidrs, proxy_hops = config.trusted_proxy_hops, "IP allowlist enabled");
+                    Ok(Some(Arc::new(allowlist)))
+                }
+                None => Ok(None),
+            }
+        };
+        let admin_allowlist = parse_allowlist("admin", &config.admin_allowed_cidrs)?;
+        let ingest_allowlist = parse_allowlist("ingest", &config.ingest_allowed_cidrs)?;
+
+        // Roles for client certificates: a typo is a startup error, never "admin for all"
+        let mtls_identities = match config.mtls.as_ref().and_then(|mtls| mtls.identities.as_deref()) {
+            Some(value) => Arc::new(mtls::IdentityMap::parse(value)?),
+            None => Arc::default(),
+        };
+
+        let fallback_chains = match &config.fallback_chains {
+            Some(value) => {
+                let chains = FallbackChains::parse(value)?;
+                info!(chains = %value, "Per-priority fallback chains configured");
+                chains
+            }
+            None => FallbackChains::default(),
+        };
+
+        let protocols = match &config.ws_protocols {
+            Some(value) => {
+                let protocols = Protocols::parse(value)?;
+                info!(protocols = %value, "Client protocol versions configured");
+                protocols
+            }
+            None => Protocols::default(),
+        };
+
+        let channel_costs = match &config.channel_costs {
+            Some(value) => {
+                let costs = ChannelCosts::parse(value)?;
+                info!(costs = %value, "Delivery costs per channel configured");
+                costs
+            }
+            None => ChannelCosts::default(),
+        };
+
+        let delivery_windows = match &config.delivery_windows {
+            Some(value) => {
+                let windows = DeliveryWindows::parse(value)?;
+                info!(windows = %value, timezone = %config.delivery_window_timezone, "Delivery windows configured");
+                windows
+            }
+            None => DeliveryWindows::default(),
+        };
+        let window_timezone: Tz = config
+            .delivery_window_timezone
+            .parse()
+            .map_err(|_| format!("Unknown DELIVERY_WINDOW_TIMEZONE '{}'", config.delivery_window_timezone))?;
+
+        // Shedding of non-urgent creates while the queue is backed up (off without limits)
+        let backpressure = Arc::new(Backpressure::from_config(&config)?);
+
+        if config.wake_source == WakeSource::Replication {
+            ReplicationSource::validate_publication(&config.replication_publication)?;
+        }
+
+        let policy_timeout = Duration::from_millis(config.policy_hook_timeout_ms.max(1));
+        let content_policy = match (self.content_policy, &config.policy_hook_url) {
+            (Some(policy), _) => Some(policy),
+            (None, Some(url)) => Some(Arc::new(HttpPolicyHook::new(url, policy_timeout)?) as Arc<dyn ContentPolicy>),
+            (None, None) => None,
+        };
+        let policy = content_policy.map(|hook| {
+            info!(
+                policy = hook.name(),
+                timeout_ms = policy_timeout.as_millis() as u64,
+                fail_mode = ?config.policy_hook_fail_mode,
+                "Content policy hook enabled"
+            );
+            PolicyGate::new(hook, policy_timeout, config.policy_hook_fail_mode)
+        });
+
+        let shadow_queue = match (self.shadow_queue, &config.shadow_kafka) {
+            (Some(queue), _) => Some(queue),
+            #[cfg(feature = "kafka")]
+            (None, Some(shadow_kafka)) => Some(Arc::new(
+                KafkaShadowQueue::new(shadow_kafka).map_err(|e| format!("Failed to create shadow Kafka queue: {}", e))?,
+            ) as Arc<dyn ShadowQueue>),
+            #[cfg(not(feature = "kafka"))]
+            (None, Some(_)) => {
+                warn!("SHADOW_KAFKA_BROKERS set but built without the 'kafka' feature - shadow queue disabled");
+                None
+            }
+            (None, None) => None,
+        };
+
+        let metrics = self
+            .metrics
+            .unwrap_or_else(|| PrometheusBuilder::new().build_recorder().handle());
+
+        Ok(Service {
+            config,
+            db,
+            fcm_client,
+            fcm_sandbox_client,
+            bus_client,
+            broadcast_signer,
+            attachment_signer,
+            slo_alerts,
+            admin_allowlist,
+            ingest_allowlist,
+            mtls_identities,
+            backpressure,
+            fallback_chains,
+            channel_costs,
+            protocols,
+            delivery_windows,
+            window_timezone,
+            policy,
+            shadow_queue,
+            queue_store,
+            metrics,
+        })
+    }
+}
+
+/// The assembled service: worker, NOTIFY listener, ingestion sources and APIs
+pub struct Service {
+    config: Config,
+    db: Database,
+    fcm_client: Option<Arc<FcmClient>>,
+    /// Sandbox devices (dev builds), None = they use `fcm_client`
+    fcm_sandbox_client: Option<Arc<FcmClient>>,
+    bus_client: Option<Arc<dyn RealtimeBus>>,
+    broadcast_signer: Option<Arc<BroadcastSigner>>,
+    attachment_signer: Option<Arc<AttachmentSigner>>,
+    slo_alerts: Option<Arc<SloAlertJob>>,
+    admin_allowlist: Option<Arc<IpAllowlist>>,
+    ingest_allowlist: Option<Arc<IpAllowlist>>,
+    /// Roles per client certificate identity (MTLS_IDENTITIES)
+    mtls_identities: Arc<mtls::IdentityMap>,
+    backpressure: Arc<Backpressure>,
+    fallback_chains: FallbackChains,
+    channel_costs: ChannelCosts,
+    protocols: Protocols,
+    delivery_windows: DeliveryWindows,
+    window_timezone: Tz,
+    /// Content policy hook (None = no POLICY_HOOK_URL and none given to the builder)
+    policy: Option<PolicyGate>,
+    /// Dual write target while migrating queues (None = shadow mode off)
+    shadow_queue: Option<Arc<dyn ShadowQueue>>,
+    /// Where the worker takes due notifications from and records outcomes
+    queue_store: Arc<dyn QueueStore>,
+    metrics: PrometheusHandle,
+}
+
+impl Service {
+    pub fn builder() -> ServiceBuilder {
+        ServiceBuilder::default()
+    }
+
+    /// Start everything and serve HTTP until `shutdown` resolves
+    ///
+    /// Background tasks are stopped before this returns.
+    pub async fn run<F>(self, shutdown: F) -> Result<(), String>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let config = &self.config;
+        let db = &self.db;
+
+        // Actor/target lookups for templates: a broken query fails startup, not every render
+        let template_lookups = TemplateLookups::from_config(db.pool().clone(), config);
+        if let Some(lookups) = &template_lookups {
+            lookups.validate().await?;
+            info!(
+                timeout_ms = config.template_lookup_timeout_ms,
+                cache_ttl_secs = config.template_lookup_cache_ttl_secs,
+                "Template lookups enabled"
+            );
+        }
+        // Same for the audience membership query
+        let audiences = AudienceResolver::from_config(db.pool().clone(), config);
+        audiences.validate().await?;
+        if audiences.is_query() {
+            info!(timeout_ms = config.audience_query_timeout_ms, "Audience members from AUDIENCE_MEMBERS_QUERY");
+        }
+        // Schema drift: name the missing columns now rather than fail every claim with a decode error
+        match SchemaQueries::check(db.pool()).await {
+            Ok(report) if report.ok => debug!("Schema check passed"),
+            Ok(report) => report.log(),
+            Err(e) => warn!(error = %e, "Schema check failed to run"),
+        }
+
+        // Developer sandbox (optional): creates its tenant, so not on a read-only replica
+        let dev_sandbox = if config.read_only { None } else { DevSandbox::from_config(db.pool(), config).await? };
+
+        let mut tasks: Vec<JoinHandle<()>> = Vec::new();
+        // The Bus traffic is stopped after the drain (the queue consumers, started below, first)
+        let mut bus_tasks: Vec<JoinHandle<()>> = Vec::new();
+
+        // NOTIFY signals to the worker (coalesced while it is busy)
+        let wake = WakeSignal::new();
+
+        // Move the pool (and the wake source with it) to a healthy host when the active one fails
+        // (not on a read-only replica: it is in recovery by design, which the check counts as down)
+        if db.hosts.has_standbys() && !config.read_only {
+            let interval = Duration::from_secs(config.database_health_check_interval_secs.max(1));
+            tasks.push(tokio::spawn(db.hosts.clone().run(interval)));
+        }
+
+        // Start the wake source: Postgres NOTIFY listener or a logical replication slot (the store's)
+        let listener_handle = if config.read_only {
+            // Nothing to wake on a read-only replica (and LISTEN needs the primary)
+            tokio::spawn(std::future::pending())
+        } else {
+            debug!("Starting wake source...");
+            let store = self.queue_store.clone();
+            let signal = wake.clone();
+            tokio::spawn(async move {
+                match store.listen(signal).await {
+                    // A store without push: the failsafe poll wakes the worker from now on
+                    Ok(()) => std::future::pending().await,
+                    Err(e) => error!(error = %e, queue = store.name(), "Wake source failed"),
+                }
+            })
+        };
+
+        // Delivery costs per tenant and day, saved by this task and on shutdown
+        let costs = CostLedger::new(db.pool().clone(), self.channel_costs.clone());
+        tasks.push(tokio::spawn(costs.clone().run(crate::worker::costs::FLUSH_INTERVAL)));
+
+        // Start worker
+        debug!("Starting notification worker...");
+        let delivery_events = events::channel();
+        let drain = Drain::default();
+        // Lanes publishing at the same time share one Bus request
+        let worker_bus = self
+            .bus_client
+            .clone()
+            .map(|bus| Arc::new(UserBatchingBus::new(bus)) as Arc<dyn RealtimeBus>);
+        let mut worker = NotificationWorker::new(db, config.clone(), worker_bus, self.fcm_client.clone())
+        .with_queue(self.queue_store.clone())
+        .with_sandbox_fcm(self.fcm_sandbox_client.clone())
+        .with_dev_sandbox(dev_sandbox.clone())
+        .with_events(delivery_events.clone())
+        .with_fallback_chains(self.fallback_chains.clone())
+        .with_delivery_windows(self.delivery_windows.clone(), self.window_timezone)
+        .with_drain(drain.clone())
+        .with_costs(costs.clone())
+        .with_protocols(self.protocols.clone())
+        .with_template_lookups(template_lookups.clone())
+        .with_audiences(audiences)
+        .with_attachments(self.attachment_signer.clone())
+        .with_policy(self.policy.clone());
+        if let Some(signer) = &self.broadcast_signer {
+            worker = worker.with_signer(signer.clone());
+        }
+        let bus_health = match &self.bus_client {
+            Some(bus) if config.bus_health_interval_secs > 0 => {
+                let health = BusHealth::default();
+                bus_tasks.push(tokio::spawn(health.clone().run(
+                    bus.clone(),
+                    Duration::from_secs(config.bus_health_interval_secs),
+                    Duration::from_secs(config.bus_health_max_backoff_secs),
+                )));
+                worker = worker.with_bus_health(health.clone());
+                Some(health)
+            }
+            _ => None,
+        };
+        let channel_health = ChannelHealth::from_config(config);
+        if let Some(health) = &channel_health {
+            worker = worker.with_channel_health(health.clone());
+        }
+        // Warm standby: everything runs, but the worker only claims while holding the lease
+        let leadership = (config.leader_election && !config.read_only).then(|| {
+            Leadership::new(db.pool().clone(), &config.instance_name, Duration::from_secs(config.leader_lease_secs))
+                .with_region(config.region.as_deref())
+        });
+        if let Some(leadership) = &leadership {
+            worker = worker.with_leadership(leadership.clone());
+        }
+        let lease_task = leadership.as_ref().map(|leadership| tokio::spawn(leadership.clone().run(wake.clone())));
+        let device_cache = worker.device_cache();
+        // Devices other services change directly: drop cached lists on their NOTIFY
+        if let Some(cache) = device_cache.as_ref().filter(|_| !config.read_only) {
+            tasks.push(tokio::spawn(cache.clone().follow_changes(db.hosts.clone())));
+        }
+        // App came to the foreground (any replica's API): deliver that user's due notifications now
+        if config.foreground_boost && !config.read_only {
+            tasks.push(tokio::spawn(foreground::follow_foreground(db.hosts.clone(), wake.clone())));
+        }
+        let worker_handle = if config.read_only {
+            warn!("READ_ONLY - serving reads only, the worker does not run");
+            tokio::spawn(std::future::pending())
+        } else {
+            // What piled up while no worker ran (an outage, a long deploy), before it shrinks
+            backlog::report_startup(db.pool(), self.bus_client.clone(), config).await;
+            let handle = tokio::spawn(async move {
+                worker.run(wake).await;
+            });
+            info!(
+                poll_interval_secs = config.worker_poll_interval_secs,
+                batch_size = config.worker_batch_size,
+                "Notification worker started"
+            );
+            handle
+        };
+
+        // Publish delivery events on the Bus (optional)
+        match (&self.bus_client, &config.bus_events_topic) {
+            (Some(_), Some(_)) if config.is_simulated() => {
+                info!("DELIVERY_MODE=simulate - delivery events are not published on the Bus")
+            }
+            (Some(bus), Some(topic)) => {
+                bus_tasks.push(tokio::spawn(events::publish_to_bus(
+                    bus.clone(),
+                    topic.clone(),
+                    delivery_events.subscribe(),
+                )));
+            }
+            (None, Some(_)) => warn!("BUS_EVENTS_TOPIC set but the Bus is not configured - delivery events disabled"),
+            _ => {}
+        }
+
+        // Background jobs and queue consumers all write: a read-only replica runs none of them
+        let ingestion = if config.read_only {
+            info!("READ_ONLY - background jobs and ingestion disabled");
+            Vec::new()
+        } else {
+            tasks.extend(self.start_jobs(&costs).await);
+            self.start_ingestion(&delivery_events).await
+        };
+
+        // HTTP server (health + metrics, user API if configured)
+        debug!("Starting HTTP server...");
+        let metrics = self.metrics.clone();
+        let readiness = (drain.clone(), self.bus_client.is_some(), bus_health.clone());
+        let mut router = Router::new()
+            .route("/health", get(health_handler))
+            .route("/healthz", get(health_handler))
+            .route("/readyz", get(move || ready_handler(readiness.clone())))
+            .route("/metrics", get(move || async move { metrics.render() }));
+
+        // Public keys for client-side verification of signed broadcasts
+        if let Some(signer) = &self.broadcast_signer {
+            let keys = serde_json::json!({ "keys": [signer.public_key()] });
+            router = router.route(
+                "/.well-known/broadcast-signing-keys",
+                get(move || async move { axum::Json(keys) }),
+            );
+        }
+
+        // Webhook ingestion: sources without a row in webhook_sources are rejected
+        let mut ingest_router = ip_allowlist::protect(
+            ingest::webhook::router(
+                db.pool().clone(),
+                self.backpressure.clone(),
+                Duration::from_secs(config.webhook_tolerance_secs),
+            ),
+            self.ingest_allowlist.clone(),
+        );
+        if config.read_only {
+            ingest_router = ingest_router.layer(axum::middleware::from_fn(api::reject_writes));
+        }
+        router = router.nest("/ingest", ingest_router.clone());
+
+        let postgres = match db.server_version().await {
+            Ok(version) => Some(version),
+            Err(e) => {
+                warn!(error = %e, "Failed to read the database server version");
+                None
+            }
+        };
+        let status = Arc::new(ServiceStatus::new(
+            config,
+            Channels {
+                bus: self.bus_client.is_some(),
+                fcm: self.fcm_client.is_some(),
+                fcm_sandbox: self.fcm_sandbox_client.is_some(),
+                email_digest: config.digest_email_url.is_some() && config.digest_poll_interval_secs > 0,
+            },
+            postgres,
+        ));
+
+        let api_state = ApiState {
+            pool: db.pool().clone(),
+            jwt_secret: config.jwt_secret.as_deref().map(Into::into),
+            bus_client: self.bus_client.clone(),
+            protocols: self.protocols.clone(),
+            admin_token: config.admin_token.as_deref().map(Into::into),
+            admin_allowlist: self.admin_allowlist.clone(),
+            device_cache,
+            drain: drain.clone(),
+            prestop_timeout_secs: config.prestop_timeout_secs,
+            bus_health,
+            channel_health,
+            leadership: leadership.clone(),
+            dismisser: (!config.first_ack_types.is_empty()).then(|| {
+                Arc::new(
+                    Dismisser::new(db.pool().clone(), config, self.bus_client.clone(), self.fcm_client.clone())
+                        .with_sandbox_fcm(self.fcm_sandbox_client.clone())
+                        .with_dev_sandbox(dev_sandbox.clone())
+                        .with_protocols(self.protocols.clone()),
+                )
+            }),
+            test_sender: Arc::new(
+                TestSender::new(db.pool().clone(), config, self.bus_client.clone(), self.fcm_client.clone())
+                    .with_sandbox_fcm(self.fcm_sandbox_client.clone())
+                    .with_dev_sandbox(dev_sandbox.clone())
+                    .with_protocols(self.protocols.clone())
+                    .with_template_lookups(template_lookups),
+            ),
+            read_state: Arc::new(
+                ReadStateFanout::new(db.pool().clone(), config, self.bus_client.clone(), self.fcm_client.clone())
+                    .with_sandbox_fcm(self.fcm_sandbox_client.clone())
+                    .with_dev_sandbox(dev_sandbox.clone())
+                    .with_protocols(self.protocols.clone()),
+            ),
+            actions: Arc::new(ActionRelay::new(db.pool().clone(), config, self.bus_client.clone())),
+            router: Arc::new(
+                ChannelRouter::new(db.pool().clone())
+                    .with_chains(self.fallback_chains.clone())
+                    .with_windows(self.delivery_windows.clone(), self.window_timezone),
+            ),
+            limits: ingest::limits::CreateLimits::from_config(config),
+            backpressure: self.backpressure.clone(),
+            foreground_boost: config.foreground_boost,
+            status: status.clone(),
+            read_only: config.read_only,
+            attachments: self.attachment_signer.clone(),
+            dev_sandbox,
+        };
+
+        if config.has_api() {
+            router = router
+                .nest("/api/v1", api::router(api_state.clone()))
+                .nest("/admin", api::admin_router(api_state.clone()));
+            info!(
+                user_endpoints = config.jwt_secret.is_some(),
+                management_endpoints = config.admin_token.is_some(),
+                "API enabled at /api/v1"
+            );
+        } else {
+            warn!("JWT_SECRET/ADMIN_TOKEN not configured - API disabled");
+        }
+
+        // Start mTLS listener (optional): client certificates instead of bearer tokens
+        if let Some(mtls_config) = &config.mtls {
+            match mtls::server_config(mtls_config) {
+                Ok(tls) => {
+                    let mtls_router = Router::new()
+                        .nest("/api/v1", api::router(api_state.clone()))
+                        .nest("/ingest", ingest_router);
+                    let mtls_addr = format!("{}:{}", config.server_host, mtls_config.port);
+                    let identities = self.mtls_identities.clone();
+                    match mtls_addr.parse() {
+                        Ok(mtls_addr) => {
+                            tasks.push(tokio::spawn(async move {
+                                if let Err(e) = mtls::serve(mtls_addr, mtls_router, tls, identities).await {
+                                    error!(error = %e, "mTLS listener stopped");
+                                }
+                            }));
+                        }
+                        Err(e) => error!(error = %e, addr = %mtls_addr, "Invalid mTLS address - listener disabled"),
+                    }
+                }
+                Err(e) => error!(error = %e, "Failed to load mTLS certificates - listener disabled"),
+            }
+        }
+
+        // Start gRPC API (optional, requires ADMIN_TOKEN)
+        match (config.grpc_port, &config.admin_token) {
+            // Only CreateNotification, which a read-only replica can't serve
+            (Some(_), _) if config.read_only => info!("READ_ONLY - gRPC API disabled"),
+            (Some(port), Some(token)) => match format!("{}:{}", config.server_host, port).parse() {
+                Ok(grpc_addr) => {
+                    let (pool, token) = (db.pool().clone(), Arc::from(token.as_str()));
+                    let backpressure = self.backpressure.clone();
+                    tasks.push(tokio::spawn(async move {
+                        if let Err(e) = grpc::serve(grpc_addr, pool, backpressure, token).await {
+                            error!(error = %e, "gRPC server stopped");
+                        }
+                    }));
+                }
+                Err(e) => error!(error = %e, "Invalid gRPC address - gRPC API disabled"),
+            },
+            (Some(_), None) => warn!("GRPC_PORT set but ADMIN_TOKEN not configured - gRPC API disabled"),
+            (None, _) => debug!("GRPC_PORT not configured - gRPC API disabled"),
+        }
+
+        let addr = config.server_addr();
+        let tcp_listener = match TcpListener::bind(&addr).await {
+            Ok(l) => {
+                debug!("TCP listener bound to {}", addr);
+                l
+            }
+            Err(e) => {
+                listener_handle.abort();
+                worker_handle.abort();
+                tasks.iter().chain(&ingestion).chain(&bus_tasks).for_each(JoinHandle::abort);
+                return Err(format!("Failed to bind HTTP server on {}: {}", addr, e));
+            }
+        };
+
+        status.log();
+
+        // HTTP is stopped last: while draining, /readyz reports not-ready and the API keeps answering
+        let (stop_http, http_stopped) = oneshot::channel::<()>();
+        let mut server_handle = tokio::spawn(async move {
+            axum::serve(tcp_listener, router.into_make_service_with_connect_info::<SocketAddr>())
+                .with_graceful_shutdown(async {
+                    let _ = http_stopped.await;
+                })
+                .await
+        });
+
+        // Run until shutdown is requested; any task ending before that is a failure
+        let (mut listener_handle, mut worker_handle) = (listener_handle, worker_handle);
+        let failure = tokio::select! {
+            _ = &mut listener_handle => Some("Wake source stopped unexpectedly".to_string()),
+            _ = &mut worker_handle => Some("Worker stopped unexpectedly".to_string()),
+            served = &mut server_handle => Some(match served {
+                Ok(Ok(())) => "Server stopped unexpectedly".to_string(),
+                Ok(Err(e)) => format!("Server failed: {}", e),
+                Err(e) => format!("Server task failed: {}", e),
+            }),
+            _ = shutdown => None,
+        };
+        if let Some(failure) = failure {
+            for task in [&listener_handle, &worker_handle].into_iter().chain(&lease_task) {
+                task.abort();
+            }
+            server_handle.abort();
+            tasks.iter().chain(&ingestion).chain(&bus_tasks).for_each(JoinHandle::abort);
+            return Err(failure);
+        }
+
+        ShutdownSequence {
+            ingestion,
+            wake_source: listener_handle,
+            drain,
+            worker: worker_handle,
+            costs,
+            leader: leadership.zip(lease_task),
+            bus: bus_tasks,
+            stop_http,
+            server: server_handle,
+            background: tasks,
+        }
+        .run(Duration::from_secs(config.shutdown_timeout_secs))
+        .await
+    }
+
+    /// Scheduler, campaigns, receipts, archiver and the other jobs configured
+    async fn start_jobs(&self, costs: &CostLedger) -> Vec<JoinHandle<()>> {
+        let (config, db) = (&self.config, &self.db);
+        let mut tasks = Vec::new();
+
+        // Start receipt dispatcher (optional)
+        if let Some(secret) = &config.receipt_signing_secret {
+            let dispatcher = ReceiptDispatcher::new(db.pool().clone(), secret.clone(), config.receipt_max_attempts);
+            tasks.push(tokio::spawn(async move { dispatcher.run().await }));
+            info!(max_attempts = config.receipt_max_attempts, "Delivery receipts enabled");
+        } else {
+            debug!("RECEIPT_SIGNING_SECRET not configured - delivery receipts disabled");
+        }
+
+        // Start recurring notification scheduler
+        if config.recurring_poll_interval_secs > 0 {
+            let scheduler = RecurringScheduler::new(
+                db.pool().clone(),
+                Duration::from_secs(config.recurring_poll_interval_secs),
+            );
+            tasks.push(tokio::spawn(async move { scheduler.run().await }));
+        } else {
+            debug!("RECURRING_POLL_INTERVAL_SECS=0 - recurring notifications disabled");
+        }
+
+        // Start re-engagement job (only tenants with a policy are nudged)
+        if config.reengagement_interval_secs > 0 {
+            let job = ReengagementJob::new(db.pool().clone(), Duration::from_secs(config.reengagement_interval_secs));
+            tasks.push(tokio::spawn(async move { job.run().await }));
+        } else {
+            debug!("REENGAGEMENT_INTERVAL_SECS=0 - re-engagement disabled");
+        }
+
+        // Start analytics rollups (GET /admin/analytics)
+        if config.analytics_interval_secs > 0 {
+            let job = AnalyticsJob::new(
+                db.pool().clone(),
+                Duration::from_secs(config.analytics_interval_secs),
+                config.analytics_backfill_days,
+            );
+            tasks.push(tokio::spawn(async move { job.run().await }));
+        } else {
+            debug!("ANALYTICS_INTERVAL_SECS=0 - analytics rollups disabled");
+        }
+
+        // Start SLO alerting (GET /admin/stats lists the open incidents)
+        if let Some(job) = self.slo_alerts.clone() {
+            tasks.push(tokio::spawn(async move { job.run().await }));
+        } else {
+            debug!("SLO_ALERT_INTERVAL_SECS=0 - SLO alerts disabled");
+        }
+
+        // Start campaign runner
+        if config.campaign_poll_interval_secs > 0 {
+            let runner = CampaignRunner::new(
+                db.pool().clone(),
+                Duration::from_secs(config.campaign_poll_interval_secs),
+            );
+            tasks.push(tokio::spawn(async move { runner.run().await }));
+        } else {
+            debug!("CAMPAIGN_POLL_INTERVAL_SECS=0 - campaigns disabled");
+        }
+
+        // Start per-user broadcast fan-out (released on the campaign interval, even when campaigns are off)
+        if config.broadcast_fanout == BroadcastFanout::PerUser {
+            let runner = BroadcastRunner::new(
+                db.pool().clone(),
+                Duration::from_secs(config.campaign_poll_interval_secs.max(1)),
+                config.broadcast_fanout_rate_per_minute,
+                config.broadcast_fanout_jitter_secs,
+            );
+            tasks.push(tokio::spawn(async move { runner.run().await }));
+        }
+
+        // Start cold-storage archiver (optional)
+        match (&config.archive_url, config.archive_poll_interval_secs) {
+            (Some(_), 0) => debug!("ARCHIVE_POLL_INTERVAL_SECS=0 - archiving disabled"),
+            (Some(_), poll_interval_secs) => match ArchiveStore::open(config).await {
+                Ok(store) => {
+                    let archiver = Archiver::new(
+                        db.pool().clone(),
+                        store,
+                        config.archive_after_days,
+                        config.archive_batch_size,
+                        Duration::from_secs(poll_interval_secs),
+                    );
+                    tasks.push(tokio::spawn(async move { archiver.run().await }));
+                }
+                Err(e) => error!(error = %e, "Invalid archive store - archiving disabled"),
+            },
+            (None, _) => debug!("ARCHIVE_URL not configured - archiving disabled"),
+        }
+
+        // Start email digest job (optional)
+        match (&config.digest_email_url, config.digest_poll_interval_secs) {
+            (Some(_), 0) => debug!("DIGEST_POLL_INTERVAL_SECS=0 - email digests disabled"),
+            (Some(url), poll_interval_secs) => {
+                let relay = EmailRelay::new(
+                    url.clone(),
+                    config.digest_email_token.clone(),
+                    config.digest_email_from.clone(),
+                );
+                let job = DigestJob::new(
+                    db.pool().clone(),
+                    relay,
+                    config.digest_hour,
+                    config.digest_max_items,
+                    config.delivery_window_timezone.clone(),
+                    Duration::from_secs(poll_interval_secs),
+                )
+                .with_costs(costs.clone());
+                tasks.push(tokio::spawn(async move { job.run().await }));
+            }
+            (None, _) => debug!("DIGEST_EMAIL_URL not configured - email digests disabled"),
+        }
+
+        // Start shadow queue (dual write to the queue backend being migrated to, GET /admin/shadow)
+        if let Some(queue) = &self.shadow_queue {
+            let runner = ShadowRunner::new(db.pool().clone(), queue.clone(), config);
+            tasks.push(tokio::spawn(runner.run()));
+        }
+
+        tasks
+    }
+
+    /// Queue consumers (Kafka, NATS, SQS) configured and compiled in
+    #[cfg_attr(not(feature = "nats"), allow(unused_variables))]
+    async fn start_ingestion(&self, events: &events::EventSender) -> Vec<JoinHandle<()>> {
+        let config = &self.config;
+        #[cfg_attr(not(any(feature = "kafka", feature = "nats", feature = "sqs")), allow(unused_mut))]
+        let mut ingestion = Vec::new();
+
+        #[cfg(feature = "kafka")]
+        if let Some(kafka_config) = &config.kafka {
+            match ingest::kafka::KafkaSource::new(kafka_config, self.db.pool().clone()) {
+                Ok(source) => {
+                    ingestion.push(tokio::spawn(async move { source.run().await }));
+                    info!(topic = %kafka_config.topic, "Kafka ingestion started");
+                }
+                Err(e) => error!(error = %e, "Failed to start Kafka source - ingestion disabled"),
+            }
+        }
+        #[cfg(not(feature = "kafka"))]
+        if config.kafka.is_some() {
+            warn!("KAFKA_BROKERS set but built without the 'kafka' feature - Kafka ingestion disabled");
+        }
+
+        #[cfg(feature = "nats")]
+        if let Some(nats_config) = &config.nats {
+            use ingest::nats::{publish_events, NatsSource};
+
+            match NatsSource::connect(nats_config, self.db.pool().clone()).await {
+                Ok(source) => {
+                    ingestion.push(tokio::spawn(publish_events(
+                        source.client(),
+                        nats_config.clone(),
+                        events.subscribe(),
+                    )));
+                    ingestion.push(tokio::spawn(async move {
+                        if let Err(e) = source.run().await {
+                            error!(error = %e, "NATS ingestion stopped");
+                        }
+                    }));
+                    info!(url = %nats_config.url, "NATS ingestion + delivery events started");
+                }
+                Err(e) => error!(error = %e, "Failed to connect to NATS - ingestion disabled"),
+            }
+        }
+        #[cfg(not(feature = "nats"))]
+        if config.nats.is_some() {
+            warn!("NATS_URL set but built without the 'nats' feature - NATS disabled");
+        }
+
+        #[cfg(feature = "sqs")]
+        if let Some(sqs_config) = &config.sqs {
+            let source = ingest::sqs::SqsSource::new(sqs_config, self.db.pool().clone()).await;
+            ingestion.push(tokio::spawn(async move { source.run().await }));
+            info!(queue = %sqs_config.queue_url, "SQS ingestion started");
+        }
+        #[cfg(not(feature = "sqs"))]
+        if config.sqs.is_some() {
+            warn!("SQS_QUEUE_URL set but built without the 'sqs' feature - SQS ingestion disabled");
+        }
+
+        ingestion
+    }
+}
+
+/// What `Service::run` stops on shutdown, in this order
+///
+/// Every phase shares one deadline (SHUTDOWN_TIMEOUT_SECS). A drain or HTTP close that
+/// runs into it is aborted, so the process exits before Kubernetes kills it.
+struct ShutdownSequence {
+    /// Queue consumers (Kafka, NATS, SQS): redelivered after an abort
+    ingestion: Vec<JoinHandle<()>>,
+    wake_source: JoinHandle<()>,
+    drain: Drain,
+    worker: JoinHandle<()>,
+    /// Saved once the worker stopped
+    costs: CostLedger,
+    /// Leader lease and its renewal task: released once the worker stopped
+    leader: Option<(Leadership, JoinHandle<()>)>,
+    /// Bus health probe and delivery-event publisher
+    bus: Vec<JoinHandle<()>>,
+    stop_http: oneshot::Sender<()>,
+    server: JoinHandle<std::io::Result<()>>,
+    /// Scheduler, campaigns, receipts, archiver, ...
+    background: Vec<JoinHandle<()>>,
+}
+
+impl ShutdownSequence {
+    async fn run(mut self, timeout: Duration) -> Result<(), String> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        info!(timeout_secs = timeout.as_secs(), "Shutting down");
+
+        info!("Shutdown 1/5: stopping ingestion");
+        self.ingestion.iter().for_each(JoinHandle::abort);
+        for task in self.ingestion {
+            let _ = task.await;
+        }
+
+        info!("Shutdown 2/5: stopping claims");
+        self.drain.start();
+        self.wake_source.abort();
+
+        info!(in_flight = self.drain.in_flight(), "Shutdown 3/5: draining the worker");
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        if self.drain.wait_idle(remaining).await {
+            info!("Worker drained");
+        } else {
+            warn!(in_flight = self.drain.in_flight(), "Shutdown timeout: aborting in-flight deliveries");
+        }
+        self.worker.abort();
+        self.costs.flush().await;
+        // Hand over now rather than make the standby wait for the lease to lapse
+        if let Some((leadership, renewal)) = self.leader.take() {
+            renewal.abort();
+            let _ = renewal.await;
+            leadership.release().await;
+        }
+
+        // The Bus connections are the clients', held by websocket-bus: only our own Bus tasks stop
+        info!("Shutdown 4/5: stopping Bus tasks");
+        self.bus.iter().for_each(JoinHandle::abort);
+
+        info!("Shutdown 5/5: stopping HTTP");
+        let _ = self.stop_http.send(());
+        let result = match tokio::time::timeout_at(deadline, &mut self.server).await {
+            Ok(Ok(Ok(()))) => {
+                info!("Server shutdown complete");
+                Ok(())
+            }
+            Ok(Ok(Err(e))) => Err(format!("Server failed: {}", e)),
+            Ok(Err(e)) => Err(format!("Server task failed: {}", e)),
+            Err(_) => {
+                warn!("Shutdown timeout: closing open HTTP connections");
+                self.server.abort();
+                Ok(())
+            }
+        };
+
+        self.background.iter().for_each(JoinHandle::abort);
+        result
+    }
+}
+
+/// FCM client from FCM_CREDENTIALS or GOOGLE_APPLICATION_CREDENTIALS (None = push disabled)
+fn fcm_from_config(config: &Config) -> Option<Arc<FcmClient>> {
+    debug!("Initializing FCM client...");
+    match (&config.fcm_credentials_path, &config.fcm_project_id) {
+        // FCM_CREDENTIALS (secret backend) takes precedence over the credentials file
+        (_, Some(project_id)) if config.fcm_credentials.is_some() => {
+            let credentials = config.fcm_credentials.as_deref().unwrap_or_default();
+            match FcmClient::from_json(credentials, project_id) {
+                Ok(client) => {
+                    info!(project_id = %project_id, "FCM client initialized from FCM_CREDENTIALS");
+                    Some(Arc::new(client.with_endpoints(&config.fcm_base_url, &config.fcm_token_url)))
+                }
+                Err(e) => {
+                    error!(error = %e, "Failed to initialize FCM client - push disabled");
+                    None
+                }
+            }
+        }
+        (Some(path), Some(project_id)) => {
+            trace!("FCM credentials path: {}", path);
+            trace!("FCM project ID: {}", project_id);
+            match FcmClient::new(path, project_id) {
+                Ok(client) => {
+                    info!(project_id = %project_id, "FCM client initialized");
+                    Some(Arc::new(client.with_endpoints(&config.fcm_base_url, &config.fcm_token_url)))
+                }
+                Err(e) => {
+                    error!(error = %e, path = %path, "Failed to initialize FCM client - push disabled");
+                    None
+                }
+            }
+        }
+        _ => {
+            warn!("FCM not configured - push notifications disabled");
+            debug!("  FCM_PROJECT_ID: {:?}", config.fcm_project_id);
+            debug!("  GOOGLE_APPLICATION_CREDENTIALS: {:?}", config.fcm_credentials_path);
+            None
+        }
+    }
+}
+
+/// Sandbox FCM client from FCM_SANDBOX_PROJECT_ID + FCM_SANDBOX_CREDENTIALS_PATH
+///
+/// None = sandbox devices go through the production project.
+fn fcm_sandbox_from_config(config: &Config) -> Option<Arc<FcmClient>> {
+    let (Some(project_id), Some(path)) = (&config.fcm_sandbox_project_id, &config.fcm_sandbox_credentials_path) else {
+        if config.fcm_sandbox_project_id.is_some() || config.fcm_sandbox_credentials_path.is_some() {
+            warn!("FCM_SANDBOX_PROJECT_ID and FCM_SANDBOX_CREDENTIALS_PATH must be set together - sandbox project disabled");
+        }
+        return None;
+    };
+    match FcmClient::new(path, project_id) {
+        Ok(client) => {
+            info!(project_id = %project_id, "Sandbox FCM client initialized");
+            Some(Arc::new(client.with_endpoints(&config.fcm_base_url, &config.fcm_token_url)))
+        }
+        Err(e) => {
+            error!(error = %e, path = %path, "Failed to initialize sandbox FCM client - sandbox devices use production");
+            None
+        }
+    }
+}
+
+/// BusClient for websocket-bus (None = real-time delivery disabled)
+fn bus_from_config(config: &Config) -> Option<Arc<dyn RealtimeBus>> {
+    debug!("Initializing WebSocket Bus client...");
+    match (&config.websocket_bus_url, &config.service_token) {
+        (Some(url), Some(token)) => {
+            let client = BusClient::new(url, token);
+            info!(bus_url = %url, "WebSocket Bus client initialized");
+            Some(Arc::new(client))
+        }
+        _ => {
+            warn!("WebSocket Bus not configured - real-time delivery disabled");
+            debug!("  WEBSOCKET_BUS_URL: {:?}", config.websocket_bus_url);
+            debug!("  SERVICE_TOKEN: {:?}", config.service_token.as_ref().map(|_| "[REDACTED]"));
+            None
+        }
+    }
+}
+
+async fn health_handler() -> &'static str {
+    "OK"
+}
+
+/// Not ready once a preStop drain started (liveness stays OK)
+///
+/// Reports the Bus but doesn't fail on it: push still delivers, and pulling every replica
+/// out of the Service would also stop the API and webhook ingestion.
+async fn ready_handler(
+    (drain, bus_configured, bus_health): (Drain, bool, Option<BusHealth>),
+) -> (StatusCode, Json<serde_json::Value>) {
+    let bus = match (&bus_health, bus_configured) {
+        (Some(health), _) if health.is_up() => "up",
+        (Some(_), _) => "down",
+        (None, true) => "not_probed",
+        (None, false) => "disabled",
+    };
+    if drain.is_draining() {
+        (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "status": "draining", "bus": bus })))
+    } else {
+        (StatusCode::OK, Json(json!({ "status": "ready", "bus": bus })))
+    }
+}
@@ -0,0 +1,80 @@
+//! Kafka as the shadow queue backend (feature `kafka`, SHADOW_KAFKA_BROKERS).
+//!
+//! Offsets are committed automatically: a copy lost between receive and the ledger write
+//! is what the comparison is there to report, not something to redeliver.
+
+use super::{ShadowError, ShadowQueue};
+use crate::config::ShadowKafkaConfig;
+use axum::async_trait;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::error::KafkaError;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use rdkafka::{ClientConfig, Message};
+use std::time::Duration;
+use tracing::debug;
+
+/// How long `receive` waits for a message before returning none
+const RECEIVE_WAIT: Duration = Duration::from_secs(1);
+
+pub struct KafkaShadowQueue {
+    consumer: StreamConsumer,
+    producer: FutureProducer,
+    topic: String,
+}
+
+impl KafkaShadowQueue {
+    pub fn new(config: &ShadowKafkaConfig) -> Result<Self, KafkaError> {
+        debug!(
+            brokers = %config.brokers,
+            topic = %config.topic,
+            group_id = %config.group_id,
+            "Creating KafkaShadowQueue"
+        );
+
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("group.id", &config.group_id)
+            .set("enable.auto.commit", "true")
+            .set("auto.offset.reset", "latest")
+            .create()?;
+        consumer.subscribe(&[config.topic.as_str()])?;
+
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("message.timeout.ms", "10000")
+            .create()?;
+
+        Ok(Self {
+            consumer,
+            producer,
+            topic: config.topic.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl ShadowQueue for KafkaShadowQueue {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    async fn publish(&self, key: &str, payload: &[u8]) -> Result<(), ShadowError> {
+        self.producer
+            .send(
+                FutureRecord::to(&self.topic).key(key).payload(payload),
+                Timeout::After(Duration::from_secs(10)),
+            )
+            .await
+            .map(|_| ())
+            .map_err(|(e, _)| ShadowError(e.to_string()))
+    }
+
+    async fn receive(&self) -> Result<Vec<Vec<u8>>, ShadowError> {
+        match tokio::time::timeout(RECEIVE_WAIT, self.consumer.recv()).await {
+            Err(_) => Ok(Vec::new()),
+            Ok(Ok(message)) => Ok(message.payload().map(|payload| vec![payload.to_vec()]).unwrap_or_default()),
+            Ok(Err(e)) => Err(ShadowError(e.to_string())),
+        }
+    }
+}
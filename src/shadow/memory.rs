@@ -0,0 +1,108 @@
+//! In-memory [`ShadowQueue`] for tests and local runs.
+//!
+//! Everything published is handed back to the reader, except what [`MemoryShadowQueue::lose`]
+//! drops. [`MemoryShadowQueue::inject`] hands back extra messages (duplicates, altered copies,
+//! strangers) and [`MemoryShadowQueue::fail_with`] makes publishes fail.
+
+use super::{ShadowError, ShadowQueue};
+use axum::async_trait;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// How long `receive` waits for a message before returning none
+const RECEIVE_WAIT: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+struct MemoryState {
+    /// Waiting for the reader
+    pending: Vec<Vec<u8>>,
+    published: Vec<Vec<u8>>,
+    /// Notification ids accepted but never handed back
+    lost: HashSet<Uuid>,
+    failure: Option<String>,
+}
+
+/// Clones share the messages
+#[derive(Clone, Default)]
+pub struct MemoryShadowQueue {
+    state: Arc<Mutex<MemoryState>>,
+    arrived: Arc<Notify>,
+}
+
+impl MemoryShadowQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept the notification's publishes but never hand them to the reader
+    pub fn lose(&self, notification_id: Uuid) {
+        self.state().lost.insert(notification_id);
+    }
+
+    /// Hand a message to the reader as if someone had published it
+    pub fn inject(&self, payload: Vec<u8>) {
+        self.state().pending.push(payload);
+        self.arrived.notify_one();
+    }
+
+    /// Fail every publish until `recover`
+    pub fn fail_with(&self, message: &str) {
+        self.state().failure = Some(message.to_string());
+    }
+
+    pub fn recover(&self) {
+        self.state().failure = None;
+    }
+
+    /// Every successful publish so far, in order (lost ones included)
+    pub fn published(&self) -> Vec<Vec<u8>> {
+        self.state().published.clone()
+    }
+
+    /// The published copy of one notification
+    pub fn published_copy(&self, notification_id: Uuid) -> Option<Vec<u8>> {
+        self.published().into_iter().find(|payload| notification_id_of(payload) == Some(notification_id))
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MemoryState> {
+        self.state.lock().expect("memory shadow queue lock poisoned")
+    }
+}
+
+fn notification_id_of(payload: &[u8]) -> Option<Uuid> {
+    let value: serde_json::Value = serde_json::from_slice(payload).ok()?;
+    value["id"].as_str()?.parse().ok()
+}
+
+#[async_trait]
+impl ShadowQueue for MemoryShadowQueue {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    async fn publish(&self, _key: &str, payload: &[u8]) -> Result<(), ShadowError> {
+        let mut state = self.state();
+        if let Some(message) = &state.failure {
+            return Err(ShadowError(message.clone()));
+        }
+        state.published.push(payload.to_vec());
+        let lost = notification_id_of(payload).is_some_and(|id| state.lost.contains(&id));
+        if !lost {
+            state.pending.push(payload.to_vec());
+            self.arrived.notify_one();
+        }
+        Ok(())
+    }
+
+    async fn receive(&self) -> Result<Vec<Vec<u8>>, ShadowError> {
+        let pending = std::mem::take(&mut self.state().pending);
+        if !pending.is_empty() {
+            return Ok(pending);
+        }
+        let _ = tokio::time::timeout(RECEIVE_WAIT, self.arrived.notified()).await;
+        Ok(std::mem::take(&mut self.state().pending))
+    }
+}
@@ -0,0 +1,228 @@
+//! Shadow queue: dual write to a candidate queue backend and compare it with Postgres.
+//!
+//! Meant for de-risking a move off the Postgres queue. While a [`ShadowQueue`] is configured
+//! (SHADOW_KAFKA_BROKERS, or one handed to `ServiceBuilder::shadow_queue`), a trigger keeps a
+//! ledger row per inserted notification. [`ShadowRunner`] publishes every ledgered notification
+//! to the backend (dual write), consumes the backend like a future worker would (shadow read)
+//! and, once SHADOW_GRACE_SECS have passed, compares what came back with the Postgres row.
+//! Divergences are counted in `notifications_shadow_compared_total{divergence}` and listed by
+//! `GET /admin/shadow`. Delivery never depends on the shadow side; Postgres stays the queue.
+
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod memory;
+
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaShadowQueue;
+pub use memory::MemoryShadowQueue;
+
+use crate::config::Config;
+use crate::db::ShadowQueries;
+use crate::models::Notification;
+use axum::async_trait;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, instrument, warn};
+
+/// Ledger rows published per claim
+const PUBLISH_BATCH: i64 = 100;
+/// A claimed row is retried after this when its publish failed (or the instance died)
+const PUBLISH_LEASE_SECS: i64 = 30;
+/// Pause when there was nothing (more) to publish
+const PUBLISH_IDLE: Duration = Duration::from_secs(1);
+/// Well within the 5 minutes after which the ledger trigger stops
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+/// Ledger rows compared per query
+const COMPARE_BATCH: i64 = 1000;
+/// Compared ledger rows are kept this long for `GET /admin/shadow`
+const KEEP_DAYS: i32 = 7;
+/// Max backoff while the database is unavailable
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// The candidate queue backend
+///
+/// Messages are the JSON of a [`Notification`], keyed by user so a partitioned backend keeps
+/// each user's notifications in order (like the Postgres claim does).
+#[async_trait]
+pub trait ShadowQueue: Send + Sync {
+    /// Recorded in the ledger state (`kafka`, `memory`, ...)
+    fn name(&self) -> &str;
+
+    async fn publish(&self, key: &str, payload: &[u8]) -> Result<(), ShadowError>;
+
+    /// Next messages from the backend; may return none after waiting a while
+    async fn receive(&self) -> Result<Vec<Vec<u8>>, ShadowError>;
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Shadow queue failed: {0}")]
+pub struct ShadowError(pub String);
+
+/// Dual write, shadow read and comparison (one task each)
+pub struct ShadowRunner {
+    pool: PgPool,
+    queue: Arc<dyn ShadowQueue>,
+    grace: Duration,
+    compare_interval: Duration,
+}
+
+impl ShadowRunner {
+    pub fn new(pool: PgPool, queue: Arc<dyn ShadowQueue>, config: &Config) -> Self {
+        Self {
+            pool,
+            queue,
+            grace: Duration::from_secs(config.shadow_grace_secs),
+            compare_interval: Duration::from_secs(config.shadow_compare_interval_secs.max(1)),
+        }
+    }
+
+    /// Run until the service stops
+    pub async fn run(self) {
+        info!(
+            backend = self.queue.name(),
+            grace_secs = self.grace.as_secs(),
+            "Shadow queue started, dual writing notifications"
+        );
+        tokio::join!(self.write(), self.read(), self.compare());
+    }
+
+    /// Dual write: publish ledgered notifications, keeping the ledger trigger alive
+    #[instrument(skip_all, name = "shadow_write")]
+    async fn write(&self) {
+        let mut last_heartbeat: Option<Instant> = None;
+        loop {
+            if last_heartbeat.is_none_or(|at| at.elapsed() >= HEARTBEAT_INTERVAL) {
+                match ShadowQueries::heartbeat(&self.pool, self.queue.name()).await {
+                    Ok(()) => last_heartbeat = Some(Instant::now()),
+                    Err(e) => warn!(error = %e, "Failed to record the shadow queue heartbeat"),
+                }
+            }
+
+            let claimed = match ShadowQueries::claim_unpublished(&self.pool, PUBLISH_BATCH, PUBLISH_LEASE_SECS).await {
+                Ok(claimed) => claimed,
+                Err(e) => {
+                    error!(error = %e, "Failed to claim notifications for the shadow queue");
+                    tokio::time::sleep(PUBLISH_IDLE).await;
+                    continue;
+                }
+            };
+            for notification in &claimed {
+                self.publish(notification).await;
+            }
+            if (claimed.len() as i64) < PUBLISH_BATCH {
+                tokio::time::sleep(PUBLISH_IDLE).await;
+            }
+        }
+    }
+
+    async fn publish(&self, notification: &Notification) {
+        let payload = match serde_json::to_vec(notification) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!(id = %notification.id, error = %e, "Failed to encode notification for the shadow queue");
+                return;
+            }
+        };
+        match self.queue.publish(&notification.user_id.to_string(), &payload).await {
+            Ok(()) => {
+                metrics::counter!("notifications_shadow_published_total").increment(1);
+                if let Err(e) = ShadowQueries::mark_published(&self.pool, notification.id, &notification.content_hash()).await {
+                    // Retried after the lease: shows up as a duplicate rather than a loss
+                    warn!(id = %notification.id, error = %e, "Failed to mark shadow publish");
+                }
+            }
+            Err(e) => {
+                debug!(id = %notification.id, error = %e, "Shadow publish failed, retrying after the lease");
+                metrics::counter!("notifications_shadow_publish_failures_total").increment(1);
+                if let Err(e) = ShadowQueries::mark_publish_failed(&self.pool, notification.id, &e.0).await {
+                    warn!(id = %notification.id, error = %e, "Failed to record shadow publish failure");
+                }
+            }
+        }
+    }
+
+    /// Shadow read: record every copy the backend hands back
+    #[instrument(skip_all, name = "shadow_read")]
+    async fn read(&self) {
+        loop {
+            match self.queue.receive().await {
+                Ok(messages) => {
+                    for message in messages {
+                        self.record(&message).await;
+                    }
+                }
+                Err(e) => {
+                    warn!(error = %e, "Shadow receive failed");
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+
+    async fn record(&self, message: &[u8]) {
+        let copy: Notification = match serde_json::from_slice(message) {
+            Ok(copy) => copy,
+            Err(e) => {
+                warn!(error = %e, "Undecodable message on the shadow queue");
+                metrics::counter!("notifications_shadow_undecodable_total").increment(1);
+                return;
+            }
+        };
+        metrics::counter!("notifications_shadow_received_total").increment(1);
+
+        let hash = copy.content_hash();
+        let mut backoff = Duration::from_millis(500);
+        loop {
+            match ShadowQueries::record_copy(&self.pool, copy.id, &copy.tenant_id, &hash).await {
+                Ok(recorded) => {
+                    if recorded.copies == 1 {
+                        metrics::histogram!("notifications_shadow_lag_seconds").record(recorded.lag_secs.max(0.0));
+                    }
+                    return;
+                }
+                Err(e) => {
+                    warn!(id = %copy.id, error = %e, backoff_ms = backoff.as_millis() as u64, "Failed to record shadow copy, retrying");
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+                }
+            }
+        }
+    }
+
+    /// Compare ledger rows past the grace period and prune old ones
+    #[instrument(skip_all, name = "shadow_compare")]
+    async fn compare(&self) {
+        let mut interval = tokio::time::interval(self.compare_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            loop {
+                let outcomes = match ShadowQueries::compare(&self.pool, self.grace.as_secs() as i64, COMPARE_BATCH).await {
+                    Ok(outcomes) => outcomes,
+                    Err(e) => {
+                        error!(error = %e, "Shadow comparison failed");
+                        break;
+                    }
+                };
+                let mut compared = 0;
+                for (divergence, count) in &outcomes {
+                    compared += count;
+                    metrics::counter!("notifications_shadow_compared_total", "divergence" => divergence.clone())
+                        .increment(*count as u64);
+                    if divergence != "none" {
+                        warn!(divergence = %divergence, count, "Shadow queue diverged from Postgres");
+                    }
+                }
+                if compared < COMPARE_BATCH {
+                    break;
+                }
+            }
+            match ShadowQueries::purge(&self.pool, KEEP_DAYS).await {
+                Ok(0) => {}
+                Ok(purged) => debug!(purged, "Pruned compared shadow ledger rows"),
+                Err(e) => warn!(error = %e, "Failed to prune the shadow ledger"),
+            }
+        }
+    }
+}
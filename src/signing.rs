@@ -0,0 +1,93 @@
+//! Ed25519 signatures for broadcast notifications.
+//!
+//! With BROADCAST_SIGNING_KEY set (base64 32-byte seed), every broadcast carries
+//! `signed_content` - the exact JSON string that was signed - and a detached
+//! `signature` over its UTF-8 bytes. Clients verify the signature with the public
+//! key from `GET /.well-known/broadcast-signing-keys` before parsing
+//! `signed_content`, so the signed fields never depend on JSON canonicalization.
+
+use crate::models::Notification;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::{Signer, SigningKey};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+pub const ALGORITHM: &str = "Ed25519";
+
+/// Detached signature attached to a broadcast
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastSignature {
+    pub alg: &'static str,
+    pub key_id: String,
+    /// JSON string that was signed
+    pub signed_content: String,
+    /// Base64 Ed25519 signature over `signed_content`
+    pub signature: String,
+}
+
+/// Public key as published for clients
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicKey {
+    pub alg: &'static str,
+    pub key_id: String,
+    /// Base64 raw 32-byte Ed25519 public key
+    pub public_key: String,
+}
+
+pub struct BroadcastSigner {
+    key: SigningKey,
+    key_id: String,
+}
+
+impl BroadcastSigner {
+    /// Signer from a base64-encoded 32-byte seed (e.g. `openssl rand -base64 32`)
+    pub fn from_base64_seed(seed: &str) -> Result<Self, String> {
+        let bytes = BASE64
+            .decode(seed.trim())
+            .map_err(|e| format!("BROADCAST_SIGNING_KEY is not valid base64: {}", e))?;
+        let seed: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| "BROADCAST_SIGNING_KEY must decode to 32 bytes".to_string())?;
+
+        let key = SigningKey::from_bytes(&seed);
+        // Stable id so clients can pick the right key after a rotation
+        let digest = Sha256::digest(key.verifying_key().as_bytes());
+        let key_id = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+
+        Ok(Self { key, key_id })
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            alg: ALGORITHM,
+            key_id: self.key_id.clone(),
+            public_key: BASE64.encode(self.key.verifying_key().as_bytes()),
+        }
+    }
+
+    /// Sign the client-visible content of a (rendered) broadcast
+    pub fn sign(&self, notification: &Notification) -> BroadcastSignature {
+        let content = serde_json::json!({
+            "id": notification.id,
+            "tenant_id": notification.tenant_id,
+            "notification_type": notification.notification_type,
+            "title": notification.title,
+            "message": notification.message,
+            "payload": notification.payload,
+            "deep_link": notification.deep_link,
+            "priority": notification.priority,
+            "created_at": notification.created_at,
+        })
+        .to_string();
+
+        let signature = self.key.sign(content.as_bytes());
+
+        BroadcastSignature {
+            alg: ALGORITHM,
+            key_id: self.key_id.clone(),
+            signature: BASE64.encode(signature.to_bytes()),
+            signed_content: content,
+        }
+    }
+}
@@ -0,0 +1,379 @@
+//! Escalation to operators when a notification type breaches its delivery SLO.
+//!
+//! Every SLO_ALERT_INTERVAL_SECS the [`SloAlertJob`] computes what `GET /admin/slo` shows over
+//! the last SLO_ALERT_WINDOW_SECS and checks each type against SLO_ALERT_RULES (minimum
+//! success rate, maximum p95 latency). A breach opens an incident (`activity.slo_incidents`)
+//! and sends the operators a high-priority notification through the service's own pipeline
+//! (to SLO_ALERT_USER_ID or the subscribers of SLO_ALERT_TOPIC). The type being healthy again
+//! resolves it with a second notification. Types with fewer than SLO_ALERT_MIN_VOLUME finished
+//! notifications in the window get no verdict either way. Every replica may run the job: one
+//! open incident per type and breach is enforced by the table, so one alert goes out.
+
+use crate::config::Config;
+use crate::db::slo::{SloIncident, TypeSlo};
+use crate::db::SloQueries;
+use crate::ingest;
+use crate::models::{validate_topic, NewNotification};
+use chrono::{Duration as ChronoDuration, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument, warn};
+use uuid::Uuid;
+
+/// Type of the alerts (never evaluated itself, so a failing alert can't alert about itself)
+pub const ALERT_TYPE: &str = "slo_breach";
+/// `created_by` of the alerts
+const CREATED_BY: &str = "slo-alerts";
+
+/// Thresholds of one notification type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SloRule {
+    /// Delivered / finished below this breaches (None = not checked)
+    pub min_success_rate: Option<f64>,
+    /// p95 latency (due → delivered) above this breaches (None = not checked)
+    pub max_p95_secs: Option<f64>,
+}
+
+/// SLO_ALERT_RULES: `type=min_success_rate:max_p95_secs`, comma separated, `*` for every other
+/// type and `-` for a threshold that isn't checked, e.g. `*=0.95:60,chat_message=0.99:5`
+#[derive(Debug, Clone)]
+pub struct SloRules {
+    default: SloRule,
+    types: HashMap<String, SloRule>,
+}
+
+impl Default for SloRules {
+    /// 95% delivered, latency not checked
+    fn default() -> Self {
+        Self { default: SloRule { min_success_rate: Some(0.95), max_p95_secs: None }, types: HashMap::new() }
+    }
+}
+
+impl SloRules {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut rules = Self::default();
+        let mut seen_default = false;
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let invalid = || format!("Invalid SLO_ALERT_RULES entry '{}', expected e.g. 'chat_message=0.99:5'", entry);
+            let (notification_type, thresholds) = entry.split_once('=').ok_or_else(invalid)?;
+            let (rate, p95) = thresholds.split_once(':').ok_or_else(invalid)?;
+            let threshold = |value: &str| -> Result<Option<f64>, String> {
+                match value.trim() {
+                    "-" => Ok(None),
+                    value => value.parse::<f64>().map(Some).map_err(|_| invalid()),
+                }
+            };
+            let rule = SloRule { min_success_rate: threshold(rate)?, max_p95_secs: threshold(p95)? };
+            if rule.min_success_rate.is_some_and(|rate| !(rate > 0.0 && rate <= 1.0)) {
+                return Err(format!("SLO_ALERT_RULES '{}': the success rate must be in (0, 1]", entry));
+            }
+            if rule.max_p95_secs.is_some_and(|secs| !(secs.is_finite() && secs > 0.0)) {
+                return Err(format!("SLO_ALERT_RULES '{}': the p95 latency must be positive", entry));
+            }
+
+            match notification_type.trim() {
+                "*" if seen_default => return Err("'*' appears twice in SLO_ALERT_RULES".to_string()),
+                "*" => {
+                    seen_default = true;
+                    rules.default = rule;
+                }
+                "" => return Err(invalid()),
+                notification_type => {
+                    if rules.types.insert(notification_type.to_string(), rule).is_some() {
+                        return Err(format!("Type '{}' appears twice in SLO_ALERT_RULES", notification_type));
+                    }
+                }
+            }
+        }
+        Ok(rules)
+    }
+
+    pub fn for_type(&self, notification_type: &str) -> SloRule {
+        self.types.get(notification_type).copied().unwrap_or(self.default)
+    }
+}
+
+/// Which threshold an incident is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Breach {
+    SuccessRate,
+    P95Latency,
+}
+
+impl Breach {
+    fn as_str(self) -> &'static str {
+        match self {
+            Breach::SuccessRate => "success_rate",
+            Breach::P95Latency => "p95_latency",
+        }
+    }
+
+    /// `82.0%` / `93.4s`
+    fn format(self, value: f64) -> String {
+        match self {
+            Breach::SuccessRate => format!("{:.1}%", value * 100.0),
+            Breach::P95Latency => format!("{:.1}s", value),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Breach::SuccessRate => "success rate",
+            Breach::P95Latency => "p95 latency",
+        }
+    }
+}
+
+/// One threshold checked against a type's window
+struct Check {
+    breach: Breach,
+    observed: f64,
+    threshold: f64,
+    breached: bool,
+}
+
+impl SloRule {
+    /// The configured thresholds against what the window shows (p95 only once something was delivered)
+    fn check(&self, slo: &TypeSlo) -> Vec<Check> {
+        let mut checks = Vec::new();
+        if let Some(threshold) = self.min_success_rate {
+            let observed = slo.success_rate();
+            checks.push(Check { breach: Breach::SuccessRate, observed, threshold, breached: observed < threshold });
+        }
+        if let (Some(threshold), Some(observed)) = (self.max_p95_secs, slo.p95_latency_secs) {
+            checks.push(Check { breach: Breach::P95Latency, observed, threshold, breached: observed > threshold });
+        }
+        checks
+    }
+}
+
+pub struct SloAlertJob {
+    pool: PgPool,
+    rules: SloRules,
+    /// Operator the alerts go to (or None with a topic)
+    user_id: Option<Uuid>,
+    topic: Option<String>,
+    window: ChronoDuration,
+    min_volume: i64,
+    interval: Duration,
+}
+
+impl SloAlertJob {
+    /// The job for SLO_ALERT_* (None when SLO_ALERT_INTERVAL_SECS=0)
+    pub fn from_config(pool: PgPool, config: &Config) -> Result<Option<Self>, String> {
+        if config.slo_alert_interval_secs == 0 {
+            return Ok(None);
+        }
+        let rules = match &config.slo_alert_rules {
+            Some(value) => SloRules::parse(value)?,
+            None => SloRules::default(),
+        };
+        let user_id = config
+            .slo_alert_user_id
+            .as_deref()
+            .map(|id| id.parse::<Uuid>().map_err(|_| format!("Invalid SLO_ALERT_USER_ID '{}'", id)))
+            .transpose()?;
+        if let Some(topic) = &config.slo_alert_topic {
+            validate_topic(topic).map_err(|e| format!("Invalid SLO_ALERT_TOPIC: {}", e))?;
+        }
+        match (user_id, &config.slo_alert_topic) {
+            (None, None) => return Err("SLO_ALERT_INTERVAL_SECS needs SLO_ALERT_USER_ID or SLO_ALERT_TOPIC".to_string()),
+            (Some(_), Some(_)) => return Err("Set SLO_ALERT_USER_ID or SLO_ALERT_TOPIC, not both".to_string()),
+            _ => {}
+        }
+        if config.slo_alert_window_secs <= 0 {
+            return Err("SLO_ALERT_WINDOW_SECS must be positive".to_string());
+        }
+
+        Ok(Some(Self {
+            pool,
+            rules,
+            user_id,
+            topic: config.slo_alert_topic.clone(),
+            window: ChronoDuration::seconds(config.slo_alert_window_secs),
+            min_volume: config.slo_alert_min_volume.max(1),
+            interval: Duration::from_secs(config.slo_alert_interval_secs),
+        }))
+    }
+
+    /// Job loop: evaluate, then sleep
+    #[instrument(skip(self), name = "slo_alerts")]
+    pub async fn run(&self) {
+        info!(
+            interval_secs = self.interval.as_secs(),
+            window_secs = self.window.num_seconds(),
+            min_volume = self.min_volume,
+            user_id = ?self.user_id,
+            topic = ?self.topic,
+            "SLO alerting started"
+        );
+
+        loop {
+            if let Err(e) = self.evaluate().await {
+                error!(error = %e, "Failed to evaluate delivery SLOs");
+                metrics::counter!("notifications_slo_evaluation_errors_total").increment(1);
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    /// Check every type with enough volume, opening and resolving incidents (one run of the loop)
+    pub async fn evaluate(&self) -> Result<(), sqlx::Error> {
+        let since = Utc::now() - self.window;
+        for slo in SloQueries::per_type(&self.pool, since).await? {
+            if slo.notification_type == ALERT_TYPE || slo.delivered + slo.failed < self.min_volume {
+                continue;
+            }
+            for check in self.rules.for_type(&slo.notification_type).check(&slo) {
+                if check.breached {
+                    self.open(&slo, &check).await?;
+                } else {
+                    self.resolve(&slo, &check).await?;
+                }
+            }
+        }
+
+        let open = SloQueries::open_incidents(&self.pool).await?;
+        metrics::gauge!("notifications_slo_incidents_open").set(open.len() as f64);
+        debug!(open_incidents = open.len(), "SLOs evaluated");
+        Ok(())
+    }
+
+    async fn open(&self, slo: &TypeSlo, check: &Check) -> Result<(), sqlx::Error> {
+        let notification_id = Uuid::now_v7();
+        let breach = check.breach.as_str();
+        let opened = SloQueries::open_incident(
+            &self.pool,
+            &slo.notification_type,
+            breach,
+            check.observed,
+            check.threshold,
+            notification_id,
+        )
+        .await?;
+        // Already open: the operators know
+        let Some(incident_id) = opened else {
+            return Ok(());
+        };
+
+        let title = format!(
+            "SLO breach: {} {} {} (SLO {})",
+            slo.notification_type,
+            check.breach.label(),
+            check.breach.format(check.observed),
+            check.breach.format(check.threshold)
+        );
+        let alert = self.alert(notification_id, "high", title, slo, check, incident_id);
+        match ingest::ingest(&self.pool, &alert).await {
+            Ok(_) => {
+                warn!(
+                    incident_id = incident_id,
+                    notification_type = %slo.notification_type,
+                    breach = breach,
+                    observed = check.observed,
+                    threshold = check.threshold,
+                    "🚨 Delivery SLO breached, operators alerted"
+                );
+                metrics::counter!("notifications_slo_alerts_total", "breach" => breach).increment(1);
+            }
+            Err(e) => {
+                error!(incident_id = incident_id, error = %e, "Failed to create the SLO alert, retrying next run");
+                SloQueries::discard_incident(&self.pool, incident_id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn resolve(&self, slo: &TypeSlo, check: &Check) -> Result<(), sqlx::Error> {
+        let Some(incident) = SloQueries::resolve_incident(&self.pool, &slo.notification_type, check.breach.as_str()).await?
+        else {
+            return Ok(());
+        };
+
+        let title = format!(
+            "SLO recovered: {} {} {} (SLO {})",
+            slo.notification_type,
+            check.breach.label(),
+            check.breach.format(check.observed),
+            check.breach.format(check.threshold)
+        );
+        let alert = self.alert(Uuid::now_v7(), "normal", title, slo, check, incident.id);
+        if let Err(e) = ingest::ingest(&self.pool, &alert).await {
+            error!(incident_id = incident.id, error = %e, "Failed to create the SLO recovery notice");
+        }
+        info!(
+            incident_id = incident.id,
+            notification_type = %incident.notification_type,
+            breach = %incident.breach,
+            open_secs = duration_secs(&incident),
+            "✓ Delivery SLO recovered"
+        );
+        Ok(())
+    }
+
+    /// The notification for the operators (grouped per type, so a recovery replaces the breach)
+    fn alert(
+        &self,
+        id: Uuid,
+        priority: &str,
+        title: String,
+        slo: &TypeSlo,
+        check: &Check,
+        incident_id: i64,
+    ) -> NewNotification {
+        let message = format!(
+            "Last {} min: {} delivered, {} failed",
+            self.window.num_minutes(),
+            slo.delivered,
+            slo.failed
+        );
+        NewNotification {
+            id: Some(id),
+            user_id: self.user_id,
+            actor_user_id: None,
+            notification_type: ALERT_TYPE.to_string(),
+            target_type: None,
+            target_id: None,
+            title,
+            message: Some(message),
+            payload: Some(serde_json::json!({
+                "incident_id": incident_id,
+                "notification_type": slo.notification_type,
+                "breach": check.breach.as_str(),
+                "observed": check.observed,
+                "threshold": check.threshold,
+                "delivered": slo.delivered,
+                "failed": slo.failed,
+                "window_secs": self.window.num_seconds(),
+            })),
+            deep_link: None,
+            priority: Some(priority.to_string()),
+            group_key: Some(format!("slo:{}:{}", slo.notification_type, check.breach.as_str())),
+            message_key: None,
+            message_args: None,
+            template_key: None,
+            actions: None,
+            attachments: None,
+            deliver_at: None,
+            deliver_at_local: None,
+            event_source: None,
+            event_time: None,
+            callback_url: None,
+            tenant_id: None,
+            topic: self.topic.clone(),
+            audience: None,
+            allow_duplicate: false,
+            pinned_until: None,
+            expires_at: None,
+            deadline: None,
+            region: None,
+            created_by: Some(CREATED_BY.to_string()),
+        }
+    }
+}
+
+fn duration_secs(incident: &SloIncident) -> i64 {
+    let resolved_at = incident.resolved_at.unwrap_or_else(Utc::now);
+    (resolved_at - incident.opened_at).num_seconds()
+}
@@ -0,0 +1,155 @@
+//! What this instance runs with: logged once as the `service_started` event and served at
+//! `GET /admin/status`, so deployment tooling can assert on the running configuration
+//! instead of scraping log banners.
+
+use crate::config::Config;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tracing::info;
+
+/// Locked versions of the main dependencies, `name=version,...` (see build.rs)
+const DEPENDENCY_VERSIONS: &str = env!("DEPENDENCY_VERSIONS");
+
+/// Cargo features and the optional crate each one pulls in
+const FEATURES: &[(&str, bool, Option<&str>)] = &[
+    ("kafka", cfg!(feature = "kafka"), Some("rdkafka")),
+    ("nats", cfg!(feature = "nats"), Some("async-nats")),
+    ("sqs", cfg!(feature = "sqs"), None),
+    ("secretsmanager", cfg!(feature = "secretsmanager"), None),
+    ("s3", cfg!(feature = "s3"), None),
+    ("redis", cfg!(feature = "redis"), Some("redis")),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceStatus {
+    pub service: &'static str,
+    pub version: &'static str,
+    /// HOSTNAME (the leader lease holder name adds a uuid to it)
+    pub instance: String,
+    /// REGION (null = single-region deployment)
+    pub region: Option<String>,
+    pub started_at: DateTime<Utc>,
+    /// `live` or `simulate` (DELIVERY_MODE)
+    pub delivery_mode: &'static str,
+    /// READ_ONLY: serves reads only, nothing is delivered
+    pub read_only: bool,
+    pub channels: Channels,
+    pub endpoints: Endpoints,
+    pub sources: Sources,
+    /// Cargo features this binary was built with
+    pub features: Vec<&'static str>,
+    pub dependencies: Dependencies,
+    /// Effective configuration by env var, secrets redacted (`Config::summary`)
+    pub config: Value,
+}
+
+/// Delivery channels this instance can use
+#[derive(Debug, Clone, Serialize)]
+pub struct Channels {
+    pub bus: bool,
+    pub fcm: bool,
+    /// Separate project for sandbox devices (otherwise they use `fcm`)
+    pub fcm_sandbox: bool,
+    pub email_digest: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Endpoints {
+    /// HOST:PORT of health, metrics, webhooks and the API
+    pub http: String,
+    pub user_api: bool,
+    pub management_api: bool,
+    pub grpc_port: Option<u16>,
+    pub mtls_port: Option<u16>,
+}
+
+/// Ingestion besides the HTTP API and webhooks (configured and compiled in)
+#[derive(Debug, Clone, Serialize)]
+pub struct Sources {
+    pub kafka: bool,
+    pub nats: bool,
+    pub sqs: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Dependencies {
+    /// `server_version` of the database at startup (null = the query failed)
+    pub postgres: Option<String>,
+    /// Crate versions from Cargo.lock; a crate locked in two versions lists both
+    pub crates: BTreeMap<&'static str, String>,
+}
+
+impl ServiceStatus {
+    pub fn new(config: &Config, channels: Channels, postgres: Option<String>) -> Self {
+        let admin = config.admin_token.is_some();
+        Self {
+            service: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            instance: config.instance_name.clone(),
+            region: config.region.clone(),
+            started_at: Utc::now(),
+            delivery_mode: config.delivery_mode.as_str(),
+            read_only: config.read_only,
+            channels,
+            endpoints: Endpoints {
+                http: config.server_addr(),
+                user_api: config.jwt_secret.is_some(),
+                management_api: admin,
+                grpc_port: config.grpc_port.filter(|_| admin && !config.read_only),
+                mtls_port: config.mtls.as_ref().map(|mtls| mtls.port),
+            },
+            sources: Sources {
+                kafka: cfg!(feature = "kafka") && config.kafka.is_some() && !config.read_only,
+                nats: cfg!(feature = "nats") && config.nats.is_some() && !config.read_only,
+                sqs: cfg!(feature = "sqs") && config.sqs.is_some() && !config.read_only,
+            },
+            features: FEATURES.iter().filter(|(_, on, _)| *on).map(|(name, _, _)| *name).collect(),
+            dependencies: Dependencies { postgres, crates: locked_crates() },
+            config: config.summary(),
+        }
+    }
+
+    /// The one startup event: the headline fields, plus the whole status as JSON
+    pub fn log(&self) {
+        info!(
+            event = "service_started",
+            version = self.version,
+            instance = %self.instance,
+            region = self.region.as_deref().unwrap_or("-"),
+            http = %self.endpoints.http,
+            delivery_mode = self.delivery_mode,
+            read_only = self.read_only,
+            bus = self.channels.bus,
+            fcm = self.channels.fcm,
+            user_api = self.endpoints.user_api,
+            management_api = self.endpoints.management_api,
+            status = %serde_json::to_string(self).unwrap_or_default(),
+            "Service started"
+        );
+    }
+}
+
+/// DEPENDENCY_VERSIONS without the optional crates of features that are off
+fn locked_crates() -> BTreeMap<&'static str, String> {
+    let disabled: Vec<&str> = FEATURES
+        .iter()
+        .filter(|(_, on, _)| !on)
+        .filter_map(|(_, _, dependency)| *dependency)
+        .collect();
+    let mut crates: BTreeMap<&'static str, String> = BTreeMap::new();
+    for (name, version) in DEPENDENCY_VERSIONS.split(',').filter_map(|entry| entry.split_once('=')) {
+        if disabled.contains(&name) {
+            continue;
+        }
+        crates
+            .entry(name)
+            .and_modify(|versions| {
+                versions.push_str(", ");
+                versions.push_str(version);
+            })
+            .or_insert_with(|| version.to_string());
+    }
+    crates
+}
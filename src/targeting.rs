@@ -0,0 +1,231 @@
+//! Device targeting: campaign filters such as `app_version < 3.2` evaluated per device.
+//!
+//! A filter is a list of conditions `<field> <op> <value>` that must all hold. Fields are
+//! `app_version` and `os_version` (compared as dotted numbers: `3.10 > 3.9`, suffixes like
+//! `-beta` ignored), `device_type` and `locale` (`=` / `!=` only, case-insensitive; `nl`
+//! matches `nl-NL`). A device without the field never matches. The worker checks every
+//! device in Rust ([`DeviceFilter::matches`]); a `devices` campaign audience resolves the
+//! same conditions in SQL ([`DeviceFilter::sql`]), so both sides must agree.
+
+use crate::db::queries::UserDevice;
+
+/// Conditions accepted per filter
+pub const MAX_CONDITIONS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    AppVersion,
+    OsVersion,
+    DeviceType,
+    Locale,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "app_version" => Some(Field::AppVersion),
+            "os_version" => Some(Field::OsVersion),
+            "device_type" => Some(Field::DeviceType),
+            "locale" => Some(Field::Locale),
+            _ => None,
+        }
+    }
+
+    fn is_version(self) -> bool {
+        matches!(self, Field::AppVersion | Field::OsVersion)
+    }
+
+    fn column(self) -> &'static str {
+        match self {
+            Field::AppVersion => "app_version",
+            Field::OsVersion => "os_version",
+            Field::DeviceType => "device_type",
+            Field::Locale => "locale",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    /// Longest first, so `<=` isn't read as `<`
+    const ALL: [(&'static str, Op); 6] = [
+        ("<=", Op::Le),
+        (">=", Op::Ge),
+        ("!=", Op::Ne),
+        ("<", Op::Lt),
+        (">", Op::Gt),
+        ("=", Op::Eq),
+    ];
+
+    fn sql(self) -> &'static str {
+        match self {
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Eq => "=",
+            Op::Ne => "<>",
+        }
+    }
+
+    fn holds<T: Ord>(self, left: T, right: T) -> bool {
+        match self {
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+            Op::Eq => left == right,
+            Op::Ne => left != right,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    field: Field,
+    op: Op,
+    value: String,
+}
+
+/// Parsed `device_filter` of a campaign or notification
+#[derive(Debug, Clone)]
+pub struct DeviceFilter {
+    conditions: Vec<Condition>,
+}
+
+/// A bound parameter of [`DeviceFilter::sql`]
+#[derive(Debug, Clone)]
+pub enum FilterParam {
+    Version(Vec<i64>),
+    Text(String),
+}
+
+impl DeviceFilter {
+    /// Parse conditions like `app_version < 3.2` (all must hold)
+    pub fn parse(conditions: &[String]) -> Result<Self, String> {
+        if conditions.is_empty() {
+            return Err("device_filter needs at least one condition".to_string());
+        }
+        if conditions.len() > MAX_CONDITIONS {
+            return Err(format!("device_filter is limited to {} conditions", MAX_CONDITIONS));
+        }
+        let conditions = conditions.iter().map(|c| parse_condition(c)).collect::<Result<_, _>>()?;
+        Ok(Self { conditions })
+    }
+
+    /// Whether a device satisfies every condition
+    pub fn matches(&self, device: &UserDevice) -> bool {
+        self.conditions.iter().all(|condition| {
+            let value = match condition.field {
+                Field::AppVersion => device.app_version.as_deref(),
+                Field::OsVersion => device.os_version.as_deref(),
+                Field::DeviceType => Some(device.device_type.as_str()),
+                Field::Locale => device.locale.as_deref(),
+            };
+            let Some(value) = value else {
+                return false;
+            };
+            if condition.field.is_version() {
+                return condition.op.holds(version(value), version(&condition.value));
+            }
+            let (value, wanted) = (value.to_lowercase(), condition.value.to_lowercase());
+            let equal = value == wanted
+                || (condition.field == Field::Locale && value.starts_with(&format!("{}-", wanted)));
+            if condition.op == Op::Eq {
+                equal
+            } else {
+                !equal
+            }
+        })
+    }
+
+    /// The conditions as a SQL predicate over `activity.user_devices` aliased `alias`
+    ///
+    /// Placeholders start at `$first_param`; bind the returned parameters in order.
+    pub fn sql(&self, alias: &str, first_param: usize) -> (String, Vec<FilterParam>) {
+        let mut params = Vec::with_capacity(self.conditions.len());
+        let predicates: Vec<String> = self
+            .conditions
+            .iter()
+            .enumerate()
+            .map(|(i, condition)| {
+                let param = first_param + i;
+                let column = format!("{}.{}", alias, condition.field.column());
+                if condition.field.is_version() {
+                    params.push(FilterParam::Version(version(&condition.value)));
+                    // Same as `version`: leading digits and dots, empty parts dropped
+                    return format!(
+                        "array_remove(string_to_array(substring(btrim({}) from '^[0-9.]*'), '.'), '')::bigint[] {} ${}::bigint[]",
+                        column,
+                        condition.op.sql(),
+                        param
+                    );
+                }
+                params.push(FilterParam::Text(condition.value.to_lowercase()));
+                let equal = if condition.field == Field::Locale {
+                    format!("(lower({0}) = ${1} OR lower({0}) LIKE ${1} || '-%')", column, param)
+                } else {
+                    format!("lower({}) = ${}", column, param)
+                };
+                if condition.op == Op::Eq {
+                    equal
+                } else {
+                    format!("({} IS NOT NULL AND NOT {})", column, equal)
+                }
+            })
+            .collect();
+        (predicates.join(" AND "), params)
+    }
+}
+
+fn parse_condition(condition: &str) -> Result<Condition, String> {
+    let invalid = || format!("Invalid device_filter condition '{}', expected e.g. 'app_version < 3.2'", condition);
+    let at = condition.find(['<', '>', '=', '!']).ok_or_else(invalid)?;
+    let (name, rest) = condition.split_at(at);
+    let (symbol, op) = Op::ALL
+        .iter()
+        .find(|(symbol, _)| rest.starts_with(symbol))
+        .copied()
+        .ok_or_else(invalid)?;
+    let value = rest[symbol.len()..].trim();
+    let field = Field::parse(name.trim()).ok_or_else(|| {
+        format!(
+            "Unknown device_filter field '{}' (app_version, os_version, device_type, locale)",
+            name.trim()
+        )
+    })?;
+    if value.is_empty() {
+        return Err(invalid());
+    }
+    if field.is_version() && version(value).is_empty() {
+        return Err(format!("'{}' is not a version", value));
+    }
+    if !field.is_version() {
+        if !matches!(op, Op::Eq | Op::Ne) {
+            return Err(format!("{} only supports = and !=", field.column()));
+        }
+        if !value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(format!("Invalid {} '{}'", field.column(), value));
+        }
+    }
+    Ok(Condition { field, op, value: value.to_string() })
+}
+
+/// `3.10.1-beta` → [3, 10, 1]: compared element-wise, a missing part sorts first
+fn version(value: &str) -> Vec<i64> {
+    let numeric = value.trim().split(|c: char| !c.is_ascii_digit() && c != '.').next().unwrap_or("");
+    numeric
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
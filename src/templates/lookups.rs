@@ -0,0 +1,183 @@
+//! `actor.*` and `target.*` template variables, looked up by id.
+//!
+//! Producers send `actor_user_id` / `target_type` + `target_id` and templates say
+//! `{{ actor.display_name }} commented on {{ target.title }}`. The lookups are operator SQL
+//! (TEMPLATE_ACTOR_QUERY with `$1` = actor id, `$2` = tenant; TEMPLATE_TARGET_QUERY with
+//! `$1` = target type, `$2` = target id, `$3` = tenant). The first row's columns become the
+//! variables. They run read-only with a statement timeout, and only scalar columns are kept:
+//! strings are capped and stripped of control characters, because they end up in push text.
+//! Results, including "no row", are cached per id. A failed or slow lookup leaves the
+//! variables out, so templates should use `default(...)` for them.
+
+use crate::config::Config;
+use crate::models::Notification;
+use moka::future::Cache;
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, trace, warn};
+use uuid::Uuid;
+
+/// Longest string value handed to templates (characters)
+const MAX_VALUE_CHARS: usize = 200;
+/// Cached lookups (actors and targets together)
+const CACHE_CAPACITY: u64 = 10_000;
+
+type Variables = Option<Arc<Map<String, Value>>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum LookupKey {
+    Actor { tenant_id: String, actor_id: Uuid },
+    Target { tenant_id: String, target_type: String, target_id: Uuid },
+}
+
+#[derive(Clone)]
+pub struct TemplateLookups {
+    pool: PgPool,
+    actor_query: Option<Arc<str>>,
+    target_query: Option<Arc<str>>,
+    timeout: Duration,
+    cache: Cache<LookupKey, Variables>,
+}
+
+impl TemplateLookups {
+    /// Lookups for TEMPLATE_ACTOR_QUERY / TEMPLATE_TARGET_QUERY - None when neither is set
+    pub fn from_config(pool: PgPool, config: &Config) -> Option<Self> {
+        let query = |sql: &Option<String>| -> Option<Arc<str>> {
+            sql.as_deref()
+                .map(|sql| sql.trim().trim_end_matches(';'))
+                .filter(|sql| !sql.is_empty())
+                .map(Arc::from)
+        };
+        let (actor_query, target_query) = (query(&config.template_actor_query), query(&config.template_target_query));
+        if actor_query.is_none() && target_query.is_none() {
+            return None;
+        }
+        Some(Self {
+            pool,
+            actor_query,
+            target_query,
+            timeout: Duration::from_millis(config.template_lookup_timeout_ms.max(1)),
+            cache: Cache::builder()
+                .max_capacity(CACHE_CAPACITY)
+                .time_to_live(Duration::from_secs(config.template_lookup_cache_ttl_secs.max(1)))
+                .build(),
+        })
+    }
+
+    /// Run both queries once with ids that match nothing (startup check)
+    pub async fn validate(&self) -> Result<(), String> {
+        if let Some(sql) = &self.actor_query {
+            self.fetch(sql, &actor_params(Uuid::nil(), ""))
+                .await
+                .map_err(|e| format!("Invalid TEMPLATE_ACTOR_QUERY: {}", e))?;
+        }
+        if let Some(sql) = &self.target_query {
+            self.fetch(sql, &target_params("", Uuid::nil(), ""))
+                .await
+                .map_err(|e| format!("Invalid TEMPLATE_TARGET_QUERY: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Add `actor` and `target` to a template context (as far as they resolve)
+    pub async fn extend(&self, notification: &Notification, variables: &mut Map<String, Value>) {
+        let tenant_id = notification.tenant_id.as_str();
+        if let (Some(sql), Some(actor_id)) = (&self.actor_query, notification.actor_user_id) {
+            let key = LookupKey::Actor { tenant_id: tenant_id.to_string(), actor_id };
+            if let Some(actor) = self.lookup(key, sql, actor_params(actor_id, tenant_id)).await {
+                variables.insert("actor".to_string(), Value::Object((*actor).clone()));
+            }
+        }
+        if let (Some(sql), Some(target_type), Some(target_id)) =
+            (&self.target_query, notification.target_type.as_deref(), notification.target_id)
+        {
+            let key = LookupKey::Target { tenant_id: tenant_id.to_string(), target_type: target_type.to_string(), target_id };
+            if let Some(target) = self.lookup(key, sql, target_params(target_type, target_id, tenant_id)).await {
+                variables.insert("target".to_string(), Value::Object((*target).clone()));
+            }
+        }
+    }
+
+    /// Cached row, else the query (errors and timeouts are not cached)
+    async fn lookup(&self, key: LookupKey, sql: &str, params: Vec<Param>) -> Variables {
+        if let Some(cached) = self.cache.get(&key).await {
+            trace!(key = ?key, found = cached.is_some(), "Template lookup cache hit");
+            return cached;
+        }
+        match self.fetch(sql, &params).await {
+            Ok(row) => {
+                let row = row.map(|row| Arc::new(sanitize(row)));
+                self.cache.insert(key, row.clone()).await;
+                metrics::counter!("notifications_template_lookups_total", "outcome" => if row.is_some() { "found" } else { "missing" }).increment(1);
+                row
+            }
+            Err(e) => {
+                warn!(key = ?key, error = %e, "Template lookup failed, rendering without it");
+                metrics::counter!("notifications_template_lookups_total", "outcome" => "failed").increment(1);
+                None
+            }
+        }
+    }
+
+    async fn fetch(&self, sql: &str, params: &[Param]) -> Result<Option<Map<String, Value>>, String> {
+        let query = async {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query("SET TRANSACTION READ ONLY").persistent(false).execute(&mut *tx).await?;
+            sqlx::query(&format!("SET LOCAL statement_timeout = '{}ms'", self.timeout.as_millis()))
+                .persistent(false)
+                .execute(&mut *tx)
+                .await?;
+
+            // The lookup is SQL by design; it is only ever embedded as a sub-select
+            let wrapped = format!("SELECT to_jsonb(lookup) FROM ({}) AS lookup LIMIT 1", sql);
+            let mut query = sqlx::query_scalar::<_, Value>(&wrapped).persistent(false);
+            for param in params {
+                query = match param {
+                    Param::Uuid(id) => query.bind(*id),
+                    Param::Text(text) => query.bind(text.clone()),
+                };
+            }
+            let row = query.fetch_optional(&mut *tx).await;
+            tx.rollback().await?;
+            row
+        };
+        match tokio::time::timeout(self.timeout, query).await {
+            Ok(Ok(Some(Value::Object(row)))) => Ok(Some(row)),
+            Ok(Ok(_)) => Ok(None),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => {
+                debug!(timeout_ms = self.timeout.as_millis() as u64, "Template lookup timed out");
+                Err(format!("timed out after {}ms", self.timeout.as_millis()))
+            }
+        }
+    }
+}
+
+enum Param {
+    Uuid(Uuid),
+    Text(String),
+}
+
+fn actor_params(actor_id: Uuid, tenant_id: &str) -> Vec<Param> {
+    vec![Param::Uuid(actor_id), Param::Text(tenant_id.to_string())]
+}
+
+fn target_params(target_type: &str, target_id: Uuid, tenant_id: &str) -> Vec<Param> {
+    vec![Param::Text(target_type.to_string()), Param::Uuid(target_id), Param::Text(tenant_id.to_string())]
+}
+
+/// Scalars only; strings without control characters and capped at MAX_VALUE_CHARS
+fn sanitize(row: Map<String, Value>) -> Map<String, Value> {
+    row.into_iter()
+        .filter_map(|(column, value)| match value {
+            Value::String(text) => {
+                let clean: String = text.chars().filter(|c| !c.is_control()).take(MAX_VALUE_CHARS).collect();
+                Some((column, Value::String(clean)))
+            }
+            Value::Number(_) | Value::Bool(_) | Value::Null => Some((column, value)),
+            Value::Array(_) | Value::Object(_) => None,
+        })
+        .collect()
+}
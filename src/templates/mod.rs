@@ -0,0 +1,161 @@
+//! Template rendering for notifications with a `template_key`.
+//!
+//! Templates live in `activity.notification_templates` (Tera syntax) so copy can
+//! change without a deploy. Variables are the notification's `message_args`,
+//! plus `payload` for access to producer data, plus `actor` / `target` when
+//! TEMPLATE_ACTOR_QUERY / TEMPLATE_TARGET_QUERY are configured (see `lookups`).
+
+mod lookups;
+
+pub use lookups::TemplateLookups;
+
+use crate::db::TemplateQueries;
+use crate::db::templates::NotificationTemplate;
+use crate::i18n::{LocalizedText, DEFAULT_LOCALE};
+use crate::models::Notification;
+use sqlx::PgPool;
+use tera::{Context, Tera};
+use tracing::{trace, warn};
+
+#[derive(Debug)]
+pub enum TemplateError {
+    /// No template row for this key/locale/channel
+    NotFound(String),
+    Database(sqlx::Error),
+    Render(String),
+}
+
+impl std::fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateError::NotFound(key) => write!(f, "Template not found: {}", key),
+            TemplateError::Database(e) => write!(f, "Template lookup failed: {}", e),
+            TemplateError::Render(e) => write!(f, "Template render error: {}", e),
+        }
+    }
+}
+
+/// Rendered text plus the template version that produced it (for the attempts log)
+#[derive(Debug)]
+pub struct RenderedTemplate {
+    pub text: LocalizedText,
+    pub version: i32,
+}
+
+pub struct TemplateRenderer {
+    pool: PgPool,
+    lookups: Option<TemplateLookups>,
+}
+
+impl TemplateRenderer {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool, lookups: None }
+    }
+
+    /// Resolve `actor.*` / `target.*` variables with these lookups
+    pub fn with_lookups(mut self, lookups: Option<TemplateLookups>) -> Self {
+        self.lookups = lookups;
+        self
+    }
+
+    /// Variables for the notification's templates, including looked-up actor and target
+    pub async fn variables(&self, notification: &Notification) -> serde_json::Value {
+        let mut variables = template_context(notification);
+        if let (Some(lookups), serde_json::Value::Object(map)) = (&self.lookups, &mut variables) {
+            lookups.extend(notification, map).await;
+        }
+        variables
+    }
+
+    /// Render the notification's template for a locale and channel
+    pub async fn render(
+        &self,
+        notification: &Notification,
+        template_key: &str,
+        locale: Option<&str>,
+        channel: &str,
+    ) -> Result<RenderedTemplate, TemplateError> {
+        let locale = locale.unwrap_or(DEFAULT_LOCALE);
+
+        let template = TemplateQueries::find(&self.pool, template_key, locale, DEFAULT_LOCALE, channel)
+            .await
+            .map_err(TemplateError::Database)?
+            .ok_or_else(|| TemplateError::NotFound(template_key.to_string()))?;
+
+        trace!(
+            template_key = %template_key,
+            locale = %template.locale,
+            channel = %template.channel,
+            version = template.version,
+            "Rendering template"
+        );
+
+        Ok(RenderedTemplate {
+            text: Self::render_template(&template, &self.variables(notification).await)?,
+            version: template.version,
+        })
+    }
+
+    /// Check that both templates compile (run before saving)
+    pub fn validate(title_tpl: &str, body_tpl: &str) -> Result<(), TemplateError> {
+        let mut tera = Tera::default();
+        tera.add_raw_template("title", title_tpl)
+            .map_err(|e| TemplateError::Render(format!("title: {}", error_chain(&e))))?;
+        tera.add_raw_template("body", body_tpl)
+            .map_err(|e| TemplateError::Render(format!("body: {}", error_chain(&e))))?;
+        Ok(())
+    }
+
+    /// Render a template with a JSON object of variables (also used for previews)
+    pub fn render_template(
+        template: &NotificationTemplate,
+        variables: &serde_json::Value,
+    ) -> Result<LocalizedText, TemplateError> {
+        let context = Context::from_value(variables.clone())
+            .map_err(|e| TemplateError::Render(format!("Invalid variables: {}", e)))?;
+
+        let render = |source: &str| {
+            // Push/WS text is not HTML: no autoescaping
+            Tera::one_off(source, &context, false).map_err(|e| {
+                warn!(template_key = %template.template_key, error = ?e, "Template render failed");
+                TemplateError::Render(error_chain(&e))
+            })
+        };
+
+        Ok(LocalizedText {
+            title: render(&template.title_tpl)?,
+            body: render(&template.body_tpl)?,
+        })
+    }
+}
+
+/// Render a single template source with `TemplateRenderer::variables` (experiment variants)
+pub fn render_source(source: &str, variables: &serde_json::Value) -> Result<String, TemplateError> {
+    let context = Context::from_value(variables.clone())
+        .map_err(|e| TemplateError::Render(format!("Invalid variables: {}", e)))?;
+    Tera::one_off(source, &context, false).map_err(|e| TemplateError::Render(error_chain(&e)))
+}
+
+/// Variables available to templates: message_args at top level, plus `payload`
+fn template_context(notification: &Notification) -> serde_json::Value {
+    let mut variables = match &notification.message_args {
+        Some(serde_json::Value::Object(args)) => args.clone(),
+        _ => serde_json::Map::new(),
+    };
+    variables.insert(
+        "payload".to_string(),
+        notification.payload.clone().unwrap_or(serde_json::Value::Null),
+    );
+    serde_json::Value::Object(variables)
+}
+
+/// Tera nests the useful message in the error source chain
+fn error_chain(e: &tera::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = std::error::Error::source(e);
+    while let Some(inner) = source {
+        message = format!("{}: {}", message, inner);
+        source = inner.source();
+    }
+    message
+}
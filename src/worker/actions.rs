@@ -0,0 +1,71 @@
+use crate::config::Config;
+use crate::db::actions::{ActionTarget, RecordedAction};
+use crate::db::ReceiptQueries;
+use crate::realtime::RealtimeBus;
+use bus_client::BusEnvelope;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
+
+/// Bus event type of an answer on BUS_EVENTS_TOPIC
+const ACTION_EVENT_TYPE: &str = "notification_action";
+
+/// Forwards a user's answer to an inline action to the producer
+///
+/// The answer is queued in the receipt outbox with `status: "action"`, so it reaches the
+/// notification's callback_url and the receipt webhooks for its type, signed and retried
+/// like a receipt (only with RECEIPT_SIGNING_SECRET). It is also published as
+/// `notification_action` on BUS_EVENTS_TOPIC. Best effort: the answer is already recorded.
+pub struct ActionRelay {
+    pool: PgPool,
+    bus_client: Option<Arc<dyn RealtimeBus>>,
+    events_topic: Option<String>,
+    receipts: bool,
+    simulate: bool,
+}
+
+impl ActionRelay {
+    pub fn new(pool: PgPool, config: &Config, bus_client: Option<Arc<dyn RealtimeBus>>) -> Self {
+        Self {
+            pool,
+            bus_client,
+            events_topic: config.bus_events_topic.clone(),
+            receipts: config.receipt_signing_secret.is_some(),
+            simulate: config.is_simulated(),
+        }
+    }
+
+    #[instrument(skip(self, target, answer), fields(id = %target.id, user_id = %user_id, action = %answer.action))]
+    pub async fn forward(&self, target: &ActionTarget, user_id: Uuid, answer: &RecordedAction) {
+        // Like receipts: nothing reaches producers from a simulated run
+        if self.simulate {
+            info!("🧪 Simulated action, not forwarded");
+            return;
+        }
+        let body = serde_json::json!({
+            "notification_id": target.id,
+            "tenant_id": target.tenant_id,
+            "user_id": user_id,
+            "notification_type": target.notification_type,
+            "status": "action",
+            "action": answer.action,
+            "input": answer.input,
+            "created_at": target.created_at,
+            "occurred_at": answer.occurred_at,
+        });
+
+        if self.receipts {
+            match ReceiptQueries::enqueue(&self.pool, target.id, &target.notification_type, &body).await {
+                Ok(queued) => debug!(queued = queued, "Action queued for the producer's callbacks"),
+                Err(e) => warn!(error = %e, "Failed to queue the action for the producer's callbacks"),
+            }
+        }
+        if let (Some(bus), Some(topic)) = (&self.bus_client, &self.events_topic) {
+            let envelope = BusEnvelope::new(topic.as_str(), ACTION_EVENT_TYPE).with_payload(body);
+            if let Err(e) = bus.publish(&envelope).await {
+                warn!(topic = %topic, error = %e, "Failed to publish the action on the Bus");
+            }
+        }
+    }
+}
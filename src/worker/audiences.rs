@@ -0,0 +1,91 @@
+//! Members of `org:<id>` / `team:<id>` audiences.
+//!
+//! By default from `activity.audience_members`, which producers keep in sync per organization
+//! (`PUT /api/v1/audiences/orgs/{org_id}/members`). With AUDIENCE_MEMBERS_QUERY the members
+//! come from the application's own tables instead: operator SQL with `$1` = `org` or `team`,
+//! `$2` = the id, `$3` = tenant, returning a `user_id` column. It runs read-only with a
+//! statement timeout; a failure fails the notification, which is retried like any other.
+
+use crate::config::Config;
+use crate::db::AudienceQueries;
+use crate::models::Audience;
+use sqlx::PgPool;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct AudienceResolver {
+    pool: PgPool,
+    query: Option<Arc<str>>,
+    timeout: Duration,
+}
+
+impl AudienceResolver {
+    pub fn from_config(pool: PgPool, config: &Config) -> Self {
+        let query = config
+            .audience_members_query
+            .as_deref()
+            .map(|sql| sql.trim().trim_end_matches(';'))
+            .filter(|sql| !sql.is_empty())
+            .map(Arc::from);
+        Self { pool, query, timeout: Duration::from_millis(config.audience_query_timeout_ms.max(1)) }
+    }
+
+    /// Run AUDIENCE_MEMBERS_QUERY once with an id that matches nothing (startup check)
+    pub async fn validate(&self) -> Result<(), String> {
+        match &self.query {
+            Some(sql) => self
+                .fetch(sql, &Audience::Org(""), "")
+                .await
+                .map(|_| ())
+                .map_err(|e| format!("Invalid AUDIENCE_MEMBERS_QUERY: {}", e)),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether AUDIENCE_MEMBERS_QUERY replaces the synced membership table
+    pub fn is_query(&self) -> bool {
+        self.query.is_some()
+    }
+
+    /// User ids in the audience (may repeat; the expansion dedups)
+    pub async fn members(&self, tenant_id: &str, audience: &Audience<'_>) -> Result<Vec<Uuid>, sqlx::Error> {
+        match &self.query {
+            Some(sql) => self.fetch(sql, audience, tenant_id).await,
+            None => AudienceQueries::members(&self.pool, tenant_id, audience).await,
+        }
+    }
+
+    async fn fetch(&self, sql: &str, audience: &Audience<'_>, tenant_id: &str) -> Result<Vec<Uuid>, sqlx::Error> {
+        let query = async {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query("SET TRANSACTION READ ONLY").persistent(false).execute(&mut *tx).await?;
+            sqlx::query(&format!("SET LOCAL statement_timeout = '{}ms'", self.timeout.as_millis()))
+                .persistent(false)
+                .execute(&mut *tx)
+                .await?;
+
+            // The query is SQL by design; it is only ever embedded as a sub-select
+            let wrapped = format!("SELECT DISTINCT members.user_id FROM ({}) AS members", sql);
+            let members = sqlx::query_scalar::<_, Uuid>(&wrapped)
+                .persistent(false)
+                .bind(audience.scope())
+                .bind(audience.id())
+                .bind(tenant_id)
+                .fetch_all(&mut *tx)
+                .await;
+            tx.rollback().await?;
+            members
+        };
+        // Waiting for a connection counts too; statement_timeout only covers the query
+        match tokio::time::timeout(self.timeout, query).await {
+            Ok(result) => result,
+            Err(_) => Err(sqlx::Error::Io(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("audience query timed out after {}ms", self.timeout.as_millis()),
+            ))),
+        }
+    }
+}
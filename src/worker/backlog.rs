@@ -0,0 +1,83 @@
+use crate::config::Config;
+use crate::db::MaintenanceQueries;
+use crate::realtime::RealtimeBus;
+use bus_client::BusEnvelope;
+use chrono::Utc;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+/// Report what piled up while no worker ran, before this one starts on it
+///
+/// Logs the due backlog (count, oldest age, per type) and sets the gauges
+/// `notifications_startup_backlog`, `notifications_startup_backlog_oldest_age_seconds`
+/// and `notifications_startup_backlog_by_type{notification_type}`. When the oldest row
+/// has waited longer than STARTUP_BACKLOG_ALERT_SECS it warns, counts
+/// `notifications_startup_backlog_alerts_total` and publishes a `backlog_alert` on
+/// BUS_EVENTS_TOPIC. A failed lookup is logged and never holds up the start.
+pub async fn report_startup(pool: &PgPool, bus: Option<Arc<dyn RealtimeBus>>, config: &Config) {
+    let types = match MaintenanceQueries::backlog_by_type(pool).await {
+        Ok(types) => types,
+        Err(e) => {
+            error!(error = %e, "Failed to look up the startup backlog");
+            return;
+        }
+    };
+
+    let total: i64 = types.iter().map(|backlog| backlog.count).sum();
+    let oldest_age_secs = types
+        .iter()
+        .map(|backlog| backlog.oldest_due_at)
+        .min()
+        .map(|oldest| (Utc::now() - oldest).num_seconds().max(0))
+        .unwrap_or(0);
+    metrics::gauge!("notifications_startup_backlog").set(total as f64);
+    metrics::gauge!("notifications_startup_backlog_oldest_age_seconds").set(oldest_age_secs as f64);
+    for backlog in &types {
+        metrics::gauge!("notifications_startup_backlog_by_type", "notification_type" => backlog.notification_type.clone())
+            .set(backlog.count as f64);
+    }
+    if total == 0 {
+        info!("No backlog at startup");
+        return;
+    }
+    let by_type = types
+        .iter()
+        .map(|backlog| format!("{}={}", backlog.notification_type, backlog.count))
+        .collect::<Vec<_>>()
+        .join(",");
+    info!(backlog = total, oldest_age_secs = oldest_age_secs, by_type = %by_type, "Backlog at startup");
+
+    let threshold = config.startup_backlog_alert_secs;
+    if threshold == 0 || oldest_age_secs <= threshold as i64 {
+        return;
+    }
+    warn!(
+        backlog = total,
+        oldest_age_secs = oldest_age_secs,
+        threshold_secs = threshold,
+        "⚠ Startup backlog older than STARTUP_BACKLOG_ALERT_SECS"
+    );
+    metrics::counter!("notifications_startup_backlog_alerts_total").increment(1);
+
+    // Same topic and skip rule as the delivery events
+    let (Some(bus), Some(topic)) = (bus, &config.bus_events_topic) else {
+        return;
+    };
+    if config.is_simulated() {
+        return;
+    }
+    let envelope = BusEnvelope::new(topic.as_str(), "backlog_alert").with_payload(serde_json::json!({
+        "backlog": total,
+        "oldest_age_secs": oldest_age_secs,
+        "threshold_secs": threshold,
+        "by_type": types,
+        "occurred_at": Utc::now(),
+    }));
+    // The Bus may be what was down: don't wait for it
+    tokio::spawn(async move {
+        if let Err(e) = bus.publish(&envelope).await {
+            warn!(error = %e, "Failed to publish the startup backlog alert");
+        }
+    });
+}
@@ -0,0 +1,107 @@
+use crate::realtime::RealtimeBus;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{debug, info, instrument, warn};
+
+/// Reachability of the websocket-bus, probed in the background
+///
+/// Every BUS_HEALTH_INTERVAL_SECS while the Bus answers; after a failure the probe backs
+/// off (doubling, up to BUS_HEALTH_MAX_BACKOFF_SECS) until it answers again. While it is
+/// down the worker skips the Bus and goes straight to push instead of waiting for a
+/// publish to time out. Clones share the state (`/readyz`, `GET /admin/stats`).
+#[derive(Clone)]
+pub struct BusHealth {
+    up: Arc<AtomicBool>,
+    status: Arc<RwLock<BusStatus>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BusStatus {
+    pub up: bool,
+    pub last_probe_at: Option<DateTime<Utc>>,
+    pub last_up_at: Option<DateTime<Utc>>,
+    /// Failed probes in a row (0 while up)
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    /// Current probe interval: the base interval while up, the backoff while down
+    pub next_probe_secs: u64,
+}
+
+impl Default for BusHealth {
+    fn default() -> Self {
+        // Assumed up until the first probe says otherwise
+        Self {
+            up: Arc::new(AtomicBool::new(true)),
+            status: Arc::new(RwLock::new(BusStatus { up: true, ..BusStatus::default() })),
+        }
+    }
+}
+
+impl BusHealth {
+    pub fn is_up(&self) -> bool {
+        self.up.load(Ordering::Relaxed)
+    }
+
+    pub fn status(&self) -> BusStatus {
+        self.status.read().map(|status| status.clone()).unwrap_or_default()
+    }
+
+    /// Probe loop (runs until the service stops)
+    #[instrument(skip_all, name = "bus_health")]
+    pub async fn run(self, bus: Arc<dyn RealtimeBus>, interval: Duration, max_backoff: Duration) {
+        info!(interval_secs = interval.as_secs(), max_backoff_secs = max_backoff.as_secs(), "Bus health probe started");
+        metrics::gauge!("notifications_bus_up").set(1.0);
+        loop {
+            let result = match tokio::time::timeout(interval.max(Duration::from_secs(1)), bus.health_check()).await {
+                Ok(Ok(true)) => Ok(()),
+                Ok(Ok(false)) => Err("health check reported unhealthy".to_string()),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("health check timed out".to_string()),
+            };
+            let wait = self.record(result, interval, max_backoff);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Apply a probe result - returns the wait until the next probe
+    fn record(&self, result: Result<(), String>, interval: Duration, max_backoff: Duration) -> Duration {
+        let now = Utc::now();
+        let Ok(mut status) = self.status.write() else {
+            return interval;
+        };
+        status.last_probe_at = Some(now);
+        let wait = match result {
+            Ok(()) => {
+                if !status.up {
+                    info!(failures = status.consecutive_failures, "✓ WebSocket Bus reachable again");
+                }
+                status.up = true;
+                status.last_up_at = Some(now);
+                status.consecutive_failures = 0;
+                status.last_error = None;
+                debug!("Bus health probe OK");
+                interval
+            }
+            Err(e) => {
+                status.consecutive_failures += 1;
+                if status.up {
+                    warn!(error = %e, "✗ WebSocket Bus unreachable, delivering via push until it recovers");
+                } else {
+                    debug!(error = %e, failures = status.consecutive_failures, "Bus still unreachable");
+                }
+                status.up = false;
+                status.last_error = Some(e);
+                let exponent = status.consecutive_failures.saturating_sub(1).min(16);
+                (interval * 2u32.pow(exponent)).min(max_backoff.max(interval))
+            }
+        };
+        status.next_probe_secs = wait.as_secs();
+        self.up.store(status.up, Ordering::Relaxed);
+        metrics::gauge!("notifications_bus_up").set(if status.up { 1.0 } else { 0.0 });
+        metrics::gauge!("notifications_bus_probe_failures").set(f64::from(status.consecutive_failures));
+        wait
+    }
+}
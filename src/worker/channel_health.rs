@@ -0,0 +1,179 @@
+use crate::config::Config;
+use crate::worker::router::Channel;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Outcomes older than this no longer count
+const WINDOW: Duration = Duration::from_secs(60);
+/// Outcomes kept per channel (the newest)
+const MAX_SAMPLES: usize = 200;
+/// Fewer outcomes in the window say nothing: the channel counts as healthy
+const MIN_SAMPLES: usize = 10;
+/// A channel degrades below this success ratio...
+const DEGRADE_SUCCESS: f64 = 0.9;
+/// ...and recovers only at this one, with the p90 latency well under the limit
+const RECOVER_SUCCESS: f64 = 0.97;
+const RECOVER_LATENCY: f64 = 0.7;
+
+/// Rolling latency and success per channel, and the adaptive route for high priority
+///
+/// The worker records every Bus publish and FCM send. A channel degrades when fewer than
+/// 90% of the last minute's attempts succeeded or their p90 latency passes
+/// ADAPTIVE_ROUTING_LATENCY_MS, and recovers only at 97% with the p90 under 70% of the
+/// limit, so a channel on the edge doesn't flap. While the Bus is degraded and push isn't,
+/// high priority notifications skip the Bus and go straight to FCM; the other priorities
+/// keep publishing and provide the outcomes it recovers on. Clones share the state.
+#[derive(Clone)]
+pub struct ChannelHealth {
+    max_latency: Duration,
+    bus: Arc<Mutex<ChannelStats>>,
+    push: Arc<Mutex<ChannelStats>>,
+}
+
+#[derive(Default)]
+struct ChannelStats {
+    /// (when, latency, succeeded), oldest first
+    samples: VecDeque<(Instant, Duration, bool)>,
+    degraded: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelStatus {
+    pub channel: &'static str,
+    /// Outcomes in the last minute
+    pub samples: usize,
+    pub success_ratio: Option<f64>,
+    pub p90_latency_ms: Option<u64>,
+    pub degraded: bool,
+}
+
+/// `GET /admin/stats` view of the adaptive route
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingStatus {
+    /// High priority notifications currently go straight to push
+    pub bus_skipped: bool,
+    pub channels: Vec<ChannelStatus>,
+}
+
+impl ChannelHealth {
+    pub fn new(max_latency: Duration) -> Self {
+        Self {
+            max_latency,
+            bus: Arc::default(),
+            push: Arc::default(),
+        }
+    }
+
+    /// Tracking for ADAPTIVE_ROUTING_LATENCY_MS - None when it is 0
+    pub fn from_config(config: &Config) -> Option<Self> {
+        (config.adaptive_routing_latency_ms > 0)
+            .then(|| Self::new(Duration::from_millis(config.adaptive_routing_latency_ms)))
+    }
+
+    /// Count one attempt on `channel` that took `latency`
+    pub fn record(&self, channel: Channel, latency: Duration, succeeded: bool) {
+        if let Ok(mut stats) = self.stats(channel).lock() {
+            if stats.samples.len() == MAX_SAMPLES {
+                stats.samples.pop_front();
+            }
+            stats.samples.push_back((Instant::now(), latency, succeeded));
+        }
+    }
+
+    /// Whether high priority notifications should skip the Bus right now
+    pub fn skip_bus(&self) -> bool {
+        let bus = self.evaluate(Channel::Bus);
+        let push = self.evaluate(Channel::Push);
+        let skip = bus.degraded && !push.degraded;
+        metrics::gauge!("notifications_routing_bus_skipped").set(if skip { 1.0 } else { 0.0 });
+        skip
+    }
+
+    /// The channel's p90 latency over the last minute (None = too few attempts to tell)
+    pub fn expected_latency(&self, channel: Channel) -> Option<Duration> {
+        let status = self.evaluate(channel);
+        status
+            .p90_latency_ms
+            .filter(|_| status.samples >= MIN_SAMPLES)
+            .map(Duration::from_millis)
+    }
+
+    pub fn status(&self) -> RoutingStatus {
+        let bus = self.evaluate(Channel::Bus);
+        let push = self.evaluate(Channel::Push);
+        RoutingStatus {
+            bus_skipped: bus.degraded && !push.degraded,
+            channels: vec![bus, push],
+        }
+    }
+
+    fn stats(&self, channel: Channel) -> &Mutex<ChannelStats> {
+        match channel {
+            Channel::Bus => &self.bus,
+            Channel::Push => &self.push,
+        }
+    }
+
+    /// Current numbers of a channel, moving it between healthy and degraded
+    fn evaluate(&self, channel: Channel) -> ChannelStatus {
+        let name = channel.as_str();
+        let Ok(mut stats) = self.stats(channel).lock() else {
+            return ChannelStatus { channel: name, samples: 0, success_ratio: None, p90_latency_ms: None, degraded: false };
+        };
+        let now = Instant::now();
+        while stats.samples.front().is_some_and(|(at, _, _)| now.duration_since(*at) > WINDOW) {
+            stats.samples.pop_front();
+        }
+
+        let samples = stats.samples.len();
+        let (success_ratio, p90) = if samples == 0 {
+            (None, None)
+        } else {
+            let succeeded = stats.samples.iter().filter(|(_, _, ok)| *ok).count();
+            let mut latencies: Vec<Duration> = stats.samples.iter().map(|(_, latency, _)| *latency).collect();
+            latencies.sort_unstable();
+            let p90 = latencies[(samples * 9 / 10).min(samples - 1)];
+            (Some(succeeded as f64 / samples as f64), Some(p90))
+        };
+
+        let degraded = match (success_ratio, p90) {
+            (Some(ratio), Some(p90)) if samples >= MIN_SAMPLES => {
+                if stats.degraded {
+                    ratio < RECOVER_SUCCESS || p90.as_secs_f64() > self.max_latency.as_secs_f64() * RECOVER_LATENCY
+                } else {
+                    ratio < DEGRADE_SUCCESS || p90 > self.max_latency
+                }
+            }
+            _ => false,
+        };
+        if degraded != stats.degraded {
+            if degraded {
+                warn!(
+                    channel = name,
+                    success_ratio = ?success_ratio,
+                    p90_latency_ms = ?p90.map(|p90| p90.as_millis() as u64),
+                    "✗ Channel degraded, adaptive routing avoids it for high priority"
+                );
+            } else {
+                info!(channel = name, samples = samples, "✓ Channel healthy again");
+            }
+            stats.degraded = degraded;
+        }
+
+        metrics::gauge!("notifications_channel_degraded", "channel" => name).set(if degraded { 1.0 } else { 0.0 });
+        if let (Some(ratio), Some(p90)) = (success_ratio, p90) {
+            metrics::gauge!("notifications_channel_success_ratio", "channel" => name).set(ratio);
+            metrics::gauge!("notifications_channel_latency_p90_seconds", "channel" => name).set(p90.as_secs_f64());
+        }
+        ChannelStatus {
+            channel: name,
+            samples,
+            success_ratio,
+            p90_latency_ms: p90.map(|p90| p90.as_millis() as u64),
+            degraded,
+        }
+    }
+}
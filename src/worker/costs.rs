@@ -0,0 +1,179 @@
+use crate::db::CostQueries;
+use chrono::{NaiveDate, Utc};
+use moka::future::Cache;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, instrument, warn};
+
+/// Channels with a unit price: the worker's two plus the email digest
+pub const CHANNELS: [&str; 3] = ["bus", "push", "email"];
+/// How often counted deliveries are saved
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a tenant's budget and flushed spend are cached
+const BUDGET_TTL: Duration = Duration::from_secs(30);
+
+/// Unit price per delivery and channel (CHANNEL_COSTS), e.g. `push=0.0001,email=0.002`
+///
+/// Channels without a price cost nothing. The unit is whatever the budgets use.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelCosts {
+    prices: HashMap<&'static str, f64>,
+}
+
+impl ChannelCosts {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut prices = HashMap::new();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let invalid = || format!("Invalid CHANNEL_COSTS entry '{}', expected e.g. 'email=0.002'", entry);
+            let (channel, price) = entry.split_once('=').ok_or_else(invalid)?;
+            let channel = CHANNELS
+                .into_iter()
+                .find(|c| *c == channel.trim())
+                .ok_or_else(|| format!("Unknown channel '{}' in CHANNEL_COSTS (bus, push, email)", channel.trim()))?;
+            let price: f64 = price.trim().parse().map_err(|_| invalid())?;
+            if !price.is_finite() || price < 0.0 {
+                return Err(format!("CHANNEL_COSTS '{}': the price must not be negative", entry));
+            }
+            if prices.insert(channel, price).is_some() {
+                return Err(format!("Channel '{}' appears twice in CHANNEL_COSTS", channel));
+            }
+        }
+        Ok(Self { prices })
+    }
+
+    pub fn price(&self, channel: &str) -> f64 {
+        self.prices.get(channel).copied().unwrap_or(0.0)
+    }
+
+    /// Whether delivering on `channel` counts against a budget
+    pub fn is_paid(&self, channel: &str) -> bool {
+        self.price(channel) > 0.0
+    }
+
+    fn is_free(&self) -> bool {
+        self.prices.values().all(|price| *price == 0.0)
+    }
+}
+
+/// Unsaved deliveries per (UTC day, tenant, channel)
+type PendingCounts = HashMap<(NaiveDate, String, &'static str), i64>;
+
+/// Deliveries per tenant, UTC day and channel, and the daily budget check
+///
+/// Deliveries are counted in memory and added to `activity.delivery_costs` every few
+/// seconds ([`CostLedger::run`]) and on shutdown. The budget check adds this pod's unflushed
+/// cost to the spend other pods flushed, cached for [`BUDGET_TTL`], so replicas can overshoot
+/// a cap by what they deliver in that window. Clones share the counts.
+#[derive(Clone)]
+pub struct CostLedger {
+    pool: PgPool,
+    prices: Arc<ChannelCosts>,
+    pending: Arc<Mutex<PendingCounts>>,
+    /// (day, daily_budget, flushed spend) per tenant
+    budgets: Cache<String, (NaiveDate, Option<f64>, f64)>,
+}
+
+impl CostLedger {
+    pub fn new(pool: PgPool, prices: ChannelCosts) -> Self {
+        Self {
+            pool,
+            prices: Arc::new(prices),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            budgets: Cache::builder().max_capacity(10_000).time_to_live(BUDGET_TTL).build(),
+        }
+    }
+
+    pub fn prices(&self) -> &ChannelCosts {
+        &self.prices
+    }
+
+    /// Count one delivery (`channel` is one of [`CHANNELS`])
+    pub fn record(&self, tenant_id: &str, channel: &'static str) {
+        let day = Utc::now().date_naive();
+        if let Ok(mut pending) = self.pending.lock() {
+            *pending.entry((day, tenant_id.to_string(), channel)).or_default() += 1;
+        }
+    }
+
+    /// Whether the tenant spent its daily budget (fails open on DB errors)
+    pub async fn is_over_budget(&self, tenant_id: &str) -> bool {
+        if self.prices.is_free() {
+            return false;
+        }
+        let day = Utc::now().date_naive();
+        let (budget, spent) = match self.budgets.get(tenant_id).await {
+            Some((cached_day, budget, spent)) if cached_day == day => (budget, spent),
+            _ => match CostQueries::budget(&self.pool, tenant_id, day).await {
+                Ok((budget, spent)) => {
+                    self.budgets.insert(tenant_id.to_string(), (day, budget, spent)).await;
+                    (budget, spent)
+                }
+                Err(e) => {
+                    warn!(tenant_id = %tenant_id, error = %e, "Failed to load the delivery budget, not enforcing it");
+                    return false;
+                }
+            },
+        };
+        let Some(budget) = budget else {
+            return false;
+        };
+        spent + self.pending_cost(tenant_id, day) >= budget
+    }
+
+    fn pending_cost(&self, tenant_id: &str, day: NaiveDate) -> f64 {
+        let Ok(pending) = self.pending.lock() else {
+            return 0.0;
+        };
+        pending
+            .iter()
+            .filter(|((d, tenant, _), _)| *d == day && tenant == tenant_id)
+            .map(|((_, _, channel), count)| *count as f64 * self.prices.price(channel))
+            .sum()
+    }
+
+    /// Add the counted deliveries to `activity.delivery_costs`
+    ///
+    /// Counts that fail to save are kept for the next flush.
+    pub async fn flush(&self) {
+        let counts = match self.pending.lock() {
+            Ok(mut pending) => std::mem::take(&mut *pending),
+            Err(_) => return,
+        };
+        if counts.is_empty() {
+            return;
+        }
+        let mut failed = Vec::new();
+        for ((day, tenant_id, channel), deliveries) in counts {
+            let cost = deliveries as f64 * self.prices.price(channel);
+            match CostQueries::add(&self.pool, &tenant_id, day, channel, deliveries, cost).await {
+                Ok(()) => {
+                    // The cached spend no longer includes what just moved out of `pending`
+                    self.budgets.invalidate(&tenant_id).await;
+                }
+                Err(e) => {
+                    warn!(tenant_id = %tenant_id, channel = channel, error = %e, "Failed to save delivery costs, retrying");
+                    failed.push(((day, tenant_id, channel), deliveries));
+                }
+            }
+        }
+        if !failed.is_empty() {
+            if let Ok(mut pending) = self.pending.lock() {
+                for (key, deliveries) in failed {
+                    *pending.entry(key).or_default() += deliveries;
+                }
+            }
+        }
+    }
+
+    /// Flush loop (runs until the service stops)
+    #[instrument(skip_all, name = "cost_ledger")]
+    pub async fn run(self, interval: Duration) {
+        info!(interval_secs = interval.as_secs(), "Delivery cost accounting started");
+        loop {
+            tokio::time::sleep(interval).await;
+            self.flush().await;
+        }
+    }
+}
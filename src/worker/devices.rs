@@ -0,0 +1,170 @@
+use crate::config::{Config, PushEnvironment};
+use crate::db::queries::UserDevice;
+use crate::db::DatabaseHosts;
+use crate::models::Notification;
+use crate::targeting::DeviceFilter;
+use crate::worker::windows::held_until;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use moka::future::Cache;
+use sqlx::postgres::{PgListener, PgPoolOptions};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, instrument, trace, warn};
+use uuid::Uuid;
+
+/// NOTIFY channel of migration 048, payload `<tenant_id> <user_id>`
+const DEVICE_CHANGED_CHANNEL: &str = "device_changed";
+
+/// Recently used device lists per (tenant, user), so chatty users don't cost a query per notification
+///
+/// Devices are registered by other services straight in `activity.user_devices`; the
+/// `device_changed` NOTIFY ([`DeviceCache::follow_changes`]) drops a user's entry as soon as
+/// one of their rows changes, and the short TTL catches anything a lost listener missed.
+/// Empty lists are never cached, and tokens the worker removes (UNREGISTERED) invalidate
+/// the entry immediately. Clones share the entries (the API invalidates on token refresh).
+#[derive(Clone)]
+pub struct DeviceCache {
+    cache: Cache<(String, Uuid), Arc<Vec<UserDevice>>>,
+}
+
+impl DeviceCache {
+    pub fn new(capacity: u64, ttl: Duration) -> Self {
+        Self {
+            cache: Cache::builder().max_capacity(capacity).time_to_live(ttl).build(),
+        }
+    }
+
+    /// Cache for DEVICE_CACHE_TTL_SECS / DEVICE_CACHE_CAPACITY - None when the TTL is 0
+    pub fn from_config(config: &Config) -> Option<Self> {
+        (config.device_cache_ttl_secs > 0)
+            .then(|| Self::new(config.device_cache_capacity, Duration::from_secs(config.device_cache_ttl_secs)))
+    }
+
+    pub async fn get(&self, tenant_id: &str, user_id: Uuid) -> Option<Arc<Vec<UserDevice>>> {
+        let devices = self.cache.get(&(tenant_id.to_string(), user_id)).await;
+        trace!(user_id = %user_id, hit = devices.is_some(), "Device cache lookup");
+        devices
+    }
+
+    pub async fn insert(&self, tenant_id: &str, user_id: Uuid, devices: Arc<Vec<UserDevice>>) {
+        self.cache.insert((tenant_id.to_string(), user_id), devices).await;
+    }
+
+    /// Drop a user's entry (after removing, registering or replacing a device)
+    pub async fn invalidate(&self, tenant_id: &str, user_id: Uuid) {
+        self.cache.invalidate(&(tenant_id.to_string(), user_id)).await;
+    }
+
+    /// Drop entries as devices change in the database (runs until the service stops)
+    ///
+    /// Listens on the active host and moves with it on failover. Changes made while the
+    /// connection was down were not heard, so every (re)connect clears the whole cache.
+    #[instrument(skip_all, name = "device_changes")]
+    pub async fn follow_changes(self, hosts: DatabaseHosts) {
+        loop {
+            match self.listen(&hosts).await {
+                Ok(()) => debug!(host = %hosts.active_host(), "Database host switched, moving device LISTEN"),
+                Err(e) => {
+                    warn!(error = %e, "Device change listener failed, reconnecting in 5s");
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    }
+
+    /// Listen until an error (Err) or a switch to another database host (Ok)
+    async fn listen(&self, hosts: &DatabaseHosts) -> Result<(), sqlx::Error> {
+        let mut switched = hosts.subscribe();
+        switched.mark_unchanged();
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .max_lifetime(None)
+            .idle_timeout(None)
+            .connect_with(hosts.connect_options())
+            .await?;
+        let mut listener = PgListener::connect_with(&pool).await?;
+        listener.listen(DEVICE_CHANGED_CHANNEL).await?;
+        self.cache.invalidate_all();
+        info!(channel = DEVICE_CHANGED_CHANNEL, "✓ Listening for device changes");
+
+        loop {
+            let received = tokio::select! {
+                received = listener.try_recv() => received?,
+                Ok(()) = switched.changed() => return Ok(()),
+            };
+            let Some(notification) = received else {
+                // The connection dropped; the next `try_recv` reconnects
+                warn!("Device change listener lost its connection, clearing the device cache");
+                self.cache.invalidate_all();
+                continue;
+            };
+            match parse_device_change(notification.payload()) {
+                Some((tenant_id, user_id)) => {
+                    trace!(tenant_id = %tenant_id, user_id = %user_id, "Device changed, dropping cached list");
+                    self.invalidate(tenant_id, user_id).await;
+                    metrics::counter!("notifications_device_cache_invalidations_total").increment(1);
+                }
+                None => warn!(payload = notification.payload(), "Ignoring malformed device_changed payload"),
+            }
+        }
+    }
+}
+
+/// `<tenant_id> <user_id>` - the user id is last, tenant ids are free text
+fn parse_device_change(payload: &str) -> Option<(&str, Uuid)> {
+    let (tenant_id, user_id) = payload.rsplit_once(' ')?;
+    Some((tenant_id, user_id.parse().ok()?))
+}
+
+/// Why a device doesn't get a push right now (device preferences)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceHold {
+    /// Inside the device's quiet hours, which end at `until`
+    Quiet { until: DateTime<Utc> },
+    /// The device opted out of this notification type
+    TypeDisabled,
+    /// The notification's `device_filter` excludes the device
+    NotTargeted,
+}
+
+/// The device's push environment (dev builds register as sandbox), else the service default
+pub fn push_environment(device: &UserDevice, default: PushEnvironment) -> PushEnvironment {
+    device
+        .push_environment
+        .as_deref()
+        .and_then(PushEnvironment::parse)
+        .unwrap_or(default)
+}
+
+/// Whether targeting or a device's own preferences hold back this push - None means send
+///
+/// Critical notifications ignore quiet hours (as with snooze), not the type list.
+pub fn device_hold(
+    device: &UserDevice,
+    notification: &Notification,
+    filter: Option<&DeviceFilter>,
+    now: DateTime<Utc>,
+) -> Option<DeviceHold> {
+    if filter.is_some_and(|filter| !filter.matches(device)) {
+        return Some(DeviceHold::NotTargeted);
+    }
+    if let Some(types) = &device.enabled_types {
+        if !types.contains(&notification.notification_type) {
+            return Some(DeviceHold::TypeDisabled);
+        }
+    }
+    if notification.priority.as_deref() == Some("critical") {
+        return None;
+    }
+
+    let (Some(start), Some(end)) = (device.quiet_hours_start, device.quiet_hours_end) else {
+        return None;
+    };
+    let tz: Tz = device
+        .quiet_hours_timezone
+        .as_deref()
+        .and_then(|zone| zone.parse().ok())
+        .unwrap_or(Tz::UTC);
+    held_until(start, end, tz, now).map(|until| DeviceHold::Quiet { until })
+}
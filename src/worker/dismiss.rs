@@ -0,0 +1,127 @@
+use crate::config::{Config, PushEnvironment};
+use crate::db::NotificationQueries;
+use crate::dev_sandbox::DevSandbox;
+use crate::push::fcm::{mask_token, FcmError};
+use crate::protocol::Protocols;
+use crate::push::FcmClient;
+use crate::realtime::RealtimeBus;
+use crate::worker::devices::push_environment;
+use crate::worker::tenants::TenantRegistry;
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
+
+/// Takes a first-ack-wins notification down on the devices that didn't ack it
+///
+/// Best effort: a device that misses the dismiss keeps ringing until the app times out.
+pub struct Dismisser {
+    pool: PgPool,
+    tenants: TenantRegistry,
+    bus_client: Option<Arc<dyn RealtimeBus>>,
+    protocols: Protocols,
+    types: Vec<String>,
+    /// Devices without their own push_environment
+    default_environment: PushEnvironment,
+    /// First-ack types whose dismisses are only simulated (see `Config::simulates`)
+    simulated: Vec<String>,
+}
+
+impl Dismisser {
+    pub fn new(
+        pool: PgPool,
+        config: &Config,
+        bus_client: Option<Arc<dyn RealtimeBus>>,
+        fcm_client: Option<Arc<FcmClient>>,
+    ) -> Self {
+        let tenants = TenantRegistry::new(pool.clone(), fcm_client)
+            .with_fcm_endpoints(&config.fcm_base_url, &config.fcm_token_url);
+        Self {
+            pool,
+            tenants,
+            bus_client,
+            protocols: Protocols::default(),
+            types: config.first_ack_types.clone(),
+            default_environment: config.push_environment,
+            simulated: config.first_ack_types.iter().filter(|t| config.simulates(t)).cloned().collect(),
+        }
+    }
+
+    /// FCM client for sandbox devices (see `NotificationWorker::with_sandbox_fcm`)
+    pub fn with_sandbox_fcm(mut self, fcm_sandbox: Option<Arc<FcmClient>>) -> Self {
+        self.tenants = self.tenants.with_sandbox_fcm(fcm_sandbox);
+        self
+    }
+
+    /// Developer sandbox tenant, pushed to its mock FCM (see `NotificationWorker::with_dev_sandbox`)
+    pub fn with_dev_sandbox(mut self, dev_sandbox: Option<Arc<DevSandbox>>) -> Self {
+        self.tenants = self.tenants.with_dev_sandbox(dev_sandbox);
+        self
+    }
+
+    /// Protocol versions the Bus messages are published in (WS_PROTOCOLS)
+    pub fn with_protocols(mut self, protocols: Protocols) -> Self {
+        self.protocols = protocols;
+        self
+    }
+
+    /// Whether acks of this type dismiss (FIRST_ACK_TYPES)
+    pub fn handles(&self, notification_type: &str) -> bool {
+        self.types.iter().any(|t| t == notification_type)
+    }
+
+    /// Dismiss on the Bus and push a dismiss to every device except the one that acked
+    #[instrument(skip(self, acked_by_device), fields(tenant_id = %tenant_id, user_id = %user_id, id = %id))]
+    pub async fn dismiss(
+        &self,
+        tenant_id: &str,
+        user_id: Uuid,
+        id: Uuid,
+        notification_type: &str,
+        acked_by_device: Option<&str>,
+    ) {
+        let tenant = self.tenants.resolve(tenant_id).await;
+        if self.simulated.iter().any(|t| t == notification_type) {
+            info!("🧪 Simulated dismiss");
+            return;
+        }
+
+        // Connected clients drop it from their UI; the acking client ignores its own ack
+        if let Some(bus) = self.bus_client.as_deref() {
+            let topic = tenant.topic("notifications");
+            let message = json!({ "type": "dismiss", "id": id });
+            match self.protocols.publish_to_user(bus, user_id, &topic, "dismiss", &message).await {
+                Ok(delivered_to) => debug!(delivered_to = delivered_to, "Dismiss published via Bus"),
+                Err(e) => warn!(error = %e, "Failed to publish dismiss to WebSocket Bus"),
+            }
+        }
+
+        if !tenant.has_fcm() {
+            return;
+        }
+        let devices = match NotificationQueries::get_user_devices(&self.pool, tenant_id, user_id).await {
+            Ok(devices) => devices,
+            Err(e) => {
+                warn!(error = %e, "Failed to fetch devices for dismiss");
+                return;
+            }
+        };
+        let push = FcmClient::prepare_dismiss(id);
+        let mut sent = 0u64;
+        for device in devices.iter().filter(|d| Some(d.fcm_token.as_str()) != acked_by_device) {
+            let environment = push_environment(device, self.default_environment);
+            let Some(fcm) = tenant.fcm_for(environment) else {
+                continue;
+            };
+            match fcm.send_prepared(&device.fcm_token, &push).await {
+                Ok(_) => sent += 1,
+                // The next notification's push cleans it up
+                Err(FcmError::InvalidToken) => debug!(token = %mask_token(&device.fcm_token), "Dismiss to invalid token"),
+                Err(e) => warn!(token = %mask_token(&device.fcm_token), error = %e, "Dismiss push failed"),
+            }
+        }
+        debug!(sent = sent, "Dismiss pushed to devices");
+        metrics::counter!("notifications_dismissals_sent_total").increment(sent);
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Pod shutdown state shared by the worker, `/readyz` and `POST /admin/prestop`
+///
+/// Once draining, the worker claims nothing new and `/readyz` reports not-ready; the
+/// deliveries already started finish. There is no way back: the pod is going away.
+#[derive(Clone, Default)]
+pub struct Drain {
+    state: Arc<DrainState>,
+}
+
+#[derive(Default)]
+struct DrainState {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// One delivery in progress; dropping it lets a waiting drain finish
+pub struct InFlight {
+    state: Arc<DrainState>,
+}
+
+impl Drain {
+    /// Stop taking work - false when a drain was already started
+    pub fn start(&self) -> bool {
+        !self.state.draining.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.state.draining.load(Ordering::SeqCst)
+    }
+
+    /// Deliveries currently running
+    pub fn in_flight(&self) -> usize {
+        self.state.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Register a delivery before starting it - None once draining
+    pub fn delivery(&self) -> Option<InFlight> {
+        // Count first, then check: a drain either sees this delivery or we see the drain
+        self.state.in_flight.fetch_add(1, Ordering::SeqCst);
+        let in_flight = InFlight { state: self.state.clone() };
+        (!self.is_draining()).then_some(in_flight)
+    }
+
+    /// Wait until no delivery is running - false when `timeout` passed first
+    pub async fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let idle = self.state.idle.notified();
+            tokio::pin!(idle);
+            idle.as_mut().enable();
+            if self.in_flight() == 0 {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, idle).await.is_err() {
+                return false;
+            }
+        }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.state.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.state.idle.notify_waiters();
+        }
+    }
+}
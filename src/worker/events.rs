@@ -0,0 +1,155 @@
+use crate::models::CloudEvent;
+use crate::realtime::RealtimeBus;
+use bus_client::BusEnvelope;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{info, trace, warn};
+use uuid::Uuid;
+
+/// CloudEvents `source` attribute for events emitted by this service
+pub const EVENT_SOURCE: &str = "/notifications-service";
+
+/// Buffer per subscriber; slow sinks lag (and log) instead of blocking the worker
+pub const EVENT_BUFFER: usize = 1024;
+
+/// Most delivery events sent in one Bus batch publish
+const MAX_BUS_EVENT_BATCH: usize = 100;
+
+/// Outcome of one worker pass over a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Delivered,
+    /// This attempt failed; the notification may still be retried
+    Failed,
+    Suppressed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+            DeliveryStatus::Suppressed => "suppressed",
+        }
+    }
+}
+
+/// Emitted by the worker after each delivery decision (consumed by event sinks)
+#[derive(Debug, Clone, Serialize)]
+pub struct DeliveryEvent {
+    pub notification_id: Uuid,
+    pub tenant_id: String,
+    pub user_id: Uuid,
+    pub notification_type: String,
+    pub status: DeliveryStatus,
+    /// Channel that delivered the notification (None when suppressed or failed)
+    pub channel: Option<&'static str>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl DeliveryEvent {
+    /// Routing key, e.g. `delivered.push`, `failed`, `suppressed`
+    pub fn routing_key(&self) -> String {
+        match self.channel {
+            Some(channel) => format!("{}.{}", self.status.as_str(), channel),
+            None => self.status.as_str().to_string(),
+        }
+    }
+
+    /// Bus event type, e.g. `notification_delivered`
+    pub fn bus_event_type(&self) -> String {
+        format!("notification_{}", self.status.as_str())
+    }
+
+    /// Wrap as a CloudEvent, type `<prefix>.<routing key>`, subject = notification id
+    pub fn to_cloudevent(&self, type_prefix: &str) -> CloudEvent {
+        let mut event = CloudEvent::new(
+            EVENT_SOURCE,
+            format!("{}.{}", type_prefix, self.routing_key()),
+            serde_json::to_value(self).unwrap_or_default(),
+        );
+        event.subject = Some(self.notification_id.to_string());
+        event.time = Some(self.occurred_at);
+        event
+    }
+}
+
+pub type EventSender = broadcast::Sender<DeliveryEvent>;
+
+/// Create the delivery event channel (subscribe sinks via `sender.subscribe()`)
+pub fn channel() -> EventSender {
+    broadcast::channel(EVENT_BUFFER).0
+}
+
+/// Publish worker delivery events on a Bus topic until the channel closes
+///
+/// Services (analytics, CRM) subscribe to the topic instead of polling the database.
+/// Events that queued up while a publish was in flight (a worker batch finishes many
+/// notifications at once) go out together as one batch publish.
+pub async fn publish_to_bus(bus: Arc<dyn RealtimeBus>, topic: String, mut events: broadcast::Receiver<DeliveryEvent>) {
+    info!(topic = %topic, "Bus delivery event publisher started");
+
+    loop {
+        let first = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!(skipped = skipped, "Bus event publisher lagging, events dropped");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let mut batch = vec![first];
+        while batch.len() < MAX_BUS_EVENT_BATCH {
+            match events.try_recv() {
+                Ok(event) => batch.push(event),
+                Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                    warn!(skipped = skipped, "Bus event publisher lagging, events dropped");
+                }
+                Err(_) => break,
+            }
+        }
+
+        let envelopes: Vec<BusEnvelope> = batch
+            .iter()
+            .map(|event| {
+                BusEnvelope::new(topic.as_str(), event.bus_event_type())
+                    .with_payload(serde_json::to_value(event).unwrap_or_default())
+            })
+            .collect();
+
+        if let [envelope] = envelopes.as_slice() {
+            match bus.publish(envelope).await {
+                Ok(delivered_to) => trace!(
+                    notification_id = %batch[0].notification_id,
+                    delivered_to = delivered_to,
+                    "Delivery event published to Bus"
+                ),
+                Err(e) => warn!(
+                    notification_id = %batch[0].notification_id,
+                    topic = %topic,
+                    error = %e,
+                    "Failed to publish delivery event to Bus"
+                ),
+            }
+            continue;
+        }
+
+        match bus.publish_batch(&envelopes).await {
+            Ok(delivered_to) => trace!(
+                events = envelopes.len(),
+                delivered_to = delivered_to,
+                "Delivery events batch published to Bus"
+            ),
+            Err(e) => warn!(
+                events = envelopes.len(),
+                topic = %topic,
+                error = %e,
+                "Failed to publish delivery event batch to Bus"
+            ),
+        }
+    }
+}
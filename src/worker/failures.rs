@@ -0,0 +1,60 @@
+/// Root cause of a delivery failure, stored as `notifications.failure_category`
+///
+/// Derived from the `NotificationError` at failure time (`NotificationError::category`),
+/// so `GET /admin/failures?category=` can group recurring causes without reading logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureCategory {
+    /// FCM rejected the device token (UNREGISTERED / INVALID_ARGUMENT)
+    InvalidToken,
+    /// FCM rate limit (429 QUOTA_EXCEEDED)
+    FcmQuota,
+    /// FCM unreachable, failing (5xx), not configured or its OAuth token failed
+    FcmUnavailable,
+    /// The Bus publish failed and push could not make up for it
+    BusUnreachable,
+    /// Rendering the template or catalog message failed
+    TemplateError,
+    /// The message itself was rejected (400)
+    Validation,
+    /// The user has no push device left
+    NoDevices,
+    /// Our own database calls failed (device lookup, topic expansion)
+    Database,
+    /// The content policy hook failed with POLICY_HOOK_FAIL_MODE=closed
+    PolicyHook,
+    Other,
+}
+
+impl FailureCategory {
+    pub const ALL: [FailureCategory; 10] = [
+        FailureCategory::InvalidToken,
+        FailureCategory::FcmQuota,
+        FailureCategory::FcmUnavailable,
+        FailureCategory::BusUnreachable,
+        FailureCategory::TemplateError,
+        FailureCategory::Validation,
+        FailureCategory::NoDevices,
+        FailureCategory::Database,
+        FailureCategory::PolicyHook,
+        FailureCategory::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureCategory::InvalidToken => "invalid_token",
+            FailureCategory::FcmQuota => "fcm_quota",
+            FailureCategory::FcmUnavailable => "fcm_unavailable",
+            FailureCategory::BusUnreachable => "bus_unreachable",
+            FailureCategory::TemplateError => "template_error",
+            FailureCategory::Validation => "validation",
+            FailureCategory::NoDevices => "no_devices",
+            FailureCategory::Database => "database",
+            FailureCategory::PolicyHook => "policy_hook",
+            FailureCategory::Other => "other",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        FailureCategory::ALL.into_iter().find(|category| category.as_str() == name)
+    }
+}
@@ -0,0 +1,69 @@
+//! Foreground boost: when a user's app comes to the foreground (`POST /api/v1/foreground`),
+//! the worker delivers that user's due notifications right away instead of waiting for its
+//! next cycle. The API publishes on [`FOREGROUND_CHANNEL`] so the request may land on any
+//! replica; the worker that hears it queues the user on its [`WakeSignal`].
+
+use crate::db::foreground::FOREGROUND_CHANNEL;
+use crate::db::listener::WakeSignal;
+use crate::db::DatabaseHosts;
+use sqlx::postgres::{PgListener, PgPoolOptions};
+use std::time::Duration;
+use tracing::{debug, info, instrument, trace, warn};
+use uuid::Uuid;
+
+/// Turn foreground NOTIFYs into boosts on `wake` (runs until the service stops)
+///
+/// Listens on the active host and moves with it on failover. A boost missed while the
+/// connection was down only means the notifications go out on the worker's normal cycle.
+#[instrument(skip_all, name = "foreground")]
+pub async fn follow_foreground(hosts: DatabaseHosts, wake: WakeSignal) {
+    loop {
+        match listen(&hosts, &wake).await {
+            Ok(()) => debug!(host = %hosts.active_host(), "Database host switched, moving foreground LISTEN"),
+            Err(e) => {
+                warn!(error = %e, "Foreground listener failed, reconnecting in 5s");
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    }
+}
+
+/// Listen until an error (Err) or a switch to another database host (Ok)
+async fn listen(hosts: &DatabaseHosts, wake: &WakeSignal) -> Result<(), sqlx::Error> {
+    let mut switched = hosts.subscribe();
+    switched.mark_unchanged();
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .max_lifetime(None)
+        .idle_timeout(None)
+        .connect_with(hosts.connect_options())
+        .await?;
+    let mut listener = PgListener::connect_with(&pool).await?;
+    listener.listen(FOREGROUND_CHANNEL).await?;
+    info!(channel = FOREGROUND_CHANNEL, "✓ Listening for foreground boosts");
+
+    loop {
+        let received = tokio::select! {
+            received = listener.try_recv() => received?,
+            Ok(()) = switched.changed() => return Ok(()),
+        };
+        let Some(notification) = received else {
+            // The connection dropped; the next `try_recv` reconnects
+            warn!("Foreground listener lost its connection");
+            continue;
+        };
+        match parse_foreground(notification.payload()) {
+            Some((tenant_id, user_id)) => {
+                trace!(tenant_id = %tenant_id, user_id = %user_id, "App came to the foreground, boosting");
+                wake.boost(tenant_id, user_id);
+            }
+            None => warn!(payload = notification.payload(), "Ignoring malformed user_foreground payload"),
+        }
+    }
+}
+
+/// `<tenant_id> <user_id>` - the user id is last, tenant ids are free text
+fn parse_foreground(payload: &str) -> Option<(&str, Uuid)> {
+    let (tenant_id, user_id) = payload.rsplit_once(' ')?;
+    Some((tenant_id, user_id.parse().ok()?))
+}
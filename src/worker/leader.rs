@@ -0,0 +1,145 @@
+use crate::db::leases::Lease;
+use crate::db::listener::{Wake, WakeSignal};
+use crate::db::LeaseQueries;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
+
+/// Lease the notification worker needs to claim (`worker:<REGION>` with REGION set)
+const WORKER_LEASE: &str = "worker";
+
+/// Warm standby (LEADER_ELECTION): only the holder of the worker lease claims notifications
+///
+/// Every instance connects, LISTENs and keeps its caches warm; the lease decides who works.
+/// The leader renews it every third of LEADER_LEASE_SECS. A standby tries as often, so it
+/// takes over within a third of the lease after a release (graceful shutdown) and within
+/// the lease plus a third after a crash. A leader that can't renew stops claiming when
+/// its own lease would have run out, measured from before the renewal was sent, so two
+/// instances never claim at the same time. Clones share the state (`GET /admin/stats`).
+/// With REGION set every region elects its own leader.
+#[derive(Clone)]
+pub struct Leadership {
+    pool: PgPool,
+    /// The lease name (per region)
+    name: Arc<str>,
+    holder: Arc<str>,
+    lease: Duration,
+    /// Until when this instance may claim (None = standby)
+    until: Arc<RwLock<Option<Instant>>>,
+}
+
+/// Who leads, as seen by this instance
+#[derive(Debug, Clone, Serialize)]
+pub struct LeaderStatus {
+    /// This instance holds the lease
+    pub leader: bool,
+    /// This instance's holder name
+    pub holder: String,
+    /// The lease in the database (None = nobody holds it)
+    pub lease: Option<Lease>,
+}
+
+impl Leadership {
+    /// `instance` names this pod in the lease (HOSTNAME); a per-process id keeps restarts apart
+    pub fn new(pool: PgPool, instance: &str, lease: Duration) -> Self {
+        Self {
+            pool,
+            name: Arc::from(WORKER_LEASE),
+            holder: Arc::from(format!("{}/{}", instance, Uuid::new_v4().simple())),
+            lease: lease.max(Duration::from_secs(3)),
+            until: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Compete for the lease of one region only (REGION)
+    pub fn with_region(mut self, region: Option<&str>) -> Self {
+        if let Some(region) = region {
+            self.name = Arc::from(format!("{}:{}", WORKER_LEASE, region));
+        }
+        self
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.until
+            .read()
+            .map(|until| until.is_some_and(|until| Instant::now() < until))
+            .unwrap_or(false)
+    }
+
+    pub async fn status(&self) -> LeaderStatus {
+        let lease = match LeaseQueries::current(&self.pool, &self.name).await {
+            Ok(lease) => lease,
+            Err(e) => {
+                warn!(error = %e, "Failed to read the leader lease");
+                None
+            }
+        };
+        LeaderStatus {
+            leader: self.is_leader(),
+            holder: self.holder.to_string(),
+            lease,
+        }
+    }
+
+    /// Acquire/renew loop (runs until the service stops); wakes the worker on a takeover
+    #[instrument(skip_all, name = "leader_lease")]
+    pub async fn run(self, wake: WakeSignal) {
+        let interval = (self.lease / 3).max(Duration::from_secs(1));
+        info!(
+            holder = %self.holder,
+            lease_secs = self.lease.as_secs(),
+            "Leader election started, standing by until the worker lease is ours"
+        );
+        metrics::gauge!("notifications_leader").set(0.0);
+        loop {
+            self.renew(&wake).await;
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    async fn renew(&self, wake: &WakeSignal) {
+        let was_leader = self.is_leader();
+        let sent_at = Instant::now();
+        let until = match LeaseQueries::try_acquire(&self.pool, &self.name, &self.holder, self.lease.as_secs()).await {
+            Ok(Some(_)) => Some(sent_at + self.lease),
+            Ok(None) => None,
+            Err(e) => {
+                // Keep what we had: the lease runs out on its own if this goes on
+                warn!(error = %e, leader = was_leader, "Failed to renew the leader lease");
+                metrics::gauge!("notifications_leader").set(if self.is_leader() { 1.0 } else { 0.0 });
+                return;
+            }
+        };
+        if let Ok(mut current) = self.until.write() {
+            *current = until;
+        }
+
+        match (was_leader, until.is_some()) {
+            (false, true) => {
+                info!(holder = %self.holder, "👑 Leader lease acquired, claiming notifications");
+                // Start on the backlog now instead of at the next NOTIFY or poll
+                wake.send(Wake::Urgent);
+            }
+            (true, false) => warn!(holder = %self.holder, "Leader lease lost to another instance, standing by"),
+            (true, true) => debug!("Leader lease renewed"),
+            (false, false) => debug!("Leader lease held elsewhere, standing by"),
+        }
+        metrics::gauge!("notifications_leader").set(if until.is_some() { 1.0 } else { 0.0 });
+    }
+
+    /// Stop claiming and hand the lease over (shutdown, after the worker drained)
+    pub async fn release(&self) {
+        if let Ok(mut current) = self.until.write() {
+            *current = None;
+        }
+        metrics::gauge!("notifications_leader").set(0.0);
+        match LeaseQueries::release(&self.pool, &self.name, &self.holder).await {
+            Ok(true) => info!(holder = %self.holder, "Leader lease released"),
+            Ok(false) => {}
+            Err(e) => warn!(error = %e, "Failed to release the leader lease, it lapses on its own"),
+        }
+    }
+}
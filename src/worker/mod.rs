@@ -1,3 +1,30 @@
+pub mod actions;
+pub mod audiences;
+pub mod backlog;
+pub mod bus_health;
+pub mod channel_health;
+pub mod costs;
+pub mod devices;
+pub mod dismiss;
+pub mod drain;
+pub mod events;
+pub mod failures;
+pub mod foreground;
+pub mod leader;
 pub mod processor;
+pub mod read_state;
+pub mod router;
+pub mod tenants;
+pub mod test_send;
+pub mod windows;
 
+pub use audiences::AudienceResolver;
+pub use bus_health::BusHealth;
+pub use channel_health::ChannelHealth;
+pub use costs::{ChannelCosts, CostLedger};
+pub use drain::Drain;
+pub use failures::FailureCategory;
+pub use leader::Leadership;
 pub use processor::NotificationWorker;
+pub use router::{Channel, ChannelRouter, FallbackChains};
+pub use windows::DeliveryWindows;
@@ -1,36 +1,153 @@
-use bus_client::{BusClient, BusEnvelope};
-use crate::config::Config;
-use crate::db::{NotificationQueries, Database};
-use crate::models::Notification;
-use crate::push::{FcmClient, fcm::FcmError};
-use sqlx::PgPool;
+use crate::attachments::AttachmentSigner;
+use crate::chaos::FaultInjector;
+use crate::config::{BroadcastFanout, Config, ConsumptionMode, WakeSource};
+use crate::contracts::{self, ContractError};
+use crate::db::{AttemptQueries, AudienceQueries, BroadcastQueries, BundleQueries, BusDeliveryQueries, DeviceQueries, ExperimentQueries, ForegroundQueries, MaintenanceQueries, NotificationQueries, PreferenceQueries, ReceiptQueries, RegionClaim, SuppressionQueries, TopicQueries, Database};
+use crate::db::attempts::NewAttempt;
+use crate::db::bus_deliveries::BusDelivery;
+use crate::db::listener::{Wake, WakeSignal};
+use crate::db::queries::UserDevice;
+use crate::dev_sandbox::DevSandbox;
+use crate::error::{BusError, DbError, NotificationError, PushError, ValidationError};
+use crate::i18n::Localizer;
+use crate::experiments;
+use crate::templates::{self, TemplateLookups, TemplateRenderer};
+use crate::models::{Audience, DeviceCapabilities, Notification};
+use crate::otp;
+use crate::payload_sink;
+use crate::policy::{PolicyGate, Reviewed};
+use crate::protocol::Protocols;
+use crate::signing::BroadcastSigner;
+use crate::push::{FcmClient, fcm::{FcmError, PreparedPush}};
+use crate::queue::{PgQueueStore, QueueStore};
+use crate::realtime::RealtimeBus;
+use crate::targeting::DeviceFilter;
+use crate::worker::devices::{device_hold, push_environment, DeviceCache, DeviceHold};
+use crate::worker::events::{DeliveryEvent, DeliveryStatus, EventSender};
+use crate::worker::router::{Channel, ChannelRouter, FallbackChains, Route};
+use crate::worker::audiences::AudienceResolver;
+use crate::worker::bus_health::BusHealth;
+use crate::worker::channel_health::ChannelHealth;
+use crate::worker::costs::CostLedger;
+use crate::worker::drain::Drain;
+use crate::worker::leader::Leadership;
+use crate::worker::windows::DeliveryWindows;
+use crate::worker::tenants::{TenantContext, TenantRegistry};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
+use tokio::sync::Mutex;
 use tracing::{debug, error, info, trace, warn, instrument};
 use uuid::Uuid;
 
+/// A recorded Bus publish younger than this is marked on retry instead of published again
+const BUS_DELIVERY_REUSE: Duration = Duration::from_secs(15 * 60);
+/// How often a standby refreshes its tenant cache (the registry keeps entries for 5 minutes)
+const STANDBY_WARM_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Run a query on the open claim (CONSUMPTION_MODE=transactional), otherwise on the pool
+///
+/// `on_claim!(self, queue.method(args))` updates the queue row: `NotificationQueries` on the
+/// claim, the worker's `QueueStore` otherwise.
+macro_rules! on_claim {
+    ($worker:expr, queue.$method:ident($($arg:expr),* $(,)?)) => {{
+        let mut claim = $worker.claim.lock().await;
+        match claim.as_mut() {
+            Some(tx) => NotificationQueries::$method(&mut **tx, $($arg),*).await,
+            None => $worker.queue.$method($($arg),*).await,
+        }
+    }};
+    ($worker:expr, |$executor:ident| $query:expr) => {{
+        let mut claim = $worker.claim.lock().await;
+        match claim.as_mut() {
+            Some(tx) => {
+                let $executor = &mut **tx;
+                $query.await
+            }
+            None => {
+                let $executor = &$worker.pool;
+                $query.await
+            }
+        }
+    }};
+}
+
 pub struct NotificationWorker {
     pool: PgPool,
+    /// Due rows and their outcomes (batch mode; the claim transaction uses Postgres directly)
+    queue: Arc<dyn QueueStore>,
     config: Config,
-    bus_client: Option<Arc<BusClient>>,
-    fcm_client: Option<Arc<FcmClient>>,
+    bus_client: Option<Arc<dyn RealtimeBus>>,
+    router: ChannelRouter,
+    tenants: TenantRegistry,
+    localizer: Localizer,
+    templates: TemplateRenderer,
+    events: Option<EventSender>,
+    signer: Option<Arc<BroadcastSigner>>,
+    /// Pre-signs attachment URLs (None = ATTACHMENT_STORE_URL not set)
+    attachments: Option<Arc<AttachmentSigner>>,
+    /// DEBUG_MODE + CHAOS_* only
+    faults: Option<FaultInjector>,
+    /// None when DEVICE_CACHE_TTL_SECS=0
+    devices: Option<DeviceCache>,
+    /// Maintenance mode was on at the last check
+    parked: AtomicBool,
+    /// Working off the backlog parked during maintenance (priority order, throttled)
+    draining: AtomicBool,
+    /// Pod shutdown (preStop): stop claiming, let in-flight deliveries finish
+    shutdown: Drain,
+    /// Background Bus probe (None = no Bus, or probing disabled)
+    bus_health: Option<BusHealth>,
+    /// Rolling channel latency/success for the adaptive route (None = ADAPTIVE_ROUTING_LATENCY_MS=0)
+    channel_health: Option<ChannelHealth>,
+    /// Delivery costs and daily budgets (None = not tracked)
+    costs: Option<CostLedger>,
+    /// Versions every client message goes out in (WS_PROTOCOLS)
+    protocols: Protocols,
+    /// Transaction holding the row being delivered (CONSUMPTION_MODE=transactional)
+    claim: Mutex<Option<Transaction<'static, Postgres>>>,
+    /// Leader lease (None = LEADER_ELECTION off, always claims)
+    leadership: Option<Leadership>,
+    /// Content policy hook (None = POLICY_HOOK_URL not set)
+    policy: Option<PolicyGate>,
+    /// Members of `org:` / `team:` audiences
+    audiences: AudienceResolver,
+    /// Last time a standby refreshed its tenant cache
+    warmed_at: Mutex<Option<Instant>>,
+    /// REGION: the rows this instance claims (None = all)
+    region: Option<RegionClaim>,
+}
+
+/// What maintenance mode allows this batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MaintenanceGate {
+    Open,
+    Parked,
+    Draining,
 }
 
-/// Batch processing statistics
-struct BatchStats {
-    total: usize,
-    bus_success: usize,
-    push_success: usize,
-    failed: usize,
-    duration: Duration,
+/// What the bundling window does with a notification (`NotificationWorker::bundle`)
+enum Bundling {
+    /// Not bundled, or it opened the window: delivered on its own
+    Send,
+    /// Another notification opened the window: goes out with the bundle when it ends
+    Hold(DateTime<Utc>),
+    /// Held, and its window ended: delivered once for this many notifications
+    Flush(i64),
 }
 
 impl NotificationWorker {
     pub fn new(
         db: &Database,
         config: Config,
-        bus_client: Option<Arc<BusClient>>,
+        bus_client: Option<Arc<dyn RealtimeBus>>,
         fcm_client: Option<Arc<FcmClient>>,
     ) -> Self {
         debug!(
@@ -41,33 +158,184 @@ impl NotificationWorker {
             fcm_enabled = fcm_client.is_some(),
             "Creating NotificationWorker"
         );
+        let tenants = TenantRegistry::new(db.pool().clone(), fcm_client)
+            .with_fcm_endpoints(&config.fcm_base_url, &config.fcm_token_url);
+        let faults = config.debug.chaos.clone().map(FaultInjector::new);
+        let devices = DeviceCache::from_config(&config);
+        let audiences = AudienceResolver::from_config(db.pool().clone(), &config);
+        let queue: Arc<dyn QueueStore> = Arc::new(PgQueueStore::from_config(db, &config));
+        let region = RegionClaim::from_config(&config);
         Self {
             pool: db.pool().clone(),
+            queue,
             config,
             bus_client,
-            fcm_client,
+            tenants,
+            router: ChannelRouter::new(db.pool().clone()),
+            localizer: Localizer::new(),
+            templates: TemplateRenderer::new(db.pool().clone()),
+            events: None,
+            signer: None,
+            attachments: None,
+            faults,
+            devices,
+            parked: AtomicBool::new(false),
+            draining: AtomicBool::new(false),
+            shutdown: Drain::default(),
+            bus_health: None,
+            channel_health: None,
+            costs: None,
+            protocols: Protocols::default(),
+            claim: Mutex::new(None),
+            leadership: None,
+            policy: None,
+            audiences,
+            warmed_at: Mutex::new(None),
+            region,
         }
     }
 
+    /// Take due notifications from another queue backend (`ServiceBuilder::queue_store`)
+    pub fn with_queue(mut self, queue: Arc<dyn QueueStore>) -> Self {
+        self.queue = queue;
+        self
+    }
+
+    /// Publish delivery events to the given channel (NATS, ... sinks subscribe to it)
+    pub fn with_events(mut self, events: EventSender) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Sign broadcast payloads (Bus + FCM) with this Ed25519 key
+    pub fn with_signer(mut self, signer: Arc<BroadcastSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Send attachments as URLs signed with this store's key (ATTACHMENT_STORE_URL)
+    pub fn with_attachments(mut self, attachments: Option<Arc<AttachmentSigner>>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
+    /// FCM client for sandbox devices (FCM_SANDBOX_*); tenants can override it
+    pub fn with_sandbox_fcm(mut self, fcm_sandbox: Option<Arc<FcmClient>>) -> Self {
+        self.tenants = self.tenants.with_sandbox_fcm(fcm_sandbox);
+        self
+    }
+
+    /// Developer sandbox (DEV_SANDBOX_TENANT): that tenant's pushes go to its mock FCM only
+    pub fn with_dev_sandbox(mut self, dev_sandbox: Option<Arc<DevSandbox>>) -> Self {
+        self.tenants = self.tenants.with_dev_sandbox(dev_sandbox);
+        self
+    }
+
+    /// Channels per priority (FALLBACK_CHAINS) for the router
+    pub fn with_fallback_chains(mut self, chains: FallbackChains) -> Self {
+        self.router = self.router.with_chains(chains);
+        self
+    }
+
+    /// Shutdown state shared with `/readyz` and `POST /admin/prestop`
+    pub fn with_drain(mut self, drain: Drain) -> Self {
+        self.shutdown = drain;
+        self
+    }
+
+    /// Skip the Bus while its health probe reports it down
+    pub fn with_bus_health(mut self, health: BusHealth) -> Self {
+        self.bus_health = Some(health);
+        self
+    }
+
+    /// Record channel outcomes, and send high priority straight to push while the Bus is degraded
+    pub fn with_channel_health(mut self, health: ChannelHealth) -> Self {
+        self.channel_health = Some(health);
+        self
+    }
+
+    /// Count deliveries per tenant and channel, and skip paid channels once a budget is spent
+    pub fn with_costs(mut self, costs: CostLedger) -> Self {
+        self.costs = Some(costs);
+        self
+    }
+
+    /// Protocol versions the Bus messages are published in (WS_PROTOCOLS)
+    pub fn with_protocols(mut self, protocols: Protocols) -> Self {
+        self.protocols = protocols;
+        self
+    }
+
+    /// Business hours per type (DELIVERY_WINDOWS) for the router
+    pub fn with_delivery_windows(mut self, windows: DeliveryWindows, default_timezone: Tz) -> Self {
+        self.router = self.router.with_windows(windows, default_timezone);
+        self
+    }
+
+    /// Actor/target variables for templates (TEMPLATE_ACTOR_QUERY / TEMPLATE_TARGET_QUERY)
+    pub fn with_template_lookups(mut self, lookups: Option<TemplateLookups>) -> Self {
+        self.templates = self.templates.with_lookups(lookups);
+        self
+    }
+
+    /// Claim only while holding the leader lease (LEADER_ELECTION)
+    pub fn with_leadership(mut self, leadership: Leadership) -> Self {
+        self.leadership = Some(leadership);
+        self
+    }
+
+    /// Let the content policy hook veto or rewrite every notification before delivery
+    pub fn with_policy(mut self, policy: Option<PolicyGate>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Resolve audiences with this resolver (validated at startup)
+    pub fn with_audiences(mut self, audiences: AudienceResolver) -> Self {
+        self.audiences = audiences;
+        self
+    }
+
+    /// The worker's device cache, for the API to invalidate (None when disabled)
+    pub fn device_cache(&self) -> Option<DeviceCache> {
+        self.devices.clone()
+    }
+
     /// Main worker loop - wakes on NOTIFY or timeout
-    #[instrument(skip(self, wake_rx), name = "worker_loop")]
-    pub async fn run(&self, mut wake_rx: mpsc::Receiver<()>) {
-        info!("═══════════════════════════════════════════════════════════");
-        info!("  NOTIFICATION WORKER STARTED");
-        info!("  Poll interval: {}s", self.config.worker_poll_interval_secs);
-        info!("  Batch size: {}", self.config.worker_batch_size);
-        info!("  Max retries: {}", self.config.max_retries);
-        info!("  WebSocket Bus: {}", if self.bus_client.is_some() { "ENABLED" } else { "DISABLED" });
-        info!("  FCM: {}", if self.fcm_client.is_some() { "ENABLED" } else { "DISABLED" });
-        info!("═══════════════════════════════════════════════════════════");
+    #[instrument(skip(self, wake), name = "worker_loop")]
+    pub async fn run(&self, wake: WakeSignal) {
+        // The configuration is in the `service_started` event (crate::status)
+        if self.config.is_simulated() {
+            warn!("🧪 DELIVERY MODE: SIMULATE (no FCM/Bus calls, no receipts)");
+        } else if !self.config.live_notification_types.is_empty() {
+            warn!(
+                live = ?self.config.live_notification_types,
+                "🧪 DELIVERY MODE: only LIVE_NOTIFICATION_TYPES are delivered, other types are simulated"
+            );
+        }
+        if let Some(faults) = &self.faults {
+            let chaos = faults.config();
+            warn!(
+                fcm_failure_rate = chaos.fcm_failure_rate,
+                bus_timeout_rate = chaos.bus_timeout_rate,
+                db_error_rate = chaos.db_error_rate,
+                latency_ms = chaos.latency_ms,
+                "💥 CHAOS MODE: injecting faults"
+            );
+        }
 
         let mut cycle_count: u64 = 0;
+        let mut failsafe = Duration::from_secs(self.config.worker_poll_interval_secs);
 
         loop {
             cycle_count += 1;
             trace!("───────────────────────────────────────────────────────────");
             trace!("Worker cycle #{} starting", cycle_count);
 
+            // Users whose app just came to the foreground go first
+            self.process_boosted(&wake).await;
+
             // Process all pending notifications
             let batch_start = Instant::now();
             self.process_all_pending().await;
@@ -80,27 +348,37 @@ impl NotificationWorker {
             );
 
             // Sleep until triggered or timeout
+            let idle = self.idle_timeout(&wake, &mut failsafe).await;
             debug!(
-                timeout_secs = self.config.worker_poll_interval_secs,
+                timeout_secs = idle.map(|idle| idle.as_secs()),
                 "Worker sleeping until NOTIFY or timeout"
             );
+            let timeout = async {
+                match idle {
+                    Some(idle) => tokio::time::sleep(idle).await,
+                    None => std::future::pending().await,
+                }
+            };
 
             let sleep_start = Instant::now();
             tokio::select! {
                 // Wake on NOTIFY signal
-                Some(_) = wake_rx.recv() => {
+                (first, signals) = wake.recv() => {
                     let sleep_duration = sleep_start.elapsed();
                     debug!(
                         slept_ms = sleep_duration.as_millis() as u64,
+                        urgent = first == Wake::Urgent,
+                        signals = signals,
                         "Worker WOKE: NOTIFY signal received"
                     );
                     trace!("Wake source: PostgreSQL NOTIFY trigger");
+                    self.coalesce_wakes(&wake, first, signals).await;
                 }
-                // Wake on timeout (failsafe)
-                _ = tokio::time::sleep(Duration::from_secs(self.config.worker_poll_interval_secs)) => {
+                // Wake on timeout (failsafe poll, or the next deliver_at)
+                _ = timeout => {
                     debug!(
-                        timeout_secs = self.config.worker_poll_interval_secs,
-                        "Worker WOKE: timeout reached (failsafe poll)"
+                        timeout_secs = idle.map(|idle| idle.as_secs()),
+                        "Worker WOKE: timeout reached"
                     );
                     trace!("Wake source: scheduled timeout");
                 }
@@ -108,6 +386,136 @@ impl NotificationWorker {
         }
     }
 
+    /// How long to sleep without a wake-up (None = until the next one)
+    ///
+    /// NOTIFY mode polls as a failsafe, see `failsafe_interval`. The replication source
+    /// can't miss an insert, so there the worker only sleeps until the earliest deferred
+    /// or retried notification is due. Rows that are already due but were left alone
+    /// (maintenance mode, another replica) fall back to the poll interval.
+    async fn idle_timeout(&self, wake: &WakeSignal, failsafe: &mut Duration) -> Option<Duration> {
+        let poll_interval = Duration::from_secs(self.config.worker_poll_interval_secs);
+        if self.config.wake_source != WakeSource::Replication {
+            let next = self.failsafe_interval(wake.is_listening(), *failsafe).await;
+            if next != *failsafe {
+                debug!(from_secs = failsafe.as_secs(), to_secs = next.as_secs(), "Failsafe poll interval changed");
+                metrics::gauge!("notifications_worker_poll_interval_seconds").set(next.as_secs_f64());
+                *failsafe = next;
+            }
+            return Some(next);
+        }
+        match self.queue.next_deliver_at().await {
+            Ok(None) => None,
+            Ok(Some(next)) => (next - Utc::now()).to_std().ok().or(Some(poll_interval)),
+            Err(e) => {
+                warn!(error = %e, "Failed to look up the next deliver_at, polling instead");
+                Some(poll_interval)
+            }
+        }
+    }
+
+    /// The failsafe poll interval for the next sleep in NOTIFY mode, from the current one
+    ///
+    /// While the listener is disconnected nothing else wakes the worker, so it polls every
+    /// WORKER_POLL_MIN_INTERVAL_SECS. While it is connected and nothing is unprocessed the
+    /// interval doubles up to WORKER_POLL_MAX_INTERVAL_SECS. Anything unprocessed (deferred
+    /// and retried rows are due without a NOTIFY) brings back WORKER_POLL_INTERVAL_SECS.
+    async fn failsafe_interval(&self, listening: bool, current: Duration) -> Duration {
+        let base = self.config.worker_poll_interval_secs;
+        let min = Duration::from_secs(self.config.worker_poll_min_interval_secs.min(base));
+        let max = Duration::from_secs(self.config.worker_poll_max_interval_secs.max(base));
+        let base = Duration::from_secs(base);
+        if !listening {
+            return min;
+        }
+        if max == base {
+            return base;
+        }
+        match self.queue.next_deliver_at().await {
+            Ok(None) => (current.max(base) * 2).min(max),
+            Ok(Some(_)) => base,
+            Err(e) => {
+                warn!(error = %e, "Failed to look up the next deliver_at, polling at the base interval");
+                base
+            }
+        }
+    }
+
+    /// Let a NOTIFY burst build up into one batch (WORKER_WAKE_DEBOUNCE_MS)
+    ///
+    /// Waits until the debounce window ends, WORKER_WAKE_MAX_SIGNALS signals arrived or an
+    /// urgent (high/critical) insert comes in. Pending rows are fetched afterwards, so
+    /// nothing is lost; the window only bounds the extra latency.
+    async fn coalesce_wakes(&self, wake: &WakeSignal, first: Wake, mut signals: u64) {
+        if self.config.worker_wake_debounce_ms == 0 || first == Wake::Urgent {
+            return;
+        }
+
+        let window = Duration::from_millis(self.config.worker_wake_debounce_ms);
+        let deadline = tokio::time::sleep(window);
+        tokio::pin!(deadline);
+        let start = Instant::now();
+
+        while signals < self.config.worker_wake_max_signals as u64 {
+            tokio::select! {
+                (next, folded) = wake.recv() => {
+                    signals += folded;
+                    if next == Wake::Urgent {
+                        trace!("Urgent NOTIFY during debounce, waking now");
+                        break;
+                    }
+                }
+                _ = &mut deadline => break,
+            }
+        }
+
+        debug!(
+            signals = signals,
+            waited_ms = start.elapsed().as_millis() as u64,
+            "NOTIFY signals coalesced"
+        );
+    }
+
+    /// Deliver the due notifications of users whose app came to the foreground
+    ///
+    /// Runs before the regular batch. Rows that became due without a NOTIFY (a deferral or
+    /// quiet hours that ended, failures left behind by a drain) would otherwise wait for the
+    /// failsafe poll; now they go out within a second of the app opening. Skipped whenever
+    /// the regular batch wouldn't deliver either.
+    #[instrument(skip_all, name = "process_boosted")]
+    async fn process_boosted(&self, wake: &WakeSignal) {
+        let boosted = wake.take_boosted();
+        if boosted.is_empty()
+            || self.config.consumption_mode == ConsumptionMode::Transactional
+            || self.shutdown.is_draining()
+            || self.leadership.as_ref().is_some_and(|leadership| !leadership.is_leader())
+            || self.maintenance_gate().await != MaintenanceGate::Open
+        {
+            return;
+        }
+
+        for (tenant_id, user_id) in boosted {
+            let due = match ForegroundQueries::fetch_due(&self.pool, &tenant_id, user_id, self.config.worker_batch_size).await {
+                Ok(due) => due,
+                Err(e) => {
+                    error!(error = %e, user_id = %user_id, "Failed to fetch notifications for foreground boost");
+                    continue;
+                }
+            };
+            if due.is_empty() {
+                continue;
+            }
+            metrics::counter!("notifications_foreground_boosted_total").increment(due.len() as u64);
+            let lane: Vec<&Notification> = due.iter().collect();
+            let results = self.process_lane(&lane).await;
+            info!(
+                user_id = %user_id,
+                due = due.len(),
+                delivered = results.iter().filter(|r| matches!(r, DeliveryResult::Bus | DeliveryResult::Push)).count(),
+                "Foreground boost processed"
+            );
+        }
+    }
+
     /// Process all pending notifications in batches
     #[instrument(skip(self), name = "process_all_pending")]
     async fn process_all_pending(&self) {
@@ -115,15 +523,78 @@ impl NotificationWorker {
         let mut total_bus = 0;
         let mut total_push = 0;
         let mut total_failed = 0;
+        let mut total_suppressed = 0;
+        let mut total_deferred = 0;
+        let mut total_expanded = 0;
+        let mut total_duplicates = 0;
+        let mut count = |result: &DeliveryResult| match result {
+            DeliveryResult::Bus => total_bus += 1,
+            DeliveryResult::Push => total_push += 1,
+            DeliveryResult::Failed => total_failed += 1,
+            DeliveryResult::Suppressed => total_suppressed += 1,
+            DeliveryResult::Deferred => total_deferred += 1,
+            DeliveryResult::Expanded => total_expanded += 1,
+            DeliveryResult::Duplicate => total_duplicates += 1,
+        };
         let overall_start = Instant::now();
 
         loop {
+            if self.shutdown.is_draining() {
+                debug!("Pod is draining, not claiming notifications");
+                break;
+            }
+            if self.leadership.as_ref().is_some_and(|leadership| !leadership.is_leader()) {
+                self.stand_by().await;
+                break;
+            }
+            let gate = self.maintenance_gate().await;
+            if gate == MaintenanceGate::Parked {
+                break;
+            }
+
+            if self.config.consumption_mode == ConsumptionMode::Transactional {
+                let Some(_in_flight) = self.shutdown.delivery() else {
+                    break;
+                };
+                let claim_start = Instant::now();
+                match self.process_claimed(gate == MaintenanceGate::Draining).await {
+                    Ok(Some(result)) => {
+                        count(&result);
+                        total_processed += 1;
+                        if gate == MaintenanceGate::Draining {
+                            self.throttle_drain(1, claim_start.elapsed()).await;
+                        }
+                        continue;
+                    }
+                    Ok(None) => {
+                        if gate == MaintenanceGate::Draining {
+                            self.finish_drain();
+                        }
+                    }
+                    Err(e) => error!(error = %e, "Failed to claim or commit notification"),
+                }
+                break;
+            }
+
             let fetch_start = Instant::now();
-            match NotificationQueries::fetch_unprocessed(&self.pool, self.config.worker_batch_size).await {
+            let fetched = async {
+                self.db_fault().await?;
+                self.queue
+                    .fetch_due(
+                        self.config.worker_batch_size,
+                        gate == MaintenanceGate::Draining,
+                        self.config.worker_fair_scheduling,
+                    )
+                    .await
+            };
+            match fetched.await {
                 Ok(notifications) if notifications.is_empty() => {
                     if total_processed == 0 {
                         trace!("No pending notifications in queue");
                     }
+                    if gate == MaintenanceGate::Draining {
+                        self.finish_drain();
+                    }
                     break;
                 }
                 Ok(notifications) => {
@@ -141,15 +612,10 @@ impl NotificationWorker {
                     }
 
                     let batch_start = Instant::now();
-                    for (i, notification) in notifications.iter().enumerate() {
-                        trace!("Processing {}/{} in batch", i + 1, batch_size);
-                        let result = self.process_one(notification.clone()).await;
-
-                        match result {
-                            DeliveryResult::Bus => total_bus += 1,
-                            DeliveryResult::Push => total_push += 1,
-                            DeliveryResult::Failed => total_failed += 1,
-                        }
+                    let lanes = self.lanes(&notifications);
+                    let results = futures::future::join_all(lanes.iter().map(|lane| self.process_lane(lane))).await;
+                    for result in results.iter().flatten() {
+                        count(result);
                         total_processed += 1;
                     }
 
@@ -160,6 +626,14 @@ impl NotificationWorker {
                         avg_ms = if batch_size > 0 { batch_duration.as_millis() as u64 / batch_size as u64 } else { 0 },
                         "Batch processed"
                     );
+
+                    if gate == MaintenanceGate::Draining {
+                        if (batch_size as i64) < self.config.worker_batch_size {
+                            self.finish_drain();
+                        } else {
+                            self.throttle_drain(batch_size, batch_duration).await;
+                        }
+                    }
                 }
                 Err(e) => {
                     error!(
@@ -181,6 +655,10 @@ impl NotificationWorker {
             info!("  Success via Bus: {}", total_bus);
             info!("  Success via Push: {}", total_push);
             info!("  Failed (will retry): {}", total_failed);
+            info!("  Suppressed (preferences): {}", total_suppressed);
+            info!("  Deferred (snooze): {}", total_deferred);
+            info!("  Expanded (topics): {}", total_expanded);
+            info!("  Duplicates (marked elsewhere): {}", total_duplicates);
             info!("  Total duration: {}ms", overall_duration.as_millis());
             info!("  Avg per notification: {}ms",
                 if total_processed > 0 { overall_duration.as_millis() / total_processed as u128 } else { 0 });
@@ -188,19 +666,250 @@ impl NotificationWorker {
         }
     }
 
+    /// Split a batch into WORKER_CONCURRENCY lanes by recipient, keeping the fetch order
+    fn lanes<'a>(&self, notifications: &'a [Notification]) -> Vec<Vec<&'a Notification>> {
+        let count = self.config.worker_concurrency.max(1);
+        let mut lanes = vec![Vec::new(); count];
+        for notification in notifications {
+            let mut hasher = DefaultHasher::new();
+            (&notification.tenant_id, notification.user_id).hash(&mut hasher);
+            lanes[(hasher.finish() % count as u64) as usize].push(notification);
+        }
+        lanes
+    }
+
+    /// Deliver one lane in order
+    ///
+    /// After a failure the user's later notifications in the lane are held: they stay
+    /// unprocessed and come back in the next batch behind the retried one, so nobody gets
+    /// a newer notification before an older one that is still being retried.
+    async fn process_lane(&self, lane: &[&Notification]) -> Vec<DeliveryResult> {
+        let mut results = Vec::with_capacity(lane.len());
+        let mut held = HashSet::new();
+
+        for (i, notification) in lane.iter().enumerate() {
+            let recipient = (notification.tenant_id.as_str(), notification.user_id);
+            if held.contains(&recipient) {
+                debug!(id = %notification.id, user_id = %notification.user_id, "Held behind an earlier failure for this user");
+                continue;
+            }
+            // The rest of the lane stays unprocessed for the other replicas
+            let Some(_in_flight) = self.shutdown.delivery() else {
+                info!(left = lane.len() - i, "Pod is draining, leaving the rest of the batch");
+                break;
+            };
+            trace!("Processing {}/{} in lane", i + 1, lane.len());
+            let result = self.process_one(notification).await;
+            self.emit_event(notification, &result);
+            self.record_outcome(notification, &result);
+            self.scrub_otp(notification, &result).await;
+            if matches!(result, DeliveryResult::Failed) {
+                held.insert(recipient);
+            }
+            results.push(result);
+        }
+        results
+    }
+
+    /// Claim, deliver and mark one notification in one transaction (CONSUMPTION_MODE=transactional)
+    ///
+    /// Marks, deferrals, topic copies and receipts are written through the claim, so they
+    /// commit together or not at all. If the pod dies before the commit the row is still
+    /// unprocessed and the next claim delivers it again: at least once, but never marked
+    /// without having been sent. None when nothing is due.
+    async fn process_claimed(&self, by_priority: bool) -> Result<Option<DeliveryResult>, sqlx::Error> {
+        self.db_fault().await?;
+        let mut tx = self.pool.begin().await?;
+        let claimed = NotificationQueries::claim_next(
+            &mut tx,
+            by_priority,
+            self.config.claim_timeout_secs,
+            self.region.as_ref(),
+        )
+        .await?;
+        let Some(notification) = claimed else {
+            return Ok(None);
+        };
+
+        *self.claim.lock().await = Some(tx);
+        let result = self.process_one(&notification).await;
+        let tx = self.claim.lock().await.take().expect("claim is held by process_claimed");
+
+        if let Err(e) = tx.commit().await {
+            // Delivered but not marked: the row will be claimed and sent again
+            error!(id = %notification.id, error = %e, "Failed to commit claim, notification will be redelivered");
+            return Err(e);
+        }
+        self.emit_event(&notification, &result);
+        self.record_outcome(&notification, &result);
+        self.scrub_otp(&notification, &result).await;
+        Ok(Some(result))
+    }
+
+    /// Standby (no leader lease): claim nothing, keep the tenant settings warm for a takeover
+    ///
+    /// The device cache follows its invalidations and the listener its NOTIFYs either way.
+    async fn stand_by(&self) {
+        let mut warmed_at = self.warmed_at.lock().await;
+        if warmed_at.is_some_and(|at| at.elapsed() < STANDBY_WARM_INTERVAL) {
+            trace!("Standby, not claiming notifications");
+            return;
+        }
+        *warmed_at = Some(Instant::now());
+        let tenants = self.tenants.warm().await;
+        debug!(tenants = tenants, "Standby, not claiming notifications; tenant settings warmed");
+    }
+
+    /// Read the maintenance flag; logs when it flips
+    ///
+    /// A failed read keeps the last known state.
+    async fn maintenance_gate(&self) -> MaintenanceGate {
+        let enabled = match MaintenanceQueries::get(&self.pool).await {
+            Ok(mode) => mode.enabled,
+            Err(e) => {
+                warn!(error = %e, "Failed to read maintenance mode, keeping last state");
+                self.parked.load(Ordering::Relaxed)
+            }
+        };
+
+        if enabled {
+            if !self.parked.swap(true, Ordering::Relaxed) {
+                warn!("🚧 MAINTENANCE MODE: deliveries parked, notifications are still accepted");
+                metrics::gauge!("notifications_maintenance_mode").set(1.0);
+            }
+            return MaintenanceGate::Parked;
+        }
+
+        if self.parked.swap(false, Ordering::Relaxed) {
+            info!(
+                rate_per_sec = self.config.maintenance_drain_rate_per_sec,
+                "Maintenance mode off, draining parked backlog in priority order"
+            );
+            metrics::gauge!("notifications_maintenance_mode").set(0.0);
+            self.draining.store(true, Ordering::Relaxed);
+        }
+        if self.draining.load(Ordering::Relaxed) {
+            MaintenanceGate::Draining
+        } else {
+            MaintenanceGate::Open
+        }
+    }
+
+    fn finish_drain(&self) {
+        if self.draining.swap(false, Ordering::Relaxed) {
+            info!("Parked backlog drained, back to normal delivery");
+        }
+    }
+
+    /// Hold a drained batch to MAINTENANCE_DRAIN_RATE_PER_SEC
+    async fn throttle_drain(&self, batch_size: usize, took: Duration) {
+        let rate = self.config.maintenance_drain_rate_per_sec;
+        if rate == 0 {
+            return;
+        }
+        let budget = Duration::from_secs_f64(batch_size as f64 / rate as f64);
+        if let Some(wait) = budget.checked_sub(took) {
+            trace!(wait_ms = wait.as_millis() as u64, "Throttling backlog drain");
+            tokio::time::sleep(wait).await;
+        }
+    }
+
     /// Process a single notification
     #[instrument(skip(self, notification), fields(
         id = %notification.id,
         user_id = %notification.user_id,
         notification_type = %notification.notification_type
     ))]
-    async fn process_one(&self, notification: Notification) -> DeliveryResult {
+    async fn process_one(&self, notification: &Notification) -> DeliveryResult {
         let id = notification.id;
         let user_id = notification.user_id;
 
+        // A bundle sent earlier in this batch already delivered it
+        if notification.bundled_at.is_some() && self.is_absorbed(id).await {
+            debug!(id = %id, "Delivered with an earlier bundle, skipping");
+            return DeliveryResult::Duplicate;
+        }
+
+        let tenant = self.tenants.resolve(&notification.tenant_id).await;
+        if !tenant.enabled {
+            info!(id = %id, tenant_id = %tenant.tenant_id, "⊘ Suppressed - tenant disabled");
+            self.mark_suppressed(id, "tenant_disabled").await;
+            return DeliveryResult::Suppressed;
+        }
+
+        // An announcement whose pin ran out (queued too long or re-driven later) is stale
+        if notification.pinned_until.is_some_and(|until| until <= Utc::now()) {
+            info!(id = %id, "⊘ Suppressed - announcement no longer pinned");
+            self.mark_suppressed(id, "pin_expired").await;
+            return DeliveryResult::Suppressed;
+        }
+
+        // So is one past its expiry or deadline: nobody wants yesterday's "live now" notice,
+        // and a late OTP code is worse than none
+        if let Some(expiry) = notification.expiry().filter(|at| *at <= Utc::now()) {
+            info!(id = %id, expiry = %expiry, "⊘ Suppressed - expired before delivery");
+            if notification.deadline == Some(expiry) {
+                metrics::counter!("notifications_deadline_missed_total").increment(1);
+            }
+            self.mark_suppressed(id, "expired").await;
+            return DeliveryResult::Suppressed;
+        }
+
+        // Rows inserted around the API, or queued before the schema changed
+        if let Err(e) = self.check_contract(notification).await {
+            warn!(id = %id, error = %e, "✗ Payload doesn't match its schema, giving up");
+            if self.mark_failure(notification, &e).await {
+                self.enqueue_receipt(notification, DeliveryStatus::Failed, None, Some(&e.to_string()), &[]).await;
+            }
+            return DeliveryResult::Failed;
+        }
+
+        // Topic notifications become one copy per subscriber, delivered like any other
+        if notification.is_topic_target() {
+            return self.process_topic(notification).await;
+        }
+
+        // So do organization and team audiences, one copy per member
+        if notification.is_audience_target() {
+            return self.process_audience(notification).await;
+        }
+
+        // The policy hook sees the content first: everything below delivers what it allowed
+        let reviewed = match &self.policy {
+            Some(policy) => match policy.review(notification).await {
+                Ok(Reviewed::Deliver(reviewed)) => Cow::Owned(*reviewed),
+                Ok(Reviewed::Vetoed(decision)) => {
+                    info!(id = %id, policy = %decision.policy, reason = ?decision.reason, "⊘ Suppressed - vetoed by the content policy");
+                    self.mark_suppressed(id, "policy_veto").await;
+                    let vetoed = Notification { policy: Some(decision), ..notification.clone() };
+                    self.enqueue_receipt(&vetoed, DeliveryStatus::Suppressed, None, None, &[]).await;
+                    return DeliveryResult::Suppressed;
+                }
+                Err(e) => {
+                    let e = NotificationError::from(e);
+                    warn!(id = %id, error = %e, "✗ Policy hook failed, not delivering unreviewed (fail closed)");
+                    if self.mark_failure(notification, &e).await {
+                        self.enqueue_receipt(notification, DeliveryStatus::Failed, None, Some(&e.to_string()), &[]).await;
+                    }
+                    return DeliveryResult::Failed;
+                }
+            },
+            None => Cow::Borrowed(notification),
+        };
+        let signed = self.sign_attachments(reviewed);
+        let timed = self.set_push_expiry(signed);
+        let notification = timed.as_ref();
+
         // Check for BROADCAST (UUID 00000000-0000-0000-0000-000000000000)
         if user_id.is_nil() {
-            return self.process_broadcast(notification).await;
+            return self.process_broadcast(notification, &tenant).await;
+        }
+
+        // Users on the suppression list (legal opt-out, uninstalled) get nothing at all
+        if self.is_user_suppressed(notification).await {
+            info!(id = %id, user_id = %user_id, "⊘ Suppressed - user on the suppression list");
+            self.mark_suppressed(id, "suppression_list").await;
+            return DeliveryResult::Suppressed;
         }
 
         let start = Instant::now();
@@ -208,20 +917,209 @@ impl NotificationWorker {
         trace!("══════════════════════════════════════════════════");
         trace!("PROCESSING NOTIFICATION");
         trace!("  id: {}", id);
+        trace!("  tenant_id: {}", notification.tenant_id);
         trace!("  user_id: {}", user_id);
         trace!("  type: {}", notification.notification_type);
-        trace!("  title: {:?}", notification.title);
-        trace!("  message: {:?}", notification.message);
+        let loggable = |text: &str| otp::loggable(&notification.notification_type, text).into_owned();
+        trace!("  title: {:?}", loggable(&notification.title));
+        trace!("  message: {:?}", notification.message.as_deref().map(loggable));
         trace!("  priority: {:?}", notification.priority);
         trace!("  deliver_at: {}", notification.deliver_at);
         trace!("  created_at: {}", notification.created_at);
         trace!("══════════════════════════════════════════════════");
 
-        // Try WebSocket Bus first if configured
-        if let Some(bus) = &self.bus_client {
+        // The client acked an earlier Bus delivery before its deadline (BUS_ACK_TIMEOUT_SECS)
+        if notification.bus_delivered_at.is_some() && notification.acked_at.is_some() {
+            info!(id = %id, user_id = %user_id, "✓ Delivered via WebSocket Bus (acked by the client)");
+            let delivery_id = self.recorded_bus_delivery(id).await.map(|recorded| recorded.delivery_id);
+            return self.mark_bus_delivered(notification, delivery_id).await;
+        }
+
+        // Respect user preferences (type opt-out + channel matrix) before any delivery
+        let mut channels = match self.router.route(notification).await {
+            Route::Deliver(channels) => channels,
+            Route::Suppress(reason) => {
+                info!(
+                    id = %id,
+                    user_id = %user_id,
+                    notification_type = %notification.notification_type,
+                    reason = reason,
+                    "⊘ Suppressed by user preferences"
+                );
+                self.mark_suppressed(id, reason).await;
+                return DeliveryResult::Suppressed;
+            }
+            Route::Defer { until, reason } => {
+                info!(
+                    id = %id,
+                    user_id = %user_id,
+                    until = %until,
+                    reason = reason,
+                    "⏸ Deferred by user preferences, delivery window or local send time"
+                );
+                if let Err(e) = on_claim!(self, queue.defer(id, until)) {
+                    error!(id = %id, error = %e, "Failed to defer notification");
+                }
+                return DeliveryResult::Deferred;
+            }
+        };
+
+        // Rapid-fire events of one type go out together (BUNDLE_WINDOW_SECS)
+        let bundle;
+        let notification = match self.bundle(notification).await {
+            Bundling::Send | Bundling::Flush(1) => notification,
+            Bundling::Hold(until) => {
+                info!(id = %id, user_id = %user_id, until = %until, "⧗ Held for a bundle");
+                if let Err(e) = on_claim!(self, |executor| BundleQueries::hold(executor, id, until)) {
+                    error!(id = %id, error = %e, "Failed to hold notification for its bundle");
+                }
+                metrics::counter!("notifications_bundle_held_total").increment(1);
+                return DeliveryResult::Deferred;
+            }
+            Bundling::Flush(count) => {
+                info!(id = %id, user_id = %user_id, count = count, "Delivering bundle");
+                metrics::histogram!("notifications_bundle_size").record(count as f64);
+                bundle = Notification { bundle_count: Some(count), ..notification.clone() };
+                &bundle
+            }
+        };
+
+        let over_budget = self.downgrade_over_budget(notification, &mut channels).await;
+
+        // Time-sensitive: only the channels that can still make the deadline
+        if !self.fit_deadline(notification, &mut channels) {
+            info!(id = %id, user_id = %user_id, "⊘ Suppressed - no channel fast enough for the deadline");
+            metrics::counter!("notifications_deadline_missed_total").increment(1);
+            self.mark_suppressed(id, "deadline_unreachable").await;
+            return DeliveryResult::Suppressed;
+        }
+
+        // Recipient locale is only needed when the text comes from the catalog
+        let user_locale = if notification.message_key.is_some() || notification.template_key.is_some() {
+            self.user_locale(&notification.tenant_id, user_id).await
+        } else {
+            None
+        };
+
+        // Devices fetched alongside the Bus publish (reused by the push fallback)
+        let mut prefetched_devices = None;
+        // First-ack types and OTP codes go out on every channel; the Bus reaching a device is enough to succeed
+        let first_ack = self.config.is_first_ack(&notification.notification_type)
+            || otp::is_otp(&notification.notification_type);
+        let mut rang_bus = false;
+        // Kept for last_error when push can't make up for it
+        let mut bus_error = None;
+        // The publish a Bus success is counted for
+        let mut bus_delivery = None;
+
+        // Try WebSocket Bus first if configured and allowed
+        if notification.bus_delivered_at.is_some() {
+            // The Bus reached a socket earlier but no ack came: the app may be gone, push decides
+            info!(id = %id, user_id = %user_id, "Bus delivery unconfirmed (no client ack), falling back to FCM");
+            metrics::counter!("notifications_bus_unconfirmed_total").increment(1);
+            self.record_attempt(notification, Channel::Bus, "unconfirmed", None).await;
+            if let Err(e) = BusDeliveryQueries::forget(&self.pool, id).await {
+                warn!(id = %id, error = %e, "Failed to drop the unconfirmed Bus publish");
+            }
+            rang_bus = true;
+        } else if notification.device_filter.is_some() {
+            // The Bus can't tell which app version a connection runs: targeted rows are push-only
+            debug!(user_id = %user_id, "Notification targets devices, trying FCM directly");
+        } else if !channels.contains(&Channel::Bus) {
+            debug!(
+                user_id = %user_id,
+                "Bus channel disabled by priority chain or user preference, trying FCM directly"
+            );
+        } else if self.config.simulates(&notification.notification_type) && self.bus_client.is_some() {
+            // Live connections are unknown in simulation: treat the user as offline so push runs too
+            let localized = self.render(notification, user_locale.as_deref(), Channel::Bus).await;
+            info!(id = %id, user_id = %user_id, "🧪 Simulated WebSocket Bus delivery, continuing with push");
+            self.record_attempt(&localized, Channel::Bus, "simulated", None).await;
+        } else if self.bus_health.as_ref().is_some_and(|health| !health.is_up()) {
+            // Don't wait for a publish to time out: the probe retries the Bus in the background
+            debug!(user_id = %user_id, "WebSocket Bus down (health probe), trying FCM directly");
+        } else if notification.priority.as_deref() == Some("high")
+            && channels.contains(&Channel::Push)
+            && tenant.has_fcm()
+            && self.channel_health.as_ref().is_some_and(|health| health.skip_bus())
+        {
+            // Slow or failing publishes would delay exactly the notifications that can't wait
+            debug!(id = %id, user_id = %user_id, "WebSocket Bus degraded, high priority goes straight to FCM");
+            metrics::counter!("notifications_adaptive_bus_skips_total").increment(1);
+        } else if let Some(bus) = self.bus_client.as_deref() {
             trace!("Attempting delivery via WebSocket Bus...");
 
-            match self.send_via_bus(bus, &notification).await {
+            let localized = self.render(notification, user_locale.as_deref(), Channel::Bus).await;
+            // An earlier run published but never got to the mark (crash, lost claim): don't publish again
+            let recorded = self.recorded_bus_delivery(id).await;
+            let delivery_id = recorded.as_ref().map_or_else(Uuid::now_v7, |recorded| recorded.delivery_id);
+            // Push is the fallback when the user is offline: look up devices while the publish is in flight
+            let prefetch = channels.contains(&Channel::Push) && tenant.has_fcm();
+            let publish = async {
+                match &recorded {
+                    Some(recorded) => Ok(recorded.delivered_to.max(0) as usize),
+                    None => {
+                        let published = Instant::now();
+                        let result = self.send_via_bus(bus, &tenant, &localized, delivery_id).await;
+                        self.record_channel(Channel::Bus, published, result.is_ok());
+                        result
+                    }
+                }
+            };
+            let (result, devices) = tokio::join!(publish, async {
+                if prefetch {
+                    Some(self.fetch_devices(notification).await)
+                } else {
+                    None
+                }
+            });
+            prefetched_devices = devices;
+            match &result {
+                _ if recorded.is_some() => {
+                    info!(id = %id, delivery_id = %delivery_id, "Bus publish recorded by an earlier run, not publishing again")
+                }
+                Ok(delivered_to) if *delivered_to > 0 => {
+                    self.record_attempt(&localized, Channel::Bus, "delivered", None).await
+                }
+                Ok(_) => self.record_attempt(&localized, Channel::Bus, "no_connection", None).await,
+                Err(e) => self.record_attempt(&localized, Channel::Bus, "failed", Some(&e.0)).await,
+            }
+            if matches!(result, Ok(delivered_to) if delivered_to > 0) {
+                bus_delivery = Some(delivery_id);
+            }
+
+            match result {
+                Ok(delivered_to) if delivered_to > 0 && first_ack => {
+                    // Ring every device: the first to ack dismisses the others
+                    debug!(
+                        id = %id,
+                        user_id = %user_id,
+                        delivered_to = delivered_to,
+                        "Delivered via WebSocket Bus, pushing to every device too (first-ack type)"
+                    );
+                    rang_bus = true;
+                }
+                Ok(delivered_to) if delivered_to > 0 && self.awaits_ack(&prefetched_devices) => {
+                    // Queued to a socket is not seen: push takes over unless the client acks in time
+                    let until = Utc::now() + chrono::Duration::seconds(self.config.bus_ack_timeout_secs as i64);
+                    let awaiting = on_claim!(self, queue.await_bus_ack(id, until));
+                    match awaiting {
+                        Ok(()) => {
+                            debug!(
+                                id = %id,
+                                user_id = %user_id,
+                                delivered_to = delivered_to,
+                                until = %until,
+                                "Delivered via WebSocket Bus, waiting for the client ack"
+                            );
+                            return DeliveryResult::Deferred;
+                        }
+                        Err(e) => {
+                            error!(id = %id, error = %e, "Failed to record Bus delivery, counting it as delivered");
+                            return self.mark_bus_delivered(notification, bus_delivery).await;
+                        }
+                    }
+                }
                 Ok(delivered_to) if delivered_to > 0 => {
                     let duration = start.elapsed();
                     info!(
@@ -231,8 +1129,7 @@ impl NotificationWorker {
                         duration_ms = duration.as_millis() as u64,
                         "✓ Delivered via WebSocket Bus"
                     );
-                    self.mark_success(id).await;
-                    return DeliveryResult::Bus;
+                    return self.mark_bus_delivered(notification, bus_delivery).await;
                 }
                 Ok(_) => {
                     // delivered_to == 0: User has no active connections
@@ -248,6 +1145,7 @@ impl NotificationWorker {
                         error = %e,
                         "WebSocket Bus delivery failed, falling back to FCM"
                     );
+                    bus_error = Some(e);
                 }
             }
         } else {
@@ -257,23 +1155,87 @@ impl NotificationWorker {
             );
         }
 
+        // Push disabled by preference: the row stays in the inbox, nothing left to try
+        if rang_bus && !channels.contains(&Channel::Push) {
+            return self.mark_bus_delivered(notification, bus_delivery).await;
+        }
+        if !channels.contains(&Channel::Push) {
+            // Low priorities can be Bus-only (FALLBACK_CHAINS): offline users see it in the inbox
+            let reason = if over_budget {
+                "budget_exceeded"
+            } else if self.router.chain_allows(notification, Channel::Push) {
+                "push_disabled"
+            } else {
+                "fallback_chain_exhausted"
+            };
+            info!(
+                id = %id,
+                user_id = %user_id,
+                priority = ?notification.priority,
+                reason = reason,
+                "⊘ Not delivered in real-time and push not in the route"
+            );
+            self.mark_suppressed(id, reason).await;
+            return DeliveryResult::Suppressed;
+        }
+
         // User offline or Bus failed/not configured - try push notification
         trace!("Attempting push notification delivery...");
-        match self.send_via_push(&notification).await {
-            Ok(device_count) => {
+        let pushed = self.send_via_push(&tenant, notification, user_locale.as_deref(), prefetched_devices).await;
+        if let (true, Err(e)) = (rang_bus, &pushed) {
+            let reason = match e {
+                NotPushed::Failed(e) => e.to_string(),
+                NotPushed::Held(_) => "held by device preferences".to_string(),
+            };
+            info!(id = %id, user_id = %user_id, push = %reason, "✓ Delivered via WebSocket Bus (no device rang)");
+            return self.mark_bus_delivered(notification, bus_delivery).await;
+        }
+        match pushed {
+            Ok(pushed) => {
                 let duration = start.elapsed();
                 info!(
                     id = %id,
                     user_id = %user_id,
-                    devices = device_count,
+                    devices = pushed.devices,
                     duration_ms = duration.as_millis() as u64,
                     "✓ Delivered via Push"
                 );
                 self.mark_success(id).await;
+                self.enqueue_receipt(
+                    notification,
+                    DeliveryStatus::Delivered,
+                    Some(Channel::Push),
+                    None,
+                    &pushed.message_ids,
+                )
+                .await;
                 DeliveryResult::Push
             }
-            Err(e) => {
+            Err(NotPushed::Held(DeviceHold::Quiet { until })) => {
+                info!(id = %id, user_id = %user_id, until = %until, "⏸ Deferred - every device is in its quiet hours");
+                if let Err(e) = on_claim!(self, queue.defer(id, until)) {
+                    error!(id = %id, error = %e, "Failed to defer notification");
+                }
+                DeliveryResult::Deferred
+            }
+            Err(NotPushed::Held(DeviceHold::NotTargeted)) => {
+                info!(id = %id, user_id = %user_id, "⊘ No device matches the notification's device_filter");
+                self.mark_suppressed(id, "device_not_targeted").await;
+                DeliveryResult::Suppressed
+            }
+            Err(NotPushed::Held(DeviceHold::TypeDisabled)) => {
+                info!(
+                    id = %id,
+                    user_id = %user_id,
+                    notification_type = %notification.notification_type,
+                    "⊘ Not delivered in real-time and the type is disabled on every device"
+                );
+                self.mark_suppressed(id, "device_type_disabled").await;
+                DeliveryResult::Suppressed
+            }
+            Err(NotPushed::Failed(e)) => {
                 let duration = start.elapsed();
+                let e = e.with_bus(bus_error);
                 warn!(
                     id = %id,
                     user_id = %user_id,
@@ -281,63 +1243,129 @@ impl NotificationWorker {
                     duration_ms = duration.as_millis() as u64,
                     "✗ Delivery failed"
                 );
-                self.mark_failure(id, &e).await;
+                if self.mark_failure(notification, &e).await {
+                    self.enqueue_receipt(notification, DeliveryStatus::Failed, None, Some(&e.to_string()), &[]).await;
+                }
                 DeliveryResult::Failed
             }
         }
     }
 
+    /// Wait for a client ack after a Bus delivery (BUS_ACK_TIMEOUT_SECS)?
+    ///
+    /// Only when there is a push fallback to take over: the devices were fetched
+    /// alongside the publish and at least one exists.
+    fn awaits_ack(&self, prefetched_devices: &Option<Result<Arc<Vec<UserDevice>>, DbError>>) -> bool {
+        self.config.bus_ack_timeout_secs > 0 && matches!(prefetched_devices, Some(Ok(devices)) if !devices.is_empty())
+    }
+
     /// Process a broadcast notification (User ID 0000...)
-    #[instrument(skip(self, notification), fields(id = %notification.id))]
-    async fn process_broadcast(&self, notification: Notification) -> DeliveryResult {
+    #[instrument(skip(self, notification, tenant), fields(id = %notification.id, tenant_id = %tenant.tenant_id))]
+    async fn process_broadcast(&self, notification: &Notification, tenant: &TenantContext) -> DeliveryResult {
         info!("📢 PROCESSING BROADCAST NOTIFICATION {}", notification.id);
+        if self.is_duplicate_broadcast(notification).await {
+            return DeliveryResult::Suppressed;
+        }
+        if self.config.broadcast_fanout == BroadcastFanout::PerUser {
+            return self.start_broadcast_fanout(notification).await;
+        }
+        // Topic sends can't be rendered per recipient: use the default locale
+        let bus_notification = self.render(notification, None, Channel::Bus).await;
+        let push_notification = self.render(notification, None, Channel::Push).await;
         let start = Instant::now();
         let mut bus_success = false;
         let mut push_success = false;
 
-        // 1. Broadcast via WebSocket Bus (Topic: "global_notifications")
-        if let Some(bus) = &self.bus_client {
-            // Create envelope for topic "global_notifications"
-            let envelope = BusEnvelope::new("global_notifications", "broadcast")
-                .with_payload(serde_json::json!({
-                    "type": "broadcast",
-                    "id": notification.id,
-                    "title": notification.title,
-                    "message": notification.message,
-                    "payload": notification.payload,
-                    "created_at": notification.created_at
-                }));
-
-            match bus.publish(&envelope).await {
-                Ok(response) => {
-                    info!(
-                        id = %notification.id,
-                        delivered_to = response.delivered_to,
-                        topic = "global_notifications",
-                        "✓ Broadcast published to WebSocket Bus"
-                    );
-                    bus_success = true;
-                }
-                Err(e) => {
-                    error!(error = %e, "Failed to publish broadcast to WebSocket Bus");
+        // 1. Broadcast via WebSocket Bus (Topic: "global_notifications", tenant-prefixed)
+        let topic = tenant.topic("global_notifications");
+        if let Some(bus) = self.bus_client.as_deref() {
+            let mut payload = bus_notification.bus_broadcast_payload();
+            if let Some(signer) = &self.signer {
+                payload["signature"] = serde_json::json!(signer.sign(&bus_notification));
+            }
+            payload_sink::record("bus", || {
+                serde_json::json!({
+                    "topic": topic,
+                    "event_type": "broadcast",
+                    "notification_id": notification.id,
+                    "payload": payload,
+                })
+            });
+
+            if self.config.simulates(&notification.notification_type) {
+                info!(id = %notification.id, topic = %topic, "🧪 Simulated broadcast to WebSocket Bus");
+                self.record_attempt(&bus_notification, Channel::Bus, "simulated", None).await;
+                bus_success = true;
+            } else {
+                let published = match self.bus_fault().await {
+                    Ok(()) => self
+                        .protocols
+                        .publish(bus, &topic, "broadcast", &payload)
+                        .await
+                        .map_err(|e| BusError(e.to_string())),
+                    Err(e) => Err(e),
+                };
+                match published {
+                    Ok(delivered_to) => {
+                        info!(
+                            id = %notification.id,
+                            delivered_to = delivered_to,
+                            topic = %topic,
+                            "✓ Broadcast published to WebSocket Bus"
+                        );
+                        self.record_attempt(&bus_notification, Channel::Bus, "delivered", None).await;
+                        bus_success = true;
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to publish broadcast to WebSocket Bus");
+                        self.record_attempt(&bus_notification, Channel::Bus, "failed", Some(&e.0)).await;
+                    }
                 }
             }
         }
 
-        // 2. Broadcast via FCM (Topic: "all")
-        if let Some(fcm) = &self.fcm_client {
-            // Use send_to_topic("all", ...)
-            match fcm.send_to_topic("all", &notification).await {
-                Ok(_) => {
-                    info!(
-                        id = %notification.id,
-                        topic = "all",
-                        "✓ FCM broadcast sent to topic 'all'"
-                    );
+        // 2. Broadcast via FCM (Topic: "all" in the tenant's Firebase project)
+        if tenant.fcm.is_some() || self.config.simulates(&notification.notification_type) {
+            // FCM data values are strings: the signature travels as flat fields
+            let signature_data = match &self.signer {
+                Some(signer) => {
+                    let signature = signer.sign(&push_notification);
+                    vec![
+                        ("signature_alg", signature.alg.to_string()),
+                        ("signature_key_id", signature.key_id),
+                        ("signed_content", signature.signed_content),
+                        ("signature", signature.signature),
+                    ]
+                }
+                None => Vec::new(),
+            };
+            // Simulation doesn't need FCM credentials
+            match tenant.fcm.as_ref().filter(|_| !self.config.simulates(&notification.notification_type)) {
+                None => {
+                    info!(id = %notification.id, topic = "all", "🧪 Simulated FCM broadcast");
+                    self.record_attempt(&push_notification, Channel::Push, "simulated", None).await;
                     push_success = true;
                 }
-                Err(e) => {
-                    error!(error = %e, "Failed to send FCM broadcast");
+                Some(fcm) => {
+                    let sent = match self.fcm_fault().await {
+                        Ok(()) => fcm.send_to_topic("all", &push_notification, &signature_data).await,
+                        Err(e) => Err(e),
+                    };
+                    match sent {
+                        Ok(_) => {
+                            info!(
+                                id = %notification.id,
+                                topic = "all",
+                                "✓ FCM broadcast sent to topic 'all'"
+                            );
+                            self.record_attempt(&push_notification, Channel::Push, "delivered", None).await;
+                            push_success = true;
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to send FCM broadcast");
+                            self.record_attempt(&push_notification, Channel::Push, "failed", Some(&e.to_string())).await;
+                        }
+                    }
                 }
             }
         } else {
@@ -364,46 +1392,171 @@ impl NotificationWorker {
         }
     }
 
+    /// Expand a topic notification into per-subscriber copies
+    ///
+    /// The copies are picked up by the next fetch and go through preferences, quiet hours
+    /// and the channel fallbacks per user.
+    #[instrument(skip(self, notification), fields(id = %notification.id, topic = ?notification.topic))]
+    async fn process_topic(&self, notification: &Notification) -> DeliveryResult {
+        let start = Instant::now();
+
+        match on_claim!(self, |executor| TopicQueries::expand(executor, notification.id)) {
+            Ok(created) => {
+                info!(
+                    id = %notification.id,
+                    topic = notification.topic.as_deref().unwrap_or_default(),
+                    subscribers = created,
+                    duration_ms = start.elapsed().as_millis() as u64,
+                    "✓ Topic notification expanded"
+                );
+                metrics::counter!("notifications_topic_fanout_total").increment(created);
+                DeliveryResult::Expanded
+            }
+            Err(e) => {
+                error!(id = %notification.id, error = %e, "Failed to expand topic notification");
+                self.mark_failure(notification, &DbError::new("Topic expansion failed", e).into()).await;
+                DeliveryResult::Failed
+            }
+        }
+    }
+
+    /// Fan an `org:` / `team:` notification out to the audience's members
+    ///
+    /// The members are resolved before the claim's transaction is used, so a slow
+    /// AUDIENCE_MEMBERS_QUERY doesn't hold the row lock any longer than the insert needs.
+    async fn process_audience(&self, notification: &Notification) -> DeliveryResult {
+        let start = Instant::now();
+        let raw = notification.audience.as_deref().unwrap_or_default();
+        let audience = match Audience::parse(raw) {
+            Ok(audience) => audience,
+            Err(e) => {
+                warn!(id = %notification.id, audience = raw, error = %e, "✗ Invalid audience, giving up");
+                let reason = e.to_string();
+                if self.mark_failure(notification, &e.into()).await {
+                    self.enqueue_receipt(notification, DeliveryStatus::Failed, None, Some(&reason), &[]).await;
+                }
+                return DeliveryResult::Failed;
+            }
+        };
+
+        let members = match self.audiences.members(&notification.tenant_id, &audience).await {
+            Ok(members) => members,
+            Err(e) => {
+                error!(id = %notification.id, audience = raw, error = %e, "Failed to resolve audience members");
+                self.mark_failure(notification, &DbError::new("Audience lookup failed", e).into()).await;
+                return DeliveryResult::Failed;
+            }
+        };
+
+        match on_claim!(self, |executor| AudienceQueries::expand(executor, notification.id, &members)) {
+            Ok(created) => {
+                info!(
+                    id = %notification.id,
+                    audience = raw,
+                    members = created,
+                    duration_ms = start.elapsed().as_millis() as u64,
+                    "✓ Audience notification expanded"
+                );
+                metrics::counter!("notifications_audience_fanout_total", "scope" => audience.scope()).increment(created);
+                DeliveryResult::Expanded
+            }
+            Err(e) => {
+                error!(id = %notification.id, error = %e, "Failed to expand audience notification");
+                self.mark_failure(notification, &DbError::new("Audience expansion failed", e).into()).await;
+                DeliveryResult::Failed
+            }
+        }
+    }
+
+    /// Hand a broadcast to the per-user fan-out (BROADCAST_FANOUT=per_user)
+    ///
+    /// The copies are released by `campaigns::BroadcastRunner`; this row is done.
+    async fn start_broadcast_fanout(&self, notification: &Notification) -> DeliveryResult {
+        match on_claim!(self, |executor| BroadcastQueries::start_fanout(
+            executor,
+            notification.id,
+            &notification.tenant_id
+        )) {
+            Ok(recipients) => {
+                info!(
+                    id = %notification.id,
+                    tenant_id = %notification.tenant_id,
+                    recipients = recipients,
+                    "📢 Broadcast fan-out started"
+                );
+                DeliveryResult::Expanded
+            }
+            Err(e) => {
+                error!(id = %notification.id, error = %e, "Failed to start broadcast fan-out");
+                self.mark_failure(notification, &DbError::new("Broadcast fan-out failed", e).into()).await;
+                DeliveryResult::Failed
+            }
+        }
+    }
+
     /// Send full notification via WebSocket Bus
-    #[instrument(skip(self, bus, notification), fields(
+    #[instrument(skip(self, bus, tenant, notification), fields(
         id = %notification.id,
         user_id = %notification.user_id
     ))]
-    async fn send_via_bus(&self, bus: &BusClient, notification: &Notification) -> Result<usize, String> {
+    async fn send_via_bus(
+        &self,
+        bus: &dyn RealtimeBus,
+        tenant: &TenantContext,
+        notification: &Notification,
+        delivery_id: Uuid,
+    ) -> Result<usize, BusError> {
         let start = Instant::now();
 
         // Create full notification envelope for direct client caching
-        let envelope = BusEnvelope::new("notifications", "notification")
-            .with_payload(serde_json::json!({
-                "id": notification.id,
+        let mut payload = if self.config.bus_protobuf {
+            notification.bus_payload_protobuf()
+        } else {
+            notification.bus_payload()
+        };
+        payload["delivery_id"] = serde_json::json!(delivery_id);
+        let topic = tenant.topic("notifications");
+        let redacted = otp::is_otp(&notification.notification_type);
+        payload_sink::record("bus", || {
+            serde_json::json!({
+                "topic": topic,
+                "event_type": "notification",
                 "user_id": notification.user_id,
-                "actor_user_id": notification.actor_user_id,
-                "notification_type": notification.notification_type,
-                "target_type": notification.target_type,
-                "target_id": notification.target_id,
-                "title": notification.title,
-                "message": notification.message,
-                "payload": notification.payload,
-                "deep_link": notification.deep_link,
-                "priority": notification.priority,
-                "status": "unread",
-                "created_at": notification.created_at
-            }));
-
-        trace!("notification envelope created: {:?}", envelope);
+                "notification_id": notification.id,
+                "payload": if redacted { serde_json::json!(otp::REDACTED) } else { payload.clone() },
+            })
+        });
+        if !redacted {
+            trace!("notification payload created: {:?}", payload);
+        }
         trace!("Publishing full notification to user {} via WebSocket Bus...", notification.user_id);
 
-        match bus.publish_to_user(notification.user_id, &envelope).await {
-            Ok(response) => {
+        if let Err(e) = self.bus_fault().await {
+            warn!(user_id = %notification.user_id, error = %e, "Failed to publish to WebSocket Bus");
+            return Err(e);
+        }
+
+        // The worker's bus is a UserBatchingBus: concurrent lanes share one request, and
+        // this user's own connection count still comes back for the push fallback
+        match self.protocols.publish_to_user(bus, notification.user_id, &topic, "notification", &payload).await {
+            Ok(delivered_to) => {
                 let duration = start.elapsed();
                 debug!(
                     id = %notification.id,
                     user_id = %notification.user_id,
-                    delivered_to = response.delivered_to,
+                    delivered_to = delivered_to,
                     duration_ms = duration.as_millis() as u64,
                     "Full notification published via Bus"
                 );
-                Ok(response.delivered_to)
+                if delivered_to > 0 {
+                    let connections = i32::try_from(delivered_to).unwrap_or(i32::MAX);
+                    let region = self.config.region.as_deref();
+                    let recorded = BusDeliveryQueries::record(&self.pool, delivery_id, notification.id, connections, region);
+                    if let Err(e) = recorded.await {
+                        warn!(id = %notification.id, error = %e, "Failed to record Bus publish, a retry publishes again");
+                    }
+                }
+                Ok(delivered_to)
             }
             Err(e) => {
                 let duration = start.elapsed();
@@ -413,53 +1566,106 @@ impl NotificationWorker {
                     duration_ms = duration.as_millis() as u64,
                     "Failed to publish to WebSocket Bus"
                 );
-                Err(e.to_string())
+                Err(BusError(e.to_string()))
             }
         }
     }
 
     /// Send push notification via FCM
-    #[instrument(skip(self, notification), fields(
+    ///
+    /// `prefetched_devices` is the device lookup that ran alongside the Bus publish, if any.
+    /// Returns how many devices got it and the message names FCM assigned.
+    #[instrument(skip(self, tenant, notification, prefetched_devices), fields(
         id = %notification.id,
         user_id = %notification.user_id
     ))]
-    async fn send_via_push(&self, notification: &Notification) -> Result<usize, String> {
+    async fn send_via_push(
+        &self,
+        tenant: &TenantContext,
+        notification: &Notification,
+        user_locale: Option<&str>,
+        prefetched_devices: Option<Result<Arc<Vec<UserDevice>>, DbError>>,
+    ) -> Result<Pushed, NotPushed> {
         let start = Instant::now();
 
-        let Some(fcm) = &self.fcm_client else {
+        // Simulation doesn't need FCM credentials
+        if !tenant.has_fcm() && !self.config.simulates(&notification.notification_type) {
             debug!("FCM client not configured, cannot send push");
-            return Err("FCM not configured".to_string());
-        };
+            return Err(PushError::NotConfigured.into());
+        }
 
         // Get user's devices
-        trace!("Fetching FCM devices for user {}", notification.user_id);
-        let devices = NotificationQueries::get_user_devices(&self.pool, notification.user_id)
-            .await
-            .map_err(|e| {
-                error!(error = %e, "Failed to fetch user devices from database");
-                format!("Failed to get devices: {}", e)
-            })?;
+        let devices = match prefetched_devices {
+            Some(devices) => {
+                trace!("Using devices prefetched during the Bus attempt");
+                devices?
+            }
+            None => self.fetch_devices(notification).await?,
+        };
 
         if devices.is_empty() {
             debug!(
                 user_id = %notification.user_id,
                 "No registered FCM devices for user"
             );
-            return Err("No registered devices".to_string());
+            return Err(PushError::NoDevices.into());
+        }
+
+        // Targeting, then device preferences: quiet hours and enabled types per device
+        let filter = match notification.device_filter.as_deref().map(DeviceFilter::parse) {
+            Some(Ok(filter)) => Some(filter),
+            Some(Err(e)) => {
+                warn!(id = %notification.id, error = %e, "Invalid device_filter, no device matches");
+                return Err(NotPushed::Held(DeviceHold::NotTargeted));
+            }
+            None => None,
+        };
+        let now = Utc::now();
+        let mut holds = Vec::new();
+        let eligible: Vec<&UserDevice> = devices
+            .iter()
+            .filter(|device| match device_hold(device, notification, filter.as_ref(), now) {
+                Some(hold) => {
+                    holds.push(hold);
+                    false
+                }
+                None => true,
+            })
+            .collect();
+        if eligible.is_empty() {
+            let quiet_until = holds
+                .iter()
+                .filter_map(|hold| match hold {
+                    DeviceHold::Quiet { until } => Some(*until),
+                    _ => None,
+                })
+                .min();
+            let hold = match quiet_until {
+                Some(until) => DeviceHold::Quiet { until },
+                None if holds.contains(&DeviceHold::TypeDisabled) => DeviceHold::TypeDisabled,
+                None => DeviceHold::NotTargeted,
+            };
+            return Err(NotPushed::Held(hold));
         }
 
         trace!(
-            device_count = devices.len(),
+            device_count = eligible.len(),
+            held = holds.len(),
             "Found {} FCM devices, sending push to each",
-            devices.len()
+            eligible.len()
         );
 
         let mut success_count = 0;
         let mut invalid_count = 0;
         let mut error_count = 0;
         let mut last_error = None;
+        let mut delivered_tokens = Vec::new();
+        let mut message_ids = Vec::new();
+        // Rendered once per locale, serialized once per locale and declared capabilities
+        let mut rendered: HashMap<Option<String>, Cow<'_, Notification>> = HashMap::new();
+        let mut prepared: HashMap<(Option<String>, DeviceCapabilities), PreparedPush> = HashMap::new();
 
-        for (i, device) in devices.iter().enumerate() {
+        for (i, device) in eligible.iter().enumerate() {
             let device_start = Instant::now();
             let token_preview = mask_token(&device.fcm_token);
 
@@ -469,11 +1675,60 @@ impl NotificationWorker {
                 token = %token_preview,
                 "Sending FCM push to device {}/{}",
                 i + 1,
-                devices.len()
+                eligible.len()
             );
 
-            match fcm.send(&device.fcm_token, notification).await {
-                Ok(()) => {
+            // Device locale wins over the user setting (push is rendered per device locale)
+            let locale = device.locale.as_deref().or(user_locale).map(str::to_string);
+            if !rendered.contains_key(&locale) {
+                let localized = self.render(notification, locale.as_deref(), Channel::Push).await;
+                rendered.insert(locale.clone(), localized);
+            }
+            let localized = &rendered[&locale];
+            let capabilities = device.capabilities();
+            let push = prepared
+                .entry((locale, capabilities))
+                .or_insert_with(|| FcmClient::prepare_for(localized, &capabilities));
+            if self.config.simulates(&notification.notification_type) {
+                info!(device_type = %device.device_type, token = %token_preview, "🧪 Simulated FCM push");
+                self.record_attempt(localized, Channel::Push, "simulated", None).await;
+                success_count += 1;
+                continue;
+            }
+            // Dev builds go through the sandbox project
+            let environment = push_environment(device, self.config.push_environment);
+            let result = match tenant.fcm_for(environment) {
+                Some(fcm) => {
+                    let sent = Instant::now();
+                    let result = match self.fcm_fault().await {
+                        Ok(()) => fcm.send_prepared(&device.fcm_token, push).await,
+                        Err(e) => Err(e),
+                    };
+                    // A dead token or a refused message is FCM answering, not FCM failing
+                    let answered = match &result {
+                        Err(e) => matches!(e, FcmError::InvalidToken) || e.is_rejected_message(),
+                        Ok(_) => true,
+                    };
+                    self.record_channel(Channel::Push, sent, answered);
+                    result
+                }
+                None => Err(FcmError::NotInitialized),
+            };
+            match &result {
+                Ok(message_id) => {
+                    self.record_provider_attempt(localized, Channel::Push, "delivered", None, message_id.as_deref())
+                        .await
+                }
+                Err(FcmError::InvalidToken) => {
+                    self.record_attempt(localized, Channel::Push, "invalid_token", None).await
+                }
+                Err(e) => {
+                    self.record_attempt(localized, Channel::Push, "failed", Some(&e.to_string())).await
+                }
+            }
+
+            match result {
+                Ok(message_id) => {
                     let device_duration = device_start.elapsed();
                     debug!(
                         device_index = i + 1,
@@ -483,6 +1738,8 @@ impl NotificationWorker {
                         "✓ FCM push sent successfully"
                     );
                     success_count += 1;
+                    delivered_tokens.push(device.fcm_token.clone());
+                    message_ids.extend(message_id);
                 }
                 Err(FcmError::InvalidToken) => {
                     warn!(
@@ -494,6 +1751,10 @@ impl NotificationWorker {
                     if let Err(e) = NotificationQueries::remove_device(&self.pool, &device.fcm_token).await {
                         error!(error = %e, "Failed to remove invalid FCM token");
                     }
+                    if let Some(cache) = &self.devices {
+                        cache.invalidate(&notification.tenant_id, notification.user_id).await;
+                    }
+                    self.record_unregistered(&device.fcm_token).await;
                 }
                 Err(e) => {
                     let device_duration = device_start.elapsed();
@@ -505,7 +1766,7 @@ impl NotificationWorker {
                         "✗ FCM push failed"
                     );
                     error_count += 1;
-                    last_error = Some(e.to_string());
+                    last_error = Some(e);
                 }
             }
         }
@@ -513,19 +1774,594 @@ impl NotificationWorker {
         let total_duration = start.elapsed();
 
         debug!(
-            total_devices = devices.len(),
+            total_devices = eligible.len(),
             success = success_count,
             invalid_tokens = invalid_count,
             errors = error_count,
             duration_ms = total_duration.as_millis() as u64,
             "FCM push batch complete"
         );
+        if !delivered_tokens.is_empty() {
+            if let Err(e) = DeviceQueries::touch(&self.pool, &delivered_tokens).await {
+                warn!(error = %e, "Failed to update device last_seen_at");
+            }
+        }
 
         if success_count > 0 {
-            Ok(success_count)
+            Ok(Pushed { devices: success_count, message_ids })
         } else {
-            Err(last_error.unwrap_or_else(|| "All push attempts failed".to_string()))
+            let error = match last_error {
+                Some(e) => NotificationError::from(e),
+                None if invalid_count > 0 => PushError::InvalidTokens.into(),
+                None => PushError::AllFailed.into(),
+            };
+            Err(error.into())
+        }
+    }
+
+    /// FCM devices of the notification's user (tenant-scoped, cached briefly)
+    async fn fetch_devices(&self, notification: &Notification) -> Result<Arc<Vec<UserDevice>>, DbError> {
+        if let Some(cache) = &self.devices {
+            if let Some(devices) = cache.get(&notification.tenant_id, notification.user_id).await {
+                return Ok(devices);
+            }
+        }
+
+        trace!("Fetching FCM devices for user {}", notification.user_id);
+        let devices = async {
+            self.db_fault().await?;
+            NotificationQueries::get_user_devices(&self.pool, &notification.tenant_id, notification.user_id).await
+        };
+        let devices = Arc::new(devices.await.map_err(|e| {
+            error!(error = %e, "Failed to fetch user devices from database");
+            DbError::new("Failed to get devices", e)
+        })?);
+
+        // No devices isn't cached: a first registration must be picked up right away
+        if let Some(cache) = self.devices.as_ref().filter(|_| !devices.is_empty()) {
+            cache.insert(&notification.tenant_id, notification.user_id, devices.clone()).await;
         }
+        Ok(devices)
+    }
+
+    /// The notification with its attachments signed into fresh URLs for this delivery
+    ///
+    /// Signed per attempt, so a retry or resend never carries a URL that expired in the queue.
+    fn sign_attachments<'a>(&self, notification: Cow<'a, Notification>) -> Cow<'a, Notification> {
+        let attachments = notification.attachment_keys();
+        if attachments.is_empty() {
+            return notification;
+        }
+        let Some(signer) = &self.attachments else {
+            warn!(id = %notification.id, "Attachments not sent: ATTACHMENT_STORE_URL is not set");
+            metrics::counter!("notifications_attachments_unsigned_total").increment(1);
+            return notification;
+        };
+        let mut signed = notification.into_owned();
+        signed.media = signer.sign_all(&attachments, Utc::now());
+        Cow::Owned(signed)
+    }
+
+    /// The notification with the time its pushes stop waiting at FCM/APNs for an offline device
+    ///
+    /// `expires_at` or `deadline` (the earlier) when the producer set one; low priority otherwise
+    /// gets PUSH_LOW_PRIORITY_TTL_SECS from this attempt. Everything else keeps the provider default.
+    fn set_push_expiry<'a>(&self, notification: Cow<'a, Notification>) -> Cow<'a, Notification> {
+        let ttl = self.config.push_low_priority_ttl_secs;
+        let expires_at = notification.expiry().or_else(|| {
+            (notification.is_low_priority() && ttl > 0).then(|| Utc::now() + chrono::Duration::seconds(ttl as i64))
+        });
+        let Some(expires_at) = expires_at else {
+            return notification;
+        };
+        let mut timed = notification.into_owned();
+        timed.push_expires_at = Some(expires_at);
+        Cow::Owned(timed)
+    }
+
+    /// The notification with title/message rendered for a locale and channel
+    ///
+    /// Precedence: template_key (DB template) → message_key (Fluent) → literal text.
+    /// Literal-text notifications are borrowed, not copied.
+    async fn render<'a>(
+        &self,
+        notification: &'a Notification,
+        locale: Option<&str>,
+        channel: Channel,
+    ) -> Cow<'a, Notification> {
+        let rendered = self.render_base(notification, locale, channel).await;
+        self.apply_experiment(rendered).await
+    }
+
+    async fn render_base<'a>(
+        &self,
+        notification: &'a Notification,
+        locale: Option<&str>,
+        channel: Channel,
+    ) -> Cow<'a, Notification> {
+        if let Some(template_key) = &notification.template_key {
+            match self.templates.render(notification, template_key, locale, channel.as_str()).await {
+                Ok(rendered_template) => {
+                    let mut rendered = notification.clone();
+                    rendered.title = rendered_template.text.title;
+                    rendered.message = Some(rendered_template.text.body);
+                    rendered.template_version = Some(rendered_template.version);
+                    return Cow::Owned(rendered);
+                }
+                Err(e) => {
+                    warn!(
+                        id = %notification.id,
+                        template_key = %template_key,
+                        error = %e,
+                        "Template rendering failed, falling back"
+                    );
+                }
+            }
+        }
+
+        self.localizer.localize(notification, locale)
+    }
+
+    /// Swap in the recipient's variant when the template or campaign has a running experiment
+    ///
+    /// Broadcasts have no single recipient to assign and are never part of an experiment.
+    async fn apply_experiment<'a>(&self, rendered: Cow<'a, Notification>) -> Cow<'a, Notification> {
+        let campaign_id = rendered.campaign_id();
+        if rendered.user_id.is_nil() || (rendered.template_key.is_none() && campaign_id.is_none()) {
+            return rendered;
+        }
+
+        let experiment = match ExperimentQueries::find_active(&self.pool, rendered.template_key.as_deref(), campaign_id).await {
+            Ok(Some(experiment)) => experiment,
+            Ok(None) => return rendered,
+            Err(e) => {
+                warn!(id = %rendered.id, error = %e, "Experiment lookup failed, sending original text");
+                return rendered;
+            }
+        };
+        let Some(variant) = experiments::assign(&experiment, rendered.user_id) else {
+            return rendered;
+        };
+
+        let variables = self.templates.variables(&rendered).await;
+        let render = |source: &Option<String>| {
+            source.as_deref().map(|source| templates::render_source(source, &variables)).transpose()
+        };
+        let (title, body) = match (render(&variant.title), render(&variant.body)) {
+            (Ok(title), Ok(body)) => (title, body),
+            (Err(e), _) | (_, Err(e)) => {
+                warn!(
+                    id = %rendered.id,
+                    experiment_id = %experiment.id,
+                    variant = %variant.name,
+                    error = %e,
+                    "Variant rendering failed, sending original text"
+                );
+                return rendered;
+            }
+        };
+
+        trace!(id = %rendered.id, experiment_id = %experiment.id, variant = %variant.name, "Experiment variant assigned");
+        let mut varied = rendered.into_owned();
+        if let Some(title) = title {
+            varied.title = title;
+        }
+        if let Some(body) = body {
+            varied.message = Some(body);
+        }
+        varied.experiment_id = Some(experiment.id);
+        varied.variant = Some(variant.name.clone());
+        Cow::Owned(varied)
+    }
+
+    /// Delivery counter and latency (deliver_at to delivery) per type and channel
+    ///
+    /// Types outside METRICS_NOTIFICATION_TYPES are labelled `other`.
+    fn record_outcome(&self, notification: &Notification, result: &DeliveryResult) {
+        let channel = match result {
+            DeliveryResult::Bus => Channel::Bus,
+            DeliveryResult::Push => Channel::Push,
+            _ => return,
+        };
+        let notification_type = self.config.metrics_type_label(&notification.notification_type).to_string();
+        let latency = (Utc::now() - notification.deliver_at).num_milliseconds().max(0) as f64 / 1000.0;
+        metrics::counter!(
+            "notifications_delivered_total",
+            "notification_type" => notification_type.clone(),
+            "channel" => channel.as_str()
+        )
+        .increment(1);
+        metrics::histogram!(
+            "notifications_delivery_latency_seconds",
+            "notification_type" => notification_type,
+            "channel" => channel.as_str()
+        )
+        .record(latency);
+        if let Some(costs) = &self.costs {
+            costs.record(&notification.tenant_id, channel.as_str());
+        }
+        if otp::is_otp(&notification.notification_type) {
+            // From the producer's request: the wait a user typing the code sees
+            let latency = (Utc::now() - notification.created_at).num_milliseconds().max(0) as f64 / 1000.0;
+            metrics::histogram!("notifications_otp_delivery_seconds", "channel" => channel.as_str()).record(latency);
+        }
+    }
+
+    /// Take the code off an OTP row that is done (delivered, suppressed or out of retries)
+    ///
+    /// It never shows in the inbox, so nothing needs it after delivery; archives and exports
+    /// then don't hold it either. A row still retrying or awaiting a Bus ack is left alone.
+    async fn scrub_otp(&self, notification: &Notification, result: &DeliveryResult) {
+        if !otp::is_otp(&notification.notification_type)
+            || !matches!(
+                result,
+                DeliveryResult::Bus | DeliveryResult::Push | DeliveryResult::Suppressed | DeliveryResult::Failed
+            )
+        {
+            return;
+        }
+        if let Err(e) = NotificationQueries::scrub_code(&self.pool, notification.id).await {
+            warn!(id = %notification.id, error = %e, "Failed to scrub the code from an OTP notification");
+        }
+    }
+
+    /// Emit a delivery event for sinks (no-op without subscribers)
+    fn emit_event(&self, notification: &Notification, result: &DeliveryResult) {
+        let Some(events) = &self.events else { return };
+
+        let (status, channel) = match result {
+            DeliveryResult::Bus => (DeliveryStatus::Delivered, Some(Channel::Bus.as_str())),
+            DeliveryResult::Push => (DeliveryStatus::Delivered, Some(Channel::Push.as_str())),
+            DeliveryResult::Failed => (DeliveryStatus::Failed, None),
+            DeliveryResult::Suppressed => (DeliveryStatus::Suppressed, None),
+            // Deferred rows come back later; expanded ones live on in their copies
+            // Duplicates were reported by whoever marked the row
+            DeliveryResult::Deferred | DeliveryResult::Expanded | DeliveryResult::Duplicate => return,
+        };
+
+        // Err only means nobody is subscribed right now
+        let _ = events.send(DeliveryEvent {
+            notification_id: notification.id,
+            tenant_id: notification.tenant_id.clone(),
+            user_id: notification.user_id,
+            notification_type: notification.notification_type.clone(),
+            status,
+            channel,
+            occurred_at: chrono::Utc::now(),
+        });
+    }
+
+    /// Queue a delivery receipt for producers (terminal states only, no-op when receipts are off)
+    ///
+    /// `provider_message_ids` are the FCM message names of a push delivery, one per device.
+    async fn enqueue_receipt(
+        &self,
+        notification: &Notification,
+        status: DeliveryStatus,
+        channel: Option<Channel>,
+        error: Option<&str>,
+        provider_message_ids: &[String],
+    ) {
+        // Simulated deliveries never reach producers
+        if self.config.receipt_signing_secret.is_none() || self.config.simulates(&notification.notification_type) {
+            return;
+        }
+
+        let body = serde_json::json!({
+            "notification_id": notification.id,
+            "tenant_id": notification.tenant_id,
+            "user_id": notification.user_id,
+            "notification_type": notification.notification_type,
+            "status": status,
+            "channel": channel.map(|c| c.as_str()),
+            "error": error,
+            "provider_message_ids": provider_message_ids,
+            "policy": notification.policy,
+            "created_at": notification.created_at,
+            "completed_at": chrono::Utc::now(),
+        });
+
+        let queued = on_claim!(self, |executor| {
+            ReceiptQueries::enqueue(executor, notification.id, &notification.notification_type, &body)
+        });
+        if let Err(e) = queued {
+            warn!(id = %notification.id, error = %e, "Failed to queue delivery receipt");
+        }
+    }
+
+    /// Feed an attempt into the adaptive route's channel stats
+    fn record_channel(&self, channel: Channel, started: Instant, succeeded: bool) {
+        if let Some(health) = &self.channel_health {
+            health.record(channel, started.elapsed(), succeeded);
+        }
+    }
+
+    /// Record a delivery attempt with the template version and variant that rendered it (best effort)
+    async fn record_attempt(&self, notification: &Notification, channel: Channel, outcome: &str, detail: Option<&str>) {
+        self.record_provider_attempt(notification, channel, outcome, detail, None).await
+    }
+
+    /// `record_attempt` with the provider's reference for the message (the FCM message name)
+    async fn record_provider_attempt(
+        &self,
+        notification: &Notification,
+        channel: Channel,
+        outcome: &str,
+        detail: Option<&str>,
+        provider_message_id: Option<&str>,
+    ) {
+        let attempt = NewAttempt {
+            notification_id: notification.id,
+            channel: channel.as_str(),
+            outcome,
+            detail,
+            template_key: notification.template_version.and(notification.template_key.as_deref()),
+            template_version: notification.template_version,
+            experiment_id: notification.experiment_id,
+            variant: notification.variant.as_deref(),
+            provider_message_id,
+        };
+
+        if let Err(e) = AttemptQueries::record(&self.pool, &attempt).await {
+            warn!(id = %notification.id, error = %e, "Failed to record delivery attempt");
+        }
+    }
+
+    /// Load the user's preferred locale (None on error or when unset)
+    async fn user_locale(&self, tenant_id: &str, user_id: Uuid) -> Option<String> {
+        match PreferenceQueries::get_locale(&self.pool, tenant_id, user_id).await {
+            Ok(locale) => locale,
+            Err(e) => {
+                warn!(user_id = %user_id, error = %e, "Failed to load user locale, using default");
+                None
+            }
+        }
+    }
+
+    /// Check the payload against its type's schema (a permanent validation error on mismatch)
+    ///
+    /// Fails open: if the schema can't be loaded the notification is delivered.
+    async fn check_contract(&self, notification: &Notification) -> Result<(), NotificationError> {
+        match contracts::check(&self.pool, &notification.notification_type, notification.payload.as_ref()).await {
+            Ok(()) => Ok(()),
+            Err(ContractError::Database(e)) => {
+                warn!(id = %notification.id, error = %e, "Failed to load the payload schema, delivering");
+                Ok(())
+            }
+            Err(e) => Err(ValidationError::invalid(e.to_string()).into()),
+        }
+    }
+
+    /// Apply the bundling window to a routed notification
+    ///
+    /// The first of a (user, type) in a window is sent; later ones are held to its end. The
+    /// first held row to come back opens the next window and absorbs the other held rows.
+    /// Fails open: when the window can't be read or the bundle gathered, it goes out alone.
+    async fn bundle(&self, notification: &Notification) -> Bundling {
+        if !self.config.is_bundled(&notification.notification_type)
+            || notification.priority.as_deref() == Some("critical")
+            || notification.pinned_until.is_some()
+            || notification.deadline.is_some()
+            || self.config.is_first_ack(&notification.notification_type)
+        {
+            return Bundling::Send;
+        }
+
+        let window = BundleQueries::open(
+            &self.pool,
+            &notification.tenant_id,
+            notification.user_id,
+            &notification.notification_type,
+            notification.id,
+            self.config.bundle_window_secs,
+        )
+        .await;
+        match window {
+            Ok(window) if window.opened_by != notification.id => return Bundling::Hold(window.window_ends_at),
+            Ok(_) if notification.bundled_at.is_none() => return Bundling::Send,
+            Ok(_) => {}
+            Err(e) => {
+                warn!(id = %notification.id, error = %e, "Failed to read the bundling window, sending on its own");
+                return Bundling::Send;
+            }
+        }
+
+        let absorbed = on_claim!(self, |executor| BundleQueries::absorb(
+            executor,
+            notification.id,
+            &notification.tenant_id,
+            notification.user_id,
+            &notification.notification_type
+        ));
+        match absorbed {
+            Ok(count) => Bundling::Flush(count),
+            Err(e) => {
+                warn!(id = %notification.id, error = %e, "Failed to gather the bundle, sending on its own");
+                Bundling::Send
+            }
+        }
+    }
+
+    /// Absorbed into another row's bundle (fails open: an unreadable row is delivered)
+    async fn is_absorbed(&self, id: Uuid) -> bool {
+        match BundleQueries::is_absorbed(&self.pool, id).await {
+            Ok(absorbed) => absorbed,
+            Err(e) => {
+                warn!(id = %id, error = %e, "Failed to check the bundle of a held notification, delivering");
+                false
+            }
+        }
+    }
+
+    /// The user is on the suppression list (the tenant's or the global one)
+    ///
+    /// Fails open: if the list can't be read the notification is delivered.
+    async fn is_user_suppressed(&self, notification: &Notification) -> bool {
+        let user_id = notification.user_id.to_string();
+        match SuppressionQueries::is_suppressed(&self.pool, &notification.tenant_id, "user", &user_id).await {
+            Ok(suppressed) => suppressed,
+            Err(e) => {
+                warn!(id = %notification.id, error = %e, "Failed to check the suppression list, delivering");
+                false
+            }
+        }
+    }
+
+    /// Drop the channels whose p90 latency (last minute) doesn't fit the time left before the
+    /// deadline - false when none is left
+    ///
+    /// The numbers come from adaptive routing (ADAPTIVE_ROUTING_LATENCY_MS); without it, or with
+    /// too few recent attempts on a channel, the channel counts as fast enough.
+    fn fit_deadline(&self, notification: &Notification, channels: &mut Vec<Channel>) -> bool {
+        let (Some(deadline), Some(health)) = (notification.deadline, &self.channel_health) else {
+            return true;
+        };
+        let left = (deadline - Utc::now()).to_std().unwrap_or_default();
+        let before = channels.len();
+        channels.retain(|channel| health.expected_latency(*channel).is_none_or(|latency| latency <= left));
+        if channels.len() < before {
+            info!(
+                id = %notification.id,
+                left_ms = left.as_millis() as u64,
+                channels = ?channels,
+                "Skipping channels too slow for the deadline"
+            );
+            metrics::counter!("notifications_deadline_channel_skips_total").increment((before - channels.len()) as u64);
+        }
+        !channels.is_empty()
+    }
+
+    /// Drop paid channels from the route once the tenant's daily budget is spent
+    ///
+    /// Critical notifications keep their route. Returns true when a channel was dropped.
+    async fn downgrade_over_budget(&self, notification: &Notification, channels: &mut Vec<Channel>) -> bool {
+        let Some(costs) = &self.costs else {
+            return false;
+        };
+        if notification.priority.as_deref() == Some("critical")
+            || !channels.iter().any(|channel| costs.prices().is_paid(channel.as_str()))
+            || !costs.is_over_budget(&notification.tenant_id).await
+        {
+            return false;
+        }
+        channels.retain(|channel| !costs.prices().is_paid(channel.as_str()));
+        info!(
+            id = %notification.id,
+            tenant_id = %notification.tenant_id,
+            channels = ?channels,
+            "Daily delivery budget spent, skipping paid channels"
+        );
+        metrics::counter!("notifications_budget_downgrades_total").increment(1);
+        true
+    }
+
+    /// Count an UNREGISTERED error; the token is suppressed at SUPPRESS_AFTER_UNREGISTERED
+    async fn record_unregistered(&self, fcm_token: &str) {
+        let threshold = self.config.suppress_after_unregistered;
+        if threshold <= 0 {
+            return;
+        }
+        match SuppressionQueries::record_unregistered(&self.pool, fcm_token, threshold).await {
+            Ok(true) => {
+                warn!(
+                    token = %mask_token(fcm_token),
+                    threshold = threshold,
+                    "⊘ Token keeps coming back UNREGISTERED, added to the suppression list"
+                );
+                metrics::counter!("notifications_tokens_suppressed_total").increment(1);
+            }
+            Ok(false) => {}
+            Err(e) => error!(error = %e, "Failed to record an UNREGISTERED token"),
+        }
+    }
+
+    /// An identical broadcast went out within BROADCAST_DEDUP_WINDOW_SECS: suppress this one
+    ///
+    /// Fails open: if the fingerprint can't be claimed the broadcast is sent.
+    async fn is_duplicate_broadcast(&self, notification: &Notification) -> bool {
+        let window_secs = self.config.broadcast_dedup_window_secs;
+        if window_secs == 0 {
+            return false;
+        }
+        let content_hash = notification.content_hash();
+        let claimed = on_claim!(self, |executor| BroadcastQueries::claim(
+            executor,
+            &notification.tenant_id,
+            &content_hash,
+            notification.id,
+            window_secs,
+            notification.allow_duplicate,
+        ));
+        match claimed {
+            Ok(true) => false,
+            Ok(false) => {
+                warn!(
+                    id = %notification.id,
+                    tenant_id = %notification.tenant_id,
+                    window_secs = window_secs,
+                    "⊘ Suppressed - identical broadcast already sent (set allow_duplicate to re-send)"
+                );
+                metrics::counter!("notifications_duplicate_broadcasts_total").increment(1);
+                self.mark_suppressed(notification.id, "duplicate_broadcast").await;
+                true
+            }
+            Err(e) => {
+                warn!(id = %notification.id, error = %e, "Failed to check for a duplicate broadcast, sending it");
+                false
+            }
+        }
+    }
+
+    /// Mark notification as suppressed (never delivered, never retried)
+    #[instrument(skip(self), fields(id = %id, reason = %reason))]
+    async fn mark_suppressed(&self, id: Uuid, reason: &str) {
+        trace!("Marking notification {} as suppressed ({})", id, reason);
+        let start = Instant::now();
+
+        if let Err(e) = on_claim!(self, queue.mark_suppressed(id, reason)) {
+            error!(
+                id = %id,
+                error = %e,
+                duration_ms = start.elapsed().as_millis() as u64,
+                "Failed to mark notification as suppressed in database"
+            );
+        }
+    }
+
+    /// The publish an earlier run recorded for this notification, if recent (fails open)
+    async fn recorded_bus_delivery(&self, id: Uuid) -> Option<BusDelivery> {
+        let since = Utc::now() - chrono::Duration::from_std(BUS_DELIVERY_REUSE).unwrap_or_default();
+        match BusDeliveryQueries::latest(&self.pool, id, since).await {
+            Ok(recorded) => recorded,
+            Err(e) => {
+                warn!(id = %id, error = %e, "Failed to look up recorded Bus publishes, publishing again");
+                None
+            }
+        }
+    }
+
+    /// Mark a Bus delivery and queue its receipt, unless the row was marked already
+    ///
+    /// A racing replica (CONSUMPTION_MODE=mark_after_send) or an earlier run may have
+    /// published and marked the same row: that one reported it, this one returns
+    /// [`DeliveryResult::Duplicate`] so the delivery isn't counted twice.
+    async fn mark_bus_delivered(&self, notification: &Notification, delivery_id: Option<Uuid>) -> DeliveryResult {
+        let id = notification.id;
+        let marked = async {
+            self.db_fault().await?;
+            on_claim!(self, |executor| BusDeliveryQueries::mark(executor, id, delivery_id))
+        };
+        match marked.await {
+            Ok(true) => {}
+            Ok(false) => {
+                info!(id = %id, delivery_id = ?delivery_id, "Bus delivery was already marked, not counting it again");
+                metrics::counter!("notifications_bus_duplicate_marks_total").increment(1);
+                return DeliveryResult::Duplicate;
+            }
+            Err(e) => error!(id = %id, error = %e, "Failed to mark notification as success in database"),
+        }
+        self.enqueue_receipt(notification, DeliveryStatus::Delivered, Some(Channel::Bus), None, &[]).await;
+        DeliveryResult::Bus
     }
 
     /// Mark notification as successfully delivered
@@ -534,7 +2370,11 @@ impl NotificationWorker {
         trace!("Marking notification {} as success", id);
         let start = Instant::now();
 
-        if let Err(e) = NotificationQueries::mark_success(&self.pool, id).await {
+        let marked = async {
+            self.db_fault().await?;
+            on_claim!(self, queue.mark_success(id))
+        };
+        if let Err(e) = marked.await {
             error!(
                 id = %id,
                 error = %e,
@@ -550,26 +2390,47 @@ impl NotificationWorker {
         }
     }
 
-    /// Mark notification failure with error tracking
-    #[instrument(skip(self), fields(id = %id, error = %error))]
-    async fn mark_failure(&self, id: Uuid, error: &str) {
+    /// Mark notification failure with error tracking - returns true if retries are exhausted
+    ///
+    /// A permanent error (not [`NotificationError::is_retryable`]) gives up right away.
+    #[instrument(skip(self, notification), fields(id = %notification.id, error = %error))]
+    async fn mark_failure(&self, notification: &Notification, error: &NotificationError) -> bool {
+        let id = notification.id;
         trace!(
             "Recording failure for notification {}: {}",
             id, error
         );
         let start = Instant::now();
+        let category = error.category().as_str();
+        let retryable = error.is_retryable();
+        metrics::counter!(
+            "notifications_failures_total",
+            "category" => category,
+            "retryable" => if retryable { "true" } else { "false" },
+            "notification_type" => self.config.metrics_type_label(&notification.notification_type).to_string()
+        )
+        .increment(1);
 
-        match NotificationQueries::mark_failure(
-            &self.pool,
-            id,
-            error,
-            self.config.max_retries,
-        ).await {
+        let message = error.to_string();
+        let max_retries = if retryable { self.config.max_retries } else { 1 };
+        let marked = async {
+            self.db_fault().await?;
+            on_claim!(self, queue.mark_failure(id, &message, category, max_retries))
+        };
+        match marked.await {
             Ok(stopped) => {
                 let duration = start.elapsed();
-                if stopped {
+                if stopped && !retryable {
                     warn!(
                         id = %id,
+                        category = category,
+                        duration_ms = duration.as_millis() as u64,
+                        "Notification permanently failed - error is not retryable"
+                    );
+                } else if stopped {
+                    warn!(
+                        id = %id,
+                        category = category,
                         max_retries = self.config.max_retries,
                         duration_ms = duration.as_millis() as u64,
                         "Notification permanently failed - max retries reached"
@@ -578,10 +2439,12 @@ impl NotificationWorker {
                     debug!(
                         id = %id,
                         error = %error,
+                        category = category,
                         duration_ms = duration.as_millis() as u64,
                         "Notification failure recorded, will retry later"
                     );
                 }
+                stopped
             }
             Err(e) => {
                 error!(
@@ -590,9 +2453,34 @@ impl NotificationWorker {
                     duration_ms = start.elapsed().as_millis() as u64,
                     "Failed to record notification failure in database"
                 );
+                false
             }
         }
     }
+
+    /// Injected FCM failure (chaos mode only)
+    async fn fcm_fault(&self) -> Result<(), FcmError> {
+        match &self.faults {
+            Some(faults) => faults.fcm().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Injected Bus timeout (chaos mode only)
+    async fn bus_fault(&self) -> Result<(), BusError> {
+        match &self.faults {
+            Some(faults) => faults.bus().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Injected database error (chaos mode only)
+    async fn db_fault(&self) -> Result<(), sqlx::Error> {
+        match &self.faults {
+            Some(faults) => faults.db().await,
+            None => Ok(()),
+        }
+    }
 }
 
 /// Result of notification delivery attempt
@@ -600,6 +2488,47 @@ enum DeliveryResult {
     Bus,
     Push,
     Failed,
+    /// Not delivered on purpose (user preferences) - terminal, no retry
+    Suppressed,
+    /// Postponed to a later deliver_at (e.g. snooze) - not a failure
+    Deferred,
+    /// Topic notification replaced by per-subscriber copies
+    Expanded,
+    /// Delivered, but a racing replica or an earlier run marked it first - already counted
+    Duplicate,
+}
+
+/// A push that reached at least one device
+struct Pushed {
+    devices: usize,
+    /// FCM message names, for the delivery receipt
+    message_ids: Vec<String>,
+}
+
+/// Why a push went to no device
+enum NotPushed {
+    /// Nothing could be sent - a delivery failure
+    Failed(NotificationError),
+    /// Every device's own preferences hold it back
+    Held(DeviceHold),
+}
+
+impl From<NotificationError> for NotPushed {
+    fn from(error: NotificationError) -> Self {
+        NotPushed::Failed(error)
+    }
+}
+
+impl From<PushError> for NotPushed {
+    fn from(error: PushError) -> Self {
+        NotPushed::Failed(error.into())
+    }
+}
+
+impl From<DbError> for NotPushed {
+    fn from(error: DbError) -> Self {
+        NotPushed::Failed(error.into())
+    }
 }
 
 /// Mask FCM token for logging (security)
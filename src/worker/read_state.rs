@@ -0,0 +1,126 @@
+use crate::config::{Config, PushEnvironment};
+use crate::db::{NotificationQueries, SyncQueries};
+use crate::dev_sandbox::DevSandbox;
+use crate::push::fcm::{mask_token, FcmError};
+use crate::protocol::Protocols;
+use crate::push::FcmClient;
+use crate::realtime::RealtimeBus;
+use crate::worker::devices::push_environment;
+use crate::worker::tenants::TenantRegistry;
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
+
+/// Tells a user's other connections and devices that notifications were read
+///
+/// A `read_state_changed` envelope goes to every Bus connection of the user (the one that
+/// marked them read included; clients ignore ids they already show as read). With
+/// READ_STATE_PUSH the devices also get a silent FCM message carrying the new unread count,
+/// which sets the iOS badge. Best effort, like a dismiss.
+pub struct ReadStateFanout {
+    pool: PgPool,
+    tenants: TenantRegistry,
+    bus_client: Option<Arc<dyn RealtimeBus>>,
+    protocols: Protocols,
+    push: bool,
+    /// Devices without their own push_environment
+    default_environment: PushEnvironment,
+    simulate: bool,
+}
+
+impl ReadStateFanout {
+    pub fn new(
+        pool: PgPool,
+        config: &Config,
+        bus_client: Option<Arc<dyn RealtimeBus>>,
+        fcm_client: Option<Arc<FcmClient>>,
+    ) -> Self {
+        let tenants = TenantRegistry::new(pool.clone(), fcm_client)
+            .with_fcm_endpoints(&config.fcm_base_url, &config.fcm_token_url);
+        Self {
+            pool,
+            tenants,
+            bus_client,
+            protocols: Protocols::default(),
+            push: config.read_state_push,
+            default_environment: config.push_environment,
+            simulate: config.is_simulated(),
+        }
+    }
+
+    /// FCM client for sandbox devices (see `NotificationWorker::with_sandbox_fcm`)
+    pub fn with_sandbox_fcm(mut self, fcm_sandbox: Option<Arc<FcmClient>>) -> Self {
+        self.tenants = self.tenants.with_sandbox_fcm(fcm_sandbox);
+        self
+    }
+
+    /// Developer sandbox tenant, pushed to its mock FCM (see `NotificationWorker::with_dev_sandbox`)
+    pub fn with_dev_sandbox(mut self, dev_sandbox: Option<Arc<DevSandbox>>) -> Self {
+        self.tenants = self.tenants.with_dev_sandbox(dev_sandbox);
+        self
+    }
+
+    /// Protocol versions the Bus messages are published in (WS_PROTOCOLS)
+    pub fn with_protocols(mut self, protocols: Protocols) -> Self {
+        self.protocols = protocols;
+        self
+    }
+
+    /// Publish the ids that were just marked read, skipping the push to `from_device`
+    #[instrument(skip(self, ids, from_device), fields(tenant_id = %tenant_id, user_id = %user_id, count = ids.len()))]
+    pub async fn publish(&self, tenant_id: &str, user_id: Uuid, ids: &[Uuid], from_device: Option<&str>) {
+        if ids.is_empty() {
+            return;
+        }
+        if self.simulate {
+            info!("🧪 Simulated read-state fan-out");
+            return;
+        }
+        let unread_count = match SyncQueries::unread_count(&self.pool, tenant_id, user_id).await {
+            Ok(count) => count,
+            Err(e) => {
+                warn!(error = %e, "Failed to count unread notifications, no read-state fan-out");
+                return;
+            }
+        };
+        let tenant = self.tenants.resolve(tenant_id).await;
+
+        if let Some(bus) = self.bus_client.as_deref() {
+            let message = json!({ "type": "read_state_changed", "ids": ids, "unread_count": unread_count });
+            let topic = tenant.topic("notifications");
+            match self.protocols.publish_to_user(bus, user_id, &topic, "read_state_changed", &message).await {
+                Ok(delivered_to) => debug!(delivered_to = delivered_to, "Read state published via Bus"),
+                Err(e) => warn!(error = %e, "Failed to publish read state to WebSocket Bus"),
+            }
+        }
+
+        if !self.push || !tenant.has_fcm() {
+            return;
+        }
+        let devices = match NotificationQueries::get_user_devices(&self.pool, tenant_id, user_id).await {
+            Ok(devices) => devices,
+            Err(e) => {
+                warn!(error = %e, "Failed to fetch devices for read state");
+                return;
+            }
+        };
+        let push = FcmClient::prepare_read_state(ids, unread_count);
+        let mut sent = 0u64;
+        for device in devices.iter().filter(|d| Some(d.fcm_token.as_str()) != from_device) {
+            let environment = push_environment(device, self.default_environment);
+            let Some(fcm) = tenant.fcm_for(environment) else {
+                continue;
+            };
+            match fcm.send_prepared(&device.fcm_token, &push).await {
+                Ok(_) => sent += 1,
+                // The next notification's push cleans it up
+                Err(FcmError::InvalidToken) => debug!(token = %mask_token(&device.fcm_token), "Read state to invalid token"),
+                Err(e) => warn!(token = %mask_token(&device.fcm_token), error = %e, "Read-state push failed"),
+            }
+        }
+        debug!(sent = sent, unread_count = unread_count, "Read state pushed to devices");
+        metrics::counter!("notifications_read_state_pushes_total").increment(sent);
+    }
+}
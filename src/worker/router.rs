@@ -0,0 +1,329 @@
+use crate::db::PreferenceQueries;
+use crate::models::Notification;
+use crate::worker::windows::{self, DeliveryWindows};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing::{debug, trace, warn};
+
+/// Delivery channel for a single notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    /// Real-time delivery via WebSocket Bus
+    Bus,
+    /// FCM push notification
+    Push,
+}
+
+impl Channel {
+    /// Default fallback order: real-time first, push when the user is offline
+    pub const ALL: [Channel; 2] = [Channel::Bus, Channel::Push];
+
+    /// Name as stored in `user_channel_preferences.channel`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Bus => "bus",
+            Channel::Push => "push",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Channel::ALL.into_iter().find(|channel| channel.as_str() == name)
+    }
+}
+
+/// Channels per priority (FALLBACK_CHAINS), e.g. `critical=bus>push,low=bus`
+///
+/// Priorities without a chain use [`Channel::ALL`]. User preferences can only remove
+/// channels from a chain, never add them.
+#[derive(Debug, Clone, Default)]
+pub struct FallbackChains {
+    chains: HashMap<String, Vec<Channel>>,
+}
+
+impl FallbackChains {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut chains = HashMap::new();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let invalid = || format!("Invalid FALLBACK_CHAINS entry '{}', expected e.g. 'critical=bus>push'", entry);
+            let (priority, chain) = entry.split_once('=').ok_or_else(invalid)?;
+            let priority = priority.trim();
+            if !matches!(priority, "low" | "normal" | "high" | "critical") {
+                return Err(format!("Unknown priority '{}' in FALLBACK_CHAINS", priority));
+            }
+            let channels = chain
+                .split('>')
+                .map(|name| {
+                    Channel::parse(name.trim()).ok_or_else(|| {
+                        format!("Unknown channel '{}' in FALLBACK_CHAINS (bus, push)", name.trim())
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            // The Bus publish is what tells whether the user is online, so it can only come first
+            let ordered = Channel::ALL.iter().filter(|c| channels.contains(c)).copied().collect::<Vec<_>>();
+            if channels != ordered {
+                return Err(format!("FALLBACK_CHAINS '{}': channels must follow the order bus>push, once each", entry));
+            }
+            if chains.insert(priority.to_string(), channels).is_some() {
+                return Err(format!("Priority '{}' appears twice in FALLBACK_CHAINS", priority));
+            }
+        }
+        Ok(Self { chains })
+    }
+
+    /// Chain for a notification priority (None = normal)
+    pub fn for_priority(&self, priority: Option<&str>) -> &[Channel] {
+        self.chains
+            .get(priority.unwrap_or("normal"))
+            .map(Vec::as_slice)
+            .unwrap_or(&Channel::ALL)
+    }
+}
+
+/// Routing decision for a single notification
+#[derive(Debug)]
+pub enum Route {
+    /// Try these channels in order until one delivers
+    Deliver(Vec<Channel>),
+    /// Do not deliver at all (terminal state, reason is stored on the row)
+    Suppress(&'static str),
+    /// Not now: move deliver_at to `until` and leave unprocessed
+    Defer {
+        until: DateTime<Utc>,
+        reason: &'static str,
+    },
+}
+
+/// One check of the route and what it found (`ChannelRouter::explain`)
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteStep {
+    pub check: &'static str,
+    pub outcome: String,
+}
+
+/// Append a step when the route is being explained (the closure only runs then)
+fn note(trail: &mut Option<&mut Vec<RouteStep>>, check: &'static str, outcome: impl FnOnce() -> String) {
+    if let Some(trail) = trail.as_deref_mut() {
+        trail.push(RouteStep { check, outcome: outcome() });
+    }
+}
+
+/// Decides which channels a notification may use based on its priority and user preferences
+pub struct ChannelRouter {
+    pool: PgPool,
+    chains: FallbackChains,
+    windows: DeliveryWindows,
+    /// Recipient timezone when the user has none
+    window_timezone: Tz,
+}
+
+impl ChannelRouter {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            chains: FallbackChains::default(),
+            windows: DeliveryWindows::default(),
+            window_timezone: Tz::UTC,
+        }
+    }
+
+    pub fn with_windows(mut self, windows: DeliveryWindows, default_timezone: Tz) -> Self {
+        self.windows = windows;
+        self.window_timezone = default_timezone;
+        self
+    }
+
+    pub fn with_chains(mut self, chains: FallbackChains) -> Self {
+        self.chains = chains;
+        self
+    }
+
+    /// Whether the notification's priority allows `channel` at all (before preferences)
+    pub fn chain_allows(&self, notification: &Notification, channel: Channel) -> bool {
+        self.chains.for_priority(notification.priority.as_deref()).contains(&channel)
+    }
+
+    /// Resolve the route for a user notification (fails open on DB errors)
+    pub async fn route(&self, notification: &Notification) -> Route {
+        self.resolve(notification, Utc::now(), None).await
+    }
+
+    /// The route as `route` resolves it right now, with every check it made on the way
+    pub async fn explain(&self, notification: &Notification) -> (Route, Vec<RouteStep>) {
+        self.explain_at(notification, Utc::now()).await
+    }
+
+    /// `explain` with the clock checks (local send time, delivery windows) made at `now`;
+    /// preferences and snooze are still read as they are
+    pub async fn explain_at(&self, notification: &Notification, now: DateTime<Utc>) -> (Route, Vec<RouteStep>) {
+        let mut trail = Vec::new();
+        let route = self.resolve(notification, now, Some(&mut trail)).await;
+        (route, trail)
+    }
+
+    /// The recipient's timezone (`user_notification_settings.timezone`, else DELIVERY_WINDOW_TIMEZONE)
+    async fn timezone(&self, notification: &Notification) -> Tz {
+        match PreferenceQueries::get_timezone(&self.pool, &notification.tenant_id, notification.user_id).await {
+            Ok(zone) => zone.and_then(|zone| zone.parse().ok()).unwrap_or(self.window_timezone),
+            Err(e) => {
+                warn!(
+                    id = %notification.id,
+                    error = %e,
+                    "Failed to load user timezone, using the default"
+                );
+                self.window_timezone
+            }
+        }
+    }
+
+    async fn resolve(
+        &self,
+        notification: &Notification,
+        now: DateTime<Utc>,
+        mut trail: Option<&mut Vec<RouteStep>>,
+    ) -> Route {
+        let tenant_id = notification.tenant_id.as_str();
+        let user_id = notification.user_id;
+        let notification_type = notification.notification_type.as_str();
+
+        match PreferenceQueries::is_type_enabled(&self.pool, tenant_id, user_id, notification_type).await {
+            Ok(false) => {
+                note(&mut trail, "type_preference", || format!("'{}' disabled by the user", notification_type));
+                return Route::Suppress("type_disabled");
+            }
+            Ok(true) => note(&mut trail, "type_preference", || format!("'{}' enabled", notification_type)),
+            Err(e) => {
+                note(&mut trail, "type_preference", || format!("lookup failed ({}), delivering anyway", e));
+                warn!(
+                    id = %notification.id,
+                    error = %e,
+                    "Failed to load type preference, delivering anyway"
+                );
+            }
+        }
+
+        // Muted target/thread suppresses regardless of priority
+        if let (Some(target_type), Some(target_id)) = (&notification.target_type, notification.target_id) {
+            match PreferenceQueries::is_target_muted(&self.pool, tenant_id, user_id, target_type, target_id).await {
+                Ok(true) => {
+                    note(&mut trail, "muted_target", || format!("{}/{} muted by the user", target_type, target_id));
+                    return Route::Suppress("target_muted");
+                }
+                Ok(false) => note(&mut trail, "muted_target", || format!("{}/{} not muted", target_type, target_id)),
+                Err(e) => {
+                    note(&mut trail, "muted_target", || format!("lookup failed ({}), delivering anyway", e));
+                    warn!(
+                        id = %notification.id,
+                        error = %e,
+                        "Failed to load muted targets, delivering anyway"
+                    );
+                }
+            }
+        }
+
+        // Producer's wall-clock send time, in the recipient's timezone
+        if let Some(local) = notification.deliver_at_local {
+            let tz = self.timezone(notification).await;
+            let at = windows::resolve_local(local, tz);
+            if at > now {
+                note(&mut trail, "local_time", || format!("{} in {} is {}", local, tz, at));
+                return Route::Defer { until: at, reason: "scheduled_local_time" };
+            }
+            note(&mut trail, "local_time", || format!("{} in {} has passed", local, tz));
+        }
+
+        // Snooze defers everything except critical notifications
+        if notification.priority.as_deref() == Some("critical") {
+            note(&mut trail, "snooze", || "critical, snooze doesn't apply".to_string());
+        } else {
+            match PreferenceQueries::get_active_snooze(&self.pool, tenant_id, user_id).await {
+                Ok(Some(until)) => {
+                    note(&mut trail, "snooze", || format!("snoozed until {}", until));
+                    return Route::Defer { until, reason: "snoozed" };
+                }
+                Ok(None) => note(&mut trail, "snooze", || "not snoozed".to_string()),
+                Err(e) => {
+                    note(&mut trail, "snooze", || format!("lookup failed ({}), delivering anyway", e));
+                    warn!(
+                        id = %notification.id,
+                        error = %e,
+                        "Failed to load snooze state, delivering anyway"
+                    );
+                }
+            }
+        }
+
+        // Business hours per type, recipient-local: wait for the next window start
+        if let Some(window) = self.windows.get(notification_type) {
+            let tz = self.timezone(notification).await;
+            if let Some(until) = window.closed_until(tz, now) {
+                note(&mut trail, "delivery_window", || format!("closed in {} until {}", tz, until));
+                return Route::Defer { until, reason: "outside_delivery_window" };
+            }
+            note(&mut trail, "delivery_window", || format!("open in {}", tz));
+        }
+
+        let prefs = match PreferenceQueries::get_channel_preferences(&self.pool, tenant_id, user_id, notification_type).await {
+            Ok(prefs) => {
+                note(&mut trail, "channel_preferences", || {
+                    let disabled: Vec<_> = prefs.iter().filter(|p| !p.enabled).map(|p| p.channel.as_str()).collect();
+                    if disabled.is_empty() {
+                        "no channel disabled".to_string()
+                    } else {
+                        format!("{} disabled by the user", disabled.join(", "))
+                    }
+                });
+                prefs
+            }
+            Err(e) => {
+                note(&mut trail, "channel_preferences", || format!("lookup failed ({}), using the whole chain", e));
+                warn!(
+                    id = %notification.id,
+                    error = %e,
+                    "Failed to load channel preferences, using all channels"
+                );
+                Vec::new()
+            }
+        };
+
+        let chain = self.chains.for_priority(notification.priority.as_deref());
+        note(&mut trail, "fallback_chain", || {
+            format!(
+                "{} for priority {}",
+                chain.iter().map(Channel::as_str).collect::<Vec<_>>().join(">"),
+                notification.priority.as_deref().unwrap_or("normal")
+            )
+        });
+        let channels: Vec<Channel> = chain
+            .iter()
+            .copied()
+            .filter(|channel| {
+                prefs
+                    .iter()
+                    .find(|p| p.channel == channel.as_str())
+                    .map(|p| p.enabled)
+                    .unwrap_or(true)
+            })
+            .collect();
+
+        trace!(
+            id = %notification.id,
+            channels = ?channels,
+            "Channel route resolved"
+        );
+
+        if channels.is_empty() {
+            debug!(
+                id = %notification.id,
+                notification_type = %notification_type,
+                priority = ?notification.priority,
+                "All channels in the chain disabled for this type"
+            );
+            return Route::Suppress("channels_disabled");
+        }
+
+        Route::Deliver(channels)
+    }
+}
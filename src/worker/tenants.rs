@@ -0,0 +1,230 @@
+use crate::config::PushEnvironment;
+use crate::dev_sandbox::DevSandbox;
+use crate::db::tenants::DEFAULT_TENANT;
+use crate::db::TenantQueries;
+use crate::push::fcm::{FCM_BASE_URL, TOKEN_URL};
+use crate::push::FcmClient;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{debug, error, info, warn};
+
+/// How long tenant settings are cached before re-reading `activity.tenants`
+const CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Delivery settings for one tenant
+pub struct TenantContext {
+    pub tenant_id: String,
+    /// False = notifications are suppressed (tenant switched off)
+    pub enabled: bool,
+    /// Tenant's own FCM project, or the service-wide client
+    pub fcm: Option<Arc<FcmClient>>,
+    /// Project for sandbox devices (None = they go through `fcm`)
+    fcm_sandbox: Option<Arc<FcmClient>>,
+    bus_topic_prefix: Option<String>,
+    loaded_at: Instant,
+}
+
+impl TenantContext {
+    /// Bus topic for this tenant, e.g. `brand_a.notifications`
+    pub fn topic(&self, base: &str) -> String {
+        match &self.bus_topic_prefix {
+            Some(prefix) => format!("{}.{}", prefix, base),
+            None => base.to_string(),
+        }
+    }
+
+    /// FCM client for devices in this environment
+    ///
+    /// Without a sandbox project, sandbox devices use the production one: that works when
+    /// the dev build shares the Firebase project (FCM picks the APNs gateway per token).
+    pub fn fcm_for(&self, environment: PushEnvironment) -> Option<&Arc<FcmClient>> {
+        match environment {
+            PushEnvironment::Sandbox => self.fcm_sandbox.as_ref().or(self.fcm.as_ref()),
+            PushEnvironment::Production => self.fcm.as_ref(),
+        }
+    }
+
+    /// Any FCM client at all (push is possible for some device)
+    pub fn has_fcm(&self) -> bool {
+        self.fcm.is_some() || self.fcm_sandbox.is_some()
+    }
+}
+
+/// Lazily loaded, cached tenant settings for the worker
+pub struct TenantRegistry {
+    pool: PgPool,
+    default_fcm: Option<Arc<FcmClient>>,
+    /// Service-wide sandbox project (FCM_SANDBOX_*)
+    default_fcm_sandbox: Option<Arc<FcmClient>>,
+    /// Endpoints for tenant-specific FCM clients
+    fcm_base_url: String,
+    fcm_token_url: String,
+    /// DEV_SANDBOX_TENANT: that tenant pushes to the sandbox's mock only
+    dev_sandbox: Option<Arc<DevSandbox>>,
+    cache: RwLock<HashMap<String, Arc<TenantContext>>>,
+}
+
+impl TenantRegistry {
+    pub fn new(pool: PgPool, default_fcm: Option<Arc<FcmClient>>) -> Self {
+        Self {
+            pool,
+            default_fcm,
+            default_fcm_sandbox: None,
+            fcm_base_url: FCM_BASE_URL.to_string(),
+            fcm_token_url: TOKEN_URL.to_string(),
+            dev_sandbox: None,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_fcm_endpoints(mut self, fcm_base_url: &str, fcm_token_url: &str) -> Self {
+        self.fcm_base_url = fcm_base_url.to_string();
+        self.fcm_token_url = fcm_token_url.to_string();
+        self
+    }
+
+    /// Service-wide client for sandbox devices
+    pub fn with_sandbox_fcm(mut self, fcm_sandbox: Option<Arc<FcmClient>>) -> Self {
+        self.default_fcm_sandbox = fcm_sandbox;
+        self
+    }
+
+    /// Developer sandbox: its tenant's pushes go to the mock, whatever the tenant row says
+    pub fn with_dev_sandbox(mut self, dev_sandbox: Option<Arc<DevSandbox>>) -> Self {
+        self.dev_sandbox = dev_sandbox;
+        self
+    }
+
+    /// Settings for a tenant (falls back to service-wide settings on DB errors)
+    pub async fn resolve(&self, tenant_id: &str) -> Arc<TenantContext> {
+        if let Some(context) = self.cache.read().await.get(tenant_id) {
+            if context.loaded_at.elapsed() < CACHE_TTL {
+                return context.clone();
+            }
+        }
+
+        let context = Arc::new(self.load(tenant_id).await);
+        self.cache
+            .write()
+            .await
+            .insert(tenant_id.to_string(), context.clone());
+        context
+    }
+
+    /// Load every tenant whose cached settings are stale (standby, so a takeover starts warm)
+    pub async fn warm(&self) -> usize {
+        match TenantQueries::list(&self.pool).await {
+            Ok(tenants) => {
+                for tenant in &tenants {
+                    self.resolve(&tenant.tenant_id).await;
+                }
+                tenants.len()
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to list tenants to warm the cache");
+                0
+            }
+        }
+    }
+
+    async fn load(&self, tenant_id: &str) -> TenantContext {
+        let mut context = self.load_settings(tenant_id).await;
+        if let Some(sandbox) = self.dev_sandbox.as_ref().filter(|sandbox| sandbox.tenant_id() == tenant_id) {
+            context.fcm = Some(sandbox.fcm());
+            context.fcm_sandbox = None;
+        }
+        context
+    }
+
+    async fn load_settings(&self, tenant_id: &str) -> TenantContext {
+        let fallback = |enabled| TenantContext {
+            tenant_id: tenant_id.to_string(),
+            enabled,
+            fcm: self.default_fcm.clone(),
+            fcm_sandbox: self.default_fcm_sandbox.clone(),
+            bus_topic_prefix: None,
+            loaded_at: Instant::now(),
+        };
+
+        let tenant = match TenantQueries::find(&self.pool, tenant_id).await {
+            Ok(Some(tenant)) => tenant,
+            Ok(None) => {
+                // Rows can only reference unknown tenants via direct INSERTs
+                if tenant_id != DEFAULT_TENANT {
+                    warn!(tenant_id = %tenant_id, "Unknown tenant, using service-wide settings");
+                }
+                return fallback(true);
+            }
+            Err(e) => {
+                error!(tenant_id = %tenant_id, error = %e, "Failed to load tenant, using service-wide settings");
+                return fallback(true);
+            }
+        };
+
+        let fcm = match (&tenant.fcm_project_id, &tenant.fcm_credentials_path) {
+            (Some(project_id), Some(credentials_path)) => {
+                self.tenant_client(tenant_id, project_id, credentials_path, PushEnvironment::Production)
+            }
+            _ => self.default_fcm.clone(),
+        };
+        let fcm_sandbox = match (&tenant.fcm_sandbox_project_id, &tenant.fcm_sandbox_credentials_path) {
+            (Some(project_id), Some(credentials_path)) => {
+                self.tenant_client(tenant_id, project_id, credentials_path, PushEnvironment::Sandbox)
+            }
+            // A tenant with its own project doesn't get the service-wide sandbox project either
+            _ if tenant.fcm_project_id.is_some() => None,
+            _ => self.default_fcm_sandbox.clone(),
+        };
+
+        debug!(
+            tenant_id = %tenant_id,
+            enabled = tenant.enabled,
+            own_fcm = tenant.fcm_project_id.is_some(),
+            own_fcm_sandbox = tenant.fcm_sandbox_project_id.is_some(),
+            bus_topic_prefix = ?tenant.bus_topic_prefix,
+            "Tenant settings loaded"
+        );
+
+        TenantContext {
+            tenant_id: tenant.tenant_id,
+            enabled: tenant.enabled,
+            fcm,
+            fcm_sandbox,
+            bus_topic_prefix: tenant.bus_topic_prefix,
+            loaded_at: Instant::now(),
+        }
+    }
+
+    fn tenant_client(
+        &self,
+        tenant_id: &str,
+        project_id: &str,
+        credentials_path: &str,
+        environment: PushEnvironment,
+    ) -> Option<Arc<FcmClient>> {
+        match FcmClient::new(credentials_path, project_id) {
+            Ok(client) => {
+                info!(
+                    tenant_id = %tenant_id,
+                    project_id = %project_id,
+                    environment = environment.as_str(),
+                    "Tenant FCM client initialized"
+                );
+                Some(Arc::new(client.with_endpoints(&self.fcm_base_url, &self.fcm_token_url)))
+            }
+            Err(e) => {
+                // Never fall back to another tenant's project: push stays off for this tenant
+                error!(
+                    tenant_id = %tenant_id,
+                    environment = environment.as_str(),
+                    error = %e,
+                    "Failed to initialize tenant FCM client"
+                );
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use crate::config::{Config, PushEnvironment};
+use crate::db::{NotificationQueries, PreferenceQueries};
+use crate::dev_sandbox::DevSandbox;
+use crate::i18n::Localizer;
+use crate::models::{DeviceCapabilities, Notification};
+use crate::push::fcm::{mask_token, FcmError};
+use crate::protocol::Protocols;
+use crate::push::FcmClient;
+use crate::realtime::RealtimeBus;
+use crate::templates::{TemplateLookups, TemplateRenderer};
+use crate::worker::devices::push_environment;
+use crate::worker::tenants::{TenantContext, TenantRegistry};
+use crate::worker::Channel;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
+
+/// Who a test send goes to
+#[derive(Debug, Clone)]
+pub enum TestTarget {
+    /// The user's Bus connections and every registered device
+    User(Uuid),
+    /// One device token, registered or not
+    Token { fcm_token: String, environment: PushEnvironment },
+}
+
+/// Renders a notification the way the worker does and sends it to one target only
+///
+/// Nothing is stored: no row, no attempts, no receipts. Preferences, quiet hours, snooze
+/// and experiments don't apply; the point is to see the payload arrive on a device.
+pub struct TestSender {
+    pool: PgPool,
+    tenants: TenantRegistry,
+    bus_client: Option<Arc<dyn RealtimeBus>>,
+    localizer: Localizer,
+    templates: TemplateRenderer,
+    bus_protobuf: bool,
+    protocols: Protocols,
+    /// Devices without their own push_environment
+    default_environment: PushEnvironment,
+    simulate: bool,
+    /// Render the Bus envelope of user targets without a Bus client too
+    bus_preview: bool,
+}
+
+/// What was (or would be) sent
+#[derive(Debug, Serialize)]
+pub struct TestSendReport {
+    pub id: Uuid,
+    pub dry_run: bool,
+    /// Bus envelope for a user target (None for a token, or without a Bus)
+    pub bus: Option<BusPreview>,
+    /// One entry per device
+    pub push: Vec<PushPreview>,
+    /// Rendering problems the worker would silently fall back from
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BusPreview {
+    pub topic: String,
+    pub payload: Value,
+    /// delivered / simulated / failed (absent on a dry run)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<&'static str>,
+    /// Connections the publish reached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivered_to: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PushPreview {
+    /// Masked for registered devices
+    pub token: String,
+    pub push_environment: &'static str,
+    pub locale: Option<String>,
+    /// FCM v1 request body
+    pub payload: Value,
+    /// delivered / simulated / invalid_token / failed (absent on a dry run)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Message name FCM assigned to a delivered push
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider_message_id: Option<String>,
+}
+
+impl TestSender {
+    pub fn new(
+        pool: PgPool,
+        config: &Config,
+        bus_client: Option<Arc<dyn RealtimeBus>>,
+        fcm_client: Option<Arc<FcmClient>>,
+    ) -> Self {
+        let tenants = TenantRegistry::new(pool.clone(), fcm_client)
+            .with_fcm_endpoints(&config.fcm_base_url, &config.fcm_token_url);
+        Self {
+            localizer: Localizer::new(),
+            templates: TemplateRenderer::new(pool.clone()),
+            pool,
+            tenants,
+            bus_client,
+            bus_protobuf: config.bus_protobuf,
+            protocols: Protocols::default(),
+            default_environment: config.push_environment,
+            simulate: config.is_simulated(),
+            bus_preview: false,
+        }
+    }
+
+    /// FCM client for sandbox devices (see `NotificationWorker::with_sandbox_fcm`)
+    pub fn with_sandbox_fcm(mut self, fcm_sandbox: Option<Arc<FcmClient>>) -> Self {
+        self.tenants = self.tenants.with_sandbox_fcm(fcm_sandbox);
+        self
+    }
+
+    /// Developer sandbox tenant, pushed to its mock FCM (see `NotificationWorker::with_dev_sandbox`)
+    pub fn with_dev_sandbox(mut self, dev_sandbox: Option<Arc<DevSandbox>>) -> Self {
+        self.tenants = self.tenants.with_dev_sandbox(dev_sandbox);
+        self
+    }
+
+    /// Protocol versions the Bus messages are published in (WS_PROTOCOLS)
+    pub fn with_protocols(mut self, protocols: Protocols) -> Self {
+        self.protocols = protocols;
+        self
+    }
+
+    /// Actor/target template variables (see `NotificationWorker::with_template_lookups`)
+    pub fn with_template_lookups(mut self, lookups: Option<TemplateLookups>) -> Self {
+        self.templates = self.templates.with_lookups(lookups);
+        self
+    }
+
+    /// Dry runs include the Bus envelope even without a Bus (replay compares it)
+    pub fn with_bus_preview(mut self) -> Self {
+        self.bus_preview = true;
+        self
+    }
+
+    /// Environment of a token target without one (PUSH_ENVIRONMENT)
+    pub fn default_environment(&self) -> PushEnvironment {
+        self.default_environment
+    }
+
+    /// Render for the target and send, or only build the payloads when `dry_run`
+    ///
+    /// `locale` overrides the device and user locale.
+    #[instrument(skip(self, notification), fields(id = %notification.id, dry_run = dry_run))]
+    pub async fn send(
+        &self,
+        notification: &Notification,
+        target: &TestTarget,
+        locale: Option<&str>,
+        dry_run: bool,
+    ) -> Result<TestSendReport, sqlx::Error> {
+        let tenant = self.tenants.resolve(&notification.tenant_id).await;
+        let mut report = TestSendReport {
+            id: notification.id,
+            dry_run,
+            bus: None,
+            push: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        match target {
+            TestTarget::Token { fcm_token, environment } => {
+                let rendered = self.render(notification, locale, Channel::Push, &mut report.warnings).await;
+                let mut preview = PushPreview {
+                    token: fcm_token.clone(),
+                    push_environment: environment.as_str(),
+                    locale: locale.map(str::to_string),
+                    payload: FcmClient::request_preview(fcm_token, &rendered),
+                    outcome: None,
+                    error: None,
+                    provider_message_id: None,
+                };
+                if !dry_run {
+                    self.push(&tenant, fcm_token, *environment, &rendered, &DeviceCapabilities::default(), &mut preview).await;
+                }
+                report.push.push(preview);
+            }
+            TestTarget::User(user_id) => {
+                let user_locale = match locale {
+                    Some(locale) => Some(locale.to_string()),
+                    None => PreferenceQueries::get_locale(&self.pool, &notification.tenant_id, *user_id).await?,
+                };
+                let mut notification = notification.clone();
+                notification.user_id = *user_id;
+
+                if self.bus_client.is_some() || self.bus_preview {
+                    let rendered = self
+                        .render(&notification, user_locale.as_deref(), Channel::Bus, &mut report.warnings)
+                        .await;
+                    let mut preview = BusPreview {
+                        topic: tenant.topic("notifications"),
+                        payload: if self.bus_protobuf {
+                            rendered.bus_payload_protobuf()
+                        } else {
+                            rendered.bus_payload()
+                        },
+                        outcome: None,
+                        delivered_to: None,
+                        error: None,
+                    };
+                    if let Some(bus) = self.bus_client.as_deref().filter(|_| !dry_run) {
+                        self.publish(bus, *user_id, &mut preview).await;
+                    }
+                    report.bus = Some(preview);
+                }
+
+                let devices = NotificationQueries::get_user_devices(&self.pool, &notification.tenant_id, *user_id).await?;
+                for device in devices.iter() {
+                    // Same precedence as the worker, with the requested locale on top
+                    let device_locale = locale.map(str::to_string).or_else(|| device.locale.clone()).or_else(|| user_locale.clone());
+                    let rendered = self
+                        .render(&notification, device_locale.as_deref(), Channel::Push, &mut report.warnings)
+                        .await;
+                    let environment = push_environment(device, self.default_environment);
+                    let token = mask_token(&device.fcm_token);
+                    let capabilities = device.capabilities();
+                    let mut preview = PushPreview {
+                        payload: FcmClient::request_preview_for(&token, &rendered, &capabilities),
+                        token,
+                        push_environment: environment.as_str(),
+                        locale: device_locale,
+                        outcome: None,
+                        error: None,
+                        provider_message_id: None,
+                    };
+                    if !dry_run {
+                        self.push(&tenant, &device.fcm_token, environment, &rendered, &capabilities, &mut preview).await;
+                    }
+                    report.push.push(preview);
+                }
+            }
+        }
+
+        info!(
+            bus = report.bus.is_some(),
+            devices = report.push.len(),
+            "🧪 Test notification {}",
+            if dry_run { "rendered" } else { "sent" }
+        );
+        metrics::counter!("notifications_test_sends_total", "dry_run" => dry_run.to_string()).increment(1);
+        Ok(report)
+    }
+
+    /// Template, else Fluent, else the literal text - like the worker, but the fallback is reported
+    async fn render(
+        &self,
+        notification: &Notification,
+        locale: Option<&str>,
+        channel: Channel,
+        warnings: &mut Vec<String>,
+    ) -> Notification {
+        if let Some(template_key) = &notification.template_key {
+            match self.templates.render(notification, template_key, locale, channel.as_str()).await {
+                Ok(rendered_template) => {
+                    let mut rendered = notification.clone();
+                    rendered.title = rendered_template.text.title;
+                    rendered.message = Some(rendered_template.text.body);
+                    rendered.template_version = Some(rendered_template.version);
+                    return rendered;
+                }
+                Err(e) => {
+                    let warning = format!("Template '{}' ({}): {}", template_key, channel.as_str(), e);
+                    if !warnings.contains(&warning) {
+                        warnings.push(warning);
+                    }
+                }
+            }
+        }
+        self.localizer.localize(notification, locale).into_owned()
+    }
+
+    async fn publish(&self, bus: &dyn RealtimeBus, user_id: Uuid, preview: &mut BusPreview) {
+        if self.simulate {
+            preview.outcome = Some("simulated");
+            return;
+        }
+        match self.protocols.publish_to_user(bus, user_id, &preview.topic, "notification", &preview.payload).await {
+            Ok(delivered_to) => {
+                preview.outcome = Some("delivered");
+                preview.delivered_to = Some(delivered_to);
+            }
+            Err(e) => {
+                warn!(user_id = %user_id, error = %e, "Test notification: Bus publish failed");
+                preview.outcome = Some("failed");
+                preview.error = Some(e.to_string());
+            }
+        }
+    }
+
+    async fn push(
+        &self,
+        tenant: &TenantContext,
+        fcm_token: &str,
+        environment: PushEnvironment,
+        rendered: &Notification,
+        capabilities: &DeviceCapabilities,
+        preview: &mut PushPreview,
+    ) {
+        if self.simulate {
+            preview.outcome = Some("simulated");
+            return;
+        }
+        let result = match tenant.fcm_for(environment) {
+            Some(fcm) => fcm.send_prepared(fcm_token, &FcmClient::prepare_for(rendered, capabilities)).await,
+            None => Err(FcmError::NotInitialized),
+        };
+        match result {
+            Ok(message_name) => {
+                preview.outcome = Some("delivered");
+                preview.provider_message_id = message_name;
+            }
+            // Unlike the worker, a test send leaves the token registered
+            Err(FcmError::InvalidToken) => {
+                debug!(token = %mask_token(fcm_token), "Test notification: invalid token");
+                preview.outcome = Some("invalid_token");
+            }
+            Err(e) => {
+                warn!(token = %mask_token(fcm_token), error = %e, "Test notification: push failed");
+                preview.outcome = Some("failed");
+                preview.error = Some(e.to_string());
+            }
+        }
+    }
+}
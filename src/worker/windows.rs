@@ -0,0 +1,96 @@
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+/// Local daily window per notification type (DELIVERY_WINDOWS), e.g. `marketing=09:00-20:00`
+///
+/// Outside its window a notification is deferred to the next window start in the recipient's
+/// timezone (`user_notification_settings.timezone`, else DELIVERY_WINDOW_TIMEZONE).
+#[derive(Debug, Clone, Default)]
+pub struct DeliveryWindows {
+    windows: HashMap<String, DeliveryWindow>,
+}
+
+/// `[start, end)` local time; `start > end` wraps midnight (e.g. 18:00-02:00)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeliveryWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl DeliveryWindows {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut windows = HashMap::new();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let invalid = || format!("Invalid DELIVERY_WINDOWS entry '{}', expected e.g. 'marketing=09:00-20:00'", entry);
+            let (notification_type, range) = entry.split_once('=').ok_or_else(invalid)?;
+            let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+            let time = |value: &str| NaiveTime::parse_from_str(value.trim(), "%H:%M").map_err(|_| invalid());
+            let window = DeliveryWindow { start: time(start)?, end: time(end)? };
+            if window.start == window.end {
+                return Err(format!("DELIVERY_WINDOWS '{}': start and end must differ", entry));
+            }
+            if windows.insert(notification_type.trim().to_string(), window).is_some() {
+                return Err(format!("Type '{}' appears twice in DELIVERY_WINDOWS", notification_type.trim()));
+            }
+        }
+        Ok(Self { windows })
+    }
+
+    pub fn get(&self, notification_type: &str) -> Option<DeliveryWindow> {
+        self.windows.get(notification_type).copied()
+    }
+}
+
+impl DeliveryWindow {
+    /// Next window start when `now` falls outside the window - None means deliver
+    pub fn closed_until(&self, tz: Tz, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        // Closed from the window's end until its next start
+        held_until(self.end, self.start, tz, now)
+    }
+}
+
+/// When `now` is inside the local daily span `[from, to)`: the instant `to` comes around
+///
+/// Shared by delivery windows and device quiet hours. A `to` inside a DST gap resumes an
+/// hour later.
+pub fn held_until(from: NaiveTime, to: NaiveTime, tz: Tz, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let local = now.with_timezone(&tz).naive_local();
+    let time = local.time();
+    let inside = if from <= to {
+        time >= from && time < to
+    } else {
+        // Wraps midnight, e.g. 22:00-07:00
+        time >= from || time < to
+    };
+    if !inside {
+        return None;
+    }
+
+    let mut until = local.date().and_time(to);
+    if until <= local {
+        until += ChronoDuration::days(1);
+    }
+    let until = tz
+        .from_local_datetime(&until)
+        .earliest()
+        .map(|until| until.with_timezone(&Utc))
+        .unwrap_or_else(|| now + ChronoDuration::hours(1));
+    Some(until)
+}
+
+/// The instant a wall-clock time (`deliver_at_local`) happens in `tz`
+///
+/// When clocks go back the time occurs twice and the first one wins; when they jump forward
+/// it doesn't occur at all and the offset from before the jump applies (02:30 in a 02:00-03:00
+/// gap is 03:30).
+pub fn resolve_local(local: NaiveDateTime, tz: Tz) -> DateTime<Utc> {
+    match tz.from_local_datetime(&local).earliest() {
+        Some(at) => at.with_timezone(&Utc),
+        None => {
+            // Transitions are months apart, so a day earlier is before the gap
+            let before = tz.offset_from_utc_datetime(&(local - ChronoDuration::days(1))).fix();
+            Utc.from_utc_datetime(&(local - ChronoDuration::seconds(before.local_minus_utc() as i64)))
+        }
+    }
+}
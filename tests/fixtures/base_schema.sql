@@ -0,0 +1,31 @@
+-- Base schema the migrations build on.
+-- In production these tables are owned by the shared activitydb schema; this fixture
+-- recreates only what notifications-service uses, so tests can start from an empty
+-- Postgres and apply migrations/*.sql on top.
+
+CREATE SCHEMA IF NOT EXISTS activity;
+
+CREATE TABLE IF NOT EXISTS activity.notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    actor_user_id UUID,
+    notification_type TEXT NOT NULL,
+    target_type TEXT,
+    target_id UUID,
+    title TEXT NOT NULL,
+    message TEXT,
+    payload JSONB,
+    deep_link TEXT,
+    priority TEXT DEFAULT 'normal',
+    is_processed BOOLEAN NOT NULL DEFAULT false,
+    deliver_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS activity.user_devices (
+    user_id UUID NOT NULL,
+    fcm_token TEXT NOT NULL UNIQUE,
+    device_type TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
@@ -0,0 +1,350 @@
+//! Integration test harness: a throwaway Postgres (testcontainers) with the
+//! migrations applied and the service running in-process against it.
+
+pub mod vault;
+
+use chrono::{DateTime, Utc};
+use notifications_service::config::Config;
+use notifications_service::db::Database;
+use notifications_service::push::FcmClient;
+use notifications_service::queue::QueueStore;
+use notifications_service::realtime::RealtimeBus;
+use notifications_service::shadow::ShadowQueue;
+use notifications_service::Service;
+use sqlx::PgPool;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use testcontainers_modules::postgres::Postgres;
+use testcontainers_modules::testcontainers::runners::AsyncRunner;
+use testcontainers_modules::testcontainers::ContainerAsync;
+use testcontainers_modules::testcontainers::ImageExt;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+/// Builds the queue store for `Launch::queue_store` from the test database and config
+pub type QueueFactory = Box<dyn FnOnce(&Database, &Config) -> Arc<dyn QueueStore>>;
+
+/// Attempts before the worker gives up on a notification
+pub const MAX_RETRIES: i32 = 3;
+
+/// Schema the migrations ALTER (owned by activitydb in production)
+const BASE_SCHEMA: &str = include_str!("../fixtures/base_schema.sql");
+/// User JWT secret for tests that enable the user API (`config.jwt_secret`)
+pub const JWT_SECRET: &str = "test-jwt-secret";
+
+pub struct TestService {
+    pub pool: PgPool,
+    /// `http://127.0.0.1:<port>` of the in-process HTTP server
+    pub base_url: String,
+    shutdown: Option<oneshot::Sender<()>>,
+    service: Option<JoinHandle<Result<(), String>>>,
+    // Dropped last: removes the container
+    _postgres: ContainerAsync<Postgres>,
+}
+
+/// Row for `insert_notification`; start from `TestNotification::new(..)`
+pub struct TestNotification<'a> {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub tenant_id: &'a str,
+    pub notification_type: &'a str,
+    pub title: &'a str,
+    pub message: &'a str,
+    pub priority: &'a str,
+    pub deliver_at: Option<DateTime<Utc>>,
+    pub payload: Option<serde_json::Value>,
+}
+
+impl<'a> TestNotification<'a> {
+    pub fn new(user_id: Uuid, notification_type: &'a str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            tenant_id: "default",
+            notification_type,
+            title: "Rust E2E Test",
+            message: "Integration test notification",
+            priority: "normal",
+            deliver_at: None,
+            payload: None,
+        }
+    }
+}
+
+/// Test doubles the service is built with; start from `Launch::default()`
+#[derive(Default)]
+pub struct Launch {
+    /// Pushes go here (e.g. `MockFcm::client`)
+    pub push_provider: Option<Arc<FcmClient>>,
+    /// Bus publishes go here (e.g. a `MemoryBus`)
+    pub bus: Option<Arc<dyn RealtimeBus>>,
+    /// Dual writes go here (e.g. a `MemoryShadowQueue`)
+    pub shadow_queue: Option<Arc<dyn ShadowQueue>>,
+    /// Due notifications come from the store this builds
+    pub queue_store: Option<QueueFactory>,
+}
+
+impl TestService {
+    /// Start Postgres, apply migrations and run the service with a test config
+    pub async fn start() -> Self {
+        Self::start_with(|_| {}).await
+    }
+
+    /// Like `start`, with a hook to adjust the config before the service is built
+    pub async fn start_with(configure: impl FnOnce(&mut Config)) -> Self {
+        Self::launch(Launch::default(), configure).await
+    }
+
+    /// Like `start`, with pushes going to `push_provider` (e.g. `MockFcm::client`)
+    pub async fn start_with_push(push_provider: Arc<FcmClient>) -> Self {
+        Self::start_with_push_and(push_provider, |_| {}).await
+    }
+
+    /// `start_with_push` and `start_with` combined
+    pub async fn start_with_push_and(push_provider: Arc<FcmClient>, configure: impl FnOnce(&mut Config)) -> Self {
+        Self::launch(Launch { push_provider: Some(push_provider), ..Default::default() }, configure).await
+    }
+
+    /// Like `start_with`, with the test doubles in `launch`:
+    /// `TestService::launch(Launch { bus: Some(bus), ..Default::default() }, |config| ..)`
+    pub async fn launch(launch: Launch, configure: impl FnOnce(&mut Config)) -> Self {
+        // wal_level=logical for the replication wake source (WAKE_SOURCE=replication)
+        let postgres = Postgres::default()
+            .with_cmd(["postgres", "-c", "wal_level=logical"])
+            .start()
+            .await
+            .expect("Failed to start Postgres container");
+        let host = postgres.get_host().await.expect("Failed to get container host");
+        let port = postgres.get_host_port_ipv4(5432).await.expect("Failed to get Postgres port");
+        let database_url = format!("postgres://postgres:postgres@{}:{}/postgres", host, port);
+
+        let db = Database::connect(&database_url).await.expect("Failed to connect to test DB");
+        migrate(db.pool()).await;
+
+        let mut config = test_config(database_url, free_port());
+        configure(&mut config);
+
+        let mut builder = Service::builder().config(config.clone()).database(db.clone());
+        if let Some(push_provider) = launch.push_provider {
+            builder = builder.push_provider(push_provider);
+        }
+        if let Some(bus) = launch.bus {
+            builder = builder.bus_client(bus);
+        }
+        if let Some(queue) = launch.shadow_queue {
+            builder = builder.shadow_queue(queue);
+        }
+        if let Some(store) = launch.queue_store {
+            builder = builder.queue_store(store(&db, &config));
+        }
+        let service = builder.build().expect("Failed to build service");
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let handle = tokio::spawn(service.run(async {
+            let _ = shutdown_rx.await;
+        }));
+
+        let test_service = Self {
+            pool: db.pool().clone(),
+            base_url: format!("http://{}", config.server_addr()),
+            shutdown: Some(shutdown_tx),
+            service: Some(handle),
+            _postgres: postgres,
+        };
+        test_service.wait_until_ready().await;
+        test_service
+    }
+
+    /// Insert a row into activity.notifications (fires the NOTIFY trigger)
+    pub async fn insert_notification(&self, notification: TestNotification<'_>) -> Uuid {
+        sqlx::query(
+            "INSERT INTO activity.notifications
+                (id, tenant_id, user_id, title, message, notification_type, priority, deliver_at, payload)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, COALESCE($8, now()), $9)",
+        )
+        .bind(notification.id)
+        .bind(notification.tenant_id)
+        .bind(notification.user_id)
+        .bind(notification.title)
+        .bind(notification.message)
+        .bind(notification.notification_type)
+        .bind(notification.priority)
+        .bind(notification.deliver_at)
+        .bind(notification.payload)
+        .execute(&self.pool)
+        .await
+        .expect("Failed to insert test notification");
+
+        notification.id
+    }
+
+    /// Register an FCM device token for a user (default tenant)
+    pub async fn insert_device(&self, user_id: Uuid, fcm_token: &str) {
+        sqlx::query("INSERT INTO activity.user_devices (user_id, fcm_token, device_type) VALUES ($1, $2, 'android')")
+            .bind(user_id)
+            .bind(fcm_token)
+            .execute(&self.pool)
+            .await
+            .expect("Failed to insert test device");
+    }
+
+    /// User API token for `user_id`, valid for an hour (start with `config.jwt_secret = JWT_SECRET`)
+    pub fn user_token(&self, user_id: Uuid) -> String {
+        jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({ "sub": user_id.to_string(), "exp": Utc::now().timestamp() + 3600 }),
+            &jsonwebtoken::EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+        )
+        .expect("Failed to sign token")
+    }
+
+    /// Operator token carrying a management `role` claim (start with `config.jwt_secret = JWT_SECRET`)
+    pub fn role_token(&self, role: &str) -> String {
+        jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({ "sub": Uuid::new_v4().to_string(), "role": role, "exp": Utc::now().timestamp() + 3600 }),
+            &jsonwebtoken::EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+        )
+        .expect("Failed to sign token")
+    }
+
+    /// Issue an API key through the admin API; `request` is a `CreateApiKeyRequest` body. Returns (id, key)
+    pub async fn create_api_key(&self, request: serde_json::Value) -> (Uuid, String) {
+        let response = reqwest::Client::new()
+            .post(format!("{}/api/v1/api-keys", self.base_url))
+            .bearer_auth("test-admin-token")
+            .json(&request)
+            .send()
+            .await
+            .expect("Failed to create API key");
+        assert_eq!(response.status(), 201, "API key was not created");
+        let body: serde_json::Value = response.json().await.expect("Invalid JSON");
+        let id = serde_json::from_value(body["api_key"]["id"].clone()).expect("No API key id");
+        (id, body["key"].as_str().expect("No key").to_string())
+    }
+
+    /// Poll until the worker marked the notification processed
+    pub async fn wait_for_processed(&self, id: Uuid, timeout_secs: u64) -> bool {
+        let start = std::time::Instant::now();
+        while start.elapsed().as_secs() < timeout_secs {
+            let row: (bool,) = sqlx::query_as("SELECT is_processed FROM activity.notifications WHERE id = $1")
+                .bind(id)
+                .fetch_one(&self.pool)
+                .await
+                .unwrap_or((false,));
+
+            if row.0 {
+                return true;
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+        false
+    }
+
+    /// Stop the service and wait for it to finish (otherwise it stops with the test runtime)
+    ///
+    /// The database stays up, so a test can check what the shutdown left behind.
+    pub async fn shutdown(&mut self) {
+        if let Some(tx) = self.shutdown.take() {
+            let _ = tx.send(());
+        }
+        if let Some(handle) = self.service.take() {
+            let result = handle.await.expect("Service task panicked");
+            assert!(result.is_ok(), "Service stopped with an error: {:?}", result);
+        }
+    }
+
+    async fn wait_until_ready(&self) {
+        let url = format!("{}/health", self.base_url);
+        let client = reqwest::Client::new();
+        for _ in 0..50 {
+            if client.get(&url).send().await.is_ok_and(|r| r.status().is_success()) {
+                return;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        panic!("Service did not become ready at {}", url);
+    }
+}
+
+/// Base schema + migrations/*.sql in order
+async fn migrate(pool: &PgPool) {
+    sqlx::raw_sql(BASE_SCHEMA)
+        .execute(pool)
+        .await
+        .expect("Failed to create base schema");
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations");
+    let mut migrations: Vec<_> = std::fs::read_dir(&dir)
+        .expect("Failed to read migrations directory")
+        .map(|entry| entry.expect("Failed to read migration").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+        .collect();
+    migrations.sort();
+
+    for path in migrations {
+        let sql = std::fs::read_to_string(&path).expect("Failed to read migration");
+        sqlx::raw_sql(&sql)
+            .execute(pool)
+            .await
+            .unwrap_or_else(|e| panic!("Migration {} failed: {}", path.display(), e));
+    }
+}
+
+/// Config independent of the environment: local HTTP only, no Bus/FCM/sources
+fn test_config(database_url: String, port: u16) -> Config {
+    let mut config = Config::from_env();
+    config.database_url = database_url;
+    config.database_credentials = None;
+    config.vault = None;
+    config.server_host = "127.0.0.1".to_string();
+    config.server_port = port;
+    config.jwt_secret = None;
+    config.admin_token = Some("test-admin-token".to_string());
+    config.mtls = None;
+    config.admin_allowed_cidrs = None;
+    config.ingest_allowed_cidrs = None;
+    config.receipt_signing_secret = None;
+    config.websocket_bus_url = None;
+    config.service_token = None;
+    config.bus_events_topic = None;
+    config.broadcast_signing_key = None;
+    config.grpc_port = None;
+    config.fcm_project_id = None;
+    config.fcm_credentials_path = None;
+    config.fcm_credentials = None;
+    config.template_actor_query = None;
+    config.template_target_query = None;
+    config.leader_election = false;
+    config.policy_hook_url = None;
+    config.shadow_kafka = None;
+    config.limiter_redis_url = None;
+    config.worker_poll_interval_secs = 1;
+    // Tests rely on the one-second poll, don't let an empty queue stretch it
+    config.worker_poll_max_interval_secs = 1;
+    config.recurring_poll_interval_secs = 1;
+    config.campaign_poll_interval_secs = 1;
+    // Tests roll up with AnalyticsQueries::roll_up when they need to
+    config.analytics_interval_secs = 0;
+    config.max_retries = MAX_RETRIES;
+    config.kafka = None;
+    config.nats = None;
+    config.sqs = None;
+    config
+}
+
+/// Port nothing listens on right now (for extra listeners such as GRPC_PORT)
+pub fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("Failed to find a free port")
+}
+
+/// Path of a test certificate or key under tests/fixtures/mtls
+pub fn mtls_fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/mtls/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
@@ -0,0 +1,133 @@
+//! Mock of the Vault HTTP API: a KV v1 and a KV v2 secret, a database secrets engine
+//! that issues a new user per read, and lease renewal that can be made to fail.
+
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use notifications_service::config::VaultConfig;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::net::TcpListener;
+
+const TOKEN: &str = "test-vault-token";
+
+#[derive(Default)]
+struct VaultState {
+    /// Credentials issued by `database/creds/<role>`, numbering the usernames
+    issued: AtomicUsize,
+    /// Lease of issued and renewed credentials (`database/creds/zero` always gets 0)
+    lease_secs: AtomicU64,
+    fail_renewals: AtomicBool,
+    /// Lease id and time of every renew call
+    renewals: Mutex<Vec<(String, Instant)>>,
+}
+
+pub struct MockVault {
+    addr: String,
+    state: Arc<VaultState>,
+}
+
+impl MockVault {
+    pub async fn start() -> Self {
+        let state = Arc::new(VaultState { lease_secs: AtomicU64::new(3600), ..Default::default() });
+        let router = Router::new()
+            .route("/v1/secret/data/app", get(kv_v2))
+            .route("/v1/kv/app", get(kv_v1))
+            .route("/v1/database/creds/:role", get(issue))
+            .route("/v1/sys/leases/renew", put(renew))
+            .with_state(state.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind mock Vault");
+        let addr = format!("http://{}", listener.local_addr().expect("No address"));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        Self { addr, state }
+    }
+
+    /// VAULT_ADDR / VAULT_TOKEN for a `SecretResolver`
+    pub fn config(&self) -> VaultConfig {
+        VaultConfig { addr: self.addr.clone(), token: TOKEN.to_string(), namespace: None }
+    }
+
+    pub fn set_lease_secs(&self, secs: u64) {
+        self.state.lease_secs.store(secs, Ordering::SeqCst);
+    }
+
+    /// Answer every renew call with a 500 from now on
+    pub fn fail_renewals(&self) {
+        self.state.fail_renewals.store(true, Ordering::SeqCst);
+    }
+
+    pub fn renewals(&self) -> Vec<(String, Instant)> {
+        self.state.renewals.lock().expect("mock lock poisoned").clone()
+    }
+}
+
+/// The 403 Vault answers without the right X-Vault-Token
+fn denied(headers: &HeaderMap) -> Option<Response> {
+    match headers.get("x-vault-token").and_then(|v| v.to_str().ok()) {
+        Some(TOKEN) => None,
+        _ => Some((StatusCode::FORBIDDEN, Json(json!({ "errors": ["permission denied"] }))).into_response()),
+    }
+}
+
+async fn kv_v2(headers: HeaderMap) -> Response {
+    if let Some(denied) = denied(&headers) {
+        return denied;
+    }
+    Json(json!({
+        "lease_id": "",
+        "lease_duration": 0,
+        "renewable": false,
+        "data": {
+            "data": { "username": "app", "password": "kv2-password" },
+            "metadata": { "version": 3 },
+        },
+    }))
+    .into_response()
+}
+
+async fn kv_v1(headers: HeaderMap) -> Response {
+    if let Some(denied) = denied(&headers) {
+        return denied;
+    }
+    Json(json!({ "lease_duration": 2764800, "data": { "token": "kv1-token" } })).into_response()
+}
+
+async fn issue(State(state): State<Arc<VaultState>>, Path(role): Path<String>, headers: HeaderMap) -> Response {
+    if let Some(denied) = denied(&headers) {
+        return denied;
+    }
+    let n = state.issued.fetch_add(1, Ordering::SeqCst) + 1;
+    let lease_secs = if role == "zero" { 0 } else { state.lease_secs.load(Ordering::SeqCst) };
+    Json(json!({
+        "lease_id": format!("database/creds/{}/{}", role, n),
+        "lease_duration": lease_secs,
+        "renewable": true,
+        "data": { "username": format!("v-{}-{}", role, n), "password": format!("password-{}", n) },
+    }))
+    .into_response()
+}
+
+async fn renew(State(state): State<Arc<VaultState>>, headers: HeaderMap, Json(body): Json<Value>) -> Response {
+    if let Some(denied) = denied(&headers) {
+        return denied;
+    }
+    let lease_id = body["lease_id"].as_str().unwrap_or_default().to_string();
+    state
+        .renewals
+        .lock()
+        .expect("mock lock poisoned")
+        .push((lease_id.clone(), Instant::now()));
+    if state.fail_renewals.load(Ordering::SeqCst) {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "errors": ["lease not found"] }))).into_response();
+    }
+    Json(json!({
+        "lease_id": lease_id,
+        "lease_duration": state.lease_secs.load(Ordering::SeqCst),
+        "renewable": true,
+    }))
+    .into_response()
+}
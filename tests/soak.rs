@@ -0,0 +1,638 @@
+//! Soak test: hours of mixed load against the service, with invariants checked while it runs.
+//!
+//! `SOAK_DURATION_SECS=14400 cargo test --release --test soak` (needs a Docker daemon, like
+//! the integration tests). Without SOAK_DURATION_SECS it is skipped, so `cargo test` stays fast.
+//!
+//! Load, all at once: notification creates through the API (single and batch, every
+//! priority), Bus connections coming and going, device token refreshes and re-registrations,
+//! and every SOAK_FAULT_EVERY_SECS a fault window: FCM answering 500, FCM answering 429, a Bus
+//! outage or a handful of tokens turning UNREGISTERED.
+//!
+//! Invariants, checked every SOAK_CHECK_SECS; the first violation fails the run:
+//! - no duplicate deliveries: a notification reaches a device token or Bus topic at most once
+//! - no lost notifications: SOAK_SETTLE_SECS after its creation a notification is processed,
+//!   and was delivered, suppressed or failed with its error recorded
+//! - bounded memory: from SOAK_WARMUP_SECS on, the resident size of the process (service and
+//!   driver, Linux only) stays within SOAK_MAX_RSS_GROWTH_MB of what it was then
+
+// Shared with the integration tests, which use the rest of it
+#[allow(dead_code)]
+mod harness;
+
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use harness::{Launch, TestService};
+use notifications_service::push::mock::{MockFcm, MockResponse};
+use notifications_service::realtime::MemoryBus;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::time::{interval, sleep, MissedTickBehavior};
+use uuid::Uuid;
+
+const JWT_SECRET: &str = "soak-jwt-secret";
+const NOTIFICATION_TYPE: &str = "soak";
+const PRIORITIES: [&str; 3] = ["high", "normal", "low"];
+/// How long a fault lasts
+const FAULT_WINDOW: Duration = Duration::from_secs(10);
+
+struct Settings {
+    duration: Duration,
+    users: usize,
+    creates_per_sec: usize,
+    check_every: Duration,
+    settle: Duration,
+    warmup: Duration,
+    max_rss_growth_mb: u64,
+    fault_every: Duration,
+    seed: u64,
+}
+
+impl Settings {
+    /// None = SOAK_DURATION_SECS not set
+    fn from_env() -> Option<Self> {
+        Some(Self {
+            duration: Duration::from_secs(env_u64("SOAK_DURATION_SECS")?),
+            users: env_u64("SOAK_USERS").unwrap_or(200).max(1) as usize,
+            creates_per_sec: env_u64("SOAK_CREATES_PER_SEC").unwrap_or(20) as usize,
+            check_every: Duration::from_secs(env_u64("SOAK_CHECK_SECS").unwrap_or(30).max(1)),
+            settle: Duration::from_secs(env_u64("SOAK_SETTLE_SECS").unwrap_or(300)),
+            warmup: Duration::from_secs(env_u64("SOAK_WARMUP_SECS").unwrap_or(300)),
+            max_rss_growth_mb: env_u64("SOAK_MAX_RSS_GROWTH_MB").unwrap_or(128),
+            fault_every: Duration::from_secs(env_u64("SOAK_FAULT_EVERY_SECS").unwrap_or(60).max(1)),
+            seed: env_u64("SOAK_SEED").unwrap_or_else(|| Utc::now().timestamp_micros() as u64),
+        })
+    }
+}
+
+fn env_u64(name: &str) -> Option<u64> {
+    let value = std::env::var(name).ok()?;
+    Some(value.parse().unwrap_or_else(|_| panic!("{} must be a number, got '{}'", name, value)))
+}
+
+fn main() {
+    let Some(settings) = Settings::from_env() else {
+        println!("soak: skipped (set SOAK_DURATION_SECS to run)");
+        return;
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "warn".into()),
+        )
+        .init();
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to start runtime");
+    if let Err(e) = runtime.block_on(run(settings)) {
+        eprintln!("soak: FAILED\n{}", e);
+        std::process::exit(1);
+    }
+    println!("soak: passed");
+}
+
+async fn run(settings: Settings) -> Result<(), String> {
+    let fcm = MockFcm::start().await.map_err(|e| format!("Failed to start mock FCM: {}", e))?;
+    let bus = MemoryBus::new();
+    let launch = Launch {
+        push_provider: Some(Arc::new(fcm.client("soak-project"))),
+        bus: Some(Arc::new(bus.clone())),
+        ..Default::default()
+    };
+    let mut service = TestService::launch(launch, |config| {
+        config.jwt_secret = Some(JWT_SECRET.to_string());
+    })
+    .await;
+    let api = Api::new(&service.base_url);
+    let users = Arc::new(register_users(&api, settings.users).await?);
+    println!(
+        "soak: {} users, {} creates/s for {}s (SOAK_SEED={})",
+        users.len(),
+        settings.creates_per_sec,
+        settings.duration.as_secs(),
+        settings.seed
+    );
+
+    let running = Arc::new(AtomicBool::new(true));
+    let counters = Arc::new(Counters::default());
+    let load = vec![
+        tokio::spawn(create_load(
+            api.clone(),
+            users.clone(),
+            settings.creates_per_sec,
+            Rng::new(settings.seed),
+            running.clone(),
+            counters.clone(),
+        )),
+        tokio::spawn(connection_churn(
+            bus.clone(),
+            users.clone(),
+            Rng::new(settings.seed + 1),
+            running.clone(),
+            counters.clone(),
+        )),
+        tokio::spawn(token_churn(
+            api.clone(),
+            users.clone(),
+            Rng::new(settings.seed + 2),
+            running.clone(),
+            counters.clone(),
+        )),
+        tokio::spawn(faults(
+            fcm.clone(),
+            bus.clone(),
+            users.clone(),
+            settings.fault_every,
+            Rng::new(settings.seed + 3),
+            running.clone(),
+        )),
+    ];
+
+    let started = Instant::now();
+    let mut invariants = Invariants::default();
+    let mut result = Ok(());
+    while started.elapsed() < settings.duration && result.is_ok() {
+        sleep(settings.check_every.min(settings.duration.saturating_sub(started.elapsed()))).await;
+        result = invariants.check(&service.pool, &fcm, &bus, &settings, started.elapsed()).await;
+        println!("soak: {:>6}s {} {}", started.elapsed().as_secs(), counters.summary(), invariants.summary());
+    }
+
+    running.store(false, Ordering::Relaxed);
+    for task in load {
+        let _ = task.await;
+    }
+    if result.is_ok() {
+        // Faults are off: everything created so far must settle
+        println!("soak: load stopped, auditing the last {}s of creates", settings.settle.as_secs());
+        sleep(settings.settle + Duration::from_secs(1)).await;
+        result = invariants.check(&service.pool, &fcm, &bus, &settings, started.elapsed()).await;
+        println!("soak: final {} {}", counters.summary(), invariants.summary());
+    }
+    service.shutdown().await;
+    result
+}
+
+/// A user with a JWT and one device
+struct SoakUser {
+    id: Uuid,
+    jwt: String,
+    /// Current device token (changes with churn)
+    token: Mutex<String>,
+}
+
+impl SoakUser {
+    fn token(&self) -> String {
+        self.token.lock().expect("user lock poisoned").clone()
+    }
+
+    fn set_token(&self, token: String) {
+        *self.token.lock().expect("user lock poisoned") = token;
+    }
+}
+
+/// Unique device token for `user`
+fn device_token(user: Uuid) -> String {
+    static GENERATION: AtomicU64 = AtomicU64::new(0);
+    format!("soak-{}-{}", user.simple(), GENERATION.fetch_add(1, Ordering::Relaxed))
+}
+
+async fn register_users(api: &Api, count: usize) -> Result<Vec<SoakUser>, String> {
+    let mut users = Vec::with_capacity(count);
+    for _ in 0..count {
+        let id = Uuid::new_v4();
+        let jwt = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            // Outlives any run
+            &json!({ "sub": id.to_string(), "exp": Utc::now().timestamp() + 30 * 24 * 3600 }),
+            &jsonwebtoken::EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+        )
+        .map_err(|e| format!("Failed to sign token: {}", e))?;
+        let token = device_token(id);
+        api.register(&jwt, &token).await?;
+        users.push(SoakUser { id, jwt, token: Mutex::new(token) });
+    }
+    Ok(users)
+}
+
+/// What the load generators did (not checked, printed with the progress)
+#[derive(Default)]
+struct Counters {
+    created: AtomicU64,
+    create_errors: AtomicU64,
+    reconnects: AtomicU64,
+    token_refreshes: AtomicU64,
+    reregistrations: AtomicU64,
+    churn_errors: AtomicU64,
+}
+
+impl Counters {
+    fn summary(&self) -> String {
+        let get = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        format!(
+            "created={} create_errors={} reconnects={} token_refreshes={} reregistrations={} churn_errors={}",
+            get(&self.created),
+            get(&self.create_errors),
+            get(&self.reconnects),
+            get(&self.token_refreshes),
+            get(&self.reregistrations),
+            get(&self.churn_errors)
+        )
+    }
+}
+
+#[derive(Clone)]
+struct Api {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl Api {
+    fn new(base_url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.to_string(),
+        }
+    }
+
+    async fn create(&self, notification: Value) -> Result<(), String> {
+        self.post("/api/v1/notifications", "test-admin-token", &notification).await.map(|_| ())
+    }
+
+    async fn create_batch(&self, notifications: Vec<Value>) -> Result<(), String> {
+        let body = json!({ "notifications": notifications });
+        self.post("/api/v1/notifications/batch", "test-admin-token", &body).await.map(|_| ())
+    }
+
+    async fn register(&self, jwt: &str, token: &str) -> Result<(), String> {
+        let body = json!({ "fcm_token": token, "device_type": "android" });
+        self.post("/api/v1/devices", jwt, &body).await.map(|_| ())
+    }
+
+    /// false = the old token is gone (UNREGISTERED removed it)
+    async fn refresh(&self, jwt: &str, old_token: &str, new_token: &str) -> Result<bool, String> {
+        let body = json!({ "old_token": old_token, "new_token": new_token });
+        match self.post("/api/v1/devices/token-refresh", jwt, &body).await {
+            Ok(_) => Ok(true),
+            Err(e) if e.starts_with("404") => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Err = `<status> <body>` or the transport error
+    async fn post(&self, path: &str, bearer: &str, body: &Value) -> Result<Value, String> {
+        let response = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .bearer_auth(bearer)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            return Err(format!("{} {}", status.as_u16(), body));
+        }
+        Ok(serde_json::from_str(&body).unwrap_or(Value::Null))
+    }
+}
+
+/// Sleep `duration` in steps of at most a second; false once the run is over
+async fn pause(duration: Duration, running: &AtomicBool) -> bool {
+    let until = Instant::now() + duration;
+    while running.load(Ordering::Relaxed) && Instant::now() < until {
+        sleep(until.saturating_duration_since(Instant::now()).min(Duration::from_secs(1))).await;
+    }
+    running.load(Ordering::Relaxed)
+}
+
+/// `per_sec` creates every second: a fifth in one batch request, the rest one by one
+async fn create_load(
+    api: Api,
+    users: Arc<Vec<SoakUser>>,
+    per_sec: usize,
+    mut rng: Rng,
+    running: Arc<AtomicBool>,
+    counters: Arc<Counters>,
+) {
+    let mut tick = interval(Duration::from_secs(1));
+    tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    while running.load(Ordering::Relaxed) {
+        tick.tick().await;
+        let mut singles: Vec<Value> = (0..per_sec)
+            .map(|_| {
+                json!({
+                    "id": Uuid::new_v4(),
+                    "user_id": users[rng.below(users.len())].id,
+                    "notification_type": NOTIFICATION_TYPE,
+                    "title": "Soak test",
+                    "message": "Mixed load notification",
+                    "priority": PRIORITIES[rng.below(PRIORITIES.len())],
+                })
+            })
+            .collect();
+        let batch = singles.split_off(singles.len() - per_sec / 5);
+
+        let mut results: Vec<Result<u64, String>> = join_all(singles.into_iter().map(|n| api.create(n)))
+            .await
+            .into_iter()
+            .map(|result| result.map(|()| 1))
+            .collect();
+        if !batch.is_empty() {
+            let size = batch.len() as u64;
+            results.push(api.create_batch(batch).await.map(|()| size));
+        }
+        for result in results {
+            match result {
+                Ok(created) => counters.created.fetch_add(created, Ordering::Relaxed),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Soak create failed");
+                    counters.create_errors.fetch_add(1, Ordering::Relaxed)
+                }
+            };
+        }
+    }
+}
+
+/// Every second about 2% of the users reconnect with 0-2 Bus connections
+async fn connection_churn(
+    bus: MemoryBus,
+    users: Arc<Vec<SoakUser>>,
+    mut rng: Rng,
+    running: Arc<AtomicBool>,
+    counters: Arc<Counters>,
+) {
+    while pause(Duration::from_secs(1), &running).await {
+        for user in users.iter() {
+            if rng.chance(2) {
+                bus.connect(user.id, rng.below(3));
+                counters.reconnects.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Twice a second a user's app gets a new FCM token (`onNewToken`)
+///
+/// Refreshed through the API; when the service already dropped the old token the app
+/// registers the new one instead.
+async fn token_churn(
+    api: Api,
+    users: Arc<Vec<SoakUser>>,
+    mut rng: Rng,
+    running: Arc<AtomicBool>,
+    counters: Arc<Counters>,
+) {
+    while pause(Duration::from_millis(500), &running).await {
+        let user = &users[rng.below(users.len())];
+        let new_token = device_token(user.id);
+        let result = match api.refresh(&user.jwt, &user.token(), &new_token).await {
+            Ok(true) => Ok(&counters.token_refreshes),
+            Ok(false) => api.register(&user.jwt, &new_token).await.map(|_| &counters.reregistrations),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(counter) => {
+                user.set_token(new_token);
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => {
+                tracing::warn!(user_id = %user.id, error = %e, "Soak token churn failed");
+                counters.churn_errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Every `every` one fault for FAULT_WINDOW, in turn
+async fn faults(
+    fcm: MockFcm,
+    bus: MemoryBus,
+    users: Arc<Vec<SoakUser>>,
+    every: Duration,
+    mut rng: Rng,
+    running: Arc<AtomicBool>,
+) {
+    let mut round = 0u64;
+    while pause(every, &running).await {
+        let fault = match round % 4 {
+            0 => {
+                fcm.respond_with(MockResponse::ServerError);
+                "FCM 500"
+            }
+            1 => {
+                fcm.respond_with(MockResponse::RateLimited);
+                "FCM 429"
+            }
+            2 => {
+                bus.fail_with("soak: Bus outage");
+                "Bus outage"
+            }
+            _ => {
+                // For good: the service removes them, token churn registers new ones
+                for _ in 0..(users.len() / 100).max(1) {
+                    let user = &users[rng.below(users.len())];
+                    fcm.respond_for_token(&user.token(), MockResponse::Unregistered);
+                }
+                "UNREGISTERED tokens"
+            }
+        };
+        println!("soak: fault: {} for {}s", fault, FAULT_WINDOW.as_secs());
+        pause(FAULT_WINDOW, &running).await;
+        fcm.respond_with(MockResponse::Success);
+        bus.recover();
+        round += 1;
+    }
+}
+
+/// Invariant state between checks
+struct Invariants {
+    /// Where each notification was delivered (`push:<token>`, `bus:<topic>`), until audited
+    deliveries: HashMap<Uuid, Vec<String>>,
+    /// Creates before this were audited
+    audited_until: DateTime<Utc>,
+    audited: u64,
+    delivered: u64,
+    /// Resident size at the end of the warmup
+    rss_baseline: Option<u64>,
+    rss_peak: u64,
+}
+
+impl Default for Invariants {
+    fn default() -> Self {
+        Self {
+            deliveries: HashMap::new(),
+            audited_until: DateTime::<Utc>::UNIX_EPOCH,
+            audited: 0,
+            delivered: 0,
+            rss_baseline: None,
+            rss_peak: 0,
+        }
+    }
+}
+
+impl Invariants {
+    /// Err = every violation found
+    async fn check(
+        &mut self,
+        pool: &PgPool,
+        fcm: &MockFcm,
+        bus: &MemoryBus,
+        settings: &Settings,
+        elapsed: Duration,
+    ) -> Result<(), String> {
+        let mut violations = self.record_deliveries(fcm, bus);
+        match self.audit(pool, settings.settle).await {
+            Ok(lost) => violations.extend(lost),
+            Err(e) => violations.push(format!("Audit query failed: {}", e)),
+        }
+        if elapsed >= settings.warmup {
+            violations.extend(self.check_memory(settings.max_rss_growth_mb));
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations.join("\n"))
+        }
+    }
+
+    /// Successful FCM sends and Bus notification publishes since the last check
+    fn record_deliveries(&mut self, fcm: &MockFcm, bus: &MemoryBus) -> Vec<String> {
+        let mut deliveries = Vec::new();
+        for (message, response) in fcm.take_sent() {
+            let (Some(id), Some(token)) = (parse_id(&message["data"]["id"]), message["token"].as_str()) else {
+                continue;
+            };
+            if response == MockResponse::Success {
+                deliveries.push((id, format!("push:{}", token)));
+            }
+        }
+        for published in bus.take_published() {
+            let envelope = published.envelope;
+            if published.user_id.is_none() || envelope.event_type != "notification" {
+                continue;
+            }
+            if let Some(id) = envelope.payload.as_ref().and_then(|payload| parse_id(&payload["id"])) {
+                deliveries.push((id, format!("bus:{}", envelope.topic)));
+            }
+        }
+
+        let mut violations = Vec::new();
+        for (id, channel) in deliveries {
+            self.delivered += 1;
+            let channels = self.deliveries.entry(id).or_default();
+            if channels.contains(&channel) {
+                violations.push(format!("Notification {} was delivered twice to {}", id, channel));
+            }
+            channels.push(channel);
+        }
+        violations
+    }
+
+    /// Creates that settled since the last audit: each must be processed, and delivered
+    /// unless it was suppressed or failed
+    async fn audit(&mut self, pool: &PgPool, settle: Duration) -> Result<Vec<String>, sqlx::Error> {
+        let until: DateTime<Utc> = sqlx::query_scalar("SELECT now() - make_interval(secs => $1)")
+            .bind(settle.as_secs_f64())
+            .fetch_one(pool)
+            .await?;
+        if until <= self.audited_until {
+            return Ok(Vec::new());
+        }
+        let settled: Vec<(Uuid, bool, bool, bool)> = sqlx::query_as(
+            "SELECT id, is_processed, last_error IS NOT NULL, suppressed_at IS NOT NULL
+             FROM activity.notifications
+             WHERE notification_type = $1 AND created_at >= $2 AND created_at < $3",
+        )
+        .bind(NOTIFICATION_TYPE)
+        .bind(self.audited_until)
+        .bind(until)
+        .fetch_all(pool)
+        .await?;
+
+        let mut violations = Vec::new();
+        self.audited += settled.len() as u64;
+        for (id, processed, failed, suppressed) in settled {
+            let delivered = self.deliveries.remove(&id).is_some();
+            if !processed {
+                violations.push(format!("Notification {} is not processed {}s after its creation", id, settle.as_secs()));
+            } else if !delivered && !failed && !suppressed {
+                violations.push(format!("Notification {} was processed but not delivered, suppressed or failed", id));
+            }
+        }
+        self.audited_until = until;
+        Ok(violations)
+    }
+
+    fn check_memory(&mut self, max_growth_mb: u64) -> Option<String> {
+        let rss = rss_bytes()?;
+        self.rss_peak = self.rss_peak.max(rss);
+        let baseline = *self.rss_baseline.get_or_insert(rss);
+        let growth_mb = rss.saturating_sub(baseline) / (1024 * 1024);
+        (growth_mb > max_growth_mb).then(|| {
+            format!(
+                "Resident size grew by {} MB since the warmup ({} MB -> {} MB, limit {} MB)",
+                growth_mb,
+                baseline / (1024 * 1024),
+                rss / (1024 * 1024),
+                max_growth_mb
+            )
+        })
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "delivered={} audited={} rss_baseline_mb={} rss_peak_mb={}",
+            self.delivered,
+            self.audited,
+            self.rss_baseline.map(|b| (b / (1024 * 1024)).to_string()).unwrap_or_else(|| "-".to_string()),
+            self.rss_peak / (1024 * 1024)
+        )
+    }
+}
+
+fn parse_id(value: &Value) -> Option<Uuid> {
+    value.as_str()?.parse().ok()
+}
+
+/// Resident set size of this process (Linux; None elsewhere)
+fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kb: u64 = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+/// xorshift64*: enough randomness for load, reproducible with SOAK_SEED
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next_u64() % 100 < percent
+    }
+}
@@ -0,0 +1,202 @@
+//! Golden files for everything mobile clients parse: FCM device/topic/dismiss requests and
+//! the Bus payloads (direct, protobuf-encoded, broadcast, per protocol version), plus the
+//! create API's 422 body that producers parse. No database or Docker needed:
+//! `cargo test --test wire_format_test`.
+//!
+//! A failing snapshot means the wire format changed. If that is intended, accept it
+//! with `cargo insta review` (or `INSTA_UPDATE=always`) and mention it in the PR.
+//! The Bus envelope around the payload is owned by bus-client and not covered here.
+
+use axum::response::IntoResponse;
+use chrono::{TimeZone, Utc};
+use notifications_service::api::ApiError;
+use notifications_service::ingest::limits::CreateLimits;
+use notifications_service::models::{NewNotification, Notification};
+use notifications_service::protocol::Protocol;
+use notifications_service::push::fcm::MAX_PAYLOAD_BYTES;
+use notifications_service::push::FcmClient;
+use notifications_service::signing::BroadcastSigner;
+use uuid::Uuid;
+
+/// Fixed seed so broadcast signatures are reproducible (Ed25519 is deterministic)
+const TEST_SIGNING_SEED: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+const DEVICE_TOKEN: &str = "device-token-golden";
+
+/// Title and type only (defaults everywhere else)
+fn minimal() -> Notification {
+    let created_at = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+    Notification {
+        id: Uuid::parse_str("11111111-1111-4111-8111-111111111111").unwrap(),
+        user_id: Uuid::parse_str("22222222-2222-4222-8222-222222222222").unwrap(),
+        deliver_at: created_at,
+        created_at,
+        ..Notification::draft("system", "Welcome".to_string(), None)
+    }
+}
+
+/// Every optional field a client can see
+fn full() -> Notification {
+    Notification {
+        actor_user_id: Some(Uuid::parse_str("33333333-3333-4333-8333-333333333333").unwrap()),
+        target_type: Some("post".to_string()),
+        target_id: Some(Uuid::parse_str("44444444-4444-4444-8444-444444444444").unwrap()),
+        message: Some("Alice liked your post".to_string()),
+        payload: Some(serde_json::json!({ "post_id": 42, "reactions": ["like"] })),
+        deep_link: Some("app://posts/42".to_string()),
+        priority: Some("high".to_string()),
+        group_key: Some("post:42".to_string()),
+        ..Notification {
+            notification_type: "post_liked".to_string(),
+            title: "New like".to_string(),
+            ..minimal()
+        }
+    }
+}
+
+fn critical() -> Notification {
+    Notification {
+        notification_type: "security_alert".to_string(),
+        title: "New login".to_string(),
+        message: Some("A new device signed in to your account".to_string()),
+        priority: Some("critical".to_string()),
+        ..minimal()
+    }
+}
+
+fn broadcast() -> Notification {
+    Notification {
+        user_id: Uuid::nil(),
+        notification_type: "announcement".to_string(),
+        title: "Maintenance tonight".to_string(),
+        message: Some("The app is unavailable from 02:00 to 03:00 UTC".to_string()),
+        payload: Some(serde_json::json!({ "window_minutes": 60 })),
+        ..minimal()
+    }
+}
+
+/// FCM data maps are HashMaps: sort so the snapshot doesn't depend on iteration order
+fn assert_golden(name: &str, value: &serde_json::Value) {
+    insta::with_settings!({ sort_maps => true }, {
+        insta::assert_json_snapshot!(name, value);
+    });
+}
+
+#[test]
+fn fcm_device_requests() {
+    for (name, notification) in [("minimal", minimal()), ("full", full()), ("critical", critical())] {
+        let request = FcmClient::request_preview(DEVICE_TOKEN, &notification);
+        assert_golden(&format!("fcm_device_{}", name), &request);
+    }
+}
+
+#[test]
+fn fcm_oversized_device_requests_are_trimmed() {
+    let payload_bytes = |request: &serde_json::Value| {
+        request["message"]["notification"].to_string().len() + request["message"]["data"].to_string().len()
+    };
+
+    // 1. A huge group key: only the data map gives, the copy stays intact
+    let notification = Notification { group_key: Some("g".repeat(5000)), ..full() };
+    let request = FcmClient::request_preview(DEVICE_TOKEN, &notification);
+    let data = request["message"]["data"].as_object().unwrap();
+    let mut keys: Vec<_> = data.keys().map(String::as_str).collect();
+    keys.sort_unstable();
+    assert_eq!(keys, ["deep_link", "fetch_full", "id", "type"]);
+    assert_eq!(data["fetch_full"], "true");
+    assert_eq!(request["message"]["notification"]["body"], "Alice liked your post");
+    assert!(payload_bytes(&request) <= MAX_PAYLOAD_BYTES);
+
+    // 2. A huge body (multi-byte characters) is cut on a char boundary
+    let notification = Notification { message: Some("é".repeat(3000)), ..full() };
+    let request = FcmClient::request_preview(DEVICE_TOKEN, &notification);
+    let body = request["message"]["notification"]["body"].as_str().unwrap();
+    assert!(body.starts_with("éé") && body.ends_with('…'));
+    assert_eq!(request["message"]["data"]["fetch_full"], "true");
+    assert!(payload_bytes(&request) <= MAX_PAYLOAD_BYTES);
+
+    // 3. Under the limit nothing changes
+    let request = FcmClient::request_preview(DEVICE_TOKEN, &full());
+    assert!(request["message"]["data"].get("fetch_full").is_none());
+}
+
+#[test]
+fn fcm_dismiss_request() {
+    let request = FcmClient::dismiss_request_preview(DEVICE_TOKEN, full().id);
+    assert_golden("fcm_dismiss", &request);
+}
+
+#[test]
+fn fcm_read_state_request() {
+    let request = FcmClient::read_state_request_preview(DEVICE_TOKEN, &[full().id], 3);
+    assert_golden("fcm_read_state", &request);
+}
+
+#[test]
+fn fcm_topic_requests() {
+    let notification = broadcast();
+    assert_golden("fcm_topic_unsigned", &FcmClient::topic_request_preview("all", &notification, &[]));
+
+    // Same flat fields the worker adds for signed broadcasts
+    let signer = BroadcastSigner::from_base64_seed(TEST_SIGNING_SEED).expect("valid test seed");
+    let signature = signer.sign(&notification);
+    let signature_data = vec![
+        ("signature_alg", signature.alg.to_string()),
+        ("signature_key_id", signature.key_id),
+        ("signed_content", signature.signed_content),
+        ("signature", signature.signature),
+    ];
+    assert_golden(
+        "fcm_topic_signed",
+        &FcmClient::topic_request_preview("all", &notification, &signature_data),
+    );
+}
+
+#[test]
+fn bus_payloads() {
+    for (name, notification) in [("minimal", minimal()), ("full", full()), ("critical", critical())] {
+        assert_golden(&format!("bus_payload_{}", name), &notification.bus_payload());
+    }
+    assert_golden("bus_payload_protobuf", &full().bus_payload_protobuf());
+}
+
+#[test]
+fn bus_payload_protocol_versions() {
+    let payload = minimal().bus_payload();
+    // v1 stays the unversioned shape old app builds parse
+    assert_eq!(Protocol::V1.encode(payload.clone()), payload);
+    assert_golden("bus_payload_v2", &Protocol::V2.encode(payload));
+}
+
+#[test]
+fn bus_broadcast_payloads() {
+    let notification = broadcast();
+    assert_golden("bus_broadcast_unsigned", &notification.bus_broadcast_payload());
+
+    let signer = BroadcastSigner::from_base64_seed(TEST_SIGNING_SEED).expect("valid test seed");
+    let mut payload = notification.bus_broadcast_payload();
+    payload["signature"] = serde_json::json!(signer.sign(&notification));
+    assert_golden("bus_broadcast_signed", &payload);
+}
+
+#[tokio::test]
+async fn create_api_limit_exceeded_bodies() {
+    let limits = CreateLimits { max_title_chars: 20, max_batch_size: 2, ..Default::default() };
+    let notification: NewNotification = serde_json::from_value(serde_json::json!({
+        "user_id": minimal().user_id,
+        "notification_type": "limits_test",
+        "title": "A title well over twenty characters",
+    }))
+    .expect("valid notification");
+    let body = |error: ApiError| async move {
+        let response = error.into_response();
+        assert_eq!(response.status(), 422);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("readable body");
+        serde_json::from_slice::<serde_json::Value>(&bytes).expect("JSON body")
+    };
+
+    // The original top-level fields stay next to code/message/details
+    let single = limits.check(&notification).expect_err("title over the limit");
+    assert_golden("api_limit_exceeded_single", &body(single.into()).await);
+    let batch = limits.check(&notification).expect_err("title over the limit").at(1);
+    assert_golden("api_limit_exceeded_batch", &body(batch.into()).await);
+}